@@ -0,0 +1,54 @@
+//! Generation pipeline benchmarks: `cargo bench --bench generation`.
+//!
+//! Builds planets at several resolutions with criterion, then times the common downstream work of
+//! meshing and rasterizing them. Pass a filter to run only the matching benchmarks, e.g.
+//! `cargo bench --bench generation -- build/200000`. For the time of every pipeline stage, use
+//! `PlanetParameters::build_with_stats`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::time::Duration;
+use terraforge::coords::CoordinateFrame;
+use terraforge::procedural::PlanetParameters;
+use terraforge::projection::Projection;
+use uuid::Uuid;
+
+const CELL_COUNTS: [usize; 3] = [10_000, 50_000, 200_000];
+
+fn parameters(cells: usize) -> PlanetParameters {
+    let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x5EED));
+    parameters.samples = cells;
+    parameters
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    // A 200 000-cell planet takes seconds to build, so keep to criterion's smallest sample
+    group.sample_size(10).measurement_time(Duration::from_secs(30));
+    for cells in CELL_COUNTS {
+        let parameters = parameters(cells);
+        group.bench_with_input(BenchmarkId::from_parameter(cells), &parameters, |b, parameters| b.iter(|| parameters.build()));
+    }
+    group.finish();
+}
+
+fn downstream(c: &mut Criterion) {
+    let mut mesh = c.benchmark_group("mesh");
+    mesh.sample_size(10);
+    let planets: Vec<_> = CELL_COUNTS.iter().map(|&cells| (cells, parameters(cells).build())).collect();
+    for (cells, planet) in &planets {
+        mesh.bench_with_input(BenchmarkId::from_parameter(cells), planet, |b, planet| b.iter(|| planet.mesh(&CoordinateFrame::BEVY, 10.0)));
+    }
+    mesh.finish();
+
+    let mut rasterize = c.benchmark_group("rasterize_1024");
+    rasterize.sample_size(10);
+    for (cells, planet) in &planets {
+        rasterize.bench_with_input(BenchmarkId::from_parameter(cells), planet, |b, planet| {
+            b.iter(|| planet.rasterize_cells(Projection::Equirectangular, 1024, 512))
+        });
+    }
+    rasterize.finish();
+}
+
+criterion_group!(benches, build, downstream);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+/*
+ * C interface to TerraForge, built with the `terraforge-ffi` feature:
+ *
+ *     cargo rustc --release --features terraforge-ffi --crate-type cdylib
+ *
+ * Planets are opaque handles created by tf_planet_generate and released with tf_free. Arrays
+ * returned by the accessors are owned by the planet and stay valid until it is freed.
+ */
+#ifndef TERRAFORGE_H
+#define TERRAFORGE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum TfStatus {
+    TF_OK = 0,
+    TF_NULL_POINTER = 1,
+    TF_INVALID_ARGUMENT = 2,
+    TF_PANIC = 3
+} TfStatus;
+
+/* Overrides for planet generation; see tf_planet_params_default for the "derive" values. */
+typedef struct TfPlanetParams {
+    uint32_t samples;     /* Surface cells; 0 derives it from the seed */
+    double radius_km;     /* Kilometres; 0 or less derives it */
+    double land_fraction; /* 0.0 to 1.0; negative derives it */
+    double jitter;        /* Site jitter, 0.0 to 1.0; negative derives it */
+} TfPlanetParams;
+
+typedef struct TfPlanet TfPlanet;
+
+/* Biome ids returned by tf_planet_biomes. */
+enum {
+    TF_BIOME_DEEP_WATER = 0,
+    TF_BIOME_SHALLOW_WATER = 1,
+    TF_BIOME_SAND = 2,
+    TF_BIOME_DESERT = 3,
+    TF_BIOME_GRASS = 4,
+    TF_BIOME_FOREST = 5,
+    TF_BIOME_TUNDRA = 6,
+    TF_BIOME_ROCK = 7,
+    TF_BIOME_SNOW = 8,
+    TF_BIOME_SEA_ICE = 9
+};
+
+const char *tf_version(void);
+
+TfStatus tf_planet_params_default(TfPlanetParams *params);
+TfStatus tf_planet_generate(uint64_t seed, const TfPlanetParams *params, TfPlanet **out_handle);
+void tf_free(TfPlanet *planet);
+
+size_t tf_planet_cell_count(const TfPlanet *planet);
+double tf_planet_radius(const TfPlanet *planet);
+TfStatus tf_planet_copy_sites(const TfPlanet *planet, double *out, size_t len);
+const double *tf_planet_heights(const TfPlanet *planet, size_t *out_len);
+const double *tf_planet_temperatures(const TfPlanet *planet, size_t *out_len);
+const uint8_t *tf_planet_biomes(const TfPlanet *planet, size_t *out_len);
+const size_t *tf_planet_triangles(const TfPlanet *planet, size_t *out_len);
+const size_t *tf_planet_neighbors(const TfPlanet *planet, size_t cell, size_t *out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,433 @@
+use crate::climate::SOLAR_CONSTANT;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The specific gas constant of dry air in J/(kg·K).
+pub const DRY_AIR_GAS_CONSTANT: f64 = 287.05;
+
+/// The molar gas constant in J/(mol·K).
+pub const MOLAR_GAS_CONSTANT: f64 = 8.314_462_618;
+
+// Earth's sea-level pressure in Pa
+const EARTH_SURFACE_PRESSURE: f64 = 101_325.0;
+// Warming of Earth's surface by its greenhouse effect in K, already part of
+// `climate::equilibrium_temperature`
+const EARTH_GREENHOUSE_WARMING: f64 = 33.0;
+// Earth's effective radiating temperature in K, without any greenhouse effect
+const EARTH_EFFECTIVE_TEMPERATURE: f64 = 255.0;
+// Earth's escape velocity in m/s
+const EARTH_ESCAPE_VELOCITY: f64 = 11_186.0;
+// Earth's Rayleigh scattering coefficients at sea level for red, green and blue light, per metre
+const EARTH_RAYLEIGH: [f64; 3] = [5.802e-6, 13.558e-6, 33.1e-6];
+// Greenhouse strength of methane per molecule relative to carbon dioxide
+const METHANE_WARMING_POTENTIAL: f64 = 25.0;
+
+/// The gases of an atmosphere as volume fractions, which sum to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Composition {
+    pub nitrogen: f64,
+    pub oxygen: f64,
+    pub argon: f64,
+    pub carbon_dioxide: f64,
+    pub methane: f64,
+    pub water_vapour: f64,
+    pub hydrogen: f64,
+    pub helium: f64,
+}
+
+// Molar mass in kg/mol and Rayleigh cross-section relative to nitrogen of every gas, in field order
+const GASES: [(&str, f64, f64); 8] = [
+    ("nitrogen", 0.028_013, 1.0),
+    ("oxygen", 0.031_999, 0.86),
+    ("argon", 0.039_948, 0.85),
+    ("carbon_dioxide", 0.044_010, 2.5),
+    ("methane", 0.016_043, 2.3),
+    ("water_vapour", 0.018_015, 0.75),
+    ("hydrogen", 0.002_016, 0.22),
+    ("helium", 0.004_003, 0.014),
+];
+
+impl Default for Composition {
+    /// Earth's dry air.
+    fn default() -> Self {
+        Composition {
+            nitrogen: 0.7808,
+            oxygen: 0.2095,
+            argon: 0.0093,
+            carbon_dioxide: 0.0004,
+            methane: 0.0,
+            water_vapour: 0.0,
+            hydrogen: 0.0,
+            helium: 0.0,
+        }
+    }
+}
+
+impl Composition {
+    /// Returns every gas with its name and volume fraction, in field order.
+    pub fn fractions(&self) -> [(&'static str, f64); 8] {
+        let values = [
+            self.nitrogen,
+            self.oxygen,
+            self.argon,
+            self.carbon_dioxide,
+            self.methane,
+            self.water_vapour,
+            self.hydrogen,
+            self.helium,
+        ];
+        std::array::from_fn(|i| (GASES[i].0, values[i]))
+    }
+
+    /// Returns the mean molar mass of the gas mixture in kg/mol.
+    pub fn mean_molar_mass(&self) -> f64 {
+        self.weighted(|&(_, molar_mass, _)| molar_mass)
+    }
+
+    /// Returns the specific gas constant of the mixture in J/(kg·K).
+    pub fn gas_constant(&self) -> f64 {
+        MOLAR_GAS_CONSTANT / self.mean_molar_mass().max(f64::EPSILON)
+    }
+
+    // The mean of a per-gas property weighted by volume fraction
+    fn weighted(&self, property: impl Fn(&(&str, f64, f64)) -> f64) -> f64 {
+        let fractions = self.fractions();
+        let total: f64 = fractions.iter().map(|&(_, fraction)| fraction).sum();
+        if total <= 0.0 {
+            return property(&GASES[0]);
+        }
+        fractions.iter().zip(&GASES).map(|(&(_, fraction), gas)| fraction * property(gas)).sum::<f64>() / total
+    }
+}
+
+/// An isothermal, exponential atmosphere model.
+///
+/// Density and pressure fall off as `exp(-altitude / scale_height)`, which is accurate to within
+/// a few tens of percent up to ~100 km on Earth and is cheap enough for per-frame flight physics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atmosphere {
+    surface_pressure: f64,    // Pa
+    surface_temperature: f64, // K
+    gas_constant: f64,        // Specific gas constant in J/(kg·K)
+    scale_height: f64,        // m
+    composition: Composition,
+}
+
+impl Default for Atmosphere {
+    /// Earth's standard atmosphere at sea level with a 8.5 km scale height.
+    fn default() -> Self {
+        Atmosphere {
+            surface_pressure: 101_325.0,
+            surface_temperature: 288.15,
+            gas_constant: DRY_AIR_GAS_CONSTANT,
+            scale_height: 8500.0,
+            composition: Composition::default(),
+        }
+    }
+}
+
+/// Density sampled at fixed altitudes, for engines that want a lookup table instead of a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DensityTable {
+    altitudes: Vec<f64>, // m, ascending
+    densities: Vec<f64>, // kg/m³
+}
+
+impl Atmosphere {
+    /// Creates an atmosphere from surface conditions, made of Earth's dry air until
+    /// `with_composition` says otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `surface_pressure` - Pressure at sea level in Pa.
+    /// * `surface_temperature` - Temperature at sea level in K.
+    /// * `gas_constant` - Specific gas constant of the atmosphere in J/(kg·K).
+    /// * `surface_gravity` - Gravitational acceleration at the surface in m/s², used to derive
+    ///   the scale height.
+    pub fn new(surface_pressure: f64, surface_temperature: f64, gas_constant: f64, surface_gravity: f64) -> Atmosphere {
+        let scale_height = gas_constant * surface_temperature / surface_gravity.max(f64::EPSILON);
+        Atmosphere {
+            surface_pressure: surface_pressure.max(0.0),
+            surface_temperature: surface_temperature.max(1.0),
+            gas_constant,
+            scale_height,
+            composition: Composition::default(),
+        }
+    }
+
+    /// Generates a plausible atmosphere for a rocky planet from a seed and its bulk properties.
+    ///
+    /// Bodies whose escape velocity is too low for the warmth of their orbit keep no air, like
+    /// the Moon and Mercury. Others get a surface pressure drawn on a log scale that rises with how
+    /// well they hold gas, and one of four kinds of air: nitrogen and oxygen (only in temperate
+    /// orbits), nitrogen with a little carbon dioxide, thick carbon dioxide (likelier near the
+    /// star) or nitrogen and methane (only far out). The surface temperature setting the scale
+    /// height includes the greenhouse warming of the result.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The atmosphere seed, e.g. `PlanetParameters::atmosphere_seed`.
+    /// * `mass` - The planet mass in Earth masses.
+    /// * `radius` - The planet radius in kilometres.
+    /// * `solar_flux` - The irradiance at the planet's orbit in W/m².
+    pub fn generate(seed: u64, mass: f64, radius: f64, solar_flux: f64) -> Atmosphere {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gravity = 9.80665 * mass.max(0.0) / (radius.max(1.0) / 6371.0).powi(2);
+        let escape_velocity = (2.0 * gravity * radius.max(1.0) * 1000.0).sqrt();
+        let relative_flux = (solar_flux / SOLAR_CONSTANT).max(1e-6);
+        // Hotter gas escapes more easily
+        let retention = escape_velocity / EARTH_ESCAPE_VELOCITY / relative_flux.powf(0.25);
+        if retention < 0.3 {
+            return Atmosphere::vacuum();
+        }
+
+        let temperate = (0.8..1.15).contains(&relative_flux);
+        let kind = rng.gen_range(0.0..1.0);
+        let mut composition = Composition {
+            nitrogen: 0.0,
+            oxygen: 0.0,
+            argon: 0.0,
+            carbon_dioxide: 0.0,
+            methane: 0.0,
+            water_vapour: 0.0,
+            hydrogen: 0.0,
+            helium: 0.0,
+        };
+        let mut log_pressure = rng.gen_range(-1.0..0.8) + 1.5 * (retention - 1.0).min(0.3);
+        if temperate && kind < 0.3 {
+            composition.oxygen = rng.gen_range(0.1..0.3);
+            composition.carbon_dioxide = 10f64.powf(rng.gen_range(-4.0..-2.5));
+            composition.argon = rng.gen_range(0.005..0.015);
+        } else if relative_flux < 0.12 && kind < 0.5 {
+            composition.methane = rng.gen_range(0.01..0.08);
+            composition.argon = rng.gen_range(0.0..0.01);
+        } else if kind < 0.4 + 0.4 * (relative_flux - 1.0).clamp(0.0, 1.0) {
+            // Runaway greenhouse worlds bake their carbon out of the rocks
+            composition.carbon_dioxide = rng.gen_range(0.9..0.98);
+            composition.argon = rng.gen_range(0.0..0.01);
+            log_pressure += rng.gen_range(0.5..1.5);
+        } else {
+            composition.carbon_dioxide = 10f64.powf(rng.gen_range(-3.0..-1.0));
+            composition.argon = rng.gen_range(0.005..0.02);
+        }
+        let others: f64 = composition.fractions().iter().map(|&(_, fraction)| fraction).sum();
+        composition.nitrogen = (1.0 - others).max(0.0);
+
+        let pressure = EARTH_SURFACE_PRESSURE * 10f64.powf(log_pressure);
+        let bare = EARTH_EFFECTIVE_TEMPERATURE * relative_flux.powf(0.25);
+        let warming = Atmosphere::new(pressure, bare, composition.gas_constant(), gravity).with_composition(composition).greenhouse_warming();
+        Atmosphere::new(pressure, bare + warming, composition.gas_constant(), gravity).with_composition(composition)
+    }
+
+    /// Returns the atmosphere with a different mix of gases, rescaling the gas constant and with
+    /// it the scale height.
+    pub fn with_composition(mut self, composition: Composition) -> Atmosphere {
+        let gas_constant = composition.gas_constant();
+        self.scale_height *= gas_constant / self.gas_constant;
+        self.gas_constant = gas_constant;
+        self.composition = composition;
+        self
+    }
+
+    /// Creates an airless body (zero pressure and density everywhere).
+    pub fn vacuum() -> Atmosphere {
+        Atmosphere {
+            surface_pressure: 0.0,
+            ..Atmosphere::default()
+        }
+    }
+
+    /// Returns the sea-level pressure in Pa.
+    pub fn surface_pressure(&self) -> f64 {
+        self.surface_pressure
+    }
+
+    /// Returns the sea-level temperature in K.
+    pub fn surface_temperature(&self) -> f64 {
+        self.surface_temperature
+    }
+
+    /// Returns the mix of gases.
+    pub fn composition(&self) -> &Composition {
+        &self.composition
+    }
+
+    /// Returns the specific gas constant in J/(kg·K).
+    pub fn gas_constant(&self) -> f64 {
+        self.gas_constant
+    }
+
+    /// Returns the strength of the greenhouse effect relative to Earth's: 1.0 for Earth's air,
+    /// 0.0 for a vacuum.
+    ///
+    /// Grows with pressure and, logarithmically, with carbon dioxide and methane, saturating for
+    /// thick air. Like `climate::equilibrium_temperature` this is a fit for plausible worlds, not
+    /// a radiative transfer model: Venus comes out about 220 K warmer than bare rock instead of
+    /// 500 K.
+    pub fn greenhouse_factor(&self) -> f64 {
+        let pressure = self.surface_pressure / EARTH_SURFACE_PRESSURE;
+        let composition = &self.composition;
+        let carbon_dioxide = composition.carbon_dioxide + METHANE_WARMING_POTENTIAL * composition.methane;
+        let earth_carbon_dioxide = Composition::default().carbon_dioxide;
+        // Water vapour and clouds give Earth 60% of its warming, carbon dioxide the rest
+        let depth = pressure * (0.6 + 0.4 * (1.0 + carbon_dioxide / earth_carbon_dioxide).log2());
+        let saturation = std::f64::consts::E - 1.0;
+        (saturation * depth).ln_1p() / saturation.ln_1p()
+    }
+
+    /// Returns how much the greenhouse effect warms the surface above bare rock, in K.
+    pub fn greenhouse_warming(&self) -> f64 {
+        EARTH_GREENHOUSE_WARMING * self.greenhouse_factor()
+    }
+
+    /// Returns how much warmer than Earth's the greenhouse effect keeps the surface, in K, which
+    /// the planet's temperature model adds to its Earth-fitted equilibrium; negative for thinner
+    /// air.
+    pub fn greenhouse_offset(&self) -> f64 {
+        EARTH_GREENHOUSE_WARMING * (self.greenhouse_factor() - 1.0)
+    }
+
+    /// Returns the Rayleigh scattering coefficients at sea level for red (680 nm), green (550 nm)
+    /// and blue (440 nm) light, per metre, for atmospheric scattering shaders.
+    ///
+    /// Earth's air gives the usual `[5.8e-6, 13.6e-6, 33.1e-6]`; other atmospheres scale them by
+    /// their number density and the mean cross-section of their gases. Combine with
+    /// `scale_height` for the density falloff.
+    pub fn rayleigh_coefficients(&self) -> [f64; 3] {
+        let earth = Atmosphere::default();
+        let number_density = (self.surface_pressure / self.surface_temperature) / (earth.surface_pressure / earth.surface_temperature);
+        let cross_section = |composition: &Composition| composition.weighted(|&(_, _, cross_section)| cross_section);
+        let ratio = number_density * cross_section(&self.composition) / cross_section(&earth.composition);
+        EARTH_RAYLEIGH.map(|coefficient| coefficient * ratio)
+    }
+
+    /// Returns the scale height in metres.
+    pub fn scale_height(&self) -> f64 {
+        self.scale_height
+    }
+
+    /// Returns the sea-level density in kg/m³.
+    pub fn surface_density(&self) -> f64 {
+        self.surface_pressure / (self.gas_constant * self.surface_temperature)
+    }
+
+    /// Returns the pressure at an altitude above sea level, in Pa.
+    pub fn pressure_at(&self, altitude: f64) -> f64 {
+        self.surface_pressure * (-altitude.max(0.0) / self.scale_height).exp()
+    }
+
+    /// Returns the density at an altitude above sea level, in kg/m³.
+    pub fn density_at(&self, altitude: f64) -> f64 {
+        self.surface_density() * (-altitude.max(0.0) / self.scale_height).exp()
+    }
+
+    /// Returns the altitude at which density falls to `density`, or `0.0` if the surface is
+    /// already thinner than that.
+    pub fn altitude_for_density(&self, density: f64) -> f64 {
+        let surface = self.surface_density();
+        if density <= 0.0 || surface <= density {
+            return if density <= 0.0 { f64::INFINITY } else { 0.0 };
+        }
+        self.scale_height * (surface / density).ln()
+    }
+
+    /// Returns the entry interface altitude: where density drops to the value Earth has at its
+    /// conventional 120 km entry interface (about 2.2e-8 kg/m³).
+    pub fn entry_interface_altitude(&self) -> f64 {
+        self.altitude_for_density(2.2e-8)
+    }
+
+    /// Returns the aerodynamic drag force on a body.
+    ///
+    /// # Arguments
+    ///
+    /// * `altitude` - Altitude above sea level in m.
+    /// * `speed` - Speed relative to the air in m/s.
+    /// * `drag_coefficient` - The body's drag coefficient (dimensionless).
+    /// * `area` - The reference (frontal) area in m².
+    ///
+    /// # Returns
+    ///
+    /// The drag force magnitude in newtons.
+    pub fn drag_force(&self, altitude: f64, speed: f64, drag_coefficient: f64, area: f64) -> f64 {
+        0.5 * self.density_at(altitude) * speed * speed * drag_coefficient * area
+    }
+
+    /// Returns the terminal velocity of a falling body.
+    ///
+    /// # Arguments
+    ///
+    /// * `altitude` - Altitude above sea level in m.
+    /// * `mass` - The body's mass in kg.
+    /// * `drag_coefficient` - The body's drag coefficient (dimensionless).
+    /// * `area` - The reference (frontal) area in m².
+    /// * `gravity` - Gravitational acceleration in m/s².
+    ///
+    /// # Returns
+    ///
+    /// The terminal velocity in m/s, or `f64::INFINITY` where there is no air.
+    pub fn terminal_velocity(&self, altitude: f64, mass: f64, drag_coefficient: f64, area: f64, gravity: f64) -> f64 {
+        let denominator = self.density_at(altitude) * drag_coefficient * area;
+        if denominator <= 0.0 {
+            return f64::INFINITY;
+        }
+        (2.0 * mass * gravity / denominator).sqrt()
+    }
+
+    /// Samples density at evenly spaced altitudes from sea level up to `max_altitude`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_altitude` - The top of the table in m.
+    /// * `samples` - The number of rows (at least 2).
+    pub fn density_table(&self, max_altitude: f64, samples: usize) -> DensityTable {
+        let samples = samples.max(2);
+        let altitudes: Vec<f64> = (0..samples)
+            .map(|i| max_altitude * i as f64 / (samples - 1) as f64)
+            .collect();
+        let densities = altitudes.iter().map(|&h| self.density_at(h)).collect();
+        DensityTable { altitudes, densities }
+    }
+}
+
+impl DensityTable {
+    /// Returns the sampled altitudes in metres.
+    pub fn altitudes(&self) -> &[f64] {
+        &self.altitudes
+    }
+
+    /// Returns the sampled densities in kg/m³.
+    pub fn densities(&self) -> &[f64] {
+        &self.densities
+    }
+
+    /// Interpolates density at an altitude, log-linearly between rows.
+    ///
+    /// Altitudes outside the table clamp to the first or last row.
+    pub fn density_at(&self, altitude: f64) -> f64 {
+        let last = self.altitudes.len() - 1;
+        if altitude <= self.altitudes[0] {
+            return self.densities[0];
+        }
+        if altitude >= self.altitudes[last] {
+            return self.densities[last];
+        }
+        let i = self.altitudes.partition_point(|&h| h <= altitude) - 1;
+        let t = (altitude - self.altitudes[i]) / (self.altitudes[i + 1] - self.altitudes[i]);
+        let (a, b) = (self.densities[i], self.densities[i + 1]);
+        if a > 0.0 && b > 0.0 {
+            (a.ln() + (b.ln() - a.ln()) * t).exp()
+        } else {
+            a + (b - a) * t
+        }
+    }
+
+    /// Formats the table as CSV with an `altitude_m,density_kg_m3` header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("altitude_m,density_kg_m3\n");
+        for (h, rho) in self.altitudes.iter().zip(&self.densities) {
+            csv.push_str(&format!("{},{:e}\n", h, rho));
+        }
+        csv
+    }
+}
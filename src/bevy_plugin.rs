@@ -0,0 +1,132 @@
+use crate::coords::CoordinateFrame;
+use crate::mesh::{LodStreamer, MeshBuffers, PlanetLod};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use std::sync::Arc;
+
+impl MeshBuffers {
+    /// Converts the buffers into a Bevy triangle-list `Mesh` with positions, normals, vertex colors,
+    /// UVs when present, and 32-bit indices.
+    ///
+    /// Build the buffers in `CoordinateFrame::BEVY` (or a rescaled copy of it) so the axes and
+    /// winding match Bevy's.
+    pub fn to_bevy_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors.clone());
+        if !self.uvs.is_empty() {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone());
+        }
+        mesh.insert_indices(Indices::U32(self.indices.clone()));
+        mesh
+    }
+}
+
+/// Marks an entity spawned by `PlanetLodPlugin` for one chunk of the planet.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlanetChunk {
+    pub chunk: usize,
+    pub level: usize, // Level of detail the chunk's mesh currently shows
+}
+
+/// A Bevy plugin that shows a `PlanetLod` around the origin, streaming each chunk's level of detail
+/// from the camera position with a `LodStreamer`.
+///
+/// Every frame, the first camera's position is handed to `LodStreamer::update`, and each chunk it
+/// reports is rebuilt with `PlanetLod::chunk_mesh` and swapped into that chunk's entity, spawning
+/// the entity the first time. Chunks are drawn with one `StandardMaterial` that shows the
+/// meshes' hypsometric vertex colors, unless `material` supplies another.
+///
+/// ```ignore
+/// let lod = PlanetLod::new(&parameters, 4, 64, 10.0);
+/// App::new()
+///     .add_plugins((DefaultPlugins, PlanetLodPlugin::new(lod, vec![2000.0, 6000.0, 15000.0])))
+///     .run();
+/// ```
+pub struct PlanetLodPlugin {
+    pub lod: Arc<PlanetLod>,
+    pub switch_distances: Vec<f64>,              // Kilometres, as for `LodStreamer::new`
+    pub frame: CoordinateFrame,                  // Frame of the chunk meshes and the camera
+    pub material: Option<Handle<StandardMaterial>>, // `None` for a plain material created on the first frame
+}
+
+impl PlanetLodPlugin {
+    /// Creates a plugin for a chunked planet in `CoordinateFrame::BEVY`, metres along Bevy's axes.
+    ///
+    /// # Arguments
+    ///
+    /// * `lod` - The chunked planet.
+    /// * `switch_distances` - Camera distances in kilometres at which chunks drop to the next
+    ///   coarser level, as for `LodStreamer::new`.
+    pub fn new(lod: PlanetLod, switch_distances: Vec<f64>) -> PlanetLodPlugin {
+        PlanetLodPlugin {
+            lod: Arc::new(lod),
+            switch_distances,
+            frame: CoordinateFrame::BEVY,
+            material: None,
+        }
+    }
+}
+
+impl Plugin for PlanetLodPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LodStreaming {
+            lod: self.lod.clone(),
+            streamer: LodStreamer::new(self.switch_distances.clone()),
+            frame: self.frame,
+            material: self.material.clone(),
+            entities: vec![None; self.lod.chunk_count()],
+        })
+        .add_systems(Update, stream_chunks);
+    }
+}
+
+// The streaming state of the plugin's planet
+#[derive(Resource)]
+struct LodStreaming {
+    lod: Arc<PlanetLod>,
+    streamer: LodStreamer,
+    frame: CoordinateFrame,
+    material: Option<Handle<StandardMaterial>>,
+    entities: Vec<Option<Entity>>, // Entity of each chunk, once spawned
+}
+
+// Rebuilds the chunks whose level of detail changed since the last frame
+fn stream_chunks(
+    mut commands: Commands,
+    mut streaming: ResMut<LodStreaming>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<&GlobalTransform, With<Camera>>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    let streaming = &mut *streaming;
+    let position = camera.translation();
+    let camera = streaming.frame.to_native((position.x as f64, position.y as f64, position.z as f64));
+    let changes = streaming.streamer.update(&streaming.lod, camera);
+    if changes.is_empty() {
+        return;
+    }
+    let material = streaming.material.get_or_insert_with(|| materials.add(StandardMaterial::default())).clone();
+    for change in changes {
+        let mesh = meshes.add(streaming.lod.chunk_mesh(change.chunk, change.to, &streaming.frame).to_bevy_mesh());
+        let marker = PlanetChunk {
+            chunk: change.chunk,
+            level: change.to,
+        };
+        match streaming.entities[change.chunk] {
+            // Replacing the handle drops the old mesh once nothing else holds it
+            Some(entity) => {
+                commands.entity(entity).insert((Mesh3d(mesh), marker));
+            }
+            None => {
+                let entity = commands.spawn((Mesh3d(mesh), MeshMaterial3d(material.clone()), Transform::default(), marker)).id();
+                streaming.entities[change.chunk] = Some(entity);
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use serde_json::Value;
+use std::fs;
+use std::process::ExitCode;
+use terraforge::recipe::{ExportSettings, ExportTarget, Recipe, TerrainRecipe};
+use terraforge::sampling::SiteSampling;
+
+const USAGE: &str = "\
+Usage: terraforge planet [options]
+       terraforge recipe <file>...
+       terraforge serve [options] [--port <n>]
+
+Generates planets and writes them to files. `recipe` runs recipe files written in TOML, RON or
+JSON, each describing one planet and its exports. `serve` generates the first seed's planet and
+serves map tiles of it with a web viewer on http://127.0.0.1:<port>/ [default port: 8080]; it
+needs the `tile-server` feature.
+
+Options:
+  --seed <seeds>          Seeds to generate: 42, 1,2,3 or 1..10 (end exclusive) [default: 0]
+  --cells <n>             Surface cells [default: derived from the seed]
+  --radius <km>           Planet radius [default: derived from the seed]
+  --land-fraction <f>     Share of the surface above sea level, 0 to 1 [default: derived]
+  --jitter <f>            Site jitter, 0 to 1 [default: derived]
+  --sampling <method>     Site placement: fibonacci, poisson or goldberg (hex cells)
+                          [default: fibonacci]
+  --symmetry <n>          Repeat the landmasses n times around the rotation axis for fair
+                          multiplayer maps [default: 1]
+  --export <formats>      Comma-separated: gltf, png, globe, npz, csv, unreal, tfp, normal,
+                          slope, atlas [default: gltf,png]
+  --output <dir>          Output directory, created if missing [default: .]
+  --width <px>            Width of png and globe images [default: 1024]
+  --exaggeration <f>      Vertical exaggeration of gltf meshes and normal and slope maps
+                          [default: 10]
+  --chunks <n>            Chunks in tfp planet files [default: 64]
+  --config <file>         JSON file with any of the options above, using the long names with
+                          underscores as keys; command-line options override it
+  -h, --help              Print this help
+";
+
+// Everything a run needs, filled from defaults, then the config file, then the command line
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    seeds: Vec<u64>,
+    cells: Option<usize>,
+    radius: Option<f64>,
+    land_fraction: Option<f64>,
+    jitter: Option<f64>,
+    sampling: Option<SiteSampling>,
+    symmetry: Option<u32>,
+    exports: Vec<ExportTarget>,
+    output: String,
+    settings: ExportSettings,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            seeds: vec![0],
+            cells: None,
+            radius: None,
+            land_fraction: None,
+            jitter: None,
+            sampling: None,
+            symmetry: None,
+            exports: vec![ExportTarget::Gltf, ExportTarget::Png],
+            output: ".".to_string(),
+            settings: ExportSettings::default(),
+        }
+    }
+}
+
+fn parse_seeds(text: &str) -> Result<Vec<u64>, String> {
+    let number = |s: &str| s.trim().parse::<u64>().map_err(|_| format!("invalid seed '{}'", s));
+    if let Some((start, end)) = text.split_once("..") {
+        let (start, end) = (number(start)?, number(end)?);
+        if start >= end {
+            return Err(format!("empty seed range '{}'", text));
+        }
+        return Ok((start..end).collect());
+    }
+    text.split(',').map(number).collect()
+}
+
+fn parse_exports(text: &str) -> Result<Vec<ExportTarget>, String> {
+    text.split(',')
+        .map(|format| {
+            ExportTarget::from_name(format.trim()).ok_or_else(|| {
+                let names: Vec<&str> = ExportTarget::ALL.iter().map(ExportTarget::name).collect();
+                format!("unknown export format '{}', expected one of {}", format.trim(), names.join(", "))
+            })
+        })
+        .collect()
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("invalid value '{}' for {}", text, option))
+}
+
+impl Options {
+    // Applies one option given by its name without dashes, e.g. "land-fraction" or "land_fraction"
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let option = format!("--{}", name.replace('_', "-"));
+        match option.as_str() {
+            "--seed" | "--seeds" => self.seeds = parse_seeds(value)?,
+            "--cells" => self.cells = Some(parse_number(&option, value)?),
+            "--radius" => self.radius = Some(parse_number(&option, value)?),
+            "--land-fraction" => self.land_fraction = Some(parse_number(&option, value)?),
+            "--jitter" => self.jitter = Some(parse_number(&option, value)?),
+            "--sampling" => {
+                let sampling = SiteSampling::from_name(value.trim()).ok_or_else(|| {
+                    let names: Vec<&str> = SiteSampling::ALL.iter().map(SiteSampling::name).collect();
+                    format!("unknown sampling method '{}', expected one of {}", value.trim(), names.join(", "))
+                })?;
+                self.sampling = Some(sampling);
+            }
+            "--symmetry" => self.symmetry = Some(parse_number(&option, value)?),
+            "--export" | "--exports" => self.exports = parse_exports(value)?,
+            "--output" => self.output = value.to_string(),
+            "--width" => self.settings.width = parse_number(&option, value)?,
+            "--exaggeration" => self.settings.exaggeration = parse_number(&option, value)?,
+            "--chunks" => self.settings.chunk_count = parse_number(&option, value)?,
+            _ => return Err(format!("unknown option '{}'", option)),
+        }
+        Ok(())
+    }
+
+    fn load_config(&mut self, path: &str) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("cannot read {}: {}", path, error))?;
+        let value: Value = serde_json::from_str(&text).map_err(|error| format!("invalid JSON in {}: {}", path, error))?;
+        let object = value.as_object().ok_or_else(|| format!("{} must contain a JSON object", path))?;
+        for (key, value) in object {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                Value::Number(number) => number.to_string(),
+                Value::Array(items) => items.iter().map(|item| item.as_str().map_or_else(|| item.to_string(), str::to_string)).collect::<Vec<_>>().join(","),
+                _ => return Err(format!("invalid value for '{}' in {}", key, path)),
+            };
+            self.set(key, &text).map_err(|error| format!("{}: {}", path, error))?;
+        }
+        Ok(())
+    }
+
+    fn from_args(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        // The config file goes first so the command line overrides it wherever it appears
+        if let Some(i) = args.iter().position(|arg| arg == "--config") {
+            let path = args.get(i + 1).ok_or("missing value for --config")?;
+            options.load_config(path)?;
+        }
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.strip_prefix("--") {
+                Some(rest) => match rest.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (rest, None),
+                },
+                None => return Err(format!("unexpected argument '{}'", arg)),
+            };
+            let value = match inline {
+                Some(value) => value,
+                None => args.next().ok_or_else(|| format!("missing value for --{}", name))?.clone(),
+            };
+            if name != "config" {
+                options.set(name, &value)?;
+            }
+        }
+        Ok(options)
+    }
+
+    // The recipe for one seed; its validation covers every numeric option
+    fn recipe(&self, seed: u64) -> Result<Recipe, String> {
+        let recipe = Recipe {
+            seed,
+            cells: self.cells,
+            radius: self.radius,
+            jitter: self.jitter,
+            sampling: self.sampling,
+            symmetry: self.symmetry,
+            terrain: TerrainRecipe {
+                land_fraction: self.land_fraction,
+                ..TerrainRecipe::default()
+            },
+            exports: self.exports.clone(),
+            output: self.output.clone(),
+            export_settings: self.settings.clone(),
+            ..Recipe::default()
+        };
+        recipe.validate().map_err(|error| error.to_string())?;
+        Ok(recipe)
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let options = Options::from_args(args)?;
+    fs::create_dir_all(&options.output).map_err(|error| format!("cannot create {}: {}", options.output, error))?;
+    for &seed in &options.seeds {
+        let planet = options.recipe(seed)?.build();
+        let stem = format!("planet_{}", seed);
+        println!("{}: {} cells, radius {:.0} km", stem, planet.cell_count(), planet.radius());
+        for target in &options.exports {
+            let path = target
+                .write(&planet, &options.output, &stem, &options.settings)
+                .map_err(|error| format!("cannot write {} for {}: {}", target.name(), stem, error))?;
+            println!("  wrote {}", path);
+        }
+    }
+    Ok(())
+}
+
+fn run_recipes(paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("missing recipe file".to_string());
+    }
+    for path in paths {
+        let recipe = Recipe::load(path).map_err(|error| format!("{}: {}", path, error))?;
+        println!("{}: seed {}", path, recipe.seed);
+        for written in recipe.run().map_err(|error| format!("{}: {}", path, error))? {
+            println!("  wrote {}", written);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tile-server")]
+fn serve(args: &[String]) -> Result<(), String> {
+    let mut port = 8080;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--port") {
+            Some("") => port = parse_number("--port", args.next().ok_or("missing value for --port")?)?,
+            Some(value) if value.starts_with('=') => port = parse_number("--port", &value[1..])?,
+            _ => rest.push(arg.clone()),
+        }
+    }
+    let options = Options::from_args(&rest)?;
+    let seed = options.seeds[0];
+    let planet = options.recipe(seed)?.build();
+    println!("planet_{}: {} cells, radius {:.0} km", seed, planet.cell_count(), planet.radius());
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port)).map_err(|error| format!("cannot listen on port {}: {}", port, error))?;
+    println!("serving on http://127.0.0.1:{}/", port);
+    terraforge::tile_server::TileServer::new(planet).serve(listener).map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "tile-server"))]
+fn serve(_args: &[String]) -> Result<(), String> {
+    Err("serve needs the tile-server feature: cargo install --path . --features tile-server".to_string())
+}
+
+fn exit(result: Result<(), String>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("planet") | Some("recipe") | Some("serve") if args.iter().any(|arg| arg == "-h" || arg == "--help") => {
+            print!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Some("planet") => exit(run(&args[1..])),
+        Some("recipe") => exit(run_recipes(&args[1..])),
+        Some("serve") => exit(serve(&args[1..])),
+        Some("-h") | Some("--help") | Some("help") => {
+            print!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprint!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
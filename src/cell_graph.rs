@@ -0,0 +1,162 @@
+use crate::geom::spherical::{self, GeodesicSubdivision};
+use crate::planet::Planet;
+use crate::vector::{add, cross, dot, normalize, sub, Vec3};
+use rayon::prelude::*;
+
+/// The Voronoi edge two neighboring cells share.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellEdge {
+    pub neighbor: usize,
+    pub length: f64,        // Great-circle length of the shared edge in kilometres
+    pub midpoint: Vec3,     // Middle of the shared edge on the unit sphere
+    pub corners: [Vec3; 2], // Ends of the shared edge on the unit sphere, counter-clockwise around the cell
+}
+
+impl CellEdge {
+    /// Returns the shared edge as points along the great-circle arc between its corners, for
+    /// drawing it on the surface rather than as a chord through the planet.
+    pub fn arc(&self, subdivision: GeodesicSubdivision) -> Vec<Vec3> {
+        spherical::subdivide_arc(self.corners[0], self.corners[1], subdivision)
+    }
+}
+
+/// The adjacency of a planet's cells with the geometry of every shared edge, computed once from
+/// the triangulation.
+///
+/// Neighbors are listed counter-clockwise around each cell seen from outside, so a cell's edge
+/// corners, taken in order, trace its Voronoi polygon. The graph is a snapshot; rebuild it after
+/// `Planet::insert_site` or `Planet::remove_site`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellGraph {
+    offsets: Vec<usize>, // Cell i's edges are edges[offsets[i]..offsets[i + 1]]
+    neighbors: Vec<usize>,
+    edges: Vec<CellEdge>,
+    radius: f64, // Kilometres
+}
+
+/// Returns the Voronoi vertex of a Delaunay triangle: its circumcentre on the unit sphere.
+pub(crate) fn circumcentre(sites: &[Vec3], triangle: &[usize; 3]) -> Vec3 {
+    let (a, b, c) = (sites[triangle[0]], sites[triangle[1]], sites[triangle[2]]);
+    let n = normalize(cross(sub(b, a), sub(c, a)));
+    if dot(n, a) < 0.0 { (-n.0, -n.1, -n.2) } else { n }
+}
+
+impl Planet {
+    /// Builds the adjacency graph of the cells with the length and midpoint of every shared edge.
+    pub fn cell_graph(&self) -> CellGraph {
+        CellGraph::new(self)
+    }
+}
+
+impl CellGraph {
+    /// Builds the graph of a planet's cells.
+    pub fn new(planet: &Planet) -> CellGraph {
+        let sites = planet.sites();
+        let triangles = planet.triangles();
+        let corners: Vec<Vec3> = triangles.par_iter().map(|triangle| circumcentre(sites, triangle)).collect();
+
+        // The triangle left of each directed Delaunay edge `from -> to`, listed under `from`
+        let mut left: Vec<Vec<(usize, usize)>> = vec![Vec::new(); sites.len()];
+        for (index, triangle) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                left[triangle[k]].push((triangle[(k + 1) % 3], index));
+            }
+        }
+        let left_of = |from: usize, to: usize| left[from].iter().find(|&&(next, _)| next == to).map(|&(_, t)| t);
+
+        let radius = planet.radius();
+        let cell_edges: Vec<Vec<CellEdge>> = (0..sites.len())
+            .into_par_iter()
+            .map(|cell| {
+                // Walking from one triangle around the cell visits the neighbors counter-clockwise
+                let Some(&(first, _)) = left[cell].first() else {
+                    return Vec::new();
+                };
+                let mut ordered = vec![first];
+                loop {
+                    let previous = *ordered.last().unwrap_or(&first);
+                    let Some(triangle) = left_of(cell, previous) else { break };
+                    let next = triangles[triangle].iter().copied().find(|&v| v != cell && v != previous);
+                    match next {
+                        Some(next) if next != first && ordered.len() < left[cell].len() => ordered.push(next),
+                        _ => break,
+                    }
+                }
+                ordered
+                    .into_iter()
+                    .map(|neighbor| {
+                        let before = left_of(neighbor, cell).map_or(sites[cell], |t| corners[t]);
+                        let after = left_of(cell, neighbor).map_or(sites[cell], |t| corners[t]);
+                        CellEdge {
+                            neighbor,
+                            length: spherical::distance(before, after) * radius,
+                            midpoint: normalize(add(before, after)),
+                            corners: [before, after],
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut offsets = Vec::with_capacity(sites.len() + 1);
+        offsets.push(0);
+        for list in &cell_edges {
+            offsets.push(offsets[offsets.len() - 1] + list.len());
+        }
+        let edges: Vec<CellEdge> = cell_edges.into_iter().flatten().collect();
+        let neighbors = edges.iter().map(|edge| edge.neighbor).collect();
+        CellGraph { offsets, neighbors, edges, radius }
+    }
+
+    /// Returns the number of cells.
+    pub fn cell_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Returns the number of shared edges, each counted once.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len() / 2
+    }
+
+    /// Returns the planet radius in kilometres the edge lengths were measured at.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns the neighbors of a cell, counter-clockwise seen from outside.
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[self.offsets[cell]..self.offsets[cell + 1]]
+    }
+
+    /// Returns the edges of a cell in the same order as `neighbors`.
+    pub fn edges(&self, cell: usize) -> &[CellEdge] {
+        &self.edges[self.offsets[cell]..self.offsets[cell + 1]]
+    }
+
+    /// Returns the edge between two cells, or `None` if they are not neighbors.
+    pub fn edge(&self, cell: usize, neighbor: usize) -> Option<&CellEdge> {
+        self.edges(cell).iter().find(|edge| edge.neighbor == neighbor)
+    }
+
+    /// Returns the perimeter of a cell in kilometres.
+    pub fn perimeter(&self, cell: usize) -> f64 {
+        self.edges(cell).iter().map(|edge| edge.length).sum()
+    }
+
+    /// Returns the surface area of a cell in square kilometres.
+    pub fn area(&self, cell: usize) -> f64 {
+        spherical::polygon_area(&self.polygon(cell)) * self.radius * self.radius
+    }
+
+    /// Returns the centroid of a cell's Voronoi polygon on the unit sphere, which differs from the
+    /// site, e.g. when relaxing a layout.
+    pub fn centroid(&self, cell: usize) -> Vec3 {
+        spherical::polygon_centroid(&self.polygon(cell))
+    }
+
+    /// Returns the corners of a cell's Voronoi polygon on the unit sphere, counter-clockwise seen
+    /// from outside.
+    pub fn polygon(&self, cell: usize) -> Vec<Vec3> {
+        self.edges(cell).iter().map(|edge| edge.corners[1]).collect()
+    }
+}
@@ -0,0 +1,199 @@
+use crate::pathfinding::TravelCost;
+use crate::planet::Planet;
+use crate::resources::ResourceMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The size class of a settlement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SettlementTier {
+    Hamlet,
+    Village,
+    Town,
+    City,
+    Capital,
+}
+
+impl SettlementTier {
+    // Typical population range of the tier
+    fn population_range(&self) -> (f64, f64) {
+        match self {
+            SettlementTier::Hamlet => (20.0, 200.0),
+            SettlementTier::Village => (200.0, 2_000.0),
+            SettlementTier::Town => (2_000.0, 20_000.0),
+            SettlementTier::City => (20_000.0, 500_000.0),
+            SettlementTier::Capital => (500_000.0, 5_000_000.0),
+        }
+    }
+}
+
+/// A placed settlement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub cell: usize,
+    pub tier: SettlementTier,
+    pub population: u64,
+    pub habitability: f64, // The score of the settlement's cell, 0.0 to 1.0
+}
+
+/// Settings for settlement placement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettlementSettings {
+    pub count: usize,
+    pub min_spacing_km: f64, // Minimum distance between any two settlements
+    pub roads: bool,         // Whether `generate_settlements` also connects settlements by road
+    pub travel: TravelCost,  // Cost model for roads
+}
+
+impl Default for SettlementSettings {
+    /// 60 settlements at least 150 km apart, connected by roads.
+    fn default() -> Self {
+        SettlementSettings {
+            count: 60,
+            min_spacing_km: 150.0,
+            roads: true,
+            travel: TravelCost::default(),
+        }
+    }
+}
+
+/// Settlements placed on a planet and the roads between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Civilization {
+    pub settlements: Vec<Settlement>,
+    pub roads: Vec<Vec<usize>>, // Each road as the cells it passes through
+}
+
+// Suitability of a mean annual temperature, best in temperate climates
+fn climate_factor(temperature: f64) -> f64 {
+    (-((temperature - 16.0) / 14.0).powi(2)).exp()
+}
+
+impl Planet {
+    /// Scores every cell for how attractive it is to settle.
+    ///
+    /// Combines fresh water (precipitation), flat land, coastal access, climate and nearby
+    /// resources. Ocean cells score `0.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `resources` - Resource deposits to favour, if any.
+    ///
+    /// # Returns
+    ///
+    /// A score per cell from 0.0 (uninhabitable) to 1.0.
+    pub fn cell_habitability(&self, resources: Option<&ResourceMap>) -> Vec<f64> {
+        let precipitation = self.annual_precipitation();
+        let temperature = self.mean_annual_temperature();
+        (0..self.cell_count())
+            .map(|cell| {
+                if self.is_ocean(cell) {
+                    return 0.0;
+                }
+                let water = 1.0 - (-precipitation[cell] / 600.0).exp();
+                let flat = (1.0 - self.slope(cell) / 20.0).clamp(0.0, 1.0);
+                let coast = if self.neighbors(cell).iter().any(|&n| self.is_ocean(n)) { 1.0 } else { 0.0 };
+                let climate = climate_factor(temperature[cell]);
+                let wealth = resources.map_or(0.0, |map| {
+                    std::iter::once(cell)
+                        .chain(self.neighbors(cell).iter().copied())
+                        .filter_map(|c| map.deposit(c))
+                        .map(|d| d.richness)
+                        .fold(0.0, f64::max)
+                });
+                let score = climate * (0.35 * water + 0.3 * flat + 0.2 * coast + 0.15 * wealth) * (0.5 + 0.5 * water);
+                score.clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    /// Places settlements on the most habitable cells.
+    ///
+    /// Cells are picked greedily by habitability with a little seeded jitter, keeping
+    /// `min_spacing_km` between settlements. The best sites become the largest settlements: one
+    /// capital, then cities, towns, villages and hamlets in growing numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `habitability` - The score of every cell, e.g. from `cell_habitability`.
+    /// * `seed` - The placement seed.
+    /// * `settings` - How many settlements to place and how far apart.
+    pub fn place_settlements(&self, habitability: &[f64], seed: u64, settings: &SettlementSettings) -> Vec<Settlement> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut candidates: Vec<(f64, usize)> = habitability
+            .iter()
+            .enumerate()
+            .filter(|(_, &score)| score > 0.0)
+            .map(|(cell, &score)| (score * rng.gen_range(0.85..1.15), cell))
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut cells: Vec<usize> = Vec::new();
+        for (_, cell) in candidates {
+            if cells.len() >= settings.count {
+                break;
+            }
+            if cells.iter().all(|&other| self.distance_km(cell, other) >= settings.min_spacing_km) {
+                cells.push(cell);
+            }
+        }
+
+        let total = cells.len().max(1) as f64;
+        cells
+            .into_iter()
+            .enumerate()
+            .map(|(rank, cell)| {
+                let fraction = rank as f64 / total;
+                let tier = if rank == 0 {
+                    SettlementTier::Capital
+                } else if fraction < 0.08 {
+                    SettlementTier::City
+                } else if fraction < 0.3 {
+                    SettlementTier::Town
+                } else if fraction < 0.65 {
+                    SettlementTier::Village
+                } else {
+                    SettlementTier::Hamlet
+                };
+                let (low, high) = tier.population_range();
+                let population = (low * (high / low).powf(rng.gen::<f64>())).round() as u64;
+                Settlement {
+                    cell,
+                    tier,
+                    population,
+                    habitability: habitability[cell],
+                }
+            })
+            .collect()
+    }
+
+    /// Connects every settlement to the nearest larger (or equal, earlier placed) settlement it
+    /// can reach overland.
+    ///
+    /// # Returns
+    ///
+    /// The cells along each road. Settlements with no reachable partner get no road.
+    pub fn connect_settlements(&self, settlements: &[Settlement], travel: &TravelCost) -> Vec<Vec<usize>> {
+        let mut roads = Vec::new();
+        for (index, settlement) in settlements.iter().enumerate().skip(1) {
+            let mut targets: Vec<&Settlement> = settlements[..index].iter().collect();
+            targets.sort_by(|a, b| self.distance_km(settlement.cell, a.cell).total_cmp(&self.distance_km(settlement.cell, b.cell)));
+            // Only the few nearest partners are worth a search
+            for target in targets.into_iter().take(3) {
+                if let Some((path, _)) = self.find_path(settlement.cell, target.cell, |a, b| self.travel_cost(a, b, travel)) {
+                    roads.push(path);
+                    break;
+                }
+            }
+        }
+        roads
+    }
+
+    /// Scores habitability, places settlements and optionally connects them by road.
+    pub fn generate_settlements(&self, resources: Option<&ResourceMap>, seed: u64, settings: &SettlementSettings) -> Civilization {
+        let habitability = self.cell_habitability(resources);
+        let settlements = self.place_settlements(&habitability, seed, settings);
+        let roads = if settings.roads { self.connect_settlements(&settlements, &settings.travel) } else { Vec::new() };
+        Civilization { settlements, roads }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::planet::Planet;
+use rayon::prelude::*;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// The total solar irradiance at Earth's orbit, in W/m².
+pub const SOLAR_CONSTANT: f64 = 1361.0;
+
+/// The environmental lapse rate: how fast temperature drops with altitude, in °C per metre.
+pub const LAPSE_RATE: f64 = 0.0065;
+
+/// The length of a planet's year and day, used to turn a date into an orbital position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitalCalendar {
+    year_length_days: f64,    // Local days per orbit
+    day_length_hours: f64,    // Hours per local day
+    vernal_equinox_day: f64,  // Day of year on which the sun crosses the equator northwards
+}
+
+impl Default for OrbitalCalendar {
+    /// An Earth-like calendar: 365.25 days of 24 hours, equinox on day 79.
+    fn default() -> Self {
+        OrbitalCalendar {
+            year_length_days: 365.25,
+            day_length_hours: 24.0,
+            vernal_equinox_day: 79.0,
+        }
+    }
+}
+
+impl OrbitalCalendar {
+    /// Creates a calendar with the given year and day lengths.
+    ///
+    /// # Arguments
+    ///
+    /// * `year_length_days` - The number of local days in one orbit.
+    /// * `day_length_hours` - The number of hours in one local day.
+    pub fn new(year_length_days: f64, day_length_hours: f64) -> Self {
+        OrbitalCalendar {
+            year_length_days: year_length_days.max(1.0),
+            day_length_hours: day_length_hours.max(f64::EPSILON),
+            vernal_equinox_day: year_length_days.max(1.0) * 79.0 / 365.25,
+        }
+    }
+
+    /// Returns the number of local days in one orbit.
+    pub fn year_length_days(&self) -> f64 {
+        self.year_length_days
+    }
+
+    /// Returns the number of hours in one local day.
+    pub fn day_length_hours(&self) -> f64 {
+        self.day_length_hours
+    }
+
+    /// Wraps any day count into `[0, year_length_days)`.
+    pub fn day_of_year(&self, day: f64) -> f64 {
+        day.rem_euclid(self.year_length_days)
+    }
+
+    /// Returns the orbital angle in radians measured from the vernal equinox.
+    pub fn season_angle(&self, day: f64) -> f64 {
+        2.0 * PI * (day - self.vernal_equinox_day) / self.year_length_days
+    }
+
+    /// Returns the solar declination in radians for the given day.
+    ///
+    /// # Arguments
+    ///
+    /// * `day` - The day of year (values outside one year wrap around).
+    /// * `axial_tilt_degrees` - The planet's obliquity.
+    pub fn solar_declination(&self, day: f64, axial_tilt_degrees: f64) -> f64 {
+        (axial_tilt_degrees.to_radians().sin() * self.season_angle(day).sin()).asin()
+    }
+}
+
+/// Returns the half-day length as an hour angle in radians.
+///
+/// A value of `0` means polar night and `PI` means polar day (the sun never sets).
+pub fn sunset_hour_angle(latitude_degrees: f64, declination: f64) -> f64 {
+    let x = -latitude_degrees.to_radians().tan() * declination.tan();
+    x.clamp(-1.0, 1.0).acos()
+}
+
+/// Returns the hours of daylight at a latitude on a day with the given solar declination.
+///
+/// # Arguments
+///
+/// * `latitude_degrees` - The latitude of the location.
+/// * `declination` - The solar declination in radians, see `OrbitalCalendar::solar_declination`.
+/// * `day_length_hours` - The length of a local day.
+pub fn daylight_hours(latitude_degrees: f64, declination: f64, day_length_hours: f64) -> f64 {
+    sunset_hour_angle(latitude_degrees, declination) / PI * day_length_hours
+}
+
+/// Computes the daily mean top-of-atmosphere insolation.
+///
+/// Uses the standard daily-averaged formula, which handles polar day and polar night through
+/// the clamped sunset hour angle.
+///
+/// # Arguments
+///
+/// * `latitude_degrees` - The latitude of the location.
+/// * `declination` - The solar declination in radians.
+/// * `solar_flux` - The irradiance at the planet's orbit in W/m².
+///
+/// # Returns
+///
+/// The mean insolation over one local day in W/m².
+pub fn daily_mean_insolation(latitude_degrees: f64, declination: f64, solar_flux: f64) -> f64 {
+    let phi = latitude_degrees.to_radians();
+    let h0 = sunset_hour_angle(latitude_degrees, declination);
+    let q = solar_flux / PI * (h0 * phi.sin() * declination.sin() + phi.cos() * declination.cos() * h0.sin());
+    q.max(0.0)
+}
+
+/// Converts a daily mean insolation into a surface equilibrium temperature.
+///
+/// This is a linear fit to Earth's zonal means (about 27 °C at the equator and -20 °C at the
+/// poles on the annual mean), not a radiative transfer model.
+///
+/// # Arguments
+///
+/// * `insolation` - Daily mean insolation in W/m².
+///
+/// # Returns
+///
+/// The equilibrium temperature in degrees Celsius.
+pub fn equilibrium_temperature(insolation: f64) -> f64 {
+    -48.0 + 0.18 * insolation
+}
+
+/// Adjusts a sea-level temperature for the altitude of the surface.
+///
+/// Only land above sea level is cooled; ocean surfaces stay at sea level.
+pub fn lapse_adjusted(temperature: f64, elevation: f64) -> f64 {
+    temperature - LAPSE_RATE * elevation.max(0.0)
+}
+
+/// Returns the point on the planet where the sun is directly overhead.
+///
+/// The sun crosses the prime meridian at local noon, i.e. halfway through each day.
+///
+/// # Arguments
+///
+/// * `calendar` - The planet's orbital calendar; a day is one solar day.
+/// * `axial_tilt_degrees` - The planet's obliquity.
+/// * `time_days` - The time in days since generation; the fractional part is the time of day.
+///
+/// # Returns
+///
+/// The `(latitude, longitude)` of the subsolar point in degrees.
+pub fn subsolar_point(calendar: &OrbitalCalendar, axial_tilt_degrees: f64, time_days: f64) -> (f64, f64) {
+    let latitude = calendar.solar_declination(time_days, axial_tilt_degrees).to_degrees();
+    let longitude = (180.0 - 360.0 * time_days.rem_euclid(1.0) + 180.0).rem_euclid(360.0) - 180.0;
+    (latitude, longitude)
+}
+
+/// Computes instantaneous top-of-atmosphere irradiance from the sun's position.
+///
+/// # Arguments
+///
+/// * `surface_normal` - The unit vector of the location on the sphere (z is the rotation axis).
+/// * `sun_direction` - The unit vector toward the subsolar point.
+/// * `solar_flux` - The irradiance at the planet's orbit in W/m².
+///
+/// # Returns
+///
+/// The irradiance in W/m²; `0.0` on the night side.
+pub fn instantaneous_irradiance(surface_normal: (f64, f64, f64), sun_direction: (f64, f64, f64), solar_flux: f64) -> f64 {
+    let cos_zenith = surface_normal.0 * sun_direction.0 + surface_normal.1 * sun_direction.1 + surface_normal.2 * sun_direction.2;
+    solar_flux * cos_zenith.max(0.0)
+}
+
+/// Returns a zonal mean annual precipitation for a latitude, in mm/year.
+///
+/// Reproduces the familiar banding: a wet equatorial belt, dry subtropical highs near 30°,
+/// wet mid-latitude storm tracks near 50° and dry polar deserts.
+pub fn zonal_precipitation(latitude_degrees: f64) -> f64 {
+    let lat = latitude_degrees.abs();
+    let equatorial = 2200.0 * (-(lat / 10.0).powi(2)).exp();
+    let storm_track = 1000.0 * (-((lat - 50.0) / 12.0).powi(2)).exp();
+    let polar_dryness = (1.0 - ((lat - 60.0) / 30.0).clamp(0.0, 1.0)).max(0.15);
+    (250.0 + equatorial + storm_track) * polar_dryness
+}
+
+// Distance inland (in kilometres) over which precipitation halves
+const CONTINENTALITY_KM: f64 = 1500.0;
+
+impl Planet {
+    /// Returns the mean temperature of every cell over one year, in °C.
+    pub fn mean_annual_temperature(&self) -> Vec<f64> {
+        const SAMPLES: usize = 12;
+        let calendar = self.calendar();
+        let declinations: Vec<f64> = (0..SAMPLES)
+            .map(|i| calendar.solar_declination(calendar.year_length_days() * i as f64 / SAMPLES as f64, self.axial_tilt()))
+            .collect();
+        let flux = self.solar_flux();
+        let greenhouse = self.atmosphere().greenhouse_offset();
+        let elevation = self.elevation();
+
+        (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let (latitude, _) = self.lat_lon(cell);
+                let mean = declinations
+                    .iter()
+                    .map(|&d| equilibrium_temperature(daily_mean_insolation(latitude, d, flux)))
+                    .sum::<f64>()
+                    / SAMPLES as f64;
+                lapse_adjusted(mean + greenhouse, elevation[cell])
+            })
+            .collect()
+    }
+
+    /// Estimates mean annual precipitation for every cell, in mm/year.
+    ///
+    /// Combines the zonal precipitation bands with continentality: precipitation decays with the
+    /// distance to the nearest ocean cell. Use this when no weather simulation is available.
+    pub fn annual_precipitation(&self) -> Vec<f64> {
+        // Multi-source breadth-first search from every ocean cell
+        let mut distance = vec![f64::INFINITY; self.cell_count()];
+        let mut queue = VecDeque::new();
+        for (cell, d) in distance.iter_mut().enumerate() {
+            if self.is_ocean(cell) {
+                *d = 0.0;
+                queue.push_back(cell);
+            }
+        }
+        while let Some(cell) = queue.pop_front() {
+            for &neighbor in self.neighbors(cell) {
+                let d = distance[cell] + self.distance_km(cell, neighbor);
+                if d < distance[neighbor] {
+                    distance[neighbor] = d;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        (0..self.cell_count())
+            .map(|cell| {
+                let (latitude, _) = self.lat_lon(cell);
+                let inland = if distance[cell].is_finite() { distance[cell] } else { 0.0 };
+                zonal_precipitation(latitude) * 0.5f64.powf(inland / CONTINENTALITY_KM)
+            })
+            .collect()
+    }
+}
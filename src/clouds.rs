@@ -0,0 +1,165 @@
+use crate::guard::guard_values;
+use crate::image::Image;
+use crate::noise::fbm;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::vector::{add, cross, normalize, scale, Vec3};
+use rayon::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+
+// Hours over which advected noise coordinates relax back toward their cell
+const RELAXATION_HOURS: f64 = 48.0;
+// Hours for the noise pattern to morph into an unrelated one
+const EVOLUTION_HOURS: f64 = 24.0;
+// Spatial frequency of the cloud noise on the unit sphere
+const CLOUD_FREQUENCY: f64 = 4.0;
+
+/// An evolving cloud-coverage field over the cells of a planet.
+///
+/// Coverage is seeded noise whose sample coordinates are advected by the wind field, thresholded
+/// by humidity so that moist regions are cloudier.
+#[derive(Debug, Clone)]
+pub struct CloudLayer {
+    seed: u64,
+    time_hours: f64,
+    sources: Vec<Vec3>, // Advected noise-space coordinate of each cell
+    coverage: Vec<f64>, // Cloud cover fraction, 0.0 to 1.0
+}
+
+/// Returns the unit vector a wind blows toward, given its bearing clockwise from north in degrees.
+fn wind_vector(p: Vec3, bearing_degrees: f64) -> Vec3 {
+    let east = if p.0.abs() < 1e-9 && p.1.abs() < 1e-9 {
+        (1.0, 0.0, 0.0)
+    } else {
+        normalize(cross((0.0, 0.0, 1.0), p))
+    };
+    let north = cross(p, east);
+    let bearing = bearing_degrees.to_radians();
+    add(scale(north, bearing.cos()), scale(east, bearing.sin()))
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl CloudLayer {
+    /// Creates a cloud layer for the planet with every cell's noise coordinate at rest.
+    ///
+    /// The coverage is zero until the first call to `step`.
+    pub fn new(planet: &Planet, seed: u64) -> CloudLayer {
+        CloudLayer {
+            seed,
+            time_hours: 0.0,
+            sources: planet.sites().to_vec(),
+            coverage: vec![0.0; planet.cell_count()],
+        }
+    }
+
+    /// Returns the simulated time in hours.
+    pub fn time_hours(&self) -> f64 {
+        self.time_hours
+    }
+
+    /// Returns the cloud cover fraction (0.0 to 1.0) of every cell.
+    pub fn coverage(&self) -> &[f64] {
+        &self.coverage
+    }
+
+    /// Advances the clouds by advecting them with the wind and re-thresholding by humidity.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the layer was created for.
+    /// * `humidity` - Relative humidity per cell in percent.
+    /// * `wind_speed` - Wind speed per cell in m/s.
+    /// * `wind_direction` - Per-cell bearing the wind blows toward, in degrees clockwise from north.
+    /// * `dt_hours` - The time step in hours.
+    pub fn step(&mut self, planet: &Planet, humidity: &[f64], wind_speed: &[f64], wind_direction: &[f64], dt_hours: f64) {
+        self.time_hours += dt_hours;
+        let radius_m = planet.radius() * 1000.0;
+        let relax = 1.0 - (-dt_hours / RELAXATION_HOURS).exp();
+        let sites = planet.sites();
+
+        let evolution = self.time_hours / EVOLUTION_HOURS;
+        let phase = evolution.floor() as u64;
+        let blend = evolution.fract();
+        let seed = self.seed;
+
+        self.sources
+            .par_iter_mut()
+            .zip(self.coverage.par_iter_mut())
+            .enumerate()
+            .for_each(|(cell, (source, coverage))| {
+                let site = sites[cell];
+
+                // Semi-Lagrangian step: sample the noise where the air came from
+                let angle = wind_speed[cell] * 3600.0 * dt_hours / radius_m;
+                let wind = wind_vector(site, wind_direction[cell]);
+                *source = normalize(add(*source, scale(wind, -angle)));
+                *source = normalize(add(scale(*source, 1.0 - relax), scale(site, relax)));
+
+                let p = scale(*source, CLOUD_FREQUENCY);
+                let a = fbm(seed ^ phase, p, 5, 2.0, 0.5);
+                let b = fbm(seed ^ (phase + 1), p, 5, 2.0, 0.5);
+                let noise = a + (b - a) * blend;
+
+                let moisture = (humidity[cell] / 100.0 - 0.6) * 1.5;
+                *coverage = smoothstep(0.35, 0.75, noise * 0.5 + 0.5 + moisture);
+            });
+        guard_values("cloud_coverage", &mut self.coverage, (0.0, 1.0), 0.0);
+    }
+
+    /// Renders the current coverage as an equirectangular grayscale texture.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the layer was created for.
+    /// * `width` - The texture width in pixels.
+    /// * `height` - The texture height in pixels.
+    pub fn render_frame(&self, planet: &Planet, width: usize, height: usize) -> Image {
+        let raster = planet.rasterize_values(&self.coverage, Projection::Equirectangular, width, height);
+        Image::from_raster_gray(&raster, 0.0, 1.0)
+    }
+
+    /// Steps the clouds repeatedly and writes one PNG texture per step.
+    ///
+    /// Files are named `clouds_0000.png`, `clouds_0001.png`, ... inside `directory`.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the layer was created for.
+    /// * `humidity`, `wind_speed`, `wind_direction` - Per-cell forcing, as for `step`.
+    /// * `frames` - The number of frames to write.
+    /// * `dt_hours` - The simulated time between frames.
+    /// * `width`, `height` - The texture size in pixels.
+    /// * `directory` - An existing directory to write into.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the written frames, or an `io::Error` if a file could not be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_frames(
+        &mut self,
+        planet: &Planet,
+        humidity: &[f64],
+        wind_speed: &[f64],
+        wind_direction: &[f64],
+        frames: usize,
+        dt_hours: f64,
+        width: usize,
+        height: usize,
+        directory: &str,
+    ) -> io::Result<Vec<String>> {
+        let mut paths = Vec::with_capacity(frames);
+        for frame in 0..frames {
+            self.step(planet, humidity, wind_speed, wind_direction, dt_hours);
+            let path = format!("{}/clouds_{:04}.png", directory, frame);
+            self.render_frame(planet, width, height).write_png(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
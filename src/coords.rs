@@ -0,0 +1,314 @@
+use crate::vector::Vec3;
+
+/// A universe-scale position: the index of a cubic sector plus an offset inside it.
+///
+/// A single `f64` loses sub-metre precision a few light-years from the origin when working in
+/// metres; splitting positions into an integer sector and a small offset keeps full precision
+/// everywhere, and differences between nearby positions stay exact.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SectorPosition {
+    pub sector: (i64, i64, i64),
+    pub offset: Vec3, // From the sector's minimum corner, each component in [0, sector size)
+}
+
+/// A grid of cubic sectors for expressing positions as `SectorPosition`s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectorGrid {
+    sector_size: f64,
+}
+
+impl SectorGrid {
+    /// Creates a grid of sectors with the given edge length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sector_size` is not positive and finite.
+    pub fn new(sector_size: f64) -> SectorGrid {
+        assert!(sector_size > 0.0 && sector_size.is_finite(), "sector size must be positive and finite");
+        SectorGrid { sector_size }
+    }
+
+    /// Returns the edge length of a sector.
+    pub fn sector_size(&self) -> f64 {
+        self.sector_size
+    }
+
+    // Splits one coordinate into a sector index and an offset in [0, size)
+    fn split(&self, value: f64) -> (i64, f64) {
+        let sector = (value / self.sector_size).floor();
+        let offset = value - sector * self.sector_size;
+        // Rounding can land exactly on the upper edge
+        if offset >= self.sector_size {
+            (sector as i64 + 1, offset - self.sector_size)
+        } else {
+            (sector as i64, offset.max(0.0))
+        }
+    }
+
+    /// Converts an absolute position into sector coordinates.
+    pub fn from_absolute(&self, position: Vec3) -> SectorPosition {
+        let (x, ox) = self.split(position.0);
+        let (y, oy) = self.split(position.1);
+        let (z, oz) = self.split(position.2);
+        SectorPosition {
+            sector: (x, y, z),
+            offset: (ox, oy, oz),
+        }
+    }
+
+    /// Converts sector coordinates into an absolute position, losing precision far from the
+    /// origin.
+    pub fn to_absolute(&self, position: &SectorPosition) -> Vec3 {
+        (
+            position.sector.0 as f64 * self.sector_size + position.offset.0,
+            position.sector.1 as f64 * self.sector_size + position.offset.1,
+            position.sector.2 as f64 * self.sector_size + position.offset.2,
+        )
+    }
+
+    /// Carries any offset outside [0, sector size) into the sector index.
+    pub fn normalize(&self, position: &SectorPosition) -> SectorPosition {
+        let carry = |sector: i64, offset: f64| {
+            let (extra, offset) = self.split(offset);
+            (sector + extra, offset)
+        };
+        let (x, ox) = carry(position.sector.0, position.offset.0);
+        let (y, oy) = carry(position.sector.1, position.offset.1);
+        let (z, oz) = carry(position.sector.2, position.offset.2);
+        SectorPosition {
+            sector: (x, y, z),
+            offset: (ox, oy, oz),
+        }
+    }
+
+    /// Moves a position by an offset.
+    pub fn translate(&self, position: &SectorPosition, delta: Vec3) -> SectorPosition {
+        self.normalize(&SectorPosition {
+            sector: position.sector,
+            offset: (position.offset.0 + delta.0, position.offset.1 + delta.1, position.offset.2 + delta.2),
+        })
+    }
+
+    /// Returns the vector from one position to another.
+    ///
+    /// The sector difference is taken in integers first, so the result is exact to `f64`
+    /// precision for nearby positions however far they are from the origin.
+    pub fn offset_between(&self, from: &SectorPosition, to: &SectorPosition) -> Vec3 {
+        let axis = |a: i64, b: i64, oa: f64, ob: f64| (b - a) as f64 * self.sector_size + (ob - oa);
+        (
+            axis(from.sector.0, to.sector.0, from.offset.0, to.offset.0),
+            axis(from.sector.1, to.sector.1, from.offset.1, to.offset.1),
+            axis(from.sector.2, to.sector.2, from.offset.2, to.offset.2),
+        )
+    }
+
+    /// Returns the distance between two positions.
+    pub fn distance(&self, a: &SectorPosition, b: &SectorPosition) -> f64 {
+        let d = self.offset_between(a, b);
+        (d.0 * d.0 + d.1 * d.1 + d.2 * d.2).sqrt()
+    }
+}
+
+/// A floating origin for handing positions to `f32` engines.
+///
+/// Engine-space coordinates are offsets from an origin kept near the camera, scaled into engine
+/// units. When the camera strays more than the rebase distance from the origin, `recenter` moves
+/// the origin to the camera's sector and reports the shift, so the engine can move its objects
+/// and coordinates never grow large enough for `f32` jitter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingOrigin {
+    grid: SectorGrid,
+    origin: SectorPosition, // Always a sector corner
+    scale: f64,             // Engine units per universe unit
+    rebase_distance: f64,   // Universe units
+}
+
+impl FloatingOrigin {
+    /// Creates a floating origin at the corner of the camera's sector.
+    ///
+    /// # Arguments
+    ///
+    /// * `grid` - The sector grid positions are expressed in.
+    /// * `camera` - The camera position.
+    /// * `scale` - Engine units per universe unit.
+    /// * `rebase_distance` - How far the camera may move from the origin before `recenter`
+    ///   moves it, in universe units.
+    pub fn new(grid: SectorGrid, camera: &SectorPosition, scale: f64, rebase_distance: f64) -> FloatingOrigin {
+        FloatingOrigin {
+            grid,
+            origin: SectorPosition {
+                sector: grid.normalize(camera).sector,
+                offset: (0.0, 0.0, 0.0),
+            },
+            scale,
+            rebase_distance,
+        }
+    }
+
+    /// Returns the current origin.
+    pub fn origin(&self) -> &SectorPosition {
+        &self.origin
+    }
+
+    /// Converts a position into engine space in `f64`.
+    pub fn to_local_f64(&self, position: &SectorPosition) -> Vec3 {
+        let d = self.grid.offset_between(&self.origin, position);
+        (d.0 * self.scale, d.1 * self.scale, d.2 * self.scale)
+    }
+
+    /// Converts a position into engine space.
+    pub fn to_local(&self, position: &SectorPosition) -> [f32; 3] {
+        let d = self.to_local_f64(position);
+        [d.0 as f32, d.1 as f32, d.2 as f32]
+    }
+
+    /// Converts an engine-space position back into sector coordinates.
+    pub fn from_local(&self, local: [f32; 3]) -> SectorPosition {
+        let delta = (local[0] as f64 / self.scale, local[1] as f64 / self.scale, local[2] as f64 / self.scale);
+        self.grid.translate(&self.origin, delta)
+    }
+
+    /// Moves the origin to the camera's sector if the camera is beyond the rebase distance.
+    ///
+    /// # Returns
+    ///
+    /// The engine-space shift to add to every existing object, or `None` if the origin stayed.
+    pub fn recenter(&mut self, camera: &SectorPosition) -> Option<[f32; 3]> {
+        if self.grid.distance(&self.origin, camera) <= self.rebase_distance {
+            return None;
+        }
+        let old = self.origin;
+        self.origin = SectorPosition {
+            sector: self.grid.normalize(camera).sector,
+            offset: (0.0, 0.0, 0.0),
+        };
+        let shift = self.grid.offset_between(&self.origin, &old);
+        Some([(shift.0 * self.scale) as f32, (shift.1 * self.scale) as f32, (shift.2 * self.scale) as f32])
+    }
+}
+
+/// Which axis points up in a coordinate frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+    Y,
+    Z,
+}
+
+/// Whether a coordinate frame is right- or left-handed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handedness {
+    Right,
+    Left,
+}
+
+/// The winding of a triangle's front face as seen from in front of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// The axis and unit conventions of an engine or file format.
+///
+/// TerraForge itself is right-handed and Z-up: z points through the north pole, x through
+/// longitude 0 and y through longitude 90° east, with planet distances in kilometres. A frame
+/// maps those axes onto the target's, keeping x as the first axis and mirroring the remaining
+/// horizontal axis when the handedness differs, so shapes come out unreflected. Triangles are
+/// reordered to the target's front-face winding. Every exporter converts through a frame instead
+/// of swapping axes itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateFrame {
+    pub up: UpAxis,
+    pub handedness: Handedness,
+    pub winding: Winding, // Front-face winding the target renders
+    pub scale: f64, // Target units per TerraForge unit (kilometres for planet positions)
+}
+
+/// TerraForge's own frame.
+impl Default for CoordinateFrame {
+    fn default() -> Self {
+        CoordinateFrame::TERRAFORGE
+    }
+}
+
+impl CoordinateFrame {
+    /// TerraForge's own frame: Z-up, right-handed, counter-clockwise, kilometres.
+    pub const TERRAFORGE: CoordinateFrame = CoordinateFrame {
+        up: UpAxis::Z,
+        handedness: Handedness::Right,
+        winding: Winding::CounterClockwise,
+        scale: 1.0,
+    };
+    /// Unreal Engine: Z-up, left-handed, clockwise, centimetres.
+    pub const UNREAL: CoordinateFrame = CoordinateFrame {
+        up: UpAxis::Z,
+        handedness: Handedness::Left,
+        winding: Winding::Clockwise,
+        scale: 100_000.0,
+    };
+    /// Unity: Y-up, left-handed, clockwise, metres.
+    pub const UNITY: CoordinateFrame = CoordinateFrame {
+        up: UpAxis::Y,
+        handedness: Handedness::Left,
+        winding: Winding::Clockwise,
+        scale: 1000.0,
+    };
+    /// Godot: Y-up, right-handed, clockwise, metres.
+    pub const GODOT: CoordinateFrame = CoordinateFrame {
+        up: UpAxis::Y,
+        handedness: Handedness::Right,
+        winding: Winding::Clockwise,
+        scale: 1000.0,
+    };
+    /// Bevy and glTF: Y-up, right-handed, counter-clockwise, metres.
+    pub const BEVY: CoordinateFrame = CoordinateFrame {
+        up: UpAxis::Y,
+        handedness: Handedness::Right,
+        winding: Winding::CounterClockwise,
+        scale: 1000.0,
+    };
+
+    /// Returns the frame with a different scale.
+    pub fn with_scale(self, scale: f64) -> CoordinateFrame {
+        CoordinateFrame { scale, ..self }
+    }
+
+    /// Converts a point or direction from TerraForge's frame into this one.
+    pub fn from_native(&self, (x, y, z): Vec3) -> Vec3 {
+        let s = self.scale;
+        match (self.up, self.handedness) {
+            (UpAxis::Z, Handedness::Right) => (x * s, y * s, z * s),
+            (UpAxis::Z, Handedness::Left) => (x * s, -y * s, z * s),
+            (UpAxis::Y, Handedness::Right) => (x * s, z * s, -y * s),
+            (UpAxis::Y, Handedness::Left) => (x * s, z * s, y * s),
+        }
+    }
+
+    /// Converts a point or direction from this frame into TerraForge's.
+    pub fn to_native(&self, (x, y, z): Vec3) -> Vec3 {
+        let s = 1.0 / self.scale;
+        match (self.up, self.handedness) {
+            (UpAxis::Z, Handedness::Right) => (x * s, y * s, z * s),
+            (UpAxis::Z, Handedness::Left) => (x * s, -y * s, z * s),
+            (UpAxis::Y, Handedness::Right) => (x * s, -z * s, y * s),
+            (UpAxis::Y, Handedness::Left) => (x * s, z * s, y * s),
+        }
+    }
+
+    /// Converts a point or direction from this frame into another.
+    pub fn convert_to(&self, target: &CoordinateFrame, point: Vec3) -> Vec3 {
+        target.from_native(self.to_native(point))
+    }
+
+    /// Reorders a triangle wound counter-clockwise from outside, as TerraForge's meshes are, so
+    /// its front face points outward under this frame's winding.
+    ///
+    /// The axis conversion never reflects a shape, so only the winding decides the order.
+    pub fn triangle(&self, [a, b, c]: [usize; 3]) -> [usize; 3] {
+        if self.winding == Winding::Clockwise {
+            [a, c, b]
+        } else {
+            [a, b, c]
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::npz::NpzWriter;
+use crate::planet::{Layer, Planet};
+use crate::projection::Projection;
+use rayon::prelude::*;
+use std::io;
+
+/// Describes one paired input/target dataset.
+///
+/// Every generated world is rasterized at the same resolution so the samples stack into fixed
+/// shape tensors regardless of each planet's cell count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetSpec {
+    pub inputs: Vec<Layer>, // Input channels, in order
+    pub target: Layer,      // The layer the model should predict
+    pub projection: Projection,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for DatasetSpec {
+    /// Climate inputs (latitude and insolation) paired with surface temperature at 128x64.
+    fn default() -> Self {
+        DatasetSpec {
+            inputs: vec![Layer::Latitude, Layer::Insolation],
+            target: Layer::Temperature,
+            projection: Projection::Equirectangular,
+            width: 128,
+            height: 64,
+        }
+    }
+}
+
+/// Generates one world per seed and writes the paired tensors to a numpy `.npz` archive.
+///
+/// The archive contains:
+///
+/// * `inputs` - `float32` with shape `(samples, height, width, channels)`.
+/// * `targets` - `float32` with shape `(samples, height, width)`.
+/// * `seeds` - `uint64` with shape `(samples,)`, the seed each sample was generated from.
+///
+/// Worlds are generated in parallel. Pixels outside the projected globe are `NaN`.
+///
+/// # Arguments
+///
+/// * `path` - The output `.npz` file.
+/// * `seeds` - One seed per sample.
+/// * `spec` - The layers and raster settings to export.
+/// * `generate` - Builds (and optionally simulates) the world for a seed.
+///
+/// # Returns
+///
+/// The number of samples written, or an `io::Error` if the archive could not be written.
+pub fn export_dataset<F>(path: &str, seeds: &[u64], spec: &DatasetSpec, generate: F) -> io::Result<usize>
+where
+    F: Fn(u64) -> Planet + Sync,
+{
+    let pixels = spec.width * spec.height;
+    let channels = spec.inputs.len();
+
+    let samples: Vec<(Vec<f32>, Vec<f32>)> = seeds
+        .par_iter()
+        .map(|&seed| {
+            let planet = generate(seed);
+            let cells = planet.rasterize_cells(spec.projection, spec.width, spec.height);
+            let sample = |values: &[f64], pixel: usize| -> f32 {
+                cells[pixel].map_or(f32::NAN, |cell| values[cell] as f32)
+            };
+
+            let input_layers: Vec<Vec<f64>> = spec.inputs.iter().map(|&layer| planet.layer(layer)).collect();
+            let mut inputs = Vec::with_capacity(pixels * channels);
+            for pixel in 0..pixels {
+                for values in &input_layers {
+                    inputs.push(sample(values, pixel));
+                }
+            }
+
+            let target_values = planet.layer(spec.target);
+            let targets = (0..pixels).map(|pixel| sample(&target_values, pixel)).collect();
+
+            (inputs, targets)
+        })
+        .collect();
+
+    let mut inputs = Vec::with_capacity(seeds.len() * pixels * channels);
+    let mut targets = Vec::with_capacity(seeds.len() * pixels);
+    for (sample_inputs, sample_targets) in samples {
+        inputs.extend(sample_inputs);
+        targets.extend(sample_targets);
+    }
+
+    let mut archive = NpzWriter::new();
+    archive.add_f32("inputs", &[seeds.len(), spec.height, spec.width, channels], &inputs);
+    archive.add_f32("targets", &[seeds.len(), spec.height, spec.width], &targets);
+    archive.add_u64("seeds", &[seeds.len()], seeds);
+    archive.finish(path)?;
+
+    Ok(seeds.len())
+}
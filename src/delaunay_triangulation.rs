@@ -1,12 +1,23 @@
 use spade::{DelaunayTriangulation, Point2, Triangulation};
+#[cfg(not(target_arch = "wasm32"))]
 use spade::handles::VoronoiVertex;
-use std::io::{self, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::coords::CoordinateFrame;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::planet::TriangulationError;
+use crate::planet::SITE_EPSILON;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::geom::spherical::{subdivide_arc, GeodesicSubdivision};
+use crate::vector::{cross, dot, normalize, scale, sub, Vec3};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
 
 /// Performs a stereographic projection of a 3D point onto a 2D plane.
 ///
 /// This function projects a point from the surface of a sphere onto a plane 
-/// using stereographic projection. It's adjusted for Unreal Engine coordinates where z is up.
+/// using stereographic projection from the pole at -y.
 ///
 /// # Arguments
 ///
@@ -16,17 +27,21 @@ use std::fs::File;
 ///
 /// # Returns
 ///
-/// A `Point2<f64>` representing the projected 2D point.
-fn stereographic_projection(x: f64, y: f64, z: f64) -> Point2<f64> {
-    // Adjust for Unreal Engine coordinates (z is up)
+/// A `Point2<f64>` representing the projected 2D point, or `None` for the pole itself (and
+/// anything at or beyond it), which projects to infinity.
+#[cfg(not(target_arch = "wasm32"))]
+fn stereographic_projection(x: f64, y: f64, z: f64) -> Option<Point2<f64>> {
+    if 1.0 + y <= f64::EPSILON {
+        return None;
+    }
     let scale = 1.0 / (1.0 + y);
-    Point2::new(x * scale, -z * scale)
+    Some(Point2::new(x * scale, -z * scale))
 }
 
 /// Performs an inverse stereographic projection of a 2D point back onto a 3D sphere.
 ///
 /// This function takes a point on a 2D plane and projects it back onto the surface of a unit sphere.
-/// It's adjusted for Unreal Engine coordinates where z is up.
+/// It is the inverse of `stereographic_projection`.
 ///
 /// # Arguments
 ///
@@ -35,9 +50,10 @@ fn stereographic_projection(x: f64, y: f64, z: f64) -> Point2<f64> {
 /// # Returns
 ///
 /// A tuple `(x, y, z)` representing the 3D point on the sphere's surface.
+#[cfg(not(target_arch = "wasm32"))]
 fn inverse_stereographic_projection(point: Point2<f64>) -> (f64, f64, f64) {
     let x = point.x;
-    let z = -point.y;  // Adjust for Unreal Engine coordinates
+    let z = -point.y;
     let x2z2 = x*x + z*z;
     let scale = 2.0 / (x2z2 + 1.0);
     let y = (x2z2 - 1.0) / (x2z2 + 1.0);
@@ -58,6 +74,7 @@ fn inverse_stereographic_projection(point: Point2<f64>) -> (f64, f64, f64) {
 /// # Returns
 ///
 /// A tuple `(x, y, z)` representing the coordinates of the spherical circumcenter.
+#[cfg(not(target_arch = "wasm32"))]
 fn calculate_spherical_circumcenter(a: (f64, f64, f64), b: (f64, f64, f64), c: (f64, f64, f64)) -> (f64, f64, f64) {
     // Cross product of (b-a) and (c-a)
     let normal = (
@@ -83,42 +100,52 @@ fn calculate_spherical_circumcenter(a: (f64, f64, f64), b: (f64, f64, f64), c: (
 ///
 /// # Returns
 ///
-/// A `DelaunayTriangulation<Point2<f64>>` representing the Delaunay triangulation of the projected points.
-pub fn create_spherical_voronoi(points: Vec<(f64, f64, f64)>) -> DelaunayTriangulation<Point2<f64>> {
+/// A `DelaunayTriangulation<Point2<f64>>` representing the Delaunay triangulation of the projected points,
+/// or `TriangulationError::InvalidSite` for a point that is not finite or sits on the projection pole at -y.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_spherical_voronoi(points: Vec<(f64, f64, f64)>) -> Result<DelaunayTriangulation<Point2<f64>>, TriangulationError> {
     // Project points to 2D
     let projected_points: Vec<Point2<f64>> = points
         .iter()
-        .map(|&(x, y, z)| stereographic_projection(x, y, z))
-        .collect();
+        .enumerate()
+        .map(|(i, &(x, y, z))| stereographic_projection(x, y, z).ok_or(TriangulationError::InvalidSite(i)))
+        .collect::<Result<_, _>>()?;
 
     // Create Delaunay triangulation
     let mut triangulation = DelaunayTriangulation::<Point2<f64>>::new();
-    for point in projected_points {
-        triangulation.insert(point).expect("Failed to insert point");
+    for (i, point) in projected_points.into_iter().enumerate() {
+        triangulation.insert(point).map_err(|_| TriangulationError::InvalidSite(i))?;
     }
 
     // Stitch south pole
     let south_pole = Point2::new(0.0, 0.0);
-    triangulation.insert(south_pole).expect("Failed to insert south pole");
+    triangulation.insert(south_pole).map_err(|_| TriangulationError::Degenerate)?;
 
-    triangulation
+    Ok(triangulation)
 }
 
 /// Prints the edges of the Voronoi diagram to a file.
 ///
 /// This function calculates and writes the edges of the Voronoi diagram to a file named "voronoi_edges.txt".
-/// The edges are represented as debug lines in a format suitable for visualization in Unreal Engine.
+/// The edges are represented as debug lines in a format suitable for visualization in Unreal Engine,
+/// converted with `CoordinateFrame::UNREAL`. For importing planets into Unreal, prefer the structured
+/// exporters in `unreal`.
 ///
 /// # Arguments
 ///
 /// * `triangulation` - A reference to the `DelaunayTriangulation<Point2<f64>>` object.
+/// * `subdivision` - How many lines to draw each edge with, so edges follow the sphere rather than
+///   cutting through it.
 ///
 /// # Returns
 ///
 /// A `std::io::Result<()>`, which is `Ok(())` if the file was written successfully, 
 /// or an `Err` containing the I/O error if there was a problem writing the file.
-pub fn print_voronoi_edges(triangulation: &DelaunayTriangulation<Point2<f64>>) -> std::io::Result<()> {
+#[cfg(not(target_arch = "wasm32"))]
+pub fn print_voronoi_edges(triangulation: &DelaunayTriangulation<Point2<f64>>, subdivision: GeodesicSubdivision) -> std::io::Result<()> {
     let mut file = File::create("voronoi_edges.txt")?;
+    // The unit sphere drawn 1000 Unreal units across
+    let frame = CoordinateFrame::UNREAL.with_scale(1000.0);
 
     for voronoi_edge in triangulation.undirected_voronoi_edges() {
         let (from, to) = match voronoi_edge.vertices() {
@@ -141,15 +168,132 @@ pub fn print_voronoi_edges(triangulation: &DelaunayTriangulation<Point2<f64>>) -
             _ => continue, // Skip edges that go to infinity
         };
 
-        // Adjust coordinates for Unreal Engine scale by 1000
-        let from_unreal = (from.0 * 1000.0, from.2 * 1000.0, -from.1 * 1000.0);
-        let to_unreal = (to.0 * 1000.0, to.2 * 1000.0, -to.1 * 1000.0);
+        for pair in subdivide_arc(from, to, subdivision).windows(2) {
+            let from_unreal = frame.from_native(pair[0]);
+            let to_unreal = frame.from_native(pair[1]);
 
-        writeln!(file, "DrawDebugLine(GetWorld(), FVector({:.4}, {:.4}, {:.4}), FVector({:.4}, {:.4}, {:.4}), FColor::Blue, true, -1.0f, 0, 2.0f);",
-            from_unreal.0, from_unreal.1, from_unreal.2,
-            to_unreal.0, to_unreal.1, to_unreal.2
-        )?;
+            writeln!(file, "DrawDebugLine(GetWorld(), FVector({:.4}, {:.4}, {:.4}), FVector({:.4}, {:.4}, {:.4}), FColor::Blue, true, -1.0f, 0, 2.0f);",
+                from_unreal.0, from_unreal.1, from_unreal.2,
+                to_unreal.0, to_unreal.1, to_unreal.2
+            )?;
+        }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Finds the sites that coincide with an earlier site to within `SITE_EPSILON`.
+///
+/// Sorts the sites along x and compares only those within the epsilon of each other there, so
+/// this takes O(n log n) for any sensible input.
+///
+/// # Arguments
+///
+/// * `unit` - The sites on the unit sphere.
+///
+/// # Returns
+///
+/// For every site, the earliest site it coincides with, or `None` if it is the first of its kind.
+pub(crate) fn duplicate_sites(unit: &[Vec3]) -> Vec<Option<usize>> {
+    let mut order: Vec<usize> = (0..unit.len()).collect();
+    order.sort_by(|&a, &b| unit[a].0.total_cmp(&unit[b].0));
+    let mut duplicate_of: Vec<Option<usize>> = vec![None; unit.len()];
+    for (position, &a) in order.iter().enumerate() {
+        for &b in &order[position + 1..] {
+            if unit[b].0 - unit[a].0 >= SITE_EPSILON {
+                break;
+            }
+            let offset = sub(unit[a], unit[b]);
+            if dot(offset, offset) < SITE_EPSILON * SITE_EPSILON {
+                let (first, later) = (a.min(b), a.max(b));
+                let root = duplicate_of[first].unwrap_or(first);
+                if duplicate_of[later].is_none_or(|existing| root < existing) {
+                    duplicate_of[later] = Some(root);
+                }
+            }
+        }
+    }
+    duplicate_of
+}
+
+// Projects a unit vector stereographically from `pole` onto the plane spanned by `axis_x` and
+// `axis_z`; `None` for the pole itself. The denominator is half the squared chord to the pole
+// rather than `1 + y`, which keeps full precision for sites right next to the pole.
+fn project_from_pole(p: Vec3, pole: Vec3, axis_x: Vec3, axis_z: Vec3) -> Option<Point2<f64>> {
+    let offset = sub(p, pole);
+    let denominator = dot(offset, offset) * 0.5;
+    if denominator <= 0.0 {
+        return None;
+    }
+    Some(Point2::new(dot(p, axis_x) / denominator, -dot(p, axis_z) / denominator))
+}
+
+/// Computes the Delaunay triangulation of points on a sphere.
+///
+/// The points are rotated so that the first point sits on the projection pole, the remaining
+/// points are projected stereographically and triangulated in the plane, and the first point is
+/// then stitched back in by connecting it to every edge of the planar convex hull. Because the
+/// pole is a real site rather than an inserted placeholder, the result covers the whole sphere,
+/// and no other site lands on the projection's singular point unless it coincides with the first.
+///
+/// # Arguments
+///
+/// * `sites` - The points to triangulate. They may be at any radius; only their directions are used.
+///
+/// # Returns
+///
+/// A vector of triangles as indices into `sites`, each wound counter-clockwise when viewed from
+/// outside the sphere. Sites that coincide with an earlier site, or that are not finite, are left
+/// out of the triangulation; `duplicate_sites` finds the former up front.
+pub fn spherical_delaunay(sites: &[(f64, f64, f64)]) -> Vec<[usize; 3]> {
+    if sites.len() < 4 {
+        return Vec::new();
+    }
+
+    let unit: Vec<Vec3> = sites.iter().map(|&p| normalize(p)).collect();
+
+    // Build a frame whose -y axis points at the first site, so it lands on the projection pole
+    let pole = unit[0];
+    let axis_y = scale(pole, -1.0);
+    let helper = if axis_y.0.abs() < 0.9 { (1.0, 0.0, 0.0) } else { (0.0, 0.0, 1.0) };
+    let axis_x = normalize(cross(helper, axis_y));
+    let axis_z = cross(axis_x, axis_y);
+
+    let mut triangulation = DelaunayTriangulation::<Point2<f64>>::new();
+    let mut handle_to_site: Vec<usize> = Vec::with_capacity(sites.len());
+    for (i, &p) in unit.iter().enumerate().skip(1) {
+        let Some(projected) = project_from_pole(p, pole, axis_x, axis_z) else { continue };
+        match triangulation.insert(projected) {
+            Ok(handle) if handle.index() == handle_to_site.len() => handle_to_site.push(i),
+            _ => {} // Duplicate of an existing site or an unprojectable point
+        }
+    }
+
+    let mut triangles: Vec<[usize; 3]> = triangulation
+        .inner_faces()
+        .map(|face| {
+            let [a, b, c] = face.vertices();
+            [
+                handle_to_site[a.fix().index()],
+                handle_to_site[b.fix().index()],
+                handle_to_site[c.fix().index()],
+            ]
+        })
+        .collect();
+
+    // Close the hole around the pole
+    for edge in triangulation.convex_hull() {
+        let [from, to] = edge.vertices();
+        triangles.push([0, handle_to_site[from.fix().index()], handle_to_site[to.fix().index()]]);
+    }
+
+    // Enforce a consistent outward-facing winding
+    for triangle in triangles.iter_mut() {
+        let [a, b, c] = *triangle;
+        let normal = cross(sub(unit[b], unit[a]), sub(unit[c], unit[a]));
+        if dot(normal, unit[a]) < 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+
+    triangles
+}
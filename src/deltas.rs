@@ -0,0 +1,213 @@
+use crate::geology::RockType;
+use crate::geom::spherical::{distance, interpolate, to_lat_lon};
+use crate::hydrology::Drainage;
+use crate::noise::mix64;
+use crate::planet::Planet;
+use crate::vector::{add, cross, normalize, scale, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The shape a river takes where it meets the sea.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiverMouthKind {
+    /// A fan of sediment built out into the sea and crossed by branching channels, where the river
+    /// brings more sediment than the tides can carry away.
+    Delta,
+    /// A drowned, funnel-shaped river mouth scoured by the tides.
+    Estuary,
+}
+
+/// Settings for `Planet::shape_river_mouths`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiverMouthSettings {
+    pub min_flow: f64,        // Discharge in m³/s a river needs for its mouth to be shaped
+    pub delta_threshold: f64, // Sediment supply, in thousand m³/s times km of relief, per unit of tidal energy that builds a delta
+    pub channel_flow: f64,    // Discharge in m³/s each delta channel carries
+    pub meander: f64,         // Sideways wander of delta channels as a share of their length
+}
+
+impl Default for RiverMouthSettings {
+    /// Shapes the mouths of rivers of 1000 m³/s or more. A river of 5000 m³/s draining mountains
+    /// 4 km high builds a delta against small tides, and every 2000 m³/s feeds another channel.
+    fn default() -> Self {
+        RiverMouthSettings {
+            min_flow: 1000.0,
+            delta_threshold: 5.0,
+            channel_flow: 2000.0,
+            meander: 0.15,
+        }
+    }
+}
+
+/// A river mouth shaped by `Planet::shape_river_mouths`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiverMouth {
+    pub kind: RiverMouthKind,
+    pub cell: usize,                    // The last land cell of the river before the sea
+    pub flow: f64,                      // Discharge in m³/s
+    pub cells: Vec<usize>,              // Cells added around the mouth
+    pub channels: Vec<Vec<(f64, f64)>>, // Channel polylines as (latitude, longitude) in degrees, from upstream to the sea
+}
+
+// Height in metres of the top of a delta fan at the river mouth
+const FAN_HEIGHT: f64 = 4.0;
+// Depth in metres the sea floor off a delta shoals up to with its finest sediment
+const PRODELTA_DEPTH: f64 = -20.0;
+// Depth in metres of an estuary at the coast, deepening seaward
+const ESTUARY_DEPTH: f64 = -10.0;
+// Intermediate points along each delta channel
+const CHANNEL_POINTS: usize = 4;
+// Most channels a delta splits into
+const MAX_CHANNELS: usize = 6;
+
+impl Planet {
+    /// Builds deltas and estuaries where large rivers meet the sea, so coastlines near big rivers
+    /// are not unnaturally clean.
+    ///
+    /// Every river mouth weighs the sediment the river brings, its discharge times the relief of
+    /// its catchment, against the tides, which scour harder over a shallow shelf. Sediment wins at
+    /// a delta: new cells subdivide the mouth toward the sea and rise into a low fan of alluvium
+    /// pushing the coast outward, and the sea floor beyond shoals. Tides win at an estuary: the
+    /// mouth and new cells up and down its axis sink into a funnel of water deepening seaward.
+    ///
+    /// Cells are only added, so existing cell indices stay valid, but the drainage no longer
+    /// matches the planet; compute it again for flows over the new coast.
+    ///
+    /// # Arguments
+    ///
+    /// * `drainage` - The drainage of the planet, e.g. from `Drainage::compute`.
+    /// * `settings` - Which rivers to shape and how.
+    /// * `seed` - The seed for the wander of delta channels.
+    ///
+    /// # Returns
+    ///
+    /// The shaped mouths, largest river first, with the braided channels across each delta and the
+    /// channel up each estuary.
+    pub fn shape_river_mouths(&mut self, drainage: &Drainage, settings: &RiverMouthSettings, seed: u64) -> Vec<RiverMouth> {
+        let flow = drainage.flow();
+        // The highest ground draining to every cell, for the relief that feeds sediment
+        let mut relief: Vec<f64> = self.elevation().iter().map(|&e| e.max(0.0)).collect();
+        for &cell in drainage.order() {
+            if let Some(next) = drainage.downstream(cell) {
+                relief[next] = relief[next].max(relief[cell]);
+            }
+        }
+        let mut mouths: Vec<(usize, usize)> = (0..self.cell_count())
+            .filter_map(|cell| {
+                let sea = drainage.downstream(cell)?;
+                (!self.is_ocean(cell) && self.is_ocean(sea) && flow[cell] >= settings.min_flow).then_some((cell, sea))
+            })
+            .collect();
+        mouths.sort_by(|a, b| flow[b.0].total_cmp(&flow[a.0]).then(a.0.cmp(&b.0)));
+
+        let mut shaped = Vec::new();
+        for (mouth, sea) in mouths {
+            let elevation = self.elevation();
+            let site = self.sites()[mouth];
+            let shore: Vec<usize> = self.neighbors(mouth).iter().copied().filter(|&n| self.is_ocean(n)).collect();
+            if self.is_ocean(mouth) || shore.is_empty() {
+                // A larger river's delta or estuary already swallowed this mouth
+                continue;
+            }
+            let upstream = self
+                .neighbors(mouth)
+                .iter()
+                .copied()
+                .filter(|&n| n < flow.len() && drainage.downstream(n) == Some(mouth))
+                .max_by(|&a, &b| flow[a].total_cmp(&flow[b]));
+
+            // Tides range wider over a shallow shelf, as in `Planet::tide_at`
+            let depth = shore.iter().map(|&n| -elevation[n]).fold(f64::INFINITY, f64::min);
+            let shallowness = (1.0 - depth / 200.0).clamp(0.0, 1.0);
+            let tide = self.tides().max_amplitude() * (1.0 + 2.0 * shallowness);
+            let sediment = flow[mouth] / 1000.0 * relief[mouth] / 1000.0;
+            let kind = if sediment >= settings.delta_threshold * (1.0 + tide) { RiverMouthKind::Delta } else { RiverMouthKind::Estuary };
+
+            // Subdivide toward the sea, and for an estuary up the river too
+            let mut added: Vec<(usize, f64)> = Vec::new(); // New cell and its share of the way out to sea
+            let mut insert = |planet: &mut Planet, point: Vec3, share: f64| {
+                let (lat, lon) = to_lat_lon(point);
+                if let Some(cell) = planet.insert_site(lat, lon) {
+                    added.push((cell, share));
+                }
+            };
+            for &n in &shore {
+                let target = self.sites()[n];
+                insert(self, interpolate(site, target, 1.0 / 3.0), 1.0 / 3.0);
+                insert(self, interpolate(site, target, 2.0 / 3.0), 2.0 / 3.0);
+            }
+            if let (RiverMouthKind::Estuary, Some(up)) = (kind, upstream) {
+                insert(self, interpolate(self.sites()[up], site, 0.5), -0.5);
+            }
+
+            let mut elevation = self.elevation().to_vec();
+            let mut rock_types = self.rock_types().to_vec();
+            match kind {
+                RiverMouthKind::Delta => {
+                    for &(cell, share) in &added {
+                        elevation[cell] = FAN_HEIGHT * (1.0 - 1.5 * share).max(0.0);
+                        rock_types[cell] = RockType::Alluvium;
+                    }
+                    elevation[mouth] = elevation[mouth].min(FAN_HEIGHT * 2.0);
+                    rock_types[mouth] = RockType::Alluvium;
+                    for &n in &shore {
+                        if elevation[n] < PRODELTA_DEPTH {
+                            elevation[n] += (PRODELTA_DEPTH - elevation[n]) * 0.5;
+                        }
+                    }
+                }
+                RiverMouthKind::Estuary => {
+                    for &(cell, share) in &added {
+                        elevation[cell] = elevation[cell].min(ESTUARY_DEPTH * (1.0 + share));
+                        rock_types[cell] = RockType::Sedimentary;
+                    }
+                    elevation[mouth] = elevation[mouth].min(ESTUARY_DEPTH);
+                }
+            }
+            self.set_elevation(elevation);
+            self.set_rock_types(rock_types);
+
+            let channels = match kind {
+                RiverMouthKind::Delta => {
+                    let count = ((flow[mouth] / settings.channel_flow.max(f64::MIN_POSITIVE)).round() as usize).clamp(2, MAX_CHANNELS);
+                    let mut rng = StdRng::seed_from_u64(mix64(seed ^ mix64(mouth as u64)));
+                    let start = upstream.map_or(site, |up| interpolate(self.sites()[up], site, 0.5));
+                    // The channels fan out across the shore, each ending on the delta front
+                    (0..count)
+                        .map(|i| {
+                            let n = shore[i * shore.len() / count];
+                            let end = interpolate(site, self.sites()[n], 2.0 / 3.0 + rng.gen_range(0.0..1.0 / 3.0));
+                            braided_channel(&mut rng, start, site, end, settings.meander)
+                        })
+                        .collect()
+                }
+                RiverMouthKind::Estuary => {
+                    let start = upstream.map_or(site, |up| self.sites()[up]);
+                    vec![[start, site, self.sites()[sea]].into_iter().map(to_lat_lon).collect()]
+                }
+            };
+            shaped.push(RiverMouth {
+                kind,
+                cell: mouth,
+                flow: flow[mouth],
+                cells: added.into_iter().map(|(cell, _)| cell).collect(),
+                channels,
+            });
+        }
+        shaped
+    }
+}
+
+// A channel from `start` through the river mouth to `end`, wandering sideways across the delta
+fn braided_channel(rng: &mut StdRng, start: Vec3, mouth: Vec3, end: Vec3, meander: f64) -> Vec<(f64, f64)> {
+    let side = normalize(cross(mouth, end));
+    let length = distance(mouth, end);
+    let mut points = vec![start, mouth];
+    for i in 1..CHANNEL_POINTS {
+        let along = interpolate(mouth, end, i as f64 / CHANNEL_POINTS as f64);
+        let offset = rng.gen_range(-1.0..1.0) * meander * length;
+        points.push(normalize(add(along, scale(side, offset))));
+    }
+    points.push(end);
+    points.into_iter().map(to_lat_lon).collect()
+}
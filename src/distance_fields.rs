@@ -0,0 +1,135 @@
+use crate::hydrology::Drainage;
+use crate::layers::LayerMap;
+use crate::planet::Planet;
+use crate::tectonics::distance_from;
+
+/// One of the distance fields of `DistanceFields`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DistanceField {
+    /// Distance to the shoreline, measured on land and at sea alike.
+    Coast,
+    /// Distance to the nearest river.
+    River,
+    /// Distance to the nearest mountain ridge.
+    Ridge,
+}
+
+impl DistanceField {
+    /// Every field, in declaration order.
+    pub const ALL: [DistanceField; 3] = [DistanceField::Coast, DistanceField::River, DistanceField::Ridge];
+
+    /// Returns the key the field is stored under by `DistanceFields::insert_into`.
+    pub fn key(self) -> &'static str {
+        match self {
+            DistanceField::Coast => "distance_to_coast",
+            DistanceField::River => "distance_to_river",
+            DistanceField::Ridge => "distance_to_ridge",
+        }
+    }
+}
+
+/// Settings for `DistanceFields::compute`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceFieldSettings {
+    pub river_flow: f64,      // Discharge in m³/s that makes a river, as for `Drainage::rivers`
+    pub ridge_elevation: f64, // Metres a drainage divide must rise to count as a mountain ridge
+}
+
+impl Default for DistanceFieldSettings {
+    /// Rivers of 1000 m³/s or more and ridges 1500 m or higher.
+    fn default() -> Self {
+        DistanceFieldSettings {
+            river_flow: 1000.0,
+            ridge_elevation: 1500.0,
+        }
+    }
+}
+
+/// Geodesic distances from every cell to the nearest coast, river and mountain ridge, as inputs to
+/// biome rules and as exported masks.
+///
+/// Distances are measured in kilometres along the cell graph, so they follow the surface of the
+/// planet rather than cutting through it. A field with nothing to measure to, such as the distance
+/// to a ridge on a flat world, is infinite everywhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceFields {
+    coast: Vec<f64>,
+    river: Vec<f64>,
+    ridge: Vec<f64>,
+}
+
+impl DistanceFields {
+    /// Computes the distance fields with a multi-source Dijkstra search from every source cell.
+    ///
+    /// Coast cells are the cells with a neighbor on the other side of sea level. River cells are
+    /// the land cells of `Drainage::rivers`. Ridge cells are the drainage divides, cells no other
+    /// cell drains into, at or above the ridge elevation.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet.
+    /// * `drainage` - The drainage of the planet, e.g. from `Drainage::compute`.
+    /// * `settings` - What counts as a river and a ridge.
+    pub fn compute(planet: &Planet, drainage: &Drainage, settings: &DistanceFieldSettings) -> DistanceFields {
+        let cells = 0..planet.cell_count().min(drainage.flow().len());
+        let coast: Vec<usize> = cells.clone().filter(|&cell| planet.neighbors(cell).iter().any(|&n| planet.is_ocean(n) != planet.is_ocean(cell))).collect();
+        let river: Vec<usize> = drainage.rivers(settings.river_flow).into_iter().flatten().filter(|&cell| !planet.is_ocean(cell)).collect();
+        let mut drained = vec![false; planet.cell_count()];
+        for cell in cells.clone() {
+            if let Some(next) = drainage.downstream(cell) {
+                drained[next] = true;
+            }
+        }
+        let ridge: Vec<usize> = cells.filter(|&cell| !drained[cell] && planet.elevation()[cell] >= settings.ridge_elevation).collect();
+        DistanceFields {
+            coast: distance_from(planet, &coast),
+            river: distance_from(planet, &river),
+            ridge: distance_from(planet, &ridge),
+        }
+    }
+
+    /// Returns the distance of every cell to the nearest coast in kilometres.
+    pub fn coast(&self) -> &[f64] {
+        &self.coast
+    }
+
+    /// Returns the distance of every cell to the nearest river in kilometres.
+    pub fn river(&self) -> &[f64] {
+        &self.river
+    }
+
+    /// Returns the distance of every cell to the nearest mountain ridge in kilometres.
+    pub fn ridge(&self) -> &[f64] {
+        &self.ridge
+    }
+
+    /// Returns the distance of every cell to the nearest source of a field in kilometres.
+    pub fn field(&self, field: DistanceField) -> &[f64] {
+        match field {
+            DistanceField::Coast => &self.coast,
+            DistanceField::River => &self.river,
+            DistanceField::Ridge => &self.ridge,
+        }
+    }
+
+    /// Returns a field as a mask, 255 at its sources fading linearly to 0 at `range_km` and
+    /// beyond, e.g. for blending textures near rivers.
+    pub fn mask(&self, field: DistanceField, range_km: f64) -> Vec<u8> {
+        self.field(field)
+            .iter()
+            .map(|&distance| ((1.0 - distance / range_km.max(f64::MIN_POSITIVE)).clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect()
+    }
+
+    /// Stores every field in a planet's custom layers as `f32` kilometres, under the keys of
+    /// `DistanceField::key`, so they are saved with planet files and compact planets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fields were computed for a planet with a different number of cells.
+    pub fn insert_into(&self, layers: &mut LayerMap) {
+        for field in DistanceField::ALL {
+            layers.insert_f32(field.key(), self.field(field).iter().map(|&distance| distance as f32).collect());
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::hydrology::Drainage;
+use crate::planet::Planet;
+use crate::render::materials::Material;
+use crate::vegetation::VegetationLayer;
+use rayon::prelude::*;
+
+/// The trophic level of an animal population.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaunaKind {
+    /// Grazers, browsers and, at sea, the fish that feed on plankton.
+    Herbivore,
+    /// Hunters of the herbivores.
+    Predator,
+}
+
+/// Settings for `Ecosystem`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EcologySettings {
+    pub max_herbivores: f64,   // Herbivores per km² the richest habitat can carry
+    pub max_species: f64,      // Vertebrate species in the richest habitat
+    pub herbivore_growth: f64, // Yearly growth rate of herbivores far below carrying capacity
+    pub predator_growth: f64,  // Yearly predator births per predator when herbivores are at carrying capacity
+    pub predator_death: f64,   // Yearly death rate of predators without prey
+    pub predator_ratio: f64,   // Predators per herbivore at carrying capacity at the balanced predator density
+}
+
+impl Default for EcologySettings {
+    /// Earth-like densities of up to 50 herbivores and 200 species per cell-sized patch of the
+    /// richest habitat, with predator/prey swings that settle over a decade or two.
+    fn default() -> Self {
+        EcologySettings {
+            max_herbivores: 50.0,
+            max_species: 200.0,
+            herbivore_growth: 1.0,
+            predator_growth: 0.4,
+            predator_death: 0.2,
+            predator_ratio: 0.02,
+        }
+    }
+}
+
+/// One row of a spawning table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnEntry {
+    pub kind: FaunaKind,
+    pub density: f64, // Animals per km²
+    pub weight: f64,  // Share of spawns, the entries of a table sum to 1.0 unless the cell is empty
+}
+
+/// What a cell can spawn, for survival games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnTable {
+    pub biome: Material,          // Picks the species list, e.g. camels in deserts and seals on sea ice
+    pub species: usize,           // How many distinct species to draw from that list
+    pub entries: Vec<SpawnEntry>, // Current populations, one entry per kind
+}
+
+// Largest simulation step in years; the dynamics run on yearly rates of order one
+const MAX_STEP_YEARS: f64 = 0.05;
+// Discharge in m³/s at which a river makes water plentiful whatever the rainfall
+const RIVER_FLOW: f64 = 50.0;
+
+// How much life a biome supports relative to the richest, before water and vegetation
+fn biome_factor(biome: Material) -> f64 {
+    match biome {
+        Material::DeepWater => 0.15,
+        Material::ShallowWater => 0.8,
+        Material::Sand => 0.3,
+        Material::Desert => 0.15,
+        Material::Grass => 0.8,
+        Material::Forest => 1.0,
+        Material::Tundra => 0.3,
+        Material::Rock => 0.15,
+        Material::Snow => 0.02,
+        Material::SeaIce => 0.1,
+    }
+}
+
+// Species richness response to mean annual temperature, peaking in the tropics
+fn warmth_factor(temperature: f64) -> f64 {
+    (-((temperature - 25.0) / 20.0).powi(2)).exp()
+}
+
+/// Per-cell species richness and animal populations with predator/prey dynamics.
+///
+/// Every cell gets a habitat quality from its biome, vegetation density and water, where water is
+/// rainfall, a river or a coast, whichever is most plentiful. Quality sets the herbivores the cell
+/// can carry and, with warmth, how many species live there; at sea the shallow shelf is rich and
+/// the open ocean poor. Herbivores grow logistically toward that capacity while predators eat them,
+/// grow with their catch and die back without it, so the two cycle around a balance: herbivores at
+/// `predator_death / predator_growth` of capacity and predators at whatever that leaves to eat.
+/// The cycles are damped, settling over a decade or two. Populations start away from the balance,
+/// and `step` advances them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ecosystem {
+    settings: EcologySettings,
+    biomes: Vec<Material>,
+    richness: Vec<f64>,   // Vertebrate species per cell
+    capacity: Vec<f64>,   // Herbivores per km² each cell can carry
+    herbivores: Vec<f64>, // Herbivores in units of the carrying capacity
+    predators: Vec<f64>,  // Predators in units of the balanced predator density
+    years_per_day: f64,
+    elapsed: f64, // Days stepped so far
+}
+
+impl Ecosystem {
+    /// Assigns species richness and starting populations to every cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet; its calendar converts yearly rates to its days.
+    /// * `vegetation` - The vegetation layer, e.g. from `VegetationLayer::from_planet`.
+    /// * `drainage` - The river network, e.g. from `Drainage::compute`.
+    /// * `settings` - Densities and rates of the predator/prey dynamics.
+    pub fn new(planet: &Planet, vegetation: &VegetationLayer, drainage: &Drainage, settings: EcologySettings) -> Ecosystem {
+        let biomes = planet.materials(vegetation);
+        let precipitation = planet.annual_precipitation();
+        let temperature = planet.mean_annual_temperature();
+        let density = vegetation.density();
+        let flow = drainage.flow();
+        let (richness, capacity) = (0..planet.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let biome = biome_factor(biomes[cell]);
+                let quality = if planet.is_ocean(cell) {
+                    biome
+                } else {
+                    let rain = 1.0 - (-precipitation[cell].max(0.0) / 800.0).exp();
+                    let river = 1.0 - (-flow[cell] / RIVER_FLOW).exp();
+                    let coast = if planet.is_coastal(cell) { 0.5 } else { 0.0 };
+                    let water = rain.max(river).max(coast);
+                    biome * (0.3 + 0.7 * density[cell]) * (0.4 + 0.6 * water)
+                };
+                let richness = settings.max_species * quality * (0.3 + 0.7 * warmth_factor(temperature[cell]));
+                (richness, settings.max_herbivores * quality)
+            })
+            .unzip();
+        let cells = planet.cell_count();
+        Ecosystem {
+            years_per_day: planet.calendar().day_length_hours() / 24.0 / 365.25,
+            settings,
+            biomes,
+            richness,
+            capacity,
+            herbivores: vec![0.8; cells],
+            predators: vec![0.2; cells],
+            elapsed: 0.0,
+        }
+    }
+
+    /// Builds the ecosystem from the planet's own vegetation and rivers with default settings.
+    pub fn from_planet(planet: &Planet) -> Ecosystem {
+        Ecosystem::new(planet, &VegetationLayer::from_planet(planet), &Drainage::compute(planet), EcologySettings::default())
+    }
+
+    /// Returns the settings.
+    pub fn settings(&self) -> &EcologySettings {
+        &self.settings
+    }
+
+    /// Returns the biome of every cell.
+    pub fn biomes(&self) -> &[Material] {
+        &self.biomes
+    }
+
+    /// Returns the number of vertebrate species living in every cell.
+    pub fn richness(&self) -> &[f64] {
+        &self.richness
+    }
+
+    /// Returns the herbivores per km² every cell can carry without predators.
+    pub fn carrying_capacity(&self) -> &[f64] {
+        &self.capacity
+    }
+
+    /// Returns the density of a kind of animal in every cell, in animals per km².
+    pub fn density(&self, kind: FaunaKind) -> Vec<f64> {
+        (0..self.capacity.len()).map(|cell| self.density_at(cell, kind)).collect()
+    }
+
+    /// Returns the days stepped so far.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    // Animals per km² of a kind in a cell
+    fn density_at(&self, cell: usize, kind: FaunaKind) -> f64 {
+        match kind {
+            FaunaKind::Herbivore => self.herbivores[cell] * self.capacity[cell],
+            FaunaKind::Predator => self.predators[cell] * self.balanced_predators(cell),
+        }
+    }
+
+    // Predators per km² in a cell when the populations are balanced
+    fn balanced_predators(&self, cell: usize) -> f64 {
+        let herbivores = self.balanced_herbivores();
+        self.capacity[cell] * herbivores * self.settings.predator_ratio
+    }
+
+    // Herbivores in units of the carrying capacity when the populations are balanced
+    fn balanced_herbivores(&self) -> f64 {
+        (self.settings.predator_death / self.settings.predator_growth.max(f64::MIN_POSITIVE)).min(1.0)
+    }
+
+    /// Advances the populations.
+    ///
+    /// Cells evolve independently, in steps short enough to follow the cycles whatever the span.
+    ///
+    /// # Arguments
+    ///
+    /// * `days` - Days to advance.
+    pub fn step(&mut self, days: f64) {
+        let years = days.max(0.0) * self.years_per_day;
+        let steps = (years / MAX_STEP_YEARS).ceil().max(1.0) as usize;
+        let dt = years / steps as f64;
+        let s = &self.settings;
+        // Predators at their balanced density eat exactly the growth of herbivores at the balance
+        let catch = s.herbivore_growth * (1.0 - self.balanced_herbivores());
+        self.herbivores
+            .par_iter_mut()
+            .zip(self.predators.par_iter_mut())
+            .zip(self.capacity.par_iter())
+            .for_each(|((h, p), &capacity)| {
+                if capacity <= 0.0 {
+                    return;
+                }
+                for _ in 0..steps {
+                    let dh = s.herbivore_growth * *h * (1.0 - *h) - catch * *h * *p;
+                    let dp = s.predator_growth * *h * *p - s.predator_death * *p;
+                    *h = (*h + dh * dt).max(0.0);
+                    *p = (*p + dp * dt).max(0.0);
+                }
+            });
+        self.elapsed += days.max(0.0);
+    }
+
+    /// Removes animals from a cell, e.g. when players hunt or fish it out.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - The cell hunted.
+    /// * `kind` - The kind of animal taken.
+    /// * `density` - Animals per km² to take.
+    ///
+    /// # Returns
+    ///
+    /// The animals per km² actually taken, no more than were there.
+    pub fn harvest(&mut self, cell: usize, kind: FaunaKind, density: f64) -> f64 {
+        let available = self.density_at(cell, kind);
+        let taken = density.clamp(0.0, available);
+        if available > 0.0 {
+            let population = match kind {
+                FaunaKind::Herbivore => &mut self.herbivores[cell],
+                FaunaKind::Predator => &mut self.predators[cell],
+            };
+            *population *= 1.0 - taken / available;
+        }
+        taken
+    }
+
+    /// Returns the spawning table of a cell: its biome, how many species it holds and the current
+    /// density of each kind of animal, weighted by how common it is.
+    pub fn spawn_table(&self, cell: usize) -> SpawnTable {
+        let densities = [FaunaKind::Herbivore, FaunaKind::Predator].map(|kind| (kind, self.density_at(cell, kind)));
+        let total: f64 = densities.iter().map(|&(_, density)| density).sum();
+        SpawnTable {
+            biome: self.biomes[cell],
+            species: self.richness[cell].round() as usize,
+            entries: densities
+                .into_iter()
+                .map(|(kind, density)| SpawnEntry {
+                    kind,
+                    density,
+                    weight: if total > 0.0 { density / total } else { 0.0 },
+                })
+                .collect(),
+        }
+    }
+}
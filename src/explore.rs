@@ -0,0 +1,133 @@
+use crate::hydrology::{cell_areas, Drainage};
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::procedural::{generate_batch, PlanetParameters};
+use crate::projection::Projection;
+use crate::recipe::Recipe;
+use crate::render::map::{render_map, MapBase, MapLayers, MapSettings};
+use rayon::prelude::*;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fmt::Write as _, fs, io};
+
+/// Describes the previews of a seed exploration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploreSpec {
+    pub recipe: Recipe,          // Overrides applied to every seed; its seed, cells and exports are ignored
+    pub cells: usize,            // Surface cells of each preview planet
+    pub projection: Projection,
+    pub width: usize,            // Map width in pixels; the height follows the projection's aspect ratio
+    pub base: MapBase,
+    pub river_flow: f64,         // Discharge in m³/s that makes a river, as for `Drainage::rivers`
+    pub mountain_elevation: f64, // Metres above sea level that count as mountains
+}
+
+impl Default for ExploreSpec {
+    /// 3000-cell planets derived from their seeds alone, previewed as 256 × 128 equirectangular
+    /// biome maps, with rivers of 1000 m³/s or more and mountains from 2000 m.
+    fn default() -> Self {
+        ExploreSpec {
+            recipe: Recipe::default(),
+            cells: 3000,
+            projection: Projection::Equirectangular,
+            width: 256,
+            base: MapBase::Biomes,
+            river_flow: 1000.0,
+            mountain_elevation: 2000.0,
+        }
+    }
+}
+
+/// A low-resolution preview of the planet of one seed, from `explore_seeds`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedPreview {
+    pub seed: u64,
+    pub parameters: PlanetParameters, // The preview's parameters; raise `samples` to build the full planet
+    pub map: Image,                   // Flat map with rivers drawn over it
+    pub land_fraction: f64,           // Share of the surface above sea level, 0.0 to 1.0
+    pub mountain_fraction: f64,       // Share of the surface at or above the mountain elevation, 0.0 to 1.0
+    pub river_count: usize,
+    pub highest_peak: f64,            // Metres above sea level
+}
+
+/// Generates a small preview planet for every seed in a range, so designers can pick good worlds
+/// quickly before building them in full.
+///
+/// Planets are built in parallel with `generate_batch` and rendered and measured in parallel.
+/// Every preview keeps the shape of its full-resolution planet, since sites and terrain come from
+/// seeds derived from the planet GUID, though small features blur at the preview resolution.
+///
+/// # Arguments
+///
+/// * `seeds` - The seeds to explore, as for `Recipe::seed`.
+/// * `spec` - The overrides, resolution and map style of the previews.
+///
+/// # Returns
+///
+/// One preview per seed, in seed order.
+pub fn explore_seeds(seeds: Range<u64>, spec: &ExploreSpec) -> Vec<SeedPreview> {
+    let seeds: Vec<u64> = seeds.collect();
+    let parameters: Vec<PlanetParameters> = seeds
+        .iter()
+        .map(|&seed| {
+            let recipe = Recipe { seed, cells: Some(spec.cells), ..spec.recipe.clone() };
+            recipe.parameters()
+        })
+        .collect();
+    let planets = generate_batch(&parameters);
+    seeds
+        .into_par_iter()
+        .zip(parameters)
+        .zip(planets)
+        .map(|((seed, parameters), planet)| preview(seed, parameters, &planet, spec))
+        .collect()
+}
+
+// Renders and measures one preview planet
+fn preview(seed: u64, parameters: PlanetParameters, planet: &Planet, spec: &ExploreSpec) -> SeedPreview {
+    let rivers = Drainage::compute(planet).rivers(spec.river_flow);
+    let settings = MapSettings {
+        projection: spec.projection,
+        width: spec.width,
+        height: ((spec.width as f64 / spec.projection.aspect_ratio()).round() as usize).max(1),
+        base: spec.base,
+        line_width: 1.0,
+        ..MapSettings::default()
+    };
+    let map = render_map(planet, &MapLayers { rivers: &rivers, ..MapLayers::default() }, &settings);
+
+    let areas = cell_areas(planet);
+    let total: f64 = areas.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+    let share = |keep: &dyn Fn(f64) -> bool| planet.elevation().iter().zip(&areas).filter(|&(&e, _)| keep(e)).map(|(_, area)| area).sum::<f64>() / total;
+    SeedPreview {
+        seed,
+        parameters,
+        map,
+        land_fraction: share(&|e| e >= 0.0),
+        mountain_fraction: share(&|e| e >= spec.mountain_elevation),
+        river_count: rivers.len(),
+        highest_peak: planet.elevation().iter().copied().fold(0.0, f64::max),
+    }
+}
+
+/// Writes each preview map as `seed_<seed>.png` and a `summary.csv` of their statistics to a
+/// directory, created if missing, for browsing the results outside Rust.
+///
+/// # Returns
+///
+/// `Ok(())` on success, or an `io::Error` if a file could not be written.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_previews(previews: &[SeedPreview], directory: &str) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+    let mut summary = String::from("seed,file,land_fraction,mountain_fraction,river_count,highest_peak\n");
+    for preview in previews {
+        let file = format!("seed_{}.png", preview.seed);
+        preview.map.write_png(&format!("{}/{}", directory, file))?;
+        let _ = writeln!(
+            summary,
+            "{},{},{:.4},{:.4},{},{:.0}",
+            preview.seed, file, preview.land_fraction, preview.mountain_fraction, preview.river_count, preview.highest_peak
+        );
+    }
+    fs::write(format!("{}/summary.csv", directory), summary)
+}
@@ -0,0 +1,233 @@
+use crate::planet::Planet;
+use crate::tectonics::{distance_from, Tectonics};
+use crate::vector::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+/// The kind of a discrete surface feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SurfaceFeatureKind {
+    /// A steep cone above a subduction zone.
+    Stratovolcano,
+    /// A broad, gently sloping volcano above a mantle hotspot.
+    ShieldVolcano,
+    /// An impact crater: a bowl with a raised rim.
+    Crater,
+}
+
+/// A volcano or crater placed on the surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfaceFeature {
+    pub kind: SurfaceFeatureKind,
+    pub cell: usize,
+    pub latitude: f64,  // Degrees
+    pub longitude: f64, // Degrees
+    pub radius_km: f64, // Radius of the cone base or crater rim
+    pub height: f64,    // Summit height above the surroundings, or crater depth as a negative value, in metres
+    pub age: f64,       // Million years since the feature formed
+}
+
+impl SurfaceFeature {
+    /// Returns the fraction of the original relief left after erosion.
+    ///
+    /// # Arguments
+    ///
+    /// * `half_life` - Million years for erosion to remove half of the relief.
+    pub fn erosion_factor(&self, half_life: f64) -> f64 {
+        if half_life <= 0.0 {
+            1.0
+        } else {
+            0.5f64.powf(self.age / half_life)
+        }
+    }
+
+    /// Returns the elevation change this feature causes at a distance from its centre, before
+    /// erosion.
+    pub fn profile(&self, distance_km: f64) -> f64 {
+        let r = (distance_km / self.radius_km.max(f64::EPSILON)).max(0.0);
+        match self.kind {
+            SurfaceFeatureKind::Stratovolcano => self.height * (1.0 - r).max(0.0).powi(2),
+            SurfaceFeatureKind::ShieldVolcano => self.height * (1.0 - r * r).max(0.0).powf(1.5),
+            SurfaceFeatureKind::Crater => {
+                let depth = -self.height;
+                let rim = 0.36 * depth;
+                if r <= 1.0 {
+                    rim - (depth + rim) * (1.0 - r * r)
+                } else {
+                    rim / r.powi(3) * (1.0 - (r - 1.0)).max(0.0)
+                }
+            }
+        }
+    }
+}
+
+/// Settings for impact crater placement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CraterSettings {
+    pub count: usize,
+    pub min_radius_km: f64,
+    pub max_radius_km: f64,
+    pub max_age: f64,           // Oldest crater age in million years
+    pub erosion_half_life: f64, // Million years for erosion to halve a feature's relief
+}
+
+impl Default for CraterSettings {
+    /// 40 craters of 2 to 150 km radius up to 2 billion years old, eroding with a 100 Myr half-life.
+    fn default() -> Self {
+        CraterSettings {
+            count: 40,
+            min_radius_km: 2.0,
+            max_radius_km: 150.0,
+            max_age: 2000.0,
+            erosion_half_life: 100.0,
+        }
+    }
+}
+
+// Picks a random cell whose direction is uniformly distributed on the sphere
+fn random_cell(planet: &Planet, rng: &mut StdRng) -> usize {
+    let z: f64 = rng.gen_range(-1.0..1.0);
+    let theta: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+    let r = (1.0 - z * z).sqrt();
+    let point: Vec3 = (r * theta.cos(), r * theta.sin(), z);
+    planet.nearest_cell(point, 0)
+}
+
+fn feature_at(planet: &Planet, kind: SurfaceFeatureKind, cell: usize, radius_km: f64, height: f64, age: f64) -> SurfaceFeature {
+    let (latitude, longitude) = planet.lat_lon(cell);
+    SurfaceFeature {
+        kind,
+        cell,
+        latitude,
+        longitude,
+        radius_km,
+        height,
+        age,
+    }
+}
+
+/// Places volcanoes along convergent plate boundaries and above hotspots.
+///
+/// Hotspots are tried first; further candidate cells are drawn in proportion to
+/// `Tectonics::volcanic_activity`. Cells within a
+/// hotspot's province get shield volcanoes, the rest stratovolcanoes. Volcanoes keep at least
+/// one cone diameter apart.
+///
+/// # Arguments
+///
+/// * `planet` - The planet.
+/// * `tectonics` - The planet's plate layout.
+/// * `seed` - The placement seed.
+/// * `count` - The maximum number of volcanoes; fewer are placed on quiet planets.
+pub fn place_volcanoes(planet: &Planet, tectonics: &Tectonics, seed: u64, count: usize) -> Vec<SurfaceFeature> {
+    const HOTSPOT_PROVINCE_KM: f64 = 300.0;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let activity = tectonics.volcanic_activity();
+    let hotspot_distance = distance_from(planet, tectonics.hotspots());
+
+    let mut weighted: Vec<(f64, usize)> = activity
+        .iter()
+        .enumerate()
+        .filter(|(_, &a)| a > 0.3)
+        .map(|(cell, &a)| (rng.gen::<f64>().powf(1.0 / a), cell)) // Weighted sampling keys
+        .collect();
+    weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    // Every hotspot gets the first chance at a volcano
+    let hotspots = tectonics.hotspots().iter().map(|&cell| (1.0, cell));
+    let candidates: Vec<(f64, usize)> = hotspots.chain(weighted).collect();
+
+    let mut volcanoes: Vec<SurfaceFeature> = Vec::new();
+    for (_, cell) in candidates {
+        if volcanoes.len() >= count {
+            break;
+        }
+        let (kind, radius_km, height) = if hotspot_distance[cell] < HOTSPOT_PROVINCE_KM {
+            (SurfaceFeatureKind::ShieldVolcano, rng.gen_range(40.0f64..120.0), rng.gen_range(2000.0..5000.0))
+        } else {
+            (SurfaceFeatureKind::Stratovolcano, rng.gen_range(8.0f64..30.0), rng.gen_range(1500.0..3500.0))
+        };
+        if volcanoes.iter().any(|v| planet.distance_km(v.cell, cell) < 2.0 * radius_km.max(v.radius_km)) {
+            continue;
+        }
+        let age: f64 = rng.gen_range(0.0..5.0);
+        volcanoes.push(feature_at(planet, kind, cell, radius_km, height, age));
+    }
+    volcanoes
+}
+
+/// Scatters impact craters uniformly over the surface.
+///
+/// Crater radii follow a power law, so small craters are far more common than large ones.
+/// Depths follow simple (bowl) crater proportions for small craters and flatter complex-crater
+/// proportions for large ones. A planet without cells gets no craters.
+pub fn place_craters(planet: &Planet, seed: u64, settings: &CraterSettings) -> Vec<SurfaceFeature> {
+    if planet.cell_count() == 0 {
+        return Vec::new();
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (min, max) = (settings.min_radius_km.max(f64::EPSILON), settings.max_radius_km.max(settings.min_radius_km));
+    (0..settings.count)
+        .map(|_| {
+            let cell = random_cell(planet, &mut rng);
+            // Inverse-transform sampling of a cumulative size distribution N(>r) ~ r^-2
+            let u: f64 = rng.gen();
+            let radius_km = 1.0 / (1.0 / (min * min) - u * (1.0 / (min * min) - 1.0 / (max * max))).sqrt();
+            let diameter = 2.0 * radius_km;
+            let depth = if diameter < 4.0 { 200.0 * diameter } else { 1000.0 * diameter.powf(0.3) };
+            let age = rng.gen_range(0.0..settings.max_age.max(f64::EPSILON));
+            feature_at(planet, SurfaceFeatureKind::Crater, cell, radius_km, -depth, age)
+        })
+        .collect()
+}
+
+impl Planet {
+    /// Blends surface features into the elevation field.
+    ///
+    /// Each feature's profile is scaled by its erosion factor and added to every cell within
+    /// twice its radius; the feature's own cell always receives its centre value, so features
+    /// smaller than a cell still leave a mark.
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - The features to apply.
+    /// * `erosion_half_life` - Million years for erosion to halve a feature's relief; `0.0`
+    ///   disables erosion.
+    pub fn apply_surface_features(&mut self, features: &[SurfaceFeature], erosion_half_life: f64) {
+        let mut elevation = self.elevation().to_vec();
+        let mut visited = vec![usize::MAX; self.cell_count()];
+        for (index, feature) in features.iter().enumerate() {
+            let erosion = feature.erosion_factor(erosion_half_life);
+            let reach = 2.0 * feature.radius_km;
+            visited[feature.cell] = index;
+            let mut queue = VecDeque::from([feature.cell]);
+            while let Some(cell) = queue.pop_front() {
+                let distance = self.distance_km(feature.cell, cell);
+                elevation[cell] += feature.profile(distance) * erosion;
+                for &neighbor in self.neighbors(cell) {
+                    if visited[neighbor] != index && self.distance_km(feature.cell, neighbor) < reach {
+                        visited[neighbor] = index;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        self.set_elevation(elevation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn craters_land_on_cells_of_any_planet() {
+        let settings = CraterSettings { count: 40, ..CraterSettings::default() };
+        assert!(place_craters(&Planet::new(Vec::new()), 1, &settings).is_empty());
+
+        let planet = Planet::new(vec![(1.0, 0.0, 0.0), (-1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, -1.0, 0.0), (0.0, 0.0, 1.0), (0.0, 0.0, -1.0)]);
+        let craters = place_craters(&planet, 1, &settings);
+        assert_eq!(craters.len(), 40);
+        assert!(craters.iter().all(|crater| crater.cell < planet.cell_count() && crater.height < 0.0));
+    }
+}
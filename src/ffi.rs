@@ -0,0 +1,254 @@
+use crate::guid::guid_from_seed;
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+use crate::vegetation::VegetationLayer;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Result code of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TfStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// An argument was out of range, e.g. an output buffer too small.
+    InvalidArgument = 2,
+    /// Generation panicked; the output handle was not written.
+    Panic = 3,
+}
+
+/// Overrides for planet generation. Fields left at their "derive" value are derived from the seed.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TfPlanetParams {
+    pub samples: u32,       // Surface cells; 0 derives it
+    pub radius_km: f64,     // Kilometres; 0 or less derives it
+    pub land_fraction: f64, // 0.0 to 1.0; negative derives it
+    pub jitter: f64,        // Site jitter, 0.0 to 1.0; negative derives it
+}
+
+/// A generated planet owned by the caller, released with `tf_free`.
+pub struct TfPlanet {
+    planet: Planet,
+    biomes: Vec<u8>, // Surface material id of each cell, see `render::materials::Material`
+}
+
+// Runs a fallible body, turning panics into `TfStatus::Panic` so they never cross the C boundary
+fn guarded(body: impl FnOnce() -> TfStatus) -> TfStatus {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or(TfStatus::Panic)
+}
+
+/// Returns the library version as a NUL-terminated string with static lifetime.
+#[no_mangle]
+pub extern "C" fn tf_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Fills `params` with values that derive every field from the seed.
+///
+/// # Safety
+///
+/// `params` must be null or point to writable memory for a `TfPlanetParams`.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_params_default(params: *mut TfPlanetParams) -> TfStatus {
+    if params.is_null() {
+        return TfStatus::NullPointer;
+    }
+    params.write(TfPlanetParams {
+        samples: 0,
+        radius_km: 0.0,
+        land_fraction: -1.0,
+        jitter: -1.0,
+    });
+    TfStatus::Ok
+}
+
+/// Generates a planet.
+///
+/// The planet is derived from the seed as `Planet::from_guid(guid_from_seed(seed))`, with any
+/// overrides in `params` applied first, so the same seed and parameters give the same planet
+/// everywhere.
+///
+/// # Arguments
+///
+/// * `seed` - The planet seed.
+/// * `params` - Overrides, or null to derive everything from the seed.
+/// * `out_handle` - Receives the planet, to be released with `tf_free`.
+///
+/// # Safety
+///
+/// `params` must be null or point to a valid `TfPlanetParams`, and `out_handle` must be null or
+/// point to writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_generate(seed: u64, params: *const TfPlanetParams, out_handle: *mut *mut TfPlanet) -> TfStatus {
+    if out_handle.is_null() {
+        return TfStatus::NullPointer;
+    }
+    let overrides = params.as_ref().copied();
+    guarded(|| {
+        let mut parameters = PlanetParameters::from_guid(guid_from_seed(seed));
+        if let Some(overrides) = overrides {
+            if overrides.samples > 0 {
+                parameters.samples = overrides.samples as usize;
+            }
+            if overrides.radius_km > 0.0 {
+                parameters.radius = overrides.radius_km;
+            }
+            if overrides.land_fraction >= 0.0 {
+                if overrides.land_fraction > 1.0 {
+                    return TfStatus::InvalidArgument;
+                }
+                parameters.terrain.land_fraction = overrides.land_fraction;
+            }
+            if overrides.jitter >= 0.0 {
+                parameters.jitter = overrides.jitter.min(1.0);
+            }
+        }
+        let planet = parameters.build();
+        let biomes = planet.materials(&VegetationLayer::from_planet(&planet)).into_iter().map(|material| material as u8).collect();
+        out_handle.write(Box::into_raw(Box::new(TfPlanet { planet, biomes })));
+        TfStatus::Ok
+    })
+}
+
+/// Releases a planet. Null is ignored.
+///
+/// # Safety
+///
+/// `planet` must be null or a handle from `tf_planet_generate` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn tf_free(planet: *mut TfPlanet) {
+    if !planet.is_null() {
+        drop(Box::from_raw(planet));
+    }
+}
+
+/// Returns the number of surface cells, or 0 for a null handle.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_cell_count(planet: *const TfPlanet) -> usize {
+    planet.as_ref().map_or(0, |planet| planet.planet.cell_count())
+}
+
+/// Returns the planet radius in kilometres, or 0 for a null handle.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_radius(planet: *const TfPlanet) -> f64 {
+    planet.as_ref().map_or(0.0, |planet| planet.planet.radius())
+}
+
+// Hands out a borrowed slice as a pointer and length
+unsafe fn borrow<T>(values: Option<&[T]>, out_len: *mut usize) -> *const T {
+    let values = values.unwrap_or(&[]);
+    if let Some(len) = out_len.as_mut() {
+        *len = values.len();
+    }
+    if values.is_empty() {
+        ptr::null()
+    } else {
+        values.as_ptr()
+    }
+}
+
+/// Copies the unit-sphere position of every cell into `out` as consecutive x, y, z triples.
+///
+/// # Arguments
+///
+/// * `out` - A buffer of at least `3 * tf_planet_cell_count(planet)` doubles.
+/// * `len` - The number of doubles in `out`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out` must be null or point to `len` writable
+/// doubles.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_copy_sites(planet: *const TfPlanet, out: *mut f64, len: usize) -> TfStatus {
+    let Some(planet) = planet.as_ref() else {
+        return TfStatus::NullPointer;
+    };
+    if out.is_null() {
+        return TfStatus::NullPointer;
+    }
+    let sites = planet.planet.sites();
+    if len < sites.len() * 3 {
+        return TfStatus::InvalidArgument;
+    }
+    let out = std::slice::from_raw_parts_mut(out, sites.len() * 3);
+    for (chunk, &(x, y, z)) in out.chunks_exact_mut(3).zip(sites) {
+        chunk.copy_from_slice(&[x, y, z]);
+    }
+    TfStatus::Ok
+}
+
+/// Returns the elevation of every cell in metres relative to sea level.
+///
+/// The array is owned by the planet and valid until `tf_free`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out_len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_heights(planet: *const TfPlanet, out_len: *mut usize) -> *const f64 {
+    borrow(planet.as_ref().map(|planet| planet.planet.elevation()), out_len)
+}
+
+/// Returns the surface temperature of every cell in degrees Celsius.
+///
+/// The array is owned by the planet and valid until `tf_free`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out_len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_temperatures(planet: *const TfPlanet, out_len: *mut usize) -> *const f64 {
+    borrow(planet.as_ref().map(|planet| planet.planet.temperature()), out_len)
+}
+
+/// Returns the biome of every cell as a surface material id, in the order of
+/// `render::materials::Material` (0 deep water through 9 sea ice).
+///
+/// The array is owned by the planet and valid until `tf_free`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out_len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_biomes(planet: *const TfPlanet, out_len: *mut usize) -> *const u8 {
+    borrow(planet.as_ref().map(|planet| planet.biomes.as_slice()), out_len)
+}
+
+/// Returns the mesh triangles as consecutive triples of cell indices; `out_len` receives the
+/// number of indices, three per triangle.
+///
+/// The array is owned by the planet and valid until `tf_free`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out_len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_triangles(planet: *const TfPlanet, out_len: *mut usize) -> *const usize {
+    let indices = planet.as_ref().map(|planet| planet.planet.triangles().as_flattened());
+    borrow(indices, out_len)
+}
+
+/// Returns the neighbours of a cell, or null with a length of 0 if the cell is out of range.
+///
+/// The array is owned by the planet and valid until `tf_free`.
+///
+/// # Safety
+///
+/// `planet` must be null or a live handle, and `out_len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tf_planet_neighbors(planet: *const TfPlanet, cell: usize, out_len: *mut usize) -> *const usize {
+    let neighbors = planet.as_ref().filter(|planet| cell < planet.planet.cell_count()).map(|planet| planet.planet.neighbors(cell));
+    borrow(neighbors, out_len)
+}
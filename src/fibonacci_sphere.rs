@@ -1,7 +1,10 @@
 use std::f64::consts::PI;
+use crate::noise::mix64;
+#[cfg(not(target_arch = "wasm32"))]
 use rand::Rng;
+#[cfg(not(target_arch = "wasm32"))]
 use std::io::{self, Write};
-use std::time::Instant;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
 
 /// Normalizes a 3D point to lie on the surface of a unit sphere.
@@ -38,8 +41,9 @@ fn normalize_to_sphere(point: (f64, f64, f64)) -> (f64, f64, f64) {
 ///
 /// # Returns
 ///
-/// A tuple `(x, y, z)` representing the Cartesian coordinates of the point on the sphere, 
-/// scaled by 1000 to match Unreal Engine's coordinate system.
+/// A tuple `(x, y, z)` representing the Cartesian coordinates of the point on the unit sphere,
+/// in TerraForge's Z-up frame; convert with `CoordinateFrame` for an engine.
+#[cfg(not(target_arch = "wasm32"))]
 fn fibonacci_point(index: usize, num_points: usize, jitter: f64) -> (f64, f64, f64) {
     let phi = PI * (3.0 - (5.0_f64).sqrt());
     let z = 1.0 - (index as f64 + 0.5) / (num_points as f64) * 2.0;
@@ -83,12 +87,11 @@ fn fibonacci_point(index: usize, num_points: usize, jitter: f64) -> (f64, f64, f
 ///
 /// # Note
 ///
-/// This function also writes the generated points and execution time to a file named "output.txt".
+/// This function also writes the generated points to a file named "raw_points.txt". For
+/// timings, build a planet with `PlanetParameters::build_with_stats`.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn generate_fibonacci_sphere(num_samples: usize, jitter: f64) -> io::Result<Vec<(f64, f64, f64)>> {
-    let start_time = Instant::now();
-
     let points: Vec<(f64, f64, f64)> = (0..num_samples)
-        .into_iter()
         .map(|i| {
             let (x, y, z) = fibonacci_point(i, num_samples, jitter);
             let (nx, ny, nz) = normalize_to_sphere((x, y, z));
@@ -96,14 +99,46 @@ pub fn generate_fibonacci_sphere(num_samples: usize, jitter: f64) -> io::Result<
         })
         .collect();
 
-    let duration = start_time.elapsed();
-
     let mut file = File::create("raw_points.txt")?;
     writeln!(file, "Generated points:")?;
-    writeln!(file, "Time elapsed: {:?}", duration)?;
     for point in &points {
         writeln!(file, "{:?}", point)?;
     }
 
     Ok(points)
-}
\ No newline at end of file
+}
+
+/// Generates a jittered Fibonacci sphere deterministically from a seed.
+///
+/// Unlike `generate_fibonacci_sphere`, the jitter of every point comes from hashing the seed and
+/// the point index, so the same arguments always produce the same points, and nothing is written
+/// to disk.
+///
+/// # Arguments
+///
+/// * `num_samples` - The number of points to generate on the sphere.
+/// * `jitter` - The amount of randomness to add to the point positions (0.0 to 1.0).
+/// * `seed` - The jitter seed.
+///
+/// # Returns
+///
+/// A vector of `(x, y, z)` tuples on the unit sphere.
+pub(crate) fn seeded_fibonacci_sphere(num_samples: usize, jitter: f64, seed: u64) -> Vec<(f64, f64, f64)> {
+    let phi = PI * (3.0 - (5.0_f64).sqrt());
+    let offset = |index: usize, axis: u64| {
+        let bits = mix64(seed ^ mix64(index as u64).wrapping_add(axis));
+        (bits >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    };
+    (0..num_samples)
+        .map(|i| {
+            let z = 1.0 - (i as f64 + 0.5) / (num_samples as f64) * 2.0;
+            let radius = (1.0 - z * z).sqrt();
+            let theta = phi * i as f64;
+            normalize_to_sphere((
+                radius * theta.cos() + jitter * offset(i, 1) * 0.1,
+                radius * theta.sin() + jitter * offset(i, 2) * 0.1,
+                z + jitter * offset(i, 3) * 0.1,
+            ))
+        })
+        .collect()
+}
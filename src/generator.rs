@@ -0,0 +1,157 @@
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+use crate::terrain_features::{BuildStage, TerrainPass, TerrainPipeline};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A flag shared between the code that starts a generation and the generation itself, to stop it
+/// early. Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Asks every generation holding a clone of this token to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once `cancel` has been called on this token or a clone.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned when a generation does not produce a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerationError {
+    /// The token was cancelled before the last stage started.
+    Cancelled,
+    /// The task on the blocking pool panicked or was shut down with its runtime, with tokio's
+    /// message.
+    Panicked(String),
+}
+
+impl std::fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::Cancelled => write!(f, "planet generation was cancelled"),
+            GenerationError::Panicked(message) => write!(f, "planet generation panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+/// Builds planets from `PlanetParameters`, synchronously or as a future for async servers.
+///
+/// Custom `TerrainPass`es added with `with_pass` run after the built-in stage they are attached
+/// to, in the order they were added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanetGenerator {
+    parameters: PlanetParameters,
+    pipeline: TerrainPipeline,
+}
+
+impl PlanetGenerator {
+    /// Creates a generator for the planet the parameters describe.
+    pub fn new(parameters: PlanetParameters) -> PlanetGenerator {
+        PlanetGenerator { parameters, pipeline: TerrainPipeline::new() }
+    }
+
+    /// Creates a generator for the planet derived from a GUID, as `Planet::from_guid` builds it.
+    pub fn from_guid(guid: Uuid) -> PlanetGenerator {
+        PlanetGenerator::new(PlanetParameters::from_guid(guid))
+    }
+
+    /// Returns the parameters of the planet.
+    pub fn parameters(&self) -> &PlanetParameters {
+        &self.parameters
+    }
+
+    /// Adds a terrain pass to run after a built-in stage, after the passes already there.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The stage the pass follows.
+    /// * `pass` - The pass, e.g. `terrain_features::Mesas` or a custom `TerrainPass`.
+    pub fn with_pass(mut self, after: BuildStage, pass: impl TerrainPass + 'static) -> PlanetGenerator {
+        self.pipeline.push(after, pass);
+        self
+    }
+
+    /// Returns the terrain passes.
+    pub fn pipeline(&self) -> &TerrainPipeline {
+        &self.pipeline
+    }
+
+    /// Builds the planet on the calling thread, identical to `PlanetParameters::build` when there
+    /// are no terrain passes.
+    pub fn generate(&self) -> Planet {
+        if self.pipeline.is_empty() {
+            self.parameters.build()
+        } else {
+            generate_stages(&self.parameters, &self.pipeline, &CancellationToken::new()).expect("a new token is never cancelled")
+        }
+    }
+
+    /// Builds the planet on the calling thread, checking the token before each stage.
+    ///
+    /// # Returns
+    ///
+    /// The planet, identical to the one `generate` returns, or `GenerationError::Cancelled` if the
+    /// token was cancelled first. A stage already running is finished before the check.
+    pub fn generate_cancellable(&self, cancel: &CancellationToken) -> Result<Planet, GenerationError> {
+        generate_stages(&self.parameters, &self.pipeline, cancel)
+    }
+
+    /// Returns a future that builds the planet on tokio's blocking pool, so an async runtime can
+    /// start generation without stalling its worker threads.
+    ///
+    /// The work starts when the future is first polled, which must happen inside a tokio runtime.
+    /// Dropping the future does not stop it; cancel the token to abandon it at the next stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - A token to stop the generation early, checked before each stage.
+    ///
+    /// # Returns
+    ///
+    /// A future of the planet, identical to the one `generate` returns, or the reason there is
+    /// none. It owns a copy of the parameters, so it can be spawned as a task.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn generate_async(&self, cancel: CancellationToken) -> impl std::future::Future<Output = Result<Planet, GenerationError>> + Send + 'static {
+        let parameters = self.parameters.clone();
+        let pipeline = self.pipeline.clone();
+        async move {
+            tokio::task::spawn_blocking(move || generate_stages(&parameters, &pipeline, &cancel))
+                .await
+                .unwrap_or_else(|error| Err(GenerationError::Panicked(error.to_string())))
+        }
+    }
+}
+
+// The stages of `PlanetParameters::build` with the terrain passes after theirs, stopping before any
+// stage once the token is cancelled
+fn generate_stages(parameters: &PlanetParameters, pipeline: &TerrainPipeline, cancel: &CancellationToken) -> Result<Planet, GenerationError> {
+    let check = || if cancel.is_cancelled() { Err(GenerationError::Cancelled) } else { Ok(()) };
+    check()?;
+    let sites = parameters.sites();
+    check()?;
+    let mut planet = parameters.triangulate(sites);
+    check()?;
+    planet.generate_terrain(parameters.terrain_seed, &parameters.terrain);
+    let mut planet = pipeline.run(planet, parameters, BuildStage::Terrain);
+    check()?;
+    planet.shape_surface(parameters.surface);
+    let mut planet = pipeline.run(planet, parameters, BuildStage::Surface);
+    planet.repeat_terrain(parameters.symmetry);
+    Ok(planet)
+}
@@ -0,0 +1,153 @@
+use crate::geom::spherical::{densify_lat_lon, GeodesicSubdivision};
+use crate::nations::{trace_boundaries, NationMap};
+use crate::planet::Planet;
+use crate::roads::RoadNetwork;
+use serde_json::{json, Map, Value};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+
+// GeoJSON positions are `[longitude, latitude]`; lines that cross the antimeridian are split
+// there so GIS tools don't draw them across the whole map
+fn line_geometry(points: &[(f64, f64)]) -> Option<Value> {
+    let mut parts: Vec<Vec<[f64; 2]>> = vec![Vec::new()];
+    for (i, &(latitude, longitude)) in points.iter().enumerate() {
+        if i > 0 {
+            let (previous_latitude, previous_longitude) = points[i - 1];
+            let step = longitude - previous_longitude;
+            if step.abs() > 180.0 {
+                // Interpolate the crossing latitude on the unwrapped longitude
+                let edge = if previous_longitude > 0.0 { 180.0 } else { -180.0 };
+                let unwrapped = longitude + 2.0 * edge;
+                let t = (edge - previous_longitude) / (unwrapped - previous_longitude);
+                let crossing = previous_latitude + t * (latitude - previous_latitude);
+                parts.last_mut().expect("at least one part").push([edge, crossing]);
+                parts.push(vec![[-edge, crossing]]);
+            }
+        }
+        parts.last_mut().expect("at least one part").push([longitude, latitude]);
+    }
+    parts.retain(|part| part.len() >= 2);
+    match parts.len() {
+        0 => None,
+        1 => Some(json!({ "type": "LineString", "coordinates": parts[0] })),
+        _ => Some(json!({ "type": "MultiLineString", "coordinates": parts })),
+    }
+}
+
+/// A GeoJSON `FeatureCollection` of a planet's map features, for GIS tools and web map libraries.
+///
+/// Every feature is a line in longitude/latitude degrees (lines crossing the antimeridian become
+/// `MultiLineString`s split there) with a `kind` property of `coastline`, `river`, `border` or
+/// `road`, plus properties specific to the kind.
+///
+/// GIS tools draw lines straight in longitude and latitude; `with_subdivision` adds points along
+/// each segment so long ones follow great circles as they do on the planet.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GeoJson {
+    features: Vec<Value>,
+    subdivision: GeodesicSubdivision, // Applied to lines added afterwards
+}
+
+impl GeoJson {
+    /// Creates an empty collection.
+    pub fn new() -> GeoJson {
+        GeoJson::default()
+    }
+
+    /// Subdivides the segments of lines added from now on along great circles.
+    pub fn with_subdivision(mut self, subdivision: GeodesicSubdivision) -> GeoJson {
+        self.subdivision = subdivision;
+        self
+    }
+
+    /// Returns the number of features added so far.
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Returns `true` if no features have been added.
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    fn push(&mut self, points: &[(f64, f64)], kind: &str, mut properties: Map<String, Value>) {
+        if let Some(geometry) = line_geometry(&densify_lat_lon(points, self.subdivision)) {
+            properties.insert("kind".to_string(), json!(kind));
+            self.features.push(json!({ "type": "Feature", "geometry": geometry, "properties": properties }));
+        }
+    }
+
+    /// Adds the coastlines: the cell edges between land and ocean, one feature per coast.
+    ///
+    /// Island and lake shores are closed lines. Properties: `closed`.
+    pub fn add_coastlines(&mut self, planet: &Planet) -> &mut GeoJson {
+        for ((), points) in trace_boundaries(planet, |a, b| (planet.is_ocean(a) != planet.is_ocean(b)).then_some(())) {
+            let closed = points.len() > 2 && points.first() == points.last();
+            let mut properties = Map::new();
+            properties.insert("closed".to_string(), json!(closed));
+            self.push(&points, "coastline", properties);
+        }
+        self
+    }
+
+    /// Adds rivers, each drawn through the sites of its cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the cells belong to.
+    /// * `rivers` - Each river as an ordered chain of cells from source to mouth.
+    ///
+    /// Properties: `river` (index into `rivers`) and `length_km`.
+    pub fn add_rivers(&mut self, planet: &Planet, rivers: &[Vec<usize>]) -> &mut GeoJson {
+        for (index, cells) in rivers.iter().enumerate() {
+            let points: Vec<(f64, f64)> = cells.iter().map(|&cell| planet.lat_lon(cell)).collect();
+            let length: f64 = cells.windows(2).map(|pair| planet.distance_km(pair[0], pair[1])).sum();
+            let mut properties = Map::new();
+            properties.insert("river".to_string(), json!(index));
+            properties.insert("length_km".to_string(), json!(length));
+            self.push(&points, "river", properties);
+        }
+        self
+    }
+
+    /// Adds the political borders from `NationMap::borders`.
+    ///
+    /// Properties: `nations`, the two nation indices on either side, with `null` for unclaimed
+    /// land or sea, so a nation's coast is a border with `null`.
+    pub fn add_borders(&mut self, planet: &Planet, nations: &NationMap) -> &mut GeoJson {
+        for border in nations.borders(planet) {
+            let mut properties = Map::new();
+            properties.insert("nations".to_string(), json!([border.nations.0, border.nations.1]));
+            self.push(&border.points, "border", properties);
+        }
+        self
+    }
+
+    /// Adds the road segments of a network.
+    ///
+    /// Properties: `from` and `to` (node indices), `length_km` and `traffic`.
+    pub fn add_roads(&mut self, planet: &Planet, roads: &RoadNetwork) -> &mut GeoJson {
+        for (segment, points) in roads.segments().iter().zip(roads.polylines(planet)) {
+            let mut properties = Map::new();
+            properties.insert("from".to_string(), json!(segment.from));
+            properties.insert("to".to_string(), json!(segment.to));
+            properties.insert("length_km".to_string(), json!(segment.length_km));
+            properties.insert("traffic".to_string(), json!(segment.traffic));
+            self.push(&points, "road", properties);
+        }
+        self
+    }
+
+    /// Returns the `FeatureCollection`.
+    pub fn to_json(&self) -> Value {
+        json!({ "type": "FeatureCollection", "features": self.features })
+    }
+
+    /// Writes the `FeatureCollection` to a `.geojson` file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_json().to_string())
+    }
+}
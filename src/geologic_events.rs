@@ -0,0 +1,267 @@
+use crate::features::{SurfaceFeature, SurfaceFeatureKind};
+use crate::noise::mix64;
+use crate::planet::Planet;
+use crate::tectonics::{BoundaryKind, Tectonics};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The kind of a geologic event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeologicEventKind {
+    /// A rupture on a plate boundary; the magnitude is the moment magnitude.
+    Earthquake,
+    /// A volcanic eruption; the magnitude is the Volcanic Explosivity Index.
+    Eruption,
+}
+
+/// One earthquake or eruption sampled by `GeologicActivity`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeologicEvent {
+    pub kind: GeologicEventKind,
+    pub time: f64,      // Days since generation
+    pub cell: usize,    // Epicenter or erupting volcano
+    pub magnitude: f64, // Moment magnitude for earthquakes, VEI for eruptions
+    pub radius_km: f64, // Reach of damaging shaking or of ash fall and pyroclastic flows
+}
+
+/// Settings for geologic event sampling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeologicEventSettings {
+    pub min_magnitude: f64, // Smallest earthquake reported
+    pub min_vei: f64,       // Smallest eruption reported
+    pub activity: f64,      // Multiplier on every rate; 1.0 is Earth-like
+}
+
+impl Default for GeologicEventSettings {
+    /// Earthquakes of magnitude 5 and up and eruptions of VEI 2 and up at Earth's rates.
+    fn default() -> Self {
+        GeologicEventSettings {
+            min_magnitude: 5.0,
+            min_vei: 2.0,
+            activity: 1.0,
+        }
+    }
+}
+
+// Earthquakes of magnitude 5 or more per year, per kilometre of boundary and cm/year of relative
+// plate speed: about 1500 a year on Earth's 250,000 km of boundaries moving some 4 cm/year
+const QUAKES_PER_KM_CM: f64 = 0.0025;
+// Gutenberg–Richter b-value: every magnitude step is ten times rarer
+const B_VALUE: f64 = 1.0;
+// Eruptions of VEI 2 or more per year of a volcano at full activity
+const ERUPTIONS_PER_YEAR: f64 = 0.05;
+// Stream discriminants keeping earthquakes and eruptions independent
+const EARTHQUAKE_STREAM: u64 = 0x51;
+const ERUPTION_STREAM: u64 = 0xe7;
+
+// Share of a boundary's relative motion released in large earthquakes, and the largest magnitude
+// its faults can host
+fn seismicity(kind: BoundaryKind) -> (f64, f64) {
+    match kind {
+        BoundaryKind::Convergent => (1.0, 9.5),
+        BoundaryKind::Transform => (0.6, 8.0),
+        BoundaryKind::Divergent => (0.2, 7.0),
+    }
+}
+
+// A place where events happen at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+struct Source {
+    cells: [usize; 2], // Epicenters to pick from with equal chance
+    max_magnitude: f64,
+}
+
+// The sources of one Poisson process and the cumulative rate up to each, per day
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Process {
+    sources: Vec<Source>,
+    cumulative: Vec<f64>,
+}
+
+impl Process {
+    fn push(&mut self, source: Source, rate: f64) {
+        if rate > 0.0 {
+            self.cumulative.push(self.rate() + rate);
+            self.sources.push(source);
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        self.cumulative.last().copied().unwrap_or(0.0)
+    }
+
+    // Samples the event times in `[day, day + 1)` and the source of each
+    fn sample_day(&self, rng: &mut StdRng, day: i64) -> Vec<(f64, &Source)> {
+        let rate = self.rate();
+        let mut events = Vec::new();
+        if rate <= 0.0 {
+            return events;
+        }
+        // Exponential gaps between events of a Poisson process
+        let mut time = -(1.0 - rng.gen::<f64>()).ln() / rate;
+        while time < 1.0 {
+            let pick = rng.gen::<f64>() * rate;
+            let index = self.cumulative.partition_point(|&c| c <= pick).min(self.sources.len() - 1);
+            events.push((day as f64 + time, &self.sources[index]));
+            time += -(1.0 - rng.gen::<f64>()).ln() / rate;
+        }
+        events
+    }
+}
+
+// Draws a magnitude at or above `min` from a Gutenberg–Richter distribution, capped at `max`
+fn gutenberg_richter(rng: &mut StdRng, min: f64, max: f64) -> f64 {
+    (min - (1.0 - rng.gen::<f64>()).ln() / (B_VALUE * std::f64::consts::LN_10)).min(max.max(min))
+}
+
+/// Earthquakes along active plate boundaries and eruptions at volcanoes over simulated time.
+///
+/// Both are Poisson processes. Every boundary edge quakes at a rate proportional to its relative
+/// plate speed, highest at convergent boundaries, with Gutenberg–Richter magnitudes capped by the
+/// boundary kind: up to 9.5 at subduction zones, 8 on transform faults and 7 at rifts. Every
+/// volcano erupts at a rate set by the volcanic activity of its cell; stratovolcanoes reach VEI 8
+/// while shield volcanoes stay effusive below VEI 4.
+///
+/// Events are sampled one day at a time from a stream seeded by the day, so the events of a day
+/// are the same however the time is stepped through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeologicActivity {
+    seed: u64,
+    settings: GeologicEventSettings,
+    earthquakes: Process,
+    eruptions: Process,
+}
+
+impl GeologicActivity {
+    /// Sets up event sampling for a planet.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet; its calendar converts yearly rates to its days.
+    /// * `tectonics` - The planet's plate layout, whose boundaries host earthquakes.
+    /// * `volcanoes` - The volcanoes that can erupt, e.g. from `features::place_volcanoes`. Other
+    ///   features are ignored.
+    /// * `seed` - The event seed.
+    /// * `settings` - Magnitude thresholds and overall activity.
+    pub fn new(planet: &Planet, tectonics: &Tectonics, volcanoes: &[SurfaceFeature], seed: u64, settings: GeologicEventSettings) -> GeologicActivity {
+        let years_per_day = planet.calendar().day_length_hours() / 24.0 / 365.25;
+        let scale = settings.activity.max(0.0) * years_per_day;
+
+        let graph = planet.cell_graph();
+        let mut earthquakes = Process::default();
+        let quake_fraction = 10f64.powf(-B_VALUE * (settings.min_magnitude - 5.0));
+        for boundary in tectonics.boundaries() {
+            let (share, max_magnitude) = seismicity(boundary.kind);
+            if max_magnitude < settings.min_magnitude {
+                continue;
+            }
+            let length = graph.edge(boundary.cell, boundary.neighbor).map_or(0.0, |edge| edge.length);
+            let rate = QUAKES_PER_KM_CM * length * boundary.rate * share * quake_fraction * scale;
+            earthquakes.push(Source { cells: [boundary.cell, boundary.neighbor], max_magnitude }, rate);
+        }
+
+        let mut eruptions = Process::default();
+        let activity = tectonics.volcanic_activity();
+        let eruption_fraction = 10f64.powf(-(settings.min_vei - 2.0));
+        for volcano in volcanoes {
+            let max_magnitude = match volcano.kind {
+                SurfaceFeatureKind::Stratovolcano => 8.0,
+                SurfaceFeatureKind::ShieldVolcano => 3.0,
+                SurfaceFeatureKind::Crater => continue,
+            };
+            if max_magnitude < settings.min_vei {
+                continue;
+            }
+            let rate = ERUPTIONS_PER_YEAR * activity[volcano.cell].max(0.1) * eruption_fraction * scale;
+            eruptions.push(Source { cells: [volcano.cell; 2], max_magnitude }, rate);
+        }
+
+        GeologicActivity {
+            seed,
+            settings,
+            earthquakes,
+            eruptions,
+        }
+    }
+
+    /// Returns the expected number of earthquakes per day.
+    pub fn earthquake_rate(&self) -> f64 {
+        self.earthquakes.rate()
+    }
+
+    /// Returns the expected number of eruptions per day.
+    pub fn eruption_rate(&self) -> f64 {
+        self.eruptions.rate()
+    }
+
+    /// Samples the events in a span of time.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Days since generation at the start of the span, inclusive.
+    /// * `end` - Days since generation at the end of the span, exclusive.
+    ///
+    /// # Returns
+    ///
+    /// The events in order of time.
+    pub fn events(&self, start: f64, end: f64) -> Vec<GeologicEvent> {
+        if end <= start {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+        for day in start.floor() as i64..end.ceil() as i64 {
+            let day_seed = self.seed ^ mix64(day as u64);
+            let mut rng = StdRng::seed_from_u64(mix64(day_seed ^ EARTHQUAKE_STREAM));
+            for (time, source) in self.earthquakes.sample_day(&mut rng, day) {
+                let cell = source.cells[rng.gen_range(0..2)];
+                let magnitude = gutenberg_richter(&mut rng, self.settings.min_magnitude, source.max_magnitude);
+                events.push(GeologicEvent {
+                    kind: GeologicEventKind::Earthquake,
+                    time,
+                    cell,
+                    magnitude,
+                    // Strong shaking reaches 10 km at magnitude 5 and tenfold further every two steps
+                    radius_km: 10f64.powf(0.5 * magnitude - 1.5),
+                });
+            }
+
+            let mut rng = StdRng::seed_from_u64(mix64(day_seed ^ ERUPTION_STREAM));
+            for (time, source) in self.eruptions.sample_day(&mut rng, day) {
+                let magnitude = gutenberg_richter(&mut rng, self.settings.min_vei, source.max_magnitude).floor();
+                events.push(GeologicEvent {
+                    kind: GeologicEventKind::Eruption,
+                    time,
+                    cell: source.cells[0],
+                    magnitude,
+                    // Ash and pyroclastic flows reach about 13 km at VEI 2 and 3000 km at VEI 8
+                    radius_km: 2.0 * 10f64.powf(0.4 * magnitude),
+                });
+            }
+        }
+        events.retain(|event| event.time >= start && event.time < end);
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        events
+    }
+
+}
+
+impl GeologicEvent {
+    /// Returns the cells within the event's radius, for applying damage or spawning effects.
+    pub fn affected_cells(&self, planet: &Planet) -> Vec<usize> {
+        let mut cells = vec![self.cell];
+        let mut visited = vec![false; planet.cell_count()];
+        visited[self.cell] = true;
+        let mut next = 0;
+        while next < cells.len() {
+            let cell = cells[next];
+            next += 1;
+            for &neighbor in planet.neighbors(cell) {
+                if !visited[neighbor] && planet.distance_km(self.cell, neighbor) <= self.radius_km {
+                    visited[neighbor] = true;
+                    cells.push(neighbor);
+                }
+            }
+        }
+        cells
+    }
+}
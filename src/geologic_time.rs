@@ -0,0 +1,329 @@
+use crate::geology::RockType;
+use crate::hydrology::{cell_areas, Drainage};
+use crate::ice::IceState;
+use crate::planet::Planet;
+use crate::tectonics::Tectonics;
+use crate::vector::{add, cross, dot, scale, Vec3};
+use rayon::prelude::*;
+use std::f64::consts::TAU;
+
+/// Settings for `Planet::advance_geology`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeologySettings {
+    pub time_step: f64,           // Million years per simulation step
+    pub erodibility: f64,         // Stream-power erosion in m/Myr per unit of √(m³/s) discharge and slope
+    pub glacial_erosion: f64,     // Erosion in m/Myr under a steep glacier gathering ice from 10,000 km² or more
+    pub hillslope_diffusion: f64, // Share of a land cell's difference from its neighbors smoothed away per Myr
+    pub volcanic_uplift: f64,     // Uplift in m/Myr of a cell at full volcanic activity
+    pub sea_level_amplitude: f64, // Metres the sea rises and falls around its starting level
+    pub sea_level_period: f64,    // Million years of one sea-level cycle
+}
+
+impl Default for GeologySettings {
+    /// One-million-year steps with Earth-like river and glacial erosion and uplift, and sea level
+    /// swinging 150 m over a 300-million-year cycle.
+    fn default() -> Self {
+        GeologySettings {
+            time_step: 1.0,
+            erodibility: 1000.0,
+            glacial_erosion: 100.0,
+            hillslope_diffusion: 0.002,
+            volcanic_uplift: 200.0,
+            sea_level_amplitude: 150.0,
+            sea_level_period: 300.0,
+        }
+    }
+}
+
+/// The state of a planet's geological history, advanced by `Planet::advance_geology`.
+///
+/// Holds the drifting plate layout, which changes as the plates move, and the time elapsed.
+#[derive(Debug, Clone)]
+pub struct GeologicHistory {
+    tectonics: Tectonics,
+    settings: GeologySettings,
+    elapsed: f64,      // Million years advanced so far
+    drift: Vec<f64>,   // Rotation in radians each plate has built up toward its next move
+    since_layout: f64, // Million years since the plate layout last changed
+}
+
+// Depth in metres of new crust at a spreading ridge
+const RIDGE_DEPTH: f64 = -2500.0;
+// Depth in metres old ocean floor cools and subsides toward
+const ABYSSAL_DEPTH: f64 = -5000.0;
+// Million years for ocean floor to close ~63% of the gap to the abyssal depth
+const SUBSIDENCE_MYR: f64 = 80.0;
+// Uplift in metres when a plate overrides another by one cell: more where continents collide
+const COLLISION_UPLIFT: f64 = 1500.0;
+const SUBDUCTION_UPLIFT: f64 = 300.0;
+// Sediment settles in the ocean until the floor is this high, in metres
+const SHELF_LEVEL: f64 = -50.0;
+// Elevation bounds in metres beyond which crust does not pile up or sink
+const ELEVATION_LIMITS: (f64, f64) = (-11_000.0, 9000.0);
+// Area in km² of ice and bed slope at which a glacier erodes at the full glacial erosion rate;
+// gentler and smaller ones flow slower and erode less
+const GLACIER_AREA: f64 = 10_000.0;
+const GLACIER_SLOPE: f64 = 0.02;
+// Depth in metres below sea level a glacier reaching the sea can scour a fjord to
+const FJORD_DEPTH: f64 = -1000.0;
+// Share of a glacier's downcutting its valley walls lose, widening the valley into a U
+const VALLEY_WALL_SHARE: f64 = 0.6;
+
+// Rotates a vector about a unit axis by an angle in radians
+fn rotate(v: Vec3, axis: Vec3, angle: f64) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    add(add(scale(v, cos), scale(cross(axis, v), sin)), scale(axis, dot(axis, v) * (1.0 - cos)))
+}
+
+impl GeologicHistory {
+    /// Starts a history from a planet's current plate layout.
+    pub fn new(tectonics: Tectonics, settings: GeologySettings) -> GeologicHistory {
+        GeologicHistory {
+            drift: vec![0.0; tectonics.plates().len()],
+            tectonics,
+            settings,
+            elapsed: 0.0,
+            since_layout: 0.0,
+        }
+    }
+
+    /// Returns the plate layout as it stands after the drift so far.
+    pub fn tectonics(&self) -> &Tectonics {
+        &self.tectonics
+    }
+
+    /// Returns the settings.
+    pub fn settings(&self) -> &GeologySettings {
+        &self.settings
+    }
+
+    /// Returns the million years advanced so far.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Returns the global sea level in metres relative to where it started.
+    pub fn sea_level(&self) -> f64 {
+        self.sea_level_at(self.elapsed)
+    }
+
+    // Sea level in metres relative to the start, at a time in million years
+    fn sea_level_at(&self, time: f64) -> f64 {
+        let period = self.settings.sea_level_period.max(f64::MIN_POSITIVE);
+        self.settings.sea_level_amplitude * (TAU * time / period).sin()
+    }
+}
+
+impl Planet {
+    /// Replays geological time: plate drift, volcanic uplift, erosion and sea-level change.
+    ///
+    /// Plates rotate about their Euler poles and carry their elevation and rock with them, moving
+    /// a whole cell at a time once they have built up a cell's worth of rotation. Where plates
+    /// pull apart new ocean floor forms at ridge depth and subsides as it ages; where they meet,
+    /// the higher side overrides the lower and rises, most where continents collide. Volcanic arcs
+    /// and hotspots build up, rivers cut down land in proportion to their slope and the square root
+    /// of their discharge and drop their sediment in the sea or in lakes, and hillslopes
+    /// soften. Glaciers on permanently frozen ground, wherever the climate keeps ice at high
+    /// latitudes and altitudes, grind their valleys wider and deeper into a U, and where they reach
+    /// the sea scour below sea level into fjords. Sea level rises and falls in a slow cycle,
+    /// drowning and exposing coastal plains.
+    ///
+    /// Call it repeatedly with short spans and export a map after each to animate a world's
+    /// history, or once with hundreds of million years to age a young planet.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - The plate layout and elapsed time, which move forward with the planet.
+    /// * `megayears` - Million years to advance, taken in steps of at most
+    ///   `GeologySettings::time_step`. Negative spans do nothing, and so do infinite and `NaN`
+    ///   ones, which are logged as warnings since they could never finish.
+    pub fn advance_geology(&mut self, history: &mut GeologicHistory, megayears: f64) {
+        if !megayears.is_finite() {
+            tracing::warn!("advance_geology: ignoring non-finite span of {} million years", megayears);
+            return;
+        }
+        let spacing = (2.0 * TAU / self.cell_count().max(1) as f64).sqrt();
+        let step = history.settings.time_step.max(f64::MIN_POSITIVE);
+        let mut remaining = megayears.max(0.0);
+        while remaining > 0.0 {
+            let dt = remaining.min(step);
+            remaining -= dt;
+
+            // Plates move once they have built up a cell's worth of rotation
+            let angles: Vec<f64> = history
+                .tectonics
+                .plates()
+                .iter()
+                .zip(history.drift.iter_mut())
+                .map(|(plate, drift)| {
+                    *drift += plate.angular_velocity.to_radians() * dt;
+                    if *drift >= spacing { std::mem::take(drift) } else { 0.0 }
+                })
+                .collect();
+            history.since_layout += dt;
+            if angles.iter().any(|&angle| angle > 0.0) {
+                let plate_of = self.drift_plates(&history.tectonics, &angles);
+                history.tectonics.drift_to(self, plate_of, history.since_layout);
+                history.since_layout = 0.0;
+            }
+
+            self.weather_surface(&history.tectonics, &history.settings, dt);
+
+            let sea_level_change = history.sea_level_at(history.elapsed + dt) - history.sea_level_at(history.elapsed);
+            history.elapsed += dt;
+            if sea_level_change != 0.0 {
+                self.set_elevation(self.elevation().iter().map(|&e| e - sea_level_change).collect());
+            }
+        }
+    }
+
+    // Moves every plate by its angle, carrying elevation and rock types, and returns the plate of
+    // every cell afterwards
+    fn drift_plates(&mut self, tectonics: &Tectonics, angles: &[f64]) -> Vec<usize> {
+        let plates = tectonics.plates();
+        let old_plate = tectonics.plate_ids();
+        let elevation = self.elevation();
+        let rock_types = self.rock_types();
+        // Every plate claims the cells its own cells move into
+        let moved: Vec<(usize, f64, RockType)> = (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let site = self.sites()[cell];
+                let claims: Vec<(usize, usize)> = plates
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(plate, motion)| {
+                        let source = if angles[plate] > 0.0 { self.nearest_cell(rotate(site, motion.euler_pole, -angles[plate]), cell) } else { cell };
+                        (old_plate[source] == plate).then_some((plate, source))
+                    })
+                    .collect();
+                // The higher side overrides; every plate it rides over lifts it further
+                let Some(&(plate, source)) = claims.iter().max_by(|a, b| elevation[a.1].total_cmp(&elevation[b.1])) else {
+                    // Plates pulled apart: a ridge fills the gap with new ocean floor
+                    return (old_plate[cell], RIDGE_DEPTH, RockType::Basalt);
+                };
+                let uplift: f64 = claims
+                    .iter()
+                    .filter(|&&(other, _)| other != plate)
+                    .map(|&(_, lower)| if elevation[lower] >= 0.0 { COLLISION_UPLIFT } else { SUBDUCTION_UPLIFT })
+                    .sum();
+                let height = (elevation[source] + uplift).clamp(ELEVATION_LIMITS.0, ELEVATION_LIMITS.1);
+                (plate, height, rock_types[source])
+            })
+            .collect();
+        let plate_of = moved.iter().map(|&(plate, _, _)| plate).collect();
+        self.set_elevation(moved.iter().map(|&(_, height, _)| height).collect());
+        self.set_rock_types(moved.iter().map(|&(_, _, rock)| rock).collect());
+        plate_of
+    }
+
+    // One step of volcanic uplift, ocean floor subsidence, river erosion, sediment deposition and
+    // hillslope smoothing
+    fn weather_surface(&mut self, tectonics: &Tectonics, settings: &GeologySettings, dt: f64) {
+        let activity = tectonics.volcanic_activity();
+        let subsidence = 1.0 - (-dt / SUBSIDENCE_MYR).exp();
+        let mut elevation: Vec<f64> = self
+            .elevation()
+            .iter()
+            .zip(self.rock_types())
+            .zip(activity)
+            .map(|((&e, &rock), &a)| {
+                let sunk = if e < 0.0 && rock == RockType::Basalt { e + (ABYSSAL_DEPTH - e) * subsidence } else { e };
+                (sunk + settings.volcanic_uplift * a * dt).min(ELEVATION_LIMITS.1)
+            })
+            .collect();
+
+        // Rivers cut down toward the cell they drain into, but never below it or below the sea
+        let drainage = Drainage::route(self, &elevation, &self.annual_precipitation());
+        let areas = cell_areas(self);
+        self.carve_glaciers(&mut elevation, &drainage, &areas, settings, dt);
+        let mut sediment = vec![0.0; self.cell_count()]; // m³ arriving at each cell
+        let mut rock_types = self.rock_types().to_vec();
+        for &cell in drainage.order() {
+            if let Some(lake) = drainage.lake_of(cell) {
+                // Lakes trap what arrives, silting up toward their surface, and pass the rest on
+                let area = (areas[cell] * 1.0e6).max(f64::MIN_POSITIVE);
+                let fill = (sediment[cell] / area).min((drainage.lakes()[lake].surface - elevation[cell]).max(0.0));
+                elevation[cell] += fill;
+                if fill > 10.0 {
+                    rock_types[cell] = RockType::Alluvium;
+                }
+                if let Some(next) = drainage.downstream(cell) {
+                    sediment[next] += sediment[cell] - fill * area;
+                }
+                continue;
+            }
+            match drainage.downstream(cell) {
+                Some(next) => {
+                    let drop = elevation[cell] - elevation[next].max(0.0);
+                    let slope = drop / (self.distance_km(cell, next) * 1000.0).max(f64::MIN_POSITIVE);
+                    let cut = (settings.erodibility * drainage.flow()[cell].sqrt() * slope * dt).clamp(0.0, drop.max(0.0));
+                    elevation[cell] -= cut;
+                    sediment[next] += sediment[cell] + cut * areas[cell] * 1.0e6;
+                }
+                None => {
+                    // The sea keeps what arrives, up to the shelf; so does the lowest cell of a
+                    // planet without one, up to its rim
+                    let rim = if elevation[cell] < 0.0 {
+                        SHELF_LEVEL
+                    } else {
+                        self.neighbors(cell).iter().map(|&n| elevation[n]).fold(f64::INFINITY, f64::min)
+                    };
+                    let fill = (sediment[cell] / (areas[cell] * 1.0e6).max(f64::MIN_POSITIVE)).min((rim - elevation[cell]).max(0.0));
+                    elevation[cell] += fill;
+                    if fill > 10.0 {
+                        rock_types[cell] = if elevation[cell] < 0.0 { RockType::Sedimentary } else { RockType::Alluvium };
+                    }
+                }
+            }
+        }
+
+        // Hillslopes creep toward the mean of the land around them
+        let diffusion = (settings.hillslope_diffusion * dt).min(1.0);
+        let smoothed: Vec<f64> = (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let land: Vec<f64> = self.neighbors(cell).iter().map(|&n| elevation[n]).filter(|&e| e >= 0.0).collect();
+                if elevation[cell] < 0.0 || land.is_empty() {
+                    return elevation[cell];
+                }
+                let mean = land.iter().sum::<f64>() / land.len() as f64;
+                elevation[cell] + (mean - elevation[cell]) * diffusion
+            })
+            .collect();
+        self.set_elevation(smoothed);
+        self.set_rock_types(rock_types);
+    }
+
+    // One step of glacial erosion: ice from every permanently frozen land cell flows down the
+    // drainage, and each glacier cell sinks with the ice it gathers and the slope it flows down,
+    // dragging its valley walls down with it. Glaciers reaching the sea scour below sea level,
+    // leaving fjords when the ice retreats.
+    fn carve_glaciers(&self, elevation: &mut [f64], drainage: &Drainage, areas: &[f64], settings: &GeologySettings, dt: f64) {
+        let ice = self.ice_state();
+        let glaciated = |cell: usize, elevation: &[f64]| ice[cell] == IceState::PermanentIce && elevation[cell] >= 0.0;
+        let mut gathered = vec![0.0; self.cell_count()]; // km² of ice reaching each cell
+        for &cell in drainage.order() {
+            if !glaciated(cell, elevation) {
+                continue;
+            }
+            gathered[cell] += areas[cell];
+            let reaches_sea = self.neighbors(cell).iter().any(|&n| elevation[n] < 0.0);
+            let floor = if reaches_sea { FJORD_DEPTH } else { 0.0 };
+            let slope = drainage.downstream(cell).map_or(0.0, |next| {
+                (elevation[cell] - elevation[next]) / (self.distance_km(cell, next) * 1000.0).max(f64::MIN_POSITIVE)
+            });
+            let speed = (gathered[cell] / GLACIER_AREA).sqrt().min(1.0) * (slope / GLACIER_SLOPE).clamp(0.0, 1.0);
+            let cut = (settings.glacial_erosion * speed * dt).min((elevation[cell] - floor).max(0.0));
+            elevation[cell] -= cut;
+            for &wall in self.neighbors(cell) {
+                if elevation[wall] > elevation[cell] {
+                    elevation[wall] = (elevation[wall] - cut * VALLEY_WALL_SHARE).max(elevation[cell]);
+                }
+            }
+            if let Some(next) = drainage.downstream(cell) {
+                gathered[next] += gathered[cell];
+            }
+        }
+    }
+}
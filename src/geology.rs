@@ -0,0 +1,71 @@
+use crate::noise::fbm;
+use crate::vector::{scale, Vec3};
+
+/// The dominant bedrock of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum RockType {
+    /// Crystalline continental crust; thin, acidic soils.
+    #[default]
+    Granite = 0,
+    /// Volcanic and oceanic crust; weathers into fertile soils.
+    Basalt = 1,
+    /// Sandstones and shales laid down in basins.
+    Sedimentary = 2,
+    /// Carbonate rock prone to karst.
+    Limestone = 3,
+    /// Loose river and coastal deposits on low, flat ground.
+    Alluvium = 4,
+}
+
+impl RockType {
+    /// Returns how readily the rock weathers into fertile soil, from 0.0 to 1.0.
+    pub fn soil_quality(&self) -> f64 {
+        match self {
+            RockType::Granite => 0.5,
+            RockType::Basalt => 0.9,
+            RockType::Sedimentary => 0.8,
+            RockType::Limestone => 0.65,
+            RockType::Alluvium => 1.0,
+        }
+    }
+}
+
+/// Assigns a rock type to every cell from its elevation and seeded noise.
+///
+/// Ocean floor is basaltic; low coastal plains are alluvial; lowland basins alternate between
+/// sedimentary rock and limestone; uplands are granitic with scattered basalt provinces.
+///
+/// # Arguments
+///
+/// * `sites` - Unit-sphere cell sites.
+/// * `elevation` - Elevation per cell in metres.
+/// * `seed` - The geology seed.
+///
+/// # Returns
+///
+/// One `RockType` per cell.
+pub fn classify_rock_types(sites: &[Vec3], elevation: &[f64], seed: u64) -> Vec<RockType> {
+    sites
+        .iter()
+        .zip(elevation)
+        .map(|(&p, &height)| {
+            let province = fbm(seed ^ 0x0C0C_0C0C, scale(p, 3.0), 4, 2.0, 0.5);
+            if height < 0.0 {
+                RockType::Basalt
+            } else if height < 150.0 {
+                RockType::Alluvium
+            } else if height < 1200.0 {
+                if province > 0.15 {
+                    RockType::Limestone
+                } else {
+                    RockType::Sedimentary
+                }
+            } else if province < -0.25 {
+                RockType::Basalt
+            } else {
+                RockType::Granite
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,231 @@
+use crate::vector::{add, cross, dot, length, normalize, scale, Vec3};
+
+/// Returns the unit vector of a latitude and longitude in degrees, in TerraForge's Z-up frame.
+pub fn from_lat_lon(latitude: f64, longitude: f64) -> Vec3 {
+    let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+    (lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+}
+
+/// Returns the latitude and longitude in degrees of a point; only its direction is used.
+pub fn to_lat_lon(point: Vec3) -> (f64, f64) {
+    let (x, y, z) = normalize(point);
+    (z.clamp(-1.0, 1.0).asin().to_degrees(), y.atan2(x).to_degrees())
+}
+
+/// Returns the great-circle distance between two unit vectors in radians; multiply by the
+/// radius for a surface distance.
+///
+/// Computed from both the sine and the cosine of the angle, so it stays accurate for nearby and
+/// for nearly antipodal points.
+pub fn distance(a: Vec3, b: Vec3) -> f64 {
+    length(cross(a, b)).atan2(dot(a, b))
+}
+
+/// Returns the initial bearing of the great circle from one unit vector to another, in degrees
+/// clockwise from north, 0.0 to 360.0.
+///
+/// At a pole every direction is south or north, so bearings there are measured as if standing on
+/// the meridian of longitude 0. The bearing to the same or the antipodal point is 0.0.
+pub fn bearing(from: Vec3, to: Vec3) -> f64 {
+    let (latitude, longitude) = to_lat_lon(from);
+    let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+    let east = (-lon.sin(), lon.cos(), 0.0);
+    let north = (-lat.sin() * lon.cos(), -lat.sin() * lon.sin(), lat.cos());
+    // The great circle leaves `from` along the component of `to` across it
+    let heading = cross(cross(from, to), from);
+    if length(heading) < 1e-15 {
+        return 0.0;
+    }
+    dot(heading, east).atan2(dot(heading, north)).to_degrees().rem_euclid(360.0)
+}
+
+/// Returns the signed area of a spherical triangle on the unit sphere in steradians: positive
+/// when the corners run counter-clockwise seen from outside.
+///
+/// Uses the spherical excess in the form of Van Oosterom and Strackee, which needs no angles and
+/// stays accurate for tiny triangles.
+pub fn triangle_area(a: Vec3, b: Vec3, c: Vec3) -> f64 {
+    2.0 * dot(a, cross(b, c)).atan2(1.0 + dot(a, b) + dot(b, c) + dot(c, a))
+}
+
+/// Returns the area of a spherical polygon on the unit sphere in steradians, the sum of its
+/// spherical excess; multiply by the radius squared for a surface area.
+///
+/// # Arguments
+///
+/// * `polygon` - The corners as unit vectors, in either winding, without repeating the first. The
+///   polygon must be simple and smaller than a hemisphere; it need not be convex.
+///
+/// # Returns
+///
+/// The area, 0.0 for fewer than three corners.
+pub fn polygon_area(polygon: &[Vec3]) -> f64 {
+    if polygon.len() < 3 {
+        return 0.0;
+    }
+    let first = polygon[0];
+    polygon.windows(2).skip(1).map(|pair| triangle_area(first, pair[0], pair[1])).sum::<f64>().abs()
+}
+
+/// Returns the centroid of a spherical polygon's surface, projected back onto the unit sphere.
+///
+/// Exact for any simple polygon smaller than a hemisphere: the surface integral of position is
+/// half the sum over the edges of each edge's arc length times its great circle's pole.
+///
+/// # Arguments
+///
+/// * `polygon` - The corners as unit vectors, in either winding, without repeating the first.
+///
+/// # Returns
+///
+/// A unit vector, the normalized mean of the corners for degenerate polygons.
+pub fn polygon_centroid(polygon: &[Vec3]) -> Vec3 {
+    let corner_mean = normalize(polygon.iter().fold((0.0, 0.0, 0.0), |sum, &p| add(sum, p)));
+    if polygon.len() < 3 {
+        return corner_mean;
+    }
+    let moment = (0..polygon.len()).fold((0.0, 0.0, 0.0), |sum, i| {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        add(sum, scale(normalize(cross(a, b)), distance(a, b)))
+    });
+    if length(moment) < 1e-15 {
+        return corner_mean;
+    }
+    // Clockwise polygons sum to the opposite pole
+    let centroid = normalize(moment);
+    if dot(centroid, corner_mean) < 0.0 {
+        scale(centroid, -1.0)
+    } else {
+        centroid
+    }
+}
+
+/// Returns `true` if a point lies inside a spherical polygon.
+///
+/// Sums the angles the edges turn through seen from the point, so edges are true great-circle
+/// arcs rather than straight lines in some projection. Points on the boundary may go either way.
+///
+/// # Arguments
+///
+/// * `polygon` - The corners as unit vectors, in either winding, without repeating the first. The
+///   polygon must be simple and smaller than a hemisphere.
+/// * `point` - The point to test; only its direction is used.
+pub fn polygon_contains(polygon: &[Vec3], point: Vec3) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    let p = normalize(point);
+    let winding: f64 = (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            // Angle between the planes through p and each corner, around the axis through p
+            dot(p, cross(a, b)).atan2(dot(a, b) - dot(a, p) * dot(b, p))
+        })
+        .sum();
+    // A winding of a full turn means the boundary separates p from its antipode; the polygon is
+    // on p's side when its centroid is
+    winding.abs() > std::f64::consts::PI && dot(p, polygon_centroid(polygon)) > 0.0
+}
+
+/// How finely great-circle arcs are split into straight segments for export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeodesicSubdivision {
+    /// The same number of segments for every arc; 1 draws arcs as straight chords.
+    Segments(usize),
+    /// As few segments as keep every chord within this distance of the arc, as a fraction of the
+    /// radius, e.g. 1e-4 for about 640 m on an Earth-sized planet. Long arcs get more segments
+    /// than short ones.
+    MaxChordError(f64),
+}
+
+/// Straight chords, one segment per arc, as exports have always drawn them.
+impl Default for GeodesicSubdivision {
+    fn default() -> Self {
+        GeodesicSubdivision::Segments(1)
+    }
+}
+
+// Most segments a tolerance may split one arc into
+const MAX_ARC_SEGMENTS: usize = 1024;
+
+impl GeodesicSubdivision {
+    /// Returns the number of segments for the arc between two unit vectors, at least 1.
+    pub fn segments(&self, a: Vec3, b: Vec3) -> usize {
+        match *self {
+            GeodesicSubdivision::Segments(count) => count.max(1),
+            GeodesicSubdivision::MaxChordError(error) => {
+                // A chord across angle θ sags 1 - cos(θ / 2) below the arc
+                let error = error.clamp(1e-12, 1.0);
+                let widest = 2.0 * (1.0 - error).acos();
+                ((distance(a, b) / widest).ceil() as usize).clamp(1, MAX_ARC_SEGMENTS)
+            }
+        }
+    }
+}
+
+/// Returns the point a fraction of the way along the great-circle arc between two unit vectors,
+/// at constant speed (spherical linear interpolation).
+///
+/// # Arguments
+///
+/// * `a` - The start, returned for `t` = 0.0.
+/// * `b` - The end, returned for `t` = 1.0.
+/// * `t` - The fraction of the arc; values outside 0.0 to 1.0 extrapolate along the great circle.
+///
+/// # Returns
+///
+/// A unit vector; the arc between antipodal points is ambiguous, so one is picked.
+pub fn interpolate(a: Vec3, b: Vec3, t: f64) -> Vec3 {
+    let angle = distance(a, b);
+    if angle < 1e-12 {
+        return normalize(add(scale(a, 1.0 - t), scale(b, t)));
+    }
+    let axis = cross(a, b);
+    let axis = if length(axis) > 1e-12 {
+        normalize(axis)
+    } else {
+        // Antipodes: any great circle through both will do
+        let helper = if a.2.abs() < 0.9 { (0.0, 0.0, 1.0) } else { (1.0, 0.0, 0.0) };
+        normalize(cross(a, helper))
+    };
+    // Rotate a about the arc's pole
+    let along = cross(axis, a);
+    normalize(add(scale(a, (angle * t).cos()), scale(along, (angle * t).sin())))
+}
+
+/// Splits the great-circle arc between two unit vectors into points.
+///
+/// # Returns
+///
+/// The points from `a` to `b` inclusive, `segments + 1` of them.
+pub fn subdivide_arc(a: Vec3, b: Vec3, subdivision: GeodesicSubdivision) -> Vec<Vec3> {
+    let segments = subdivision.segments(a, b);
+    let mut points: Vec<Vec3> = (0..segments).map(|i| interpolate(a, b, i as f64 / segments as f64)).collect();
+    points.push(b);
+    points
+}
+
+/// Subdivides every arc of a polyline of unit vectors so it follows the sphere.
+pub fn densify(points: &[Vec3], subdivision: GeodesicSubdivision) -> Vec<Vec3> {
+    let mut dense = Vec::with_capacity(points.len());
+    for (i, &point) in points.iter().enumerate() {
+        if i > 0 {
+            let previous = points[i - 1];
+            let segments = subdivision.segments(previous, point);
+            dense.extend((1..segments).map(|k| interpolate(previous, point, k as f64 / segments as f64)));
+        }
+        dense.push(point);
+    }
+    dense
+}
+
+/// Subdivides every arc of a polyline of `(latitude, longitude)` points in degrees, so lines
+/// drawn straight between them in longitude and latitude, as GIS tools and flat maps do, follow
+/// great circles instead.
+pub fn densify_lat_lon(points: &[(f64, f64)], subdivision: GeodesicSubdivision) -> Vec<(f64, f64)> {
+    if subdivision == GeodesicSubdivision::Segments(1) {
+        return points.to_vec();
+    }
+    let units: Vec<Vec3> = points.iter().map(|&(latitude, longitude)| from_lat_lon(latitude, longitude)).collect();
+    densify(&units, subdivision).into_iter().map(to_lat_lon).collect()
+}
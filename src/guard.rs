@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// What to do when a computed field holds a non-finite or out-of-range value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardPolicy {
+    /// Panic with the field name, index and value. The default in debug builds.
+    Panic,
+    /// Replace `NaN` with the field's fallback, clamp everything else into range and log a
+    /// warning. The default in release builds.
+    ClampAndLog,
+    /// Leave values untouched.
+    Ignore,
+}
+
+impl Default for GuardPolicy {
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            GuardPolicy::Panic
+        } else {
+            GuardPolicy::ClampAndLog
+        }
+    }
+}
+
+// 0 means "not set", i.e. use the build's default
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the policy used by every field guard in the process.
+pub fn set_guard_policy(policy: GuardPolicy) {
+    let code = match policy {
+        GuardPolicy::Panic => 1,
+        GuardPolicy::ClampAndLog => 2,
+        GuardPolicy::Ignore => 3,
+    };
+    POLICY.store(code, Ordering::Relaxed);
+}
+
+/// Returns the policy currently used by field guards.
+pub fn guard_policy() -> GuardPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        1 => GuardPolicy::Panic,
+        2 => GuardPolicy::ClampAndLog,
+        3 => GuardPolicy::Ignore,
+        _ => GuardPolicy::default(),
+    }
+}
+
+/// Validates a computed field under the current policy, see `guard_values_with`.
+pub fn guard_values(field: &str, values: &mut [f64], range: (f64, f64), fallback: f64) -> usize {
+    guard_values_with(guard_policy(), field, values, range, fallback)
+}
+
+/// Validates every value of a computed field.
+///
+/// # Arguments
+///
+/// * `policy` - What to do with bad values.
+/// * `field` - The field name, used in panics and log messages.
+/// * `values` - The values to check, repaired in place under `ClampAndLog`.
+/// * `range` - The inclusive range of plausible values.
+/// * `fallback` - The replacement for `NaN`.
+///
+/// # Returns
+///
+/// The number of bad values found.
+///
+/// # Panics
+///
+/// Panics on the first bad value under `GuardPolicy::Panic`.
+pub fn guard_values_with(policy: GuardPolicy, field: &str, values: &mut [f64], range: (f64, f64), fallback: f64) -> usize {
+    if policy == GuardPolicy::Ignore {
+        return 0;
+    }
+    let mut bad = 0;
+    let mut first = None;
+    for (index, value) in values.iter_mut().enumerate() {
+        if value.is_finite() && *value >= range.0 && *value <= range.1 {
+            continue;
+        }
+        if policy == GuardPolicy::Panic {
+            panic!("{}[{}] = {} is outside [{}, {}]", field, index, value, range.0, range.1);
+        }
+        bad += 1;
+        first.get_or_insert((index, *value));
+        *value = if value.is_nan() { fallback } else { value.clamp(range.0, range.1) };
+    }
+    if let Some((index, value)) = first {
+        tracing::warn!("{}: repaired {} bad values (first {}[{}] = {})", field, bad, field, index, value);
+    }
+    bad
+}
+
+/// Validates a single computed value under the current policy.
+///
+/// # Returns
+///
+/// The value, repaired under `ClampAndLog`.
+pub fn guard_value(field: &str, value: f64, range: (f64, f64), fallback: f64) -> f64 {
+    let mut values = [value];
+    guard_values(field, &mut values, range, fallback);
+    values[0]
+}
@@ -0,0 +1,65 @@
+use crate::noise::mix64;
+use uuid::Uuid;
+
+/// Discriminant of a universe GUID derived from a numeric seed, whose parent is the nil GUID.
+pub const UNIVERSE_DISCRIMINANT: u64 = 0;
+/// Discriminant of a star system within a galaxy; the index is the system index.
+pub const SYSTEM_DISCRIMINANT: u64 = 1;
+/// Discriminant of a planet within a star system; the index is its order from the star.
+pub const PLANET_DISCRIMINANT: u64 = 2;
+/// Discriminant of a moon within a planet; the index is its order from the planet.
+pub const MOON_DISCRIMINANT: u64 = 3;
+/// Discriminant of a planet's ring system; the index is always 0.
+pub const RING_DISCRIMINANT: u64 = 4;
+/// Discriminant of a small-body population within a star system; the index is its order.
+pub const POPULATION_DISCRIMINANT: u64 = 5;
+/// Discriminant of a body within a small-body population; the index is the body id.
+pub const BODY_DISCRIMINANT: u64 = 6;
+/// Discriminant of the per-subsystem seeds of a planet surface; the index picks the subsystem.
+pub const SEED_DISCRIMINANT: u64 = 16;
+/// Discriminant of a sector within a universe; the index packs the sector coordinates.
+pub const SECTOR_DISCRIMINANT: u64 = 32;
+/// Discriminant of a galaxy within a sector; the index is its order in the sector.
+pub const GALAXY_DISCRIMINANT: u64 = 33;
+
+/// Derives a child GUID from its parent GUID, the kind of child and the child's index.
+///
+/// This is the single derivation scheme of the whole hierarchy: universe → sector → galaxy →
+/// star system → planet → moon, and every other generated object, so any object can be
+/// regenerated from its parent without generating its siblings. The discriminants in this module
+/// keep different kinds of children apart; use a new one for a new kind of child.
+///
+/// The parent's 128 bits are read big-endian, and two 64-bit lanes are each hashed with SplitMix64
+/// from all inputs. Only integer arithmetic is used, so the result is the same on every platform
+/// and Rust version. The lanes fill a version 8 (custom) UUID, leaving 122 bits of hash.
+///
+/// # Arguments
+///
+/// * `parent` - The parent GUID.
+/// * `discriminant` - The kind of child, one of the `*_DISCRIMINANT` constants.
+/// * `index` - The child's index among children of that kind.
+pub fn derive_guid(parent: Uuid, discriminant: u64, index: u64) -> Uuid {
+    let (high, low) = parent.as_u64_pair();
+    let first = mix64(mix64(mix64(high ^ mix64(low ^ 0x243F_6A88_85A3_08D3)) ^ discriminant) ^ index);
+    let second = mix64(mix64(mix64(low ^ mix64(high ^ 0x1319_8A2E_0370_7344)) ^ discriminant.rotate_left(32)) ^ index.rotate_left(32));
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&first.to_be_bytes());
+    bytes[8..].copy_from_slice(&second.to_be_bytes());
+    // Version 8 and the RFC 9562 variant
+    bytes[6] = (bytes[6] & 0x0F) | 0x80;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+    Uuid::from_bytes(bytes)
+}
+
+/// Derives a universe GUID from a numeric seed.
+///
+/// Equivalent to `derive_guid(Uuid::nil(), UNIVERSE_DISCRIMINANT, seed)`.
+pub fn guid_from_seed(seed: u64) -> Uuid {
+    derive_guid(Uuid::nil(), UNIVERSE_DISCRIMINANT, seed)
+}
+
+/// Folds a GUID into a 64-bit seed for a random number generator.
+pub fn guid_seed(guid: Uuid) -> u64 {
+    let (high, low) = guid.as_u64_pair();
+    mix64(high ^ mix64(low))
+}
@@ -0,0 +1,141 @@
+use crate::climate::SOLAR_CONSTANT;
+use crate::planet::Planet;
+use rayon::prelude::*;
+
+/// One of the properties `Planet::habitability` scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HabitabilityFactor {
+    /// Sunlight at the planet's orbit compared with Earth's.
+    StellarFlux,
+    /// Surface pressure, oxygen and carbon dioxide.
+    Atmosphere,
+    /// How much of the surface has a mean annual temperature where liquid water is comfortable.
+    Temperature,
+    /// The balance of ocean and land.
+    Water,
+    /// Surface gravity compared with Earth's.
+    Gravity,
+}
+
+impl HabitabilityFactor {
+    /// Every factor, in the order `HabitabilityReport::factors` lists them.
+    pub const ALL: [HabitabilityFactor; 5] = [
+        HabitabilityFactor::StellarFlux,
+        HabitabilityFactor::Atmosphere,
+        HabitabilityFactor::Temperature,
+        HabitabilityFactor::Water,
+        HabitabilityFactor::Gravity,
+    ];
+
+    /// Returns the factor's name, e.g. `"stellar_flux"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HabitabilityFactor::StellarFlux => "stellar_flux",
+            HabitabilityFactor::Atmosphere => "atmosphere",
+            HabitabilityFactor::Temperature => "temperature",
+            HabitabilityFactor::Water => "water",
+            HabitabilityFactor::Gravity => "gravity",
+        }
+    }
+
+    // Share of the overall score; the weights sum to 1.0
+    fn weight(&self) -> f64 {
+        match self {
+            HabitabilityFactor::StellarFlux => 0.2,
+            HabitabilityFactor::Atmosphere => 0.25,
+            HabitabilityFactor::Temperature => 0.25,
+            HabitabilityFactor::Water => 0.15,
+            HabitabilityFactor::Gravity => 0.15,
+        }
+    }
+}
+
+/// How well a planet suits Earth-like life, overall and factor by factor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HabitabilityReport {
+    pub score: f64,                    // Overall score, 0.0 (hostile) to 1.0 (ideal)
+    pub stellar_flux: f64,             // Score of each factor, 0.0 to 1.0
+    pub atmosphere: f64,
+    pub temperature: f64,
+    pub water: f64,
+    pub gravity: f64,
+    pub mean_temperature: f64,         // Area-weighted mean annual temperature in °C
+    pub temperature_range: (f64, f64), // Coldest and warmest mean annual cell temperature in °C
+    pub ocean_fraction: f64,           // Fraction of the surface below sea level
+}
+
+// Mean annual temperatures in °C over which the surface counts as comfortable
+const COMFORTABLE_TEMPERATURE: (f64, f64) = (0.0, 35.0);
+
+// Earth Similarity Index term: 1.0 when the value equals the reference, falling toward 0.0 as the
+// ratio between them grows in either direction
+fn similarity(value: f64, reference: f64) -> f64 {
+    if value <= 0.0 {
+        return 0.0;
+    }
+    1.0 - ((value - reference) / (value + reference)).abs()
+}
+
+impl HabitabilityReport {
+    /// Returns every factor with its score, in the order of `HabitabilityFactor::ALL`.
+    pub fn factors(&self) -> [(HabitabilityFactor, f64); 5] {
+        let scores = [self.stellar_flux, self.atmosphere, self.temperature, self.water, self.gravity];
+        std::array::from_fn(|i| (HabitabilityFactor::ALL[i], scores[i]))
+    }
+
+    /// Returns the factor with the lowest score, the one holding the planet back most.
+    pub fn limiting_factor(&self) -> HabitabilityFactor {
+        self.factors().into_iter().min_by(|a, b| a.1.total_cmp(&b.1)).map_or(HabitabilityFactor::StellarFlux, |(factor, _)| factor)
+    }
+}
+
+impl Planet {
+    /// Scores how well the planet suits Earth-like life, for ranking generated worlds.
+    ///
+    /// Every factor scores 0.0 to 1.0 and the overall score is their weighted geometric mean, so
+    /// one hostile factor, such as no air, sinks the planet however good the rest are. Stellar
+    /// flux and gravity are compared with Earth's as in the Earth Similarity Index; the atmosphere
+    /// scores pressure near 1 bar, oxygen and little carbon dioxide; temperature scores the share
+    /// of the surface with a mean annual temperature of 0 to 35 °C; water scores a mix of ocean
+    /// and land, lowest for dry and for ocean-covered worlds. Earth itself scores about 0.9.
+    pub fn habitability(&self) -> HabitabilityReport {
+        let stellar_flux = similarity(self.solar_flux() / SOLAR_CONSTANT, 1.0);
+        let gravity = similarity(self.surface_gravity() / 9.80665, 1.0);
+
+        let air = self.atmosphere();
+        let composition = air.composition();
+        let atmosphere = if air.surface_pressure() <= 0.0 {
+            0.0
+        } else {
+            let pressure = (-(air.surface_pressure() / 101_325.0).log10().powi(2) / 0.36).exp();
+            let oxygen = (composition.oxygen / 0.21).min(1.0);
+            let toxicity = (-(composition.carbon_dioxide / 0.05).powi(2)).exp();
+            pressure * (0.5 + 0.5 * oxygen) * toxicity
+        };
+
+        let graph = self.cell_graph();
+        let areas: Vec<f64> = (0..self.cell_count()).into_par_iter().map(|cell| graph.area(cell)).collect();
+        let total_area: f64 = areas.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+        let annual = self.mean_annual_temperature();
+        let (low, high) = COMFORTABLE_TEMPERATURE;
+        let comfortable: f64 = (0..self.cell_count()).filter(|&cell| (low..=high).contains(&annual[cell])).map(|cell| areas[cell]).sum();
+        let mean_temperature = annual.iter().zip(&areas).map(|(t, area)| t * area).sum::<f64>() / total_area;
+        let temperature_range = annual.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &t| (min.min(t), max.max(t)));
+        let ocean: f64 = (0..self.cell_count()).filter(|&cell| self.is_ocean(cell)).map(|cell| areas[cell]).sum();
+        let ocean_fraction = ocean / total_area;
+
+        let mut report = HabitabilityReport {
+            score: 0.0,
+            stellar_flux,
+            atmosphere,
+            temperature: comfortable / total_area,
+            water: (4.0 * ocean_fraction * (1.0 - ocean_fraction)).max(0.0).sqrt(),
+            gravity,
+            mean_temperature,
+            temperature_range,
+            ocean_fraction,
+        };
+        report.score = report.factors().iter().map(|&(factor, score)| score.clamp(0.0, 1.0).powf(factor.weight())).product();
+        report
+    }
+}
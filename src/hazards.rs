@@ -0,0 +1,286 @@
+use crate::geom::spherical;
+use crate::hydrology::{cell_areas, Drainage};
+use crate::noise::mix64;
+use crate::planet::Planet;
+use crate::vegetation::VegetationLayer;
+use crate::weather::WeatherMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A kind of natural hazard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HazardKind {
+    /// Fire spreading through dry vegetation.
+    Wildfire,
+    /// Rivers overtopping their banks after extreme rain.
+    Flood,
+    /// A long spell of rain well below normal.
+    Drought,
+}
+
+/// Settings for `Hazards`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HazardSettings {
+    pub memory_days: f64,     // Days of rainfall the soil remembers when judging how dry it is
+    pub ignition_rate: f64,   // Rate per day at which fires start in a fully flammable cell
+    pub spread_rate: f64,     // Rate per day at which fire spreads to a fully flammable neighbor in still air
+    pub wind_spread: f64,     // How much each m/s of wind speeds fire downwind and slows it upwind
+    pub extreme_rain: f64,    // Rain in mm/h above which a cell feeds flood runoff
+    pub flood_ratio: f64,     // Storm discharge over the mean discharge at which a river floods
+    pub drought_wetness: f64, // Rainfall relative to normal below which land is in drought
+    pub dieback: f64,         // Share of vegetation lost per day of the deepest drought
+    pub regrowth: f64,        // Share of lost vegetation regrown per day outside drought
+}
+
+impl Default for HazardSettings {
+    /// Soil that remembers a month of rain, rare lightning fires that race downwind through dry
+    /// brush, floods where a storm pushes rivers to three times their mean flow, and droughts below
+    /// half the normal rainfall.
+    fn default() -> Self {
+        HazardSettings {
+            memory_days: 30.0,
+            ignition_rate: 0.001,
+            spread_rate: 2.0,
+            wind_spread: 0.1,
+            extreme_rain: 10.0,
+            flood_ratio: 3.0,
+            drought_wetness: 0.5,
+            dieback: 0.01,
+            regrowth: 0.002,
+        }
+    }
+}
+
+/// The cells affected by hazards during one tick of `Hazards::step`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HazardTick {
+    pub time: f64,           // Hours since the simulation started, at the end of the tick
+    pub ignited: Vec<usize>, // Cells where a new fire started
+    pub burning: Vec<usize>, // Cells burning during the tick, new fires included
+    pub flooded: Vec<usize>,
+    pub drought: Vec<usize>,
+}
+
+impl HazardTick {
+    /// Returns the cells a kind of hazard affected during the tick.
+    pub fn cells(&self, kind: HazardKind) -> &[usize] {
+        match kind {
+            HazardKind::Wildfire => &self.burning,
+            HazardKind::Flood => &self.flooded,
+            HazardKind::Drought => &self.drought,
+        }
+    }
+}
+
+// Share of extreme rain that runs straight off into the rivers
+const STORM_RUNOFF: f64 = 0.6;
+// Rainfall relative to normal above which the soil is as wet as it gets
+const MAX_WETNESS: f64 = 3.0;
+// Share of a burned cell's vegetation left standing
+const BURNED_FUEL: f64 = 0.1;
+// Vegetation density below which a cell has nothing to burn
+const MIN_FUEL: f64 = 0.05;
+
+/// Wildfires, floods and droughts spreading over a planet as its weather unfolds.
+///
+/// Every land cell tracks its wetness, the recent rainfall relative to its normal rainfall. Where
+/// it stays low the cell is in drought and its vegetation dies back; elsewhere vegetation regrows
+/// toward the `VegetationLayer` it started from. Fires start at random in hot, dry, vegetated
+/// cells, or where `ignite` lights them, burn for one tick and spread to neighbors by chance,
+/// readily through dry fuel and downwind, leaving the burned cells bare to regrow. Extreme rain
+/// runs off down the `Drainage` network, and every cell whose storm discharge reaches
+/// `flood_ratio` times its mean discharge floods, so floods reach far downstream of the storm.
+///
+/// Ticks draw from a stream seeded by the tick number, so a run is repeatable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hazards {
+    seed: u64,
+    settings: HazardSettings,
+    drainage: Drainage,
+    flow_order: Vec<usize>, // Land cells, each before the cell it drains into
+    areas: Vec<f64>,        // km²
+    normal_rain: Vec<f64>,  // Mean rain in mm/h
+    potential: Vec<f64>,    // Vegetation density without hazards
+    vegetation: Vec<f64>,   // Current vegetation density
+    wetness: Vec<f64>,      // Recent rain relative to normal
+    burning: Vec<bool>,     // Cells that burn during the next tick
+    ocean: Vec<bool>,
+    ticks: u64,
+    elapsed: f64, // Hours
+}
+
+impl Hazards {
+    /// Sets up hazards on a planet with every cell at normal wetness and nothing burning.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet.
+    /// * `vegetation` - Vegetation before any hazards, e.g. from `VegetationLayer::from_planet`.
+    /// * `drainage` - The river network floods follow, e.g. from `Drainage::compute`.
+    /// * `seed` - The hazard seed.
+    /// * `settings` - Fire, flood and drought behavior.
+    pub fn new(planet: &Planet, vegetation: &VegetationLayer, drainage: Drainage, seed: u64, settings: HazardSettings) -> Hazards {
+        let ocean: Vec<bool> = (0..planet.cell_count()).map(|cell| planet.is_ocean(cell)).collect();
+        let flow_order: Vec<usize> = drainage.order().iter().copied().filter(|&cell| !ocean[cell]).collect();
+        Hazards {
+            seed,
+            settings,
+            drainage,
+            flow_order,
+            areas: cell_areas(planet),
+            normal_rain: planet.annual_precipitation().iter().map(|&p| p / (365.25 * 24.0)).collect(),
+            potential: vegetation.density().to_vec(),
+            vegetation: vegetation.density().to_vec(),
+            wetness: vec![1.0; planet.cell_count()],
+            burning: vec![false; planet.cell_count()],
+            ocean,
+            ticks: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the settings.
+    pub fn settings(&self) -> &HazardSettings {
+        &self.settings
+    }
+
+    /// Returns the current vegetation density of every cell, thinned by drought and fire.
+    pub fn vegetation(&self) -> &[f64] {
+        &self.vegetation
+    }
+
+    /// Returns the recent rainfall of every cell relative to its normal rainfall.
+    pub fn wetness(&self) -> &[f64] {
+        &self.wetness
+    }
+
+    /// Returns the hours stepped so far.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Sets a cell alight, e.g. for a campfire out of control. It burns during the next tick if
+    /// it is land with something to burn.
+    pub fn ignite(&mut self, cell: usize) {
+        self.burning[cell] = true;
+    }
+
+    // How readily a cell burns, 0.0 to 1.0, from its fuel, dryness and temperature
+    fn flammability(&self, cell: usize, temperature: f64) -> f64 {
+        if self.ocean[cell] || self.vegetation[cell] < MIN_FUEL {
+            return 0.0;
+        }
+        let dryness = (1.0 - self.wetness[cell]).clamp(0.0, 1.0);
+        let heat = ((temperature - 5.0) / 25.0).clamp(0.0, 1.0);
+        self.vegetation[cell] * dryness * heat
+    }
+
+    /// Advances the hazards by one tick of weather.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the hazards were set up for.
+    /// * `weather` - The weather during the tick, e.g. a `WeatherMap` after `step`.
+    /// * `hours` - The length of the tick in hours.
+    ///
+    /// # Returns
+    ///
+    /// The cells each hazard affected during the tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weather` does not have one condition per cell.
+    pub fn step(&mut self, planet: &Planet, weather: &WeatherMap, hours: f64) -> HazardTick {
+        assert_eq!(weather.len(), self.ocean.len(), "weather must have one condition per cell");
+        let hours = hours.max(0.0);
+        let days = hours / 24.0;
+        let cells = self.ocean.len();
+        let conditions = weather.conditions();
+        let mut rng = StdRng::seed_from_u64(mix64(self.seed ^ mix64(self.ticks)));
+        self.ticks += 1;
+        self.elapsed += hours;
+        let mut tick = HazardTick { time: self.elapsed, ..HazardTick::default() };
+
+        // The soil forgets old rain over the memory span
+        let memory = 1.0 - (-days / self.settings.memory_days.max(f64::MIN_POSITIVE)).exp();
+        for ((wetness, condition), &normal) in self.wetness.iter_mut().zip(conditions).zip(&self.normal_rain) {
+            let ratio = (condition.precipitation / normal.max(f64::MIN_POSITIVE)).min(MAX_WETNESS);
+            *wetness += (ratio - *wetness) * memory;
+        }
+
+        // Drought thins vegetation in proportion to how far rain falls short; elsewhere it regrows
+        let threshold = self.settings.drought_wetness.max(f64::MIN_POSITIVE);
+        for cell in 0..cells {
+            if self.ocean[cell] {
+                continue;
+            }
+            if self.wetness[cell] < threshold {
+                let severity = 1.0 - self.wetness[cell] / threshold;
+                self.vegetation[cell] *= 1.0 - (self.settings.dieback * severity * days).min(1.0);
+                tick.drought.push(cell);
+            } else {
+                let regrowth = (self.settings.regrowth * days).min(1.0);
+                self.vegetation[cell] += (self.potential[cell] - self.vegetation[cell]).max(0.0) * regrowth;
+            }
+        }
+
+        // Storm runoff gathers down the rivers and floods wherever it swamps the usual flow
+        let mut storm = vec![0.0; cells];
+        for &cell in &self.flow_order {
+            let rain = conditions[cell].precipitation;
+            if rain >= self.settings.extreme_rain {
+                // km² × 1e6 m²/km² × mm/h ÷ 1000 mm/m ÷ 3600 s/h
+                storm[cell] += self.areas[cell] * 1000.0 * rain * STORM_RUNOFF / 3600.0;
+            }
+            if storm[cell] > 0.0 && storm[cell] >= self.settings.flood_ratio * self.drainage.flow()[cell] {
+                tick.flooded.push(cell);
+                self.wetness[cell] = self.wetness[cell].max(1.0);
+            }
+            if let Some(next) = self.drainage.downstream(cell) {
+                storm[next] += storm[cell];
+            }
+        }
+        tick.flooded.sort_unstable();
+
+        // Lightning starts new fires in hot, dry brush
+        for (cell, condition) in conditions.iter().enumerate() {
+            if self.burning[cell] {
+                continue;
+            }
+            let chance = 1.0 - (-self.settings.ignition_rate * self.flammability(cell, condition.temperature) * days).exp();
+            if chance > 0.0 && rng.gen::<f64>() < chance {
+                self.burning[cell] = true;
+                tick.ignited.push(cell);
+            }
+        }
+
+        // Every burning cell burns out this tick and may set its neighbors alight for the next
+        let burning: Vec<usize> = (0..cells)
+            .filter(|&cell| self.burning[cell] && !self.ocean[cell] && self.vegetation[cell] >= MIN_FUEL)
+            .collect();
+        self.burning.iter_mut().for_each(|burning| *burning = false);
+        let sites = planet.sites();
+        for &cell in &burning {
+            let condition = &conditions[cell];
+            // Wind direction is where the wind blows from, so fire runs the opposite way
+            let downwind = condition.wind_direction + 180.0;
+            for &neighbor in planet.neighbors(cell) {
+                if self.burning[neighbor] || burning.binary_search(&neighbor).is_ok() {
+                    continue;
+                }
+                let alignment = (spherical::bearing(sites[cell], sites[neighbor]) - downwind).to_radians().cos();
+                let wind = (self.settings.wind_spread * condition.wind_speed * alignment).exp();
+                let fuel = self.flammability(neighbor, conditions[neighbor].temperature);
+                let chance = 1.0 - (-self.settings.spread_rate * fuel * wind * days).exp();
+                if chance > 0.0 && rng.gen::<f64>() < chance {
+                    self.burning[neighbor] = true;
+                }
+            }
+        }
+        for &cell in &burning {
+            self.vegetation[cell] *= BURNED_FUEL;
+        }
+        tick.burning = burning;
+        tick
+    }
+}
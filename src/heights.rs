@@ -0,0 +1,121 @@
+use crate::geom::spherical::from_lat_lon;
+use crate::noise::fbm;
+use crate::planet::Planet;
+use crate::vector::{cross, dot, normalize, scale, Vec3};
+
+/// Fine noise added to interpolated heights by `Planet::detailed_elevation_at`, for bumps smaller
+/// than a cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeightDetail {
+    pub seed: u64,
+    pub octaves: u32,    // Noise octaves; 0 adds nothing
+    pub wavelength: f64, // Wavelength of the first octave in kilometres
+    pub amplitude: f64,  // Largest height of the first octave in metres
+    pub gain: f64,       // Amplitude of each octave relative to the last
+}
+
+impl Default for HeightDetail {
+    /// Four octaves starting at 10 km waves up to 50 m high, each octave half the size of the
+    /// last.
+    fn default() -> Self {
+        HeightDetail {
+            seed: 0,
+            octaves: 4,
+            wavelength: 10.0,
+            amplitude: 50.0,
+            gain: 0.5,
+        }
+    }
+}
+
+impl Planet {
+    /// Returns the height of the ground at any point, interpolated between cell sites.
+    ///
+    /// Heights blend linearly across the Delaunay triangle under the point, so they are continuous
+    /// everywhere and match `elevation()` exactly at cell sites, unlike the flat steps of the
+    /// per-cell values. Suitable for placing objects on the ground and for collision.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Latitude in degrees.
+    /// * `longitude` - Longitude in degrees.
+    ///
+    /// # Returns
+    ///
+    /// The elevation in metres relative to sea level.
+    pub fn elevation_at(&self, latitude: f64, longitude: f64) -> f64 {
+        self.elevation_near(from_lat_lon(latitude, longitude), 0).0
+    }
+
+    /// Returns the interpolated height of `elevation_at` plus fine detail noise, for surfaces that
+    /// should not look faceted up close.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Latitude in degrees.
+    /// * `longitude` - Longitude in degrees.
+    /// * `detail` - The detail noise to add.
+    pub fn detailed_elevation_at(&self, latitude: f64, longitude: f64, detail: &HeightDetail) -> f64 {
+        let point = from_lat_lon(latitude, longitude);
+        self.elevation_near(point, 0).0 + self.height_detail(point, detail)
+    }
+
+    /// Returns the interpolated height at a point, starting the search from a nearby cell, for
+    /// many queries in a row such as sampling a terrain tile.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - Any non-zero point; only its direction is used.
+    /// * `hint` - A cell to start from, e.g. the cell returned by the previous query.
+    ///
+    /// # Returns
+    ///
+    /// The elevation in metres relative to sea level and the cell nearest the point, to use as
+    /// the next hint.
+    pub fn elevation_near(&self, point: Vec3, hint: usize) -> (f64, usize) {
+        if self.cell_count() == 0 {
+            return (0.0, 0);
+        }
+        let point = normalize(point);
+        let nearest = self.nearest_cell(point, hint);
+        let sites = self.sites();
+        let elevation = self.elevation();
+        // The triangle under the point almost always has the nearest site as a corner, and
+        // otherwise one of its neighbors
+        let corners = std::iter::once(nearest).chain(self.neighbors(nearest).iter().copied());
+        for cell in corners {
+            for &a in self.neighbors(cell) {
+                for &b in self.neighbors(cell) {
+                    if a >= b || !self.neighbors(a).contains(&b) {
+                        continue;
+                    }
+                    let weights = [
+                        dot(cross(sites[a], sites[b]), point),
+                        dot(cross(sites[b], sites[cell]), point),
+                        dot(cross(sites[cell], sites[a]), point),
+                    ];
+                    // The triangle may be wound either way
+                    let sign = if weights.iter().sum::<f64>() < 0.0 { -1.0 } else { 1.0 };
+                    let weights = weights.map(|w| w * sign);
+                    if weights.iter().all(|&w| w >= -1e-12) {
+                        let total: f64 = weights.iter().sum();
+                        let height = (weights[0] * elevation[cell] + weights[1] * elevation[a] + weights[2] * elevation[b]) / total;
+                        return (height, nearest);
+                    }
+                }
+            }
+        }
+        (elevation[nearest], nearest)
+    }
+
+    // The detail noise at a point on the unit sphere
+    fn height_detail(&self, point: Vec3, detail: &HeightDetail) -> f64 {
+        if detail.octaves == 0 {
+            return 0.0;
+        }
+        let frequency = self.radius() / detail.wavelength.max(f64::MIN_POSITIVE);
+        let norm: f64 = (0..detail.octaves).map(|octave| detail.gain.powi(octave as i32)).sum();
+        // `fbm` averages its octaves; scale back so the first octave has the full amplitude
+        fbm(detail.seed, scale(point, frequency), detail.octaves, 2.0, detail.gain) * norm * detail.amplitude
+    }
+}
@@ -0,0 +1,403 @@
+use crate::vector::{add, cross, dot, normalize, scale, sub, Vec3};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// A near-uniform hexagonal tiling of the unit sphere (a Goldberg polyhedron).
+///
+/// The tiling is the dual of a geodesic icosahedron: every vertex of the subdivided icosahedron
+/// becomes a cell. All cells are hexagons except for exactly twelve pentagons sitting on the
+/// original icosahedron corners.
+#[derive(Debug, Clone)]
+pub struct HexGrid {
+    resolution: usize,
+    centers: Vec<Vec3>,
+    neighbors: Vec<Vec<usize>>,
+    corners: Vec<Vec<Vec3>>,
+}
+
+/// The assignment of underlying Voronoi cells to the hexes of a `HexGrid`.
+#[derive(Debug, Clone)]
+pub struct HexCellMapping {
+    cell_to_hex: Vec<usize>,
+    hex_to_cells: Vec<Vec<usize>>,
+    // Nearest cell to each hex center, used for hexes that are finer than the Voronoi cells
+    // and therefore have no cell assigned to them. `None` only when there are no cells at all.
+    fallback_cell: Vec<Option<usize>>,
+}
+
+/// Returns the twelve vertices and twenty faces of a unit icosahedron.
+fn icosahedron() -> (Vec<Vec3>, Vec<[usize; 3]>) {
+    let phi = (1.0 + 5.0_f64.sqrt()) / 2.0;
+    let vertices = [
+        (-1.0, phi, 0.0), (1.0, phi, 0.0), (-1.0, -phi, 0.0), (1.0, -phi, 0.0),
+        (0.0, -1.0, phi), (0.0, 1.0, phi), (0.0, -1.0, -phi), (0.0, 1.0, -phi),
+        (phi, 0.0, -1.0), (phi, 0.0, 1.0), (-phi, 0.0, -1.0), (-phi, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&v| normalize(v))
+    .collect();
+
+    let faces = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    (vertices, faces)
+}
+
+/// Quantizes a unit vector so that vertices shared between icosahedron faces deduplicate.
+fn vertex_key(v: Vec3) -> (i64, i64, i64) {
+    let q = 1.0e9;
+    ((v.0 * q).round() as i64, (v.1 * q).round() as i64, (v.2 * q).round() as i64)
+}
+
+/// Sorts points around `center` counter-clockwise when viewed from outside the sphere.
+fn sort_around(center: Vec3, points: &mut [Vec3]) {
+    if points.is_empty() {
+        return;
+    }
+    let u = normalize(sub(points[0], scale(center, dot(points[0], center))));
+    let v = cross(center, u);
+    points.sort_by(|a, b| {
+        let angle_a = dot(*a, v).atan2(dot(*a, u));
+        let angle_b = dot(*b, v).atan2(dot(*b, u));
+        angle_a.total_cmp(&angle_b)
+    });
+}
+
+impl HexGrid {
+    /// Generates a Goldberg hex grid at the given resolution.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - The icosahedron subdivision frequency. A resolution of `n` yields
+    ///   `10 * n * n + 2` cells; values below 1 are treated as 1.
+    ///
+    /// # Returns
+    ///
+    /// A `HexGrid` whose cell centers lie on the unit sphere.
+    pub fn new(resolution: usize) -> HexGrid {
+        let n = resolution.max(1);
+        let (ico_vertices, ico_faces) = icosahedron();
+
+        let mut centers: Vec<Vec3> = Vec::with_capacity(10 * n * n + 2);
+        let mut lookup: HashMap<(i64, i64, i64), usize> = HashMap::new();
+        let mut triangles: Vec<[usize; 3]> = Vec::with_capacity(20 * n * n);
+
+        for face in &ico_faces {
+            let a = ico_vertices[face[0]];
+            let b = ico_vertices[face[1]];
+            let c = ico_vertices[face[2]];
+
+            // Index the subdivided vertices of this face by their (i, j) lattice coordinates
+            let mut local = vec![vec![0usize; n + 1]; n + 1];
+            for (i, row) in local.iter_mut().enumerate() {
+                for (j, slot) in row.iter_mut().take(n - i + 1).enumerate() {
+                    let p = add(
+                        a,
+                        add(
+                            scale(sub(b, a), i as f64 / n as f64),
+                            scale(sub(c, a), j as f64 / n as f64),
+                        ),
+                    );
+                    let p = normalize(p);
+                    let index = *lookup.entry(vertex_key(p)).or_insert_with(|| {
+                        centers.push(p);
+                        centers.len() - 1
+                    });
+                    *slot = index;
+                }
+            }
+
+            for i in 0..n {
+                for j in 0..(n - i) {
+                    triangles.push([local[i][j], local[i + 1][j], local[i][j + 1]]);
+                    if i + j + 1 < n {
+                        triangles.push([local[i + 1][j], local[i + 1][j + 1], local[i][j + 1]]);
+                    }
+                }
+            }
+        }
+
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::with_capacity(6); centers.len()];
+        let mut incident: Vec<Vec<usize>> = vec![Vec::with_capacity(6); centers.len()];
+        for (t, tri) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                let from = tri[k];
+                let to = tri[(k + 1) % 3];
+                if !neighbors[from].contains(&to) {
+                    neighbors[from].push(to);
+                }
+                if !neighbors[to].contains(&from) {
+                    neighbors[to].push(from);
+                }
+                incident[from].push(t);
+            }
+        }
+
+        // Each hex corner is the centroid of one triangle touching the hex center
+        let corners = incident
+            .iter()
+            .enumerate()
+            .map(|(i, tris)| {
+                let mut points: Vec<Vec3> = tris
+                    .iter()
+                    .map(|&t| {
+                        let [a, b, c] = triangles[t];
+                        normalize(add(centers[a], add(centers[b], centers[c])))
+                    })
+                    .collect();
+                sort_around(centers[i], &mut points);
+                points
+            })
+            .collect();
+
+        for (i, list) in neighbors.iter_mut().enumerate() {
+            let center = centers[i];
+            let u = normalize(sub(centers[list[0]], scale(center, dot(centers[list[0]], center))));
+            let v = cross(center, u);
+            list.sort_by(|&a, &b| {
+                let angle_a = dot(centers[a], v).atan2(dot(centers[a], u));
+                let angle_b = dot(centers[b], v).atan2(dot(centers[b], u));
+                angle_a.total_cmp(&angle_b)
+            });
+        }
+
+        HexGrid {
+            resolution: n,
+            centers,
+            neighbors,
+            corners,
+        }
+    }
+
+    /// Picks the coarsest resolution whose hexes are no wider than `spacing_km` on a planet of
+    /// the given radius.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet_radius_km` - The planet radius in kilometres.
+    /// * `spacing_km` - The desired distance between neighboring hex centers in kilometres.
+    pub fn with_spacing(planet_radius_km: f64, spacing_km: f64) -> HexGrid {
+        // The icosahedron edge subtends ~63.4 degrees; subdividing it n times divides that arc
+        let edge_arc = 1.1071487177940904;
+        let n = (edge_arc * planet_radius_km / spacing_km.max(f64::EPSILON)).ceil() as usize;
+        HexGrid::new(n)
+    }
+
+    /// Picks the resolution whose cell count, `10 * n * n + 2`, is closest to `cell_count`.
+    pub fn with_cell_count(cell_count: usize) -> HexGrid {
+        let n = ((cell_count.saturating_sub(2) as f64 / 10.0).sqrt().round() as usize).max(1);
+        HexGrid::new(n)
+    }
+
+    /// Returns the subdivision frequency used to build the grid.
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// Returns the number of hexes (including the twelve pentagons).
+    pub fn len(&self) -> usize {
+        self.centers.len()
+    }
+
+    /// Returns `true` if the grid has no hexes. A generated grid is never empty.
+    pub fn is_empty(&self) -> bool {
+        self.centers.is_empty()
+    }
+
+    /// Returns the unit-sphere center of every hex.
+    pub fn centers(&self) -> &[Vec3] {
+        &self.centers
+    }
+
+    /// Returns the hexes sharing an edge with `hex`, ordered counter-clockwise.
+    pub fn neighbors(&self, hex: usize) -> &[usize] {
+        &self.neighbors[hex]
+    }
+
+    /// Returns the polygon outline of `hex` on the unit sphere, ordered counter-clockwise.
+    pub fn corners(&self, hex: usize) -> &[Vec3] {
+        &self.corners[hex]
+    }
+
+    /// Returns `true` if `hex` is one of the twelve pentagons.
+    pub fn is_pentagon(&self, hex: usize) -> bool {
+        self.neighbors[hex].len() == 5
+    }
+
+    /// Returns the latitude and longitude of a hex center in degrees.
+    pub fn lat_lon(&self, hex: usize) -> (f64, f64) {
+        let (x, y, z) = self.centers[hex];
+        (z.clamp(-1.0, 1.0).asin() * 180.0 / PI, y.atan2(x) * 180.0 / PI)
+    }
+
+    /// Finds the hex containing `point` by walking the hex adjacency from `hint`.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - Any non-zero point; it is projected onto the unit sphere.
+    /// * `hint` - A hex to start the search from. Nearby hints make the lookup faster.
+    pub fn nearest_hex(&self, point: Vec3, hint: usize) -> usize {
+        let p = normalize(point);
+        let mut current = hint.min(self.centers.len() - 1);
+        let mut best = dot(self.centers[current], p);
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.neighbors[current] {
+                let d = dot(self.centers[neighbor], p);
+                if d > best {
+                    best = d;
+                    current = neighbor;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Maps the Voronoi cells of a point set onto this grid.
+    ///
+    /// Each cell is assigned to the hex containing its site. Hexes that are finer than the
+    /// cells, and so receive no site, fall back to the cell nearest their center.
+    ///
+    /// # Arguments
+    ///
+    /// * `sites` - The Voronoi sites, e.g. the output of `generate_fibonacci_sphere`. The points
+    ///   may be at any radius.
+    ///
+    /// # Returns
+    ///
+    /// A `HexCellMapping` used to aggregate per-cell attributes into per-hex values.
+    pub fn map_cells(&self, sites: &[Vec3]) -> HexCellMapping {
+        let mut cell_to_hex = Vec::with_capacity(sites.len());
+        let mut hex_to_cells: Vec<Vec<usize>> = vec![Vec::new(); self.centers.len()];
+
+        let mut hint = 0;
+        for (cell, &site) in sites.iter().enumerate() {
+            hint = self.nearest_hex(site, hint);
+            cell_to_hex.push(hint);
+            hex_to_cells[hint].push(cell);
+        }
+
+        let normalized: Vec<Vec3> = sites.iter().map(|&s| normalize(s)).collect();
+        let fallback_cell = (0..self.centers.len())
+            .map(|hex| {
+                if let Some(&cell) = hex_to_cells[hex].first() {
+                    return Some(cell);
+                }
+                let center = self.centers[hex];
+                normalized
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| dot(*a.1, center).total_cmp(&dot(*b.1, center)))
+                    .map(|(cell, _)| cell)
+            })
+            .collect();
+
+        HexCellMapping {
+            cell_to_hex,
+            hex_to_cells,
+            fallback_cell,
+        }
+    }
+}
+
+impl HexCellMapping {
+    /// Returns the hex that contains the given Voronoi cell.
+    pub fn hex_of(&self, cell: usize) -> usize {
+        self.cell_to_hex[cell]
+    }
+
+    /// Returns the Voronoi cells whose sites fall inside `hex`.
+    pub fn cells_in(&self, hex: usize) -> &[usize] {
+        &self.hex_to_cells[hex]
+    }
+
+    /// Averages a per-cell attribute over every hex.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - One value per Voronoi cell, in the same order as the mapped sites.
+    ///
+    /// # Returns
+    ///
+    /// One value per hex, or `None` if no cells were mapped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` has fewer values than there are mapped cells.
+    pub fn aggregate_mean(&self, values: &[f64]) -> Option<Vec<f64>> {
+        self.aggregate_with(values, |samples| {
+            samples.iter().sum::<f64>() / samples.len() as f64
+        })
+    }
+
+    /// Aggregates a per-cell attribute over every hex with a custom reduction.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - One value per Voronoi cell, in the same order as the mapped sites.
+    /// * `reduce` - Combines the values of the cells inside a hex. It always receives at least
+    ///   one value.
+    ///
+    /// # Returns
+    ///
+    /// One value per hex, or `None` if no cells were mapped, which leaves nothing to aggregate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` has fewer values than there are mapped cells.
+    pub fn aggregate_with<F>(&self, values: &[f64], reduce: F) -> Option<Vec<f64>>
+    where
+        F: Fn(&[f64]) -> f64,
+    {
+        let mut samples = Vec::new();
+        self.hex_to_cells
+            .iter()
+            .zip(&self.fallback_cell)
+            .map(|(cells, fallback)| {
+                samples.clear();
+                if cells.is_empty() {
+                    samples.push(values[(*fallback)?]);
+                } else {
+                    samples.extend(cells.iter().map(|&cell| values[cell]));
+                }
+                Some(reduce(&samples))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_fall_back_to_the_nearest_cell() {
+        let grid = HexGrid::new(4);
+        let sites = [(1.0, 0.0, 0.0), (-1.0, 0.0, 0.0), (0.0, 0.0, 2.0)];
+        let mapping = grid.map_cells(&sites);
+        let means = mapping.aggregate_mean(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(means.len(), grid.len());
+        for (hex, mean) in means.iter().enumerate() {
+            let center = grid.centers()[hex];
+            let nearest = (0..3).max_by(|&a, &b| dot(normalize(sites[a]), center).total_cmp(&dot(normalize(sites[b]), center))).unwrap();
+            if mapping.cells_in(hex).is_empty() {
+                assert_eq!(*mean, [1.0, 2.0, 3.0][nearest]);
+            }
+        }
+        assert_eq!(means[mapping.hex_of(2)], 3.0);
+    }
+
+    #[test]
+    fn empty_mappings_have_nothing_to_aggregate() {
+        let mapping = HexGrid::new(2).map_cells(&[]);
+        assert_eq!(mapping.aggregate_mean(&[]), None);
+        assert_eq!(mapping.aggregate_with(&[], |samples| samples[0]), None);
+    }
+}
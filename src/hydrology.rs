@@ -0,0 +1,469 @@
+use crate::coords::CoordinateFrame;
+use crate::geom::spherical::triangle_area;
+use crate::planet::Planet;
+use crate::vector::{add, cross, length, normalize, scale, sub, Vec3};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Share of precipitation that runs off instead of evaporating or soaking away
+const RUNOFF_FRACTION: f64 = 0.35;
+// Seconds per year, to turn yearly runoff into discharge
+const SECONDS_PER_YEAR: f64 = 31_557_600.0;
+// Hydraulic geometry: channel width and depth in metres as a power of discharge in m³/s
+const CHANNEL_WIDTH: (f64, f64) = (4.0, 0.5);
+const CHANNEL_DEPTH: (f64, f64) = (0.3, 0.4);
+// A valley is this many times deeper and wider than the channel that cut it
+const VALLEY_DEPTH: f64 = 20.0;
+const VALLEY_WIDTH: f64 = 50.0;
+// Height of a river ribbon above the ground in metres, so it does not fight the terrain for depth
+const RIBBON_LIFT: f64 = 2.0;
+
+/// Vertex and index buffers of river ribbons for water shaders.
+///
+/// Every river of `Drainage::rivers` becomes a strip two vertices wide following its cells, as
+/// wide as its channel times the width scale it was built with. Positions, normals and flow
+/// directions are in the `CoordinateFrame` the buffers were built for, and triangles use its
+/// winding.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RiverMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,         // Unit length, straight up from the planet centre
+    pub flow_directions: Vec<[f32; 3]>, // Unit length, downstream along the river
+    pub flow: Vec<f32>,                 // Discharge in m³/s, e.g. to scale foam and scrolling speed
+    pub uvs: Vec<[f32; 2]>,             // u across from the left bank (0) to the right (1), v kilometres from the source
+    pub indices: Vec<u32>,              // Three per triangle
+}
+
+impl RiverMesh {
+    /// Returns the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// Returns the surface area of every cell in square kilometres.
+///
+/// Each Delaunay triangle gives a third of its area to each of its corners, which matches the
+/// Voronoi areas closely on a well-shaped layout and costs one pass over the triangles.
+pub fn cell_areas(planet: &Planet) -> Vec<f64> {
+    let sites = planet.sites();
+    let mut areas = vec![0.0; planet.cell_count()];
+    let square_radius = planet.radius() * planet.radius();
+    for &[a, b, c] in planet.triangles() {
+        let third = triangle_area(sites[a], sites[b], sites[c]).abs() * square_radius / 3.0;
+        areas[a] += third;
+        areas[b] += third;
+        areas[c] += third;
+    }
+    areas
+}
+
+/// A lake filling a closed basin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lake {
+    pub cells: Vec<usize>,     // Flooded cells, the deepest first
+    pub surface: f64,          // Water surface elevation in metres
+    pub outlet: Option<usize>, // The cell the lake spills into, or `None` if it evaporates all its inflow
+    pub inflow: f64,           // Discharge reaching the lake in m³/s
+}
+
+/// The surface drainage of a planet: where the water on every cell flows and how much.
+///
+/// Every land cell drains to its steepest lower neighbor. Closed basins fill with water up to the
+/// lowest point of their rim, found by a priority flood from the sea, and become lakes. The
+/// flooded cells drain to the deepest one, which spills over the rim into the river beyond. A lake
+/// whose inflow cannot make up for the evaporation from its full surface never reaches the rim: it
+/// stays a terminal lake or inland sea, as deep as its inflow can keep it, and drains nowhere.
+/// Ocean cells drain nowhere either.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drainage {
+    downstream: Vec<Option<usize>>,
+    flow: Vec<f64>,               // Discharge leaving each cell in m³/s
+    order: Vec<usize>,            // Every cell, each before the cell it drains into
+    lakes: Vec<Lake>,
+    lake_of: Vec<Option<usize>>, // Index into `lakes` of each flooded cell
+}
+
+#[derive(PartialEq)]
+struct Flood(f64, usize);
+
+impl Eq for Flood {}
+
+impl Ord for Flood {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+impl PartialOrd for Flood {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Open-water evaporation in mm/year at a mean annual temperature in °C
+fn lake_evaporation(temperature: f64) -> f64 {
+    (400.0 + 60.0 * temperature).clamp(100.0, 2500.0)
+}
+
+// Priority flood from the sea: the water level every cell fills to and the neighbor it was reached
+// from, which leads over the lowest rim toward the sea. A planet without sea floods from its
+// lowest cell.
+fn fill_depressions(planet: &Planet, elevation: &[f64]) -> (Vec<f64>, Vec<Option<usize>>) {
+    let cells = planet.cell_count();
+    let mut level = vec![f64::INFINITY; cells];
+    let mut parent = vec![None; cells];
+    let mut heap = BinaryHeap::new();
+    for cell in (0..cells).filter(|&cell| elevation[cell] < 0.0) {
+        level[cell] = elevation[cell];
+        heap.push(Flood(elevation[cell], cell));
+    }
+    if heap.is_empty() {
+        if let Some(lowest) = (0..cells).min_by(|&a, &b| elevation[a].total_cmp(&elevation[b])) {
+            level[lowest] = elevation[lowest];
+            heap.push(Flood(elevation[lowest], lowest));
+        }
+    }
+    let mut done = vec![false; cells];
+    while let Some(Flood(height, cell)) = heap.pop() {
+        if done[cell] {
+            continue;
+        }
+        done[cell] = true;
+        for &neighbor in planet.neighbors(cell) {
+            let filled = elevation[neighbor].max(height);
+            if !done[neighbor] && filled < level[neighbor] {
+                level[neighbor] = filled;
+                parent[neighbor] = Some(cell);
+                heap.push(Flood(filled, neighbor));
+            }
+        }
+    }
+    (level, parent)
+}
+
+impl Drainage {
+    /// Routes the runoff of a planet's estimated precipitation, see `Planet::annual_precipitation`.
+    pub fn compute(planet: &Planet) -> Drainage {
+        Drainage::from_precipitation(planet, &planet.annual_precipitation())
+    }
+
+    /// Routes the runoff of a given precipitation over the planet's current elevation.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet.
+    /// * `precipitation` - Mean annual precipitation of every cell in mm/year.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precipitation` does not have one value per cell.
+    pub fn from_precipitation(planet: &Planet, precipitation: &[f64]) -> Drainage {
+        Drainage::route(planet, planet.elevation(), precipitation)
+    }
+
+    // Routes runoff over an elevation field that need not be the planet's own
+    pub(crate) fn route(planet: &Planet, elevation: &[f64], precipitation: &[f64]) -> Drainage {
+        assert_eq!(precipitation.len(), planet.cell_count(), "precipitation must have one value per cell");
+        let cells = planet.cell_count();
+        let (level, parent) = fill_depressions(planet, elevation);
+        let flooded = |cell: usize| elevation[cell] >= 0.0 && level[cell] > elevation[cell];
+        let mut downstream: Vec<Option<usize>> = (0..cells)
+            .into_par_iter()
+            .map(|cell| {
+                if elevation[cell] < 0.0 {
+                    return None;
+                }
+                let steepest = planet
+                    .neighbors(cell)
+                    .iter()
+                    .filter(|&&n| elevation[n] < elevation[cell])
+                    .map(|&n| (n, (elevation[cell] - elevation[n]) / planet.distance_km(cell, n).max(f64::MIN_POSITIVE)))
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(n, _)| n);
+                match steepest {
+                    // A cell on a lake's rim at its surface level is where the lake spills, so it
+                    // must not drain back in
+                    Some(n) if flooded(n) && level[n] >= elevation[cell] => parent[cell],
+                    Some(n) => Some(n),
+                    // Flat ground drains the way the flood reached it
+                    None => parent[cell],
+                }
+            })
+            .collect();
+
+        // Every basin's flooded cells drain to its deepest one, which spills over the rim at the
+        // point the flood first reached the basin from
+        let mut basins: Vec<(Vec<usize>, Option<usize>)> = Vec::new();
+        let mut basin_of: Vec<Option<usize>> = vec![None; cells];
+        for start in 0..cells {
+            if !flooded(start) || basin_of[start].is_some() {
+                continue;
+            }
+            basin_of[start] = Some(basins.len());
+            let mut members = vec![start];
+            let mut next = 0;
+            while next < members.len() {
+                let cell = members[next];
+                next += 1;
+                for &neighbor in planet.neighbors(cell) {
+                    if flooded(neighbor) && basin_of[neighbor].is_none() && level[neighbor] == level[start] {
+                        basin_of[neighbor] = Some(basins.len());
+                        members.push(neighbor);
+                    }
+                }
+            }
+            members.sort_by(|&a, &b| elevation[a].total_cmp(&elevation[b]).then(a.cmp(&b)));
+            let spill = members.iter().find_map(|&cell| parent[cell].filter(|&p| basin_of[p] != basin_of[cell]));
+            let deepest = members[0];
+            for &cell in &members[1..] {
+                downstream[cell] = Some(deepest);
+            }
+            downstream[deepest] = spill;
+            basins.push((members, spill));
+        }
+
+        // Runoff in m³/s: km² × 1e6 m²/km² × mm/year ÷ 1000 mm/m
+        let areas = cell_areas(planet);
+        let mut flow: Vec<f64> = (0..cells)
+            .map(|cell| if elevation[cell] < 0.0 { 0.0 } else { areas[cell] * 1000.0 * precipitation[cell].max(0.0) * RUNOFF_FRACTION / SECONDS_PER_YEAR })
+            .collect();
+        let temperature = if basins.is_empty() { Vec::new() } else { planet.mean_annual_temperature() };
+
+        // Pass water on in topological order, so every cell is visited after everything upstream
+        // of it and every lake knows its whole inflow before it spills
+        let mut inflows = vec![0usize; cells];
+        for next in downstream.iter().flatten() {
+            inflows[*next] += 1;
+        }
+        let mut order: Vec<usize> = (0..cells).filter(|&cell| inflows[cell] == 0).collect();
+        let mut lakes = Vec::new();
+        let mut lake_of = vec![None; cells];
+        let mut next = 0;
+        while next < order.len() {
+            let cell = order[next];
+            next += 1;
+            if let Some(basin) = basin_of[cell].filter(|&basin| basins[basin].0[0] == cell) {
+                let (members, spill) = &basins[basin];
+                let inflow = flow[cell];
+                // Net loss in m³/s from each flooded cell: evaporation less the rain falling on it
+                let loss = |cell: usize| areas[cell] * 1000.0 * (lake_evaporation(temperature[cell]) - precipitation[cell]).max(0.0) / SECONDS_PER_YEAR;
+                let full_loss: f64 = members.iter().map(|&m| loss(m)).sum();
+                let (wet, surface, outlet) = if inflow >= full_loss {
+                    flow[cell] = inflow - full_loss;
+                    (members.len(), level[cell], *spill)
+                } else {
+                    // The lake rises only until evaporation balances its inflow
+                    let (mut wet, mut lost) = (0, 0.0);
+                    while wet < members.len() && lost < inflow {
+                        lost += loss(members[wet]);
+                        wet += 1;
+                    }
+                    let wet = wet.max(1);
+                    // The rim cell no longer waits on the lake
+                    if let Some(rim) = downstream[cell].take() {
+                        inflows[rim] -= 1;
+                        if inflows[rim] == 0 {
+                            order.push(rim);
+                        }
+                    }
+                    let surface = members.get(wet).map_or(level[cell], |&m| elevation[m].min(level[cell]));
+                    (wet, surface, None)
+                };
+                for &member in &members[..wet] {
+                    lake_of[member] = Some(lakes.len());
+                }
+                lakes.push(Lake {
+                    cells: members[..wet].to_vec(),
+                    surface,
+                    outlet,
+                    inflow,
+                });
+            }
+            if let Some(down) = downstream[cell] {
+                flow[down] += flow[cell];
+                inflows[down] -= 1;
+                if inflows[down] == 0 {
+                    order.push(down);
+                }
+            }
+        }
+        Drainage {
+            downstream,
+            flow,
+            order,
+            lakes,
+            lake_of,
+        }
+    }
+
+    /// Returns the cell a cell drains into, or `None` for ocean cells and terminal lakes.
+    pub fn downstream(&self, cell: usize) -> Option<usize> {
+        self.downstream[cell]
+    }
+
+    /// Returns every cell ordered so that each comes before the cell it drains into, e.g. to pass
+    /// water or sediment downstream in one sweep.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Returns the lakes.
+    pub fn lakes(&self) -> &[Lake] {
+        &self.lakes
+    }
+
+    /// Returns the index into `lakes` of the lake covering a cell, or `None` for dry land and sea.
+    pub fn lake_of(&self, cell: usize) -> Option<usize> {
+        self.lake_of[cell]
+    }
+
+    /// Returns the discharge through every cell in m³/s, its own runoff plus all of upstream.
+    pub fn flow(&self) -> &[f64] {
+        &self.flow
+    }
+
+    /// Returns the width in metres of the channel through a cell, from its discharge.
+    pub fn channel_width(&self, cell: usize) -> f64 {
+        CHANNEL_WIDTH.0 * self.flow[cell].powf(CHANNEL_WIDTH.1)
+    }
+
+    /// Returns the depth in metres of the channel through a cell, from its discharge.
+    pub fn channel_depth(&self, cell: usize) -> f64 {
+        CHANNEL_DEPTH.0 * self.flow[cell].powf(CHANNEL_DEPTH.1)
+    }
+
+    /// Traces the rivers carrying at least `min_flow` m³/s.
+    ///
+    /// # Returns
+    ///
+    /// Each river as a chain of cells from its source to the cell it ends in: the ocean, a lake or
+    /// the larger river it joins. Rivers leaving a lake start at its outlet. Suitable for
+    /// `render::map::MapLayers::rivers`.
+    pub fn rivers(&self, min_flow: f64) -> Vec<Vec<usize>> {
+        let cells = self.flow.len();
+        let is_river = |cell: usize| self.flow[cell] >= min_flow && self.downstream[cell].is_some() && self.lake_of[cell].is_none();
+        // The main stem through a confluence continues from the largest inflowing river
+        let mut main_inflow: Vec<Option<usize>> = vec![None; cells];
+        let mut has_inflow = vec![false; cells];
+        for cell in (0..cells).filter(|&cell| is_river(cell)) {
+            if let Some(next) = self.downstream[cell] {
+                has_inflow[next] = true;
+                if main_inflow[next].is_none_or(|main| self.flow[cell] > self.flow[main]) {
+                    main_inflow[next] = Some(cell);
+                }
+            }
+        }
+        (0..cells)
+            .filter(|&cell| is_river(cell) && !has_inflow[cell])
+            .map(|source| {
+                let mut river = vec![source];
+                let mut cell = source;
+                while let Some(next) = self.downstream[cell] {
+                    river.push(next);
+                    if !is_river(next) || main_inflow[next] != Some(cell) {
+                        break;
+                    }
+                    cell = next;
+                }
+                river
+            })
+            .collect()
+    }
+
+    /// Builds ribbon meshes of the rivers carrying at least `min_flow` m³/s.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the drainage was computed for.
+    /// * `min_flow` - The smallest discharge drawn, as for `rivers`.
+    /// * `frame` - The target engine's axes, units and winding, e.g. `CoordinateFrame::BEVY`.
+    /// * `exaggeration` - Multiplier on elevation, matching the terrain mesh, e.g. from
+    ///   `Planet::mesh`.
+    /// * `width_scale` - Multiplier on channel width, so rivers stay visible on coarse planets;
+    ///   1.0 is true width.
+    pub fn river_mesh(&self, planet: &Planet, min_flow: f64, frame: &CoordinateFrame, exaggeration: f64, width_scale: f64) -> RiverMesh {
+        let direction_frame = frame.with_scale(1.0);
+        let to_f32 = |(x, y, z): Vec3| [x as f32, y as f32, z as f32];
+        let mut mesh = RiverMesh::default();
+        for river in self.rivers(min_flow) {
+            let points: Vec<Vec3> = river
+                .iter()
+                .map(|&cell| {
+                    let height = planet.elevation()[cell].max(0.0) + RIBBON_LIFT;
+                    scale(planet.sites()[cell], planet.radius() + height / 1000.0 * exaggeration)
+                })
+                .collect();
+            let mut distance = 0.0;
+            for (i, &cell) in river.iter().enumerate() {
+                // The river's mouth cell carries the flow of the river arriving there
+                let source = if i + 1 == river.len() && i > 0 { river[i - 1] } else { cell };
+                let tangent = normalize(sub(points[(i + 1).min(points.len() - 1)], points[i.saturating_sub(1)]));
+                let up = planet.sites()[cell];
+                let half_width = self.channel_width(source) * width_scale / 2000.0;
+                let left = scale(normalize(cross(up, tangent)), half_width);
+                if i > 0 {
+                    distance += length(sub(points[i], points[i - 1]));
+                }
+                let base = mesh.positions.len() as u32;
+                for (side, u) in [(left, 0.0), (scale(left, -1.0), 1.0)] {
+                    mesh.positions.push(to_f32(frame.from_native(add(points[i], side))));
+                    mesh.normals.push(to_f32(direction_frame.from_native(up)));
+                    mesh.flow_directions.push(to_f32(direction_frame.from_native(tangent)));
+                    mesh.flow.push(self.flow[source] as f32);
+                    mesh.uvs.push([u, distance as f32]);
+                }
+                if i > 0 {
+                    // Left and right of the previous vertex pair, then of this one
+                    let [pl, pr, l, r] = [base - 2, base - 1, base, base + 1];
+                    for triangle in [[pl, pr, l], [pr, r, l]] {
+                        let [a, b, c] = frame.triangle(triangle.map(|index| index as usize));
+                        mesh.indices.extend([a as u32, b as u32, c as u32]);
+                    }
+                }
+            }
+        }
+        mesh
+    }
+}
+
+impl Planet {
+    /// Carves river valleys into the elevation.
+    ///
+    /// Every cell of a river carrying at least `min_flow` m³/s sinks by twenty times its channel
+    /// depth, but never below the cell it drains into or below sea level, so the rivers keep
+    /// flowing the same way. Its banks sink by up to half as much where the valley, fifty times as
+    /// wide as the channel, spans a good share of the distance to them. Recompute the drainage
+    /// afterwards for flows over the carved terrain.
+    ///
+    /// # Arguments
+    ///
+    /// * `drainage` - The drainage of the planet's current elevation, e.g. from
+    ///   `Drainage::compute`.
+    /// * `min_flow` - The smallest discharge that cuts a valley.
+    pub fn carve_valleys(&mut self, drainage: &Drainage, min_flow: f64) {
+        let mut elevation = self.elevation().to_vec();
+        let is_river = |cell: usize| drainage.flow[cell] >= min_flow && drainage.downstream[cell].is_some() && drainage.lake_of[cell].is_none();
+        // Cells downstream come first, so each is cut no deeper than what it drains into
+        for &cell in drainage.order.iter().rev().filter(|&&cell| is_river(cell)) {
+            let floor = drainage.downstream[cell].map_or(0.0, |next| elevation[next].max(0.0));
+            let depth = (VALLEY_DEPTH * drainage.channel_depth(cell)).min(elevation[cell] - floor).max(0.0);
+            elevation[cell] -= depth;
+            let valley_width = VALLEY_WIDTH * drainage.channel_width(cell) / 1000.0;
+            for &bank in self.neighbors(cell) {
+                if is_river(bank) || elevation[bank] <= elevation[cell] {
+                    continue;
+                }
+                let share = 0.5 * (valley_width / self.distance_km(cell, bank).max(f64::MIN_POSITIVE)).min(1.0);
+                elevation[bank] = (elevation[bank] - depth * share).max(elevation[cell]);
+            }
+        }
+        self.set_elevation(elevation);
+    }
+}
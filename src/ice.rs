@@ -0,0 +1,130 @@
+use crate::climate;
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::raster::Raster;
+use rayon::prelude::*;
+
+// Seawater freezes a little below 0 °C
+const SEA_ICE_TEMPERATURE: f64 = -2.0;
+// Snow cover fraction above which a cell counts as snow covered
+const SNOW_THRESHOLD: f64 = 0.5;
+// Samples per year used to find the warmest part of the year
+const ANNUAL_SAMPLES: usize = 12;
+
+/// The snow and ice state of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum IceState {
+    /// No snow or ice.
+    Free = 0,
+    /// Land covered by snow that melts during the warm season.
+    SeasonalSnow = 1,
+    /// Frozen ocean that thaws during the warm season.
+    SeaIce = 2,
+    /// Land or ocean that stays frozen all year: polar caps and glaciated peaks.
+    PermanentIce = 3,
+}
+
+impl IceState {
+    /// Returns `true` for any state other than `Free`.
+    pub fn is_frozen(&self) -> bool {
+        *self != IceState::Free
+    }
+}
+
+impl Planet {
+    /// Returns the warmest equilibrium temperature each cell reaches over a year, in °C.
+    ///
+    /// Cells whose warmest temperature stays below freezing hold permanent ice.
+    pub fn warmest_temperature(&self) -> Vec<f64> {
+        let calendar = self.calendar();
+        let declinations: Vec<f64> = (0..ANNUAL_SAMPLES)
+            .map(|i| {
+                let day = calendar.year_length_days() * i as f64 / ANNUAL_SAMPLES as f64;
+                calendar.solar_declination(day, self.axial_tilt())
+            })
+            .collect();
+        let flux = self.solar_flux();
+        let greenhouse = self.atmosphere().greenhouse_offset();
+        let elevation = self.elevation();
+
+        (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let (latitude, _) = self.lat_lon(cell);
+                declinations
+                    .iter()
+                    .map(|&d| climate::equilibrium_temperature(climate::daily_mean_insolation(latitude, d, flux)))
+                    .fold(f64::NEG_INFINITY, f64::max)
+                    + greenhouse
+                    - climate::LAPSE_RATE * elevation[cell].max(0.0)
+            })
+            .collect()
+    }
+
+    /// Classifies every cell's snow and ice state for the current day.
+    ///
+    /// Permanent ice comes from the annual climate (the warmest part of the year stays below
+    /// freezing), while seasonal snow and sea ice follow the current temperature and snow cover,
+    /// so they advance and retreat as the planet is advanced through the year.
+    pub fn ice_state(&self) -> Vec<IceState> {
+        let warmest = self.warmest_temperature();
+        let temperature = self.temperature();
+        let snow = self.snow_cover();
+        (0..self.cell_count())
+            .map(|cell| {
+                let ocean = self.is_ocean(cell);
+                let freezing = if ocean { SEA_ICE_TEMPERATURE } else { 0.0 };
+                if warmest[cell] < freezing {
+                    IceState::PermanentIce
+                } else if ocean {
+                    if temperature[cell] < SEA_ICE_TEMPERATURE {
+                        IceState::SeaIce
+                    } else {
+                        IceState::Free
+                    }
+                } else if snow[cell] >= SNOW_THRESHOLD {
+                    IceState::SeasonalSnow
+                } else {
+                    IceState::Free
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the altitude of the snow line at a latitude on the current day, in metres.
+    ///
+    /// This is the height at which the lapse-rate-adjusted equilibrium temperature reaches
+    /// freezing; it is `0.0` where it is already freezing at sea level.
+    pub fn snow_line_altitude(&self, latitude: f64) -> f64 {
+        let insolation = climate::daily_mean_insolation(latitude, self.solar_declination(), self.solar_flux());
+        ((climate::equilibrium_temperature(insolation) + self.atmosphere().greenhouse_offset()) / climate::LAPSE_RATE).max(0.0)
+    }
+
+    /// Renders the snow and ice state as a mask texture.
+    ///
+    /// # Arguments
+    ///
+    /// * `projection` - The map projection of the texture.
+    /// * `width`, `height` - The texture size in pixels.
+    /// * `include_seasonal` - Whether seasonal snow and sea ice count as ice, or only permanent ice.
+    ///
+    /// # Returns
+    ///
+    /// A grayscale image that is white where there is ice and black elsewhere.
+    pub fn ice_mask(&self, projection: Projection, width: usize, height: usize, include_seasonal: bool) -> Image {
+        let values: Vec<f64> = self
+            .ice_state()
+            .iter()
+            .map(|state| match state {
+                IceState::PermanentIce => 1.0,
+                IceState::Free => 0.0,
+                _ if include_seasonal => 1.0,
+                _ => 0.0,
+            })
+            .collect();
+        let raster: Raster = self.rasterize_values(&values, projection, width, height);
+        Image::from_raster_gray(&raster, 0.0, 1.0)
+    }
+}
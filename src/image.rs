@@ -0,0 +1,148 @@
+use crate::npz::crc32;
+use crate::raster::Raster;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, BufWriter, Write};
+
+/// An 8-bit RGBA image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    /// Creates an image filled with a single color.
+    pub fn new(width: usize, height: usize, fill: [u8; 4]) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![fill; width * height],
+        }
+    }
+
+    /// Converts a raster to a grayscale image, mapping `min..=max` to black..white.
+    ///
+    /// `NaN` samples become fully transparent.
+    pub fn from_raster_gray(raster: &Raster, min: f64, max: f64) -> Image {
+        let range = (max - min).max(f64::EPSILON);
+        let pixels = raster
+            .as_slice()
+            .iter()
+            .map(|&v| {
+                if v.is_nan() {
+                    [0, 0, 0, 0]
+                } else {
+                    let g = (((v - min) / range).clamp(0.0, 1.0) * 255.0).round() as u8;
+                    [g, g, g, 255]
+                }
+            })
+            .collect();
+        Image {
+            width: raster.width(),
+            height: raster.height(),
+            pixels,
+        }
+    }
+
+    /// Returns the image width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the image height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the pixel at the given column and row.
+    pub fn get(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Sets the pixel at the given column and row. Out-of-bounds writes are ignored.
+    pub fn set(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    /// Returns all pixels in row-major order.
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    /// Encodes the image as a PNG.
+    ///
+    /// The image data is stored without compression, which keeps the encoder dependency-free at
+    /// the cost of file size.
+    pub fn encode_png(&self) -> Vec<u8> {
+        // Each scanline is prefixed with filter type 0 (none)
+        let mut raw = Vec::with_capacity(self.height * (1 + self.width * 4));
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            for pixel in row {
+                raw.extend_from_slice(pixel);
+            }
+        }
+
+        // Wrap the scanlines in a zlib stream of stored deflate blocks
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(65535).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+        }
+        while let Some(block) = blocks.next() {
+            let last = blocks.peek().is_none();
+            let len = block.len() as u16;
+            zlib.push(last as u8);
+            zlib.extend_from_slice(&len.to_le_bytes());
+            zlib.extend_from_slice(&(!len).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, no interlace
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &zlib);
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Writes the image to a PNG file.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `io::Error` if the file could not be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_png(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&self.encode_png())?;
+        file.flush()
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
@@ -0,0 +1,248 @@
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::vector::{add, dot, length, scale};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+/// The geometry a label describes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelGeometry {
+    /// A single place, e.g. a city or a peak.
+    Point(usize),
+    /// A region made of cells, e.g. a continent, sea or nation.
+    Area(Vec<usize>),
+    /// An ordered chain of cells, e.g. a river or road.
+    Path(Vec<usize>),
+}
+
+/// A named map feature to be labelled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelFeature {
+    pub name: String,
+    pub geometry: LabelGeometry,
+    pub priority: f64,  // Higher priority labels are placed first and win collisions
+    pub font_size: f64, // Text height in pixels
+}
+
+impl LabelFeature {
+    /// Creates a feature with priority `0.0` and a 12 px font.
+    pub fn new(name: &str, geometry: LabelGeometry) -> LabelFeature {
+        LabelFeature {
+            name: name.to_string(),
+            geometry,
+            priority: 0.0,
+            font_size: 12.0,
+        }
+    }
+}
+
+/// A label that was placed on the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedLabel {
+    pub name: String,
+    pub x: f64,           // Anchor (text centre) in pixels from the left edge
+    pub y: f64,           // Anchor (text centre) in pixels from the top edge
+    pub angle: f64,       // Rotation in degrees, clockwise; non-zero for path labels
+    pub font_size: f64,
+    pub bounds: [f64; 4], // Axis-aligned box `[min_x, min_y, max_x, max_y]` in pixels
+}
+
+// Approximate advance width of one glyph relative to the font size
+const GLYPH_ASPECT: f64 = 0.6;
+// Candidate anchors tried per feature before giving up
+const CANDIDATES: usize = 16;
+
+// The axis-aligned box of a rotated text rectangle centred on `(x, y)`
+fn text_bounds(name: &str, font_size: f64, x: f64, y: f64, angle: f64) -> [f64; 4] {
+    let half_width = name.chars().count() as f64 * font_size * GLYPH_ASPECT / 2.0;
+    let half_height = font_size / 2.0;
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let extent_x = half_width * cos.abs() + half_height * sin.abs();
+    let extent_y = half_width * sin.abs() + half_height * cos.abs();
+    [x - extent_x, y - extent_y, x + extent_x, y + extent_y]
+}
+
+fn overlaps(a: &[f64; 4], b: &[f64; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+// Area cells ordered outward from the one nearest the region's centroid, for regions with no edge
+fn centroid_order(planet: &Planet, cells: &[usize]) -> Vec<usize> {
+    let mut ordered = cells.to_vec();
+    ordered.sort_unstable();
+    ordered.dedup();
+    let sum = ordered.iter().fold((0.0, 0.0, 0.0), |sum, &cell| add(sum, planet.sites()[cell]));
+    // Sites placed symmetrically over the sphere can cancel out, leaving no direction to prefer
+    if length(sum) > 0.0 {
+        let centroid = scale(sum, 1.0 / length(sum));
+        ordered.sort_by(|&a, &b| dot(planet.sites()[b], centroid).total_cmp(&dot(planet.sites()[a], centroid)).then(a.cmp(&b)));
+    }
+    ordered
+}
+
+// Area cells ordered from the most interior outward, by graph hops to the region's edge
+fn interior_order(planet: &Planet, cells: &[usize]) -> Vec<usize> {
+    let region: HashSet<usize> = cells.iter().copied().collect();
+    let mut depth: Vec<(usize, usize)> = Vec::with_capacity(cells.len());
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for &cell in cells {
+        if planet.neighbors(cell).iter().any(|n| !region.contains(n)) && seen.insert(cell) {
+            queue.push_back((cell, 0));
+        }
+    }
+    // A region covering the whole planet has no edge to measure from
+    if queue.is_empty() {
+        return centroid_order(planet, cells);
+    }
+    while let Some((cell, d)) = queue.pop_front() {
+        depth.push((cell, d));
+        for &neighbor in planet.neighbors(cell) {
+            if region.contains(&neighbor) && seen.insert(neighbor) {
+                queue.push_back((neighbor, d + 1));
+            }
+        }
+    }
+    depth.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    depth.into_iter().map(|(cell, _)| cell).collect()
+}
+
+/// Places labels for named features on a projected map, avoiding collisions.
+///
+/// Features are placed greedily in order of priority. Point labels try positions around the
+/// point; area labels try the most interior cells first (so the name sits in the middle of the
+/// region, away from its coasts), or the cells nearest its centroid when the region covers the
+/// whole planet and has no coasts; path labels are centred on successive points along the path
+/// and rotated to follow it. A label is dropped if none of its candidates fits on the map
+/// without overlapping an earlier label.
+///
+/// # Arguments
+///
+/// * `planet` - The planet the feature cells belong to.
+/// * `features` - The features to label.
+/// * `projection` - The map projection.
+/// * `width`, `height` - The map size in pixels.
+///
+/// # Returns
+///
+/// The labels that could be placed, in placement order.
+pub fn place_labels(planet: &Planet, features: &[LabelFeature], projection: Projection, width: usize, height: usize) -> Vec<PlacedLabel> {
+    let (w, h) = (width as f64, height as f64);
+    let to_pixel = |cell: usize| -> Option<(f64, f64)> {
+        let (latitude, longitude) = planet.lat_lon(cell);
+        projection.forward(latitude, longitude).map(|(u, v)| (u * w, v * h))
+    };
+
+    let mut order: Vec<&LabelFeature> = features.iter().collect();
+    order.sort_by(|a, b| b.priority.total_cmp(&a.priority));
+
+    let mut placed: Vec<PlacedLabel> = Vec::new();
+    for feature in order {
+        let size = feature.font_size;
+        let candidates: Vec<(f64, f64, f64)> = match &feature.geometry {
+            LabelGeometry::Point(cell) => match to_pixel(*cell) {
+                Some((x, y)) => {
+                    let half_width = feature.name.chars().count() as f64 * size * GLYPH_ASPECT / 2.0;
+                    let dx = half_width + size * 0.4;
+                    let dy = size;
+                    [(dx, 0.0), (-dx, 0.0), (0.0, -dy), (0.0, dy), (dx, -dy), (-dx, -dy), (dx, dy), (-dx, dy)]
+                        .iter()
+                        .map(|&(ox, oy)| (x + ox, y + oy, 0.0))
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+            LabelGeometry::Area(cells) => interior_order(planet, cells)
+                .into_iter()
+                .filter_map(to_pixel)
+                .take(CANDIDATES)
+                .map(|(x, y)| (x, y, 0.0))
+                .collect(),
+            LabelGeometry::Path(cells) => {
+                let points: Vec<(f64, f64)> = cells.iter().filter_map(|&c| to_pixel(c)).collect();
+                if points.len() < 2 {
+                    points.iter().map(|&(x, y)| (x, y, 0.0)).collect()
+                } else {
+                    // Start in the middle of the path and alternate outward
+                    let middle = points.len() / 2;
+                    let span = (points.len() / 6).max(1);
+                    (0..points.len())
+                        .map(|i| if i % 2 == 0 { middle + i / 2 } else { middle.saturating_sub(i / 2 + 1) })
+                        .filter(|&i| i < points.len())
+                        .take(CANDIDATES)
+                        .map(|i| {
+                            let a = points[i.saturating_sub(span)];
+                            let b = points[(i + span).min(points.len() - 1)];
+                            let mut angle = (b.1 - a.1).atan2(b.0 - a.0).to_degrees();
+                            // Keep text upright
+                            if angle > 90.0 {
+                                angle -= 180.0;
+                            } else if angle < -90.0 {
+                                angle += 180.0;
+                            }
+                            (points[i].0, points[i].1, angle)
+                        })
+                        .collect()
+                }
+            }
+        };
+
+        let fit = candidates.into_iter().find_map(|(x, y, angle)| {
+            let bounds = text_bounds(&feature.name, size, x, y, angle);
+            let inside = bounds[0] >= 0.0 && bounds[1] >= 0.0 && bounds[2] <= w && bounds[3] <= h;
+            (inside && !placed.iter().any(|label| overlaps(&label.bounds, &bounds))).then_some((x, y, angle, bounds))
+        });
+        if let Some((x, y, angle, bounds)) = fit {
+            placed.push(PlacedLabel {
+                name: feature.name.clone(),
+                x,
+                y,
+                angle,
+                font_size: size,
+                bounds,
+            });
+        }
+    }
+    placed
+}
+
+// Escapes text for inclusion in XML
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Renders placed labels as SVG `<text>` elements, ready to embed in an SVG map.
+///
+/// Labels are drawn with a white halo so they stay readable over any terrain colour.
+pub fn labels_to_svg(labels: &[PlacedLabel]) -> String {
+    let mut svg = String::from("<g font-family=\"sans-serif\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"#222\" stroke=\"#fff\" stroke-width=\"2\" paint-order=\"stroke\">\n");
+    for label in labels {
+        let _ = write!(svg, "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\"", label.x, label.y, label.font_size);
+        if label.angle != 0.0 {
+            let _ = write!(svg, " transform=\"rotate({:.1} {:.1} {:.1})\"", label.angle, label.x, label.y);
+        }
+        let _ = writeln!(svg, ">{}</text>", escape_xml(&label.name));
+    }
+    svg.push_str("</g>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+
+    #[test]
+    fn areas_without_edges_are_labelled_near_their_centroid() {
+        let planet = Planet::new(seeded_fibonacci_sphere(400, 0.0, 9));
+        let everything: Vec<usize> = (0..planet.cell_count()).collect();
+        let north: Vec<usize> = everything.iter().copied().filter(|&cell| planet.sites()[cell].2 > 0.5).collect();
+        let features = [LabelFeature::new("World", LabelGeometry::Area(everything)), LabelFeature::new("North", LabelGeometry::Area(north))];
+        let labels = place_labels(&planet, &features, Projection::Equirectangular, 512, 256);
+        assert_eq!(labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>(), ["World", "North"]);
+
+        // The symmetric octahedron has no centroid, so its cells are tried in order
+        let octahedron = Planet::new(vec![(1.0, 0.0, 0.0), (-1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, -1.0, 0.0), (0.0, 0.0, 1.0), (0.0, 0.0, -1.0)]);
+        assert_eq!(interior_order(&octahedron, &[5, 3, 1, 0, 2, 4, 3]), [0, 1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::planet_file::LayerData;
+use std::collections::BTreeMap;
+
+/// An enum stored per cell in a custom layer, e.g. the faction holding each cell.
+///
+/// Values are stored as one-byte codes, so an enum has at most 256 variants.
+pub trait LayerEnum: Copy {
+    /// The variant names, in code order.
+    const VARIANTS: &'static [&'static str];
+
+    /// Returns the code of the value, its index in `VARIANTS`.
+    fn code(self) -> u8;
+
+    /// Returns the value with a code, or `None` for an unknown code.
+    fn from_code(code: u8) -> Option<Self>;
+}
+
+/// The values of a custom per-cell layer, one per cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomLayer {
+    /// Continuous values, e.g. pollution or faction influence.
+    F32(Vec<f32>),
+    /// Small counts or flags, e.g. fog-of-war visibility.
+    U8(Vec<u8>),
+    /// Codes of a `LayerEnum`, with its variant names.
+    Enum { variants: Vec<String>, codes: Vec<u8> },
+}
+
+impl CustomLayer {
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            CustomLayer::F32(values) => values.len(),
+            CustomLayer::U8(values) => values.len(),
+            CustomLayer::Enum { codes, .. } => codes.len(),
+        }
+    }
+
+    /// Returns true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the values as `f64`, enums as their codes, e.g. for rasterizing like a built-in
+    /// `Layer`.
+    pub fn to_f64(&self) -> Vec<f64> {
+        match self {
+            CustomLayer::F32(values) => values.iter().map(|&value| value as f64).collect(),
+            CustomLayer::U8(values) | CustomLayer::Enum { codes: values, .. } => values.iter().map(|&value| value as f64).collect(),
+        }
+    }
+
+    /// Returns the values for a `PlanetFileWriter` layer, enums as their codes.
+    pub fn to_layer_data(&self) -> LayerData {
+        match self {
+            CustomLayer::F32(values) => LayerData::F32(values.clone()),
+            CustomLayer::U8(values) | CustomLayer::Enum { codes: values, .. } => LayerData::U8(values.clone()),
+        }
+    }
+
+    // Appends a copy of a cell's value for a new cell
+    fn push_copy(&mut self, cell: usize) {
+        match self {
+            CustomLayer::F32(values) => values.push(values[cell]),
+            CustomLayer::U8(values) | CustomLayer::Enum { codes: values, .. } => values.push(values[cell]),
+        }
+    }
+
+    fn swap_remove(&mut self, cell: usize) {
+        match self {
+            CustomLayer::F32(values) => {
+                values.swap_remove(cell);
+            }
+            CustomLayer::U8(values) | CustomLayer::Enum { codes: values, .. } => {
+                values.swap_remove(cell);
+            }
+        }
+    }
+
+    fn memory_bytes(&self) -> usize {
+        match self {
+            CustomLayer::F32(values) => values.capacity() * std::mem::size_of::<f32>(),
+            CustomLayer::U8(values) => values.capacity(),
+            CustomLayer::Enum { variants, codes } => codes.capacity() + variants.iter().map(|name| name.capacity()).sum::<usize>(),
+        }
+    }
+}
+
+/// User-defined per-cell layers of a planet, keyed by name, e.g. pollution, faction influence or
+/// fog of war.
+///
+/// Layers follow the planet's cells: a cell added with `Planet::insert_site` copies the values of
+/// its nearest neighbor, and `Planet::remove_site` moves values like it moves cells. They are
+/// written to planet files and compact planets alongside the built-in layers, in key order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LayerMap {
+    cells: usize,
+    layers: BTreeMap<String, CustomLayer>,
+}
+
+impl LayerMap {
+    // An empty map for a planet with this many cells
+    pub(crate) fn new(cells: usize) -> LayerMap {
+        LayerMap { cells, layers: BTreeMap::new() }
+    }
+
+    /// Adds a layer, replacing any layer with the same key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is longer than 255 bytes, the longest a planet file stores, or the layer
+    /// does not have one value per cell.
+    pub fn insert(&mut self, key: &str, layer: CustomLayer) {
+        assert!(key.len() <= u8::MAX as usize, "layer key '{}' is longer than 255 bytes", key);
+        assert_eq!(layer.len(), self.cells, "layer '{}' must have one value per cell", key);
+        self.layers.insert(key.to_string(), layer);
+    }
+
+    /// Adds a layer of `f32` values, replacing any layer with the same key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one value per cell.
+    pub fn insert_f32(&mut self, key: &str, values: Vec<f32>) {
+        self.insert(key, CustomLayer::F32(values));
+    }
+
+    /// Adds a layer of `u8` values, replacing any layer with the same key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one value per cell.
+    pub fn insert_u8(&mut self, key: &str, values: Vec<u8>) {
+        self.insert(key, CustomLayer::U8(values));
+    }
+
+    /// Adds a layer of enum values, replacing any layer with the same key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one value per cell.
+    pub fn insert_enum<T: LayerEnum>(&mut self, key: &str, values: &[T]) {
+        let variants = T::VARIANTS.iter().map(|name| name.to_string()).collect();
+        self.insert(key, CustomLayer::Enum { variants, codes: values.iter().map(|value| value.code()).collect() });
+    }
+
+    /// Removes a layer and returns it, or `None` if there is no layer with the key.
+    pub fn remove(&mut self, key: &str) -> Option<CustomLayer> {
+        self.layers.remove(key)
+    }
+
+    /// Returns a layer, or `None` if there is no layer with the key.
+    pub fn get(&self, key: &str) -> Option<&CustomLayer> {
+        self.layers.get(key)
+    }
+
+    /// Returns the values of an `f32` layer, or `None` if there is no such layer.
+    pub fn f32(&self, key: &str) -> Option<&[f32]> {
+        match self.layers.get(key) {
+            Some(CustomLayer::F32(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of an `f32` layer for editing, or `None` if there is no such layer.
+    pub fn f32_mut(&mut self, key: &str) -> Option<&mut [f32]> {
+        match self.layers.get_mut(key) {
+            Some(CustomLayer::F32(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of a `u8` layer, or `None` if there is no such layer.
+    pub fn u8(&self, key: &str) -> Option<&[u8]> {
+        match self.layers.get(key) {
+            Some(CustomLayer::U8(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the values of a `u8` layer for editing, or `None` if there is no such layer.
+    pub fn u8_mut(&mut self, key: &str) -> Option<&mut [u8]> {
+        match self.layers.get_mut(key) {
+            Some(CustomLayer::U8(values)) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an enum layer in one cell, or `None` if there is no such layer or the
+    /// stored code is not a variant of `T`.
+    pub fn enum_at<T: LayerEnum>(&self, key: &str, cell: usize) -> Option<T> {
+        match self.layers.get(key) {
+            Some(CustomLayer::Enum { codes, .. }) => T::from_code(codes[cell]),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of an enum layer in one cell.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there is no enum layer with the key, in which case nothing changes.
+    pub fn set_enum<T: LayerEnum>(&mut self, key: &str, cell: usize, value: T) -> bool {
+        match self.layers.get_mut(key) {
+            Some(CustomLayer::Enum { codes, .. }) => {
+                codes[cell] = value.code();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the keys of the layers, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.layers.keys().map(|key| key.as_str())
+    }
+
+    /// Returns the layers with their keys, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CustomLayer)> {
+        self.layers.iter().map(|(key, layer)| (key.as_str(), layer))
+    }
+
+    /// Returns the number of layers.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns true if there are no layers.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    // Gives a new last cell the values of an existing cell
+    pub(crate) fn push_copy(&mut self, cell: usize) {
+        self.cells += 1;
+        self.layers.values_mut().for_each(|layer| layer.push_copy(cell));
+    }
+
+    // Removes a cell, moving the last cell into its index
+    pub(crate) fn swap_remove(&mut self, cell: usize) {
+        self.cells -= 1;
+        self.layers.values_mut().for_each(|layer| layer.swap_remove(cell));
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.layers.iter().map(|(key, layer)| key.capacity() + layer.memory_bytes()).sum()
+    }
+}
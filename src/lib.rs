@@ -1,8 +1,96 @@
 mod fibonacci_sphere;
 mod delaunay_triangulation;
+mod vector;
+pub mod hex_grid;
+pub mod weather;
+pub mod climate;
+pub mod planet;
+pub mod projection;
+pub mod raster;
+pub mod npz;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataset;
+pub mod noise;
+pub mod image;
+pub mod clouds;
+pub mod terrain;
+pub mod render;
+pub mod atmosphere;
+pub mod ice;
+pub mod magnetism;
+pub mod geology;
+pub mod vegetation;
+pub mod tectonics;
+pub mod resources;
+pub mod radio;
+pub mod labels;
+pub mod region;
+pub mod features;
+pub mod pathfinding;
+pub mod civilization;
+pub mod stitch;
+pub mod roads;
+pub mod scatter;
+pub mod soak;
+pub mod nations;
+pub mod guard;
+pub mod guid;
+pub mod naming;
+pub mod simulation;
+pub mod space;
+pub mod star_system;
+pub mod small_bodies;
+pub mod orbits;
+pub mod coords;
+pub mod octree;
+pub mod nbody;
+pub mod observer;
+pub mod snapshot;
+pub mod unreal;
+pub mod mesh;
+pub mod recipe;
+pub mod planet_file;
+pub mod geojson;
+pub mod relief;
+pub mod precision;
+pub mod sampling;
+pub mod cell_graph;
+pub mod geom;
+pub mod topology;
+pub mod habitability;
+pub mod surfaces;
+pub mod tides;
+pub mod geologic_events;
+pub mod hydrology;
+pub mod geologic_time;
+pub mod ecology;
+pub mod hazards;
+pub mod deltas;
+pub mod terrain_features;
+pub mod layers;
+pub mod patch;
+pub mod heights;
+pub mod distance_fields;
+pub mod symmetry;
+pub mod explore;
+pub mod statistics;
+#[cfg(feature = "terraforge-ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+#[cfg(feature = "tile-server")]
+pub mod tile_server;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+pub mod procedural;
+pub mod generator;
 
+#[cfg(not(target_arch = "wasm32"))]
 use fibonacci_sphere::generate_fibonacci_sphere;
+#[cfg(not(target_arch = "wasm32"))]
 use delaunay_triangulation::{create_spherical_voronoi, print_voronoi_edges};
+#[cfg(not(target_arch = "wasm32"))]
+use geom::spherical::GeodesicSubdivision;
 
 /// The main function of the program.
 ///
@@ -13,14 +101,16 @@ use delaunay_triangulation::{create_spherical_voronoi, print_voronoi_edges};
 ///
 /// A `std::io::Result<()>`, which is `Ok(())` if all operations were successful, 
 /// or an `Err` containing the I/O error if there was a problem during execution.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn main() -> std::io::Result<()> {
     let num_samples = 1000; // Increase the number of points for better coverage
     let jitter = 0.1; // Adjust this value to control the randomness (0.0 to 1.0)
     let points = generate_fibonacci_sphere(num_samples, jitter)?;
     
-    let triangulation = create_spherical_voronoi(points);
+    let triangulation = create_spherical_voronoi(points).map_err(std::io::Error::other)?;
 
-    print_voronoi_edges(&triangulation)?;
+    // Edges hug the sphere to within a thousandth of its radius
+    print_voronoi_edges(&triangulation, GeodesicSubdivision::MaxChordError(1e-3))?;
 
     println!("Voronoi edges have been written to voronoi_edges.txt");
 
@@ -0,0 +1,165 @@
+use crate::planet::Planet;
+use crate::raster::lat_lon_to_unit;
+use crate::vector::{add, cross, dot, length, normalize, scale, sub, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+/// A local magnetic anomaly, modelled as a small radial dipole buried below the surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagneticAnomaly {
+    pub latitude: f64,  // Degrees
+    pub longitude: f64, // Degrees
+    pub depth: f64,     // Depth below the surface as a fraction of the planet radius
+    pub strength: f64,  // Dipole moment relative to the planetary dipole; negative flips polarity
+}
+
+/// A planetary magnetic field: a tilted, slowly drifting dipole plus local anomalies.
+///
+/// Fields are dimensionless (the planetary dipole has unit moment); only directions matter for
+/// declination and inclination.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagneticField {
+    dipole_tilt: f64,      // Angle between the dipole and rotation axes in degrees
+    dipole_longitude: f64, // Longitude of the north magnetic pole at time zero in degrees
+    drift_rate: f64,       // Westward drift of the magnetic pole in degrees per year
+    anomalies: Vec<MagneticAnomaly>,
+}
+
+/// The compass readings at a location.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompassReading {
+    /// Angle from geographic north to magnetic north in degrees, positive eastward.
+    pub declination: f64,
+    /// Dip of the field below the horizontal in degrees, positive downward.
+    pub inclination: f64,
+    /// Field strength relative to the dipole strength at the magnetic equator.
+    pub intensity: f64,
+}
+
+// Field of a point dipole with moment `moment` located at `source`, evaluated at `point`
+fn dipole_field(point: Vec3, source: Vec3, moment: Vec3) -> Vec3 {
+    let offset = sub(point, source);
+    let distance = length(offset);
+    if distance < 1e-9 {
+        return (0.0, 0.0, 0.0);
+    }
+    let u = scale(offset, 1.0 / distance);
+    scale(sub(scale(u, 3.0 * dot(moment, u)), moment), 1.0 / distance.powi(3))
+}
+
+impl MagneticField {
+    /// Generates a field deterministically from a seed.
+    ///
+    /// The dipole is tilted 3–15° from the rotation axis and drifts westward at up to 0.3° per
+    /// year; between 6 and 16 crustal anomalies are scattered over the surface.
+    pub fn generate(seed: u64) -> MagneticField {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let dipole_tilt = rng.gen_range(3.0..15.0);
+        let dipole_longitude = rng.gen_range(-180.0..180.0);
+        let drift_rate = rng.gen_range(0.0..0.3);
+        let count = rng.gen_range(6..16);
+        let anomalies = (0..count)
+            .map(|_| MagneticAnomaly {
+                latitude: rng.gen_range(-1.0f64..1.0).asin().to_degrees(),
+                longitude: rng.gen_range(-180.0..180.0),
+                depth: rng.gen_range(0.005..0.03),
+                strength: rng.gen_range(-1.0..1.0) * 2e-6,
+            })
+            .collect();
+
+        MagneticField {
+            dipole_tilt,
+            dipole_longitude,
+            drift_rate,
+            anomalies,
+        }
+    }
+
+    /// Creates a field with an explicit dipole and no anomalies.
+    pub fn dipole(tilt_degrees: f64, pole_longitude: f64, drift_degrees_per_year: f64) -> MagneticField {
+        MagneticField {
+            dipole_tilt: tilt_degrees,
+            dipole_longitude: pole_longitude,
+            drift_rate: drift_degrees_per_year,
+            anomalies: Vec::new(),
+        }
+    }
+
+    /// Adds an anomaly, e.g. above an ore body.
+    pub fn add_anomaly(&mut self, anomaly: MagneticAnomaly) {
+        self.anomalies.push(anomaly);
+    }
+
+    /// Returns the crustal anomalies.
+    pub fn anomalies(&self) -> &[MagneticAnomaly] {
+        &self.anomalies
+    }
+
+    /// Returns the location of the north magnetic pole at `time_years` as `(latitude, longitude)`.
+    pub fn north_pole(&self, time_years: f64) -> (f64, f64) {
+        let longitude = (self.dipole_longitude - self.drift_rate * time_years + 180.0).rem_euclid(360.0) - 180.0;
+        (90.0 - self.dipole_tilt, longitude)
+    }
+
+    /// Returns the field vector at a point on the surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - A unit vector on the planet surface (z is the rotation axis).
+    /// * `time_years` - Time since generation in years; the dipole drifts over time.
+    pub fn field_at(&self, point: Vec3, time_years: f64) -> Vec3 {
+        let (pole_lat, pole_lon) = self.north_pole(time_years);
+        // Field lines enter the planet near the north magnetic pole, so the moment points south
+        let moment = scale(lat_lon_to_unit(pole_lat, pole_lon), -1.0);
+        let mut field = dipole_field(point, (0.0, 0.0, 0.0), moment);
+
+        for anomaly in &self.anomalies {
+            let direction = lat_lon_to_unit(anomaly.latitude, anomaly.longitude);
+            let source = scale(direction, 1.0 - anomaly.depth);
+            field = add(field, dipole_field(point, source, scale(direction, -anomaly.strength)));
+        }
+        field
+    }
+
+    /// Returns what a compass and dip needle read at a point on the surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - A unit vector on the planet surface (z is the rotation axis).
+    /// * `time_years` - Time since generation in years.
+    pub fn reading_at(&self, point: Vec3, time_years: f64) -> CompassReading {
+        let up = normalize(point);
+        let east = if up.0.abs() < 1e-9 && up.1.abs() < 1e-9 {
+            (0.0, 1.0, 0.0)
+        } else {
+            normalize(cross((0.0, 0.0, 1.0), up))
+        };
+        let north = cross(up, east);
+
+        let field = self.field_at(up, time_years);
+        let (bn, be, bu) = (dot(field, north), dot(field, east), dot(field, up));
+        let horizontal = (bn * bn + be * be).sqrt();
+
+        CompassReading {
+            declination: be.atan2(bn).to_degrees(),
+            inclination: (-bu).atan2(horizontal).to_degrees(),
+            intensity: length(field),
+        }
+    }
+}
+
+impl Planet {
+    /// Returns the compass reading at every cell site.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The planet's magnetic field.
+    /// * `time_years` - Time since generation in years.
+    pub fn compass_readings(&self, field: &MagneticField, time_years: f64) -> Vec<CompassReading> {
+        self.sites()
+            .par_iter()
+            .map(|&site| field.reading_at(site, time_years))
+            .collect()
+    }
+}
@@ -0,0 +1,438 @@
+use crate::coords::CoordinateFrame;
+use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+use crate::octree::Octree;
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+use crate::projection::{cube_atlas_uv, cube_face, cube_face_offset};
+use crate::render::elevation_color;
+use crate::vector::{add, cross, length, normalize, scale, sub, Vec3};
+use serde_json::json;
+use std::collections::HashMap;
+
+// Fewest cells a reduced level of detail is built with
+const MIN_LOD_SAMPLES: usize = 64;
+
+/// Vertex and index buffers of a planet surface, laid out the way game engines take them.
+///
+/// Each field maps directly onto an engine mesh attribute, e.g. Bevy's `Mesh::ATTRIBUTE_POSITION`,
+/// `ATTRIBUTE_NORMAL`, `ATTRIBUTE_COLOR` and `Indices::U32`. Positions and normals are in the
+/// `CoordinateFrame` the buffers were built for, and triangles use its winding.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>, // Unit length, averaged over the faces around each vertex
+    pub colors: Vec<[f32; 4]>,  // Linear RGBA hypsometric tint
+    pub indices: Vec<u32>,      // Three per triangle
+    pub uvs: Vec<[f32; 2]>,     // `Projection::CubeAtlas` texture coordinates; empty unless built by `Planet::atlas_mesh`
+}
+
+impl MeshBuffers {
+    /// Returns the number of vertices.
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+// Converts an sRGB channel to linear light
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Surface position of a cell in kilometres, displaced by its elevation
+fn surface_point(planet: &Planet, cell: usize, exaggeration: f64) -> Vec3 {
+    scale(planet.sites()[cell], planet.radius() + planet.elevation()[cell] / 1000.0 * exaggeration)
+}
+
+// Outward normal of every cell of the displaced surface, in TerraForge's frame
+fn vertex_normals(planet: &Planet, exaggeration: f64) -> Vec<Vec3> {
+    let points: Vec<Vec3> = (0..planet.cell_count()).map(|cell| surface_point(planet, cell, exaggeration)).collect();
+    let mut normals = vec![(0.0, 0.0, 0.0); points.len()];
+    for &[a, b, c] in planet.triangles() {
+        // Area-weighted, since the cross product's length is twice the face area
+        let face = cross(sub(points[b], points[a]), sub(points[c], points[a]));
+        for vertex in [a, b, c] {
+            normals[vertex] = add(normals[vertex], face);
+        }
+    }
+    normals
+        .into_iter()
+        .zip(planet.sites())
+        .map(|(normal, &site)| if length(normal) > 0.0 { normalize(normal) } else { site })
+        .collect()
+}
+
+// The cube atlas face a triangle is textured from: of the faces its corners lie on, the one that
+// keeps the corners least far out into the gutter
+fn triangle_face(planet: &Planet, triangle: [usize; 3]) -> usize {
+    let corners = triangle.map(|cell| planet.sites()[cell]);
+    let reach = |face: usize| corners.iter().map(|&corner| cube_face_offset(face, corner)).fold(0.0, f64::max);
+    corners.map(cube_face).into_iter().min_by(|&a, &b| reach(a).total_cmp(&reach(b))).unwrap_or(0)
+}
+
+// Builds buffers for a subset of a planet's triangles, keeping only the vertices they use; with
+// `atlas`, vertices get cube atlas UVs and are duplicated where triangles on different faces meet
+fn build_buffers(planet: &Planet, normals: &[Vec3], triangles: impl Iterator<Item = [usize; 3]>, frame: &CoordinateFrame, exaggeration: f64, atlas: bool) -> MeshBuffers {
+    let direction_frame = frame.with_scale(1.0);
+    let mut buffers = MeshBuffers::default();
+    let mut local: HashMap<(usize, usize), u32> = HashMap::new();
+    for triangle in triangles {
+        let face = if atlas { triangle_face(planet, triangle) } else { 0 };
+        for cell in frame.triangle(triangle) {
+            let index = *local.entry((cell, face)).or_insert_with(|| {
+                let (x, y, z) = frame.from_native(surface_point(planet, cell, exaggeration));
+                let (nx, ny, nz) = direction_frame.from_native(normals[cell]);
+                let [r, g, b] = elevation_color(planet.elevation()[cell]);
+                buffers.positions.push([x as f32, y as f32, z as f32]);
+                buffers.normals.push([nx as f32, ny as f32, nz as f32]);
+                buffers.colors.push([srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), 1.0]);
+                if atlas {
+                    let (u, v) = cube_atlas_uv(face, planet.sites()[cell]);
+                    buffers.uvs.push([u as f32, v as f32]);
+                }
+                (buffers.positions.len() - 1) as u32
+            });
+            buffers.indices.push(index);
+        }
+    }
+    buffers
+}
+
+impl Planet {
+    /// Builds engine-ready mesh buffers for the whole surface, one vertex per cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The target engine's axes, units and winding, e.g. `CoordinateFrame::BEVY`.
+    /// * `exaggeration` - Multiplier on elevation when displacing the surface; 1.0 is true scale.
+    pub fn mesh(&self, frame: &CoordinateFrame, exaggeration: f64) -> MeshBuffers {
+        let normals = vertex_normals(self, exaggeration);
+        build_buffers(self, &normals, self.triangles().iter().copied(), frame, exaggeration, false)
+    }
+
+    /// Builds mesh buffers like `mesh`, with UVs into a `Projection::CubeAtlas` texture.
+    ///
+    /// Each triangle is mapped whole onto one cube face, so nothing is stretched across the poles
+    /// or the antimeridian; cells where triangles on different faces meet get one vertex per face.
+    /// Any raster rendered in `Projection::CubeAtlas`, e.g. `render_map` or `Planet::normal_map`,
+    /// textures the mesh without seams. Meshes of fewer than about 5,000 cells have triangles too
+    /// large for the atlas gutters and are textured slightly distorted at the cube edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The target engine's axes, units and winding, e.g. `CoordinateFrame::BEVY`.
+    /// * `exaggeration` - Multiplier on elevation when displacing the surface; 1.0 is true scale.
+    pub fn atlas_mesh(&self, frame: &CoordinateFrame, exaggeration: f64) -> MeshBuffers {
+        let normals = vertex_normals(self, exaggeration);
+        build_buffers(self, &normals, self.triangles().iter().copied(), frame, exaggeration, true)
+    }
+}
+
+// One level of detail: a planet and its triangles grouped by chunk
+#[derive(Debug)]
+struct LodLevel {
+    planet: Planet,
+    normals: Vec<Vec3>,
+    chunks: Vec<Vec<usize>>, // Triangle indices per chunk
+}
+
+/// A planet split into chunks, each available at several levels of detail.
+///
+/// Level 0 has the full cell count of the parameters and every further level a quarter of the
+/// previous one. All levels are generated from the same seeds, so they show the same terrain at
+/// decreasing resolution. Chunks are fixed patches of the sphere shared by every level, so a
+/// renderer can swap one chunk's level without touching its neighbours.
+#[derive(Debug)]
+pub struct PlanetLod {
+    levels: Vec<LodLevel>,
+    centres: Vec<Vec3>, // Unit direction of each chunk's centre
+    radius: f64,        // Kilometres
+    exaggeration: f64,
+}
+
+impl PlanetLod {
+    /// Generates every level of detail of a planet.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - The planet, e.g. from `PlanetParameters::from_guid`.
+    /// * `levels` - How many levels to build, at least 1.
+    /// * `chunk_count` - How many chunks to split the sphere into, at least 1.
+    /// * `exaggeration` - Multiplier on elevation when displacing the surface.
+    pub fn new(parameters: &PlanetParameters, levels: usize, chunk_count: usize, exaggeration: f64) -> PlanetLod {
+        let centres = seeded_fibonacci_sphere(chunk_count.max(1), 0.0, 0);
+        let lookup = Octree::new(centres.iter().enumerate().map(|(chunk, &centre)| (centre, chunk)));
+
+        let levels = (0..levels.max(1))
+            .map(|level| {
+                let mut reduced = parameters.clone();
+                reduced.samples = (parameters.samples >> (2 * level)).max(MIN_LOD_SAMPLES);
+                let planet = reduced.build();
+                let mut chunks = vec![Vec::new(); centres.len()];
+                for (i, &[a, b, c]) in planet.triangles().iter().enumerate() {
+                    let sites = planet.sites();
+                    let centroid = normalize(add(add(sites[a], sites[b]), sites[c]));
+                    let ((_, chunk), _) = lookup.nearest(centroid).expect("at least one chunk");
+                    chunks[*chunk].push(i);
+                }
+                LodLevel {
+                    normals: vertex_normals(&planet, exaggeration),
+                    planet,
+                    chunks,
+                }
+            })
+            .collect();
+
+        PlanetLod {
+            levels,
+            centres,
+            radius: parameters.radius,
+            exaggeration,
+        }
+    }
+
+    /// Returns the number of levels of detail.
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Returns the number of chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.centres.len()
+    }
+
+    /// Returns the planet generated for a level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is out of range.
+    pub fn planet(&self, level: usize) -> &Planet {
+        &self.levels[level].planet
+    }
+
+    /// Returns the centre of a chunk on the sea-level surface, in kilometres in TerraForge's frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of range.
+    pub fn chunk_centre(&self, chunk: usize) -> Vec3 {
+        scale(self.centres[chunk], self.radius)
+    }
+
+    /// Builds the mesh of one chunk at one level of detail.
+    ///
+    /// Normals are computed over the whole level, so lighting matches across chunk borders.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` or `level` is out of range.
+    pub fn chunk_mesh(&self, chunk: usize, level: usize, frame: &CoordinateFrame) -> MeshBuffers {
+        let level = &self.levels[level];
+        let triangles = level.chunks[chunk].iter().map(|&i| level.planet.triangles()[i]);
+        build_buffers(&level.planet, &level.normals, triangles, frame, self.exaggeration, false)
+    }
+}
+
+/// A change of one chunk's level of detail reported by `LodStreamer::update`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LodChange {
+    pub chunk: usize,
+    pub from: Option<usize>, // `None` the first time the chunk is shown
+    pub to: usize,
+}
+
+/// Picks a level of detail for every chunk of a `PlanetLod` from the camera distance.
+///
+/// A chunk uses level `i` when the camera is farther from its centre than `i` of the switch
+/// distances, capped at the coarsest level. Engine integrations call `update` each frame and
+/// rebuild only the chunks it reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodStreamer {
+    switch_distances: Vec<f64>, // Kilometres, ascending
+    current: Vec<Option<usize>>,
+}
+
+impl LodStreamer {
+    /// Creates a streamer with nothing shown yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `switch_distances` - Camera distances in kilometres at which chunks drop to the next
+    ///   coarser level. They are sorted.
+    pub fn new(mut switch_distances: Vec<f64>) -> LodStreamer {
+        switch_distances.sort_by(f64::total_cmp);
+        LodStreamer {
+            switch_distances,
+            current: Vec::new(),
+        }
+    }
+
+    /// Returns the level each chunk currently shows, or `None` before the first update.
+    pub fn levels(&self) -> &[Option<usize>] {
+        &self.current
+    }
+
+    /// Updates the levels for a camera position.
+    ///
+    /// # Arguments
+    ///
+    /// * `lod` - The chunked planet.
+    /// * `camera` - The camera position in kilometres from the planet centre, in TerraForge's
+    ///   frame; convert engine positions with `CoordinateFrame::to_native`.
+    ///
+    /// # Returns
+    ///
+    /// The chunks whose level changed, in chunk order.
+    pub fn update(&mut self, lod: &PlanetLod, camera: Vec3) -> Vec<LodChange> {
+        self.current.resize(lod.chunk_count(), None);
+        let coarsest = lod.level_count() - 1;
+        let mut changes = Vec::new();
+        for chunk in 0..lod.chunk_count() {
+            let distance = length(sub(camera, lod.chunk_centre(chunk)));
+            let level = self.switch_distances.partition_point(|&d| d < distance).min(coarsest);
+            if self.current[chunk] != Some(level) {
+                changes.push(LodChange {
+                    chunk,
+                    from: self.current[chunk],
+                    to: level,
+                });
+                self.current[chunk] = Some(level);
+            }
+        }
+        changes
+    }
+}
+
+// Standard base64 with padding, for data URIs
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+impl MeshBuffers {
+    /// Encodes the buffers as a self-contained glTF 2.0 document with one mesh.
+    ///
+    /// The binary data is embedded as a base64 data URI, so the result is a single `.gltf` file.
+    /// glTF is Y-up, right-handed, counter-clockwise and in metres, so build the buffers with
+    /// `CoordinateFrame::BEVY` for a correctly oriented model. UVs, if any, become `TEXCOORD_0`.
+    pub fn to_gltf(&self) -> String {
+        self.gltf(None)
+    }
+
+    /// Encodes the buffers as a glTF document like `to_gltf`, textured with an embedded PNG.
+    ///
+    /// The texture is the base color of the mesh's material and replaces the vertex colors, which
+    /// are left out. Build the buffers with `Planet::atlas_mesh` and render the texture in
+    /// `Projection::CubeAtlas`.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture_png` - The encoded texture, e.g. from `Image::encode_png`.
+    pub fn to_gltf_textured(&self, texture_png: &[u8]) -> String {
+        self.gltf(Some(texture_png))
+    }
+
+    fn gltf(&self, texture_png: Option<&[u8]>) -> String {
+        let colors = texture_png.is_none();
+        let mut data = Vec::with_capacity(self.positions.len() * 48 + self.indices.len() * 4);
+        for values in [&self.positions, &self.normals] {
+            data.extend(values.iter().flatten().flat_map(|v| v.to_le_bytes()));
+        }
+        if colors {
+            data.extend(self.colors.iter().flatten().flat_map(|v| v.to_le_bytes()));
+        }
+        data.extend(self.uvs.iter().flatten().flat_map(|v| v.to_le_bytes()));
+        data.extend(self.indices.iter().flat_map(|i| i.to_le_bytes()));
+
+        let vertices = self.positions.len();
+        let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        if vertices == 0 {
+            (min, max) = ([0.0; 3], [0.0; 3]);
+        }
+
+        // Accessors in the order the data was written: positions, normals, colors, UVs, indices
+        let mut attributes = serde_json::Map::new();
+        let mut accessors = vec![
+            json!({ "bufferView": 0, "componentType": 5126, "count": vertices, "type": "VEC3", "min": min, "max": max }),
+            json!({ "bufferView": 1, "componentType": 5126, "count": vertices, "type": "VEC3" }),
+        ];
+        let mut lengths = vec![vertices * 12, vertices * 12];
+        attributes.insert("POSITION".to_string(), json!(0));
+        attributes.insert("NORMAL".to_string(), json!(1));
+        if colors {
+            attributes.insert("COLOR_0".to_string(), json!(accessors.len()));
+            accessors.push(json!({ "bufferView": accessors.len(), "componentType": 5126, "count": vertices, "type": "VEC4" }));
+            lengths.push(vertices * 16);
+        }
+        if !self.uvs.is_empty() {
+            attributes.insert("TEXCOORD_0".to_string(), json!(accessors.len()));
+            accessors.push(json!({ "bufferView": accessors.len(), "componentType": 5126, "count": vertices, "type": "VEC2" }));
+            lengths.push(vertices * 8);
+        }
+        let indices = accessors.len();
+        accessors.push(json!({ "bufferView": indices, "componentType": 5125, "count": self.indices.len(), "type": "SCALAR" }));
+        lengths.push(self.indices.len() * 4);
+
+        let mut offset = 0;
+        let views: Vec<serde_json::Value> = lengths
+            .iter()
+            .enumerate()
+            .map(|(i, &length)| {
+                let target = if i == indices { 34963 } else { 34962 }; // ELEMENT_ARRAY_BUFFER, ARRAY_BUFFER
+                let view = json!({ "buffer": 0, "byteOffset": offset, "byteLength": length, "target": target });
+                offset += length;
+                view
+            })
+            .collect();
+
+        let mut primitive = json!({ "attributes": attributes, "indices": indices, "mode": 4 });
+        let mut document = json!({
+            "asset": { "version": "2.0", "generator": "TerraForge" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "accessors": accessors,
+            "bufferViews": views,
+            "buffers": [{
+                "byteLength": data.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", base64(&data)),
+            }],
+        });
+        if let Some(png) = texture_png {
+            primitive["material"] = json!(0);
+            document["materials"] = json!([{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 }, "metallicFactor": 0.0 } }]);
+            document["textures"] = json!([{ "source": 0, "sampler": 0 }]);
+            // CLAMP_TO_EDGE, so tiles at the atlas border don't bleed into the opposite side
+            document["samplers"] = json!([{ "magFilter": 9729, "minFilter": 9987, "wrapS": 33071, "wrapT": 33071 }]);
+            document["images"] = json!([{ "uri": format!("data:image/png;base64,{}", base64(png)) }]);
+        }
+        document["meshes"] = json!([{ "primitives": [primitive] }]);
+        document.to_string()
+    }
+}
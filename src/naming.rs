@@ -0,0 +1,267 @@
+use crate::noise::hash_values;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Produces base names from a seed. Implement this to plug in a new culture.
+pub trait NameGenerator: Send + Sync {
+    /// Returns a capitalized base name; the same seed must always give the same name.
+    fn generate(&self, seed: u64) -> String;
+}
+
+/// A culture's sounds: names are built from syllables of onset, nucleus and optional coda.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhonemeSet {
+    pub onsets: Vec<String>,
+    pub nuclei: Vec<String>,
+    pub codas: Vec<String>,
+    pub coda_chance: f64,          // Probability that a syllable ends in a coda
+    pub syllables: (usize, usize), // Inclusive range of syllables per name
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for PhonemeSet {
+    /// A neutral, easily pronounced set.
+    fn default() -> Self {
+        PhonemeSet {
+            onsets: strings(&["b", "d", "f", "g", "k", "l", "m", "n", "p", "r", "s", "t", "v", "z", "th", "br", "st"]),
+            nuclei: strings(&["a", "e", "i", "o", "u", "ai", "ea", "ou"]),
+            codas: strings(&["n", "r", "s", "l", "th", "nd", "m"]),
+            coda_chance: 0.35,
+            syllables: (2, 3),
+        }
+    }
+}
+
+impl PhonemeSet {
+    /// Harsh consonant clusters and long vowels.
+    pub fn northern() -> PhonemeSet {
+        PhonemeSet {
+            onsets: strings(&["h", "k", "sk", "st", "th", "v", "r", "g", "bj", "fr", "dr", "sv"]),
+            nuclei: strings(&["a", "e", "i", "o", "u", "y", "aa", "ei", "ö"]),
+            codas: strings(&["rd", "k", "ld", "rn", "nd", "st", "r", "m"]),
+            coda_chance: 0.6,
+            syllables: (1, 3),
+        }
+    }
+
+    /// Soft consonants and open syllables.
+    pub fn sylvan() -> PhonemeSet {
+        PhonemeSet {
+            onsets: strings(&["l", "m", "n", "s", "th", "f", "v", "el", "ar", "gl"]),
+            nuclei: strings(&["a", "e", "i", "ia", "ae", "ie", "o"]),
+            codas: strings(&["l", "n", "s", "th", "r"]),
+            coda_chance: 0.25,
+            syllables: (2, 4),
+        }
+    }
+
+    /// Guttural and sibilant sounds with doubled vowels.
+    pub fn desert() -> PhonemeSet {
+        PhonemeSet {
+            onsets: strings(&["q", "kh", "z", "sh", "j", "r", "d", "m", "s", "h", "b"]),
+            nuclei: strings(&["a", "aa", "i", "ii", "u", "uu", "e"]),
+            codas: strings(&["r", "m", "d", "sh", "l", "n", "q"]),
+            coda_chance: 0.5,
+            syllables: (2, 3),
+        }
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn pick<'a>(rng: &mut StdRng, items: &'a [String]) -> &'a str {
+    if items.is_empty() {
+        ""
+    } else {
+        &items[rng.gen_range(0..items.len())]
+    }
+}
+
+impl NameGenerator for PhonemeSet {
+    fn generate(&self, seed: u64) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (low, high) = (self.syllables.0.max(1), self.syllables.1.max(self.syllables.0.max(1)));
+        let count = rng.gen_range(low..=high);
+        let mut name = String::new();
+        for _ in 0..count {
+            name.push_str(pick(&mut rng, &self.onsets));
+            name.push_str(pick(&mut rng, &self.nuclei));
+            if rng.gen::<f64>() < self.coda_chance {
+                name.push_str(pick(&mut rng, &self.codas));
+            }
+        }
+        capitalize(&name)
+    }
+}
+
+/// A character-level Markov chain trained on example names, for cultures defined by samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkovNames {
+    order: usize,
+    transitions: BTreeMap<String, Vec<char>>, // Context -> observed next characters ('\0' ends a name)
+    length: (usize, usize),                   // Shortest and longest training name in characters
+    samples: BTreeSet<String>,                // Training names, which are never returned verbatim
+}
+
+impl MarkovNames {
+    /// Trains a chain on example names.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Example names of the culture.
+    /// * `order` - The number of preceding characters each choice depends on (2 or 3 works well).
+    pub fn train(samples: &[&str], order: usize) -> MarkovNames {
+        let order = order.max(1);
+        let mut transitions: BTreeMap<String, Vec<char>> = BTreeMap::new();
+        let mut length = (usize::MAX, 0);
+        for sample in samples {
+            let lower = sample.to_lowercase();
+            let chars: Vec<char> = std::iter::repeat_n('^', order).chain(lower.chars()).chain(std::iter::once('\0')).collect();
+            length = (length.0.min(lower.chars().count()), length.1.max(lower.chars().count()));
+            for window in chars.windows(order + 1) {
+                let context: String = window[..order].iter().collect();
+                transitions.entry(context).or_default().push(window[order]);
+            }
+        }
+        if length.0 == usize::MAX {
+            length = (0, 0);
+        }
+        let samples = samples.iter().map(|s| s.to_lowercase()).collect();
+        MarkovNames {
+            order,
+            transitions,
+            length,
+            samples,
+        }
+    }
+}
+
+impl NameGenerator for MarkovNames {
+    fn generate(&self, seed: u64) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let max = self.length.1.max(3) + 2;
+        // A few attempts to land within the trained length range on a new name
+        for _ in 0..16 {
+            let mut context: Vec<char> = vec!['^'; self.order];
+            let mut name = String::new();
+            loop {
+                let key: String = context.iter().collect();
+                let Some(options) = self.transitions.get(&key) else { break };
+                let next = options[rng.gen_range(0..options.len())];
+                if next == '\0' || name.chars().count() >= max {
+                    break;
+                }
+                name.push(next);
+                context.remove(0);
+                context.push(next);
+            }
+            if name.chars().count() >= self.length.0.max(3) && !self.samples.contains(&name) {
+                return capitalize(&name);
+            }
+        }
+        capitalize(&PhonemeSet::default().generate(seed))
+    }
+}
+
+/// The kind of thing being named, which decides the name's form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedFeature {
+    Planet,
+    Continent,
+    Ocean,
+    MountainRange,
+    River,
+    Settlement,
+}
+
+impl NamedFeature {
+    fn code(&self) -> i64 {
+        *self as i64
+    }
+}
+
+/// Names features for one culture, deterministically from a seed.
+pub struct Namer {
+    generator: Box<dyn NameGenerator>,
+    seed: u64,
+}
+
+impl Namer {
+    /// Creates a namer for a culture.
+    ///
+    /// # Arguments
+    ///
+    /// * `generator` - The culture's base-name generator, e.g. a `PhonemeSet` or `MarkovNames`.
+    /// * `seed` - The naming seed; the same seed and feature always give the same name.
+    pub fn new(generator: impl NameGenerator + 'static, seed: u64) -> Namer {
+        Namer {
+            generator: Box::new(generator),
+            seed,
+        }
+    }
+
+    /// Returns the bare generated name for a feature, without any descriptive form.
+    pub fn base_name(&self, kind: NamedFeature, id: u64) -> String {
+        self.generator.generate(hash_values(self.seed, &[kind.code(), id as i64]))
+    }
+
+    /// Returns the full name of a feature, such as "Tarnos Ocean" or "Mount Velar".
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - What is being named.
+    /// * `id` - Identifies the feature among others of its kind, e.g. a cell or settlement index.
+    pub fn name(&self, kind: NamedFeature, id: u64) -> String {
+        let base = self.base_name(kind, id);
+        let form = hash_values(self.seed ^ 0x4E41_4D45, &[kind.code(), id as i64]) % 3;
+        match kind {
+            NamedFeature::Planet | NamedFeature::Settlement => base,
+            NamedFeature::Continent => match form {
+                0 => format!("{}ia", base.trim_end_matches(['a', 'e', 'i', 'o', 'u'])),
+                _ => base,
+            },
+            NamedFeature::Ocean => match form {
+                0 => format!("Sea of {}", base),
+                _ => format!("{} Ocean", base),
+            },
+            NamedFeature::MountainRange => match form {
+                0 => format!("{} Mountains", base),
+                1 => format!("{} Range", base),
+                _ => format!("Mount {}", base),
+            },
+            NamedFeature::River => match form {
+                0 => format!("River {}", base),
+                _ => format!("{} River", base),
+            },
+        }
+    }
+
+    /// Returns distinct names for `count` features of one kind, with ids `0..count`.
+    ///
+    /// Collisions are resolved by drawing further names, so the result is still deterministic.
+    pub fn unique_names(&self, kind: NamedFeature, count: usize) -> Vec<String> {
+        let mut used = HashSet::new();
+        (0..count as u64)
+            .map(|id| {
+                let mut attempt = 0u64;
+                loop {
+                    let name = self.name(kind, id + attempt * count as u64);
+                    if used.insert(name.clone()) || attempt > 64 {
+                        return name;
+                    }
+                    attempt += 1;
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,256 @@
+use crate::cell_graph::circumcentre;
+use crate::civilization::Settlement;
+use crate::planet::Planet;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+
+/// Settings for nation growth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NationSettings {
+    pub count: usize,       // Number of nations, seeded from the largest settlements
+    pub max_extent_km: f64, // Travel cost (in effective km) beyond which land stays unclaimed
+    pub mountain_cost: f64, // Extra cost per km of travel per 1000 m climbed or above the highland line
+    pub river_cost: f64,    // Extra cost in km for crossing into or out of a river cell
+}
+
+impl Default for NationSettings {
+    /// Twelve nations of up to 2500 km reach, with mountains and rivers acting as borders.
+    fn default() -> Self {
+        NationSettings {
+            count: 12,
+            max_extent_km: 2500.0,
+            mountain_cost: 4.0,
+            river_cost: 400.0,
+        }
+    }
+}
+
+// The owners on either side of a border, smaller first
+type NationPair = (Option<usize>, Option<usize>);
+
+/// One stretch of border between two nations, or a nation and unclaimed land.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NationBorder {
+    pub nations: NationPair,
+    pub points: Vec<(f64, f64)>, // Polyline of `(latitude, longitude)` in degrees along the Voronoi cell edges
+}
+
+/// The nation owning each cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NationMap {
+    capitals: Vec<usize>,          // Capital cell of each nation
+    nation_of: Vec<Option<usize>>, // Owning nation of each cell
+}
+
+// Elevation in metres above which terrain counts as mountainous
+const HIGHLAND: f64 = 2500.0;
+
+#[derive(PartialEq)]
+struct Claim(f64, usize, usize);
+
+impl Eq for Claim {}
+
+impl Ord for Claim {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Claim {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Planet {
+    /// Grows nations outward from their capitals over the cell graph.
+    ///
+    /// The largest settlements become capitals. Each nation claims land in order of travel cost,
+    /// so borders settle where two nations' costs meet. Mountains make travel expensive and
+    /// river crossings add a fixed cost, so borders tend to follow ridges and rivers. Populous
+    /// nations project further. Oceans are never claimed.
+    ///
+    /// # Arguments
+    ///
+    /// * `settlements` - Candidate capitals, e.g. from `Planet::place_settlements`.
+    /// * `rivers` - Cells carrying a river; pass an empty slice if none have been generated.
+    /// * `settings` - The number of nations and the terrain costs.
+    pub fn grow_nations(&self, settlements: &[Settlement], rivers: &[usize], settings: &NationSettings) -> NationMap {
+        let mut ranked: Vec<&Settlement> = settlements.iter().filter(|s| !self.is_ocean(s.cell)).collect();
+        ranked.sort_by(|a, b| b.population.cmp(&a.population).then(a.cell.cmp(&b.cell)));
+        let capitals: Vec<usize> = ranked.iter().take(settings.count).map(|s| s.cell).collect();
+        let reach: Vec<f64> = ranked
+            .iter()
+            .take(settings.count)
+            .map(|s| (s.population.max(1) as f64).log10().max(1.0) / 6.0)
+            .collect();
+
+        let river: HashSet<usize> = rivers.iter().copied().collect();
+        let elevation = self.elevation();
+        let mut cost = vec![f64::INFINITY; self.cell_count()];
+        let mut nation_of = vec![None; self.cell_count()];
+        let mut heap = BinaryHeap::new();
+        for (nation, &cell) in capitals.iter().enumerate() {
+            cost[cell] = 0.0;
+            nation_of[cell] = Some(nation);
+            heap.push(Claim(0.0, cell, nation));
+        }
+        while let Some(Claim(c, cell, nation)) = heap.pop() {
+            if c > cost[cell] || nation_of[cell] != Some(nation) {
+                continue;
+            }
+            for &neighbor in self.neighbors(cell) {
+                if self.is_ocean(neighbor) {
+                    continue;
+                }
+                let climb = (elevation[neighbor] - elevation[cell]).abs() / 1000.0;
+                let highland = ((elevation[neighbor] - HIGHLAND) / 1000.0).max(0.0);
+                let mut step = self.distance_km(cell, neighbor) * (1.0 + settings.mountain_cost * (climb + highland));
+                if river.contains(&cell) != river.contains(&neighbor) {
+                    step += settings.river_cost;
+                }
+                let next = c + step / reach[nation];
+                if next < cost[neighbor] && next <= settings.max_extent_km {
+                    cost[neighbor] = next;
+                    nation_of[neighbor] = Some(nation);
+                    heap.push(Claim(next, neighbor, nation));
+                }
+            }
+        }
+        NationMap { capitals, nation_of }
+    }
+}
+
+impl NationMap {
+    /// Returns the number of nations.
+    pub fn len(&self) -> usize {
+        self.capitals.len()
+    }
+
+    /// Returns `true` if there are no nations.
+    pub fn is_empty(&self) -> bool {
+        self.capitals.is_empty()
+    }
+
+    /// Returns the capital cell of every nation.
+    pub fn capitals(&self) -> &[usize] {
+        &self.capitals
+    }
+
+    /// Returns the nation owning each cell, `None` for oceans and unclaimed land.
+    pub fn nation_of(&self) -> &[Option<usize>] {
+        &self.nation_of
+    }
+
+    /// Returns the nation IDs as a per-cell layer, with `-1.0` for unowned cells.
+    pub fn id_layer(&self) -> Vec<f64> {
+        self.nation_of.iter().map(|n| n.map_or(-1.0, |n| n as f64)).collect()
+    }
+
+    /// Returns the number of cells each nation owns.
+    pub fn cell_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.capitals.len()];
+        for nation in self.nation_of.iter().flatten() {
+            counts[*nation] += 1;
+        }
+        counts
+    }
+
+    /// Traces the borders between nations (and between nations and unowned cells) as polylines.
+    ///
+    /// Borders run along the Voronoi edges between cells, joined into continuous polylines per
+    /// pair of neighbours. Coastlines of a nation appear as borders with `None`.
+    pub fn borders(&self, planet: &Planet) -> Vec<NationBorder> {
+        trace_boundaries(planet, |a, b| {
+            let (owner_a, owner_b) = (self.nation_of[a], self.nation_of[b]);
+            (owner_a != owner_b).then(|| (owner_a.min(owner_b), owner_a.max(owner_b)))
+        })
+        .into_iter()
+        .map(|(nations, points)| NationBorder { nations, points })
+        .collect()
+    }
+}
+
+/// Traces the Voronoi edges between differing neighbouring cells as polylines.
+///
+/// # Arguments
+///
+/// * `planet` - The planet whose cells are traced.
+/// * `key` - Called with the two cells of each Delaunay edge (smaller index first); `Some` marks
+///   the edge between them as a boundary, and edges with equal keys are joined into polylines.
+///
+/// # Returns
+///
+/// Each polyline with its key, as `(latitude, longitude)` points in degrees. Closed boundaries
+/// such as island coasts start and end at the same point.
+pub(crate) fn trace_boundaries<K: Ord + Clone>(planet: &Planet, key: impl Fn(usize, usize) -> Option<K>) -> Vec<(K, Vec<(f64, f64)>)> {
+    let sites = planet.sites();
+    let triangles = planet.triangles();
+
+    // The two triangles on either side of every Delaunay edge
+    let mut edge_triangles: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (index, triangle) in triangles.iter().enumerate() {
+        for k in 0..3 {
+            let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+            edge_triangles.entry((a.min(b), a.max(b))).or_default().push(index);
+        }
+    }
+
+    // Boundary segments between Voronoi vertices, grouped by key
+    let mut segments: BTreeMap<K, Vec<(usize, usize)>> = BTreeMap::new();
+    for (&(a, b), sides) in &edge_triangles {
+        if sides.len() != 2 {
+            continue;
+        }
+        if let Some(key) = key(a, b) {
+            segments.entry(key).or_default().push((sides[0], sides[1]));
+        }
+    }
+
+    let mut boundaries = Vec::new();
+    for (key, mut pending) in segments {
+        pending.sort_unstable();
+        let mut by_vertex: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, &(u, v)) in pending.iter().enumerate() {
+            by_vertex.entry(u).or_default().push(index);
+            by_vertex.entry(v).or_default().push(index);
+        }
+        let mut used = vec![false; pending.len()];
+        // Start chains at loose ends first so open borders come out as single polylines
+        let mut starts: Vec<usize> = (0..pending.len()).collect();
+        starts.sort_by_key(|&i| {
+            let (u, v) = pending[i];
+            (by_vertex[&u].len() == 2 && by_vertex[&v].len() == 2, i)
+        });
+        for start in starts {
+            if used[start] {
+                continue;
+            }
+            used[start] = true;
+            let (u, v) = pending[start];
+            let (mut chain_start, mut chain) = if by_vertex[&u].len() == 2 { (v, vec![v, u]) } else { (u, vec![u, v]) };
+            // Extend from the end, then from the start
+            for _ in 0..2 {
+                loop {
+                    let tip = *chain.last().unwrap_or(&chain_start);
+                    let next = by_vertex[&tip].iter().copied().find(|&i| !used[i]);
+                    let Some(next) = next else { break };
+                    used[next] = true;
+                    let (p, q) = pending[next];
+                    chain.push(if p == tip { q } else { p });
+                }
+                chain.reverse();
+                chain_start = chain[0];
+            }
+            let points = chain
+                .iter()
+                .map(|&t| {
+                    let c = circumcentre(sites, &triangles[t]);
+                    (c.2.clamp(-1.0, 1.0).asin().to_degrees(), c.1.atan2(c.0).to_degrees())
+                })
+                .collect();
+            boundaries.push((key.clone(), points));
+        }
+    }
+    boundaries
+}
@@ -0,0 +1,278 @@
+use crate::octree::Octree;
+use crate::orbits::BodyId;
+use crate::star_system::{StarSystem, EARTH_MASSES_PER_SOLAR_MASS};
+use crate::vector::{add, length, scale, sub, Vec3};
+use rayon::prelude::*;
+use uuid::Uuid;
+
+/// The gravitational constant in AU³ / (solar mass · day²), the square of Gauss's constant.
+pub const GRAVITATIONAL_CONSTANT: f64 = 2.959_122_082_855_911e-4;
+
+// Step used to estimate orbital velocities from positions, in days
+const VELOCITY_STEP: f64 = 1e-4;
+
+/// A body moving under the gravity of the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GravityBody {
+    pub guid: Uuid,
+    pub mass: f64,      // Solar masses
+    pub position: Vec3, // AU
+    pub velocity: Vec3, // AU per day
+}
+
+/// How a `GravitySimulation` computes accelerations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GravitySolver {
+    /// Sums every pair exactly, O(n²). Best for planetary systems and small clusters.
+    Direct,
+    /// Approximates distant groups of bodies by their centre of mass, O(n log n).
+    ///
+    /// `theta` is the opening angle: a node of edge length `s` at distance `d` is treated as a
+    /// single mass when `s / d < theta`. 0.5 is a common choice; 0.0 is exact.
+    BarnesHut { theta: f64 },
+}
+
+/// Direct summation.
+impl Default for GravitySolver {
+    fn default() -> Self {
+        GravitySolver::Direct
+    }
+}
+
+/// An N-body gravity simulation integrated with velocity Verlet.
+///
+/// An alternative to fixed Keplerian ellipses for star clusters and planetary systems where
+/// bodies perturb each other. Distances are in astronomical units, times in Earth days and
+/// masses in solar masses, matching `OrbitTree`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GravitySimulation {
+    bodies: Vec<GravityBody>,
+    accelerations: Vec<Vec3>, // Accelerations at the current positions, AU per day²
+    solver: GravitySolver,
+    softening: f64,           // AU, keeps close encounters finite
+    time: f64,                // Days
+}
+
+impl GravitySimulation {
+    /// Creates a simulation with direct summation and no softening.
+    ///
+    /// # Arguments
+    ///
+    /// * `bodies` - The bodies and their initial states.
+    /// * `time` - The starting time in days.
+    pub fn new(bodies: Vec<GravityBody>, time: f64) -> GravitySimulation {
+        let mut simulation = GravitySimulation {
+            accelerations: Vec::new(),
+            bodies,
+            solver: GravitySolver::default(),
+            softening: 0.0,
+            time,
+        };
+        simulation.accelerations = simulation.compute_accelerations();
+        simulation
+    }
+
+    /// Returns the bodies in their current state.
+    pub fn bodies(&self) -> &[GravityBody] {
+        &self.bodies
+    }
+
+    /// Finds a body by GUID.
+    pub fn body(&self, guid: Uuid) -> Option<&GravityBody> {
+        self.bodies.iter().find(|body| body.guid == guid)
+    }
+
+    /// Returns the simulation time in days.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns the solver.
+    pub fn solver(&self) -> GravitySolver {
+        self.solver
+    }
+
+    /// Sets the solver used from the next step.
+    pub fn set_solver(&mut self, solver: GravitySolver) {
+        self.solver = solver;
+        self.accelerations = self.compute_accelerations();
+    }
+
+    /// Sets the softening length in AU, which limits the force between bodies closer than it.
+    pub fn set_softening(&mut self, softening: f64) {
+        self.softening = softening.max(0.0);
+        self.accelerations = self.compute_accelerations();
+    }
+
+    /// Advances the simulation by one velocity Verlet step.
+    ///
+    /// Velocity Verlet is symplectic, so energy errors stay bounded over long runs instead of
+    /// drifting. Pick `dt` well below the shortest orbital period, e.g. a fiftieth of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The time step in days.
+    pub fn step(&mut self, dt: f64) {
+        for (body, acceleration) in self.bodies.iter_mut().zip(&self.accelerations) {
+            body.velocity = add(body.velocity, scale(*acceleration, 0.5 * dt));
+            body.position = add(body.position, scale(body.velocity, dt));
+        }
+        self.accelerations = self.compute_accelerations();
+        for (body, acceleration) in self.bodies.iter_mut().zip(&self.accelerations) {
+            body.velocity = add(body.velocity, scale(*acceleration, 0.5 * dt));
+        }
+        self.time += dt;
+    }
+
+    /// Advances the simulation by `steps` steps of `dt` days.
+    pub fn run(&mut self, dt: f64, steps: usize) {
+        for _ in 0..steps {
+            self.step(dt);
+        }
+    }
+
+    /// Returns the total kinetic and potential energy, in solar masses · AU² / day².
+    ///
+    /// Useful for checking the step size: the value should stay nearly constant.
+    pub fn total_energy(&self) -> f64 {
+        let softening_squared = self.softening * self.softening;
+        let mut energy = 0.0;
+        for (i, body) in self.bodies.iter().enumerate() {
+            energy += 0.5 * body.mass * length(body.velocity).powi(2);
+            for other in &self.bodies[i + 1..] {
+                let distance = (length(sub(other.position, body.position)).powi(2) + softening_squared).sqrt();
+                energy -= GRAVITATIONAL_CONSTANT * body.mass * other.mass / distance;
+            }
+        }
+        energy
+    }
+
+    /// Returns the position of the system's centre of mass in AU.
+    pub fn centre_of_mass(&self) -> Vec3 {
+        let total: f64 = self.bodies.iter().map(|body| body.mass).sum();
+        let weighted = self.bodies.iter().fold((0.0, 0.0, 0.0), |sum, body| add(sum, scale(body.position, body.mass)));
+        scale(weighted, 1.0 / total.max(f64::MIN_POSITIVE))
+    }
+
+    // Acceleration at a point due to a mass at another point
+    fn pull(&self, at: Vec3, from: Vec3, mass: f64) -> Vec3 {
+        let offset = sub(from, at);
+        let distance_squared = offset.0 * offset.0 + offset.1 * offset.1 + offset.2 * offset.2 + self.softening * self.softening;
+        if distance_squared == 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        scale(offset, GRAVITATIONAL_CONSTANT * mass / (distance_squared * distance_squared.sqrt()))
+    }
+
+    fn compute_accelerations(&self) -> Vec<Vec3> {
+        match self.solver {
+            GravitySolver::Direct => (0..self.bodies.len())
+                .into_par_iter()
+                .map(|i| {
+                    self.bodies.iter().enumerate().filter(|&(j, _)| j != i).fold((0.0, 0.0, 0.0), |sum, (_, other)| {
+                        add(sum, self.pull(self.bodies[i].position, other.position, other.mass))
+                    })
+                })
+                .collect(),
+            GravitySolver::BarnesHut { theta } => self.barnes_hut_accelerations(theta),
+        }
+    }
+
+    fn barnes_hut_accelerations(&self, theta: f64) -> Vec<Vec3> {
+        let tree = Octree::new(self.bodies.iter().enumerate().map(|(i, body)| (body.position, i)));
+        if tree.is_empty() {
+            return vec![(0.0, 0.0, 0.0); self.bodies.len()];
+        }
+
+        // Mass and centre of mass of every node, children before parents
+        let mut mass = vec![0.0; tree.node_count()];
+        let mut centre = vec![(0.0, 0.0, 0.0); tree.node_count()];
+        for node in (0..tree.node_count()).rev() {
+            let (m, weighted) = match tree.node_children(node) {
+                Some(first) => (first..first + 8).fold((0.0, (0.0, 0.0, 0.0)), |(m, w), child| (m + mass[child], add(w, scale(centre[child], mass[child])))),
+                None => tree.node_items(node).iter().fold((0.0, (0.0, 0.0, 0.0)), |(m, w), &(p, i)| {
+                    (m + self.bodies[i].mass, add(w, scale(p, self.bodies[i].mass)))
+                }),
+            };
+            mass[node] = m;
+            centre[node] = if m > 0.0 { scale(weighted, 1.0 / m) } else { (0.0, 0.0, 0.0) };
+        }
+
+        let theta_squared = theta * theta;
+        self.bodies
+            .par_iter()
+            .enumerate()
+            .map(|(i, body)| {
+                let mut acceleration = (0.0, 0.0, 0.0);
+                let mut stack = vec![0];
+                while let Some(node) = stack.pop() {
+                    if mass[node] == 0.0 {
+                        continue;
+                    }
+                    match tree.node_children(node) {
+                        Some(first) => {
+                            let offset = sub(centre[node], body.position);
+                            let distance_squared = offset.0 * offset.0 + offset.1 * offset.1 + offset.2 * offset.2;
+                            if tree.node_size(node).powi(2) < theta_squared * distance_squared {
+                                acceleration = add(acceleration, self.pull(body.position, centre[node], mass[node]));
+                            } else {
+                                stack.extend(first..first + 8);
+                            }
+                        }
+                        None => {
+                            for &(p, j) in tree.node_items(node) {
+                                if j != i {
+                                    acceleration = add(acceleration, self.pull(body.position, p, self.bodies[j].mass));
+                                }
+                            }
+                        }
+                    }
+                }
+                acceleration
+            })
+            .collect()
+    }
+}
+
+impl StarSystem {
+    /// Builds an N-body simulation of the star, planets and moons, starting from their Keplerian
+    /// orbits at a time.
+    ///
+    /// Velocities are estimated from the orbits, and the whole system is moved into its
+    /// barycentric frame so it does not drift.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Earth days since the epoch.
+    pub fn gravity_simulation(&self, time: f64) -> GravitySimulation {
+        let tree = self.orbit_tree();
+        let before = tree.positions_at(time - VELOCITY_STEP);
+        let now = tree.positions_at(time);
+        let after = tree.positions_at(time + VELOCITY_STEP);
+
+        let mut masses = vec![self.star().mass];
+        for planet in self.planets() {
+            masses.push(planet.mass / EARTH_MASSES_PER_SOLAR_MASS);
+            masses.extend(planet.moons.iter().map(|moon| moon.mass / EARTH_MASSES_PER_SOLAR_MASS));
+        }
+
+        let mut bodies: Vec<GravityBody> = (0..tree.len())
+            .map(|i| GravityBody {
+                guid: tree.guid(BodyId(i)),
+                mass: masses[i],
+                position: now[i],
+                velocity: scale(sub(after[i], before[i]), 0.5 / VELOCITY_STEP),
+            })
+            .collect();
+
+        let total: f64 = masses.iter().sum();
+        let (position, velocity) = bodies.iter().fold(((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)), |(p, v), body| {
+            (add(p, scale(body.position, body.mass / total)), add(v, scale(body.velocity, body.mass / total)))
+        });
+        for body in &mut bodies {
+            body.position = sub(body.position, position);
+            body.velocity = sub(body.velocity, velocity);
+        }
+        GravitySimulation::new(bodies, time)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::vector::Vec3;
+
+/// Mixes a 64-bit value into a well-distributed hash (SplitMix64 finalizer).
+pub(crate) fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Hashes a seed and a list of integers into a 64-bit value.
+pub(crate) fn hash_values(seed: u64, values: &[i64]) -> u64 {
+    values.iter().fold(mix64(seed), |h, &v| mix64(h ^ v as u64))
+}
+
+// The gradient for a lattice corner, chosen from the 12 cube-edge directions
+fn gradient(seed: u64, ix: i64, iy: i64, iz: i64) -> Vec3 {
+    const GRADIENTS: [Vec3; 12] = [
+        (1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, -1.0, 0.0), (-1.0, -1.0, 0.0),
+        (1.0, 0.0, 1.0), (-1.0, 0.0, 1.0), (1.0, 0.0, -1.0), (-1.0, 0.0, -1.0),
+        (0.0, 1.0, 1.0), (0.0, -1.0, 1.0), (0.0, 1.0, -1.0), (0.0, -1.0, -1.0),
+    ];
+    GRADIENTS[(hash_values(seed, &[ix, iy, iz]) % 12) as usize]
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Samples seeded 3D gradient (Perlin) noise.
+///
+/// # Arguments
+///
+/// * `seed` - Selects an independent noise field.
+/// * `p` - The sample position.
+///
+/// # Returns
+///
+/// A smoothly varying value, roughly in `[-1, 1]`.
+pub fn gradient_noise(seed: u64, p: Vec3) -> f64 {
+    let (fx, fy, fz) = (p.0.floor(), p.1.floor(), p.2.floor());
+    let (ix, iy, iz) = (fx as i64, fy as i64, fz as i64);
+    let (dx, dy, dz) = (p.0 - fx, p.1 - fy, p.2 - fz);
+
+    let corner = |cx: i64, cy: i64, cz: i64| -> f64 {
+        let g = gradient(seed, ix + cx, iy + cy, iz + cz);
+        g.0 * (dx - cx as f64) + g.1 * (dy - cy as f64) + g.2 * (dz - cz as f64)
+    };
+
+    let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+/// Samples fractal Brownian motion built from `gradient_noise`.
+///
+/// # Arguments
+///
+/// * `seed` - Selects an independent noise field; each octave uses a derived seed.
+/// * `p` - The sample position.
+/// * `octaves` - The number of noise layers to sum.
+/// * `lacunarity` - The frequency multiplier between octaves (typically 2.0).
+/// * `gain` - The amplitude multiplier between octaves (typically 0.5).
+///
+/// # Returns
+///
+/// The normalized sum, roughly in `[-1, 1]`.
+pub fn fbm(seed: u64, p: Vec3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut norm = 0.0;
+    for octave in 0..octaves.max(1) {
+        let q = (p.0 * frequency, p.1 * frequency, p.2 * frequency);
+        sum += amplitude * gradient_noise(mix64(seed ^ octave as u64), q);
+        norm += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    sum / norm
+}
@@ -0,0 +1,355 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+
+/// The element types that can be written to an `.npy` array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpyType {
+    F32,
+    F64,
+    U8,
+    U32,
+    U64,
+}
+
+impl NpyType {
+    // The numpy dtype descriptor (little-endian)
+    fn descr(&self) -> &'static str {
+        match self {
+            NpyType::F32 => "<f4",
+            NpyType::F64 => "<f8",
+            NpyType::U8 => "|u1",
+            NpyType::U32 => "<u4",
+            NpyType::U64 => "<u8",
+        }
+    }
+}
+
+/// Encodes an array as a version 1.0 `.npy` file.
+///
+/// # Arguments
+///
+/// * `dtype` - The element type of `data`.
+/// * `shape` - The array shape in C (row-major) order.
+/// * `data` - The little-endian element bytes.
+///
+/// # Returns
+///
+/// The complete `.npy` file contents.
+pub fn encode_npy(dtype: NpyType, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape_text = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        dtype.descr(),
+        shape_text
+    );
+    // Magic (6) + version (2) + header length (2) + header must be a multiple of 64 bytes
+    let total = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - total % 64) % 64));
+    header.push('\n');
+
+    let mut out = Vec::with_capacity(10 + header.len() + data.len());
+    out.extend_from_slice(b"\x93NUMPY");
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// Computes the CRC-32 (IEEE) checksum used by zip archives.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+/// Writes numpy `.npz` archives (uncompressed zip files of `.npy` arrays).
+///
+/// Arrays are buffered in memory and written when `finish` is called.
+pub struct NpzWriter {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl Default for NpzWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NpzWriter {
+    /// Creates an empty archive.
+    pub fn new() -> Self {
+        NpzWriter { entries: Vec::new() }
+    }
+
+    /// Adds an `f32` array.
+    pub fn add_f32(&mut self, name: &str, shape: &[usize], values: &[f32]) {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.add_raw(name, NpyType::F32, shape, &data);
+    }
+
+    /// Adds an `f64` array.
+    pub fn add_f64(&mut self, name: &str, shape: &[usize], values: &[f64]) {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.add_raw(name, NpyType::F64, shape, &data);
+    }
+
+    /// Adds a `u8` array.
+    pub fn add_u8(&mut self, name: &str, shape: &[usize], values: &[u8]) {
+        self.add_raw(name, NpyType::U8, shape, values);
+    }
+
+    /// Adds a `u32` array.
+    pub fn add_u32(&mut self, name: &str, shape: &[usize], values: &[u32]) {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.add_raw(name, NpyType::U32, shape, &data);
+    }
+
+    /// Adds a `u64` array.
+    pub fn add_u64(&mut self, name: &str, shape: &[usize], values: &[u64]) {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.add_raw(name, NpyType::U64, shape, &data);
+    }
+
+    /// Adds an array from raw little-endian element bytes.
+    pub fn add_raw(&mut self, name: &str, dtype: NpyType, shape: &[usize], data: &[u8]) {
+        self.entries.push((format!("{}.npy", name), encode_npy(dtype, shape, data)));
+    }
+
+    /// Encodes the archive as the bytes of an `.npz` file.
+    ///
+    /// Arrays of 4 GiB or more, archives that grow past 4 GiB and archives of more than 65 535
+    /// arrays get ZIP64 records, which numpy reads like any other archive.
+    pub fn into_bytes(self) -> Vec<u8> {
+        encode_zip(&self.entries, ZIP64_LIMIT)
+    }
+
+    /// Writes the archive to `path`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or an `io::Error` if the file could not be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn finish(self, path: &str) -> io::Result<()> {
+        fs::write(path, self.into_bytes())
+    }
+}
+
+// Sizes, offsets and counts from this value up do not fit the classic zip fields
+const ZIP64_LIMIT: u64 = u32::MAX as u64;
+
+// Stores `entries` in a zip archive, moving any size, offset or count of at least `zip64_from`
+// into ZIP64 records
+fn encode_zip(entries: &[(String, Vec<u8>)], zip64_from: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let crc = crc32(data);
+        let size = data.len() as u64;
+        let offset = out.len() as u64;
+        let large_size = size >= zip64_from;
+        let large_offset = offset >= zip64_from;
+        let version: u16 = if large_size || large_offset { 45 } else { 20 };
+        let size32 = if large_size { u32::MAX } else { size as u32 };
+
+        // The local header's ZIP64 field must hold both sizes
+        let mut local_extra = Vec::new();
+        if large_size {
+            local_extra.extend_from_slice(&1u16.to_le_bytes()); // ZIP64 extended information
+            local_extra.extend_from_slice(&16u16.to_le_bytes());
+            local_extra.extend_from_slice(&size.to_le_bytes()); // Uncompressed size
+            local_extra.extend_from_slice(&size.to_le_bytes()); // Compressed size
+        }
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes()); // Local file header signature
+        out.extend_from_slice(&version.to_le_bytes()); // Version needed to extract
+        out.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        out.extend_from_slice(&0u16.to_le_bytes()); // Compression: stored
+        out.extend_from_slice(&0u32.to_le_bytes()); // Modification time and date
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&size32.to_le_bytes()); // Compressed size
+        out.extend_from_slice(&size32.to_le_bytes()); // Uncompressed size
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(local_extra.len() as u16).to_le_bytes()); // Extra field length
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&local_extra);
+        out.extend_from_slice(data);
+
+        // The central header's ZIP64 field holds only the values that overflowed, in this order
+        let mut central_extra = Vec::new();
+        if large_size {
+            central_extra.extend_from_slice(&size.to_le_bytes());
+            central_extra.extend_from_slice(&size.to_le_bytes());
+        }
+        if large_offset {
+            central_extra.extend_from_slice(&offset.to_le_bytes());
+        }
+        if !central_extra.is_empty() {
+            let length = central_extra.len() as u16;
+            central_extra.splice(0..0, [1u16.to_le_bytes(), length.to_le_bytes()].concat());
+        }
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes()); // Central directory signature
+        central.extend_from_slice(&version.to_le_bytes()); // Version made by
+        central.extend_from_slice(&version.to_le_bytes()); // Version needed to extract
+        central.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        central.extend_from_slice(&0u16.to_le_bytes()); // Compression: stored
+        central.extend_from_slice(&0u32.to_le_bytes()); // Modification time and date
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&size32.to_le_bytes());
+        central.extend_from_slice(&size32.to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&(central_extra.len() as u16).to_le_bytes()); // Extra field length
+        central.extend_from_slice(&[0; 6]); // Comment, disk, internal attributes
+        central.extend_from_slice(&0u32.to_le_bytes()); // External attributes
+        central.extend_from_slice(&(if large_offset { u32::MAX } else { offset as u32 }).to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        central.extend_from_slice(&central_extra);
+    }
+
+    let count = entries.len() as u64;
+    let central_offset = out.len() as u64;
+    let central_size = central.len() as u64;
+    out.extend_from_slice(&central);
+
+    let zip64 = count >= zip64_from.min(u16::MAX as u64) || central_size >= zip64_from || central_offset >= zip64_from;
+    if zip64 {
+        let record_offset = out.len() as u64;
+        out.extend_from_slice(&0x0606_4b50u32.to_le_bytes()); // ZIP64 end of central directory signature
+        out.extend_from_slice(&44u64.to_le_bytes()); // Size of the rest of the record
+        out.extend_from_slice(&45u16.to_le_bytes()); // Version made by
+        out.extend_from_slice(&45u16.to_le_bytes()); // Version needed to extract
+        out.extend_from_slice(&[0; 8]); // Disk numbers
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&central_size.to_le_bytes());
+        out.extend_from_slice(&central_offset.to_le_bytes());
+
+        out.extend_from_slice(&0x0706_4b50u32.to_le_bytes()); // ZIP64 end of central directory locator
+        out.extend_from_slice(&0u32.to_le_bytes()); // Disk of the record
+        out.extend_from_slice(&record_offset.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes()); // Total disks
+    }
+    // With ZIP64 records, every field of the classic record is set to its maximum
+    let count16 = if zip64 { u16::MAX } else { count as u16 };
+    let clamp = |value: u64| if zip64 { u32::MAX } else { value as u32 };
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes()); // End of central directory signature
+    out.extend_from_slice(&[0; 4]); // Disk numbers
+    out.extend_from_slice(&count16.to_le_bytes());
+    out.extend_from_slice(&count16.to_le_bytes());
+    out.extend_from_slice(&clamp(central_size).to_le_bytes());
+    out.extend_from_slice(&clamp(central_offset).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u64 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u64
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u64 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as u64
+    }
+
+    fn u64_at(bytes: &[u8], at: usize) -> u64 {
+        u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    // Reads a stored zip archive back through its central directory, following ZIP64 records
+    fn read_zip(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(bytes, end), 0x0605_4b50);
+        let (mut count, mut central) = (u16_at(bytes, end + 10), u32_at(bytes, end + 16));
+        if count == 0xFFFF || central == 0xFFFF_FFFF {
+            assert_eq!(u32_at(bytes, end - 20), 0x0706_4b50);
+            let record = u64_at(bytes, end - 12) as usize;
+            assert_eq!(u32_at(bytes, record), 0x0606_4b50);
+            count = u64_at(bytes, record + 32);
+            central = u64_at(bytes, record + 48);
+        }
+
+        let mut at = central as usize;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(bytes, at), 0x0201_4b50);
+            let crc = u32_at(bytes, at + 16);
+            let (mut size, mut offset) = (u32_at(bytes, at + 24), u32_at(bytes, at + 42));
+            let (name_length, extra_length) = (u16_at(bytes, at + 28) as usize, u16_at(bytes, at + 30) as usize);
+            let name = String::from_utf8(bytes[at + 46..at + 46 + name_length].to_vec()).unwrap();
+            let extra = &bytes[at + 46 + name_length..at + 46 + name_length + extra_length];
+            let mut field = 4;
+            if size == 0xFFFF_FFFF {
+                assert_eq!(u16_at(extra, 0), 1);
+                size = u64_at(extra, field);
+                field += 16;
+            }
+            if offset == 0xFFFF_FFFF {
+                assert_eq!(u16_at(extra, 0), 1);
+                offset = u64_at(extra, field);
+            }
+
+            let local = offset as usize;
+            assert_eq!(u32_at(bytes, local), 0x0403_4b50);
+            assert_eq!(bytes[local + 30..local + 30 + name_length], *name.as_bytes());
+            let start = local + 30 + name_length + u16_at(bytes, local + 28) as usize;
+            let data = bytes[start..start + size as usize].to_vec();
+            assert_eq!(crc32(&data) as u64, crc);
+            entries.push((name, data));
+            at += 46 + name_length + extra_length;
+        }
+        entries
+    }
+
+    fn arrays() -> Vec<(String, Vec<u8>)> {
+        (0..5u8).map(|i| (format!("array_{}.npy", i), encode_npy(NpyType::U8, &[i as usize * 40], &vec![i; i as usize * 40]))).collect()
+    }
+
+    #[test]
+    fn archive_round_trips() {
+        let mut writer = NpzWriter::new();
+        writer.add_f32("elevation", &[2, 3], &[0.0, 1.5, -2.0, 3.25, 4.0, 5.0]);
+        writer.add_u64("guid", &[2], &[7, u64::MAX]);
+        let expected = writer.entries.clone();
+
+        let entries = read_zip(&writer.into_bytes());
+        assert_eq!(entries, expected);
+        assert_eq!(entries[0].0, "elevation.npy");
+        assert!(entries[0].1.starts_with(b"\x93NUMPY\x01\x00"));
+        // The header pads the data to a 64-byte boundary
+        assert_eq!((entries[0].1.len() - 24) % 64, 0);
+    }
+
+    #[test]
+    fn oversized_archive_round_trips_through_zip64_records() {
+        let entries = arrays();
+        // Every entry past the first is large and starts past the limit, and so is the directory
+        let bytes = encode_zip(&entries, 100);
+        assert_ne!(bytes, encode_zip(&entries, ZIP64_LIMIT));
+        assert_eq!(read_zip(&bytes), entries);
+        assert_eq!(read_zip(&encode_zip(&entries, ZIP64_LIMIT)), entries);
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+
+/// Identifies a subscription to a simulation's hooks, for cancelling it with `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(u64);
+
+// Source of subscription ids, unique across every simulation in the process
+static NEXT_SUBSCRIPTION: AtomicU64 = AtomicU64::new(0);
+
+// Callbacks subscribed to one hook, called in subscription order
+pub(crate) struct Observers<F: ?Sized> {
+    callbacks: Vec<(SubscriptionId, Box<F>)>,
+}
+
+impl<F: ?Sized> Default for Observers<F> {
+    fn default() -> Self {
+        Observers { callbacks: Vec::new() }
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Observers<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.callbacks.len())
+    }
+}
+
+impl<F: ?Sized> Observers<F> {
+    pub(crate) fn add(&mut self, callback: Box<F>) -> SubscriptionId {
+        let id = SubscriptionId(NEXT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed));
+        self.callbacks.push((id, callback));
+        id
+    }
+
+    // Returns `true` if the subscription was found
+    pub(crate) fn remove(&mut self, id: SubscriptionId) -> bool {
+        let before = self.callbacks.len();
+        self.callbacks.retain(|(subscription, _)| *subscription != id);
+        self.callbacks.len() != before
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<F>> {
+        self.callbacks.iter_mut().map(|(_, callback)| callback)
+    }
+}
+
+impl<E: Clone + Send + 'static> Observers<dyn FnMut(&E) + Send> {
+    // Subscribes a channel that receives a copy of every event
+    pub(crate) fn add_channel(&mut self) -> (SubscriptionId, Receiver<E>) {
+        let (sender, receiver) = channel();
+        let id = self.add(Box::new(move |event: &E| {
+            // A dropped receiver only means nobody is listening; the subscription stays until
+            // `unsubscribe`
+            let _ = sender.send(event.clone());
+        }));
+        (id, receiver)
+    }
+
+    pub(crate) fn notify(&mut self, event: &E) {
+        for callback in self.iter_mut() {
+            callback(event);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::vector::{sub, Vec3};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Items per leaf before it splits, and the deepest level, which bounds the cost of coincident
+// points
+const LEAF_CAPACITY: usize = 16;
+const MAX_DEPTH: usize = 21;
+
+// A node covering an axis-aligned box and the items in `start..end`
+#[derive(Debug, Clone, PartialEq)]
+struct OctreeNode {
+    min: Vec3,
+    max: Vec3,
+    start: usize,
+    end: usize,
+    children: Option<usize>, // Index of the first of eight consecutive child nodes
+}
+
+impl OctreeNode {
+    // Squared distance from a point to the node's box, zero inside it
+    fn distance_squared(&self, p: Vec3) -> f64 {
+        let gap = |v: f64, low: f64, high: f64| (low - v).max(v - high).max(0.0);
+        let (dx, dy, dz) = (gap(p.0, self.min.0, self.max.0), gap(p.1, self.min.1, self.max.1), gap(p.2, self.min.2, self.max.2));
+        dx * dx + dy * dy + dz * dz
+    }
+}
+
+#[derive(PartialEq)]
+struct Candidate(f64, usize, bool); // Squared distance, node or item index, whether an item
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn distance_squared(a: Vec3, b: Vec3) -> f64 {
+    let d = sub(a, b);
+    d.0 * d.0 + d.1 * d.1 + d.2 * d.2
+}
+
+/// An octree over points carrying values, for range and nearest-neighbor queries.
+///
+/// The tree is built once from all items; nodes split into eight octants until they hold at most
+/// 16 items. Positions may be in any unit, e.g. kiloparsecs for systems in a galaxy or universe
+/// units for galaxies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Octree<T> {
+    items: Vec<(Vec3, T)>,
+    nodes: Vec<OctreeNode>,
+}
+
+impl<T> Octree<T> {
+    /// Builds an octree.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The points and their values. Points with non-finite coordinates are dropped.
+    pub fn new(items: impl IntoIterator<Item = (Vec3, T)>) -> Octree<T> {
+        let mut items: Vec<(Vec3, T)> = items
+            .into_iter()
+            .filter(|(p, _)| p.0.is_finite() && p.1.is_finite() && p.2.is_finite())
+            .collect();
+        let mut min = (f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (p, _) in &items {
+            min = (min.0.min(p.0), min.1.min(p.1), min.2.min(p.2));
+            max = (max.0.max(p.0), max.1.max(p.1), max.2.max(p.2));
+        }
+        if items.is_empty() {
+            return Octree { items, nodes: Vec::new() };
+        }
+        // Make the root a cube so octants stay cubic
+        let half = ((max.0 - min.0).max(max.1 - min.1).max(max.2 - min.2) * 0.5).max(f64::MIN_POSITIVE);
+        let centre = ((min.0 + max.0) * 0.5, (min.1 + max.1) * 0.5, (min.2 + max.2) * 0.5);
+        let mut nodes = vec![OctreeNode {
+            min: (centre.0 - half, centre.1 - half, centre.2 - half),
+            max: (centre.0 + half, centre.1 + half, centre.2 + half),
+            start: 0,
+            end: items.len(),
+            children: None,
+        }];
+        split(&mut nodes, &mut items, 0, 0);
+        Octree { items, nodes }
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the tree holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns all items in tree order.
+    pub fn items(&self) -> &[(Vec3, T)] {
+        &self.items
+    }
+
+    // Returns the number of nodes; children always follow their parent
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Returns the index of a node's first child, or `None` for a leaf
+    pub(crate) fn node_children(&self, node: usize) -> Option<usize> {
+        self.nodes[node].children
+    }
+
+    // Returns the items inside a node
+    pub(crate) fn node_items(&self, node: usize) -> &[(Vec3, T)] {
+        &self.items[self.nodes[node].start..self.nodes[node].end]
+    }
+
+    // Returns the edge length of a node's cube
+    pub(crate) fn node_size(&self, node: usize) -> f64 {
+        self.nodes[node].max.0 - self.nodes[node].min.0
+    }
+
+    /// Returns every item within a distance of a point, in no particular order.
+    pub fn within(&self, centre: Vec3, radius: f64) -> Vec<&(Vec3, T)> {
+        let radius_squared = radius * radius;
+        let mut found = Vec::new();
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.distance_squared(centre) > radius_squared {
+                continue;
+            }
+            match node.children {
+                Some(first) => stack.extend(first..first + 8),
+                None => found.extend(self.items[node.start..node.end].iter().filter(|(p, _)| distance_squared(*p, centre) <= radius_squared)),
+            }
+        }
+        found
+    }
+
+    /// Returns the item nearest to a point and its distance, or `None` for an empty tree.
+    pub fn nearest(&self, point: Vec3) -> Option<(&(Vec3, T), f64)> {
+        self.k_nearest(point, 1).into_iter().next()
+    }
+
+    /// Returns up to `k` items nearest to a point with their distances, nearest first.
+    ///
+    /// Searches best-first: nodes and items share one queue ordered by distance, so the search
+    /// stops as soon as `k` items have been taken from it.
+    pub fn k_nearest(&self, point: Vec3, k: usize) -> Vec<(&(Vec3, T), f64)> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() || k == 0 {
+            return found;
+        }
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate(self.nodes[0].distance_squared(point), 0, false));
+        while let Some(Candidate(distance, index, is_item)) = heap.pop() {
+            if is_item {
+                found.push((&self.items[index], distance.sqrt()));
+                if found.len() == k {
+                    break;
+                }
+                continue;
+            }
+            let node = &self.nodes[index];
+            match node.children {
+                Some(first) => {
+                    for child in first..first + 8 {
+                        if self.nodes[child].start < self.nodes[child].end {
+                            heap.push(Candidate(self.nodes[child].distance_squared(point), child, false));
+                        }
+                    }
+                }
+                None => {
+                    for item in node.start..node.end {
+                        heap.push(Candidate(distance_squared(self.items[item].0, point), item, true));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+// Splits a node into octants, reordering its items so each child's items are contiguous
+fn split<T>(nodes: &mut Vec<OctreeNode>, items: &mut [(Vec3, T)], index: usize, depth: usize) {
+    let OctreeNode { min, max, start, end, .. } = nodes[index];
+    if end - start <= LEAF_CAPACITY || depth >= MAX_DEPTH {
+        return;
+    }
+    let centre = ((min.0 + max.0) * 0.5, (min.1 + max.1) * 0.5, (min.2 + max.2) * 0.5);
+    let octant = |p: Vec3| (p.0 >= centre.0) as usize | ((p.1 >= centre.1) as usize) << 1 | ((p.2 >= centre.2) as usize) << 2;
+
+    // Sort the items by octant; octant codes are small, so a stable sort by key is enough
+    items[start..end].sort_by_key(|(p, _)| octant(*p));
+    let mut bounds = [end; 9];
+    for (o, bound) in bounds.iter_mut().enumerate().take(8) {
+        *bound = start + items[start..end].partition_point(|(p, _)| octant(*p) < o);
+    }
+
+    let first = nodes.len();
+    nodes[index].children = Some(first);
+    for o in 0..8 {
+        let pick = |bit: usize, low: f64, mid: f64, high: f64| if o & bit == 0 { (low, mid) } else { (mid, high) };
+        let (x0, x1) = pick(1, min.0, centre.0, max.0);
+        let (y0, y1) = pick(2, min.1, centre.1, max.1);
+        let (z0, z1) = pick(4, min.2, centre.2, max.2);
+        nodes.push(OctreeNode {
+            min: (x0, y0, z0),
+            max: (x1, y1, z1),
+            start: bounds[o],
+            end: bounds[o + 1],
+            children: None,
+        });
+    }
+    for child in first..first + 8 {
+        split(nodes, items, child, depth + 1);
+    }
+}
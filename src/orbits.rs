@@ -0,0 +1,204 @@
+use crate::star_system::{OrbitalElements, StarSystem};
+use crate::vector::{add, Vec3};
+use uuid::Uuid;
+
+/// The index of a body in an `OrbitTree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyId(pub usize);
+
+// One body of the tree
+#[derive(Debug, Clone, PartialEq)]
+struct OrbitNode {
+    guid: Uuid,
+    parent: Option<BodyId>,
+    orbit: Option<OrbitalElements>, // Relative to the parent's equator; `None` for a fixed root
+    equator: (f64, f64),            // Tilt and azimuth of the body's equator in radians
+}
+
+/// A hierarchy of bodies, each orbiting its parent: moons around planets around stars around
+/// a galactic centre.
+///
+/// Every orbit is expressed in its parent's equatorial frame, and positions are composed up the
+/// chain, so a moon follows its planet as the planet moves. Distances are in astronomical units
+/// and times in Earth days throughout.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrbitTree {
+    nodes: Vec<OrbitNode>,
+}
+
+impl OrbitTree {
+    /// Creates an empty tree.
+    pub fn new() -> OrbitTree {
+        OrbitTree::default()
+    }
+
+    /// Adds a body fixed at the origin, such as a galactic centre or a lone star.
+    pub fn add_root(&mut self, guid: Uuid) -> BodyId {
+        self.nodes.push(OrbitNode {
+            guid,
+            parent: None,
+            orbit: None,
+            equator: (0.0, 0.0),
+        });
+        BodyId(self.nodes.len() - 1)
+    }
+
+    /// Adds a body orbiting another.
+    ///
+    /// # Arguments
+    ///
+    /// * `guid` - The body's GUID.
+    /// * `parent` - The body it orbits.
+    /// * `orbit` - Its orbit, relative to the parent's equator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is not in the tree.
+    pub fn add(&mut self, guid: Uuid, parent: BodyId, orbit: OrbitalElements) -> BodyId {
+        assert!(parent.0 < self.nodes.len(), "parent must be in the tree");
+        self.nodes.push(OrbitNode {
+            guid,
+            parent: Some(parent),
+            orbit: Some(orbit),
+            equator: (0.0, 0.0),
+        });
+        BodyId(self.nodes.len() - 1)
+    }
+
+    /// Tilts a body's equator, which is the reference plane of its children's orbits.
+    ///
+    /// # Arguments
+    ///
+    /// * `tilt` - Angle between the equator and the parent's reference plane in radians.
+    /// * `azimuth` - Direction, in the parent's reference plane, of the axis the equator is tilted
+    ///   about, in radians.
+    pub fn set_equator(&mut self, body: BodyId, tilt: f64, azimuth: f64) {
+        self.nodes[body.0].equator = (tilt, azimuth);
+    }
+
+    /// Returns the number of bodies.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the tree has no bodies.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the GUID of a body.
+    pub fn guid(&self, body: BodyId) -> Uuid {
+        self.nodes[body.0].guid
+    }
+
+    /// Returns the body a body orbits, or `None` for a root.
+    pub fn parent(&self, body: BodyId) -> Option<BodyId> {
+        self.nodes[body.0].parent
+    }
+
+    /// Returns a body's orbit relative to its parent, or `None` for a root.
+    pub fn orbit(&self, body: BodyId) -> Option<&OrbitalElements> {
+        self.nodes[body.0].orbit.as_ref()
+    }
+
+    /// Returns the bodies directly orbiting a body.
+    pub fn children(&self, body: BodyId) -> Vec<BodyId> {
+        (0..self.nodes.len()).filter(|&i| self.nodes[i].parent == Some(body)).map(BodyId).collect()
+    }
+
+    /// Finds a body by GUID.
+    pub fn find(&self, guid: Uuid) -> Option<BodyId> {
+        self.nodes.iter().position(|node| node.guid == guid).map(BodyId)
+    }
+
+    // Rotates a vector from a body's equatorial frame into its parent's reference frame
+    fn equator_to_parent(&self, body: BodyId, v: Vec3) -> Vec3 {
+        let (tilt, azimuth) = self.nodes[body.0].equator;
+        if tilt == 0.0 {
+            return v;
+        }
+        // Rotate by `tilt` about the horizontal axis pointing at `azimuth` (Rodrigues' formula)
+        let (sin_a, cos_a) = azimuth.sin_cos();
+        let (sin_t, cos_t) = tilt.sin_cos();
+        let k = (cos_a, sin_a, 0.0);
+        let k_dot_v = k.0 * v.0 + k.1 * v.1;
+        let k_cross_v = (k.1 * v.2, -k.0 * v.2, k.0 * v.1 - k.1 * v.0);
+        (
+            v.0 * cos_t + k_cross_v.0 * sin_t + k.0 * k_dot_v * (1.0 - cos_t),
+            v.1 * cos_t + k_cross_v.1 * sin_t + k.1 * k_dot_v * (1.0 - cos_t),
+            v.2 * cos_t + k_cross_v.2 * sin_t,
+        )
+    }
+
+    /// Returns a body's position relative to its root at a time.
+    ///
+    /// Each local orbital position is turned into the root frame through the equators of the
+    /// body's ancestors and added to its parent's position, in the same order as `positions_at`,
+    /// so both give bit-identical results.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The body.
+    /// * `time` - Earth days since the epoch.
+    pub fn position_at(&self, body: BodyId, time: f64) -> Vec3 {
+        let mut chain = vec![body];
+        while let Some(parent) = self.nodes[chain[chain.len() - 1].0].parent {
+            chain.push(parent);
+        }
+        chain.iter().rev().fold((0.0, 0.0, 0.0), |position, id| match (self.nodes[id.0].parent, &self.nodes[id.0].orbit) {
+            (Some(parent), Some(orbit)) => add(position, self.to_root(parent, orbit.position_at(time))),
+            _ => position,
+        })
+    }
+
+    /// Returns every body's position relative to its root at a time, indexed by `BodyId`.
+    ///
+    /// Bodies are always added after their parents, so this is a single pass.
+    pub fn positions_at(&self, time: f64) -> Vec<Vec3> {
+        let mut positions: Vec<Vec3> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let position = match (node.parent, &node.orbit) {
+                (Some(parent), Some(orbit)) => add(positions[parent.0], self.to_root(parent, orbit.position_at(time))),
+                _ => (0.0, 0.0, 0.0),
+            };
+            positions.push(position);
+        }
+        positions
+    }
+
+    // Rotates an offset in a body's equatorial frame into the root frame
+    fn to_root(&self, body: BodyId, offset: Vec3) -> Vec3 {
+        let mut offset = offset;
+        let mut current = Some(body);
+        while let Some(id) = current {
+            offset = self.equator_to_parent(id, offset);
+            current = self.nodes[id.0].parent;
+        }
+        offset
+    }
+}
+
+impl StarSystem {
+    /// Adds the system's planets and their moons to an orbit tree under the star.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The tree, which must already contain the star, e.g. orbiting a galactic centre.
+    /// * `star` - The star's body in the tree.
+    pub fn add_to_tree(&self, tree: &mut OrbitTree, star: BodyId) {
+        for planet in self.planets() {
+            let id = tree.add(planet.guid, star, planet.orbit.clone());
+            for moon in &planet.moons {
+                tree.add(moon.guid, id, moon.orbit.clone());
+            }
+        }
+    }
+
+    /// Builds the system's orbit tree with the star as the fixed root.
+    pub fn orbit_tree(&self) -> OrbitTree {
+        let mut tree = OrbitTree::new();
+        let star = tree.add_root(self.guid());
+        self.add_to_tree(&mut tree, star);
+        tree
+    }
+}
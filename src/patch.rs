@@ -0,0 +1,431 @@
+use crate::geology::RockType;
+use crate::naming::NamedFeature;
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+use uuid::Uuid;
+
+// File signature and format version of an encoded `WorldPatch`
+const MAGIC: &[u8; 4] = b"TFWP";
+const VERSION: u32 = 1;
+
+/// Error returned when a world patch cannot be decoded or applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The file could not be read.
+    Io(String),
+    /// The data does not start with the patch signature.
+    NotAPatch,
+    /// The patch was written by a newer, unknown format version.
+    UnsupportedVersion(u32),
+    /// The data ended before the patch did.
+    Truncated,
+    /// A value is out of range, or an edit does not fit the planet it is applied to.
+    Invalid(String),
+    /// The planet is not the base world the patch was recorded against: its GUID or cell count
+    /// differs.
+    Mismatch,
+}
+
+impl std::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Io(message) => write!(f, "cannot read world patch: {}", message),
+            PatchError::NotAPatch => write!(f, "data is not a world patch"),
+            PatchError::UnsupportedVersion(version) => write!(f, "unsupported world patch version {}", version),
+            PatchError::Truncated => write!(f, "world patch is truncated"),
+            PatchError::Invalid(message) => write!(f, "invalid world patch: {}", message),
+            PatchError::Mismatch => write!(f, "world patch does not match the base world"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// One recorded change to a world.
+///
+/// Cell indices are those of the planet when the change was made, so a change refers to the cell
+/// layout left by the changes before it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    /// A cell's elevation set in metres, e.g. by player terraforming.
+    Elevation { cell: usize, elevation: f64 },
+    /// A cell's bedrock replaced.
+    RockType { cell: usize, rock_type: RockType },
+    /// A cell destroyed with `Planet::remove_site`; the last cell moves into its index.
+    Destroy { cell: usize },
+    /// A cell added with `Planet::insert_site` at a latitude and longitude in degrees.
+    Insert { latitude: f64, longitude: f64 },
+    /// A feature given a new name, replacing the one its `Namer` generates.
+    Rename { kind: NamedFeature, id: u64, name: String },
+}
+
+/// Runtime changes to a generated world, stored as edits on top of its deterministic base.
+///
+/// The base world is always regenerated from the planet GUID; a save only has to keep the patch.
+/// Make changes through the patch so they are recorded as they are applied, then `save` it and
+/// later `rebuild` the world from the same parameters. Changes replay in the order they were made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldPatch {
+    guid: Uuid,
+    base_cells: usize,
+    ops: Vec<PatchOp>,
+}
+
+impl WorldPatch {
+    /// Starts an empty patch for a freshly generated world.
+    ///
+    /// # Arguments
+    ///
+    /// * `guid` - The planet GUID the base world is generated from.
+    /// * `base` - The base world, before any change.
+    pub fn new(guid: Uuid, base: &Planet) -> WorldPatch {
+        WorldPatch {
+            guid,
+            base_cells: base.cell_count(),
+            ops: Vec::new(),
+        }
+    }
+
+    /// Returns the planet GUID of the base world.
+    pub fn guid(&self) -> Uuid {
+        self.guid
+    }
+
+    /// Returns the recorded changes, oldest first.
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.ops
+    }
+
+    /// Returns the number of recorded changes.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns true if nothing has changed.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Sets the elevation of cells and records the change.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The world being changed.
+    /// * `edits` - Cells and their new elevation in metres.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a cell is out of range.
+    pub fn terraform(&mut self, planet: &mut Planet, edits: &[(usize, f64)]) {
+        let mut elevation = planet.elevation().to_vec();
+        for &(cell, value) in edits {
+            elevation[cell] = value;
+            self.ops.push(PatchOp::Elevation { cell, elevation: value });
+        }
+        planet.set_elevation(elevation);
+    }
+
+    /// Sets the bedrock of a cell and records the change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is out of range.
+    pub fn set_rock_type(&mut self, planet: &mut Planet, cell: usize, rock_type: RockType) {
+        let mut rock_types = planet.rock_types().to_vec();
+        rock_types[cell] = rock_type;
+        planet.set_rock_types(rock_types);
+        self.ops.push(PatchOp::RockType { cell, rock_type });
+    }
+
+    /// Destroys a cell with `Planet::remove_site` and records the change.
+    ///
+    /// # Returns
+    ///
+    /// The former index of the cell that moved into `cell`, as `remove_site` returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics where `remove_site` does.
+    pub fn destroy(&mut self, planet: &mut Planet, cell: usize) -> Option<usize> {
+        let moved = planet.remove_site(cell);
+        self.ops.push(PatchOp::Destroy { cell });
+        moved
+    }
+
+    /// Adds a cell with `Planet::insert_site` and records the change if a cell was added.
+    ///
+    /// # Returns
+    ///
+    /// The index of the new cell, or `None` if a site already exists at that position.
+    pub fn insert_site(&mut self, planet: &mut Planet, latitude: f64, longitude: f64) -> Option<usize> {
+        let cell = planet.insert_site(latitude, longitude)?;
+        self.ops.push(PatchOp::Insert { latitude, longitude });
+        Some(cell)
+    }
+
+    /// Renames a feature and records the change.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of feature, as given to `Namer::name`.
+    /// * `id` - The feature's id among its kind, as given to `Namer::name`.
+    /// * `name` - The new name.
+    pub fn rename(&mut self, kind: NamedFeature, id: u64, name: &str) {
+        self.ops.push(PatchOp::Rename { kind, id, name: name.to_string() });
+    }
+
+    /// Returns the latest name given to a feature, or `None` if it keeps its generated name.
+    pub fn name(&self, kind: NamedFeature, id: u64) -> Option<&str> {
+        self.ops.iter().rev().find_map(|op| match op {
+            PatchOp::Rename { kind: k, id: i, name } if *k == kind && *i == id => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Replays the changes on a freshly generated base world.
+    ///
+    /// Consecutive elevation and rock type edits are written together, so the climate is reset
+    /// once per run of edits rather than once per edit.
+    ///
+    /// # Returns
+    ///
+    /// `PatchError::Mismatch` if the planet does not have the base world's cell count, or
+    /// `PatchError::Invalid` if an edit names a cell out of range. The planet is left part-way
+    /// changed on error.
+    pub fn apply(&self, planet: &mut Planet) -> Result<(), PatchError> {
+        if planet.cell_count() != self.base_cells {
+            return Err(PatchError::Mismatch);
+        }
+        let mut elevation: Option<Vec<f64>> = None;
+        let mut rock_types: Option<Vec<RockType>> = None;
+        let flush = |planet: &mut Planet, elevation: &mut Option<Vec<f64>>, rock_types: &mut Option<Vec<RockType>>| {
+            if let Some(elevation) = elevation.take() {
+                planet.set_elevation(elevation);
+            }
+            if let Some(rock_types) = rock_types.take() {
+                planet.set_rock_types(rock_types);
+            }
+        };
+        for (index, op) in self.ops.iter().enumerate() {
+            let cells = planet.cell_count();
+            let check = |cell: usize| {
+                if cell < cells {
+                    Ok(cell)
+                } else {
+                    Err(PatchError::Invalid(format!("change {} names cell {} of {}", index, cell, cells)))
+                }
+            };
+            match op {
+                PatchOp::Elevation { cell, elevation: value } => {
+                    let cell = check(*cell)?;
+                    elevation.get_or_insert_with(|| planet.elevation().to_vec())[cell] = *value;
+                }
+                PatchOp::RockType { cell, rock_type } => {
+                    let cell = check(*cell)?;
+                    rock_types.get_or_insert_with(|| planet.rock_types().to_vec())[cell] = *rock_type;
+                }
+                PatchOp::Destroy { cell } => {
+                    let cell = check(*cell)?;
+                    if cells < 5 {
+                        return Err(PatchError::Invalid(format!("change {} destroys a cell of a planet with {} cells", index, cells)));
+                    }
+                    flush(planet, &mut elevation, &mut rock_types);
+                    planet.remove_site(cell);
+                }
+                PatchOp::Insert { latitude, longitude } => {
+                    flush(planet, &mut elevation, &mut rock_types);
+                    if planet.insert_site(*latitude, *longitude).is_none() {
+                        return Err(PatchError::Invalid(format!("change {} inserts a cell where one exists", index)));
+                    }
+                }
+                PatchOp::Rename { .. } => {}
+            }
+        }
+        flush(planet, &mut elevation, &mut rock_types);
+        Ok(())
+    }
+
+    /// Regenerates the base world from its parameters and replays the changes on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters` - The parameters the base world was built from, e.g.
+    ///   `PlanetParameters::from_guid(patch.guid())`.
+    ///
+    /// # Returns
+    ///
+    /// The changed world, or `PatchError::Mismatch` if the parameters are for another planet.
+    pub fn rebuild(&self, parameters: &PlanetParameters) -> Result<Planet, PatchError> {
+        if parameters.guid != self.guid {
+            return Err(PatchError::Mismatch);
+        }
+        let mut planet = parameters.build();
+        self.apply(&mut planet)?;
+        Ok(planet)
+    }
+
+    /// Encodes the patch in a compact little-endian binary format.
+    ///
+    /// The format starts with the signature `TFWP` and a format version, so `from_bytes` can
+    /// reject foreign or newer data. Floats are stored bit-exactly.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(36 + self.ops.len() * 17);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(self.guid.as_bytes());
+        out.extend_from_slice(&(self.base_cells as u64).to_le_bytes());
+        out.extend_from_slice(&(self.ops.len() as u64).to_le_bytes());
+        for op in &self.ops {
+            match op {
+                PatchOp::Elevation { cell, elevation } => {
+                    out.push(0);
+                    out.extend_from_slice(&(*cell as u64).to_le_bytes());
+                    out.extend_from_slice(&elevation.to_le_bytes());
+                }
+                PatchOp::RockType { cell, rock_type } => {
+                    out.push(1);
+                    out.extend_from_slice(&(*cell as u64).to_le_bytes());
+                    out.push(*rock_type as u8);
+                }
+                PatchOp::Destroy { cell } => {
+                    out.push(2);
+                    out.extend_from_slice(&(*cell as u64).to_le_bytes());
+                }
+                PatchOp::Insert { latitude, longitude } => {
+                    out.push(3);
+                    out.extend_from_slice(&latitude.to_le_bytes());
+                    out.extend_from_slice(&longitude.to_le_bytes());
+                }
+                PatchOp::Rename { kind, id, name } => {
+                    out.push(4);
+                    out.push(*kind as u8);
+                    out.extend_from_slice(&id.to_le_bytes());
+                    out.extend_from_slice(&(name.len() as u64).to_le_bytes());
+                    out.extend_from_slice(name.as_bytes());
+                }
+            }
+        }
+        out
+    }
+
+    /// Decodes a patch written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<WorldPatch, PatchError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4).ok() != Some(MAGIC.as_slice()) {
+            return Err(PatchError::NotAPatch);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(PatchError::UnsupportedVersion(version));
+        }
+        let guid = Uuid::from_bytes(reader.array()?);
+        let base_cells = reader.u64()? as usize;
+        let count = reader.count(9)?;
+        let mut ops = Vec::with_capacity(count);
+        for _ in 0..count {
+            ops.push(match reader.u8()? {
+                0 => PatchOp::Elevation { cell: reader.u64()? as usize, elevation: reader.f64()? },
+                1 => PatchOp::RockType { cell: reader.u64()? as usize, rock_type: reader.rock_type()? },
+                2 => PatchOp::Destroy { cell: reader.u64()? as usize },
+                3 => PatchOp::Insert { latitude: reader.f64()?, longitude: reader.f64()? },
+                4 => {
+                    let kind = reader.named_feature()?;
+                    let id = reader.u64()?;
+                    let length = reader.count(1)?;
+                    let name = String::from_utf8(reader.take(length)?.to_vec()).map_err(|_| PatchError::Invalid("name is not UTF-8".to_string()))?;
+                    PatchOp::Rename { kind, id, name }
+                }
+                other => return Err(PatchError::Invalid(format!("unknown change {}", other))),
+            });
+        }
+        if !reader.bytes.is_empty() {
+            return Err(PatchError::Invalid("trailing data".to_string()));
+        }
+        Ok(WorldPatch { guid, base_cells, ops })
+    }
+
+    /// Writes the patch to a file in the format of `to_bytes`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Reads a patch written by `save`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<WorldPatch, PatchError> {
+        let bytes = fs::read(path).map_err(|error| PatchError::Io(error.to_string()))?;
+        WorldPatch::from_bytes(&bytes)
+    }
+}
+
+// Reads little-endian values from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], PatchError> {
+        if self.bytes.len() < count {
+            return Err(PatchError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], PatchError> {
+        Ok(self.take(N)?.try_into().expect("slice has N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, PatchError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, PatchError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, PatchError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, PatchError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    // A count of items that each take at least `item_size` bytes, checked against the data left
+    fn count(&mut self, item_size: usize) -> Result<usize, PatchError> {
+        let count = self.u64()?;
+        if count > (self.bytes.len() / item_size) as u64 {
+            return Err(PatchError::Truncated);
+        }
+        Ok(count as usize)
+    }
+
+    fn rock_type(&mut self) -> Result<RockType, PatchError> {
+        match self.u8()? {
+            0 => Ok(RockType::Granite),
+            1 => Ok(RockType::Basalt),
+            2 => Ok(RockType::Sedimentary),
+            3 => Ok(RockType::Limestone),
+            4 => Ok(RockType::Alluvium),
+            other => Err(PatchError::Invalid(format!("unknown rock type {}", other))),
+        }
+    }
+
+    fn named_feature(&mut self) -> Result<NamedFeature, PatchError> {
+        match self.u8()? {
+            0 => Ok(NamedFeature::Planet),
+            1 => Ok(NamedFeature::Continent),
+            2 => Ok(NamedFeature::Ocean),
+            3 => Ok(NamedFeature::MountainRange),
+            4 => Ok(NamedFeature::River),
+            5 => Ok(NamedFeature::Settlement),
+            other => Err(PatchError::Invalid(format!("unknown feature kind {}", other))),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::planet::Planet;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The cost model for overland travel between neighboring cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelCost {
+    pub slope_penalty: f64, // Extra cost per degree of slope, as a fraction of the distance
+    pub max_slope: f64,     // Slopes steeper than this (in degrees) are impassable
+    pub allow_water: bool,  // Whether paths may cross ocean cells
+    pub water_penalty: f64, // Cost multiplier for crossing ocean when it is allowed
+}
+
+impl Default for TravelCost {
+    /// Land-only travel, with slopes above 30° impassable.
+    fn default() -> Self {
+        TravelCost {
+            slope_penalty: 0.1,
+            max_slope: 30.0,
+            allow_water: false,
+            water_penalty: 3.0,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Open(f64, usize);
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Planet {
+    /// Returns the cost of stepping between two neighboring cells under a cost model.
+    ///
+    /// The cost is the great-circle distance in kilometres scaled up by the slope of the step.
+    ///
+    /// # Returns
+    ///
+    /// The step cost, or `None` if the step is impassable.
+    pub fn travel_cost(&self, from: usize, to: usize, cost: &TravelCost) -> Option<f64> {
+        let distance = self.distance_km(from, to);
+        let water = self.is_ocean(from) || self.is_ocean(to);
+        if water && !cost.allow_water {
+            return None;
+        }
+        let rise = (self.elevation()[to].max(0.0) - self.elevation()[from].max(0.0)).abs();
+        let slope = rise.atan2(distance * 1000.0).to_degrees();
+        if slope > cost.max_slope {
+            return None;
+        }
+        let multiplier = if water { cost.water_penalty } else { 1.0 };
+        Some(distance * (1.0 + cost.slope_penalty * slope) * multiplier)
+    }
+
+    /// Finds the cheapest path between two cells with A* search over the cell graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `from`, `to` - The start and goal cells.
+    /// * `step_cost` - The cost of moving between two neighboring cells, or `None` if the step
+    ///   is impassable. Costs must be at least the great-circle distance in kilometres for the
+    ///   search to find the optimal path.
+    ///
+    /// # Returns
+    ///
+    /// The cells along the path (including both ends) and its total cost, or `None` if the goal
+    /// cannot be reached.
+    pub fn find_path<F>(&self, from: usize, to: usize, step_cost: F) -> Option<(Vec<usize>, f64)>
+    where
+        F: Fn(usize, usize) -> Option<f64>,
+    {
+        let mut cost = vec![f64::INFINITY; self.cell_count()];
+        let mut came_from = vec![usize::MAX; self.cell_count()];
+        let mut open = BinaryHeap::new();
+        cost[from] = 0.0;
+        open.push(Open(self.distance_km(from, to), from));
+
+        while let Some(Open(_, cell)) = open.pop() {
+            if cell == to {
+                let mut path = vec![to];
+                while let Some(&last) = path.last() {
+                    if last == from {
+                        break;
+                    }
+                    path.push(came_from[last]);
+                }
+                path.reverse();
+                return Some((path, cost[to]));
+            }
+            for &neighbor in self.neighbors(cell) {
+                if let Some(step) = step_cost(cell, neighbor) {
+                    let next = cost[cell] + step;
+                    if next < cost[neighbor] {
+                        cost[neighbor] = next;
+                        came_from[neighbor] = cell;
+                        open.push(Open(next + self.distance_km(neighbor, to), neighbor));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
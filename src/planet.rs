@@ -0,0 +1,853 @@
+use crate::atmosphere::Atmosphere;
+use crate::climate::{self, OrbitalCalendar, SOLAR_CONSTANT};
+use crate::delaunay_triangulation::{duplicate_sites, spherical_delaunay};
+use crate::geology::{self, RockType};
+use crate::guard::guard_values;
+use crate::layers::LayerMap;
+use crate::precision::{values_as, CompactPlanet, Precision};
+use crate::terrain::{self, TerrainSettings};
+use crate::tides::Tides;
+use crate::raster::lat_lon_to_unit;
+use crate::vector::{cross, dot, length, normalize, scale, sub, Vec3};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+/// A built-in per-cell layer of a `Planet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Elevation relative to sea level in metres.
+    Elevation,
+    /// Snow and ice state as the numeric code of `ice::IceState`.
+    Ice,
+    /// Bedrock as the numeric code of `geology::RockType`.
+    RockType,
+    /// Steepest slope to a neighboring cell in degrees.
+    Slope,
+    /// Surface temperature in degrees Celsius.
+    Temperature,
+    /// Snow cover fraction, 0.0 to 1.0.
+    SnowCover,
+    /// Daily mean insolation for the current day in W/m².
+    Insolation,
+    /// Latitude of the cell site in degrees.
+    Latitude,
+    /// Longitude of the cell site in degrees.
+    Longitude,
+    /// Instantaneous irradiance at the current time in W/m², zero on the night side.
+    Irradiance,
+}
+
+/// Sites closer together than this angle in radians, about 6 mm on an Earth-sized planet, count as
+/// the same site.
+pub const SITE_EPSILON: f64 = 1e-9;
+
+/// Error returned when a set of cell sites cannot be triangulated into a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriangulationError {
+    /// The site at this index is not finite or is at the origin, so it has no direction.
+    InvalidSite(usize),
+    /// Only this many distinct sites are left; covering the sphere takes at least four.
+    TooFewSites(usize),
+    /// The sites do not span the sphere, e.g. they all lie on one great circle.
+    Degenerate,
+}
+
+impl std::fmt::Display for TriangulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriangulationError::InvalidSite(index) => write!(f, "site {} is not a finite, non-zero point", index),
+            TriangulationError::TooFewSites(count) => write!(f, "{} distinct sites cannot cover a sphere; at least 4 are needed", count),
+            TriangulationError::Degenerate => write!(f, "the sites do not span the sphere"),
+        }
+    }
+}
+
+impl std::error::Error for TriangulationError {}
+
+/// A planet surface made of spherical Voronoi cells.
+///
+/// Every cell is identified by the index of its site. The planet owns the Delaunay triangulation
+/// of the sites, the cell adjacency derived from it, and the per-cell layers produced by the
+/// simulations.
+#[derive(Debug, Clone)]
+pub struct Planet {
+    radius: f64, // Planet radius in kilometres
+    surface_gravity: f64, // m/s²
+    atmosphere: Atmosphere,
+    tides: Tides,
+    sites: Vec<Vec3>, // Cell sites on the unit sphere, z is the rotation axis
+    triangles: Vec<[usize; 3]>,
+    neighbors: Vec<Vec<usize>>,
+    elevation: Vec<f64>, // Metres relative to sea level
+    rock_types: Vec<RockType>,
+    axial_tilt: f64, // Obliquity in degrees
+    calendar: OrbitalCalendar,
+    solar_flux: f64, // Irradiance at the planet's orbit in W/m²
+    day: f64, // Days elapsed since generation
+    temperature: Vec<f64>, // Surface temperature in degrees Celsius
+    snow_cover: Vec<f64>, // Snow cover fraction, 0.0 to 1.0
+    custom_layers: LayerMap,
+}
+
+// Days for the surface temperature to close ~63% of the gap to its equilibrium
+const THERMAL_LAG_DAYS: f64 = 30.0;
+// Snow cover gained per day per degree below freezing
+const SNOW_ACCUMULATION_RATE: f64 = 0.01;
+// Snow cover lost per day per degree above freezing
+const SNOW_MELT_RATE: f64 = 0.02;
+// Plausible bounds for guarded fields, far outside anything the simulations should produce
+const ELEVATION_RANGE: (f64, f64) = (-20_000.0, 20_000.0);
+const TEMPERATURE_RANGE: (f64, f64) = (-273.15, 2_000.0);
+
+// A site has a direction only if it is finite and not at the origin
+fn site_is_valid(point: Vec3) -> bool {
+    let length_squared = dot(point, point);
+    length_squared.is_finite() && length_squared > 0.0
+}
+
+impl Planet {
+    /// Creates a planet from a set of cell sites.
+    ///
+    /// Sites that are not finite or at the origin are dropped, as are sites within `SITE_EPSILON`
+    /// radians (about 6 mm on Earth) of an earlier site, so the planet may have fewer cells than
+    /// there are points. With fewer than four sites left the planet has cells but no triangles.
+    /// Use `try_new` to be told about such input instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The cell sites, e.g. the output of `generate_fibonacci_sphere`. They may be at
+    ///   any radius; only their directions are used.
+    ///
+    /// # Returns
+    ///
+    /// An Earth-sized planet with Earth's axial tilt and calendar, at day 0.
+    pub fn new(points: Vec<(f64, f64, f64)>) -> Planet {
+        let valid: Vec<Vec3> = points.into_iter().filter(|&point| site_is_valid(point)).map(normalize).collect();
+        let duplicates = duplicate_sites(&valid);
+        let sites: Vec<Vec3> = valid.into_iter().zip(duplicates).filter(|(_, duplicate)| duplicate.is_none()).map(|(site, _)| site).collect();
+        let triangles = spherical_delaunay(&sites);
+        Planet::from_triangulation(sites, triangles)
+    }
+
+    /// Creates a planet from a set of cell sites like `new`, but rejects input that cannot make a
+    /// complete planet instead of dropping points.
+    ///
+    /// Near-coincident sites, which jittered or hand-placed sites can produce, are still merged:
+    /// every site within `SITE_EPSILON` of an earlier one is dropped and the earlier one kept.
+    ///
+    /// The triangulation projects the sites stereographically from the first site, which is then
+    /// stitched back in; no other site can land on the projection pole because any site close
+    /// enough to do so is merged into the first.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The cell sites, at any radius.
+    ///
+    /// # Returns
+    ///
+    /// The planet, or the reason the sites cannot cover the sphere.
+    pub fn try_new(points: Vec<(f64, f64, f64)>) -> Result<Planet, TriangulationError> {
+        if let Some(index) = points.iter().position(|&point| !site_is_valid(point)) {
+            return Err(TriangulationError::InvalidSite(index));
+        }
+        let unit: Vec<Vec3> = points.into_iter().map(normalize).collect();
+        let duplicates = duplicate_sites(&unit);
+        let sites: Vec<Vec3> = unit.into_iter().zip(duplicates).filter(|(_, duplicate)| duplicate.is_none()).map(|(site, _)| site).collect();
+        if sites.len() < 4 {
+            return Err(TriangulationError::TooFewSites(sites.len()));
+        }
+        let triangles = spherical_delaunay(&sites);
+        // A triangulation covering the sphere has 2n - 4 triangles, none of them flat; sites on
+        // one great circle give the right count but only flat triangles
+        let flat = triangles.par_iter().any(|&[a, b, c]| {
+            let (ab, ac) = (sub(sites[b], sites[a]), sub(sites[c], sites[a]));
+            dot(sites[a], cross(ab, ac)) <= 1e-12 * length(ab) * length(ac)
+        });
+        if triangles.len() != 2 * sites.len() - 4 || flat {
+            return Err(TriangulationError::Degenerate);
+        }
+        Ok(Planet::from_triangulation(sites, triangles))
+    }
+
+    // A fresh Earth-like planet on already triangulated unit sites
+    fn from_triangulation(sites: Vec<Vec3>, triangles: Vec<[usize; 3]>) -> Planet {
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); sites.len()];
+        for triangle in &triangles {
+            for k in 0..3 {
+                let from = triangle[k];
+                let to = triangle[(k + 1) % 3];
+                if !neighbors[from].contains(&to) {
+                    neighbors[from].push(to);
+                }
+                if !neighbors[to].contains(&from) {
+                    neighbors[to].push(from);
+                }
+            }
+        }
+
+        let mut planet = Planet {
+            radius: 6371.0,
+            surface_gravity: 9.80665,
+            atmosphere: Atmosphere::default(),
+            tides: Tides::default(),
+            elevation: vec![0.0; sites.len()],
+            rock_types: vec![RockType::default(); sites.len()],
+            temperature: vec![0.0; sites.len()],
+            snow_cover: vec![0.0; sites.len()],
+            custom_layers: LayerMap::new(sites.len()),
+            sites,
+            triangles,
+            neighbors,
+            axial_tilt: 23.44,
+            calendar: OrbitalCalendar::default(),
+            solar_flux: SOLAR_CONSTANT,
+            day: 0.0,
+        };
+        planet.reset_climate();
+        planet
+    }
+
+    /// Stores the planet compactly in precision `T`, e.g. `planet.compact::<f32>()` before keeping
+    /// it resident or uploading it to a GPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planet has more than `u32::MAX` cells.
+    pub fn compact<T: Precision>(&self) -> CompactPlanet<T> {
+        assert!(self.cell_count() <= u32::MAX as usize, "compact planets index cells with u32");
+        let mut neighbor_offsets = Vec::with_capacity(self.cell_count() + 1);
+        neighbor_offsets.push(0);
+        for list in &self.neighbors {
+            neighbor_offsets.push(neighbor_offsets[neighbor_offsets.len() - 1] + list.len() as u32);
+        }
+        CompactPlanet {
+            positions: self.sites.iter().map(|&(x, y, z)| [T::from_f64(x), T::from_f64(y), T::from_f64(z)]).collect(),
+            triangles: self.triangles.iter().map(|triangle| triangle.map(|cell| cell as u32)).collect(),
+            neighbor_offsets,
+            neighbors: self.neighbors.iter().flatten().map(|&cell| cell as u32).collect(),
+            elevation: values_as(&self.elevation),
+            temperature: values_as(&self.temperature),
+            snow_cover: values_as(&self.snow_cover),
+            rock_types: self.rock_types.clone(),
+            custom_layers: self.custom_layers.clone(),
+            radius: self.radius,
+            surface_gravity: self.surface_gravity,
+            atmosphere: self.atmosphere.clone(),
+            tides: self.tides.clone(),
+            axial_tilt: self.axial_tilt,
+            calendar: self.calendar.clone(),
+            solar_flux: self.solar_flux,
+            day: self.day,
+        }
+    }
+
+    /// Restores a planet from its compact form, reusing the stored triangulation.
+    ///
+    /// Sites are renormalized after widening, so a planet stored in `f32` comes back with sites
+    /// within about 1e-7 of the original directions and layers rounded to `f32`.
+    pub fn from_compact<T: Precision>(compact: &CompactPlanet<T>) -> Planet {
+        Planet {
+            radius: compact.radius,
+            surface_gravity: compact.surface_gravity,
+            atmosphere: compact.atmosphere.clone(),
+            tides: compact.tides.clone(),
+            sites: compact.positions.iter().map(|&[x, y, z]| normalize((x.to_f64(), y.to_f64(), z.to_f64()))).collect(),
+            triangles: compact.triangles.iter().map(|triangle| triangle.map(|cell| cell as usize)).collect(),
+            neighbors: (0..compact.cell_count()).map(|cell| compact.neighbors(cell).iter().map(|&n| n as usize).collect()).collect(),
+            elevation: compact.elevation.iter().map(|value| value.to_f64()).collect(),
+            rock_types: compact.rock_types.clone(),
+            axial_tilt: compact.axial_tilt,
+            calendar: compact.calendar.clone(),
+            solar_flux: compact.solar_flux,
+            day: compact.day,
+            temperature: compact.temperature.iter().map(|value| value.to_f64()).collect(),
+            snow_cover: compact.snow_cover.iter().map(|value| value.to_f64()).collect(),
+            custom_layers: compact.custom_layers.clone(),
+        }
+    }
+
+    /// Returns the planet radius in kilometres.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Sets the planet radius in kilometres.
+    pub fn set_radius(&mut self, radius_km: f64) {
+        self.radius = radius_km;
+    }
+
+    /// Returns the gravitational acceleration at the surface in m/s².
+    pub fn surface_gravity(&self) -> f64 {
+        self.surface_gravity
+    }
+
+    /// Sets the gravitational acceleration at the surface in m/s².
+    pub fn set_surface_gravity(&mut self, gravity: f64) {
+        self.surface_gravity = gravity;
+    }
+
+    /// Returns the atmosphere, used for flight and entry physics and for the greenhouse warming
+    /// of the temperature model.
+    pub fn atmosphere(&self) -> &Atmosphere {
+        &self.atmosphere
+    }
+
+    /// Replaces the atmosphere and resets temperatures to the equilibrium of its greenhouse effect.
+    pub fn set_atmosphere(&mut self, atmosphere: Atmosphere) {
+        self.atmosphere = atmosphere;
+        self.reset_climate();
+    }
+
+    /// Returns the bodies raising ocean tides, see `tide_at`.
+    pub fn tides(&self) -> &Tides {
+        &self.tides
+    }
+
+    /// Replaces the bodies raising ocean tides.
+    pub fn set_tides(&mut self, tides: Tides) {
+        self.tides = tides;
+    }
+
+    /// Returns the number of cells.
+    pub fn cell_count(&self) -> usize {
+        self.sites.len()
+    }
+
+    /// Returns the unit-sphere site of every cell.
+    pub fn sites(&self) -> &[Vec3] {
+        &self.sites
+    }
+
+    /// Returns the Delaunay triangles connecting the cell sites.
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Returns the cells sharing a Voronoi edge with `cell`.
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[cell]
+    }
+
+    /// Finds the cell whose site is nearest to `point`.
+    ///
+    /// Walks the Delaunay adjacency from `hint`, which always reaches the nearest site because
+    /// every Voronoi cell is convex on the sphere.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - Any non-zero point; only its direction is used.
+    /// * `hint` - A cell to start from. Nearby hints make the lookup faster.
+    pub fn nearest_cell(&self, point: Vec3, hint: usize) -> usize {
+        let p = normalize(point);
+        let mut current = hint.min(self.sites.len().saturating_sub(1));
+        let mut best = dot(self.sites[current], p);
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.neighbors[current] {
+                let d = dot(self.sites[neighbor], p);
+                if d > best {
+                    best = d;
+                    current = neighbor;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Returns the values of a built-in layer, one per cell.
+    pub fn layer(&self, layer: Layer) -> Vec<f64> {
+        match layer {
+            Layer::Elevation => self.elevation.clone(),
+            Layer::Ice => self.ice_state().iter().map(|&state| state as u8 as f64).collect(),
+            Layer::RockType => self.rock_types.iter().map(|&rock| rock as u8 as f64).collect(),
+            Layer::Slope => (0..self.cell_count()).map(|cell| self.slope(cell)).collect(),
+            Layer::Temperature => self.temperature.clone(),
+            Layer::SnowCover => self.snow_cover.clone(),
+            Layer::Insolation => (0..self.cell_count()).map(|cell| self.insolation(cell)).collect(),
+            Layer::Latitude => (0..self.cell_count()).map(|cell| self.lat_lon(cell).0).collect(),
+            Layer::Longitude => (0..self.cell_count()).map(|cell| self.lat_lon(cell).1).collect(),
+            Layer::Irradiance => (0..self.cell_count()).map(|cell| self.irradiance(cell, self.day)).collect(),
+        }
+    }
+
+    /// Returns the elevation of every cell in metres relative to sea level.
+    pub fn elevation(&self) -> &[f64] {
+        &self.elevation
+    }
+
+    /// Replaces the elevation layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `elevation` does not have one value per cell.
+    pub fn set_elevation(&mut self, elevation: Vec<f64>) {
+        assert_eq!(elevation.len(), self.cell_count(), "elevation must have one value per cell");
+        self.elevation = elevation;
+        guard_values("elevation", &mut self.elevation, ELEVATION_RANGE, 0.0);
+        self.reset_climate();
+    }
+
+    /// Generates the elevation layer from seeded noise.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The terrain seed.
+    /// * `settings` - The shape of the terrain, see `TerrainSettings`.
+    pub fn generate_terrain(&mut self, seed: u64, settings: &TerrainSettings) {
+        self.elevation = terrain::generate_elevation(&self.sites, seed, settings);
+        guard_values("elevation", &mut self.elevation, ELEVATION_RANGE, 0.0);
+        self.rock_types = geology::classify_rock_types(&self.sites, &self.elevation, seed);
+        self.reset_climate();
+    }
+
+    /// Returns the bedrock of every cell.
+    pub fn rock_types(&self) -> &[RockType] {
+        &self.rock_types
+    }
+
+    /// Returns the user-defined per-cell layers.
+    pub fn custom_layers(&self) -> &LayerMap {
+        &self.custom_layers
+    }
+
+    /// Returns the user-defined per-cell layers for adding, editing or removing layers.
+    pub fn custom_layers_mut(&mut self) -> &mut LayerMap {
+        &mut self.custom_layers
+    }
+
+    /// Replaces the rock type layer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rock_types` does not have one value per cell.
+    pub fn set_rock_types(&mut self, rock_types: Vec<RockType>) {
+        assert_eq!(rock_types.len(), self.cell_count(), "rock types must have one value per cell");
+        self.rock_types = rock_types;
+    }
+
+    /// Estimates the heap memory the planet occupies in bytes: its sites, triangulation, adjacency
+    /// and per-cell layers, counted at their capacity.
+    pub fn memory_bytes(&self) -> usize {
+        let vec_bytes = |capacity: usize, element: usize| capacity * element;
+        vec_bytes(self.sites.capacity(), std::mem::size_of::<Vec3>())
+            + vec_bytes(self.triangles.capacity(), std::mem::size_of::<[usize; 3]>())
+            + vec_bytes(self.neighbors.capacity(), std::mem::size_of::<Vec<usize>>())
+            + self.neighbors.iter().map(|list| vec_bytes(list.capacity(), std::mem::size_of::<usize>())).sum::<usize>()
+            + vec_bytes(self.elevation.capacity(), std::mem::size_of::<f64>())
+            + vec_bytes(self.rock_types.capacity(), std::mem::size_of::<RockType>())
+            + vec_bytes(self.temperature.capacity(), std::mem::size_of::<f64>())
+            + vec_bytes(self.snow_cover.capacity(), std::mem::size_of::<f64>())
+            + self.custom_layers.memory_bytes()
+    }
+
+    /// Returns the great-circle distance between two cell sites in kilometres.
+    pub fn distance_km(&self, a: usize, b: usize) -> f64 {
+        dot(self.sites[a], self.sites[b]).clamp(-1.0, 1.0).acos() * self.radius
+    }
+
+    /// Returns the steepest slope from a cell to any of its neighbors, in degrees.
+    ///
+    /// Slopes are measured on the surface above sea level, so the ocean floor is flat.
+    pub fn slope(&self, cell: usize) -> f64 {
+        let height = self.elevation[cell].max(0.0);
+        self.neighbors[cell]
+            .iter()
+            .map(|&neighbor| {
+                let offset = sub(self.sites[neighbor], self.sites[cell]);
+                let tangent = sub(offset, scale(self.sites[cell], dot(offset, self.sites[cell])));
+                let run = length(tangent) * self.radius * 1000.0;
+                let rise = (self.elevation[neighbor].max(0.0) - height).abs();
+                rise.atan2(run).to_degrees()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Returns `true` if the cell lies below sea level.
+    pub fn is_ocean(&self, cell: usize) -> bool {
+        self.elevation[cell] < 0.0
+    }
+
+    /// Returns the latitude and longitude of a cell site in degrees.
+    pub fn lat_lon(&self, cell: usize) -> (f64, f64) {
+        let (x, y, z) = self.sites[cell];
+        (z.clamp(-1.0, 1.0).asin() * 180.0 / PI, y.atan2(x) * 180.0 / PI)
+    }
+
+    /// Returns the axial tilt in degrees.
+    pub fn axial_tilt(&self) -> f64 {
+        self.axial_tilt
+    }
+
+    /// Sets the axial tilt (obliquity) in degrees and re-initializes the seasonal climate.
+    ///
+    /// # Arguments
+    ///
+    /// * `degrees` - The angle between the rotation axis and the orbit normal, clamped to `0..=180`.
+    pub fn set_axial_tilt(&mut self, degrees: f64) {
+        self.axial_tilt = degrees.clamp(0.0, 180.0);
+        self.reset_climate();
+    }
+
+    /// Returns the orbital calendar.
+    pub fn calendar(&self) -> &OrbitalCalendar {
+        &self.calendar
+    }
+
+    /// Replaces the orbital calendar and re-initializes the seasonal climate.
+    pub fn set_calendar(&mut self, calendar: OrbitalCalendar) {
+        self.calendar = calendar;
+        self.reset_climate();
+    }
+
+    /// Returns the stellar irradiance at the planet's orbit in W/m².
+    pub fn solar_flux(&self) -> f64 {
+        self.solar_flux
+    }
+
+    /// Sets the stellar irradiance at the planet's orbit in W/m² and re-initializes the climate.
+    pub fn set_solar_flux(&mut self, flux: f64) {
+        self.solar_flux = flux.max(0.0);
+        self.reset_climate();
+    }
+
+    /// Returns the number of days simulated since generation.
+    pub fn day(&self) -> f64 {
+        self.day
+    }
+
+    /// Returns the current day of the year.
+    pub fn day_of_year(&self) -> f64 {
+        self.calendar.day_of_year(self.day)
+    }
+
+    /// Returns the current solar declination in radians.
+    pub fn solar_declination(&self) -> f64 {
+        self.calendar.solar_declination(self.day, self.axial_tilt)
+    }
+
+    /// Returns the daily mean insolation of a cell on the current day, in W/m².
+    pub fn insolation(&self, cell: usize) -> f64 {
+        let (lat, _) = self.lat_lon(cell);
+        climate::daily_mean_insolation(lat, self.solar_declination(), self.solar_flux)
+    }
+
+    /// Returns the point on the surface where the sun is directly overhead at `time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Days since generation; the fractional part is the local time of day at the
+    ///   prime meridian, with noon at `0.5`.
+    ///
+    /// # Returns
+    ///
+    /// The `(latitude, longitude)` of the subsolar point in degrees.
+    pub fn subsolar_point(&self, time: f64) -> (f64, f64) {
+        climate::subsolar_point(&self.calendar, self.axial_tilt, time)
+    }
+
+    /// Returns the instantaneous sunlight falling on a cell.
+    ///
+    /// Combines the planet's rotation (the time of day), axial tilt and orbital position (the
+    /// day of year) into the sun's elevation above the cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - The cell to query.
+    /// * `time` - Days since generation; the fractional part is the time of day.
+    ///
+    /// # Returns
+    ///
+    /// The top-of-atmosphere irradiance in W/m², or `0.0` if the cell is on the night side.
+    pub fn irradiance(&self, cell: usize, time: f64) -> f64 {
+        let (lat, lon) = self.subsolar_point(time);
+        let sun = crate::raster::lat_lon_to_unit(lat, lon);
+        climate::instantaneous_irradiance(self.sites[cell], sun, self.solar_flux)
+    }
+
+    /// Returns `true` if the sun is above the horizon of `cell` at `time`.
+    pub fn is_daylit(&self, cell: usize, time: f64) -> bool {
+        self.irradiance(cell, time) > 0.0
+    }
+
+    /// Returns the hours of daylight a cell receives on the current day.
+    ///
+    /// Cells in polar day return the full day length and cells in polar night return `0.0`.
+    pub fn daylight_hours(&self, cell: usize) -> f64 {
+        let (lat, _) = self.lat_lon(cell);
+        climate::daylight_hours(lat, self.solar_declination(), self.calendar.day_length_hours())
+    }
+
+    /// Returns the surface temperature of every cell in degrees Celsius.
+    pub fn temperature(&self) -> &[f64] {
+        &self.temperature
+    }
+
+    /// Replaces the surface temperature of every cell, in degrees Celsius.
+    ///
+    /// The temperature keeps evolving from these values on the next `advance_days`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `temperature` does not have one value per cell.
+    pub fn set_temperature(&mut self, temperature: Vec<f64>) {
+        assert_eq!(temperature.len(), self.cell_count(), "temperature must have one value per cell");
+        self.temperature = temperature;
+        guard_values("temperature", &mut self.temperature, TEMPERATURE_RANGE, 0.0);
+    }
+
+    /// Returns the snow cover fraction (0.0 to 1.0) of every cell.
+    pub fn snow_cover(&self) -> &[f64] {
+        &self.snow_cover
+    }
+
+    /// Advances the seasonal climate by the given number of days.
+    ///
+    /// Temperatures relax toward the equilibrium set by the day's insolation with a thermal lag,
+    /// and snow accumulates below freezing and melts above it, so snow lines advance in winter
+    /// and retreat in summer.
+    ///
+    /// # Arguments
+    ///
+    /// * `days` - The number of days to simulate. The simulation steps at most one day at a time.
+    ///   Negative spans do nothing, and so do infinite and `NaN` ones, which are logged as
+    ///   warnings since they could never finish.
+    pub fn advance_days(&mut self, days: f64) {
+        if !days.is_finite() {
+            tracing::warn!("advance_days: ignoring non-finite span of {} days", days);
+            return;
+        }
+        let mut remaining = days.max(0.0);
+        while remaining > 0.0 {
+            let dt = remaining.min(1.0);
+            self.day += dt;
+            remaining -= dt;
+
+            let declination = self.solar_declination();
+            let flux = self.solar_flux;
+            let relax = 1.0 - (-dt / THERMAL_LAG_DAYS).exp();
+            let greenhouse = self.atmosphere.greenhouse_offset();
+            let sites = &self.sites;
+            let elevation = &self.elevation;
+
+            self.temperature
+                .par_iter_mut()
+                .zip(self.snow_cover.par_iter_mut())
+                .enumerate()
+                .for_each(|(cell, (temperature, snow))| {
+                    let latitude = sites[cell].2.clamp(-1.0, 1.0).asin() * 180.0 / PI;
+                    let insolation = climate::daily_mean_insolation(latitude, declination, flux);
+                    let target = climate::lapse_adjusted(climate::equilibrium_temperature(insolation) + greenhouse, elevation[cell]);
+                    *temperature += (target - *temperature) * relax;
+
+                    if *temperature < 0.0 {
+                        *snow += SNOW_ACCUMULATION_RATE * -*temperature * dt;
+                    } else {
+                        *snow -= SNOW_MELT_RATE * *temperature * dt;
+                    }
+                    *snow = snow.clamp(0.0, 1.0);
+                });
+            guard_values("temperature", &mut self.temperature, TEMPERATURE_RANGE, 0.0);
+            guard_values("snow_cover", &mut self.snow_cover, (0.0, 1.0), 0.0);
+        }
+    }
+
+    // Sets temperatures to the current equilibrium and snow to match them
+    fn reset_climate(&mut self) {
+        let declination = self.solar_declination();
+        let greenhouse = self.atmosphere.greenhouse_offset();
+        for cell in 0..self.sites.len() {
+            let (lat, _) = self.lat_lon(cell);
+            let insolation = climate::daily_mean_insolation(lat, declination, self.solar_flux);
+            self.temperature[cell] = climate::lapse_adjusted(climate::equilibrium_temperature(insolation) + greenhouse, self.elevation[cell]);
+            self.snow_cover[cell] = if self.temperature[cell] < 0.0 { 1.0 } else { 0.0 };
+        }
+        guard_values("temperature", &mut self.temperature, TEMPERATURE_RANGE, 0.0);
+    }
+
+    /// Adds a cell site and updates the triangulation around it, for interactive editing of the
+    /// cell layout.
+    ///
+    /// Only the triangles whose circumcircles contain the new site are replaced, so the cost is one
+    /// pass over the triangle list plus work proportional to the cells around the site, far less
+    /// than building a new planet. The new cell takes its layers from its neighbors: elevation,
+    /// temperature and snow cover weighted by inverse distance, rock type and custom layers from the
+    /// nearest.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Latitude of the new site in degrees.
+    /// * `longitude` - Longitude of the new site in degrees.
+    ///
+    /// # Returns
+    ///
+    /// The index of the new cell, always the previous cell count, or `None` if a site already
+    /// exists at that position.
+    pub fn insert_site(&mut self, latitude: f64, longitude: f64) -> Option<usize> {
+        let site = lat_lon_to_unit(latitude, longitude);
+        if self.sites.is_empty() || dot(site, self.sites[self.nearest_cell(site, 0)]) > 1.0 - 1e-15 {
+            return None;
+        }
+
+        // The triangles the site sees from outside the hull of the sites, which is the Delaunay
+        // triangulation on a sphere; together they form a disk bounded by the horizon
+        let visible: Vec<usize> = (0..self.triangles.len())
+            .filter(|&index| {
+                let [a, b, c] = self.triangles[index];
+                let (a, b, c) = (self.sites[a], self.sites[b], self.sites[c]);
+                dot(sub(site, a), cross(sub(b, a), sub(c, a))) > 0.0
+            })
+            .collect();
+        if visible.is_empty() {
+            return None;
+        }
+        let edges: Vec<(usize, usize)> = visible
+            .iter()
+            .flat_map(|&index| {
+                let [a, b, c] = self.triangles[index];
+                [(a, b), (b, c), (c, a)]
+            })
+            .collect();
+        let edge_set: HashSet<(usize, usize)> = edges.iter().copied().collect();
+        let cell = self.sites.len();
+        for &(a, b) in &edges {
+            if edge_set.contains(&(b, a)) {
+                self.neighbors[a].retain(|&n| n != b);
+            } else {
+                self.triangles.push([a, b, cell]);
+            }
+        }
+        for &index in visible.iter().rev() {
+            self.triangles.swap_remove(index);
+        }
+
+        let mut ring: Vec<usize> = edges.iter().filter(|&&(a, b)| !edge_set.contains(&(b, a))).map(|&(a, _)| a).collect();
+        ring.sort_unstable();
+        for &neighbor in &ring {
+            self.neighbors[neighbor].push(cell);
+        }
+        let weights: Vec<f64> = ring.iter().map(|&n| 1.0 / dot(site, self.sites[n]).clamp(-1.0, 1.0).acos().max(1e-12)).collect();
+        let total: f64 = weights.iter().sum();
+        let blend = |layer: &[f64]| ring.iter().zip(&weights).map(|(&n, w)| layer[n] * w).sum::<f64>() / total;
+        let nearest = ring.iter().zip(&weights).max_by(|a, b| a.1.total_cmp(b.1)).map_or(0, |(&n, _)| n);
+        self.elevation.push(blend(&self.elevation));
+        self.temperature.push(blend(&self.temperature));
+        self.snow_cover.push(blend(&self.snow_cover));
+        self.rock_types.push(self.rock_types[nearest]);
+        self.custom_layers.push_copy(nearest);
+        self.sites.push(site);
+        self.neighbors.push(ring);
+        Some(cell)
+    }
+
+    /// Removes a cell and fills the hole it leaves in the triangulation with Delaunay triangles
+    /// between its former neighbors, which grow to cover its area.
+    ///
+    /// As with `Vec::swap_remove`, the last cell moves into the removed cell's index so every other
+    /// index stays valid.
+    ///
+    /// # Returns
+    ///
+    /// The former index of the cell that moved into `cell`, or `None` if `cell` was the last one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is out of range or the planet has fewer than five cells, the fewest that
+    /// still tile the sphere after a removal.
+    pub fn remove_site(&mut self, cell: usize) -> Option<usize> {
+        assert!(cell < self.sites.len(), "cell {} is out of range", cell);
+        assert!(self.sites.len() >= 5, "a planet needs at least four cells");
+
+        // The neighbors in counter-clockwise order, from the triangles around the cell
+        let mut next: HashMap<usize, usize> = HashMap::new();
+        self.triangles.retain(|&[a, b, c]| {
+            let rotated = if a == cell { Some((b, c)) } else if b == cell { Some((c, a)) } else if c == cell { Some((a, b)) } else { None };
+            if let Some((from, to)) = rotated {
+                next.insert(from, to);
+            }
+            rotated.is_none()
+        });
+        let start = self.neighbors[cell][0];
+        let mut ring = vec![start];
+        while let Some(&following) = next.get(ring.last().expect("ring is never empty")) {
+            if following == start {
+                break;
+            }
+            ring.push(following);
+        }
+        for &neighbor in &ring {
+            self.neighbors[neighbor].retain(|&n| n != cell);
+        }
+
+        // Clip ears off the ring, each a triangle no other ring site lies outside of, which keeps
+        // the hull convex and so the triangulation Delaunay
+        while ring.len() > 3 {
+            let ear = (0..ring.len())
+                .map(|i| {
+                    let (a, b, c) = (ring[i], ring[(i + 1) % ring.len()], ring[(i + 2) % ring.len()]);
+                    let (pa, pb, pc) = (self.sites[a], self.sites[b], self.sites[c]);
+                    let normal = cross(sub(pb, pa), sub(pc, pa));
+                    let convex = dot(normal, pa) > 0.0;
+                    let violation = ring.iter().filter(|&&q| q != a && q != b && q != c).map(|&q| dot(sub(self.sites[q], pa), normal)).fold(f64::NEG_INFINITY, f64::max);
+                    (i, convex, violation)
+                })
+                .filter(|&(_, convex, _)| convex)
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .map_or(0, |(i, _, _)| i);
+            let (a, b, c) = (ring[ear], ring[(ear + 1) % ring.len()], ring[(ear + 2) % ring.len()]);
+            self.triangles.push([a, b, c]);
+            self.neighbors[a].push(c);
+            self.neighbors[c].push(a);
+            ring.remove((ear + 1) % ring.len());
+        }
+        self.triangles.push([ring[0], ring[1], ring[2]]);
+
+        // Move the last cell into the freed index
+        let last = self.sites.len() - 1;
+        self.sites.swap_remove(cell);
+        self.neighbors.swap_remove(cell);
+        self.elevation.swap_remove(cell);
+        self.temperature.swap_remove(cell);
+        self.snow_cover.swap_remove(cell);
+        self.rock_types.swap_remove(cell);
+        self.custom_layers.swap_remove(cell);
+        if cell == last {
+            return None;
+        }
+        for triangle in &mut self.triangles {
+            for vertex in triangle.iter_mut().filter(|vertex| **vertex == last) {
+                *vertex = cell;
+            }
+        }
+        for neighbor in self.neighbors[cell].clone() {
+            for n in self.neighbors[neighbor].iter_mut().filter(|n| **n == last) {
+                *n = cell;
+            }
+        }
+        Some(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::procedural::PlanetParameters;
+    use uuid::Uuid;
+
+    #[test]
+    fn endless_spans_advance_nothing() {
+        let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x537));
+        parameters.samples = 300;
+        let mut planet = parameters.build();
+        let before = planet.clone();
+        for days in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN, -3.0] {
+            planet.advance_days(days);
+            assert_eq!(planet.day(), before.day());
+            assert_eq!(planet.temperature(), before.temperature());
+        }
+        planet.advance_days(2.5);
+        assert_eq!(planet.day(), before.day() + 2.5);
+    }
+}
@@ -0,0 +1,988 @@
+use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+use crate::npz::{crc32, NpyType};
+use crate::octree::Octree;
+use crate::planet::Planet;
+use crate::vector::{add, dot, normalize, Vec3};
+use crate::vegetation::VegetationLayer;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+
+// File signature and format version of a planet file
+const MAGIC: &[u8; 4] = b"TFPF";
+const VERSION: u32 = 1;
+
+// Bytes of the fixed header, of one chunk table entry and of one directory entry
+const HEADER_SIZE: usize = 44;
+const CHUNK_ENTRY_SIZE: usize = 24;
+const BLOCK_ENTRY_SIZE: usize = 29;
+
+/// Error returned when a planet file cannot be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanetFileError {
+    /// The file could not be opened or mapped.
+    Io(String),
+    /// The data does not start with the planet file signature.
+    NotAPlanetFile,
+    /// The file was written by a newer, unknown format version.
+    UnsupportedVersion(u32),
+    /// The data ended before the header, tables or a block did.
+    Truncated,
+    /// A block failed its checksum or a value is out of range.
+    Corrupt(String),
+    /// The file has no layer with this name.
+    UnknownLayer(String),
+    /// The chunk index is not below the chunk count.
+    ChunkOutOfRange(usize),
+}
+
+impl std::fmt::Display for PlanetFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanetFileError::Io(message) => write!(f, "cannot read planet file: {}", message),
+            PlanetFileError::NotAPlanetFile => write!(f, "data is not a planet file"),
+            PlanetFileError::UnsupportedVersion(version) => write!(f, "unsupported planet file version {}", version),
+            PlanetFileError::Truncated => write!(f, "planet file is truncated"),
+            PlanetFileError::Corrupt(message) => write!(f, "corrupt planet file: {}", message),
+            PlanetFileError::UnknownLayer(name) => write!(f, "planet file has no layer '{}'", name),
+            PlanetFileError::ChunkOutOfRange(chunk) => write!(f, "chunk {} is out of range", chunk),
+        }
+    }
+}
+
+impl std::error::Error for PlanetFileError {}
+
+/// How a block is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    /// The raw little-endian values.
+    None = 0,
+    /// The bytes regrouped by their position within each value, then run-length encoded.
+    ///
+    /// Regrouping puts the slowly varying high bytes of neighbouring values next to each other,
+    /// which is where smooth layers such as elevation compress.
+    ShuffledRle = 1,
+}
+
+impl Compression {
+    fn from_code(code: u8) -> Option<Compression> {
+        match code {
+            0 => Some(Compression::None),
+            1 => Some(Compression::ShuffledRle),
+            _ => None,
+        }
+    }
+}
+
+/// The values of one layer, for one chunk or for the whole planet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerData {
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    U8(Vec<u8>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl LayerData {
+    /// Returns the element type.
+    pub fn dtype(&self) -> NpyType {
+        match self {
+            LayerData::F32(_) => NpyType::F32,
+            LayerData::F64(_) => NpyType::F64,
+            LayerData::U8(_) => NpyType::U8,
+            LayerData::U32(_) => NpyType::U32,
+            LayerData::U64(_) => NpyType::U64,
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            LayerData::F32(values) => values.len(),
+            LayerData::F64(values) => values.len(),
+            LayerData::U8(values) => values.len(),
+            LayerData::U32(values) => values.len(),
+            LayerData::U64(values) => values.len(),
+        }
+    }
+
+    /// Returns true if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The little-endian bytes of values `first * components .. (first + 1) * components` for
+    // each index in `indices`
+    fn gather(&self, indices: &[usize], components: usize) -> Vec<u8> {
+        fn pick<T: Copy, const N: usize>(values: &[T], indices: &[usize], components: usize, bytes: impl Fn(T) -> [u8; N]) -> Vec<u8> {
+            let mut out = Vec::with_capacity(indices.len() * components * N);
+            for &index in indices {
+                for &value in &values[index * components..(index + 1) * components] {
+                    out.extend_from_slice(&bytes(value));
+                }
+            }
+            out
+        }
+        match self {
+            LayerData::F32(values) => pick(values, indices, components, f32::to_le_bytes),
+            LayerData::F64(values) => pick(values, indices, components, f64::to_le_bytes),
+            LayerData::U8(values) => pick(values, indices, components, u8::to_le_bytes),
+            LayerData::U32(values) => pick(values, indices, components, u32::to_le_bytes),
+            LayerData::U64(values) => pick(values, indices, components, u64::to_le_bytes),
+        }
+    }
+
+    fn from_bytes(dtype: NpyType, bytes: &[u8]) -> LayerData {
+        fn parse<T, const N: usize>(bytes: &[u8], from: impl Fn([u8; N]) -> T) -> Vec<T> {
+            bytes.chunks_exact(N).map(|chunk| from(chunk.try_into().expect("chunk has N bytes"))).collect()
+        }
+        match dtype {
+            NpyType::F32 => LayerData::F32(parse(bytes, f32::from_le_bytes)),
+            NpyType::F64 => LayerData::F64(parse(bytes, f64::from_le_bytes)),
+            NpyType::U8 => LayerData::U8(bytes.to_vec()),
+            NpyType::U32 => LayerData::U32(parse(bytes, u32::from_le_bytes)),
+            NpyType::U64 => LayerData::U64(parse(bytes, u64::from_le_bytes)),
+        }
+    }
+}
+
+fn dtype_code(dtype: NpyType) -> u8 {
+    match dtype {
+        NpyType::F32 => 0,
+        NpyType::F64 => 1,
+        NpyType::U8 => 2,
+        NpyType::U32 => 3,
+        NpyType::U64 => 4,
+    }
+}
+
+fn dtype_from_code(code: u8) -> Option<NpyType> {
+    match code {
+        0 => Some(NpyType::F32),
+        1 => Some(NpyType::F64),
+        2 => Some(NpyType::U8),
+        3 => Some(NpyType::U32),
+        4 => Some(NpyType::U64),
+        _ => None,
+    }
+}
+
+fn dtype_size(dtype: NpyType) -> usize {
+    match dtype {
+        NpyType::U8 => 1,
+        NpyType::F32 | NpyType::U32 => 4,
+        NpyType::F64 | NpyType::U64 => 8,
+    }
+}
+
+/// The name and shape of a layer in a planet file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerInfo {
+    pub name: String,
+    pub dtype: NpyType,
+    pub components: usize, // Values per cell, e.g. 3 for a position
+}
+
+// Regroups the bytes of `size`-byte values so byte k of every value is contiguous
+fn shuffle(bytes: &[u8], size: usize) -> Vec<u8> {
+    let count = bytes.len() / size;
+    let mut out = Vec::with_capacity(bytes.len());
+    for k in 0..size {
+        out.extend((0..count).map(|i| bytes[i * size + k]));
+    }
+    out
+}
+
+fn unshuffle(bytes: &[u8], size: usize) -> Vec<u8> {
+    let count = bytes.len() / size;
+    let mut out = vec![0; bytes.len()];
+    for k in 0..size {
+        for i in 0..count {
+            out[i * size + k] = bytes[k * count + i];
+        }
+    }
+    out
+}
+
+// PackBits: a control byte n below 128 is followed by n + 1 literal bytes, and n above 128 by
+// one byte repeated 257 - n times
+fn rle_encode(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = bytes[i..].iter().take(128).take_while(|&&b| b == bytes[i]).count();
+        if run >= 2 {
+            out.push((257 - run) as u8);
+            out.push(bytes[i]);
+            i += run;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && i - start < 128 && !(i + 1 < bytes.len() && bytes[i] == bytes[i + 1]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&bytes[start..i]);
+    }
+    out
+}
+
+fn rle_decode(bytes: &[u8], raw_length: usize) -> Option<Vec<u8>> {
+    // A run of two bytes expands to at most 128, so a corrupt length cannot force a huge allocation
+    let mut out = Vec::with_capacity(raw_length.min(bytes.len().saturating_mul(128)));
+    let mut i = 0;
+    while i < bytes.len() {
+        let control = bytes[i] as usize;
+        i += 1;
+        if control < 128 {
+            out.extend_from_slice(bytes.get(i..i + control + 1)?);
+            i += control + 1;
+        } else if control > 128 {
+            out.extend(std::iter::repeat_n(*bytes.get(i)?, 257 - control));
+            i += 1;
+        }
+        if out.len() > raw_length {
+            return None;
+        }
+    }
+    (out.len() == raw_length).then_some(out)
+}
+
+// One encoded block and the directory entry describing it
+struct Block {
+    data: Vec<u8>,
+    raw_length: usize,
+    compression: Compression,
+    crc: u32,
+}
+
+impl Block {
+    fn encode(raw: Vec<u8>, element_size: usize, compression: Compression) -> Block {
+        let crc = crc32(&raw);
+        let raw_length = raw.len();
+        if compression == Compression::ShuffledRle {
+            let packed = rle_encode(&shuffle(&raw, element_size));
+            if packed.len() < raw.len() {
+                return Block {
+                    data: packed,
+                    raw_length,
+                    compression,
+                    crc,
+                };
+            }
+        }
+        Block {
+            data: raw,
+            raw_length,
+            compression: Compression::None,
+            crc,
+        }
+    }
+}
+
+/// Writes a planet as a chunked binary file that `PlanetFile` can read piece by piece.
+///
+/// The surface is split into chunks, patches of neighbouring cells around evenly spread centres.
+/// Each chunk stores its cell ids, its triangles and one block per layer, so a game can load the
+/// region around the player without reading the rest of the planet. Every planet file has the
+/// layers `position` (3 × f32, unit vector of each cell's site), `elevation` (f32, metres),
+/// `temperature` (f32, °C) and `biome` (u8, `Material` id); `add_layer` adds more.
+///
+/// The file is little-endian:
+///
+/// | Part | Contents |
+/// |------|----------|
+/// | Header | `TFPF`, u32 version, u64 cells, u64 triangles, f64 radius (km), u32 chunks, u32 layers, u32 table bytes |
+/// | Layer table | per layer: u8 name length, UTF-8 name, u8 type (f32, f64, u8, u32, u64), u32 components |
+/// | Chunk table | per chunk: f32 × 3 centre, f32 angular radius (radians), u32 cells, u32 triangles |
+/// | Directory | per chunk, for cells, triangles, then each layer: u64 offset, u64 stored length, u64 raw length, u8 compression, u32 CRC-32 of the raw bytes |
+/// | Blocks | cell ids (u32), triangles (u32 × 3 of global cell ids), layer values in chunk cell order |
+#[derive(Debug, Clone)]
+pub struct PlanetFileWriter {
+    cell_count: usize,
+    triangles: Vec<[u32; 3]>,
+    radius: f64,
+    centres: Vec<Vec3>,
+    extents: Vec<f64>,                            // Angular radius of each chunk
+    chunk_cells: Vec<Vec<usize>>,
+    chunk_triangles: Vec<Vec<usize>>,
+    layers: Vec<(LayerInfo, LayerData)>,
+    compression: Compression,
+}
+
+impl PlanetFileWriter {
+    /// Splits a planet into chunks and collects its standard layers, then its custom layers under
+    /// their keys. A custom layer named like a standard one replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet to write.
+    /// * `chunk_count` - How many chunks to split the surface into, at least 1. Around 64 suits
+    ///   streaming; more chunks mean smaller reads and a larger directory.
+    pub fn new(planet: &Planet, chunk_count: usize) -> PlanetFileWriter {
+        let centres = seeded_fibonacci_sphere(chunk_count.max(1), 0.0, 0);
+        let lookup = Octree::new(centres.iter().enumerate().map(|(chunk, &centre)| (centre, chunk)));
+        let nearest = |point: Vec3| lookup.nearest(point).expect("at least one chunk").0 .1;
+        let sites = planet.sites();
+
+        let mut chunk_cells = vec![Vec::new(); centres.len()];
+        let mut extents = vec![0.0f64; centres.len()];
+        for (cell, &site) in sites.iter().enumerate() {
+            chunk_cells[nearest(site)].push(cell);
+        }
+        let mut chunk_triangles = vec![Vec::new(); centres.len()];
+        for (i, &[a, b, c]) in planet.triangles().iter().enumerate() {
+            let chunk = nearest(normalize(add(add(sites[a], sites[b]), sites[c])));
+            chunk_triangles[chunk].push(i);
+            for vertex in [a, b, c] {
+                extents[chunk] = extents[chunk].max(dot(centres[chunk], sites[vertex]).clamp(-1.0, 1.0).acos());
+            }
+        }
+        for (chunk, cells) in chunk_cells.iter().enumerate() {
+            for &cell in cells {
+                extents[chunk] = extents[chunk].max(dot(centres[chunk], sites[cell]).clamp(-1.0, 1.0).acos());
+            }
+        }
+
+        let mut writer = PlanetFileWriter {
+            cell_count: planet.cell_count(),
+            triangles: planet.triangles().iter().map(|t| t.map(|cell| cell as u32)).collect(),
+            radius: planet.radius(),
+            centres,
+            extents,
+            chunk_cells,
+            chunk_triangles,
+            layers: Vec::new(),
+            compression: Compression::ShuffledRle,
+        };
+        let positions = sites.iter().flat_map(|&(x, y, z)| [x as f32, y as f32, z as f32]).collect();
+        let biomes = planet.materials(&VegetationLayer::from_planet(planet)).into_iter().map(|material| material as u8).collect();
+        writer.add_layer("position", 3, LayerData::F32(positions));
+        writer.add_layer("elevation", 1, LayerData::F32(planet.elevation().iter().map(|&h| h as f32).collect()));
+        writer.add_layer("temperature", 1, LayerData::F32(planet.temperature().iter().map(|&t| t as f32).collect()));
+        writer.add_layer("biome", 1, LayerData::U8(biomes));
+        for (key, layer) in planet.custom_layers().iter() {
+            writer.add_layer(key, 1, layer.to_layer_data());
+        }
+        writer
+    }
+
+    /// Sets how blocks are stored; `ShuffledRle` by default. A block is stored raw whenever
+    /// compressing would not make it smaller.
+    pub fn compression(&mut self, compression: Compression) -> &mut PlanetFileWriter {
+        self.compression = compression;
+        self
+    }
+
+    /// Adds a per-cell layer, replacing any layer with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The layer name, at most 255 bytes.
+    /// * `components` - Values per cell, at least 1.
+    /// * `data` - `components` values for each cell, in cell order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is too long, `components` is 0, or `data` does not hold `components`
+    /// values per cell.
+    pub fn add_layer(&mut self, name: &str, components: usize, data: LayerData) -> &mut PlanetFileWriter {
+        assert!(name.len() <= u8::MAX as usize, "layer name '{}' is longer than 255 bytes", name);
+        assert!(components > 0, "layer '{}' needs at least one component", name);
+        assert_eq!(data.len(), self.cell_count * components, "layer '{}' needs {} values per cell", name, components);
+        self.layers.retain(|(info, _)| info.name != name);
+        let info = LayerInfo {
+            name: name.to_string(),
+            dtype: data.dtype(),
+            components,
+        };
+        self.layers.push((info, data));
+        self
+    }
+
+    /// Encodes the file in the format described on `PlanetFileWriter`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let chunk_count = self.centres.len();
+        let blocks_per_chunk = 2 + self.layers.len();
+        let triangles = LayerData::U32(self.triangles.iter().flatten().copied().collect());
+        let cell_ids = LayerData::U32((0..self.cell_count as u32).collect());
+
+        let mut blocks = Vec::with_capacity(chunk_count * blocks_per_chunk);
+        for chunk in 0..chunk_count {
+            let cells = &self.chunk_cells[chunk];
+            blocks.push(Block::encode(cell_ids.gather(cells, 1), 4, self.compression));
+            blocks.push(Block::encode(triangles.gather(&self.chunk_triangles[chunk], 3), 4, self.compression));
+            for (info, data) in &self.layers {
+                blocks.push(Block::encode(data.gather(cells, info.components), dtype_size(info.dtype), self.compression));
+            }
+        }
+
+        let mut tables = Vec::new();
+        for (info, _) in &self.layers {
+            tables.push(info.name.len() as u8);
+            tables.extend_from_slice(info.name.as_bytes());
+            tables.push(dtype_code(info.dtype));
+            tables.extend_from_slice(&(info.components as u32).to_le_bytes());
+        }
+        for chunk in 0..chunk_count {
+            let (x, y, z) = self.centres[chunk];
+            for value in [x as f32, y as f32, z as f32, self.extents[chunk] as f32] {
+                tables.extend_from_slice(&value.to_le_bytes());
+            }
+            tables.extend_from_slice(&(self.chunk_cells[chunk].len() as u32).to_le_bytes());
+            tables.extend_from_slice(&(self.chunk_triangles[chunk].len() as u32).to_le_bytes());
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.cell_count as u64).to_le_bytes());
+        out.extend_from_slice(&(self.triangles.len() as u64).to_le_bytes());
+        out.extend_from_slice(&self.radius.to_le_bytes());
+        out.extend_from_slice(&(chunk_count as u32).to_le_bytes());
+        out.extend_from_slice(&(self.layers.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(tables.len() as u32).to_le_bytes());
+        out.extend_from_slice(&tables);
+
+        let mut offset = (out.len() + blocks.len() * BLOCK_ENTRY_SIZE) as u64;
+        for block in &blocks {
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&(block.data.len() as u64).to_le_bytes());
+            out.extend_from_slice(&(block.raw_length as u64).to_le_bytes());
+            out.push(block.compression as u8);
+            out.extend_from_slice(&block.crc.to_le_bytes());
+            offset += block.data.len() as u64;
+        }
+        for block in blocks {
+            out.extend_from_slice(&block.data);
+        }
+        out
+    }
+
+    /// Writes the file to disk.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+// A directory entry
+#[derive(Debug, Clone, Copy)]
+struct BlockEntry {
+    offset: usize,
+    stored_length: usize,
+    raw_length: usize,
+    compression: Compression,
+    crc: u32,
+}
+
+// A chunk table entry
+#[derive(Debug, Clone, Copy)]
+struct ChunkEntry {
+    centre: Vec3,
+    extent: f64,
+    cell_count: usize,
+    triangle_count: usize,
+}
+
+// Reads little-endian values from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], PlanetFileError> {
+        if self.bytes.len() < count {
+            return Err(PlanetFileError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], PlanetFileError> {
+        Ok(self.take(N)?.try_into().expect("slice has N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, PlanetFileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, PlanetFileError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, PlanetFileError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, PlanetFileError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, PlanetFileError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    // A size that must fit in memory and in the data
+    fn size(&mut self, limit: usize) -> Result<usize, PlanetFileError> {
+        let value = self.u64()?;
+        if value > limit as u64 {
+            return Err(PlanetFileError::Truncated);
+        }
+        Ok(value as usize)
+    }
+}
+
+/// Random access to a planet file written by `PlanetFileWriter`.
+///
+/// Opening a file reads only its header, tables and directory; each block is decoded and
+/// checksummed when asked for. `B` is the storage: a `Vec<u8>` as read by `open`, a borrowed
+/// `&[u8]` (for example a file an engine has already mapped or packed into an asset bundle), or
+/// the `MappedFile` that `map` maps, so only the pages of the chunks actually read are loaded
+/// from disk.
+#[derive(Debug)]
+pub struct PlanetFile<B: AsRef<[u8]>> {
+    bytes: B,
+    cell_count: usize,
+    triangle_count: usize,
+    radius: f64,
+    layers: Vec<LayerInfo>,
+    chunks: Vec<ChunkEntry>,
+    blocks: Vec<BlockEntry>, // `2 + layers.len()` per chunk: cells, triangles, then the layers
+}
+
+impl<B: AsRef<[u8]>> PlanetFile<B> {
+    /// Parses the header, tables and directory of a planet file.
+    ///
+    /// # Returns
+    ///
+    /// The reader, or an error if the data is not a planet file or its directory points outside
+    /// the data.
+    pub fn from_bytes(bytes: B) -> Result<PlanetFile<B>, PlanetFileError> {
+        let data = bytes.as_ref();
+        let mut reader = Reader { bytes: data };
+        if reader.take(4).ok() != Some(MAGIC.as_slice()) {
+            return Err(PlanetFileError::NotAPlanetFile);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(PlanetFileError::UnsupportedVersion(version));
+        }
+        let cell_count = reader.size(u32::MAX as usize)?;
+        let triangle_count = reader.size(u32::MAX as usize)?;
+        let radius = reader.f64()?;
+        let chunk_count = reader.u32()? as usize;
+        let layer_count = reader.u32()? as usize;
+        let table_length = reader.u32()? as usize;
+        debug_assert_eq!(data.len() - reader.bytes.len(), HEADER_SIZE);
+        let mut tables = Reader { bytes: reader.take(table_length)? };
+
+        let mut layers = Vec::with_capacity(layer_count.min(256));
+        for _ in 0..layer_count {
+            let length = tables.u8()? as usize;
+            let name = String::from_utf8(tables.take(length)?.to_vec()).map_err(|_| PlanetFileError::Corrupt("layer name is not UTF-8".to_string()))?;
+            let dtype = dtype_from_code(tables.u8()?).ok_or_else(|| PlanetFileError::Corrupt(format!("layer '{}' has an unknown type", name)))?;
+            let components = tables.u32()? as usize;
+            layers.push(LayerInfo { name, dtype, components });
+        }
+        if tables.bytes.len() != chunk_count * CHUNK_ENTRY_SIZE {
+            return Err(PlanetFileError::Corrupt("chunk table size does not match the chunk count".to_string()));
+        }
+        let mut chunks = Vec::with_capacity(chunk_count);
+        for _ in 0..chunk_count {
+            let centre = (tables.f32()? as f64, tables.f32()? as f64, tables.f32()? as f64);
+            chunks.push(ChunkEntry {
+                centre,
+                extent: tables.f32()? as f64,
+                cell_count: tables.u32()? as usize,
+                triangle_count: tables.u32()? as usize,
+            });
+        }
+
+        let block_count = chunk_count * (2 + layers.len());
+        if reader.bytes.len() / BLOCK_ENTRY_SIZE < block_count {
+            return Err(PlanetFileError::Truncated);
+        }
+        let mut blocks = Vec::with_capacity(block_count);
+        for _ in 0..block_count {
+            let entry = BlockEntry {
+                offset: reader.size(data.len())?,
+                stored_length: reader.size(data.len())?,
+                raw_length: reader.size(u32::MAX as usize * 8)?,
+                compression: Compression::from_code(reader.u8()?).ok_or_else(|| PlanetFileError::Corrupt("unknown compression".to_string()))?,
+                crc: reader.u32()?,
+            };
+            if entry.offset + entry.stored_length > data.len() {
+                return Err(PlanetFileError::Truncated);
+            }
+            blocks.push(entry);
+        }
+
+        Ok(PlanetFile {
+            bytes,
+            cell_count,
+            triangle_count,
+            radius,
+            layers,
+            chunks,
+            blocks,
+        })
+    }
+
+    /// Returns the number of cells on the whole planet.
+    pub fn cell_count(&self) -> usize {
+        self.cell_count
+    }
+
+    /// Returns the number of triangles on the whole planet.
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+
+    /// Returns the radius in kilometres.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns the layers in the file.
+    pub fn layers(&self) -> &[LayerInfo] {
+        &self.layers
+    }
+
+    /// Returns the number of chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the unit direction of a chunk's centre in TerraForge's frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of range.
+    pub fn chunk_centre(&self, chunk: usize) -> Vec3 {
+        self.chunks[chunk].centre
+    }
+
+    /// Returns the number of cells in a chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of range.
+    pub fn chunk_cell_count(&self, chunk: usize) -> usize {
+        self.chunks[chunk].cell_count
+    }
+
+    /// Returns the number of triangles in a chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk` is out of range.
+    pub fn chunk_triangle_count(&self, chunk: usize) -> usize {
+        self.chunks[chunk].triangle_count
+    }
+
+    /// Returns the chunk holding the cells nearest a direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - Any non-zero vector in TerraForge's frame, e.g. a camera position.
+    pub fn chunk_at(&self, direction: Vec3) -> usize {
+        let direction = normalize(direction);
+        (0..self.chunks.len())
+            .max_by(|&a, &b| dot(self.chunks[a].centre, direction).total_cmp(&dot(self.chunks[b].centre, direction)))
+            .unwrap_or(0)
+    }
+
+    /// Returns every chunk with a cell or triangle within a distance of a surface point.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - Any non-zero vector in TerraForge's frame pointing at the point.
+    /// * `distance_km` - The great-circle distance along the surface.
+    pub fn chunks_within(&self, direction: Vec3, distance_km: f64) -> Vec<usize> {
+        let direction = normalize(direction);
+        let angle = distance_km / self.radius;
+        (0..self.chunks.len())
+            .filter(|&chunk| {
+                let entry = &self.chunks[chunk];
+                dot(entry.centre, direction).clamp(-1.0, 1.0).acos() <= angle + entry.extent
+            })
+            .collect()
+    }
+
+    // Decodes and verifies one block
+    fn block(&self, chunk: usize, index: usize) -> Result<Vec<u8>, PlanetFileError> {
+        if chunk >= self.chunks.len() {
+            return Err(PlanetFileError::ChunkOutOfRange(chunk));
+        }
+        let entry = self.blocks[chunk * (2 + self.layers.len()) + index];
+        let stored = &self.bytes.as_ref()[entry.offset..entry.offset + entry.stored_length];
+        let element_size = match index {
+            0 | 1 => 4,
+            _ => dtype_size(self.layers[index - 2].dtype),
+        };
+        let raw = match entry.compression {
+            Compression::None => Some(stored.to_vec()),
+            Compression::ShuffledRle => rle_decode(stored, entry.raw_length).map(|shuffled| unshuffle(&shuffled, element_size)),
+        };
+        match raw {
+            Some(raw) if raw.len() == entry.raw_length && crc32(&raw) == entry.crc => Ok(raw),
+            _ => Err(PlanetFileError::Corrupt(format!("block {} of chunk {} fails its checksum", index, chunk))),
+        }
+    }
+
+    /// Returns the global ids of a chunk's cells, the order its layer values follow.
+    pub fn chunk_cells(&self, chunk: usize) -> Result<Vec<u32>, PlanetFileError> {
+        Ok(self.block(chunk, 0)?.chunks_exact(4).map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes"))).collect())
+    }
+
+    /// Returns a chunk's triangles as global cell ids, wound counter-clockwise from outside.
+    ///
+    /// Triangles along a chunk border can use cells of the neighbouring chunk.
+    pub fn chunk_triangles(&self, chunk: usize) -> Result<Vec<[u32; 3]>, PlanetFileError> {
+        let ids: Vec<u32> = self.block(chunk, 1)?.chunks_exact(4).map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes"))).collect();
+        Ok(ids.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect())
+    }
+
+    /// Reads one layer of one chunk, `components` values per cell in `chunk_cells` order.
+    pub fn read_layer(&self, name: &str, chunk: usize) -> Result<LayerData, PlanetFileError> {
+        let layer = self.layers.iter().position(|info| info.name == name).ok_or_else(|| PlanetFileError::UnknownLayer(name.to_string()))?;
+        let raw = self.block(chunk, 2 + layer)?;
+        Ok(LayerData::from_bytes(self.layers[layer].dtype, &raw))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PlanetFile<Vec<u8>> {
+    /// Reads a planet file into memory and parses its directory.
+    pub fn open(path: &str) -> Result<PlanetFile<Vec<u8>>, PlanetFileError> {
+        let bytes = fs::read(path).map_err(|error| PlanetFileError::Io(format!("{}: {}", path, error)))?;
+        PlanetFile::from_bytes(bytes)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PlanetFile<MappedFile> {
+    /// Maps a planet file into memory and parses its directory, so only the pages of the chunks
+    /// actually read are loaded from disk.
+    ///
+    /// # Safety
+    ///
+    /// The same as for `MappedFile::open`: nothing may truncate or modify the file while the
+    /// returned reader exists.
+    pub unsafe fn map(path: &str) -> Result<PlanetFile<MappedFile>, PlanetFileError> {
+        // SAFETY: the caller upholds `MappedFile::open`'s contract for the reader's lifetime
+        let mapped = unsafe { MappedFile::open(path) }.map_err(|error| PlanetFileError::Io(format!("{}: {}", path, error)))?;
+        PlanetFile::from_bytes(mapped)
+    }
+}
+
+/// A read-only view of a whole file, memory-mapped on Unix and read into memory elsewhere.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct MappedFile {
+    #[cfg(unix)]
+    pointer: *mut libc::c_void,
+    #[cfg(unix)]
+    length: usize,
+    #[cfg(not(unix))]
+    bytes: Vec<u8>,
+}
+
+// The mapping is private and read-only, so sharing it between threads is sound
+#[cfg(all(unix, not(target_arch = "wasm32")))]
+unsafe impl Send for MappedFile {}
+#[cfg(all(unix, not(target_arch = "wasm32")))]
+unsafe impl Sync for MappedFile {}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MappedFile {
+    /// Maps a file.
+    ///
+    /// # Safety
+    ///
+    /// The mapping hands out the file's bytes as an immutable `&[u8]`, so nothing, in this process
+    /// or another, may truncate or modify the file while the `MappedFile` exists. Reading a page
+    /// past the end of a truncated file raises `SIGBUS`, and a modified file changes bytes the
+    /// borrow checker assumes are frozen.
+    #[cfg(unix)]
+    pub unsafe fn open(path: &str) -> io::Result<MappedFile> {
+        use std::os::unix::io::AsRawFd;
+        let file = fs::File::open(path)?;
+        let length = usize::try_from(file.metadata()?.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file is too large to map"))?;
+        if length == 0 {
+            return Ok(MappedFile {
+                pointer: std::ptr::null_mut(),
+                length,
+            });
+        }
+        // SAFETY: a fresh private read-only mapping of an open file descriptor; the descriptor
+        // may be closed once the mapping exists
+        let pointer = unsafe { libc::mmap(std::ptr::null_mut(), length, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if pointer == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MappedFile { pointer, length })
+    }
+
+    /// Reads a file into memory.
+    ///
+    /// # Safety
+    ///
+    /// Always safe here; the function is `unsafe` only to match the memory-mapping version on Unix.
+    #[cfg(not(unix))]
+    pub unsafe fn open(path: &str) -> io::Result<MappedFile> {
+        Ok(MappedFile { bytes: fs::read(path)? })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AsRef<[u8]> for MappedFile {
+    #[cfg(unix)]
+    fn as_ref(&self) -> &[u8] {
+        if self.length == 0 {
+            return &[];
+        }
+        // SAFETY: the mapping covers `length` readable bytes and lives as long as `self`
+        unsafe { std::slice::from_raw_parts(self.pointer as *const u8, self.length) }
+    }
+
+    #[cfg(not(unix))]
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(all(unix, not(target_arch = "wasm32")))]
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.length > 0 {
+            // SAFETY: unmaps exactly the mapping created in `open`, which no borrow outlives
+            unsafe {
+                libc::munmap(self.pointer, self.length);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procedural::PlanetParameters;
+    use uuid::Uuid;
+
+    fn samples() -> Vec<Vec<u8>> {
+        let mut noise = Vec::new();
+        let mut state = 0x9E37_79B9u32;
+        for _ in 0..1000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            noise.push(state as u8);
+        }
+        vec![
+            Vec::new(),
+            vec![7],
+            vec![7, 7],
+            vec![1, 2],
+            vec![0; 128],
+            vec![0; 129],
+            vec![3; 1000],
+            (0..=255).collect(),
+            (0..600).map(|i| (i / 5) as u8).collect(),
+            noise,
+        ]
+    }
+
+    fn small_planet_file() -> Vec<u8> {
+        let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x7F9));
+        parameters.samples = 500;
+        let mut writer = PlanetFileWriter::new(&parameters.build(), 8);
+        writer.add_layer("ids", 2, LayerData::U64((0..1000).collect()));
+        writer.to_bytes()
+    }
+
+    #[test]
+    fn rle_round_trips() {
+        for bytes in samples() {
+            assert_eq!(rle_decode(&rle_encode(&bytes), bytes.len()), Some(bytes.clone()));
+            assert_eq!(unshuffle(&shuffle(&bytes, 4), 4)[..bytes.len() / 4 * 4], bytes[..bytes.len() / 4 * 4]);
+        }
+    }
+
+    #[test]
+    fn rle_rejects_truncated_and_mislabelled_input() {
+        for bytes in samples().into_iter().filter(|bytes| !bytes.is_empty()) {
+            let encoded = rle_encode(&bytes);
+            // Every cut either ends a control byte's data early or decodes to too few bytes
+            for cut in 0..encoded.len() {
+                assert_eq!(rle_decode(&encoded[..cut], bytes.len()), None);
+            }
+            assert_eq!(rle_decode(&encoded, bytes.len() - 1), None);
+            assert_eq!(rle_decode(&encoded, bytes.len() + 1), None);
+        }
+        assert_eq!(rle_decode(&[0x81, 5], usize::MAX), None);
+    }
+
+    #[test]
+    fn planet_file_round_trips() {
+        let bytes = small_planet_file();
+        let file = PlanetFile::from_bytes(bytes.as_slice()).unwrap();
+        assert_eq!(file.chunk_count(), 8);
+        assert_eq!(file.layers().len(), 5);
+
+        let mut seen = vec![false; file.cell_count()];
+        for chunk in 0..file.chunk_count() {
+            let cells = file.chunk_cells(chunk).unwrap();
+            assert_eq!(cells.len(), file.chunk_cell_count(chunk));
+            assert_eq!(file.chunk_triangles(chunk).unwrap().len(), file.chunk_triangle_count(chunk));
+            let expected: Vec<u64> = cells.iter().flat_map(|&cell| [2 * cell as u64, 2 * cell as u64 + 1]).collect();
+            assert_eq!(file.read_layer("ids", chunk), Ok(LayerData::U64(expected)));
+            for cell in cells {
+                assert!(!seen[cell as usize], "cell {} is in two chunks", cell);
+                seen[cell as usize] = true;
+            }
+        }
+        assert!(seen.iter().all(|&seen| seen));
+        assert_eq!(file.read_layer("missing", 0), Err(PlanetFileError::UnknownLayer("missing".to_string())));
+        assert_eq!(file.chunk_cells(8), Err(PlanetFileError::ChunkOutOfRange(8)));
+    }
+
+    #[test]
+    fn truncated_and_corrupt_files_are_rejected() {
+        let bytes = small_planet_file();
+        assert_eq!(PlanetFile::from_bytes(&bytes[..3]).err(), Some(PlanetFileError::NotAPlanetFile));
+        // Any cut before the last block leaves a block the directory points past
+        for cut in [10, HEADER_SIZE, bytes.len() / 2, bytes.len() - 1] {
+            assert_eq!(PlanetFile::from_bytes(&bytes[..cut]).err(), Some(PlanetFileError::Truncated), "cut at {}", cut);
+        }
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 0xFF;
+        let file = PlanetFile::from_bytes(flipped).unwrap();
+        let last = file.chunk_count() - 1;
+        assert!(matches!(file.read_layer("ids", last), Err(PlanetFileError::Corrupt(_))));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn opened_and_mapped_files_match() {
+        let bytes = small_planet_file();
+        let path = std::env::temp_dir().join(format!("terraforge-planet-file-{}.tfp", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, &bytes).unwrap();
+        let opened = PlanetFile::open(path).unwrap();
+        // SAFETY: the file is private to this test and is not changed while mapped
+        let mapped = unsafe { PlanetFile::map(path) }.unwrap();
+        for chunk in 0..opened.chunk_count() {
+            assert_eq!(opened.read_layer("elevation", chunk), mapped.read_layer("elevation", chunk));
+        }
+        drop(mapped);
+        fs::remove_file(path).unwrap();
+        assert!(matches!(PlanetFile::open(path), Err(PlanetFileError::Io(_))));
+    }
+}
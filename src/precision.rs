@@ -0,0 +1,139 @@
+use crate::atmosphere::Atmosphere;
+use crate::climate::OrbitalCalendar;
+use crate::geology::RockType;
+use crate::layers::LayerMap;
+use crate::tides::Tides;
+
+/// A floating-point type that per-cell data can be stored in.
+///
+/// Generation always computes in `f64`; `f32` storage halves the memory of positions and layers
+/// and matches what GPUs take, at about seven significant digits, which is millimetres of
+/// elevation and under a metre of position on an Earth-sized planet.
+pub trait Precision: Copy + Default + PartialEq + PartialOrd + Send + Sync + std::fmt::Debug + 'static {
+    /// Converts from `f64`, rounding to the nearest representable value.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts to `f64` exactly.
+    fn to_f64(self) -> f64;
+}
+
+impl Precision for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Precision for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Converts a point cloud, e.g. from `seeded_fibonacci_sphere`, to arrays of a storage precision.
+pub fn points_as<T: Precision>(points: &[(f64, f64, f64)]) -> Vec<[T; 3]> {
+    points.iter().map(|&(x, y, z)| [T::from_f64(x), T::from_f64(y), T::from_f64(z)]).collect()
+}
+
+/// Converts values to a storage precision.
+pub fn values_as<T: Precision>(values: &[f64]) -> Vec<T> {
+    values.iter().map(|&value| T::from_f64(value)).collect()
+}
+
+/// A generated planet stored compactly for keeping many planets resident or uploading them to a
+/// GPU: sites and layers in precision `T`, and triangles and adjacency as `u32` indices.
+///
+/// Created with `Planet::compact` after generating in `f64`; `Planet::from_compact` turns it back
+/// into a `Planet` without triangulating again. A `CompactPlanet<f32>` takes about a third of the
+/// memory of the `Planet` it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactPlanet<T: Precision> {
+    pub(crate) positions: Vec<[T; 3]>, // Cell sites on the unit sphere
+    pub(crate) triangles: Vec<[u32; 3]>,
+    pub(crate) neighbor_offsets: Vec<u32>, // Cell i's neighbors are neighbors[offsets[i]..offsets[i + 1]]
+    pub(crate) neighbors: Vec<u32>,
+    pub(crate) elevation: Vec<T>,   // Metres relative to sea level
+    pub(crate) temperature: Vec<T>, // Degrees Celsius
+    pub(crate) snow_cover: Vec<T>,  // Fraction, 0.0 to 1.0
+    pub(crate) rock_types: Vec<RockType>,
+    pub(crate) custom_layers: LayerMap,
+    pub(crate) radius: f64, // Kilometres
+    pub(crate) surface_gravity: f64,
+    pub(crate) atmosphere: Atmosphere,
+    pub(crate) tides: Tides,
+    pub(crate) axial_tilt: f64,
+    pub(crate) calendar: OrbitalCalendar,
+    pub(crate) solar_flux: f64,
+    pub(crate) day: f64,
+}
+
+impl<T: Precision> CompactPlanet<T> {
+    /// Returns the number of cells.
+    pub fn cell_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the cell sites on the unit sphere.
+    pub fn positions(&self) -> &[[T; 3]] {
+        &self.positions
+    }
+
+    /// Returns the Delaunay triangles, wound counter-clockwise seen from outside.
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    /// Returns the neighbors of a cell.
+    pub fn neighbors(&self, cell: usize) -> &[u32] {
+        &self.neighbors[self.neighbor_offsets[cell] as usize..self.neighbor_offsets[cell + 1] as usize]
+    }
+
+    /// Returns the elevation of every cell in metres relative to sea level.
+    pub fn elevation(&self) -> &[T] {
+        &self.elevation
+    }
+
+    /// Returns the surface temperature of every cell in degrees Celsius.
+    pub fn temperature(&self) -> &[T] {
+        &self.temperature
+    }
+
+    /// Returns the snow cover fraction of every cell.
+    pub fn snow_cover(&self) -> &[T] {
+        &self.snow_cover
+    }
+
+    /// Returns the bedrock of every cell.
+    pub fn rock_types(&self) -> &[RockType] {
+        &self.rock_types
+    }
+
+    /// Returns the user-defined per-cell layers, kept at full precision.
+    pub fn custom_layers(&self) -> &LayerMap {
+        &self.custom_layers
+    }
+
+    /// Returns the planet radius in kilometres.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Estimates the heap memory of the compact planet in bytes, comparable to
+    /// `Planet::memory_bytes`.
+    pub fn memory_bytes(&self) -> usize {
+        let scalar = std::mem::size_of::<T>();
+        self.positions.capacity() * 3 * scalar
+            + self.triangles.capacity() * 12
+            + (self.neighbor_offsets.capacity() + self.neighbors.capacity()) * 4
+            + (self.elevation.capacity() + self.temperature.capacity() + self.snow_cover.capacity()) * scalar
+            + self.rock_types.capacity() * std::mem::size_of::<RockType>()
+            + self.custom_layers.memory_bytes()
+    }
+}
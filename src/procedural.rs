@@ -0,0 +1,434 @@
+use crate::atmosphere::Atmosphere;
+use crate::climate::{OrbitalCalendar, SOLAR_CONSTANT};
+use crate::features::SurfaceFeature;
+use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+use crate::geologic_events::{GeologicActivity, GeologicEventSettings};
+use crate::geologic_time::{GeologicHistory, GeologySettings};
+use crate::guid::{derive_guid, guid_seed, SEED_DISCRIMINANT};
+use crate::hazards::{HazardSettings, Hazards};
+use crate::hex_grid::HexGrid;
+use crate::hydrology::Drainage;
+use crate::planet::Planet;
+use crate::sampling::{density_sphere, poisson_radius, poisson_sphere, regions_density, symmetric_sphere, DetailRegion, SiteSampling};
+use crate::star_system::{Moon, StarSystem, SystemPlanet};
+use crate::surfaces::{Surface, SurfaceKind};
+use crate::tectonics::Tectonics;
+use crate::terrain::TerrainSettings;
+use crate::tides::Tides;
+use crate::vegetation::VegetationLayer;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use uuid::Uuid;
+
+/// The broad thermal regime of a planet, set by the stellar flux it receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClimateBand {
+    /// Well beyond the habitable zone; oceans freeze solid.
+    Frozen,
+    /// Near the outer edge of the habitable zone.
+    Cold,
+    /// Earth-like irradiance.
+    Temperate,
+    /// Near the inner edge of the habitable zone.
+    Warm,
+    /// Well inside the habitable zone; surface water boils away.
+    Scorching,
+}
+
+impl ClimateBand {
+    /// Returns the range of stellar flux covered by this band, as a fraction of Earth's.
+    pub fn flux_range(&self) -> (f64, f64) {
+        match self {
+            ClimateBand::Frozen => (0.1, 0.45),
+            ClimateBand::Cold => (0.45, 0.8),
+            ClimateBand::Temperate => (0.8, 1.15),
+            ClimateBand::Warm => (1.15, 1.6),
+            ClimateBand::Scorching => (1.6, 4.0),
+        }
+    }
+
+    /// Returns the band covering a stellar flux given as a fraction of Earth's.
+    pub fn from_relative_flux(relative_flux: f64) -> ClimateBand {
+        [ClimateBand::Frozen, ClimateBand::Cold, ClimateBand::Temperate, ClimateBand::Warm]
+            .into_iter()
+            .find(|band| relative_flux < band.flux_range().1)
+            .unwrap_or(ClimateBand::Scorching)
+    }
+}
+
+/// Everything needed to build a planet surface, derived deterministically from a planet GUID.
+///
+/// Every subsystem gets its own seed derived from the GUID, so adding a subsystem never shifts
+/// the output of another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanetParameters {
+    pub guid: Uuid,
+    pub radius: f64,          // Kilometres
+    pub surface_gravity: f64, // m/s²
+    pub samples: usize,       // Number of surface cells
+    pub jitter: f64,          // Site jitter, 0.0 to 1.0
+    pub sampling: SiteSampling,
+    pub symmetry: u32,        // N-fold rotational symmetry about the rotation axis for fair multiplayer maps; 1 for none
+    pub plate_count: usize,
+    pub axial_tilt: f64,      // Degrees
+    pub calendar: OrbitalCalendar,
+    pub climate_band: ClimateBand,
+    pub solar_flux: f64,      // W/m²
+    pub terrain: TerrainSettings,
+    pub detail_regions: Vec<DetailRegion>, // Areas with finer (or coarser) cells; empty for a uniform layout
+    pub surface: SurfaceKind,
+    pub tides: Tides,
+    pub sites_seed: u64,
+    pub terrain_seed: u64,
+    pub tectonics_seed: u64,
+    pub climate_seed: u64,
+    pub resources_seed: u64,
+    pub settlements_seed: u64,
+    pub atmosphere_seed: u64,
+    pub surface_seed: u64,
+    pub events_seed: u64,
+    pub hazards_seed: u64,
+}
+
+// Highest peaks a derived planet gets, leaving headroom below the ±20 km the planet guards for
+// landforms raised on top of the terrain
+const MAX_PEAK_ELEVATION: f64 = 15_000.0;
+
+// The height of the peaks on a planet with this surface gravity in m/s², from their height under
+// Earth's gravity
+fn peak_elevation(earth_peak: f64, surface_gravity: f64) -> f64 {
+    (earth_peak * 9.80665 / surface_gravity).min(MAX_PEAK_ELEVATION)
+}
+
+impl PlanetParameters {
+    /// Derives planet parameters from a planet GUID alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `guid` - The planet GUID, e.g. `SystemPlanet::guid`.
+    pub fn from_guid(guid: Uuid) -> PlanetParameters {
+        PlanetParameters::derive(guid).0
+    }
+
+    // Derives the parameters of `from_guid`, along with the height of the planet's peaks under
+    // Earth's gravity so callers that change the gravity can rescale them
+    fn derive(guid: Uuid) -> (PlanetParameters, f64) {
+        let seed = |index: u64| guid_seed(derive_guid(guid, SEED_DISCRIMINANT, index));
+        let mut rng = StdRng::seed_from_u64(guid_seed(guid));
+
+        let radius = 6371.0 * rng.gen_range(0.4f64..1.6);
+        let climate_band = match rng.gen_range(0..10) {
+            0 | 1 => ClimateBand::Frozen,
+            2 | 3 => ClimateBand::Cold,
+            4..=6 => ClimateBand::Temperate,
+            7 | 8 => ClimateBand::Warm,
+            _ => ClimateBand::Scorching,
+        };
+        let (low, high) = climate_band.flux_range();
+        let solar_flux = SOLAR_CONSTANT * rng.gen_range(low..high);
+        let year_length_days = rng.gen_range(80.0f64..900.0);
+        let day_length_hours = rng.gen_range(10.0f64..60.0);
+        let land_fraction = rng.gen_range(0.1f64..0.6);
+        let samples = rng.gen_range(4000..=12000);
+        let jitter = rng.gen_range(0.0f64..0.5);
+        let plate_count = rng.gen_range(6..=18);
+        let axial_tilt = rng.gen::<f64>().powi(2) * 45.0;
+        let continent_frequency = rng.gen_range(1.0f64..2.6);
+        let earth_peak = rng.gen_range(5000.0f64..9000.0);
+
+        let parameters = PlanetParameters {
+            guid,
+            radius,
+            // Constant density: gravity scales with radius
+            surface_gravity: 9.80665 * radius / 6371.0,
+            samples,
+            jitter,
+            sampling: SiteSampling::Fibonacci,
+            symmetry: 1,
+            plate_count,
+            axial_tilt,
+            calendar: OrbitalCalendar::new(year_length_days, day_length_hours),
+            climate_band,
+            solar_flux,
+            terrain: TerrainSettings {
+                continent_frequency,
+                land_fraction,
+                // Smaller planets, with weaker gravity, support taller mountains
+                max_elevation: (earth_peak * 6371.0 / radius.max(1.0)).min(MAX_PEAK_ELEVATION),
+                max_depth: rng.gen_range(6000.0f64..11000.0),
+                mountain_strength: rng.gen_range(0.3f64..0.7),
+            },
+            detail_regions: Vec::new(),
+            surface: SurfaceKind::Rocky,
+            tides: Tides::default(),
+            sites_seed: seed(0),
+            terrain_seed: seed(1),
+            tectonics_seed: seed(2),
+            climate_seed: seed(3),
+            resources_seed: seed(4),
+            settlements_seed: seed(5),
+            atmosphere_seed: seed(6),
+            surface_seed: seed(7),
+            events_seed: seed(8),
+            hazards_seed: seed(9),
+        };
+        (parameters, earth_peak)
+    }
+
+    /// Derives planet parameters for a planet of a generated star system.
+    ///
+    /// Starts from `from_guid` with the planet's GUID, then takes the radius, year length and
+    /// stellar flux from the system so the surface agrees with the orbit, rescales the peaks to the
+    /// planet's gravity, picks the surface kind
+    /// from the planet's composition and equilibrium temperature, and raises tides from its moons
+    /// and star.
+    pub fn from_system_planet(system: &StarSystem, planet: &SystemPlanet) -> PlanetParameters {
+        let (mut parameters, earth_peak) = PlanetParameters::derive(planet.guid);
+        parameters.radius = planet.radius;
+        parameters.surface_gravity = 9.80665 * planet.mass / (planet.radius / 6371.0).powi(2);
+        parameters.terrain.max_elevation = peak_elevation(earth_peak, parameters.surface_gravity);
+        let day_length_hours = parameters.calendar.day_length_hours();
+        parameters.calendar = OrbitalCalendar::new(planet.orbit.period * 24.0 / day_length_hours, day_length_hours);
+        parameters.solar_flux = system.stellar_flux(planet);
+        parameters.climate_band = ClimateBand::from_relative_flux(parameters.solar_flux / SOLAR_CONSTANT);
+        parameters.surface = SurfaceKind::for_planet(planet.kind, planet.equilibrium_temperature);
+        parameters.tides = Tides::from_system(system, planet);
+        parameters
+    }
+
+    /// Derives planet parameters for a moon of a generated star system.
+    ///
+    /// Starts from `from_guid` with the moon's GUID, then takes the radius from the moon, rescaling
+    /// the peaks to its gravity, and the year length and stellar flux from its planet. Tidally locked moons turn once per orbit. Icy
+    /// moons are ice worlds; rocky moons pick their surface from the planet's equilibrium
+    /// temperature.
+    pub fn from_moon(system: &StarSystem, planet: &SystemPlanet, moon: &Moon) -> PlanetParameters {
+        let (mut parameters, earth_peak) = PlanetParameters::derive(moon.guid);
+        parameters.radius = moon.radius;
+        parameters.surface_gravity = 9.80665 * moon.mass / (moon.radius / 6371.0).powi(2);
+        parameters.terrain.max_elevation = peak_elevation(earth_peak, parameters.surface_gravity);
+        let day_length_hours = if moon.tidally_locked { moon.orbit.period * 24.0 } else { parameters.calendar.day_length_hours() };
+        parameters.calendar = OrbitalCalendar::new(planet.orbit.period * 24.0 / day_length_hours, day_length_hours);
+        parameters.solar_flux = system.stellar_flux(planet);
+        parameters.climate_band = ClimateBand::from_relative_flux(parameters.solar_flux / SOLAR_CONSTANT);
+        parameters.surface = SurfaceKind::for_moon(moon.kind, planet.equilibrium_temperature);
+        parameters
+    }
+
+    /// Builds the planet surface described by these parameters.
+    ///
+    /// Sites, terrain, rock types and the atmosphere are generated from the derived seeds, the
+    /// elevation is reshaped for the `surface` kind with `Planet::shape_surface`, and the climate is
+    /// reset to equilibrium, warmed by the atmosphere's greenhouse effect, at day 0. Sites follow
+    /// `sampling`, giving about `samples` cells with Poisson sampling and the nearest `10 * n * n + 2`
+    /// with Goldberg, unless `detail_regions` asks for finer or coarser cells somewhere, which always
+    /// uses the jittered lattice of `sampling::density_sphere`. A `symmetry` above 1 repeats the sites
+    /// of the first wedge of longitude with `sampling::symmetric_sphere` and the terrain with
+    /// `Planet::repeat_terrain`, so every wedge has the same landmasses and biomes.
+    pub fn build(&self) -> Planet {
+        self.build_stages(|_| {})
+    }
+
+    /// Builds the planet like `build` and reports how long each stage took.
+    ///
+    /// # Returns
+    ///
+    /// The planet, identical to the one `build` returns, and its `GenerationStats`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_with_stats(&self) -> (Planet, GenerationStats) {
+        let mut marks: Vec<(&'static str, Instant)> = Vec::new();
+        let planet = self.build_stages(|stage| marks.push((stage, Instant::now())));
+        let end = Instant::now();
+        let stages = marks
+            .iter()
+            .enumerate()
+            .map(|(i, &(stage, start))| (stage, marks.get(i + 1).map_or(end, |&(_, next)| next) - start))
+            .collect();
+        let stats = GenerationStats {
+            stages,
+            cell_count: planet.cell_count(),
+            triangle_count: planet.triangles().len(),
+            memory_bytes: planet.memory_bytes(),
+        };
+        (planet, stats)
+    }
+
+    // Runs the build pipeline, calling `stage` with each stage's name as it starts
+    fn build_stages(&self, mut stage: impl FnMut(&'static str)) -> Planet {
+        stage("sites");
+        let sites = self.sites();
+        stage("triangulation");
+        let mut planet = self.triangulate(sites);
+        stage("terrain");
+        planet.generate_terrain(self.terrain_seed, &self.terrain);
+        stage("surface");
+        planet.shape_surface(self.surface);
+        planet.repeat_terrain(self.symmetry);
+        planet
+    }
+
+    // The "sites" stage: the cell sites on the unit sphere
+    pub(crate) fn sites(&self) -> Vec<(f64, f64, f64)> {
+        let sites = if !self.detail_regions.is_empty() {
+            density_sphere(self.samples, self.jitter, self.sites_seed, |point| regions_density(&self.detail_regions, point))
+        } else {
+            match self.sampling {
+                SiteSampling::Fibonacci => seeded_fibonacci_sphere(self.samples, self.jitter, self.sites_seed),
+                SiteSampling::Poisson => poisson_sphere(poisson_radius(self.samples), self.sites_seed),
+                SiteSampling::Goldberg => HexGrid::with_cell_count(self.samples).centers().to_vec(),
+            }
+        };
+        symmetric_sphere(sites, self.symmetry)
+    }
+
+    // The "triangulation" stage: a bare planet on the sites with these physical parameters
+    pub(crate) fn triangulate(&self, sites: Vec<(f64, f64, f64)>) -> Planet {
+        let mut planet = Planet::new(sites);
+        planet.set_radius(self.radius);
+        planet.set_surface_gravity(self.surface_gravity);
+        planet.set_axial_tilt(self.axial_tilt);
+        planet.set_calendar(self.calendar.clone());
+        planet.set_solar_flux(self.solar_flux);
+        planet.set_atmosphere(self.atmosphere());
+        planet.set_tides(self.tides.clone());
+        planet
+    }
+
+    /// Returns the mass implied by the radius and surface gravity, in Earth masses.
+    pub fn mass(&self) -> f64 {
+        self.surface_gravity / 9.80665 * (self.radius / 6371.0).powi(2)
+    }
+
+    /// Generates the atmosphere of a planet built from these parameters, see
+    /// `Atmosphere::generate`. It follows the radius, surface gravity and solar flux, so it
+    /// changes with them.
+    pub fn atmosphere(&self) -> Atmosphere {
+        Atmosphere::generate(self.atmosphere_seed, self.mass(), self.radius, self.solar_flux)
+    }
+
+    /// Generates the surface colors of a planet built from these parameters, see
+    /// `Surface::generate`.
+    pub fn surface(&self, planet: &Planet) -> Surface {
+        Surface::generate(planet, self.surface, self.surface_seed)
+    }
+
+    /// Generates the plate layout of a planet built from these parameters.
+    pub fn tectonics(&self, planet: &Planet) -> Tectonics {
+        Tectonics::generate(planet, self.tectonics_seed, self.plate_count)
+    }
+
+    /// Sets up earthquake and eruption sampling for a planet built from these parameters, see
+    /// `GeologicActivity::new`.
+    pub fn geologic_activity(&self, planet: &Planet, tectonics: &Tectonics, volcanoes: &[SurfaceFeature], settings: GeologicEventSettings) -> GeologicActivity {
+        GeologicActivity::new(planet, tectonics, volcanoes, self.events_seed, settings)
+    }
+
+    /// Starts the geological history of a planet built from these parameters from its plate
+    /// layout, for `Planet::advance_geology`.
+    pub fn geologic_history(&self, planet: &Planet, settings: GeologySettings) -> GeologicHistory {
+        GeologicHistory::new(self.tectonics(planet), settings)
+    }
+
+    /// Sets up wildfires, floods and droughts on a planet built from these parameters, with its
+    /// own vegetation and rivers, see `Hazards::new`.
+    pub fn hazards(&self, planet: &Planet, settings: HazardSettings) -> Hazards {
+        Hazards::new(planet, &VegetationLayer::from_planet(planet), Drainage::compute(planet), self.hazards_seed, settings)
+    }
+}
+
+/// Builds many planets at once on the current rayon thread pool, e.g. to pre-generate the worlds
+/// of a server at startup.
+///
+/// Each stage runs for every planet before the next starts, so the triangulations, which are
+/// single-threaded, run side by side on different cores while the parallel stages split their
+/// work across the same threads instead of each planet starting its own. Use
+/// `generate_batch_in` to cap the number of threads.
+///
+/// # Arguments
+///
+/// * `specs` - The parameters of every planet.
+///
+/// # Returns
+///
+/// The planets in the order of `specs`, each identical to the one `PlanetParameters::build`
+/// returns.
+pub fn generate_batch(specs: &[PlanetParameters]) -> Vec<Planet> {
+    let sites: Vec<Vec<(f64, f64, f64)>> = specs.par_iter().map(|spec| spec.sites()).collect();
+    let mut planets: Vec<Planet> = specs.par_iter().zip(sites).map(|(spec, sites)| spec.triangulate(sites)).collect();
+    planets.par_iter_mut().zip(specs).for_each(|(planet, spec)| planet.generate_terrain(spec.terrain_seed, &spec.terrain));
+    planets.par_iter_mut().zip(specs).for_each(|(planet, spec)| {
+        planet.shape_surface(spec.surface);
+        planet.repeat_terrain(spec.symmetry);
+    });
+    planets
+}
+
+/// Builds many planets like `generate_batch` on a given thread pool, which can be kept and reused
+/// across batches and sized to leave cores free for other work.
+///
+/// # Arguments
+///
+/// * `pool` - The pool to run every stage on, e.g. built once with
+///   `rayon::ThreadPoolBuilder::new().num_threads(4).build()`.
+/// * `specs` - The parameters of every planet.
+pub fn generate_batch_in(pool: &rayon::ThreadPool, specs: &[PlanetParameters]) -> Vec<Planet> {
+    pool.install(|| generate_batch(specs))
+}
+
+/// Timings and sizes of one run of the generation pipeline, from
+/// `PlanetParameters::build_with_stats`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GenerationStats {
+    pub stages: Vec<(&'static str, Duration)>, // Wall-clock time of each stage in pipeline order
+    pub cell_count: usize,
+    pub triangle_count: usize,
+    pub memory_bytes: usize, // Estimated heap size of the planet, see `Planet::memory_bytes`
+}
+
+impl GenerationStats {
+    /// Returns the time of the whole pipeline.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|&(_, time)| time).sum()
+    }
+
+    /// Returns the time of one stage, e.g. `"triangulation"`, or `None` if no stage has that name.
+    pub fn stage(&self, name: &str) -> Option<Duration> {
+        self.stages.iter().find(|&&(stage, _)| stage == name).map(|&(_, time)| time)
+    }
+}
+
+impl std::fmt::Display for GenerationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} cells, {} triangles, {:.1} MiB", self.cell_count, self.triangle_count, self.memory_bytes as f64 / (1024.0 * 1024.0))?;
+        for (stage, time) in &self.stages {
+            write!(f, "; {} {:.1} ms", stage, time.as_secs_f64() * 1000.0)?;
+        }
+        write!(f, "; total {:.1} ms", self.total().as_secs_f64() * 1000.0)
+    }
+}
+
+impl Planet {
+    /// Generates a planet deterministically from its GUID.
+    ///
+    /// Radius, cell count, terrain shape, climate band and every seed are derived from the GUID
+    /// with no global random state, so the same GUID regenerates the same planet anywhere. See
+    /// `PlanetParameters` for the derived values.
+    ///
+    /// # Determinism
+    ///
+    /// The whole chain from a galaxy GUID through `StarSystem` to the planet surface and its
+    /// exported buffers is part of the public API: the same GUID produces the same planet across
+    /// runs, thread counts and platforms. `tests/determinism.rs` pins this with golden hashes, and
+    /// any change that alters them is a breaking change.
+    ///
+    /// # Arguments
+    ///
+    /// * `guid` - The planet GUID, e.g. `SystemPlanet::guid`.
+    pub fn from_guid(guid: Uuid) -> Planet {
+        PlanetParameters::from_guid(guid).build()
+    }
+}
@@ -0,0 +1,367 @@
+use crate::raster::lat_lon_to_unit;
+use crate::vector::{add, cross, dot, normalize, scale, Vec3};
+
+/// A map projection between latitude/longitude and normalized 2D map coordinates.
+///
+/// Map coordinates `(u, v)` run from `(0, 0)` at the top-left corner of the map to `(1, 1)` at
+/// the bottom-right corner, so they can be scaled directly to any raster resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Projection {
+    /// Plate carrée: longitude and latitude map linearly to `u` and `v`.
+    #[default]
+    Equirectangular,
+    /// Equal-area pseudocylindrical projection with an elliptical outline, 2:1 wide.
+    Mollweide,
+    /// Compromise pseudocylindrical projection popular for world maps, about 1.97:1 wide.
+    Robinson,
+    /// Texture atlas of the six gnomonic faces of a cube, 3:2 wide, with no poles or seams for
+    /// a mesh to cross; `Planet::atlas_mesh` builds meshes with UVs into it.
+    ///
+    /// The top row holds the +x, +y and +z (north) faces and the bottom row the -x, -y and -z
+    /// faces, each upright as seen from outside the planet. Every tile has a gutter continuing
+    /// its face past the cube edge, so the triangles along an edge are textured from one tile.
+    CubeAtlas,
+    /// Conformal cylindrical projection used by web maps, square, cut off at `MERCATOR_LIMIT` so
+    /// the poles, which it stretches to infinity, do not appear.
+    Mercator,
+    /// The whole globe in a disc around a centre point, with true distances and bearings from the
+    /// centre; the antipode is the rim.
+    AzimuthalEquidistant { latitude: f64, longitude: f64 },
+    /// Lambert's equal-area projection of the whole globe in a disc around a centre point.
+    LambertAzimuthal { latitude: f64, longitude: f64 },
+    /// The hemisphere facing a centre point as seen from far away, like a photograph of a globe.
+    Orthographic { latitude: f64, longitude: f64 },
+}
+
+/// The latitude in degrees at which `Projection::Mercator` is cut off, where its map is square.
+pub const MERCATOR_LIMIT: f64 = 85.05112878;
+
+// Width of the gutter around each cube atlas face, as a fraction of the face's half-width
+const CUBE_GUTTER: f64 = 0.125;
+
+// Normal, rightward and upward axes of each cube atlas face in atlas order
+const CUBE_FACES: [(Vec3, Vec3, Vec3); 6] = [
+    ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)),
+    ((0.0, 1.0, 0.0), (-1.0, 0.0, 0.0), (0.0, 0.0, 1.0)),
+    ((0.0, 0.0, 1.0), (0.0, 1.0, 0.0), (-1.0, 0.0, 0.0)),
+    ((-1.0, 0.0, 0.0), (0.0, -1.0, 0.0), (0.0, 0.0, 1.0)),
+    ((0.0, -1.0, 0.0), (1.0, 0.0, 0.0), (0.0, 0.0, 1.0)),
+    ((0.0, 0.0, -1.0), (0.0, 1.0, 0.0), (1.0, 0.0, 0.0)),
+];
+
+/// Returns the cube atlas face a unit direction lies on, 0 to 5 in `Projection::CubeAtlas` order.
+pub(crate) fn cube_face(direction: Vec3) -> usize {
+    (0..6).max_by(|&a, &b| dot(direction, CUBE_FACES[a].0).total_cmp(&dot(direction, CUBE_FACES[b].0))).unwrap_or(0)
+}
+
+/// Returns how far a unit direction projects from the centre of a cube atlas face: at most 1.0 on
+/// the face, up to `1.0 + CUBE_GUTTER` in its gutter, and infinite behind it.
+pub(crate) fn cube_face_offset(face: usize, direction: Vec3) -> f64 {
+    let (normal, right, up) = CUBE_FACES[face];
+    let depth = dot(direction, normal);
+    if depth > 0.0 {
+        dot(direction, right).abs().max(dot(direction, up).abs()) / depth
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Projects a unit direction onto a cube atlas face, through the gutter if it lies off the face.
+///
+/// # Returns
+///
+/// Normalized `(u, v)` atlas coordinates inside the face's tile; directions too far off the face
+/// for its gutter are clamped to the tile's edge.
+pub(crate) fn cube_atlas_uv(face: usize, direction: Vec3) -> (f64, f64) {
+    let (normal, right, up) = CUBE_FACES[face];
+    let limit = 1.0 + CUBE_GUTTER;
+    let depth = dot(direction, normal);
+    let (a, b) = if depth > 0.0 {
+        ((dot(direction, right) / depth).clamp(-limit, limit), (dot(direction, up) / depth).clamp(-limit, limit))
+    } else {
+        (dot(direction, right).signum() * limit, dot(direction, up).signum() * limit)
+    };
+    let x = (a + limit) / (2.0 * limit);
+    let y = (limit - b) / (2.0 * limit);
+    (((face % 3) as f64 + x) / 3.0, ((face / 3) as f64 + y) / 2.0)
+}
+
+// Robinson's table of parallel length and distance from the equator at 5° steps of latitude
+const ROBINSON: [(f64, f64); 19] = [
+    (1.0000, 0.0000),
+    (0.9986, 0.0620),
+    (0.9954, 0.1240),
+    (0.9900, 0.1860),
+    (0.9822, 0.2480),
+    (0.9730, 0.3100),
+    (0.9600, 0.3720),
+    (0.9427, 0.4340),
+    (0.9216, 0.4958),
+    (0.8962, 0.5571),
+    (0.8679, 0.6176),
+    (0.8350, 0.6769),
+    (0.7986, 0.7346),
+    (0.7597, 0.7903),
+    (0.7186, 0.8435),
+    (0.6732, 0.8936),
+    (0.6213, 0.9394),
+    (0.5722, 0.9761),
+    (0.5322, 1.0000),
+];
+
+// The Robinson table interpolated at an absolute latitude in degrees
+fn robinson_at(latitude: f64) -> (f64, f64) {
+    let position = (latitude.abs() / 5.0).min(18.0);
+    let i = (position.floor() as usize).min(17);
+    let t = position - i as f64;
+    let (a, b) = (ROBINSON[i], ROBINSON[i + 1]);
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+// The absolute latitude in degrees at which the Robinson table reaches a distance from the equator
+fn robinson_latitude(y: f64) -> f64 {
+    let i = ROBINSON.windows(2).position(|w| y <= w[1].1).unwrap_or(17);
+    let (a, b) = (ROBINSON[i], ROBINSON[i + 1]);
+    5.0 * (i as f64 + ((y - a.1) / (b.1 - a.1)).clamp(0.0, 1.0))
+}
+
+// Solves 2θ + sin 2θ = π sin φ for Mollweide's auxiliary angle θ by Newton's method
+fn mollweide_theta(latitude: f64) -> f64 {
+    let target = std::f64::consts::PI * latitude.to_radians().sin();
+    let mut theta = latitude.to_radians();
+    for _ in 0..20 {
+        let f = 2.0 * theta + (2.0 * theta).sin() - target;
+        let derivative = 2.0 + 2.0 * (2.0 * theta).cos();
+        if derivative.abs() < 1e-12 {
+            break;
+        }
+        theta -= f / derivative;
+    }
+    theta
+}
+
+// The centre of an azimuthal projection and its east and north axes
+fn azimuthal_frame(latitude: f64, longitude: f64) -> (Vec3, Vec3, Vec3) {
+    let centre = lat_lon_to_unit(latitude, longitude);
+    let axis = if centre.2.abs() > 1.0 - 1e-12 { (1.0, 0.0, 0.0) } else { (0.0, 0.0, 1.0) };
+    let east = normalize(cross(axis, centre));
+    (centre, east, cross(centre, east))
+}
+
+// Map coordinates of an azimuthal projection, given the radius of the disc (0.0 to 1.0) at an
+// angular distance from the centre
+fn azimuthal_forward(latitude: f64, longitude: f64, centre: (f64, f64), radius: impl Fn(f64) -> Option<f64>) -> Option<(f64, f64)> {
+    let (pole, east, north) = azimuthal_frame(centre.0, centre.1);
+    let point = lat_lon_to_unit(latitude, longitude);
+    let (x, y) = (dot(point, east), dot(point, north));
+    let angle = dot(point, pole).clamp(-1.0, 1.0).acos();
+    let rho = radius(angle)?;
+    let sideways = x.hypot(y);
+    let (dx, dy) = if sideways > 1e-15 { (x / sideways, y / sideways) } else { (1.0, 0.0) };
+    Some((0.5 + 0.5 * rho * dx, 0.5 - 0.5 * rho * dy))
+}
+
+// The latitude and longitude at map coordinates of an azimuthal projection, given the angular
+// distance from the centre at a radius of the disc
+fn azimuthal_inverse(u: f64, v: f64, centre: (f64, f64), angle: impl Fn(f64) -> f64) -> Option<(f64, f64)> {
+    let (a, b) = (2.0 * u - 1.0, 1.0 - 2.0 * v);
+    let rho = a.hypot(b);
+    // Points `forward` puts on the rim can land a few ulps outside it
+    if rho > 1.0 + 1e-12 {
+        return None;
+    }
+    let rho = rho.min(1.0);
+    let (pole, east, north) = azimuthal_frame(centre.0, centre.1);
+    let c = angle(rho);
+    let direction = if rho > 1e-15 { add(scale(east, a / rho), scale(north, b / rho)) } else { east };
+    let (x, y, z) = normalize(add(scale(pole, c.cos()), scale(direction, c.sin())));
+    Some((z.clamp(-1.0, 1.0).asin().to_degrees(), y.atan2(x).to_degrees()))
+}
+
+impl Projection {
+    /// Returns the width of the map divided by its height, for choosing raster sizes.
+    pub fn aspect_ratio(&self) -> f64 {
+        match self {
+            Projection::Equirectangular | Projection::Mollweide => 2.0,
+            Projection::Robinson => 1.97,
+            Projection::CubeAtlas => 1.5,
+            Projection::Mercator | Projection::AzimuthalEquidistant { .. } | Projection::LambertAzimuthal { .. } | Projection::Orthographic { .. } => 1.0,
+        }
+    }
+
+    /// Projects a latitude and longitude (in degrees) to map coordinates.
+    ///
+    /// # Returns
+    ///
+    /// `Some((u, v))` for points that appear on the map, or `None` for points the projection
+    /// cannot show.
+    pub fn forward(&self, latitude: f64, longitude: f64) -> Option<(f64, f64)> {
+        match self {
+            Projection::Equirectangular => {
+                let u = (longitude + 180.0) / 360.0;
+                let v = (90.0 - latitude) / 180.0;
+                Some((u, v))
+            }
+            Projection::Mollweide => {
+                let theta = mollweide_theta(latitude);
+                let u = 0.5 + longitude / 360.0 * theta.cos();
+                let v = 0.5 - 0.5 * theta.sin();
+                Some((u, v))
+            }
+            Projection::Robinson => {
+                let (length, distance) = robinson_at(latitude);
+                let u = 0.5 + longitude / 360.0 * length;
+                let v = 0.5 - 0.5 * distance * latitude.signum();
+                Some((u, v))
+            }
+            Projection::CubeAtlas => {
+                let direction = lat_lon_to_unit(latitude, longitude);
+                Some(cube_atlas_uv(cube_face(direction), direction))
+            }
+            Projection::Mercator => {
+                if latitude.abs() > MERCATOR_LIMIT {
+                    return None;
+                }
+                let y = (std::f64::consts::FRAC_PI_4 + latitude.to_radians() / 2.0).tan().ln();
+                Some(((longitude + 180.0) / 360.0, 0.5 - y / (2.0 * std::f64::consts::PI)))
+            }
+            Projection::AzimuthalEquidistant { latitude: lat, longitude: lon } => azimuthal_forward(latitude, longitude, (*lat, *lon), |angle| Some(angle / std::f64::consts::PI)),
+            Projection::LambertAzimuthal { latitude: lat, longitude: lon } => azimuthal_forward(latitude, longitude, (*lat, *lon), |angle| Some((angle / 2.0).sin())),
+            Projection::Orthographic { latitude: lat, longitude: lon } => {
+                azimuthal_forward(latitude, longitude, (*lat, *lon), |angle| (angle <= std::f64::consts::FRAC_PI_2).then_some(angle.sin()))
+            }
+        }
+    }
+
+    /// Converts map coordinates back to a latitude and longitude in degrees.
+    ///
+    /// # Returns
+    ///
+    /// `Some((latitude, longitude))`, or `None` if `(u, v)` lies outside the projected globe.
+    pub fn inverse(&self, u: f64, v: f64) -> Option<(f64, f64)> {
+        match self {
+            Projection::Equirectangular => {
+                if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                    return None;
+                }
+                Some((90.0 - v * 180.0, u * 360.0 - 180.0))
+            }
+            Projection::Mollweide => {
+                let sin_theta = 1.0 - 2.0 * v;
+                if sin_theta.abs() > 1.0 {
+                    return None;
+                }
+                let theta = sin_theta.asin();
+                let latitude = ((2.0 * theta + (2.0 * theta).sin()) / std::f64::consts::PI).clamp(-1.0, 1.0).asin().to_degrees();
+                let longitude = (u - 0.5) * 360.0 / theta.cos().max(1e-12);
+                (longitude.abs() <= 180.0).then_some((latitude, longitude))
+            }
+            Projection::Robinson => {
+                let y = 1.0 - 2.0 * v;
+                if y.abs() > 1.0 {
+                    return None;
+                }
+                let latitude = robinson_latitude(y.abs()) * y.signum();
+                let longitude = (u - 0.5) * 360.0 / robinson_at(latitude).0;
+                (longitude.abs() <= 180.0).then_some((latitude, longitude))
+            }
+            Projection::CubeAtlas => {
+                if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                    return None;
+                }
+                let (column, row) = (((u * 3.0) as usize).min(2), ((v * 2.0) as usize).min(1));
+                let (normal, right, up) = CUBE_FACES[row * 3 + column];
+                let limit = 1.0 + CUBE_GUTTER;
+                let a = (u * 3.0 - column as f64) * 2.0 * limit - limit;
+                let b = limit - (v * 2.0 - row as f64) * 2.0 * limit;
+                let (x, y, z) = normalize(add(normal, add(scale(right, a), scale(up, b))));
+                Some((z.clamp(-1.0, 1.0).asin().to_degrees(), y.atan2(x).to_degrees()))
+            }
+            Projection::Mercator => {
+                if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                    return None;
+                }
+                let latitude = ((1.0 - 2.0 * v) * std::f64::consts::PI).sinh().atan().to_degrees();
+                Some((latitude, u * 360.0 - 180.0))
+            }
+            Projection::AzimuthalEquidistant { latitude, longitude } => azimuthal_inverse(u, v, (*latitude, *longitude), |rho| rho * std::f64::consts::PI),
+            Projection::LambertAzimuthal { latitude, longitude } => azimuthal_inverse(u, v, (*latitude, *longitude), |rho| 2.0 * rho.asin()),
+            Projection::Orthographic { latitude, longitude } => azimuthal_inverse(u, v, (*latitude, *longitude), f64::asin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Straight-line distance between two points on the unit sphere given by latitude and longitude
+    fn separation(a: (f64, f64), b: (f64, f64)) -> f64 {
+        let ((ax, ay, az), (bx, by, bz)) = (lat_lon_to_unit(a.0, a.1), lat_lon_to_unit(b.0, b.1));
+        ((ax - bx).powi(2) + (ay - by).powi(2) + (az - bz).powi(2)).sqrt()
+    }
+
+    // Latitudes and longitudes every 7.5 degrees, poles included
+    fn grid() -> impl Iterator<Item = (f64, f64)> {
+        (-12..=12).flat_map(|i| (-24..24).map(move |j| (i as f64 * 7.5, j as f64 * 7.5)))
+    }
+
+    // Checks that every point of the grid the projection shows lands on the map and comes back
+    // within a distance of the unit sphere
+    fn assert_round_trips(projection: Projection, tolerance: f64) {
+        for point in grid() {
+            let Some((u, v)) = projection.forward(point.0, point.1) else { continue };
+            assert!((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v), "{projection:?}: {point:?} maps to ({u}, {v})");
+            let back = projection.inverse(u, v).unwrap_or_else(|| panic!("{projection:?}: ({u}, {v}) from {point:?} has no inverse"));
+            assert!(separation(point, back) < tolerance, "{projection:?}: {point:?} comes back as {back:?}");
+        }
+    }
+
+    #[test]
+    fn cube_atlas_round_trips_and_keeps_faces_in_their_tiles() {
+        assert_round_trips(Projection::CubeAtlas, 1e-9);
+        for point in grid() {
+            let direction = lat_lon_to_unit(point.0, point.1);
+            let face = cube_face(direction);
+            let (u, v) = Projection::CubeAtlas.forward(point.0, point.1).unwrap();
+            assert_eq!(((u * 3.0) as usize).min(2) + 3 * ((v * 2.0) as usize).min(1), face, "{point:?}");
+            assert!(cube_face_offset(face, direction) <= 1.0 + 1e-12);
+        }
+    }
+
+    #[test]
+    fn mercator_round_trips_and_cuts_off_the_poles() {
+        assert_round_trips(Projection::Mercator, 1e-9);
+        assert_eq!(Projection::Mercator.forward(MERCATOR_LIMIT + 0.01, 0.0), None);
+        assert_eq!(Projection::Mercator.forward(-90.0, 0.0), None);
+        let (_, top) = Projection::Mercator.forward(MERCATOR_LIMIT, 0.0).unwrap();
+        let (_, bottom) = Projection::Mercator.forward(-MERCATOR_LIMIT, 0.0).unwrap();
+        assert!(top.abs() < 1e-9 && (bottom - 1.0).abs() < 1e-9, "limit maps to {top} and {bottom}");
+    }
+
+    #[test]
+    fn azimuthal_projections_round_trip_around_any_centre() {
+        for (latitude, longitude) in [(0.0, 0.0), (90.0, 0.0), (-90.0, 0.0), (37.5, -122.0), (-12.0, 170.0)] {
+            assert_round_trips(Projection::AzimuthalEquidistant { latitude, longitude }, 1e-9);
+            assert_round_trips(Projection::LambertAzimuthal { latitude, longitude }, 1e-9);
+            // The inverse takes the arcsine of the radius, which loses precision towards the limb
+            assert_round_trips(Projection::Orthographic { latitude, longitude }, 1e-6);
+        }
+    }
+
+    #[test]
+    fn azimuthal_projections_centre_their_point_and_scale_distances() {
+        let (latitude, longitude) = (37.5, -122.0);
+        let equidistant = Projection::AzimuthalEquidistant { latitude, longitude };
+        let orthographic = Projection::Orthographic { latitude, longitude };
+        let (u, v) = equidistant.forward(latitude, longitude).unwrap();
+        assert!((u - 0.5).abs() < 1e-12 && (v - 0.5).abs() < 1e-12);
+        for point in grid() {
+            let angle = dot(lat_lon_to_unit(latitude, longitude), lat_lon_to_unit(point.0, point.1)).clamp(-1.0, 1.0).acos();
+            let (u, v) = equidistant.forward(point.0, point.1).unwrap();
+            let rho = 2.0 * ((u - 0.5).powi(2) + (v - 0.5).powi(2)).sqrt();
+            assert!((rho * std::f64::consts::PI - angle).abs() < 1e-9, "{point:?} lies {angle} rad away but at radius {rho}");
+            assert_eq!(orthographic.forward(point.0, point.1).is_some(), angle <= std::f64::consts::FRAC_PI_2, "{point:?}");
+        }
+    }
+}
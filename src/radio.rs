@@ -0,0 +1,194 @@
+use crate::planet::Planet;
+use crate::vector::{add, normalize, scale, Vec3};
+use rayon::prelude::*;
+
+/// The speed of light in m/s.
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/// A radio transmitter standing on a cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transmitter {
+    pub cell: usize,
+    pub mast_height: f64,   // Antenna height above the surface in metres
+    pub power_dbm: f64,     // Effective radiated power in dBm
+    pub frequency_mhz: f64, // Carrier frequency in MHz
+}
+
+impl Transmitter {
+    /// Creates a 20 W (43 dBm) VHF transmitter at 150 MHz on a 30 m mast.
+    pub fn new(cell: usize) -> Transmitter {
+        Transmitter {
+            cell,
+            mast_height: 30.0,
+            power_dbm: 43.0,
+            frequency_mhz: 150.0,
+        }
+    }
+}
+
+/// Settings shared by every path in a coverage computation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageSettings {
+    pub receiver_height: f64,        // Receiver antenna height above the surface in metres
+    pub max_range_km: f64,           // Cells further away than this receive no signal
+    pub effective_earth_factor: f64, // Radius multiplier for atmospheric refraction (4/3 on Earth)
+    pub samples_per_cell: f64,       // Terrain samples per cell spacing along each path
+}
+
+impl Default for CoverageSettings {
+    /// A handheld receiver at 1.5 m, 200 km range and standard 4/3-earth refraction.
+    fn default() -> Self {
+        CoverageSettings {
+            receiver_height: 1.5,
+            max_range_km: 200.0,
+            effective_earth_factor: 4.0 / 3.0,
+            samples_per_cell: 2.0,
+        }
+    }
+}
+
+/// Returns the free-space path loss in dB.
+///
+/// # Arguments
+///
+/// * `distance_km` - The path length.
+/// * `frequency_mhz` - The carrier frequency.
+pub fn free_space_path_loss(distance_km: f64, frequency_mhz: f64) -> f64 {
+    20.0 * distance_km.max(1e-3).log10() + 20.0 * frequency_mhz.log10() + 32.44
+}
+
+/// Returns the knife-edge diffraction loss in dB for the Fresnel-Kirchhoff parameter `v`.
+///
+/// Uses the ITU-R P.526 approximation; paths that clear the first Fresnel zone comfortably
+/// (`v <= -0.78`) have no loss.
+pub fn knife_edge_loss(v: f64) -> f64 {
+    if v <= -0.78 {
+        0.0
+    } else {
+        6.9 + 20.0 * (((v - 0.1).powi(2) + 1.0).sqrt() + v - 0.1).log10()
+    }
+}
+
+// The surface a radio path sees: sea level over the ocean
+fn surface_height(planet: &Planet, cell: usize) -> f64 {
+    planet.elevation()[cell].max(0.0)
+}
+
+// Walks the great circle from `a` to `b`, returning the largest diffraction parameter along it
+fn worst_obstruction(planet: &Planet, from: usize, to: usize, heights: (f64, f64), wavelength: f64, settings: &CoverageSettings) -> f64 {
+    let (a, b) = (planet.sites()[from], planet.sites()[to]);
+    let distance = planet.distance_km(from, to) * 1000.0;
+    if distance <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    let spacing = (4.0 * std::f64::consts::PI / planet.cell_count() as f64).sqrt() * planet.radius() * 1000.0;
+    let steps = ((distance / spacing) * settings.samples_per_cell).ceil().max(1.0) as usize;
+    let effective_radius = planet.radius() * 1000.0 * settings.effective_earth_factor;
+
+    let start = surface_height(planet, from) + heights.0;
+    let end = surface_height(planet, to) + heights.1;
+    let mut hint = from;
+    let mut worst = f64::NEG_INFINITY;
+    for step in 1..steps {
+        let t = step as f64 / steps as f64;
+        let point: Vec3 = normalize(add(scale(a, 1.0 - t), scale(b, t)));
+        hint = planet.nearest_cell(point, hint);
+        let (d1, d2) = (distance * t, distance * (1.0 - t));
+        let bulge = d1 * d2 / (2.0 * effective_radius);
+        let ray = start + (end - start) * t;
+        let clearance = surface_height(planet, hint) + bulge - ray;
+        let v = clearance * (2.0 * distance / (wavelength * d1 * d2)).sqrt();
+        worst = worst.max(v);
+    }
+    worst
+}
+
+impl Planet {
+    /// Returns `true` if the direct ray between two antennas clears the terrain.
+    ///
+    /// The ray is bent by atmospheric refraction through `settings.effective_earth_factor`.
+    ///
+    /// # Arguments
+    ///
+    /// * `from`, `to` - The cells the antennas stand on.
+    /// * `from_height`, `to_height` - The antenna heights above the surface in metres.
+    /// * `settings` - The sampling and refraction settings.
+    pub fn line_of_sight(&self, from: usize, to: usize, from_height: f64, to_height: f64, settings: &CoverageSettings) -> bool {
+        // An infinitesimal wavelength turns the diffraction parameter into a plain clearance test
+        worst_obstruction(self, from, to, (from_height, to_height), 1e-9, settings) <= 0.0
+    }
+
+    /// Computes the received signal strength from one transmitter at every cell.
+    ///
+    /// Each path combines free-space loss with a single knife-edge diffraction loss at the worst
+    /// obstruction along the great circle, which is the usual quick approximation for terrain
+    /// shadowing.
+    ///
+    /// # Arguments
+    ///
+    /// * `transmitter` - The transmitter.
+    /// * `settings` - Receiver height, range and sampling settings.
+    ///
+    /// # Returns
+    ///
+    /// The received power per cell in dBm; `f64::NEG_INFINITY` beyond `max_range_km`.
+    pub fn signal_strength(&self, transmitter: &Transmitter, settings: &CoverageSettings) -> Vec<f64> {
+        let wavelength = SPEED_OF_LIGHT / (transmitter.frequency_mhz * 1.0e6);
+        (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let distance = self.distance_km(transmitter.cell, cell);
+                if distance > settings.max_range_km {
+                    return f64::NEG_INFINITY;
+                }
+                let heights = (transmitter.mast_height, settings.receiver_height);
+                let v = worst_obstruction(self, transmitter.cell, cell, heights, wavelength, settings);
+                transmitter.power_dbm - free_space_path_loss(distance, transmitter.frequency_mhz) - knife_edge_loss(v)
+            })
+            .collect()
+    }
+
+    /// Computes the best-server coverage of a set of transmitters.
+    ///
+    /// # Returns
+    ///
+    /// The strongest received power per cell in dBm, and the index of the transmitter providing
+    /// it (`None` where no transmitter is in range).
+    pub fn coverage(&self, transmitters: &[Transmitter], settings: &CoverageSettings) -> (Vec<f64>, Vec<Option<usize>>) {
+        let mut strength = vec![f64::NEG_INFINITY; self.cell_count()];
+        let mut server = vec![None; self.cell_count()];
+        for (index, transmitter) in transmitters.iter().enumerate() {
+            for (cell, signal) in self.signal_strength(transmitter, settings).into_iter().enumerate() {
+                if signal > strength[cell] {
+                    strength[cell] = signal;
+                    server[cell] = Some(index);
+                }
+            }
+        }
+        (strength, server)
+    }
+
+    /// Picks the tower site that covers the most cells for tower-placement tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `candidates` - Transmitters to evaluate; typically one template on different cells.
+    /// * `sensitivity_dbm` - The weakest usable signal.
+    /// * `settings` - Receiver height, range and sampling settings.
+    ///
+    /// # Returns
+    ///
+    /// The index of the best candidate and the number of cells it covers, or `None` if there
+    /// are no candidates.
+    pub fn best_tower_site(&self, candidates: &[Transmitter], sensitivity_dbm: f64, settings: &CoverageSettings) -> Option<(usize, usize)> {
+        candidates
+            .iter()
+            .enumerate()
+            .map(|(index, transmitter)| {
+                let covered = self.signal_strength(transmitter, settings).iter().filter(|&&s| s >= sensitivity_dbm).count();
+                (index, covered)
+            })
+            .max_by_key(|&(index, covered)| (covered, std::cmp::Reverse(index)))
+    }
+}
+
@@ -0,0 +1,143 @@
+use crate::planet::{Layer, Planet};
+use crate::projection::Projection;
+use crate::vector::Vec3;
+use rayon::prelude::*;
+
+/// A row-major 2D grid of samples.
+///
+/// The layout matches `ndarray`'s standard (C-order) layout, so a raster converts without
+/// copying via `Array2::from_shape_vec(raster.shape(), raster.into_vec())`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    data: Vec<f64>,
+}
+
+impl Raster {
+    /// Creates a raster from row-major data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data.len() != width * height`.
+    pub fn from_vec(width: usize, height: usize, data: Vec<f64>) -> Raster {
+        assert_eq!(data.len(), width * height, "raster data does not match its dimensions");
+        Raster { width, height, data }
+    }
+
+    /// Returns the number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the shape as `(rows, columns)`, the order `ndarray` expects.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    /// Returns the sample at the given row and column.
+    pub fn get(&self, row: usize, column: usize) -> f64 {
+        self.data[row * self.width + column]
+    }
+
+    /// Returns all samples in row-major order.
+    pub fn as_slice(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Consumes the raster and returns its samples in row-major order.
+    pub fn into_vec(self) -> Vec<f64> {
+        self.data
+    }
+
+    /// Returns the smallest and largest finite samples, or `None` if there are none.
+    pub fn min_max(&self) -> Option<(f64, f64)> {
+        self.data
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold(None, |acc, &v| match acc {
+                None => Some((v, v)),
+                Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+            })
+    }
+}
+
+/// Converts a latitude and longitude in degrees to a unit vector (z is the rotation axis).
+pub(crate) fn lat_lon_to_unit(latitude: f64, longitude: f64) -> Vec3 {
+    let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+    (lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+}
+
+impl Planet {
+    /// Samples a layer onto a regular grid in the given map projection.
+    ///
+    /// Every pixel takes the value of the cell containing the pixel center. Pixels outside the
+    /// projected globe are `NaN`.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - The per-cell layer to sample.
+    /// * `projection` - The projection mapping pixels to the sphere.
+    /// * `width` - The number of columns.
+    /// * `height` - The number of rows.
+    ///
+    /// # Returns
+    ///
+    /// A `Raster` of `height` rows by `width` columns.
+    pub fn rasterize(&self, layer: Layer, projection: Projection, width: usize, height: usize) -> Raster {
+        self.rasterize_values(&self.layer(layer), projection, width, height)
+    }
+
+    /// Samples arbitrary per-cell values onto a regular grid in the given map projection.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - One value per cell.
+    /// * `projection` - The projection mapping pixels to the sphere.
+    /// * `width` - The number of columns.
+    /// * `height` - The number of rows.
+    ///
+    /// # Returns
+    ///
+    /// A `Raster` of `height` rows by `width` columns; `NaN` outside the projected globe.
+    pub fn rasterize_values(&self, values: &[f64], projection: Projection, width: usize, height: usize) -> Raster {
+        let cells = self.rasterize_cells(projection, width, height);
+        let data = cells
+            .into_iter()
+            .map(|cell| cell.map_or(f64::NAN, |cell| values[cell]))
+            .collect();
+        Raster::from_vec(width, height, data)
+    }
+
+    /// Finds the cell under every pixel of a projected grid.
+    ///
+    /// # Returns
+    ///
+    /// One entry per pixel in row-major order; `None` for pixels outside the projected globe.
+    pub fn rasterize_cells(&self, projection: Projection, width: usize, height: usize) -> Vec<Option<usize>> {
+        if self.cell_count() == 0 {
+            return vec![None; width * height];
+        }
+        (0..height)
+            .into_par_iter()
+            .flat_map_iter(|row| {
+                let mut hint = 0;
+                (0..width)
+                    .map(|column| {
+                        let u = (column as f64 + 0.5) / width as f64;
+                        let v = (row as f64 + 0.5) / height as f64;
+                        projection.inverse(u, v).map(|(lat, lon)| {
+                            hint = self.nearest_cell(lat_lon_to_unit(lat, lon), hint);
+                            hint
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
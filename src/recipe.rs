@@ -0,0 +1,638 @@
+use crate::climate::{OrbitalCalendar, SOLAR_CONSTANT};
+use crate::guid::guid_from_seed;
+use crate::planet::Planet;
+use crate::procedural::{ClimateBand, PlanetParameters};
+use crate::sampling::SiteSampling;
+use crate::surfaces::SurfaceKind;
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::coords::CoordinateFrame;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::image::Image;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::npz::NpzWriter;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::planet_file::PlanetFileWriter;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::projection::Projection;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::relief::ReliefSettings;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::render::globe::{render_globe, GlobeSettings};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::render::map::{render_map, MapLayers, MapSettings};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::unreal::{write_unreal_data_table, write_unreal_mesh, UnrealTransform};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, io};
+
+/// Error returned when a recipe cannot be read, parsed or run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeError {
+    /// A file could not be read or written.
+    Io(String),
+    /// The text is not valid TOML, RON or JSON, or a field has the wrong type; the line is 1-based,
+    /// or 0 if unknown.
+    Syntax { line: usize, message: String },
+    /// A field has an invalid value.
+    InvalidField(String),
+    /// The file extension is not `.toml`, `.ron` or `.json`.
+    UnknownFormat(String),
+}
+
+impl std::fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecipeError::Io(message) => write!(f, "recipe I/O failed: {}", message),
+            RecipeError::Syntax { line, message } => write!(f, "recipe syntax error on line {}: {}", line, message),
+            RecipeError::InvalidField(field) => write!(f, "recipe field `{}` is invalid", field),
+            RecipeError::UnknownFormat(path) => write!(f, "cannot tell the recipe format of {}", path),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+/// A file format a recipe can export a planet to.
+///
+/// Deserializes from its short name or its variant name, e.g. `"csv"` in TOML or `UnrealCsv` in
+/// RON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ExportTarget {
+    /// A self-contained glTF 2.0 mesh with vertex colors, `<stem>.gltf`.
+    #[serde(rename = "gltf", alias = "Gltf")]
+    Gltf,
+    /// An equirectangular hypsometric map, `<stem>.png`.
+    #[serde(rename = "png", alias = "Png")]
+    Png,
+    /// A shaded render of the globe, `<stem>_globe.png`.
+    #[serde(rename = "globe", alias = "Globe")]
+    Globe,
+    /// Cell sites, triangles, elevation and temperature as NumPy arrays, `<stem>.npz`.
+    #[serde(rename = "npz", alias = "Npz")]
+    Npz,
+    /// An Unreal Engine DataTable, `<stem>.csv`.
+    #[serde(rename = "csv", alias = "UnrealCsv")]
+    UnrealCsv,
+    /// An Unreal Engine mesh blob, `<stem>.tfue`.
+    #[serde(rename = "unreal", alias = "UnrealMesh")]
+    UnrealMesh,
+    /// A chunked planet file for streaming, `<stem>.tfp`.
+    #[serde(rename = "tfp", alias = "PlanetFile")]
+    PlanetFile,
+    /// An equirectangular tangent-space normal map, `<stem>_normal.png`.
+    #[serde(rename = "normal", alias = "NormalMap")]
+    NormalMap,
+    /// An equirectangular slope map, black flat to white vertical, `<stem>_slope.png`.
+    #[serde(rename = "slope", alias = "SlopeMap")]
+    SlopeMap,
+    /// A glTF mesh textured with an embedded cube atlas elevation map, `<stem>_atlas.gltf`.
+    #[serde(rename = "atlas", alias = "AtlasGltf")]
+    AtlasGltf,
+}
+
+impl ExportTarget {
+    /// Every target in declaration order.
+    pub const ALL: [ExportTarget; 10] = [
+        ExportTarget::Gltf,
+        ExportTarget::Png,
+        ExportTarget::Globe,
+        ExportTarget::Npz,
+        ExportTarget::UnrealCsv,
+        ExportTarget::UnrealMesh,
+        ExportTarget::PlanetFile,
+        ExportTarget::NormalMap,
+        ExportTarget::SlopeMap,
+        ExportTarget::AtlasGltf,
+    ];
+
+    /// Returns the short name used in recipes and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportTarget::Gltf => "gltf",
+            ExportTarget::Png => "png",
+            ExportTarget::Globe => "globe",
+            ExportTarget::Npz => "npz",
+            ExportTarget::UnrealCsv => "csv",
+            ExportTarget::UnrealMesh => "unreal",
+            ExportTarget::PlanetFile => "tfp",
+            ExportTarget::NormalMap => "normal",
+            ExportTarget::SlopeMap => "slope",
+            ExportTarget::AtlasGltf => "atlas",
+        }
+    }
+
+    /// Looks a target up by its short name or variant name, ignoring case and underscores.
+    pub fn from_name(name: &str) -> Option<ExportTarget> {
+        let name = name.to_lowercase().replace('_', "");
+        ExportTarget::ALL
+            .into_iter()
+            .find(|target| target.name() == name || format!("{:?}", target).to_lowercase() == name)
+    }
+
+    /// Writes a planet in this format.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet to export.
+    /// * `directory` - An existing directory to write into.
+    /// * `stem` - The file name without extension.
+    /// * `settings` - Image size and mesh exaggeration.
+    ///
+    /// # Returns
+    ///
+    /// The path of the written file, or an `io::Error` if it could not be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, planet: &Planet, directory: &str, stem: &str, settings: &ExportSettings) -> io::Result<String> {
+        let path = |suffix: &str| format!("{}/{}{}", directory, stem, suffix);
+        let relief = ReliefSettings {
+            width: settings.width,
+            height: (settings.width / 2).max(1),
+            exaggeration: settings.exaggeration,
+            ..ReliefSettings::default()
+        };
+        let written = match self {
+            ExportTarget::Gltf => {
+                let path = path(".gltf");
+                fs::write(&path, planet.mesh(&CoordinateFrame::BEVY, settings.exaggeration).to_gltf())?;
+                path
+            }
+            ExportTarget::Png => {
+                let path = path(".png");
+                let map = MapSettings {
+                    width: settings.width,
+                    height: (settings.width / 2).max(1),
+                    ..MapSettings::default()
+                };
+                render_map(planet, &MapLayers::default(), &map).write_png(&path)?;
+                path
+            }
+            ExportTarget::Globe => {
+                let path = path("_globe.png");
+                let globe = GlobeSettings {
+                    width: settings.width,
+                    height: settings.width,
+                    ..GlobeSettings::default()
+                };
+                render_globe(planet, None, &globe).write_png(&path)?;
+                path
+            }
+            ExportTarget::Npz => {
+                let path = path(".npz");
+                let cells = planet.cell_count();
+                let sites: Vec<f64> = planet.sites().iter().flat_map(|&(x, y, z)| [x, y, z]).collect();
+                let triangles: Vec<u32> = planet.triangles().iter().flatten().map(|&cell| cell as u32).collect();
+                let mut archive = NpzWriter::new();
+                archive.add_f64("sites", &[cells, 3], &sites);
+                archive.add_u32("triangles", &[planet.triangles().len(), 3], &triangles);
+                archive.add_f64("elevation", &[cells], planet.elevation());
+                archive.add_f64("temperature", &[cells], planet.temperature());
+                archive.finish(&path)?;
+                path
+            }
+            ExportTarget::UnrealCsv => {
+                let path = path(".csv");
+                write_unreal_data_table(&path, planet, &UnrealTransform::default())?;
+                path
+            }
+            ExportTarget::UnrealMesh => {
+                let path = path(".tfue");
+                write_unreal_mesh(&path, planet, &UnrealTransform::default())?;
+                path
+            }
+            ExportTarget::PlanetFile => {
+                let path = path(".tfp");
+                PlanetFileWriter::new(planet, settings.chunk_count).write(&path)?;
+                path
+            }
+            ExportTarget::NormalMap => {
+                let path = path("_normal.png");
+                planet.normal_map(&relief).write_png(&path)?;
+                path
+            }
+            ExportTarget::SlopeMap => {
+                let path = path("_slope.png");
+                Image::from_raster_gray(&planet.slope_map(&relief), 0.0, 90.0).write_png(&path)?;
+                path
+            }
+            ExportTarget::AtlasGltf => {
+                let path = path("_atlas.gltf");
+                let map = MapSettings {
+                    projection: Projection::CubeAtlas,
+                    width: settings.width,
+                    height: (settings.width * 2 / 3).max(1),
+                    ..MapSettings::default()
+                };
+                let texture = render_map(planet, &MapLayers::default(), &map).encode_png();
+                fs::write(&path, planet.atlas_mesh(&CoordinateFrame::BEVY, settings.exaggeration).to_gltf_textured(&texture))?;
+                path
+            }
+        };
+        Ok(written)
+    }
+}
+
+/// Settings shared by the export targets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportSettings {
+    pub width: usize,       // Width of image exports in pixels
+    pub exaggeration: f64,  // Vertical exaggeration of mesh, normal and slope exports
+    pub chunk_count: usize, // Chunks in planet file exports
+}
+
+/// 1024-pixel images, tenfold exaggeration, which keeps relief visible at planetary scale, and 64
+/// planet file chunks.
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            width: 1024,
+            exaggeration: 10.0,
+            chunk_count: 64,
+        }
+    }
+}
+
+/// Overrides of the terrain settings derived from the seed; `None` keeps the derived value.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct TerrainRecipe {
+    pub continent_frequency: Option<f64>,
+    pub land_fraction: Option<f64>,
+    pub max_elevation: Option<f64>,    // Metres
+    pub max_depth: Option<f64>,        // Metres, positive
+    pub mountain_strength: Option<f64>,
+}
+
+/// Overrides of the climate derived from the seed; `None` keeps the derived value.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ClimateRecipe {
+    pub axial_tilt: Option<f64>,       // Degrees
+    pub solar_flux: Option<f64>,       // W/m²; the climate band follows it
+    pub year_length_days: Option<f64>,
+    pub day_length_hours: Option<f64>,
+}
+
+/// A complete, version-controllable planet definition: what to generate and what to export.
+///
+/// A recipe is written in TOML, RON or JSON with the same structure. In TOML:
+///
+/// ```toml
+/// [planet]
+/// seed = 42
+/// cells = 50000
+/// radius = 6371.0       # Optional, like every field below; omitted values derive from the seed
+/// sampling = "poisson"  # Or "fibonacci" or "goldberg"
+/// symmetry = 3          # Landmasses repeated three times around the rotation axis
+/// surface = "ice"       # Or "rocky", "gas_giant", "ice_giant" or "lava"
+///
+/// [tectonics]
+/// plate_count = 12
+///
+/// [terrain]
+/// land_fraction = 0.35
+///
+/// [climate]
+/// axial_tilt = 23.4
+///
+/// [export]
+/// formats = ["gltf", "png"]
+/// output = "planets"
+/// ```
+///
+/// and in RON:
+///
+/// ```ron
+/// Recipe(
+///     planet: (seed: 42, cells: 50000),
+///     terrain: (land_fraction: 0.35),
+///     export: (formats: [Gltf, Png], output: "planets"),
+/// )
+/// ```
+///
+/// Deserializing a `Recipe` directly reads this layout but does not check it; `from_toml`,
+/// `from_ron`, `from_json` and `load` also `validate` it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RecipeDocument")]
+pub struct Recipe {
+    pub seed: u64,
+    pub cells: Option<usize>,
+    pub radius: Option<f64>,            // Kilometres
+    pub jitter: Option<f64>,            // Site jitter, 0.0 to 1.0
+    pub sampling: Option<SiteSampling>,
+    pub symmetry: Option<u32>,          // N-fold rotational symmetry, see `PlanetParameters::symmetry`
+    pub surface: Option<SurfaceKind>,
+    pub plate_count: Option<usize>,
+    pub terrain: TerrainRecipe,
+    pub climate: ClimateRecipe,
+    pub exports: Vec<ExportTarget>,
+    pub output: String,                 // Directory the exports are written to
+    pub export_settings: ExportSettings,
+}
+
+/// Seed 0 with everything derived from it, exported as glTF and PNG to the working directory.
+impl Default for Recipe {
+    fn default() -> Self {
+        Recipe {
+            seed: 0,
+            cells: None,
+            radius: None,
+            jitter: None,
+            sampling: None,
+            symmetry: None,
+            surface: None,
+            plate_count: None,
+            terrain: TerrainRecipe::default(),
+            climate: ClimateRecipe::default(),
+            exports: vec![ExportTarget::Gltf, ExportTarget::Png],
+            output: ".".to_string(),
+            export_settings: ExportSettings::default(),
+        }
+    }
+}
+
+impl Recipe {
+    /// Reads a recipe from a parsed document; see `Recipe` for the layout. Missing sections and
+    /// fields keep their defaults.
+    pub fn from_json(value: &Value) -> Result<Recipe, RecipeError> {
+        let recipe = Recipe::deserialize(value).map_err(|error| RecipeError::Syntax {
+            line: error.line(),
+            message: error.to_string(),
+        })?;
+        recipe.validate()?;
+        Ok(recipe)
+    }
+
+    /// Parses a TOML recipe.
+    pub fn from_toml(text: &str) -> Result<Recipe, RecipeError> {
+        let recipe: Recipe = toml::from_str(text).map_err(|error| RecipeError::Syntax {
+            line: error.span().map_or(0, |span| text[..span.start].matches('\n').count() + 1),
+            message: error.message().to_string(),
+        })?;
+        recipe.validate()?;
+        Ok(recipe)
+    }
+
+    /// Parses a RON recipe. `Some` may be left out around optional values, as in the example
+    /// above.
+    pub fn from_ron(text: &str) -> Result<Recipe, RecipeError> {
+        let options = ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+        let recipe: Recipe = options.from_str(text).map_err(|error| RecipeError::Syntax {
+            line: error.position.line,
+            message: error.code.to_string(),
+        })?;
+        recipe.validate()?;
+        Ok(recipe)
+    }
+
+    /// Loads a recipe file, choosing the format from its `.toml`, `.ron` or `.json` extension.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Recipe, RecipeError> {
+        let text = fs::read_to_string(path).map_err(|error| RecipeError::Io(format!("{}: {}", path, error)))?;
+        let extension = path.rsplit_once('.').map(|(_, extension)| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("toml") => Recipe::from_toml(&text),
+            Some("ron") => Recipe::from_ron(&text),
+            Some("json") => {
+                let recipe: Recipe = serde_json::from_str(&text).map_err(|error| RecipeError::Syntax {
+                    line: error.line(),
+                    message: error.to_string(),
+                })?;
+                recipe.validate()?;
+                Ok(recipe)
+            }
+            _ => Err(RecipeError::UnknownFormat(path.to_string())),
+        }
+    }
+
+    /// Checks that every value is in range.
+    pub fn validate(&self) -> Result<(), RecipeError> {
+        let invalid = |field: &str| Err(RecipeError::InvalidField(field.to_string()));
+        let not_positive = |value: Option<f64>| value.is_some_and(|v| v <= 0.0);
+        let fraction = |value: Option<f64>| value.is_some_and(|v| !(0.0..=1.0).contains(&v));
+        // TOML and RON can spell out infinities and NaN, which no field accepts
+        let numbers = [
+            ("planet.radius", self.radius),
+            ("planet.jitter", self.jitter),
+            ("terrain.continent_frequency", self.terrain.continent_frequency),
+            ("terrain.land_fraction", self.terrain.land_fraction),
+            ("terrain.max_elevation", self.terrain.max_elevation),
+            ("terrain.max_depth", self.terrain.max_depth),
+            ("terrain.mountain_strength", self.terrain.mountain_strength),
+            ("climate.axial_tilt", self.climate.axial_tilt),
+            ("climate.solar_flux", self.climate.solar_flux),
+            ("climate.year_length_days", self.climate.year_length_days),
+            ("climate.day_length_hours", self.climate.day_length_hours),
+            ("export.exaggeration", Some(self.export_settings.exaggeration)),
+        ];
+        if let Some((field, _)) = numbers.iter().find(|(_, value)| value.is_some_and(|v| !v.is_finite())) {
+            return invalid(field);
+        }
+        if self.cells.is_some_and(|cells| cells < 4) {
+            return invalid("planet.cells");
+        }
+        if not_positive(self.radius) {
+            return invalid("planet.radius");
+        }
+        if fraction(self.jitter) {
+            return invalid("planet.jitter");
+        }
+        if self.symmetry == Some(0) {
+            return invalid("planet.symmetry");
+        }
+        if self.plate_count == Some(0) {
+            return invalid("tectonics.plate_count");
+        }
+        if not_positive(self.terrain.continent_frequency) {
+            return invalid("terrain.continent_frequency");
+        }
+        if fraction(self.terrain.land_fraction) {
+            return invalid("terrain.land_fraction");
+        }
+        if fraction(self.terrain.mountain_strength) {
+            return invalid("terrain.mountain_strength");
+        }
+        if self.terrain.max_elevation.is_some_and(|v| v < 0.0) {
+            return invalid("terrain.max_elevation");
+        }
+        if self.terrain.max_depth.is_some_and(|v| v < 0.0) {
+            return invalid("terrain.max_depth");
+        }
+        if self.climate.axial_tilt.is_some_and(|v| !(0.0..=180.0).contains(&v)) {
+            return invalid("climate.axial_tilt");
+        }
+        if self.climate.solar_flux.is_some_and(|v| v < 0.0) {
+            return invalid("climate.solar_flux");
+        }
+        if not_positive(self.climate.year_length_days) {
+            return invalid("climate.year_length_days");
+        }
+        if not_positive(self.climate.day_length_hours) {
+            return invalid("climate.day_length_hours");
+        }
+        if self.export_settings.width == 0 {
+            return invalid("export.width");
+        }
+        if self.export_settings.chunk_count == 0 {
+            return invalid("export.chunk_count");
+        }
+        Ok(())
+    }
+
+    /// Returns the planet parameters: derived from the seed, then overridden by the recipe.
+    pub fn parameters(&self) -> PlanetParameters {
+        let mut parameters = PlanetParameters::from_guid(guid_from_seed(self.seed));
+        let set = |target: &mut f64, value: Option<f64>| {
+            if let Some(value) = value {
+                *target = value;
+            }
+        };
+        if let Some(cells) = self.cells {
+            parameters.samples = cells;
+        }
+        if let Some(radius) = self.radius {
+            parameters.radius = radius;
+            parameters.surface_gravity = 9.80665 * radius / 6371.0;
+        }
+        set(&mut parameters.jitter, self.jitter);
+        if let Some(sampling) = self.sampling {
+            parameters.sampling = sampling;
+        }
+        if let Some(symmetry) = self.symmetry {
+            parameters.symmetry = symmetry;
+        }
+        if let Some(surface) = self.surface {
+            parameters.surface = surface;
+        }
+        if let Some(plates) = self.plate_count {
+            parameters.plate_count = plates;
+        }
+
+        let terrain = &mut parameters.terrain;
+        set(&mut terrain.continent_frequency, self.terrain.continent_frequency);
+        set(&mut terrain.land_fraction, self.terrain.land_fraction);
+        set(&mut terrain.max_elevation, self.terrain.max_elevation);
+        set(&mut terrain.max_depth, self.terrain.max_depth);
+        set(&mut terrain.mountain_strength, self.terrain.mountain_strength);
+
+        set(&mut parameters.axial_tilt, self.climate.axial_tilt);
+        if let Some(flux) = self.climate.solar_flux {
+            parameters.solar_flux = flux;
+            parameters.climate_band = ClimateBand::from_relative_flux(flux / SOLAR_CONSTANT);
+        }
+        if self.climate.year_length_days.is_some() || self.climate.day_length_hours.is_some() {
+            let calendar = &parameters.calendar;
+            parameters.calendar = OrbitalCalendar::new(
+                self.climate.year_length_days.unwrap_or(calendar.year_length_days()),
+                self.climate.day_length_hours.unwrap_or(calendar.day_length_hours()),
+            );
+        }
+        parameters
+    }
+
+    /// Builds the planet.
+    pub fn build(&self) -> Planet {
+        self.parameters().build()
+    }
+
+    /// Builds the planet and writes every export to the output directory, creating it if needed.
+    ///
+    /// Files are named `planet_<seed>` plus the target's extension.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the written files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(&self) -> Result<Vec<String>, RecipeError> {
+        let io_error = |error: io::Error| RecipeError::Io(error.to_string());
+        fs::create_dir_all(&self.output).map_err(io_error)?;
+        let planet = self.build();
+        let stem = format!("planet_{}", self.seed);
+        let mut written = Vec::new();
+        for target in &self.exports {
+            written.push(target.write(&planet, &self.output, &stem, &self.export_settings).map_err(io_error)?);
+        }
+        Ok(written)
+    }
+}
+
+// The file layout of a recipe, one struct per section
+#[derive(Deserialize, Default)]
+#[serde(rename = "Recipe", default)]
+struct RecipeDocument {
+    planet: PlanetSection,
+    tectonics: TectonicsSection,
+    terrain: TerrainRecipe,
+    climate: ClimateRecipe,
+    export: ExportSection,
+}
+
+// The `[planet]` section
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PlanetSection {
+    seed: u64,
+    cells: Option<usize>,
+    radius: Option<f64>,
+    jitter: Option<f64>,
+    sampling: Option<SiteSampling>,
+    symmetry: Option<u32>,
+    surface: Option<SurfaceKind>,
+}
+
+// The `[tectonics]` section
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct TectonicsSection {
+    plate_count: Option<usize>,
+}
+
+// The `[export]` section
+#[derive(Deserialize)]
+#[serde(default)]
+struct ExportSection {
+    formats: Vec<ExportTarget>,
+    output: String,
+    width: usize,
+    exaggeration: f64,
+    chunk_count: usize,
+}
+
+impl Default for ExportSection {
+    fn default() -> Self {
+        let recipe = Recipe::default();
+        ExportSection {
+            formats: recipe.exports,
+            output: recipe.output,
+            width: recipe.export_settings.width,
+            exaggeration: recipe.export_settings.exaggeration,
+            chunk_count: recipe.export_settings.chunk_count,
+        }
+    }
+}
+
+impl From<RecipeDocument> for Recipe {
+    fn from(document: RecipeDocument) -> Recipe {
+        let RecipeDocument { planet, tectonics, terrain, climate, export } = document;
+        Recipe {
+            seed: planet.seed,
+            cells: planet.cells,
+            radius: planet.radius,
+            jitter: planet.jitter,
+            sampling: planet.sampling,
+            symmetry: planet.symmetry,
+            surface: planet.surface,
+            plate_count: tectonics.plate_count,
+            terrain,
+            climate,
+            exports: export.formats,
+            output: export.output,
+            export_settings: ExportSettings {
+                width: export.width,
+                exaggeration: export.exaggeration,
+                chunk_count: export.chunk_count,
+            },
+        }
+    }
+}
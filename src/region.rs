@@ -0,0 +1,282 @@
+use crate::geology::RockType;
+use crate::planet::Planet;
+use crate::vector::{cross, dot, normalize, Vec3};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The extent of a carved region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegionBounds {
+    pub min_x: f64, // Local kilometres east of the origin
+    pub min_y: f64, // Local kilometres north of the origin
+    pub max_x: f64,
+    pub max_y: f64,
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_longitude: f64, // May exceed `max_longitude` when the region spans the antimeridian
+    pub max_longitude: f64,
+}
+
+/// A self-contained piece of a planet, such as one continent or island.
+///
+/// A region owns copies of every layer for its cells, its own adjacency and planar coordinates
+/// re-based on the region's centre, so it can be saved and used without the planet it came from.
+/// Local cell indices run from `0` to `len() - 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    origin: (f64, f64),         // Latitude and longitude of the local origin in degrees
+    planet_radius: f64,         // Kilometres
+    source_cells: Vec<usize>,   // Planet cell of each local cell
+    sites: Vec<Vec3>,           // Unit-sphere sites, as on the planet
+    positions: Vec<(f64, f64)>, // Local east/north coordinates in kilometres
+    neighbors: Vec<Vec<usize>>, // Local adjacency, clipped to the region
+    elevation: Vec<f64>,
+    rock_types: Vec<RockType>,
+    temperature: Vec<f64>,
+    snow_cover: Vec<f64>,
+    bounds: RegionBounds,
+}
+
+impl Planet {
+    /// Splits the land into connected landmasses.
+    ///
+    /// # Returns
+    ///
+    /// The cells of each landmass, largest first.
+    pub fn landmasses(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.cell_count()];
+        let mut landmasses = Vec::new();
+        for start in 0..self.cell_count() {
+            if seen[start] || self.is_ocean(start) {
+                continue;
+            }
+            seen[start] = true;
+            let mut cells = Vec::new();
+            let mut queue = VecDeque::from([start]);
+            while let Some(cell) = queue.pop_front() {
+                cells.push(cell);
+                for &neighbor in self.neighbors(cell) {
+                    if !seen[neighbor] && !self.is_ocean(neighbor) {
+                        seen[neighbor] = true;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+            landmasses.push(cells);
+        }
+        landmasses.sort_by_key(|cells| std::cmp::Reverse(cells.len()));
+        landmasses
+    }
+
+    /// Carves the landmass containing `cell` out as a standalone region.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - Any land cell of the landmass.
+    /// * `margin` - Rings of surrounding cells (usually coastal waters) to include.
+    ///
+    /// # Returns
+    ///
+    /// The region, or `None` if `cell` is ocean.
+    pub fn carve_landmass(&self, cell: usize, margin: usize) -> Option<Region> {
+        if self.is_ocean(cell) {
+            return None;
+        }
+        let landmass = self.landmasses().into_iter().find(|cells| cells.contains(&cell))?;
+        Some(self.carve_region(&landmass, margin))
+    }
+
+    /// Carves an arbitrary set of cells out as a standalone region.
+    ///
+    /// The local origin is the centroid of the selected cells, and local coordinates use an
+    /// azimuthal equidistant projection about it, so distances from the origin are true
+    /// great-circle distances.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The planet cells to include.
+    /// * `margin` - Rings of neighboring cells to add around the selection.
+    pub fn carve_region(&self, cells: &[usize], margin: usize) -> Region {
+        let mut included: HashSet<usize> = cells.iter().copied().collect();
+        let mut source_cells: Vec<usize> = cells.iter().copied().collect::<HashSet<_>>().into_iter().collect();
+        source_cells.sort_unstable();
+        let mut ring = source_cells.clone();
+        for _ in 0..margin {
+            let mut next = Vec::new();
+            for &cell in &ring {
+                for &neighbor in self.neighbors(cell) {
+                    if included.insert(neighbor) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            next.sort_unstable();
+            source_cells.extend_from_slice(&next);
+            ring = next;
+        }
+
+        let local: HashMap<usize, usize> = source_cells.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+        let sites: Vec<Vec3> = source_cells.iter().map(|&c| self.sites()[c]).collect();
+
+        let sum = sites.iter().fold((0.0, 0.0, 0.0), |s, p| (s.0 + p.0, s.1 + p.1, s.2 + p.2));
+        let centre = if dot(sum, sum) > 0.0 { normalize(sum) } else { (1.0, 0.0, 0.0) };
+        let east = {
+            let e = cross((0.0, 0.0, 1.0), centre);
+            if dot(e, e) < 1e-12 { (0.0, 1.0, 0.0) } else { normalize(e) }
+        };
+        let north = cross(centre, east);
+        let radius = self.radius();
+        let positions: Vec<(f64, f64)> = sites
+            .iter()
+            .map(|&p| {
+                let angle = dot(p, centre).clamp(-1.0, 1.0).acos();
+                let (x, y) = (dot(p, east), dot(p, north));
+                let planar = (x * x + y * y).sqrt();
+                if planar < 1e-12 {
+                    (0.0, 0.0)
+                } else {
+                    (x / planar * angle * radius, y / planar * angle * radius)
+                }
+            })
+            .collect();
+
+        let neighbors = source_cells
+            .iter()
+            .map(|&c| self.neighbors(c).iter().filter_map(|n| local.get(n).copied()).collect())
+            .collect();
+
+        let lat_lons: Vec<(f64, f64)> = source_cells.iter().map(|&c| self.lat_lon(c)).collect();
+        let bounds = region_bounds(&positions, &lat_lons);
+        let origin_latitude = centre.2.clamp(-1.0, 1.0).asin().to_degrees();
+        let origin_longitude = centre.1.atan2(centre.0).to_degrees();
+
+        let clip = |values: &[f64]| source_cells.iter().map(|&c| values[c]).collect::<Vec<f64>>();
+        Region {
+            origin: (origin_latitude, origin_longitude),
+            planet_radius: radius,
+            elevation: clip(self.elevation()),
+            rock_types: source_cells.iter().map(|&c| self.rock_types()[c]).collect(),
+            temperature: clip(self.temperature()),
+            snow_cover: clip(self.snow_cover()),
+            source_cells,
+            sites,
+            positions,
+            neighbors,
+            bounds,
+        }
+    }
+}
+
+fn region_bounds(positions: &[(f64, f64)], lat_lons: &[(f64, f64)]) -> RegionBounds {
+    let mut bounds = RegionBounds {
+        min_x: f64::INFINITY,
+        min_y: f64::INFINITY,
+        max_x: f64::NEG_INFINITY,
+        max_y: f64::NEG_INFINITY,
+        min_latitude: f64::INFINITY,
+        max_latitude: f64::NEG_INFINITY,
+        min_longitude: 180.0,
+        max_longitude: -180.0,
+    };
+    for (&(x, y), &(latitude, _)) in positions.iter().zip(lat_lons) {
+        bounds.min_x = bounds.min_x.min(x);
+        bounds.min_y = bounds.min_y.min(y);
+        bounds.max_x = bounds.max_x.max(x);
+        bounds.max_y = bounds.max_y.max(y);
+        bounds.min_latitude = bounds.min_latitude.min(latitude);
+        bounds.max_latitude = bounds.max_latitude.max(latitude);
+    }
+
+    // The longitude range is the complement of the widest gap between occupied longitudes
+    let mut longitudes: Vec<f64> = lat_lons.iter().map(|&(_, lon)| lon).collect();
+    longitudes.sort_by(f64::total_cmp);
+    if let (Some(&first), Some(&last)) = (longitudes.first(), longitudes.last()) {
+        let (mut gap, mut start, mut end) = (first + 360.0 - last, first, last);
+        for pair in longitudes.windows(2) {
+            if pair[1] - pair[0] > gap {
+                gap = pair[1] - pair[0];
+                start = pair[1];
+                end = pair[0];
+            }
+        }
+        bounds.min_longitude = start;
+        bounds.max_longitude = end;
+    }
+    bounds
+}
+
+impl Region {
+    /// Returns the number of cells in the region.
+    pub fn len(&self) -> usize {
+        self.source_cells.len()
+    }
+
+    /// Returns `true` if the region has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.source_cells.is_empty()
+    }
+
+    /// Returns the latitude and longitude of the local origin in degrees.
+    pub fn origin(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    /// Returns the radius of the source planet in kilometres.
+    pub fn planet_radius(&self) -> f64 {
+        self.planet_radius
+    }
+
+    /// Returns the region's extent in local and geographic coordinates.
+    pub fn bounds(&self) -> RegionBounds {
+        self.bounds
+    }
+
+    /// Returns the planet cell each local cell was carved from.
+    pub fn source_cells(&self) -> &[usize] {
+        &self.source_cells
+    }
+
+    /// Returns the local cell carved from a planet cell, if it is part of the region.
+    pub fn local_cell(&self, source_cell: usize) -> Option<usize> {
+        self.source_cells.iter().position(|&c| c == source_cell)
+    }
+
+    /// Returns the unit-sphere site of every local cell.
+    pub fn sites(&self) -> &[Vec3] {
+        &self.sites
+    }
+
+    /// Returns the local east/north position of every cell in kilometres.
+    pub fn positions(&self) -> &[(f64, f64)] {
+        &self.positions
+    }
+
+    /// Returns the local neighbors of a cell; neighbors outside the region are dropped.
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[cell]
+    }
+
+    /// Returns the elevation of every cell in metres.
+    pub fn elevation(&self) -> &[f64] {
+        &self.elevation
+    }
+
+    /// Returns the bedrock of every cell.
+    pub fn rock_types(&self) -> &[RockType] {
+        &self.rock_types
+    }
+
+    /// Returns the surface temperature of every cell in °C, as of carving.
+    pub fn temperature(&self) -> &[f64] {
+        &self.temperature
+    }
+
+    /// Returns the snow cover fraction of every cell, as of carving.
+    pub fn snow_cover(&self) -> &[f64] {
+        &self.snow_cover
+    }
+
+    /// Clips any other per-cell planet layer (e.g. resources or vegetation) to the region.
+    pub fn clip<T: Clone>(&self, planet_values: &[T]) -> Vec<T> {
+        self.source_cells.iter().map(|&c| planet_values[c].clone()).collect()
+    }
+}
@@ -0,0 +1,214 @@
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::raster::{lat_lon_to_unit, Raster};
+use crate::vector::{cross, dot, length, normalize, sub, Vec3};
+use rayon::prelude::*;
+
+/// Settings for the derived relief rasters: normal, slope and curvature maps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliefSettings {
+    pub projection: Projection,
+    pub width: usize,
+    pub height: usize,
+    pub exaggeration: f64,   // Multiplier on heights before normals and slopes are taken
+    pub flatten_ocean: bool, // Measure the sea surface instead of the sea floor, as `Planet::slope` does
+}
+
+/// A 1024 × 512 equirectangular map of the true relief, sea floor included.
+impl Default for ReliefSettings {
+    fn default() -> Self {
+        ReliefSettings {
+            projection: Projection::Equirectangular,
+            width: 1024,
+            height: 512,
+            exaggeration: 1.0,
+            flatten_ocean: false,
+        }
+    }
+}
+
+// East and north unit vectors of the tangent plane at a site
+fn tangent_basis(site: Vec3) -> (Vec3, Vec3) {
+    let pole = if site.2.abs() < 0.999_999 { (0.0, 0.0, 1.0) } else { (1.0, 0.0, 0.0) };
+    let east = normalize(cross(pole, site));
+    (east, cross(site, east))
+}
+
+impl Planet {
+    // Elevation as the relief maps see it
+    fn relief_heights(&self, flatten_ocean: bool) -> Vec<f64> {
+        if flatten_ocean {
+            self.elevation().iter().map(|&height| height.max(0.0)).collect()
+        } else {
+            self.elevation().to_vec()
+        }
+    }
+
+    /// Fits the elevation gradient at every cell.
+    ///
+    /// The gradient is the least-squares plane through the height differences to the cell's
+    /// neighbours, measured in the local tangent plane, so it is smooth where `Layer::Slope` (the
+    /// single steepest neighbour) is not.
+    ///
+    /// # Arguments
+    ///
+    /// * `flatten_ocean` - Treat cells below sea level as lying at sea level.
+    ///
+    /// # Returns
+    ///
+    /// One `(east, north)` gradient per cell in metres of rise per metre of distance.
+    pub fn elevation_gradients(&self, flatten_ocean: bool) -> Vec<(f64, f64)> {
+        let heights = self.relief_heights(flatten_ocean);
+        let metres = self.radius() * 1000.0;
+        (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let site = self.sites()[cell];
+                let (east, north) = tangent_basis(site);
+                let (mut xx, mut xy, mut yy, mut xh, mut yh) = (0.0, 0.0, 0.0, 0.0, 0.0);
+                for &neighbor in self.neighbors(cell) {
+                    let offset = sub(self.sites()[neighbor], site);
+                    let (x, y) = (dot(offset, east) * metres, dot(offset, north) * metres);
+                    let rise = heights[neighbor] - heights[cell];
+                    xx += x * x;
+                    xy += x * y;
+                    yy += y * y;
+                    xh += x * rise;
+                    yh += y * rise;
+                }
+                let determinant = xx * yy - xy * xy;
+                if determinant.abs() < f64::EPSILON {
+                    return (0.0, 0.0);
+                }
+                ((yy * xh - xy * yh) / determinant, (xx * yh - xy * xh) / determinant)
+            })
+            .collect()
+    }
+
+    /// Estimates the curvature of the terrain at every cell from the Laplacian of elevation.
+    ///
+    /// # Arguments
+    ///
+    /// * `flatten_ocean` - Treat cells below sea level as lying at sea level.
+    ///
+    /// # Returns
+    ///
+    /// One value per cell in 1/m: positive on ridges and peaks, negative in valleys and basins,
+    /// zero on planes.
+    pub fn curvatures(&self, flatten_ocean: bool) -> Vec<f64> {
+        let heights = self.relief_heights(flatten_ocean);
+        let metres = self.radius() * 1000.0;
+        (0..self.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                let neighbors = self.neighbors(cell);
+                if neighbors.is_empty() {
+                    return 0.0;
+                }
+                let count = neighbors.len() as f64;
+                let mean_height = neighbors.iter().map(|&n| heights[n]).sum::<f64>() / count;
+                let mean_spacing = neighbors.iter().map(|&n| (length(sub(self.sites()[n], self.sites()[cell])) * metres).powi(2)).sum::<f64>() / count;
+                // On an even mesh the neighbour mean exceeds the centre by a quarter of the
+                // spacing squared times the Laplacian
+                -4.0 * (mean_height - heights[cell]) / mean_spacing.max(f64::EPSILON)
+            })
+            .collect()
+    }
+
+    // Interpolates per-cell values at every pixel of a projected grid, linearly across the
+    // Delaunay triangle under the pixel; `None` outside the projected globe
+    fn sample_smooth<const N: usize>(&self, values: &[[f64; N]], projection: Projection, width: usize, height: usize) -> Vec<Option<[f64; N]>> {
+        let mut incident: Vec<Vec<usize>> = vec![Vec::new(); self.cell_count()];
+        for (index, triangle) in self.triangles().iter().enumerate() {
+            for &cell in triangle {
+                incident[cell].push(index);
+            }
+        }
+        let sites = self.sites();
+        self.rasterize_cells(projection, width, height)
+            .into_par_iter()
+            .enumerate()
+            .map(|(pixel, nearest)| {
+                let nearest = nearest?;
+                let u = (pixel % width) as f64 / width as f64 + 0.5 / width as f64;
+                let v = (pixel / width) as f64 / height as f64 + 0.5 / height as f64;
+                let point = projection.inverse(u, v).map_or(sites[nearest], |(lat, lon)| lat_lon_to_unit(lat, lon));
+                for &index in &incident[nearest] {
+                    let [a, b, c] = self.triangles()[index];
+                    let weights = [
+                        dot(cross(sites[b], sites[c]), point),
+                        dot(cross(sites[c], sites[a]), point),
+                        dot(cross(sites[a], sites[b]), point),
+                    ];
+                    if weights.iter().all(|&w| w >= -1e-12) {
+                        let total: f64 = weights.iter().sum();
+                        let mut out = [0.0; N];
+                        for (k, value) in out.iter_mut().enumerate() {
+                            *value = (weights[0] * values[a][k] + weights[1] * values[b][k] + weights[2] * values[c][k]) / total;
+                        }
+                        return Some(out);
+                    }
+                }
+                Some(values[nearest])
+            })
+            .collect()
+    }
+
+    /// Renders a tangent-space normal map of the terrain.
+    ///
+    /// Each pixel encodes the surface normal in the frame of the map: red is east (right), green
+    /// is north (up) and blue is straight up out of the surface, each mapped from -1..1 to 0..255,
+    /// the OpenGL convention; invert green for DirectX. Flat ground is `(128, 128, 255)`. Pixels
+    /// outside the projected globe are transparent.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - Projection, size, exaggeration and sea handling.
+    pub fn normal_map(&self, settings: &ReliefSettings) -> Image {
+        let gradients: Vec<[f64; 2]> = self.elevation_gradients(settings.flatten_ocean).into_iter().map(|(east, north)| [east, north]).collect();
+        let samples = self.sample_smooth(&gradients, settings.projection, settings.width, settings.height);
+        let mut image = Image::new(settings.width, settings.height, [128, 128, 255, 0]);
+        let encode = |component: f64| ((component * 0.5 + 0.5) * 255.0).round().clamp(0.0, 255.0) as u8;
+        for (pixel, sample) in samples.into_iter().enumerate() {
+            if let Some([east, north]) = sample {
+                let (x, y, z) = normalize((-east * settings.exaggeration, -north * settings.exaggeration, 1.0));
+                image.set(pixel % settings.width, pixel / settings.width, [encode(x), encode(y), encode(z), 255]);
+            }
+        }
+        image
+    }
+
+    /// Renders the slope of the terrain in degrees, 0 on flat ground.
+    ///
+    /// # Returns
+    ///
+    /// A `Raster` of `settings.height` rows by `settings.width` columns; `NaN` outside the
+    /// projected globe. `Image::from_raster_gray(&raster, 0.0, 90.0)` turns it into a mask.
+    pub fn slope_map(&self, settings: &ReliefSettings) -> Raster {
+        let gradients: Vec<[f64; 2]> = self.elevation_gradients(settings.flatten_ocean).into_iter().map(|(east, north)| [east, north]).collect();
+        let data = self
+            .sample_smooth(&gradients, settings.projection, settings.width, settings.height)
+            .into_iter()
+            .map(|sample| sample.map_or(f64::NAN, |[east, north]| (east.hypot(north) * settings.exaggeration).atan().to_degrees()))
+            .collect();
+        Raster::from_vec(settings.width, settings.height, data)
+    }
+
+    /// Renders the curvature of the terrain from `Planet::curvatures`, scaled by the exaggeration.
+    ///
+    /// # Returns
+    ///
+    /// A `Raster` in 1/m, positive on ridges and negative in valleys; `NaN` outside the projected
+    /// globe.
+    pub fn curvature_map(&self, settings: &ReliefSettings) -> Raster {
+        let curvatures: Vec<[f64; 1]> = self.curvatures(settings.flatten_ocean).into_iter().map(|c| [c * settings.exaggeration]).collect();
+        let data = self
+            .sample_smooth(&curvatures, settings.projection, settings.width, settings.height)
+            .into_iter()
+            .map(|sample| sample.map_or(f64::NAN, |[curvature]| curvature))
+            .collect();
+        Raster::from_vec(settings.width, settings.height, data)
+    }
+}
+
@@ -0,0 +1,213 @@
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::raster::lat_lon_to_unit;
+use crate::render::elevation_color;
+use crate::vector::{add, cross, dot, length, normalize, scale, sub, Vec3};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+/// Settings for the offline globe renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobeSettings {
+    pub width: usize,
+    pub height: usize,
+    pub view_latitude: f64,  // Degrees; the point on the globe facing the camera
+    pub view_longitude: f64,
+    pub sun_latitude: f64,   // Degrees; the subsolar point
+    pub sun_longitude: f64,
+    pub exaggeration: f64,   // Vertical exaggeration applied to terrain shading and shadows
+    pub shadow_samples: usize, // Jittered sun directions averaged for soft shadows
+    pub sun_angular_radius: f64, // Degrees; larger values give softer shadows
+    pub ambient: f64,        // Light reaching fully shadowed surfaces, 0.0 to 1.0
+}
+
+impl Default for GlobeSettings {
+    fn default() -> Self {
+        GlobeSettings {
+            width: 512,
+            height: 512,
+            view_latitude: 20.0,
+            view_longitude: 0.0,
+            sun_latitude: 10.0,
+            sun_longitude: -40.0,
+            exaggeration: 20.0,
+            shadow_samples: 8,
+            sun_angular_radius: 2.0,
+            ambient: 0.08,
+        }
+    }
+}
+
+impl GlobeSettings {
+    /// Places the sun where it stands over `planet` at `time` (days since generation).
+    pub fn with_sun_at_time(mut self, planet: &Planet, time: f64) -> Self {
+        let (lat, lon) = planet.subsolar_point(time);
+        self.sun_latitude = lat;
+        self.sun_longitude = lon;
+        self
+    }
+}
+
+// An orthonormal tangent basis around `axis`
+fn basis(axis: Vec3) -> (Vec3, Vec3) {
+    let helper = if axis.2.abs() < 0.99 { (0.0, 0.0, 1.0) } else { (1.0, 0.0, 0.0) };
+    let right = normalize(cross(helper, axis));
+    (right, cross(axis, right))
+}
+
+// Per-cell surface normal perturbed by the elevation gradient
+fn shading_normal(planet: &Planet, cell: usize, exaggeration: f64) -> Vec3 {
+    let site = planet.sites()[cell];
+    if planet.is_ocean(cell) {
+        return site;
+    }
+    let elevation = planet.elevation();
+    let radius_m = planet.radius() * 1000.0;
+    let mut gradient = (0.0, 0.0, 0.0);
+    for &neighbor in planet.neighbors(cell) {
+        let offset = sub(planet.sites()[neighbor], site);
+        let tangent = sub(offset, scale(site, dot(offset, site)));
+        let distance = length(tangent) * radius_m;
+        if distance > 0.0 {
+            let slope = (elevation[neighbor].max(0.0) - elevation[cell]) / distance;
+            gradient = add(gradient, scale(normalize(tangent), slope));
+        }
+    }
+    let count = planet.neighbors(cell).len().max(1) as f64;
+    normalize(sub(site, scale(gradient, exaggeration / count)))
+}
+
+// Fraction of jittered sun directions that are not blocked by terrain, marching toward the sun
+fn sun_visibility(planet: &Planet, cell: usize, sun: Vec3, settings: &GlobeSettings) -> f64 {
+    let site = planet.sites()[cell];
+    let elevation = planet.elevation();
+    let radius_m = planet.radius() * 1000.0;
+    let spacing = (4.0 * PI / planet.cell_count().max(1) as f64).sqrt();
+    let base = elevation[cell].max(0.0) * settings.exaggeration;
+    let (right, up) = basis(sun);
+    let spread = settings.sun_angular_radius.to_radians().tan();
+    let samples = settings.shadow_samples.max(1);
+
+    let mut lit = 0;
+    for s in 0..samples {
+        // Golden-angle disc distribution of sun directions
+        let r = ((s as f64 + 0.5) / samples as f64).sqrt() * spread;
+        let theta = s as f64 * PI * (3.0 - 5.0_f64.sqrt());
+        let direction = normalize(add(sun, add(scale(right, r * theta.cos()), scale(up, r * theta.sin()))));
+
+        let altitude = dot(site, direction).clamp(-1.0, 1.0).asin();
+        if altitude <= 0.0 {
+            continue;
+        }
+        let toward = normalize(sub(direction, scale(site, dot(direction, site))));
+        let mut hint = cell;
+        let mut blocked = false;
+        for step in 1..=12 {
+            let angle = step as f64 * spacing * 1.5;
+            let point = add(scale(site, angle.cos()), scale(toward, angle.sin()));
+            hint = planet.nearest_cell(point, hint);
+            let distance = angle * radius_m;
+            let rise = elevation[hint].max(0.0) * settings.exaggeration - base - distance * distance / (2.0 * radius_m);
+            if rise.atan2(distance) > altitude {
+                blocked = true;
+                break;
+            }
+        }
+        if !blocked {
+            lit += 1;
+        }
+    }
+    lit as f64 / samples as f64
+}
+
+/// Renders a lit image of the planet as seen from space.
+///
+/// Each pixel is ray-cast against the globe. Surfaces are colored by elevation, shaded by the
+/// sun with terrain-derived normals and soft shadows, oceans get a specular highlight, and an
+/// optional cloud layer is composited on top.
+///
+/// # Arguments
+///
+/// * `planet` - The planet to render.
+/// * `clouds` - Optional per-cell cloud cover, 0.0 to 1.0 (e.g. `CloudLayer::coverage`).
+/// * `settings` - Camera, sun and quality settings.
+///
+/// # Returns
+///
+/// An RGBA image; pixels that miss the globe are black.
+pub fn render_globe(planet: &Planet, clouds: Option<&[f64]>, settings: &GlobeSettings) -> Image {
+    let mut image = Image::new(settings.width, settings.height, [0, 0, 0, 255]);
+    if planet.cell_count() == 0 {
+        return image;
+    }
+
+    let view = lat_lon_to_unit(settings.view_latitude, settings.view_longitude);
+    let sun = lat_lon_to_unit(settings.sun_latitude, settings.sun_longitude);
+    let half = normalize(add(sun, view));
+
+    // Lighting is evaluated once per cell, then looked up per pixel
+    let lighting: Vec<(f64, f64)> = (0..planet.cell_count())
+        .into_par_iter()
+        .map(|cell| {
+            let normal = shading_normal(planet, cell, settings.exaggeration);
+            let diffuse = dot(normal, sun).max(0.0);
+            let visibility = if diffuse > 0.0 { sun_visibility(planet, cell, sun, settings) } else { 0.0 };
+            (diffuse, visibility)
+        })
+        .collect();
+
+    let (right, up) = basis(view);
+    let size = settings.width.min(settings.height) as f64;
+    let rows: Vec<Vec<[u8; 4]>> = (0..settings.height)
+        .into_par_iter()
+        .map(|y| {
+            let mut hint = 0;
+            (0..settings.width)
+                .map(|x| {
+                    let u = (2.0 * (x as f64 + 0.5) - settings.width as f64) / size;
+                    let v = (settings.height as f64 - 2.0 * (y as f64 + 0.5)) / size;
+                    let r2 = u * u + v * v;
+                    if r2 > 1.0 {
+                        return [0, 0, 0, 255];
+                    }
+                    let point = add(scale(view, (1.0 - r2).sqrt()), add(scale(right, u), scale(up, v)));
+                    hint = planet.nearest_cell(point, hint);
+                    let cell = hint;
+
+                    let base = elevation_color(planet.elevation()[cell]);
+                    let (diffuse, visibility) = lighting[cell];
+                    let light = settings.ambient + (1.0 - settings.ambient) * diffuse * visibility;
+                    let mut color = [base[0] as f64 * light, base[1] as f64 * light, base[2] as f64 * light];
+
+                    if planet.is_ocean(cell) && visibility > 0.0 {
+                        let specular = dot(point, half).max(0.0).powf(60.0) * 200.0 * visibility;
+                        for channel in color.iter_mut() {
+                            *channel += specular;
+                        }
+                    }
+
+                    if let Some(cover) = clouds.map(|c| c[cell].clamp(0.0, 1.0) * 0.85) {
+                        let cloud_light = 255.0 * (settings.ambient + (1.0 - settings.ambient) * dot(point, sun).max(0.0));
+                        for channel in color.iter_mut() {
+                            *channel = *channel * (1.0 - cover) + cloud_light * cover;
+                        }
+                    }
+
+                    [
+                        color[0].clamp(0.0, 255.0) as u8,
+                        color[1].clamp(0.0, 255.0) as u8,
+                        color[2].clamp(0.0, 255.0) as u8,
+                        255,
+                    ]
+                })
+                .collect()
+        })
+        .collect();
+
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, pixel) in row.into_iter().enumerate() {
+            image.set(x, y, pixel);
+        }
+    }
+    image
+}
@@ -0,0 +1,228 @@
+use crate::geom::spherical::{densify_lat_lon, GeodesicSubdivision};
+use crate::image::Image;
+use crate::mesh::base64;
+use crate::nations::NationBorder;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::render::elevation_color;
+use crate::render::materials::MaterialPalette;
+use crate::vegetation::VegetationLayer;
+use std::fmt::Write;
+
+/// What fills the land and sea of a flat map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapBase {
+    /// Hypsometric tint of elevation and depth.
+    #[default]
+    Elevation,
+    /// The surface material of each cell in the default `MaterialPalette` colors.
+    Biomes,
+}
+
+/// Settings for flat map rendering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapSettings {
+    pub projection: Projection,
+    pub width: usize,
+    pub height: usize,
+    pub base: MapBase,
+    pub background: [u8; 4],              // RGBA outside the projected globe
+    pub river_color: [u8; 4],             // RGBA
+    pub border_color: [u8; 4],            // RGBA
+    pub line_width: f64,                  // Pixels
+    pub subdivision: GeodesicSubdivision, // Splitting of line segments so they follow great circles
+}
+
+/// A 1024 × 512 equirectangular elevation map with blue rivers and red borders on a transparent
+/// background.
+impl Default for MapSettings {
+    fn default() -> Self {
+        MapSettings {
+            projection: Projection::Equirectangular,
+            width: 1024,
+            height: 512,
+            base: MapBase::Elevation,
+            background: [0, 0, 0, 0],
+            river_color: [40, 90, 200, 255],
+            border_color: [200, 30, 30, 255],
+            line_width: 1.5,
+            subdivision: GeodesicSubdivision::default(),
+        }
+    }
+}
+
+/// The line layers drawn over the base of a flat map; each defaults to empty.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MapLayers<'a> {
+    pub rivers: &'a [Vec<usize>],    // Each river as an ordered chain of cells
+    pub borders: &'a [NationBorder], // E.g. from `NationMap::borders`
+}
+
+// Converts a linear color channel to an sRGB byte
+fn linear_to_srgb(channel: f32) -> u8 {
+    let c = channel.clamp(0.0, 1.0);
+    let encoded = if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (encoded * 255.0).round() as u8
+}
+
+// The base color of every cell
+pub(crate) fn cell_colors(planet: &Planet, base: MapBase) -> Vec<[u8; 3]> {
+    match base {
+        MapBase::Elevation => planet.elevation().iter().map(|&height| elevation_color(height)).collect(),
+        MapBase::Biomes => {
+            let palette = MaterialPalette::default();
+            planet
+                .materials(&VegetationLayer::from_planet(planet))
+                .into_iter()
+                .map(|material| palette.entry(material).albedo.map(linear_to_srgb))
+                .collect()
+        }
+    }
+}
+
+// The base layer as pixels
+fn base_image(planet: &Planet, settings: &MapSettings) -> Image {
+    let (width, height) = (settings.width, settings.height);
+    let colors = cell_colors(planet, settings.base);
+    let mut image = Image::new(width, height, settings.background);
+    for (pixel, cell) in planet.rasterize_cells(settings.projection, width, height).into_iter().enumerate() {
+        if let Some(cell) = cell {
+            let [r, g, b] = colors[cell];
+            image.set(pixel % width, pixel / width, [r, g, b, 255]);
+        }
+    }
+    image
+}
+
+// The overlay a line belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overlay {
+    River,
+    Border,
+}
+
+impl Overlay {
+    fn color(&self, settings: &MapSettings) -> [u8; 4] {
+        match self {
+            Overlay::River => settings.river_color,
+            Overlay::Border => settings.border_color,
+        }
+    }
+}
+
+// Every line of the overlays in pixel coordinates, rivers first, split where it wraps around
+// the map edge
+fn overlay_lines(planet: &Planet, layers: &MapLayers, settings: &MapSettings) -> Vec<(Vec<(f64, f64)>, Overlay)> {
+    let rivers = layers.rivers.iter().map(|cells| (cells.iter().map(|&cell| planet.lat_lon(cell)).collect::<Vec<_>>(), Overlay::River));
+    let borders = layers.borders.iter().map(|border| (border.points.clone(), Overlay::Border));
+    let mut lines = Vec::new();
+    for (points, overlay) in rivers.chain(borders) {
+        let mut line: Vec<(f64, f64)> = Vec::new();
+        let mut previous_longitude = None;
+        for (latitude, longitude) in densify_lat_lon(&points, settings.subdivision) {
+            let wraps = previous_longitude.is_some_and(|previous: f64| (longitude - previous).abs() > 180.0);
+            previous_longitude = Some(longitude);
+            if wraps && !line.is_empty() {
+                lines.push((std::mem::take(&mut line), overlay));
+            }
+            if let Some((u, v)) = settings.projection.forward(latitude, longitude) {
+                line.push((u * settings.width as f64, v * settings.height as f64));
+            }
+        }
+        lines.push((line, overlay));
+    }
+    lines.retain(|(line, _)| line.len() >= 2);
+    lines
+}
+
+// Alpha-blends a color over one pixel
+fn blend(image: &mut Image, x: usize, y: usize, color: [u8; 4], coverage: f64) {
+    let alpha = color[3] as f64 / 255.0 * coverage.clamp(0.0, 1.0);
+    let under = image.get(x, y);
+    let mix = |over: u8, under: u8| (over as f64 * alpha + under as f64 * (1.0 - alpha)).round() as u8;
+    let out_alpha = (alpha * 255.0 + under[3] as f64 * (1.0 - alpha)).round() as u8;
+    image.set(x, y, [mix(color[0], under[0]), mix(color[1], under[1]), mix(color[2], under[2]), out_alpha]);
+}
+
+// Draws an antialiased line segment of a given width
+fn draw_segment(image: &mut Image, a: (f64, f64), b: (f64, f64), width: f64, color: [u8; 4]) {
+    let half = width / 2.0;
+    let min_x = (a.0.min(b.0) - half - 1.0).floor().max(0.0) as usize;
+    let min_y = (a.1.min(b.1) - half - 1.0).floor().max(0.0) as usize;
+    let max_x = ((a.0.max(b.0) + half + 1.0).ceil().max(0.0) as usize).min(image.width());
+    let max_y = ((a.1.max(b.1) + half + 1.0).ceil().max(0.0) as usize).min(image.height());
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let t = if length_squared > 0.0 { (((px - a.0) * dx + (py - a.1) * dy) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+            let distance = ((px - a.0 - t * dx).powi(2) + (py - a.1 - t * dy).powi(2)).sqrt();
+            let coverage = half + 0.5 - distance;
+            if coverage > 0.0 {
+                blend(image, x, y, color, coverage);
+            }
+        }
+    }
+}
+
+/// Renders a flat map of a planet as an image, ready for `Image::write_png`.
+///
+/// The base layer fills every pixel inside the projected globe; rivers and then borders are
+/// drawn over it as antialiased lines. Lines are split where they cross the antimeridian rather
+/// than drawn across the map.
+///
+/// # Arguments
+///
+/// * `planet` - The planet to draw.
+/// * `layers` - The rivers and borders to overlay.
+/// * `settings` - Projection, size, base layer and line styles.
+pub fn render_map(planet: &Planet, layers: &MapLayers, settings: &MapSettings) -> Image {
+    let mut image = base_image(planet, settings);
+    for (line, overlay) in overlay_lines(planet, layers, settings) {
+        for pair in line.windows(2) {
+            draw_segment(&mut image, pair[0], pair[1], settings.line_width, overlay.color(settings));
+        }
+    }
+    image
+}
+
+/// Renders a flat map of a planet as an SVG document.
+///
+/// The base layer is embedded as a PNG image and the rivers and borders are vector polylines in
+/// the groups `rivers` and `borders`, so they stay sharp when zoomed and can be restyled in any
+/// SVG editor. Coordinates are in pixels of `settings.width` × `settings.height`.
+///
+/// # Arguments
+///
+/// * `planet` - The planet to draw.
+/// * `layers` - The rivers and borders to overlay.
+/// * `settings` - Projection, size, base layer and line styles.
+pub fn render_map_svg(planet: &Planet, layers: &MapLayers, settings: &MapSettings) -> String {
+    let (width, height) = (settings.width, settings.height);
+    let base = base_image(planet, settings).encode_png();
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#);
+    let _ = writeln!(svg, r#"  <image width="{}" height="{}" href="data:image/png;base64,{}"/>"#, width, height, base64(&base));
+    let lines = overlay_lines(planet, layers, settings);
+    for (group, overlay) in [("rivers", Overlay::River), ("borders", Overlay::Border)] {
+        let color = overlay.color(settings);
+        let _ = writeln!(
+            svg,
+            r##"  <g id="{}" fill="none" stroke="#{:02x}{:02x}{:02x}" stroke-opacity="{:.3}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round">"##,
+            group,
+            color[0],
+            color[1],
+            color[2],
+            color[3] as f64 / 255.0,
+            settings.line_width
+        );
+        for (line, _) in lines.iter().filter(|(_, kind)| *kind == overlay) {
+            let points: Vec<String> = line.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+            let _ = writeln!(svg, r#"    <polyline points="{}"/>"#, points.join(" "));
+        }
+        let _ = writeln!(svg, "  </g>");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
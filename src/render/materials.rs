@@ -0,0 +1,240 @@
+use crate::ice::IceState;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::vegetation::VegetationLayer;
+use serde_json::{json, Value};
+
+/// A terrain material: one layer of a texture-array terrain shader.
+///
+/// The numeric code is the layer index in the texture array and the value stored in the material
+/// ID layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Material {
+    /// Ocean deeper than the continental shelf.
+    DeepWater = 0,
+    /// Ocean over the continental shelf.
+    ShallowWater = 1,
+    /// Beaches and low coastal land.
+    Sand = 2,
+    /// Sparse vegetation on dry ground.
+    Desert = 3,
+    /// Open grassland and scrub.
+    Grass = 4,
+    /// Dense vegetation.
+    Forest = 5,
+    /// Cold, sparsely vegetated ground.
+    Tundra = 6,
+    /// Bare rock on steep slopes and high ground.
+    Rock = 7,
+    /// Snow and land ice.
+    Snow = 8,
+    /// Frozen ocean.
+    SeaIce = 9,
+}
+
+impl Material {
+    /// Every material in code order.
+    pub const ALL: [Material; 10] = [
+        Material::DeepWater,
+        Material::ShallowWater,
+        Material::Sand,
+        Material::Desert,
+        Material::Grass,
+        Material::Forest,
+        Material::Tundra,
+        Material::Rock,
+        Material::Snow,
+        Material::SeaIce,
+    ];
+
+    /// Returns the lowercase name used in palette descriptions.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Material::DeepWater => "deep_water",
+            Material::ShallowWater => "shallow_water",
+            Material::Sand => "sand",
+            Material::Desert => "desert",
+            Material::Grass => "grass",
+            Material::Forest => "forest",
+            Material::Tundra => "tundra",
+            Material::Rock => "rock",
+            Material::Snow => "snow",
+            Material::SeaIce => "sea_ice",
+        }
+    }
+}
+
+/// How a material blends into its neighbors in the shader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendRule {
+    /// A linear cross-fade over the transition width.
+    Linear,
+    /// A height-based blend: the material with the taller height map wins, giving sharp,
+    /// natural-looking edges such as rock poking through grass. The value is the blend contrast.
+    Height(f32),
+    /// No blending; the material edge stays hard, e.g. for water.
+    Hard,
+}
+
+/// The shading parameters of one material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialEntry {
+    pub material: Material,
+    pub albedo: [f32; 3], // Linear RGB, 0.0 to 1.0
+    pub roughness: f32,   // 0.0 (mirror) to 1.0 (fully diffuse)
+    pub blend: BlendRule,
+    pub blend_width: f32, // Transition width in cells
+    pub priority: u8,     // Wins ties in height blending; higher draws on top
+}
+
+/// The shading description of every material, indexed by material code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialPalette {
+    entries: Vec<MaterialEntry>,
+}
+
+impl Default for MaterialPalette {
+    /// A naturalistic palette with height blending between land materials and hard water edges.
+    fn default() -> Self {
+        let entry = |material, albedo, roughness, blend, blend_width, priority| MaterialEntry {
+            material,
+            albedo,
+            roughness,
+            blend,
+            blend_width,
+            priority,
+        };
+        MaterialPalette {
+            entries: vec![
+                entry(Material::DeepWater, [0.02, 0.05, 0.16], 0.05, BlendRule::Linear, 2.0, 0),
+                entry(Material::ShallowWater, [0.05, 0.18, 0.30], 0.08, BlendRule::Hard, 0.0, 1),
+                entry(Material::Sand, [0.76, 0.69, 0.50], 0.90, BlendRule::Linear, 1.0, 2),
+                entry(Material::Desert, [0.80, 0.60, 0.38], 0.95, BlendRule::Height(0.4), 1.5, 3),
+                entry(Material::Grass, [0.30, 0.45, 0.18], 0.85, BlendRule::Linear, 1.5, 4),
+                entry(Material::Forest, [0.12, 0.28, 0.10], 0.90, BlendRule::Height(0.3), 1.0, 5),
+                entry(Material::Tundra, [0.45, 0.45, 0.36], 0.90, BlendRule::Linear, 1.5, 4),
+                entry(Material::Rock, [0.38, 0.35, 0.32], 0.80, BlendRule::Height(0.6), 0.75, 6),
+                entry(Material::Snow, [0.92, 0.93, 0.95], 0.60, BlendRule::Height(0.5), 1.0, 7),
+                entry(Material::SeaIce, [0.80, 0.86, 0.90], 0.40, BlendRule::Hard, 0.0, 2),
+            ],
+        }
+    }
+}
+
+impl MaterialPalette {
+    /// Returns the entries in material code order.
+    pub fn entries(&self) -> &[MaterialEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry of a material.
+    pub fn entry(&self, material: Material) -> &MaterialEntry {
+        &self.entries[material as usize]
+    }
+
+    /// Replaces the entry of a material.
+    pub fn set_entry(&mut self, entry: MaterialEntry) {
+        let index = entry.material as usize;
+        self.entries[index] = entry;
+    }
+
+    /// Describes the palette as JSON for engine import.
+    ///
+    /// The result is an object with a `materials` array in layer order; each element carries
+    /// `id`, `name`, `albedo`, `roughness`, `blend` (`"linear"`, `"height"` or `"hard"`),
+    /// `blend_contrast`, `blend_width` and `priority`.
+    pub fn to_json(&self) -> Value {
+        let materials: Vec<Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let (blend, contrast) = match entry.blend {
+                    BlendRule::Linear => ("linear", 0.0),
+                    BlendRule::Height(contrast) => ("height", contrast),
+                    BlendRule::Hard => ("hard", 0.0),
+                };
+                json!({
+                    "id": entry.material as u8,
+                    "name": entry.material.name(),
+                    "albedo": entry.albedo,
+                    "roughness": entry.roughness,
+                    "blend": blend,
+                    "blend_contrast": contrast,
+                    "blend_width": entry.blend_width,
+                    "priority": entry.priority,
+                })
+            })
+            .collect();
+        json!({ "materials": materials })
+    }
+}
+
+// Depth of the continental shelf edge in metres
+const SHELF_DEPTH: f64 = -200.0;
+// Coastal land below this elevation in metres is sand
+const BEACH_HEIGHT: f64 = 20.0;
+// Slopes steeper than this in degrees expose bare rock
+const ROCK_SLOPE: f64 = 30.0;
+
+impl Planet {
+    /// Assigns a terrain material to every cell.
+    ///
+    /// Ice comes from `ice_state`; on ice-free ground steep slopes are rock, low coasts are sand,
+    /// and the rest follows vegetation density and mean annual temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `vegetation` - The vegetation layer, e.g. from `VegetationLayer::from_planet`.
+    ///
+    /// # Returns
+    ///
+    /// One material per cell.
+    pub fn materials(&self, vegetation: &VegetationLayer) -> Vec<Material> {
+        let ice = self.ice_state();
+        let temperature = self.mean_annual_temperature();
+        let density = vegetation.density();
+        let elevation = self.elevation();
+        (0..self.cell_count())
+            .map(|cell| {
+                let height = elevation[cell];
+                match ice[cell] {
+                    IceState::PermanentIce | IceState::SeaIce if height < 0.0 => Material::SeaIce,
+                    IceState::PermanentIce | IceState::SeasonalSnow => Material::Snow,
+                    _ if height < SHELF_DEPTH => Material::DeepWater,
+                    _ if height < 0.0 => Material::ShallowWater,
+                    _ if self.slope(cell) > ROCK_SLOPE => Material::Rock,
+                    _ if height < BEACH_HEIGHT => Material::Sand,
+                    _ if temperature[cell] < 0.0 => Material::Tundra,
+                    _ if density[cell] > 0.6 => Material::Forest,
+                    _ if density[cell] > 0.25 => Material::Grass,
+                    _ => Material::Desert,
+                }
+            })
+            .collect()
+    }
+
+    /// Renders a material ID indirection texture for texture-array terrain shaders.
+    ///
+    /// # Arguments
+    ///
+    /// * `materials` - One material per cell, e.g. from `materials`.
+    /// * `projection` - The map projection of the texture.
+    /// * `width`, `height` - The texture size in pixels.
+    ///
+    /// # Returns
+    ///
+    /// One material code per pixel in row-major order; pixels outside the projected globe get
+    /// `u8::MAX`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `materials` does not have one value per cell.
+    pub fn material_id_texture(&self, materials: &[Material], projection: Projection, width: usize, height: usize) -> Vec<u8> {
+        assert_eq!(materials.len(), self.cell_count(), "materials must have one value per cell");
+        self.rasterize_cells(projection, width, height)
+            .into_iter()
+            .map(|cell| cell.map_or(u8::MAX, |cell| materials[cell] as u8))
+            .collect()
+    }
+}
@@ -0,0 +1,50 @@
+pub mod globe;
+pub mod map;
+pub mod materials;
+
+/// Returns the hypsometric tint for an elevation, the classic atlas coloring of depth and height.
+///
+/// # Arguments
+///
+/// * `elevation` - Elevation relative to sea level in metres.
+///
+/// # Returns
+///
+/// An RGB color.
+pub fn elevation_color(elevation: f64) -> [u8; 3] {
+    const OCEAN: [(f64, [f64; 3]); 3] = [
+        (-10000.0, [10.0, 20.0, 70.0]),
+        (-200.0, [40.0, 90.0, 170.0]),
+        (0.0, [70.0, 130.0, 200.0]),
+    ];
+    const LAND: [(f64, [f64; 3]); 5] = [
+        (0.0, [60.0, 130.0, 60.0]),
+        (500.0, [120.0, 160.0, 80.0]),
+        (2000.0, [160.0, 130.0, 90.0]),
+        (4000.0, [140.0, 120.0, 110.0]),
+        (6000.0, [250.0, 250.0, 250.0]),
+    ];
+    let stops: &[(f64, [f64; 3])] = if elevation < 0.0 { &OCEAN } else { &LAND };
+    gradient_color(stops, elevation)
+}
+
+/// Linearly interpolates a color ramp given as `(value, rgb)` stops sorted by value.
+pub(crate) fn gradient_color(stops: &[(f64, [f64; 3])], value: f64) -> [u8; 3] {
+    let first = stops[0];
+    let last = stops[stops.len() - 1];
+    let rgb = if value <= first.0 {
+        first.1
+    } else if value >= last.0 {
+        last.1
+    } else {
+        let i = stops.windows(2).position(|w| value <= w[1].0).unwrap_or(0);
+        let (a, b) = (stops[i], stops[i + 1]);
+        let t = (value - a.0) / (b.0 - a.0);
+        [
+            a.1[0] + (b.1[0] - a.1[0]) * t,
+            a.1[1] + (b.1[1] - a.1[1]) * t,
+            a.1[2] + (b.1[2] - a.1[2]) * t,
+        ]
+    };
+    [rgb[0].round() as u8, rgb[1].round() as u8, rgb[2].round() as u8]
+}
@@ -0,0 +1,163 @@
+use crate::geology::RockType;
+use crate::noise::fbm;
+use crate::planet::Planet;
+use crate::tectonics::{BoundaryKind, Tectonics};
+use crate::vector::scale;
+
+/// A kind of exploitable deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ResourceKind {
+    /// Banded iron formations in old, stable continental interiors.
+    Iron = 1,
+    /// Porphyry copper above young subduction zones.
+    Copper = 2,
+    /// Gold veins along long-lived convergent margins.
+    Gold = 3,
+    /// Oil and gas trapped in sedimentary basins and continental shelves.
+    Oil = 4,
+    /// Hot ground above volcanic arcs and hotspots.
+    Geothermal = 5,
+}
+
+impl ResourceKind {
+    /// Every resource kind, in declaration order.
+    pub const ALL: [ResourceKind; 5] = [
+        ResourceKind::Iron,
+        ResourceKind::Copper,
+        ResourceKind::Gold,
+        ResourceKind::Oil,
+        ResourceKind::Geothermal,
+    ];
+
+    // How strong a cell's suitability must be before a deposit forms; rarer kinds sit higher
+    fn threshold(&self) -> f64 {
+        match self {
+            ResourceKind::Iron => 0.3,
+            ResourceKind::Copper => 0.4,
+            ResourceKind::Gold => 0.5,
+            ResourceKind::Oil => 0.4,
+            ResourceKind::Geothermal => 0.35,
+        }
+    }
+}
+
+/// A deposit in one cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceDeposit {
+    pub kind: ResourceKind,
+    pub richness: f64, // From just above 0.0 (marginal) to 1.0 (world class)
+}
+
+/// The resource deposits of every cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceMap {
+    deposits: Vec<Option<ResourceDeposit>>,
+}
+
+// Distance in km from a convergent boundary over which ore-forming fluids reach
+const ORE_BELT_KM: f64 = 600.0;
+// Boundary age in million years treated as "old" for gold and "young" for copper
+const OLD_BOUNDARY_MYR: f64 = 250.0;
+// Deepest seabed in metres still counted as continental shelf for oil
+const SHELF_DEPTH: f64 = -300.0;
+
+impl ResourceMap {
+    /// Places deposits deterministically from the planet's geology, its tectonic history and noise.
+    ///
+    /// Each cell gets a suitability score per resource kind from the geological setting, which is
+    /// then modulated by seeded noise so deposits form clustered veins and fields rather than
+    /// uniform bands. A cell holds at most one deposit: the kind whose score clears its threshold
+    /// by the widest margin.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet, with elevation and rock types generated.
+    /// * `tectonics` - The plate layout of the planet.
+    /// * `seed` - The resource seed.
+    pub fn generate(planet: &Planet, tectonics: &Tectonics, seed: u64) -> ResourceMap {
+        let convergent = tectonics.nearest_boundary(planet, BoundaryKind::Convergent);
+        let interior = tectonics.interior_depth(planet);
+        let deepest = interior.iter().copied().filter(|&d| d != usize::MAX).max().unwrap_or(0).max(1) as f64;
+        let volcanic = tectonics.volcanic_activity();
+        let elevation = planet.elevation();
+        let rock_types = planet.rock_types();
+
+        let deposits = (0..planet.cell_count())
+            .map(|cell| {
+                let p = planet.sites()[cell];
+                let height = elevation[cell];
+                let land = height >= 0.0;
+                let rock = rock_types[cell];
+
+                let (belt, age) = match convergent[cell] {
+                    Some((boundary, distance)) => ((1.0 - distance / ORE_BELT_KM).max(0.0), (boundary.age / OLD_BOUNDARY_MYR).min(1.0)),
+                    None => (0.0, 0.0),
+                };
+                let craton = if interior[cell] == usize::MAX { 0.0 } else { interior[cell] as f64 / deepest };
+
+                let suitability = |kind: ResourceKind| -> f64 {
+                    match kind {
+                        ResourceKind::Iron if land && matches!(rock, RockType::Granite | RockType::Sedimentary) => craton,
+                        ResourceKind::Copper if height > SHELF_DEPTH => belt * (1.0 - 0.6 * age),
+                        ResourceKind::Gold if land => belt * (0.4 + 0.6 * age),
+                        ResourceKind::Oil if height > SHELF_DEPTH
+                            && (!land || matches!(rock, RockType::Sedimentary | RockType::Limestone | RockType::Alluvium)) =>
+                        {
+                            (1.0 - volcanic[cell] * 2.0).max(0.0) * (1.0 - height.max(0.0) / 1500.0).max(0.0)
+                        }
+                        ResourceKind::Geothermal if height > SHELF_DEPTH => volcanic[cell],
+                        _ => 0.0,
+                    }
+                };
+
+                ResourceKind::ALL
+                    .iter()
+                    .filter_map(|&kind| {
+                        let frequency = if kind == ResourceKind::Oil { 6.0 } else { 12.0 };
+                        let noise = fbm(seed ^ (kind as u64).wrapping_mul(0x9E37_79B9), scale(p, frequency), 3, 2.0, 0.5);
+                        let score = suitability(kind) * (0.5 + noise).clamp(0.0, 1.0);
+                        let margin = (score - kind.threshold()) / (1.0 - kind.threshold());
+                        (margin > 0.0).then_some(ResourceDeposit {
+                            kind,
+                            richness: margin.min(1.0),
+                        })
+                    })
+                    .max_by(|a, b| a.richness.total_cmp(&b.richness))
+            })
+            .collect();
+
+        ResourceMap { deposits }
+    }
+
+    /// Returns the deposit in a cell, if any.
+    pub fn deposit(&self, cell: usize) -> Option<ResourceDeposit> {
+        self.deposits[cell]
+    }
+
+    /// Returns the deposit of every cell.
+    pub fn deposits(&self) -> &[Option<ResourceDeposit>] {
+        &self.deposits
+    }
+
+    /// Returns the resource kind of each cell as a code: `0` for none, otherwise the
+    /// `ResourceKind` discriminant.
+    pub fn kind_codes(&self) -> Vec<u8> {
+        self.deposits.iter().map(|d| d.map_or(0, |d| d.kind as u8)).collect()
+    }
+
+    /// Returns the richness of every cell, `0.0` where there is no deposit.
+    pub fn richness(&self) -> Vec<f64> {
+        self.deposits.iter().map(|d| d.map_or(0.0, |d| d.richness)).collect()
+    }
+
+    /// Returns the cells holding a deposit of the given kind.
+    pub fn cells_with(&self, kind: ResourceKind) -> Vec<usize> {
+        self.deposits
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.is_some_and(|d| d.kind == kind))
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+}
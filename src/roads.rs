@@ -0,0 +1,195 @@
+use crate::civilization::Settlement;
+use crate::pathfinding::TravelCost;
+use crate::planet::Planet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Settings for road network generation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadSettings {
+    pub travel: TravelCost,  // Terrain cost model; oceans and steep slopes are impassable by default
+    pub links: usize,        // Each settlement is linked to this many nearest neighbours
+    pub max_link_km: f64,    // Settlements further apart than this are not linked directly
+    pub reuse_discount: f64, // Cost multiplier for travelling along an existing road, below 1.0
+}
+
+impl Default for RoadSettings {
+    /// Three links per settlement up to 1500 km, with existing roads costing half as much.
+    fn default() -> Self {
+        RoadSettings {
+            travel: TravelCost::default(),
+            links: 3,
+            max_link_km: 1500.0,
+            reuse_discount: 0.5,
+        }
+    }
+}
+
+/// One stretch of road between two network nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadSegment {
+    pub from: usize,       // Index into `RoadNetwork::nodes`
+    pub to: usize,         // Index into `RoadNetwork::nodes`
+    pub cells: Vec<usize>, // Cells along the segment, from `from` to `to`
+    pub length_km: f64,
+    pub traffic: usize,    // Number of settlement-to-settlement routes using the segment
+}
+
+/// A road network as a graph: nodes at settlements and junctions, segments between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoadNetwork {
+    nodes: Vec<usize>, // Cell of each node
+    segments: Vec<RoadSegment>,
+}
+
+impl Planet {
+    /// Generates roads linking settlements into a shared network.
+    ///
+    /// Links are routed from the most important (most populous) settlement pairs down, each as
+    /// the cheapest overland path under `settings.travel`. Existing roads are discounted by
+    /// `reuse_discount`, so later routes join earlier ones and merge into shared trunks. The
+    /// resulting roads are split into segments at settlements and junctions.
+    ///
+    /// # Arguments
+    ///
+    /// * `settlements` - The settlements to link, e.g. from `Planet::place_settlements`.
+    /// * `settings` - The routing settings.
+    pub fn generate_roads(&self, settlements: &[Settlement], settings: &RoadSettings) -> RoadNetwork {
+        let mut links: BTreeSet<(usize, usize)> = BTreeSet::new();
+        for (i, settlement) in settlements.iter().enumerate() {
+            let mut others: Vec<(f64, usize)> = settlements
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(j, other)| (self.distance_km(settlement.cell, other.cell), j))
+                .filter(|&(distance, _)| distance <= settings.max_link_km)
+                .collect();
+            others.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for &(_, j) in others.iter().take(settings.links) {
+                links.insert((i.min(j), i.max(j)));
+            }
+        }
+        let mut links: Vec<(usize, usize)> = links.into_iter().collect();
+        links.sort_by(|a, b| {
+            let importance = |&(i, j): &(usize, usize)| settlements[i].population as f64 * settlements[j].population as f64;
+            importance(b).total_cmp(&importance(a))
+        });
+
+        // Usage count of every undirected cell-to-cell road edge
+        let mut usage: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for (i, j) in links {
+            let route = self.find_path(settlements[i].cell, settlements[j].cell, |a, b| {
+                let cost = self.travel_cost(a, b, &settings.travel)?;
+                // Charging new road instead of discounting old keeps costs admissible for A*
+                let reused = usage.contains_key(&(a.min(b), a.max(b)));
+                Some(if reused { cost } else { cost / settings.reuse_discount.clamp(f64::EPSILON, 1.0) })
+            });
+            if let Some((path, _)) = route {
+                for pair in path.windows(2) {
+                    *usage.entry((pair[0].min(pair[1]), pair[0].max(pair[1]))).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let anchors: BTreeSet<usize> = settlements.iter().map(|s| s.cell).collect();
+        RoadNetwork::from_edges(self, &usage, &anchors)
+    }
+}
+
+impl RoadNetwork {
+    // Splits the road edges into segments between anchors and junctions
+    fn from_edges(planet: &Planet, usage: &BTreeMap<(usize, usize), usize>, anchors: &BTreeSet<usize>) -> RoadNetwork {
+        let mut adjacency: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &(a, b) in usage.keys() {
+            adjacency.entry(a).or_default().push(b);
+            adjacency.entry(b).or_default().push(a);
+        }
+        let mut node_cells: Vec<usize> = adjacency
+            .iter()
+            .filter(|(cell, next)| anchors.contains(cell) || next.len() != 2)
+            .map(|(&cell, _)| cell)
+            .collect();
+        let mut node_of: HashMap<usize, usize> = node_cells.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+
+        let mut walked: BTreeSet<(usize, usize)> = BTreeSet::new();
+        let mut segments = Vec::new();
+        let mut start_index = 0;
+        loop {
+            // Closed loops without a junction get a node of their own
+            if start_index == node_cells.len() {
+                match usage.keys().find(|edge| !walked.contains(edge)) {
+                    Some(&(a, _)) => {
+                        node_of.insert(a, node_cells.len());
+                        node_cells.push(a);
+                    }
+                    None => break,
+                }
+            }
+            let start = node_cells[start_index];
+            start_index += 1;
+            for &first in &adjacency[&start] {
+                if walked.contains(&(start.min(first), start.max(first))) {
+                    continue;
+                }
+                let mut cells = vec![start];
+                let (mut previous, mut current) = (start, first);
+                let mut traffic = usize::MAX;
+                loop {
+                    let edge = (previous.min(current), previous.max(current));
+                    walked.insert(edge);
+                    traffic = traffic.min(usage[&edge]);
+                    cells.push(current);
+                    if node_of.contains_key(&current) {
+                        break;
+                    }
+                    let next = adjacency[&current].iter().copied().find(|&n| n != previous).unwrap_or(previous);
+                    previous = current;
+                    current = next;
+                }
+                let length_km = cells.windows(2).map(|pair| planet.distance_km(pair[0], pair[1])).sum();
+                segments.push(RoadSegment {
+                    from: node_of[&start],
+                    to: node_of[&current],
+                    cells,
+                    length_km,
+                    traffic,
+                });
+            }
+        }
+        RoadNetwork { nodes: node_cells, segments }
+    }
+
+    /// Returns the cell of every node (settlements and junctions).
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    /// Returns every road segment.
+    pub fn segments(&self) -> &[RoadSegment] {
+        &self.segments
+    }
+
+    /// Returns the total road length in kilometres.
+    pub fn total_length_km(&self) -> f64 {
+        self.segments.iter().map(|s| s.length_km).sum()
+    }
+
+    /// Returns, for each node, the indices of the segments touching it.
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for (index, segment) in self.segments.iter().enumerate() {
+            adjacency[segment.from].push(index);
+            if segment.to != segment.from {
+                adjacency[segment.to].push(index);
+            }
+        }
+        adjacency
+    }
+
+    /// Returns every segment as a polyline of `(latitude, longitude)` points in degrees.
+    pub fn polylines(&self, planet: &Planet) -> Vec<Vec<(f64, f64)>> {
+        self.segments
+            .iter()
+            .map(|segment| segment.cells.iter().map(|&cell| planet.lat_lon(cell)).collect())
+            .collect()
+    }
+}
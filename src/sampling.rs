@@ -0,0 +1,314 @@
+use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+use crate::noise::mix64;
+use crate::raster::lat_lon_to_unit;
+use crate::vector::{add, cross, dot, normalize, scale, sub};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::f64::consts::PI;
+
+// Largest ratio of peak to mean density honored; denser peaks are flattened to it
+const MAX_DENSITY_RATIO: f64 = 64.0;
+
+// Candidates tried around an active point before it is retired
+const POISSON_ATTEMPTS: usize = 30;
+
+// Points per steradian times the squared radius that `poisson_sphere` produces, measured
+const POISSON_PACKING: f64 = 0.88;
+
+// Hashes the packed grid cell keys of `poisson_sphere`, which are already well spread, far faster
+// than the default SipHash
+#[derive(Default)]
+struct CellHasher(u64);
+
+impl Hasher for CellHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0.rotate_left(8) ^ byte as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = (self.0 ^ value).wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(29);
+    }
+}
+
+/// How the generator places cell sites.
+///
+/// Deserializes from its short name or its variant name, e.g. `"poisson"` in TOML or `Poisson` in
+/// RON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteSampling {
+    /// A Fibonacci spiral, optionally jittered: the most even spacing, but the spiral shows
+    /// through as rows of similar cells.
+    #[serde(alias = "Fibonacci")]
+    Fibonacci,
+    /// Poisson-disk (blue noise) sampling: irregular cells with no pattern, no two sites closer
+    /// than a minimum distance. Ignores jitter.
+    #[serde(alias = "Poisson")]
+    Poisson,
+    /// The centres of a `HexGrid`, so the cells form a Goldberg polyhedron: near-regular hexagons
+    /// and exactly twelve pentagons, as strategy games want. Cell `i` is hex `i` of
+    /// `HexGrid::with_cell_count(samples)`. Ignores jitter.
+    #[serde(alias = "Goldberg")]
+    Goldberg,
+}
+
+impl SiteSampling {
+    /// Every sampling method.
+    pub const ALL: [SiteSampling; 3] = [SiteSampling::Fibonacci, SiteSampling::Poisson, SiteSampling::Goldberg];
+
+    /// Returns the short name used in recipes and on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SiteSampling::Fibonacci => "fibonacci",
+            SiteSampling::Poisson => "poisson",
+            SiteSampling::Goldberg => "goldberg",
+        }
+    }
+
+    /// Looks a method up by its short name, ignoring case.
+    pub fn from_name(name: &str) -> Option<SiteSampling> {
+        let name = name.to_lowercase();
+        SiteSampling::ALL.into_iter().find(|sampling| sampling.name() == name)
+    }
+}
+
+/// A patch of the sphere sampled more densely (or sparsely) than the rest, e.g. a gameplay area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailRegion {
+    pub latitude: f64,  // Centre in degrees
+    pub longitude: f64, // Centre in degrees
+    pub radius: f64,    // Angular radius in degrees
+    pub density: f64,   // Cells per area relative to outside the region, e.g. 4.0 for cells a quarter the size
+}
+
+impl DetailRegion {
+    /// Returns the relative density the region asks for in a unit direction: `density` within
+    /// `radius`, easing to 1.0 across a border of half the radius so cell sizes change gradually.
+    pub fn density_at(&self, direction: (f64, f64, f64)) -> f64 {
+        let centre = lat_lon_to_unit(self.latitude, self.longitude);
+        let distance = dot(normalize(direction), centre).clamp(-1.0, 1.0).acos().to_degrees();
+        let border = (self.radius * 0.5).max(f64::EPSILON);
+        let t = ((distance - self.radius) / border).clamp(0.0, 1.0);
+        let weight = 1.0 - t * t * (3.0 - 2.0 * t);
+        1.0 + (self.density.max(0.0) - 1.0) * weight
+    }
+}
+
+/// Returns the combined relative density of several detail regions in a unit direction; where
+/// regions overlap their densities multiply.
+pub fn regions_density(regions: &[DetailRegion], direction: (f64, f64, f64)) -> f64 {
+    regions.iter().map(|region| region.density_at(direction)).product()
+}
+
+/// Makes a point set N-fold rotationally symmetric about the z axis, for planets whose
+/// landmasses repeat around the rotation axis.
+///
+/// Only the points in the first wedge of `360 / folds` degrees of longitude are kept, and copies
+/// of them are turned into every other wedge, so cells come out identical in every wedge. Points on
+/// the axis are copied onto themselves; `Planet::new` merges the copies.
+///
+/// # Arguments
+///
+/// * `points` - Points on the unit sphere, e.g. from `seeded_fibonacci_sphere`; the result has
+///   about as many if they are spread evenly.
+/// * `folds` - How many times the wedge repeats; 0 or 1 returns the points unchanged.
+pub fn symmetric_sphere(points: Vec<(f64, f64, f64)>, folds: u32) -> Vec<(f64, f64, f64)> {
+    if folds <= 1 {
+        return points;
+    }
+    let wedge = 2.0 * PI / folds as f64;
+    let kept: Vec<(f64, f64, f64)> = points.into_iter().filter(|&(x, y, _)| y.atan2(x).rem_euclid(2.0 * PI) < wedge).collect();
+    (0..folds)
+        .flat_map(|k| {
+            let (sin, cos) = (k as f64 * wedge).sin_cos();
+            kept.iter().map(move |&(x, y, z)| (x * cos - y * sin, x * sin + y * cos, z))
+        })
+        .collect()
+}
+
+/// Generates points on the unit sphere whose spacing follows a density function, for finer cells
+/// where detail matters, e.g. near coastlines or around a gameplay area.
+///
+/// A Fibonacci lattice fine enough for the densest region is thinned by systematic sampling along
+/// the spiral, keeping each lattice point with a probability proportional to the density there.
+/// Unlike independent random rejection this keeps the points evenly spread where the density is
+/// constant; cells at the latitudes of a dense region come out less regular than elsewhere, though
+/// no more uneven than jittered ones. The same arguments always produce the same points.
+///
+/// # Arguments
+///
+/// * `num_samples` - The number of points to generate; the result has this many give or take one.
+/// * `jitter` - Randomness of the positions, 0.0 to 1.0, as a fraction of the local spacing.
+/// * `seed` - The jitter and thinning seed.
+/// * `density` - Relative density at a unit direction; only ratios matter. Negative values count
+///   as zero, and peaks above 64 times the mean are flattened to that.
+///
+/// # Returns
+///
+/// A vector of `(x, y, z)` tuples on the unit sphere, spread uniformly if the density is zero
+/// everywhere.
+pub fn density_sphere(num_samples: usize, jitter: f64, seed: u64, density: impl Fn((f64, f64, f64)) -> f64 + Sync) -> Vec<(f64, f64, f64)> {
+    if num_samples == 0 {
+        return Vec::new();
+    }
+    let clean = |point: (f64, f64, f64)| {
+        let value = density(point);
+        if value.is_finite() {
+            value.max(0.0)
+        } else {
+            0.0
+        }
+    };
+    // A coarse pass finds how much finer than uniform the lattice has to be
+    let pilot: Vec<f64> = seeded_fibonacci_sphere(num_samples.clamp(64, 4096), 0.0, 0).into_par_iter().map(clean).collect();
+    let pilot_mean = pilot.iter().sum::<f64>() / pilot.len() as f64;
+    let pilot_max = pilot.iter().fold(0.0, |max: f64, &value| max.max(value));
+    let uniform = pilot_mean <= 0.0;
+    let ratio = if uniform { 1.0 } else { (pilot_max / pilot_mean).clamp(1.0, MAX_DENSITY_RATIO) };
+    let lattice_size = ((num_samples as f64 * ratio * 1.05).ceil() as usize).max(num_samples);
+    let lattice = seeded_fibonacci_sphere(lattice_size, 0.0, 0);
+    let mut densities: Vec<f64> = lattice.par_iter().map(|&point| if uniform { 1.0 } else { clean(point) }).collect();
+    let mut total: f64 = densities.iter().sum();
+    if total <= 0.0 {
+        // The pilot saw density the lattice missed; fall back to uniform
+        densities.fill(1.0);
+        total = lattice_size as f64;
+    }
+
+    let random = |index: usize, axis: u64| (mix64(seed ^ mix64(index as u64).wrapping_add(axis)) >> 11) as f64 / (1u64 << 53) as f64;
+    let mut accumulated = random(usize::MAX, 0);
+    let mut points = Vec::with_capacity(num_samples + 1);
+    for (index, (&(x, y, z), &value)) in lattice.iter().zip(&densities).enumerate() {
+        let probability = (value * num_samples as f64 / total).min(1.0);
+        accumulated += probability;
+        if accumulated < 1.0 {
+            continue;
+        }
+        accumulated -= 1.0;
+        // Jitter by a fraction of the spacing the point's cell will have
+        let spacing = (4.0 * PI / num_samples as f64 * total / lattice_size as f64 / value).sqrt();
+        let amount = jitter * spacing * 0.5;
+        points.push(normalize((
+            x + amount * (random(index, 1) - 0.5),
+            y + amount * (random(index, 2) - 0.5),
+            z + amount * (random(index, 3) - 0.5),
+        )));
+    }
+    points
+}
+
+/// Returns the `poisson_sphere` radius that gives about `num_samples` points, within a percent or
+/// two.
+pub fn poisson_radius(num_samples: usize) -> f64 {
+    (4.0 * PI * POISSON_PACKING / num_samples.max(1) as f64).sqrt()
+}
+
+/// Generates points on the unit sphere by Poisson-disk sampling: no two points are closer than
+/// `radius`, and no gap is wide enough for another point. The resulting blue noise has none of the
+/// spiral rows a Fibonacci lattice leaves in the cell pattern.
+///
+/// Uses Bridson's algorithm on the sphere: starting from one random point, candidates around a
+/// randomly chosen active point are kept if nothing lies within `radius`, and a point with no room
+/// left around it is retired. A spatial hash makes each check constant time. The same arguments
+/// always produce the same points.
+///
+/// # Arguments
+///
+/// * `radius` - The minimum angular distance between points in radians; `poisson_radius` turns a
+///   point count into a radius.
+/// * `seed` - The random seed.
+///
+/// # Returns
+///
+/// A vector of `(x, y, z)` tuples on the unit sphere, about 11 / `radius`² of them.
+///
+/// # Panics
+///
+/// Panics if `radius` is not positive.
+pub fn poisson_sphere(radius: f64, seed: u64) -> Vec<(f64, f64, f64)> {
+    assert!(radius > 0.0, "poisson_sphere radius must be positive");
+    let radius = radius.min(PI);
+    let chord = 2.0 * (radius * 0.5).sin();
+    let chord_squared = chord * chord;
+    // Grid cells two chords wide, so every point that can crowd a candidate is in the 27 around
+    // its centre
+    let cell_size = 2.0 * chord;
+    let cell_of = |(x, y, z): (f64, f64, f64)| ((x / cell_size).floor() as i64, (y / cell_size).floor() as i64, (z / cell_size).floor() as i64);
+    let key = |x: i64, y: i64, z: i64| ((x & 0x1F_FFFF) << 42 | (y & 0x1F_FFFF) << 21 | (z & 0x1F_FFFF)) as u64;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let first = loop {
+        let candidate = (rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        let length_squared = dot(candidate, candidate);
+        if length_squared > 1e-6 && length_squared <= 1.0 {
+            break normalize(candidate);
+        }
+    };
+    let mut points = vec![first];
+    let mut grid: HashMap<u64, Vec<u32>, BuildHasherDefault<CellHasher>> = HashMap::default();
+    let (x, y, z) = cell_of(first);
+    grid.entry(key(x, y, z)).or_default().push(0);
+    let mut active = vec![0];
+
+    let distance = (radius * 1.000001).min(PI);
+    let (along, across) = (distance.cos(), distance.sin());
+    let mut nearby = Vec::new();
+    while !active.is_empty() {
+        let slot = rng.gen_range(0..active.len());
+        let centre = points[active[slot]];
+        // Every candidate lies within a chord of the centre, so only points within two chords of
+        // it can crowd one
+        nearby.clear();
+        let (cx, cy, cz) = cell_of(centre);
+        for x in cx - 1..=cx + 1 {
+            for y in cy - 1..=cy + 1 {
+                for z in cz - 1..=cz + 1 {
+                    if let Some(cell) = grid.get(&key(x, y, z)) {
+                        nearby.extend(cell.iter().map(|&other| points[other as usize]).filter(|&other| {
+                            let offset = sub(other, centre);
+                            dot(offset, offset) < 4.0 * chord_squared
+                        }));
+                    }
+                }
+            }
+        }
+        // Candidates just beyond the radius at evenly spaced bearings from a random start pack
+        // tighter than Bridson's random annulus and need fewer attempts
+        let helper = if centre.2.abs() < 0.9 { (0.0, 0.0, 1.0) } else { (1.0, 0.0, 0.0) };
+        let east = normalize(cross(helper, centre));
+        let north = cross(centre, east);
+        let start = rng.gen_range(0.0..2.0 * PI);
+        let placed = (0..POISSON_ATTEMPTS).find_map(|attempt| {
+            let bearing = start + 2.0 * PI * attempt as f64 / POISSON_ATTEMPTS as f64;
+            let heading = add(scale(east, bearing.cos()), scale(north, bearing.sin()));
+            let candidate = normalize(add(scale(centre, along), scale(heading, across)));
+            let crowded = nearby.iter().any(|&other| {
+                let offset = sub(other, candidate);
+                dot(offset, offset) < chord_squared
+            });
+            (!crowded).then_some(candidate)
+        });
+        match placed {
+            Some(candidate) => {
+                let (x, y, z) = cell_of(candidate);
+                grid.entry(key(x, y, z)).or_default().push(points.len() as u32);
+                active.push(points.len());
+                points.push(candidate);
+            }
+            None => {
+                active.swap_remove(slot);
+            }
+        }
+    }
+    points
+}
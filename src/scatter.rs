@@ -0,0 +1,298 @@
+use crate::cell_graph::CellGraph;
+use crate::noise::{gradient_noise, hash_values, mix64};
+use crate::planet::Planet;
+use crate::render::materials::Material;
+use crate::vector::{add, cross, dot, normalize, scale, Vec3};
+
+/// A one-metre square of ground inside a cell, addressed in the cell's local metre grid.
+///
+/// Squares are identified purely by their coordinates, so any client can address the same square
+/// without shared state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScatterChunk {
+    pub cell: usize,
+    pub x: i64, // Metres east of the cell site
+    pub y: i64, // Metres north of the cell site
+}
+
+impl From<usize> for ScatterChunk {
+    /// The square at the cell site.
+    fn from(cell: usize) -> Self {
+        ScatterChunk { cell, x: 0, y: 0 }
+    }
+}
+
+/// One small prop (a rock, pebble, twig or other debris) placed by `micro_scatter`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScatterProp {
+    pub x: f64,        // Metres east of the cell site
+    pub y: f64,        // Metres north of the cell site
+    pub rotation: f64, // Yaw in degrees, 0.0 to 360.0
+    pub scale: f64,    // Size multiplier, 0.5 to 1.5
+    pub variant: u32,  // Which prop model to use; reduce modulo the number of models
+}
+
+/// A lazy, deterministic stream of props for one ground square.
+#[derive(Debug, Clone)]
+pub struct MicroScatter {
+    chunk: ScatterChunk,
+    key: u64,
+    count: usize,
+    index: usize,
+}
+
+// Maps a hash to a float in [0, 1)
+fn unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Yields the props in a one-metre ground square.
+///
+/// Nothing is stored: every prop is derived from a hash of the seed, the cell and the square's
+/// coordinates, so the same call returns the same props on every machine. Stream the squares a
+/// client can see and discard them when they go out of view.
+///
+/// # Arguments
+///
+/// * `chunk` - The ground square (or a cell, meaning the square at its site).
+/// * `density` - The mean number of props per square metre. Fractional densities place a prop
+///   in a matching fraction of squares.
+/// * `seed` - The scatter seed; use a different seed per prop category.
+///
+/// # Returns
+///
+/// An iterator over the square's props.
+pub fn micro_scatter(chunk: impl Into<ScatterChunk>, density: f64, seed: u64) -> MicroScatter {
+    let chunk = chunk.into();
+    let key = hash_values(seed, &[chunk.cell as i64, chunk.x, chunk.y]);
+    let density = density.max(0.0);
+    let extra = (unit(mix64(key)) < density.fract()) as usize;
+    MicroScatter {
+        chunk,
+        key,
+        count: density.floor() as usize + extra,
+        index: 0,
+    }
+}
+
+impl Iterator for MicroScatter {
+    type Item = ScatterProp;
+
+    fn next(&mut self) -> Option<ScatterProp> {
+        if self.index >= self.count {
+            return None;
+        }
+        let h = hash_values(self.key, &[self.index as i64]);
+        self.index += 1;
+        Some(ScatterProp {
+            x: self.chunk.x as f64 + unit(mix64(h ^ 1)),
+            y: self.chunk.y as f64 + unit(mix64(h ^ 2)),
+            rotation: unit(mix64(h ^ 3)) * 360.0,
+            scale: 0.5 + unit(mix64(h ^ 4)),
+            variant: (mix64(h ^ 5) >> 32) as u32,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for MicroScatter {}
+
+/// Yields the props of every square in a rectangle of a cell's local metre grid.
+///
+/// # Arguments
+///
+/// * `cell` - The cell.
+/// * `min`, `max` - The inclusive square coordinates `(x, y)` of opposite corners, in metres
+///   from the cell site.
+/// * `density` - The mean number of props per square metre.
+/// * `seed` - The scatter seed.
+pub fn micro_scatter_area(cell: usize, min: (i64, i64), max: (i64, i64), density: f64, seed: u64) -> impl Iterator<Item = ScatterProp> {
+    (min.1..=max.1).flat_map(move |y| (min.0..=max.0).flat_map(move |x| micro_scatter(ScatterChunk { cell, x, y }, density, seed)))
+}
+
+/// What a cell must be like for props to be scattered on it, e.g. trees only on gentle forest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScatterFilter {
+    pub max_slope: f64,       // Steepest cell slope in degrees
+    pub biomes: Vec<Material>, // Biomes to scatter on; empty for any biome
+}
+
+impl Default for ScatterFilter {
+    /// Any slope and any biome.
+    fn default() -> Self {
+        ScatterFilter {
+            max_slope: 90.0,
+            biomes: Vec::new(),
+        }
+    }
+}
+
+impl ScatterFilter {
+    /// Returns true if props may be scattered on the cell.
+    pub fn allows(&self, cell: &Cell) -> bool {
+        cell.slope <= self.max_slope && (self.biomes.is_empty() || self.biomes.contains(&cell.biome))
+    }
+}
+
+/// One object (a tree, rock or building) placed by `Cell::scatter_points`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScatterPoint {
+    pub position: Vec3, // On the unit sphere
+    pub x: f64,         // Metres east of the cell site in the cell's tangent plane
+    pub y: f64,         // Metres north of the cell site in the cell's tangent plane
+    pub rotation: f64,  // Yaw in degrees, 0.0 to 360.0
+    pub scale: f64,     // Size multiplier, 0.5 to 1.5
+    pub variant: u32,   // Which model to use; reduce modulo the number of models
+}
+
+/// One cell of a planet with the shape and surface that decide what is placed on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub index: usize,
+    pub site: Vec3,         // On the unit sphere
+    pub polygon: Vec<Vec3>, // Voronoi corners on the unit sphere, counter-clockwise seen from outside
+    pub radius: f64,        // Planet radius in kilometres
+    pub slope: f64,         // Degrees
+    pub biome: Material,
+}
+
+// Share of blue-noise candidates that survive thinning, measured over large cells
+const BLUE_NOISE_KEPT: f64 = 0.56;
+// Closest two blue-noise points come, in grid spacings
+const BLUE_NOISE_RADIUS: f64 = 0.8;
+
+impl Cell {
+    /// Collects a cell's shape and surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet.
+    /// * `graph` - The planet's cell graph, for the cell polygon.
+    /// * `biomes` - The biome of every cell, e.g. from `Planet::materials`.
+    /// * `cell` - The cell.
+    pub fn new(planet: &Planet, graph: &CellGraph, biomes: &[Material], cell: usize) -> Cell {
+        Cell {
+            index: cell,
+            site: planet.sites()[cell],
+            polygon: graph.polygon(cell),
+            radius: planet.radius(),
+            slope: planet.slope(cell),
+            biome: biomes[cell],
+        }
+    }
+
+    /// Yields blue-noise positions inside the cell, for trees, rocks or buildings.
+    ///
+    /// Points are spread evenly without clumps or gaps: candidates are jittered on a grid in the
+    /// cell's tangent plane, and of any two closer than most of a grid spacing only the one with
+    /// the higher hashed priority survives. Every candidate and priority is derived from a hash of
+    /// the seed, the cell and its grid square, so nothing is stored and the same call yields the
+    /// same points on every machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `density` - Mean points per square kilometre.
+    /// * `seed` - The scatter seed; use a different seed per prop category.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the points, row by row from the south-west of the cell.
+    pub fn scatter_points(&self, density: f64, seed: u64) -> impl Iterator<Item = ScatterPoint> + '_ {
+        self.scatter(density, seed, None)
+    }
+
+    /// Yields green-noise positions inside the cell: the blue-noise points of `scatter_points`
+    /// thinned in patches, so they gather in clumps like groves or boulder fields with open ground
+    /// between. The mean density is unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `density` - Mean points per square kilometre.
+    /// * `clump_km` - Typical size of a clump in kilometres.
+    /// * `seed` - The scatter seed.
+    pub fn scatter_clumped(&self, density: f64, clump_km: f64, seed: u64) -> impl Iterator<Item = ScatterPoint> + '_ {
+        self.scatter(density * 2.0, seed, Some(self.radius / clump_km.max(f64::MIN_POSITIVE)))
+    }
+
+    /// Yields the points of `scatter_points`, or none if the filter rejects the cell's slope or
+    /// biome.
+    pub fn scatter_points_where(&self, density: f64, seed: u64, filter: &ScatterFilter) -> impl Iterator<Item = ScatterPoint> + '_ {
+        let density = if filter.allows(self) { density } else { 0.0 };
+        self.scatter(density, seed, None)
+    }
+
+    // The east and north unit vectors of the cell's tangent plane
+    fn frame(&self) -> (Vec3, Vec3) {
+        let axis = if self.site.0.abs() < 1e-9 && self.site.1.abs() < 1e-9 { (1.0, 0.0, 0.0) } else { (0.0, 0.0, 1.0) };
+        let east = normalize(cross(axis, self.site));
+        (east, cross(self.site, east))
+    }
+
+    // Blue-noise points at `density`, thinned by patch noise of this frequency on the unit sphere
+    // when clumping
+    fn scatter(&self, density: f64, seed: u64, clumping: Option<f64>) -> impl Iterator<Item = ScatterPoint> + '_ {
+        let metres = self.radius * 1000.0;
+        let (east, north) = self.frame();
+        // Gnomonic projection onto the tangent plane, where the cell's edges stay straight
+        let project = move |p: Vec3| {
+            let d = dot(p, self.site);
+            (dot(p, east) / d * metres, dot(p, north) / d * metres)
+        };
+        let corners: Vec<(f64, f64)> = self.polygon.iter().map(|&p| project(p)).collect();
+        let (mut min, mut max) = ((f64::INFINITY, f64::INFINITY), (f64::NEG_INFINITY, f64::NEG_INFINITY));
+        for &(x, y) in &corners {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        let spacing = if density > 0.0 && corners.len() >= 3 { (BLUE_NOISE_KEPT * 1e6 / density).sqrt() } else { f64::INFINITY };
+        // Grid squares covering the cell, none if there is nothing to scatter
+        let (low, high) = if spacing.is_finite() {
+            (((min.0 / spacing).floor() as i64, (min.1 / spacing).floor() as i64), ((max.0 / spacing).floor() as i64, (max.1 / spacing).floor() as i64))
+        } else {
+            ((0, 0), (-1, -1))
+        };
+        let (rows, columns) = (low.1..=high.1, low.0..=high.0);
+        let key = hash_values(seed, &[self.index as i64]);
+        // The candidate of a grid square and its priority
+        let candidate = move |i: i64, j: i64| {
+            let h = hash_values(key, &[i, j]);
+            ((i as f64 + unit(mix64(h ^ 1))) * spacing, (j as f64 + unit(mix64(h ^ 2))) * spacing, h)
+        };
+        let inside = move |x: f64, y: f64| {
+            (0..corners.len()).all(|k| {
+                let (a, b) = (corners[k], corners[(k + 1) % corners.len()]);
+                (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0) >= 0.0
+            })
+        };
+        let reach = BLUE_NOISE_RADIUS * spacing;
+        rows.flat_map(move |j| columns.clone().map(move |i| (i, j)))
+            .filter_map(move |(i, j)| {
+                let (x, y, h) = candidate(i, j);
+                let crowded = (-1..=1).flat_map(|dj| (-1..=1).map(move |di| (di, dj))).any(|(di, dj)| {
+                    let (ox, oy, oh) = candidate(i + di, j + dj);
+                    (di, dj) != (0, 0) && oh > h && (ox - x).hypot(oy - y) < reach
+                });
+                if crowded || !inside(x, y) {
+                    return None;
+                }
+                let position = normalize(add(self.site, add(scale(east, x / metres), scale(north, y / metres))));
+                if let Some(frequency) = clumping {
+                    if gradient_noise(key, scale(position, frequency)) < 0.0 {
+                        return None;
+                    }
+                }
+                Some(ScatterPoint {
+                    position,
+                    x,
+                    y,
+                    rotation: unit(mix64(h ^ 3)) * 360.0,
+                    scale: 0.5 + unit(mix64(h ^ 4)),
+                    variant: (mix64(h ^ 5) >> 32) as u32,
+                })
+            })
+    }
+}
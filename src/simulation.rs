@@ -0,0 +1,518 @@
+use crate::geologic_time::GeologicHistory;
+use crate::geology;
+use crate::planet::Planet;
+use crate::terrain::{self, TerrainSettings};
+use crate::observer::{Observers, SubscriptionId};
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// A piece of long-running work that can be advanced in small, bounded steps.
+///
+/// Each `step` should take well under a millisecond so a frame budget can be honoured closely.
+pub trait SimulationTask: Send {
+    /// A short name used for progress reporting.
+    fn name(&self) -> &str;
+
+    /// Performs one unit of work.
+    ///
+    /// # Returns
+    ///
+    /// `true` once the task has finished.
+    fn step(&mut self, planet: &mut Planet) -> bool;
+
+    /// Returns an estimate of the units of work left.
+    fn remaining(&self) -> usize;
+}
+
+// Cells processed per unit by the chunked tasks
+const CHUNK: usize = 2048;
+
+enum TerrainPhase {
+    Noise,
+    Shape((f64, f64, f64)),
+}
+
+/// Streams terrain generation over many steps, producing the same result as
+/// `Planet::generate_terrain`.
+pub struct TerrainStream {
+    seed: u64,
+    settings: TerrainSettings,
+    cells: usize, // Cell count of the planet, known after the first step
+    raw: Vec<f64>,
+    elevation: Vec<f64>,
+    phase: TerrainPhase,
+}
+
+impl TerrainStream {
+    /// Creates the task.
+    pub fn new(seed: u64, settings: TerrainSettings) -> TerrainStream {
+        TerrainStream {
+            seed,
+            settings,
+            cells: 0,
+            raw: Vec::new(),
+            elevation: Vec::new(),
+            phase: TerrainPhase::Noise,
+        }
+    }
+}
+
+impl SimulationTask for TerrainStream {
+    fn name(&self) -> &str {
+        "terrain"
+    }
+
+    fn step(&mut self, planet: &mut Planet) -> bool {
+        let sites = planet.sites();
+        self.cells = sites.len();
+        match self.phase {
+            TerrainPhase::Noise => {
+                let end = (self.raw.len() + CHUNK).min(sites.len());
+                let start = self.raw.len();
+                self.raw.extend(sites[start..end].iter().map(|&p| terrain::continent_noise(p, self.seed, &self.settings)));
+                if self.raw.len() == sites.len() {
+                    self.phase = TerrainPhase::Shape(terrain::sea_levels(&self.raw, &self.settings));
+                }
+                false
+            }
+            TerrainPhase::Shape(levels) => {
+                let start = self.elevation.len();
+                let end = (start + CHUNK).min(sites.len());
+                self.elevation.extend(
+                    (start..end).map(|cell| terrain::shape_elevation(sites[cell], self.raw[cell], self.seed, levels, &self.settings)),
+                );
+                if self.elevation.len() < sites.len() {
+                    return false;
+                }
+                let rock_types = geology::classify_rock_types(sites, &self.elevation, self.seed);
+                planet.set_elevation(std::mem::take(&mut self.elevation));
+                planet.set_rock_types(rock_types);
+                true
+            }
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        // Two passes over the cells; the size is unknown until the first step sees the planet
+        if self.cells == 0 {
+            return 2;
+        }
+        (2 * self.cells - self.raw.len() - self.elevation.len()).div_ceil(CHUNK)
+    }
+}
+
+/// Advances the planet's climate one day per step.
+pub struct AdvanceClimate {
+    days: f64,
+}
+
+impl AdvanceClimate {
+    /// Creates the task. Negative spans do nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `days` is infinite or `NaN`, which would never finish.
+    pub fn new(days: f64) -> AdvanceClimate {
+        assert!(days.is_finite(), "climate span must be finite, got {}", days);
+        AdvanceClimate { days: days.max(0.0) }
+    }
+}
+
+impl SimulationTask for AdvanceClimate {
+    fn name(&self) -> &str {
+        "climate"
+    }
+
+    fn step(&mut self, planet: &mut Planet) -> bool {
+        let dt = self.days.min(1.0);
+        planet.advance_days(dt);
+        self.days -= dt;
+        self.days <= 0.0
+    }
+
+    fn remaining(&self) -> usize {
+        self.days.ceil() as usize
+    }
+}
+
+/// Continues a planet's geological history, erosion included, one time step per step.
+///
+/// Produces the same result as one `Planet::advance_geology` call over the whole span.
+pub struct AdvanceGeology {
+    history: GeologicHistory,
+    megayears: f64,
+}
+
+impl AdvanceGeology {
+    /// Creates the task. Negative spans do nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - The plate layout and elapsed time, e.g. from
+    ///   `PlanetParameters::geologic_history`. The task owns it and moves it forward with the
+    ///   planet.
+    /// * `megayears` - Million years to advance, one `GeologySettings::time_step` per step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `megayears` is infinite or `NaN`, which would never finish.
+    pub fn new(history: GeologicHistory, megayears: f64) -> AdvanceGeology {
+        assert!(megayears.is_finite(), "geologic span must be finite, got {}", megayears);
+        AdvanceGeology {
+            history,
+            megayears: megayears.max(0.0),
+        }
+    }
+
+    /// Returns the history as advanced so far.
+    pub fn history(&self) -> &GeologicHistory {
+        &self.history
+    }
+
+    // Million years advanced per step
+    fn time_step(&self) -> f64 {
+        self.history.settings().time_step.max(f64::MIN_POSITIVE)
+    }
+}
+
+impl SimulationTask for AdvanceGeology {
+    fn name(&self) -> &str {
+        "erosion"
+    }
+
+    fn step(&mut self, planet: &mut Planet) -> bool {
+        let dt = self.megayears.min(self.time_step());
+        planet.advance_geology(&mut self.history, dt);
+        self.megayears -= dt;
+        self.megayears <= 0.0
+    }
+
+    fn remaining(&self) -> usize {
+        // Casts saturate, so tiny time steps report `usize::MAX`
+        (self.megayears / self.time_step()).ceil() as usize
+    }
+}
+
+/// Diffuses surface temperature between neighbouring cells, spreading weather laterally.
+///
+/// Each pass blends every cell toward the mean of its neighbours, processed in chunks.
+pub struct TemperatureDiffusion {
+    passes: usize,
+    rate: f64,
+    source: Vec<f64>,
+    next: Vec<f64>,
+}
+
+impl TemperatureDiffusion {
+    /// Creates the task.
+    ///
+    /// # Arguments
+    ///
+    /// * `passes` - The number of diffusion passes.
+    /// * `rate` - How far each pass moves a cell toward its neighbours' mean, 0.0 to 1.0.
+    pub fn new(passes: usize, rate: f64) -> TemperatureDiffusion {
+        TemperatureDiffusion {
+            passes,
+            rate: rate.clamp(0.0, 1.0),
+            source: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+}
+
+impl SimulationTask for TemperatureDiffusion {
+    fn name(&self) -> &str {
+        "diffusion"
+    }
+
+    fn step(&mut self, planet: &mut Planet) -> bool {
+        if self.passes == 0 {
+            return true;
+        }
+        if self.next.is_empty() {
+            self.source = planet.temperature().to_vec();
+        }
+        let start = self.next.len();
+        let end = (start + CHUNK).min(self.source.len());
+        for cell in start..end {
+            let neighbors = planet.neighbors(cell);
+            let mean = neighbors.iter().map(|&n| self.source[n]).sum::<f64>() / neighbors.len().max(1) as f64;
+            self.next.push(self.source[cell] + (mean - self.source[cell]) * self.rate);
+        }
+        if self.next.len() == self.source.len() {
+            planet.set_temperature(std::mem::take(&mut self.next));
+            self.passes -= 1;
+        }
+        self.passes == 0
+    }
+
+    fn remaining(&self) -> usize {
+        let per_pass = self.source.len().div_ceil(CHUNK).max(1);
+        self.passes.saturating_mul(per_pass) - self.next.len() / CHUNK
+    }
+}
+
+/// What a budgeted tick achieved and what is left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickStatus {
+    pub units_done: usize,
+    pub tasks_completed: usize,
+    pub pending_tasks: usize,
+    pub remaining_units: usize, // Estimate across all pending tasks, saturating at `usize::MAX`
+    pub elapsed: Duration,
+}
+
+impl TickStatus {
+    /// Returns `true` if no work is pending.
+    pub fn is_idle(&self) -> bool {
+        self.pending_tasks == 0
+    }
+}
+
+/// A change in a planet simulation, delivered to `on_event` subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationEvent {
+    /// A task finished; carries the task's name.
+    TaskCompleted(String),
+    /// Cells whose temperature dropped below freezing.
+    CellsFroze(Vec<usize>),
+    /// Cells whose temperature rose to freezing or above.
+    CellsThawed(Vec<usize>),
+}
+
+/// A callback run after every budgeted tick with the planet and the tick's status.
+pub type SimulationTickCallback = dyn FnMut(&Planet, &TickStatus) + Send;
+/// A callback run for every simulation event.
+pub type SimulationEventCallback = dyn FnMut(&SimulationEvent) + Send;
+
+/// A planet together with a queue of incremental work.
+///
+/// Tasks run in the order they were scheduled. Call `tick_budgeted` once per frame from the game
+/// loop to make progress without exceeding a frame's time slice, and subscribe with `on_tick` or
+/// `on_event` to react to changes.
+pub struct Simulation {
+    planet: Planet,
+    tasks: VecDeque<Box<dyn SimulationTask>>,
+    tick_observers: Observers<SimulationTickCallback>,
+    event_observers: Observers<SimulationEventCallback>,
+}
+
+impl Simulation {
+    /// Creates a simulation with no pending work.
+    pub fn new(planet: Planet) -> Simulation {
+        Simulation {
+            planet,
+            tasks: VecDeque::new(),
+            tick_observers: Observers::default(),
+            event_observers: Observers::default(),
+        }
+    }
+
+    /// Returns the planet.
+    pub fn planet(&self) -> &Planet {
+        &self.planet
+    }
+
+    /// Returns the planet for direct modification.
+    pub fn planet_mut(&mut self) -> &mut Planet {
+        &mut self.planet
+    }
+
+    /// Consumes the simulation, returning the planet.
+    pub fn into_planet(self) -> Planet {
+        self.planet
+    }
+
+    /// Queues a task behind any pending work.
+    pub fn schedule(&mut self, task: impl SimulationTask + 'static) {
+        self.tasks.push_back(Box::new(task));
+    }
+
+    /// Subscribes a callback run after every `tick_budgeted`, with the planet and the tick's
+    /// status.
+    pub fn on_tick(&mut self, callback: impl FnMut(&Planet, &TickStatus) + Send + 'static) -> SubscriptionId {
+        self.tick_observers.add(Box::new(callback))
+    }
+
+    /// Subscribes a callback run for every event.
+    ///
+    /// Events are raised by `tick_budgeted` and `run_to_completion`. Freezing and thawing are
+    /// found by comparing temperatures before and after, which costs a pass over the cells while
+    /// any event subscription exists.
+    pub fn on_event(&mut self, callback: impl FnMut(&SimulationEvent) + Send + 'static) -> SubscriptionId {
+        self.event_observers.add(Box::new(callback))
+    }
+
+    /// Subscribes a channel receiving a copy of every event, for consumers on other threads.
+    pub fn subscribe_events(&mut self) -> (SubscriptionId, Receiver<SimulationEvent>) {
+        self.event_observers.add_channel()
+    }
+
+    /// Cancels a tick or event subscription.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the subscription existed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.tick_observers.remove(id) || self.event_observers.remove(id)
+    }
+
+    // Which cells are below freezing, when anyone is listening for freeze events
+    fn frozen_cells(&self) -> Option<Vec<bool>> {
+        if self.event_observers.is_empty() {
+            return None;
+        }
+        Some(self.planet.temperature().iter().map(|&temperature| temperature < 0.0).collect())
+    }
+
+    // Raises the events for finished tasks and for cells that froze or thawed
+    fn notify_changes(&mut self, completed: Vec<String>, frozen_before: Option<Vec<bool>>) {
+        for name in completed {
+            self.event_observers.notify(&SimulationEvent::TaskCompleted(name));
+        }
+        let (Some(before), Some(after)) = (frozen_before, self.frozen_cells()) else {
+            return;
+        };
+        // Cells may have been regenerated with a different count; nothing to compare then
+        if before.len() != after.len() {
+            return;
+        }
+        let froze: Vec<usize> = (0..after.len()).filter(|&cell| after[cell] && !before[cell]).collect();
+        let thawed: Vec<usize> = (0..after.len()).filter(|&cell| before[cell] && !after[cell]).collect();
+        if !froze.is_empty() {
+            self.event_observers.notify(&SimulationEvent::CellsFroze(froze));
+        }
+        if !thawed.is_empty() {
+            self.event_observers.notify(&SimulationEvent::CellsThawed(thawed));
+        }
+    }
+
+    /// Returns the names of the pending tasks, in order.
+    pub fn pending(&self) -> Vec<&str> {
+        self.tasks.iter().map(|task| task.name()).collect()
+    }
+
+    /// Performs as much pending work as fits in `budget`.
+    ///
+    /// Work is done one small unit at a time and the clock is checked between units, so the
+    /// overrun is at most one unit. At least one unit is done per call, so progress is made even
+    /// with a zero budget.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tick_budgeted(&mut self, budget: Duration) -> TickStatus {
+        let start = Instant::now();
+        let frozen_before = self.frozen_cells();
+        let mut units_done = 0;
+        let mut completed = Vec::new();
+        while let Some(task) = self.tasks.front_mut() {
+            if units_done > 0 && start.elapsed() >= budget {
+                break;
+            }
+            units_done += 1;
+            if task.step(&mut self.planet) {
+                completed.push(task.name().to_string());
+                self.tasks.pop_front();
+            }
+        }
+        let status = TickStatus {
+            units_done,
+            tasks_completed: completed.len(),
+            pending_tasks: self.tasks.len(),
+            remaining_units: self.tasks.iter().fold(0, |sum, task| sum.saturating_add(task.remaining())),
+            elapsed: start.elapsed(),
+        };
+        self.notify_changes(completed, frozen_before);
+        for callback in self.tick_observers.iter_mut() {
+            callback(&self.planet, &status);
+        }
+        status
+    }
+
+    /// Runs every pending task to completion.
+    pub fn run_to_completion(&mut self) {
+        let frozen_before = self.frozen_cells();
+        let mut completed = Vec::new();
+        while let Some(mut task) = self.tasks.pop_front() {
+            while !task.step(&mut self.planet) {}
+            completed.push(task.name().to_string());
+        }
+        self.notify_changes(completed, frozen_before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geologic_time::GeologySettings;
+    use crate::procedural::PlanetParameters;
+    use uuid::Uuid;
+
+    // A task that never finishes and cannot tell how much work is left
+    struct Endless;
+
+    impl SimulationTask for Endless {
+        fn name(&self) -> &str {
+            "endless"
+        }
+
+        fn step(&mut self, _planet: &mut Planet) -> bool {
+            false
+        }
+
+        fn remaining(&self) -> usize {
+            usize::MAX
+        }
+    }
+
+    fn small_planet() -> (PlanetParameters, Planet) {
+        let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x6E0));
+        parameters.samples = 400;
+        let planet = parameters.build();
+        (parameters, planet)
+    }
+
+    #[test]
+    fn erosion_task_matches_one_geology_call() {
+        let (parameters, planet) = small_planet();
+        let settings = GeologySettings { time_step: 0.5, ..GeologySettings::default() };
+
+        let mut direct = planet.clone();
+        let mut history = parameters.geologic_history(&planet, settings.clone());
+        direct.advance_geology(&mut history, 2.25);
+
+        let mut simulation = Simulation::new(planet.clone());
+        simulation.schedule(AdvanceGeology::new(parameters.geologic_history(&planet, settings), 2.25));
+        let status = simulation.tick_budgeted(Duration::ZERO);
+        assert_eq!((status.units_done, status.remaining_units), (1, 4));
+        simulation.run_to_completion();
+        assert_eq!(simulation.planet().elevation(), direct.elevation());
+        assert_eq!(simulation.planet().rock_types(), direct.rock_types());
+    }
+
+    #[test]
+    fn remaining_work_saturates() {
+        let (_, planet) = small_planet();
+        let mut simulation = Simulation::new(planet);
+        simulation.schedule(Endless);
+        simulation.schedule(Endless);
+        simulation.schedule(TemperatureDiffusion::new(usize::MAX, 0.5));
+        let status = simulation.tick_budgeted(Duration::ZERO);
+        assert_eq!(status.remaining_units, usize::MAX);
+        assert_eq!(status.pending_tasks, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "climate span must be finite")]
+    fn endless_climate_spans_are_rejected() {
+        AdvanceClimate::new(f64::INFINITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "geologic span must be finite")]
+    fn endless_geologic_spans_are_rejected() {
+        let (parameters, planet) = small_planet();
+        AdvanceGeology::new(parameters.geologic_history(&planet, GeologySettings::default()), f64::NAN);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::guid::{derive_guid, guid_seed, BODY_DISCRIMINANT, POPULATION_DISCRIMINANT};
+use crate::noise::mix64;
+use crate::star_system::{OrbitalElements, PlanetKind, Star, SystemPlanet};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::TAU;
+use std::ops::Range;
+use uuid::Uuid;
+
+/// The kind of a small-body population.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PopulationKind {
+    /// Rocky bodies on low-inclination orbits in the gap between the rocky planets and the giants.
+    AsteroidBelt,
+    /// Icy bodies on low-inclination orbits beyond the outermost planet.
+    KuiperBelt,
+    /// A spherical shell of comets on distant, isotropic orbits.
+    OortCloud,
+}
+
+/// A population of asteroids or comets occupying a shell around a star.
+///
+/// Bodies are never stored: each is generated from the population GUID and its orbital ID, so a
+/// population of billions of comets costs as much as one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallBodyPopulation {
+    pub guid: Uuid,
+    pub kind: PopulationKind,
+    pub inner_radius: f64,    // Astronomical units
+    pub outer_radius: f64,    // Astronomical units
+    pub count: u64,           // Bodies at least `min_body_radius` across
+    pub min_body_radius: f64, // Kilometres
+    pub max_eccentricity: f64,
+    pub max_inclination: f64, // Radians
+    pub star_mass: f64,       // Solar masses, for orbital periods
+}
+
+/// One asteroid or comet, generated on demand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallBody {
+    pub guid: Uuid,
+    pub id: u64,     // Orbital ID within the population
+    pub radius: f64, // Kilometres
+    pub icy: bool,   // Whether the body outgasses near the star like a comet
+    pub orbit: OrbitalElements,
+}
+
+// Exponent of the cumulative size distribution N(>r) ∝ r^-q of collisionally evolved populations
+const SIZE_EXPONENT: f64 = 2.5;
+
+impl SmallBodyPopulation {
+    /// Returns the GUID of the body with an orbital ID, without generating it.
+    pub fn body_guid(&self, id: u64) -> Uuid {
+        derive_guid(self.guid, BODY_DISCRIMINANT, id)
+    }
+
+    /// Generates the body with an orbital ID.
+    ///
+    /// # Returns
+    ///
+    /// The body, or `None` if `id` is not below `count`.
+    pub fn body(&self, id: u64) -> Option<SmallBody> {
+        if id >= self.count {
+            return None;
+        }
+        let guid = self.body_guid(id);
+        let mut rng = StdRng::seed_from_u64(guid_seed(guid));
+
+        let semi_major_axis = match self.kind {
+            // Uniform surface density across the belt
+            PopulationKind::AsteroidBelt | PopulationKind::KuiperBelt => {
+                let (inner, outer) = (self.inner_radius.powi(2), self.outer_radius.powi(2));
+                (inner + (outer - inner) * rng.gen::<f64>()).sqrt()
+            }
+            PopulationKind::OortCloud => self.inner_radius * (self.outer_radius / self.inner_radius).powf(rng.gen::<f64>()),
+        };
+        let inclination = match self.kind {
+            // Isotropic: uniform in cos(i)
+            PopulationKind::OortCloud => (1.0 - 2.0 * rng.gen::<f64>()).acos(),
+            _ => rng.gen::<f64>().powi(2) * self.max_inclination,
+        };
+        let orbit = OrbitalElements {
+            semi_major_axis,
+            eccentricity: rng.gen::<f64>().sqrt() * self.max_eccentricity,
+            inclination,
+            ascending_node: rng.gen_range(0.0..TAU),
+            argument_of_periapsis: rng.gen_range(0.0..TAU),
+            mean_anomaly_at_epoch: rng.gen_range(0.0..TAU),
+            period: 365.25 * (semi_major_axis.powi(3) / self.star_mass).sqrt(),
+        };
+        let radius = (self.min_body_radius * (1.0 - rng.gen::<f64>()).powf(-1.0 / SIZE_EXPONENT)).min(500.0);
+        let icy = match self.kind {
+            PopulationKind::AsteroidBelt => rng.gen_bool(0.1),
+            _ => true,
+        };
+        Some(SmallBody {
+            guid,
+            id,
+            radius,
+            icy,
+            orbit,
+        })
+    }
+
+    /// Generates the bodies in a range of orbital IDs, clamped to the population.
+    pub fn bodies(&self, ids: Range<u64>) -> impl Iterator<Item = SmallBody> + '_ {
+        (ids.start.min(self.count)..ids.end.min(self.count)).filter_map(move |id| self.body(id))
+    }
+
+    /// Returns the position of a body at a time without keeping the body around.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The body's orbital ID.
+    /// * `time` - Earth days since the epoch.
+    ///
+    /// # Returns
+    ///
+    /// The position relative to the star in astronomical units, or `None` if `id` is not below
+    /// `count`.
+    pub fn position(&self, id: u64, time: f64) -> Option<(f64, f64, f64)> {
+        self.body(id).map(|body| body.orbit.position_at(time))
+    }
+}
+
+// Generates the small-body populations of a system.
+//
+// An asteroid belt forms where a giant stopped a planet from accreting, so it sits just inside the
+// innermost giant; a Kuiper belt lies beyond the outermost planet; every system gets an Oort cloud
+// scaled by the star's mass.
+pub(crate) fn generate_populations(system: Uuid, star: &Star, planets: &[SystemPlanet]) -> Vec<SmallBodyPopulation> {
+    let mut rng = StdRng::seed_from_u64(mix64(guid_seed(system) ^ POPULATION_DISCRIMINANT));
+    let mut populations = Vec::new();
+    let mut push = |kind, inner_radius: f64, outer_radius: f64, count: f64, min_body_radius, max_eccentricity, max_inclination: f64| {
+        populations.push(SmallBodyPopulation {
+            guid: derive_guid(system, POPULATION_DISCRIMINANT, populations.len() as u64),
+            kind,
+            inner_radius,
+            outer_radius,
+            count: count as u64,
+            min_body_radius,
+            max_eccentricity,
+            max_inclination: max_inclination.to_radians(),
+            star_mass: star.mass,
+        })
+    };
+
+    let first_giant = planets.iter().position(|planet| planet.kind != PlanetKind::Rocky);
+    if let Some(giant) = first_giant.filter(|_| rng.gen_bool(0.7)) {
+        let outer = planets[giant].orbit.semi_major_axis * 0.63;
+        let inner = planets[..giant].last().map_or(outer * 0.5, |planet| planet.orbit.semi_major_axis * 1.2);
+        if inner < outer {
+            push(PopulationKind::AsteroidBelt, inner, outer, 10f64.powf(rng.gen_range(5.0..7.0)), 1.0, 0.3, 20.0);
+        }
+    }
+
+    let frost_line = 2.7 * star.luminosity.sqrt();
+    let outermost = planets.last().map_or(frost_line * 5.0, |planet| planet.orbit.semi_major_axis);
+    if rng.gen_bool(0.8) {
+        let inner = outermost * rng.gen_range(1.2..1.6);
+        push(PopulationKind::KuiperBelt, inner, inner * rng.gen_range(1.3..2.0), 10f64.powf(rng.gen_range(5.0..8.0)), 10.0, 0.25, 30.0);
+    }
+
+    let scale = star.mass.sqrt();
+    push(PopulationKind::OortCloud, 2000.0 * scale, 100_000.0 * scale, 10f64.powf(rng.gen_range(10.0..12.0)), 1.0, 0.9, 180.0);
+    populations
+}
@@ -0,0 +1,355 @@
+use crate::space::{GalaxyOrbitRanges, OrbitModel, UniverseConfig, UniverseEvent, UniverseState};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+use uuid::Uuid;
+
+// File signature and format version of an encoded `UniverseState`
+const MAGIC: &[u8; 4] = b"TFUS";
+const VERSION: u32 = 1;
+
+/// Error returned when a universe snapshot cannot be decoded or resumed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    /// The file could not be read.
+    Io(String),
+    /// The data does not start with the snapshot signature.
+    NotASnapshot,
+    /// The snapshot was written by a newer, unknown format version.
+    UnsupportedVersion(u32),
+    /// The data ended before the snapshot did.
+    Truncated,
+    /// A value is out of range or inconsistent.
+    Invalid(String),
+    /// Regenerating the universe from the snapshot's seed and configuration gave different
+    /// galaxies than the snapshot holds.
+    Mismatch,
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(message) => write!(f, "cannot read snapshot: {}", message),
+            SnapshotError::NotASnapshot => write!(f, "data is not a universe snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "unsupported snapshot version {}", version),
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::Invalid(message) => write!(f, "invalid snapshot: {}", message),
+            SnapshotError::Mismatch => write!(f, "snapshot does not match the regenerated universe"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+// Reads little-endian values from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < count {
+            return Err(SnapshotError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.take(N)?.try_into().expect("slice has N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, SnapshotError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn guid(&mut self) -> Result<Uuid, SnapshotError> {
+        Ok(Uuid::from_bytes(self.array()?))
+    }
+
+    fn sector(&mut self) -> Result<(i32, i32, i32), SnapshotError> {
+        Ok((self.i32()?, self.i32()?, self.i32()?))
+    }
+
+    // A count of items that each take at least `item_size` bytes, checked against the data left
+    fn count(&mut self, item_size: usize) -> Result<usize, SnapshotError> {
+        let count = self.u64()?;
+        if count > (self.bytes.len() / item_size) as u64 {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(count as usize)
+    }
+
+    fn model(&mut self) -> Result<OrbitModel, SnapshotError> {
+        match self.u8()? {
+            0 => Ok(OrbitModel::Kepler),
+            1 => Ok(OrbitModel::Approximate),
+            other => Err(SnapshotError::Invalid(format!("unknown orbit model {}", other))),
+        }
+    }
+
+    fn range(&mut self) -> Result<std::ops::Range<f64>, SnapshotError> {
+        Ok(self.f64()?..self.f64()?)
+    }
+}
+
+fn put_sector(out: &mut Vec<u8>, (x, y, z): (i32, i32, i32)) {
+    for value in [x, y, z] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn model_code(model: OrbitModel) -> u8 {
+    match model {
+        OrbitModel::Kepler => 0,
+        OrbitModel::Approximate => 1,
+    }
+}
+
+impl UniverseState {
+    /// Encodes the state in a compact little-endian binary format.
+    ///
+    /// The format starts with the signature `TFUS` and a format version, so `from_bytes` can
+    /// reject foreign or newer data. Floats are stored bit-exactly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `models`, `x`, `y` or `z` is shorter than `guids`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128 + self.guids.len() * 41);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.time.to_le_bytes());
+        out.extend_from_slice(&self.time_scale.to_le_bytes());
+        out.extend_from_slice(self.seed.as_bytes());
+
+        let config = &self.config;
+        out.extend_from_slice(&config.galaxy_count_range.start.to_le_bytes());
+        out.extend_from_slice(&config.galaxy_count_range.end.to_le_bytes());
+        for value in [
+            config.extent,
+            config.sector_size,
+            config.orbit.semi_major_axis.start,
+            config.orbit.semi_major_axis.end,
+            config.orbit.semi_minor_axis.start,
+            config.orbit.semi_minor_axis.end,
+            config.orbit.period.start,
+            config.orbit.period.end,
+        ] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.push(model_code(self.default_model));
+
+        out.extend_from_slice(&(self.sectors.len() as u64).to_le_bytes());
+        for &sector in &self.sectors {
+            put_sector(&mut out, sector);
+        }
+
+        out.extend_from_slice(&(self.guids.len() as u64).to_le_bytes());
+        for i in 0..self.guids.len() {
+            out.extend_from_slice(self.guids[i].as_bytes());
+            out.push(model_code(self.models[i]));
+            for value in [self.x[i], self.y[i], self.z[i]] {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        match &self.pending_events {
+            None => out.push(0),
+            Some(events) => {
+                out.push(1);
+                out.extend_from_slice(&(events.len() as u64).to_le_bytes());
+                for event in events {
+                    match event {
+                        UniverseEvent::SectorLoaded(sector) => {
+                            out.push(0);
+                            put_sector(&mut out, *sector);
+                        }
+                        UniverseEvent::SectorUnloaded(sector) => {
+                            out.push(1);
+                            put_sector(&mut out, *sector);
+                        }
+                        UniverseEvent::GalaxyChangedSector { guid, from, to } => {
+                            out.push(2);
+                            out.extend_from_slice(guid.as_bytes());
+                            put_sector(&mut out, *from);
+                            put_sector(&mut out, *to);
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Decodes a state written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<UniverseState, SnapshotError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4).ok() != Some(MAGIC.as_slice()) {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let time = reader.f64()?;
+        let time_scale = reader.f64()?;
+        let seed = reader.guid()?;
+        let config = UniverseConfig {
+            galaxy_count_range: reader.u64()?..reader.u64()?,
+            extent: reader.f64()?,
+            sector_size: reader.f64()?,
+            orbit: GalaxyOrbitRanges {
+                semi_major_axis: reader.range()?,
+                semi_minor_axis: reader.range()?,
+                period: reader.range()?,
+            },
+        };
+        let default_model = reader.model()?;
+
+        let sector_count = reader.count(12)?;
+        let sectors = (0..sector_count).map(|_| reader.sector()).collect::<Result<Vec<_>, _>>()?;
+
+        let galaxy_count = reader.count(41)?;
+        let mut guids = Vec::with_capacity(galaxy_count);
+        let mut models = Vec::with_capacity(galaxy_count);
+        let (mut x, mut y, mut z) = (Vec::with_capacity(galaxy_count), Vec::with_capacity(galaxy_count), Vec::with_capacity(galaxy_count));
+        for _ in 0..galaxy_count {
+            guids.push(reader.guid()?);
+            models.push(reader.model()?);
+            x.push(reader.f64()?);
+            y.push(reader.f64()?);
+            z.push(reader.f64()?);
+        }
+
+        let pending_events = match reader.u8()? {
+            0 => None,
+            1 => {
+                let count = reader.count(13)?;
+                let mut events = Vec::with_capacity(count);
+                for _ in 0..count {
+                    events.push(match reader.u8()? {
+                        0 => UniverseEvent::SectorLoaded(reader.sector()?),
+                        1 => UniverseEvent::SectorUnloaded(reader.sector()?),
+                        2 => UniverseEvent::GalaxyChangedSector {
+                            guid: reader.guid()?,
+                            from: reader.sector()?,
+                            to: reader.sector()?,
+                        },
+                        other => return Err(SnapshotError::Invalid(format!("unknown event {}", other))),
+                    });
+                }
+                Some(events)
+            }
+            other => return Err(SnapshotError::Invalid(format!("bad event queue flag {}", other))),
+        };
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::Invalid("trailing data".to_string()));
+        }
+
+        Ok(UniverseState {
+            time,
+            time_scale,
+            seed,
+            config,
+            sectors,
+            default_model,
+            guids,
+            models,
+            x,
+            y,
+            z,
+            pending_events,
+        })
+    }
+
+    /// Writes the state to a file in the format of `to_bytes`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Reads a state written by `save`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<UniverseState, SnapshotError> {
+        let bytes = fs::read(path).map_err(|error| SnapshotError::Io(error.to_string()))?;
+        UniverseState::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::space::UniverseSimulation;
+
+    // A small simulation with mixed orbit models, queued events and some time elapsed
+    fn simulation() -> UniverseSimulation {
+        let mut simulation = UniverseSimulation::with_sectors(0x567, &[(0, 0, 0)]);
+        simulation.set_event_queue(true);
+        simulation.load_sectors(&[(1, 0, 0), (0, -1, 0)]);
+        simulation.universe_mut().set_orbit_model(&[(1, 0, 0)], OrbitModel::Approximate);
+        simulation.set_time_scale(250.0);
+        simulation.step(3600.0);
+        simulation
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let state = simulation().snapshot();
+        assert!(state.pending_events.as_ref().is_some_and(|events| !events.is_empty()));
+        assert!(state.models.contains(&OrbitModel::Approximate) && state.models.contains(&OrbitModel::Kepler));
+        assert_eq!(UniverseState::from_bytes(&state.to_bytes()), Ok(state));
+    }
+
+    #[test]
+    fn rejects_foreign_truncated_and_newer_data() {
+        let bytes = simulation().snapshot().to_bytes();
+        assert_eq!(UniverseState::from_bytes(b"PK\x03\x04"), Err(SnapshotError::NotASnapshot));
+        for length in [4, 20, bytes.len() / 2, bytes.len() - 1] {
+            assert_eq!(UniverseState::from_bytes(&bytes[..length]), Err(SnapshotError::Truncated), "cut at {length}");
+        }
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(UniverseState::from_bytes(&newer), Err(SnapshotError::UnsupportedVersion(VERSION + 1)));
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(UniverseState::from_bytes(&trailing), Err(SnapshotError::Invalid(_))));
+    }
+
+    #[test]
+    fn resumed_simulation_continues_where_the_original_was() {
+        let mut original = simulation();
+        let mut resumed = UniverseSimulation::resume(UniverseState::from_bytes(&original.snapshot().to_bytes()).unwrap()).unwrap();
+        for _ in 0..5 {
+            original.step(7200.0);
+            resumed.step(7200.0);
+        }
+        assert_eq!(resumed.snapshot(), original.snapshot());
+        assert_eq!(resumed.drain_events(), original.drain_events());
+    }
+
+    #[test]
+    fn resume_rejects_galaxies_that_do_not_regenerate() {
+        let mut state = simulation().snapshot();
+        state.guids[0] = Uuid::nil();
+        assert_eq!(UniverseSimulation::resume(state).err(), Some(SnapshotError::Mismatch));
+    }
+}
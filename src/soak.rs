@@ -0,0 +1,631 @@
+use crate::clouds::CloudLayer;
+use crate::ecology::{Ecosystem, FaunaKind};
+use crate::nbody::{GravitySimulation, GravitySolver};
+use crate::planet::Planet;
+use crate::space::{OrbitModel, UniverseSimulation};
+use crate::vector::{add, cross, length, scale, sub, Vec3};
+use crate::weather::{self, WeatherCondition};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// A simulation that can be driven by the soak harness.
+///
+/// Implement this for any coupled simulation that should be exercised over long runs. `check`
+/// reports invariant violations; `metrics` reports quantities whose slow drift the harness
+/// tracks.
+pub trait SoakSystem {
+    /// A short name used in reports.
+    fn name(&self) -> &str;
+
+    /// Advances the simulation by one tick.
+    fn tick(&mut self, tick: u64);
+
+    /// Returns a description of every invariant that is currently violated.
+    fn check(&self) -> Vec<String>;
+
+    /// Returns the tracked quantities and their current values.
+    fn metrics(&self) -> Vec<(&'static str, f64)>;
+}
+
+// Reports non-finite values and values outside `range`
+fn check_range(label: &str, values: impl Iterator<Item = f64>, range: (f64, f64), violations: &mut Vec<String>) {
+    let (mut non_finite, mut out_of_range, mut first) = (0usize, 0usize, None);
+    for (index, value) in values.enumerate() {
+        if !value.is_finite() {
+            non_finite += 1;
+            first.get_or_insert((index, value));
+        } else if value < range.0 || value > range.1 {
+            out_of_range += 1;
+            first.get_or_insert((index, value));
+        }
+    }
+    if let Some((index, value)) = first {
+        violations.push(format!(
+            "{}: {} non-finite and {} out of [{}, {}] values, first at {} = {}",
+            label, non_finite, out_of_range, range.0, range.1, index, value
+        ));
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+/// The planet's temperature and snow cycle coupled with its cloud layer.
+pub struct ClimateSoak {
+    planet: Planet,
+    clouds: CloudLayer,
+    days_per_tick: f64,
+}
+
+impl ClimateSoak {
+    /// Creates the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet to simulate; it is advanced in place.
+    /// * `seed` - The cloud seed.
+    /// * `days_per_tick` - Simulated days per tick.
+    pub fn new(planet: Planet, seed: u64, days_per_tick: f64) -> ClimateSoak {
+        let clouds = CloudLayer::new(&planet, seed);
+        ClimateSoak {
+            planet,
+            clouds,
+            days_per_tick,
+        }
+    }
+
+    /// Returns the simulated planet.
+    pub fn planet(&self) -> &Planet {
+        &self.planet
+    }
+}
+
+impl SoakSystem for ClimateSoak {
+    fn name(&self) -> &str {
+        "climate"
+    }
+
+    fn tick(&mut self, _tick: u64) {
+        self.planet.advance_days(self.days_per_tick);
+        // Humidity follows temperature; winds are steady westerlies
+        let humidity: Vec<f64> = self.planet.temperature().iter().map(|t| (50.0 + t).clamp(0.0, 100.0)).collect();
+        let wind_speed = vec![10.0; self.planet.cell_count()];
+        let wind_direction = vec![90.0; self.planet.cell_count()];
+        let hours = self.days_per_tick * self.planet.calendar().day_length_hours();
+        self.clouds.step(&self.planet, &humidity, &wind_speed, &wind_direction, hours);
+    }
+
+    fn check(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        check_range("temperature", self.planet.temperature().iter().copied(), (-150.0, 150.0), &mut violations);
+        check_range("snow_cover", self.planet.snow_cover().iter().copied(), (0.0, 1.0), &mut violations);
+        check_range("cloud_coverage", self.clouds.coverage().iter().copied(), (0.0, 1.0), &mut violations);
+        violations
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("mean_temperature", mean(self.planet.temperature())),
+            ("mean_snow_cover", mean(self.planet.snow_cover())),
+            ("mean_cloud_coverage", mean(self.clouds.coverage())),
+        ]
+    }
+}
+
+/// The cell-based weather simulation with seasonal forcing.
+pub struct WeatherSoak {
+    conditions: Vec<WeatherCondition>, // One per cell of `planet`
+    planet: Planet, // Supplies the cells, calendar and axial tilt
+    days_per_tick: f64,
+    day: f64,
+}
+
+impl WeatherSoak {
+    /// Creates the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - Supplies the cells, orbital calendar and axial tilt.
+    /// * `seed` - The initial weather seed.
+    /// * `days_per_tick` - Simulated days per tick.
+    pub fn new(planet: Planet, seed: u64, days_per_tick: f64) -> WeatherSoak {
+        WeatherSoak {
+            conditions: weather::generate_weather_conditions(seed, &planet),
+            planet,
+            days_per_tick,
+            day: 0.0,
+        }
+    }
+}
+
+impl SoakSystem for WeatherSoak {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn tick(&mut self, tick: u64) {
+        self.day += self.days_per_tick;
+        weather::simulate_weather(&mut self.conditions, &self.planet, tick as usize);
+        let relax = 1.0 - (-self.days_per_tick / 30.0).exp();
+        weather::apply_seasonal_forcing(&mut self.conditions, &self.planet, self.day, relax);
+    }
+
+    fn check(&self) -> Vec<String> {
+        const RANGES: [(f64, f64); 5] = [(-30.0, 50.0), (0.0, 100.0), (0.0, 30.0), (-360.0, 360.0), (0.0, 100.0)];
+        let mut violations = Vec::new();
+        for (field, range) in RANGES.iter().enumerate() {
+            let name = self.conditions.first().map_or("", |c| c.fields()[field].0);
+            check_range(name, self.conditions.iter().map(|c| c.fields()[field].1), *range, &mut violations);
+        }
+        violations
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        let field_mean = |field: usize| mean(&self.conditions.iter().map(|c| c.fields()[field].1).collect::<Vec<f64>>());
+        vec![
+            ("mean_temperature", field_mean(0)),
+            ("mean_humidity", field_mean(1)),
+            ("mean_wind_speed", field_mean(2)),
+            ("mean_precipitation", field_mean(4)),
+        ]
+    }
+}
+
+/// An N-body gravity simulation, checked for conservation of energy and momentum.
+pub struct GravitySoak {
+    simulation: GravitySimulation,
+    days_per_tick: f64,
+    energy_tolerance: f64, // Largest relative energy error allowed
+    initial_energy: f64,
+    initial_momentum: Vec3,
+}
+
+impl GravitySoak {
+    /// Creates the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `simulation` - The simulation to run; it is advanced in place.
+    /// * `days_per_tick` - The step size in days, as for `GravitySimulation::step`.
+    /// * `energy_tolerance` - The largest relative change in total energy allowed. Velocity Verlet
+    ///   keeps the error bounded, at a level set by the step size.
+    pub fn new(simulation: GravitySimulation, days_per_tick: f64, energy_tolerance: f64) -> GravitySoak {
+        GravitySoak {
+            initial_energy: simulation.total_energy(),
+            initial_momentum: momentum(&simulation),
+            simulation,
+            days_per_tick,
+            energy_tolerance,
+        }
+    }
+
+    /// Returns the simulation.
+    pub fn simulation(&self) -> &GravitySimulation {
+        &self.simulation
+    }
+
+    // Change in total energy relative to the starting energy
+    fn energy_error(&self) -> f64 {
+        (self.simulation.total_energy() - self.initial_energy).abs() / self.initial_energy.abs().max(f64::MIN_POSITIVE)
+    }
+
+    // Change in total momentum relative to the sum of the bodies' momentum magnitudes
+    fn momentum_error(&self) -> f64 {
+        let scale: f64 = self.simulation.bodies().iter().map(|body| body.mass * length(body.velocity)).sum();
+        length(sub(momentum(&self.simulation), self.initial_momentum)) / scale.max(f64::MIN_POSITIVE)
+    }
+}
+
+// Total momentum of a simulation's bodies, solar masses · AU / day
+fn momentum(simulation: &GravitySimulation) -> Vec3 {
+    simulation.bodies().iter().fold((0.0, 0.0, 0.0), |sum, body| add(sum, scale(body.velocity, body.mass)))
+}
+
+impl SoakSystem for GravitySoak {
+    fn name(&self) -> &str {
+        "gravity"
+    }
+
+    fn tick(&mut self, _tick: u64) {
+        self.simulation.step(self.days_per_tick);
+    }
+
+    fn check(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let bodies = self.simulation.bodies();
+        check_range("position", bodies.iter().flat_map(|body| [body.position.0, body.position.1, body.position.2]), (f64::MIN, f64::MAX), &mut violations);
+        check_range("velocity", bodies.iter().flat_map(|body| [body.velocity.0, body.velocity.1, body.velocity.2]), (f64::MIN, f64::MAX), &mut violations);
+        check_range("energy_error", std::iter::once(self.energy_error()), (0.0, self.energy_tolerance), &mut violations);
+        // Pairwise forces cancel exactly only when every pair is summed
+        if self.simulation.solver() == GravitySolver::Direct {
+            check_range("momentum_error", std::iter::once(self.momentum_error()), (0.0, 1e-9), &mut violations);
+        }
+        violations
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("total_energy", self.simulation.total_energy()),
+            ("energy_error", self.energy_error()),
+            ("momentum_error", self.momentum_error()),
+        ]
+    }
+}
+
+// Time step, in orbital time units, of the central differences that estimate galaxy velocities
+const GALAXY_VELOCITY_STEP: f64 = 1e-5;
+
+/// The loaded galaxies of a universe on their orbits.
+///
+/// Keplerian orbits conserve each galaxy's orbital energy and angular momentum. The galaxies'
+/// velocities are estimated from their positions just before and after the current time, and the
+/// energy and angular momentum they give are checked against the values set by the orbits' axes
+/// and periods. Galaxies on `OrbitModel::Approximate` orbits, which conserve neither, are only
+/// checked for finite positions.
+pub struct UniverseSoak {
+    simulation: UniverseSimulation,
+    seconds_per_tick: f64,
+    tolerance: f64, // Largest relative error in orbital energy and angular momentum
+    // Gravitational parameter, specific energy and angular momentum of each Keplerian galaxy, in
+    // the order of `loaded_guids`
+    orbits: Vec<Option<(f64, f64, f64)>>,
+}
+
+impl UniverseSoak {
+    /// Creates the system.
+    ///
+    /// Galaxies must be loaded beforehand; sectors loaded later are not checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `simulation` - The simulation to run; it is advanced in place.
+    /// * `seconds_per_tick` - Simulated seconds per tick, as for `UniverseSimulation::step`.
+    /// * `tolerance` - The largest relative error allowed in a galaxy's angular momentum, and in its
+    ///   orbital energy relative to its potential energy.
+    pub fn new(simulation: UniverseSimulation, seconds_per_tick: f64, tolerance: f64) -> UniverseSoak {
+        let universe = simulation.universe();
+        let index: HashMap<_, _> = universe.loaded_guids().iter().enumerate().map(|(i, guid)| (*guid, i)).collect();
+        let models = universe.loaded_orbit_models();
+        let mut orbits = vec![None; index.len()];
+        for galaxy in universe.galaxies() {
+            let i = index[&galaxy.guid()];
+            if models[i] != OrbitModel::Kepler {
+                continue;
+            }
+            let orbit = galaxy.orbit();
+            let (major, minor) = (orbit.semi_major_axis.max(orbit.semi_minor_axis), orbit.semi_major_axis.min(orbit.semi_minor_axis));
+            let rate = 2.0 * PI / orbit.period;
+            let mu = rate * rate * major.powi(3);
+            orbits[i] = Some((mu, -mu / (2.0 * major), rate * major * minor));
+        }
+        UniverseSoak {
+            simulation,
+            seconds_per_tick,
+            tolerance,
+            orbits,
+        }
+    }
+
+    /// Returns the simulation.
+    pub fn simulation(&self) -> &UniverseSimulation {
+        &self.simulation
+    }
+
+    // The largest relative errors in orbital energy and angular momentum over the Keplerian galaxies
+    fn orbit_errors(&self) -> (f64, f64) {
+        let universe = self.simulation.universe();
+        let time = universe.time();
+        let (x, y, z) = universe.loaded_positions();
+        let (bx, by, bz) = universe.positions_at(time - GALAXY_VELOCITY_STEP);
+        let (ax, ay, az) = universe.positions_at(time + GALAXY_VELOCITY_STEP);
+        let (mut energy_error, mut momentum_error) = (0.0f64, 0.0f64);
+        for (i, orbit) in self.orbits.iter().enumerate() {
+            let Some((mu, energy, momentum)) = *orbit else {
+                continue;
+            };
+            let position = (x[i], y[i], z[i]);
+            let velocity = scale(sub((ax[i], ay[i], az[i]), (bx[i], by[i], bz[i])), 0.5 / GALAXY_VELOCITY_STEP);
+            // Relative to the potential, since near periapsis the kinetic and potential terms of
+            // eccentric orbits nearly cancel
+            let potential = mu / length(position);
+            let measured = 0.5 * length(velocity).powi(2) - potential;
+            energy_error = energy_error.max((measured - energy).abs() / potential);
+            momentum_error = momentum_error.max((length(cross(position, velocity)) - momentum).abs() / momentum);
+        }
+        (energy_error, momentum_error)
+    }
+}
+
+impl SoakSystem for UniverseSoak {
+    fn name(&self) -> &str {
+        "universe"
+    }
+
+    fn tick(&mut self, _tick: u64) {
+        self.simulation.step(self.seconds_per_tick);
+    }
+
+    fn check(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let (x, y, z) = self.simulation.universe().loaded_positions();
+        check_range("position", x.iter().chain(y).chain(z).copied(), (f64::MIN, f64::MAX), &mut violations);
+        let (energy_error, momentum_error) = self.orbit_errors();
+        check_range("orbital_energy_error", std::iter::once(energy_error), (0.0, self.tolerance), &mut violations);
+        check_range("angular_momentum_error", std::iter::once(momentum_error), (0.0, self.tolerance), &mut violations);
+        violations
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        let (x, y, z) = self.simulation.universe().loaded_positions();
+        let radii: Vec<f64> = (0..x.len()).map(|i| length((x[i], y[i], z[i]))).collect();
+        let (energy_error, momentum_error) = self.orbit_errors();
+        vec![
+            ("mean_radius", mean(&radii)),
+            ("orbital_energy_error", energy_error),
+            ("angular_momentum_error", momentum_error),
+        ]
+    }
+}
+
+/// The predator and prey populations of a planet's ecosystem.
+pub struct EcologySoak {
+    ecosystem: Ecosystem,
+    days_per_tick: f64,
+}
+
+impl EcologySoak {
+    /// Creates the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `ecosystem` - The ecosystem to simulate; it is advanced in place.
+    /// * `days_per_tick` - Simulated days per tick.
+    pub fn new(ecosystem: Ecosystem, days_per_tick: f64) -> EcologySoak {
+        EcologySoak {
+            ecosystem,
+            days_per_tick,
+        }
+    }
+
+    /// Returns the simulated ecosystem.
+    pub fn ecosystem(&self) -> &Ecosystem {
+        &self.ecosystem
+    }
+}
+
+impl SoakSystem for EcologySoak {
+    fn name(&self) -> &str {
+        "ecology"
+    }
+
+    fn tick(&mut self, _tick: u64) {
+        self.ecosystem.step(self.days_per_tick);
+    }
+
+    fn check(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let capacity = self.ecosystem.carrying_capacity();
+        // Herbivores never outgrow what their cell can carry
+        let herbivores = self.ecosystem.density(FaunaKind::Herbivore);
+        let filled = herbivores.iter().zip(capacity).filter(|(_, &capacity)| capacity > 0.0).map(|(density, capacity)| density / capacity);
+        check_range("herbivore_capacity_fraction", filled, (0.0, 1.0), &mut violations);
+        check_range("predator_density", self.ecosystem.density(FaunaKind::Predator).into_iter(), (0.0, f64::MAX), &mut violations);
+        violations
+    }
+
+    fn metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("mean_herbivore_density", mean(&self.ecosystem.density(FaunaKind::Herbivore))),
+            ("mean_predator_density", mean(&self.ecosystem.density(FaunaKind::Predator))),
+        ]
+    }
+}
+
+/// Settings for a soak run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakSettings {
+    pub ticks: u64,
+    pub check_every: u64,             // Ticks between invariant checks
+    pub sample_every: u64,            // Ticks between drift samples
+    pub stop_on_violation: bool,      // Stop at the first check that finds a violation
+    pub time_limit: Option<Duration>, // Stop early once this much wall time has passed
+}
+
+impl Default for SoakSettings {
+    /// One million ticks, checking every 100 and sampling every 10 000, stopping at the first violation.
+    fn default() -> Self {
+        SoakSettings {
+            ticks: 1_000_000,
+            check_every: 100,
+            sample_every: 10_000,
+            stop_on_violation: true,
+            time_limit: None,
+        }
+    }
+}
+
+/// An invariant violation found during a soak run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakViolation {
+    pub tick: u64,
+    pub system: String,
+    pub message: String,
+}
+
+/// How far one metric moved over a soak run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDrift {
+    pub system: String,
+    pub metric: &'static str,
+    pub first: f64,
+    pub last: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl MetricDrift {
+    /// Returns the change from the first to the last sample.
+    pub fn drift(&self) -> f64 {
+        self.last - self.first
+    }
+}
+
+/// The outcome of a soak run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakReport {
+    pub ticks_run: u64,
+    pub elapsed: Duration,
+    pub violations: Vec<SoakViolation>,
+    pub drift: Vec<MetricDrift>,
+}
+
+impl SoakReport {
+    /// Returns `true` if no invariant was violated.
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+// Folds the current metric values into the running drift records
+#[cfg(not(target_arch = "wasm32"))]
+fn record_metrics(systems: &[Box<dyn SoakSystem>], drift: &mut Vec<MetricDrift>) {
+    for system in systems {
+        for (metric, value) in system.metrics() {
+            match drift.iter_mut().find(|d| d.system == system.name() && d.metric == metric) {
+                Some(d) => {
+                    d.last = value;
+                    d.min = d.min.min(value);
+                    d.max = d.max.max(value);
+                }
+                None => drift.push(MetricDrift {
+                    system: system.name().to_string(),
+                    metric,
+                    first: value,
+                    last: value,
+                    min: value,
+                    max: value,
+                }),
+            }
+        }
+    }
+}
+
+/// Runs coupled simulations for a long time, checking invariants and tracking drift.
+///
+/// Every system is ticked in turn. Every `check_every` ticks all invariants are checked, and
+/// every `sample_every` ticks the metrics are sampled; the report records each metric's first,
+/// last, minimum and maximum sampled value so slow drift shows up even when no invariant trips.
+///
+/// # Arguments
+///
+/// * `systems` - The simulations to run together.
+/// * `settings` - Run length and check frequency.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_soak(systems: &mut [Box<dyn SoakSystem>], settings: &SoakSettings) -> SoakReport {
+    let start = Instant::now();
+    let mut violations = Vec::new();
+    let mut drift: Vec<MetricDrift> = Vec::new();
+    record_metrics(systems, &mut drift);
+
+    let mut ticks_run = 0;
+    for tick in 0..settings.ticks {
+        for system in systems.iter_mut() {
+            system.tick(tick);
+        }
+        ticks_run = tick + 1;
+
+        let last = ticks_run == settings.ticks;
+        if last || ticks_run % settings.check_every.max(1) == 0 {
+            for system in systems.iter() {
+                violations.extend(system.check().into_iter().map(|message| SoakViolation {
+                    tick,
+                    system: system.name().to_string(),
+                    message,
+                }));
+            }
+        }
+        if last || ticks_run % settings.sample_every.max(1) == 0 {
+            record_metrics(systems, &mut drift);
+        }
+        if (settings.stop_on_violation && !violations.is_empty()) || settings.time_limit.is_some_and(|limit| start.elapsed() >= limit) {
+            break;
+        }
+    }
+
+    SoakReport {
+        ticks_run,
+        elapsed: start.elapsed(),
+        violations,
+        drift,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guid::guid_from_seed;
+    use crate::nbody::{GravityBody, GRAVITATIONAL_CONSTANT};
+    use crate::procedural::PlanetParameters;
+    use crate::space::Universe;
+    use uuid::Uuid;
+
+    fn settings(ticks: u64) -> SoakSettings {
+        SoakSettings {
+            ticks,
+            check_every: 10,
+            sample_every: 50,
+            ..SoakSettings::default()
+        }
+    }
+
+    // The Sun and a planet on a circular orbit at 1 AU
+    fn sun_and_planet() -> GravitySimulation {
+        let sun = GravityBody { guid: Uuid::from_u128(1), mass: 1.0, position: (0.0, 0.0, 0.0), velocity: (0.0, 0.0, 0.0) };
+        let planet = GravityBody { guid: Uuid::from_u128(2), mass: 3e-6, position: (1.0, 0.0, 0.0), velocity: (0.0, GRAVITATIONAL_CONSTANT.sqrt(), 0.0) };
+        GravitySimulation::new(vec![sun, planet], 0.0)
+    }
+
+    #[test]
+    fn gravity_conserves_energy_and_momentum() {
+        let mut systems: Vec<Box<dyn SoakSystem>> = vec![Box::new(GravitySoak::new(sun_and_planet(), 1.0, 1e-4))];
+        let report = run_soak(&mut systems, &settings(3650));
+        assert!(report.passed(), "{:?}", report.violations);
+        assert_eq!(report.ticks_run, 3650);
+
+        // Steps of a third of the orbit cannot follow it
+        let mut systems: Vec<Box<dyn SoakSystem>> = vec![Box::new(GravitySoak::new(sun_and_planet(), 120.0, 1e-4))];
+        let report = run_soak(&mut systems, &settings(100));
+        assert!(report.violations.iter().any(|violation| violation.message.starts_with("energy_error")));
+        assert!(report.ticks_run < 100);
+    }
+
+    #[test]
+    fn galaxies_keep_their_orbital_energy_and_angular_momentum() {
+        for model in [OrbitModel::Kepler, OrbitModel::Approximate] {
+            let mut universe = Universe::new(guid_from_seed(5));
+            universe.set_default_orbit_model(model);
+            universe.load_sectors(&[(0, 0, 0), (3, -2, 1), (-5, 5, -5)]);
+            let soak = UniverseSoak::new(UniverseSimulation::from_universe(universe), 600.0, 1e-4);
+            assert!(soak.check().is_empty(), "{:?}", soak.check());
+            let mut systems: Vec<Box<dyn SoakSystem>> = vec![Box::new(soak)];
+            let report = run_soak(&mut systems, &settings(500));
+            assert!(report.passed(), "{model:?}: {:?}", report.violations);
+        }
+    }
+
+    #[test]
+    fn populations_stay_within_capacity() {
+        let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x5EED));
+        parameters.samples = 500;
+        let ecosystem = Ecosystem::from_planet(&parameters.build());
+        let mut systems: Vec<Box<dyn SoakSystem>> = vec![Box::new(EcologySoak::new(ecosystem, 30.0))];
+        let report = run_soak(&mut systems, &settings(500));
+        assert!(report.passed(), "{:?}", report.violations);
+        let herbivores = report.drift.iter().find(|drift| drift.metric == "mean_herbivore_density").unwrap();
+        assert!(herbivores.min > 0.0);
+    }
+}
@@ -1,71 +1,534 @@
 use rayon::prelude::*;
 use uuid::Uuid;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+#[cfg(not(target_arch = "wasm32"))]
 use std::thread;
 use rand::{SeedableRng, Rng};
 use rand::rngs::StdRng;
-use std::f64::consts::PI;
+use std::f64::consts::{PI, TAU};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::{Duration, Instant};
+use crate::coords::SectorGrid;
+use crate::guid::{derive_guid, guid_from_seed, guid_seed, GALAXY_DISCRIMINANT, SECTOR_DISCRIMINANT, SYSTEM_DISCRIMINANT};
+use crate::noise::mix64;
+use crate::observer::{Observers, SubscriptionId};
+use crate::octree::Octree;
+use crate::snapshot::SnapshotError;
+use crate::render::gradient_color;
+use crate::star_system::{eccentric_anomaly, OrbitalElements};
+use crate::vector::{add, cross, dot, length, normalize, scale, sub};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-// Struct representing a Galaxy
-#[derive(Debug)]
-struct Galaxy {
+/// The morphological class of a galaxy, after the Hubble sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GalaxyClass {
+    /// A thin exponential disk with logarithmic spiral arms around a central bulge.
+    Spiral,
+    /// A spiral whose arms start from the ends of a central bar of stars.
+    BarredSpiral,
+    /// A smooth, flattened spheroid of old stars with no disk.
+    Elliptical,
+    /// A lumpy disk dominated by scattered star-forming clumps.
+    Irregular,
+    /// A small, faint spheroid a few hundred parsecs across.
+    Dwarf,
+}
+
+impl GalaxyClass {
+    /// Every class, in declaration order.
+    pub const ALL: [GalaxyClass; 5] = [
+        GalaxyClass::Spiral,
+        GalaxyClass::BarredSpiral,
+        GalaxyClass::Elliptical,
+        GalaxyClass::Irregular,
+        GalaxyClass::Dwarf,
+    ];
+
+    /// Returns the share of galaxies of this class. The shares of all classes sum to 1.
+    pub fn frequency(&self) -> f64 {
+        match self {
+            GalaxyClass::Spiral => 0.25,
+            GalaxyClass::BarredSpiral => 0.3,
+            GalaxyClass::Elliptical => 0.15,
+            GalaxyClass::Irregular => 0.1,
+            GalaxyClass::Dwarf => 0.2,
+        }
+    }
+
+    /// Returns the range of star counts of galaxies of this class.
+    pub fn star_count_range(&self) -> Range<u64> {
+        match self {
+            GalaxyClass::Spiral | GalaxyClass::BarredSpiral => 20_000_000_000..400_000_000_000,
+            GalaxyClass::Elliptical => 100_000_000_000..1_000_000_000_000,
+            GalaxyClass::Irregular => 100_000_000..10_000_000_000,
+            GalaxyClass::Dwarf => 1_000_000..1_000_000_000,
+        }
+    }
+
+    /// Returns the range of integrated B−V color indices of galaxies of this class; higher is
+    /// redder, from older stellar populations.
+    pub fn color_index_range(&self) -> Range<f64> {
+        match self {
+            GalaxyClass::Spiral | GalaxyClass::BarredSpiral => 0.55..0.8,
+            GalaxyClass::Elliptical => 0.85..1.0,
+            GalaxyClass::Irregular => 0.3..0.5,
+            GalaxyClass::Dwarf => 0.45..0.9,
+        }
+    }
+
+    /// Draws a class with probabilities given by `frequency`, deterministically from a galaxy GUID.
+    pub fn generate(guid: Uuid) -> GalaxyClass {
+        let mut rng = StdRng::seed_from_u64(mix64(guid_seed(guid) ^ 0x57A7_C7E5));
+        GalaxyClass::sample(&mut rng)
+    }
+
+    // Draws a class with probabilities given by `frequency`
+    fn sample(rng: &mut impl Rng) -> GalaxyClass {
+        let mut pick: f64 = rng.gen();
+        for class in GalaxyClass::ALL {
+            pick -= class.frequency();
+            if pick < 0.0 {
+                return class;
+            }
+        }
+        GalaxyClass::Dwarf
+    }
+}
+
+/// The spatial distribution of stars in a galaxy.
+///
+/// Stars are drawn from up to four components: an exponential disk (optionally wound into
+/// spiral arms or broken into clumps), a central bar, a Hernquist bulge, and an extended stellar
+/// halo. All
+/// lengths are in kiloparsecs, relative to the galaxy's centre with the disk in the xy-plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalaxyStructure {
+    pub galaxy_class: GalaxyClass,
+    pub disk_scale_length: f64, // Radius over which disk density falls by a factor of e
+    pub disk_scale_height: f64, // Height over which disk density falls by a factor of e
+    pub bulge_radius: f64,      // Hernquist scale radius of the bulge
+    pub bulge_fraction: f64,    // Share of stars in the bulge, 0.0 to 1.0
+    pub bulge_flattening: f64,  // Ratio of the bulge's vertical to horizontal extent
+    pub halo_radius: f64,       // Hernquist scale radius of the halo
+    pub halo_fraction: f64,     // Share of stars in the halo, 0.0 to 1.0
+    pub arm_count: u32,         // Spiral arms; 0 for no arms
+    pub arm_pitch: f64,         // Pitch angle of the arms in radians
+    pub arm_strength: f64,      // Share of disk stars concentrated in the arms, 0.0 to 1.0
+    pub arm_width: f64,         // Angular spread of stars around an arm in radians
+    pub clump_count: u32,       // Star-forming clumps in the disk
+    pub clump_fraction: f64,    // Share of disk stars in clumps, 0.0 to 1.0
+    pub bar_length: f64,        // Half-length of the bar along the x-axis; 0.0 for no bar
+    pub bar_fraction: f64,      // Share of disk stars in the bar, 0.0 to 1.0
+    clump_seed: u64,
+}
+
+// Salt separating a system's position stream from its contents
+const POSITION_SALT: u64 = 0x9A1A_C71C_0FF5_E700;
+// Salt for a galaxy's star count and color
+const CLASS_PROPERTIES_SALT: u64 = 0xC0_10E5;
+
+// A standard normal sample by the Box-Muller transform
+fn gaussian(rng: &mut impl Rng) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    (-2.0 * u.ln()).sqrt() * (TAU * rng.gen::<f64>()).cos()
+}
+
+// A radius drawn from a Hernquist profile, truncated at 10 scale radii
+fn hernquist_radius(rng: &mut impl Rng, scale: f64) -> f64 {
+    let s = rng.gen::<f64>().sqrt().min(10.0 / 11.0);
+    scale * s / (1.0 - s)
+}
+
+// A uniformly distributed direction on the unit sphere
+fn random_direction(rng: &mut impl Rng) -> (f64, f64, f64) {
+    let z: f64 = rng.gen_range(-1.0..1.0);
+    let angle = rng.gen_range(0.0..TAU);
+    let r = (1.0 - z * z).sqrt();
+    (r * angle.cos(), r * angle.sin(), z)
+}
+
+impl GalaxyStructure {
+    /// Generates the structure of a galaxy deterministically from its GUID.
+    ///
+    /// The class is drawn as in `GalaxyClass::generate`, and the parameters are drawn from ranges
+    /// typical of that class.
+    pub fn generate(guid: Uuid) -> GalaxyStructure {
+        let mut rng = StdRng::seed_from_u64(mix64(guid_seed(guid) ^ 0x57A7_C7E5));
+        let galaxy_class = GalaxyClass::sample(&mut rng);
+        GalaxyStructure::for_class(galaxy_class, &mut rng)
+    }
+
+    /// Draws the parameters of a galaxy of the given class.
+    pub fn for_class(galaxy_class: GalaxyClass, rng: &mut impl Rng) -> GalaxyStructure {
+        let disk_scale_length = rng.gen_range(1.5f64..5.0);
+        let mut structure = GalaxyStructure {
+            galaxy_class,
+            disk_scale_length,
+            disk_scale_height: disk_scale_length * rng.gen_range(0.05..0.12),
+            bulge_radius: disk_scale_length * rng.gen_range(0.1..0.3),
+            bulge_fraction: rng.gen_range(0.1..0.35),
+            bulge_flattening: rng.gen_range(0.6..0.9),
+            halo_radius: disk_scale_length * rng.gen_range(4.0..8.0),
+            halo_fraction: rng.gen_range(0.01..0.03),
+            arm_count: 0,
+            arm_pitch: 0.0,
+            arm_strength: 0.0,
+            arm_width: 0.0,
+            clump_count: 0,
+            clump_fraction: 0.0,
+            bar_length: 0.0,
+            bar_fraction: 0.0,
+            clump_seed: rng.gen(),
+        };
+        match galaxy_class {
+            GalaxyClass::Spiral => {
+                structure.arm_count = rng.gen_range(2..=4);
+                structure.arm_pitch = rng.gen_range(10.0f64..30.0).to_radians();
+                structure.arm_strength = rng.gen_range(0.4..0.8);
+                structure.arm_width = rng.gen_range(0.15..0.4);
+            }
+            GalaxyClass::BarredSpiral => {
+                // Barred spirals are mostly two-armed, with the arms trailing from the bar's ends
+                structure.arm_count = if rng.gen_bool(0.8) { 2 } else { 4 };
+                structure.arm_pitch = rng.gen_range(10.0f64..25.0).to_radians();
+                structure.arm_strength = rng.gen_range(0.5..0.8);
+                structure.arm_width = rng.gen_range(0.15..0.35);
+                structure.bar_length = disk_scale_length * rng.gen_range(0.5..1.2);
+                structure.bar_fraction = rng.gen_range(0.1..0.25);
+            }
+            GalaxyClass::Elliptical => {
+                // All bulge: a spheroid with a Hernquist profile
+                structure.bulge_radius = rng.gen_range(1.0..6.0);
+                structure.bulge_fraction = 1.0 - structure.halo_fraction;
+                structure.bulge_flattening = rng.gen_range(0.3..1.0);
+            }
+            GalaxyClass::Irregular => {
+                structure.disk_scale_length *= 0.5;
+                structure.disk_scale_height = structure.disk_scale_length * rng.gen_range(0.15..0.3);
+                structure.bulge_fraction = rng.gen_range(0.0..0.05);
+                structure.clump_count = rng.gen_range(3..=12);
+                structure.clump_fraction = rng.gen_range(0.3..0.7);
+            }
+            GalaxyClass::Dwarf => {
+                // A dwarf spheroidal: a small, diffuse bulge with a faint halo
+                structure.bulge_radius = rng.gen_range(0.1..0.6);
+                structure.bulge_fraction = 1.0 - structure.halo_fraction;
+                structure.bulge_flattening = rng.gen_range(0.5..1.0);
+                structure.halo_radius = structure.bulge_radius * rng.gen_range(4.0..8.0);
+            }
+        }
+        structure
+    }
+
+    // The centre of a star-forming clump in the disk plane
+    fn clump_centre(&self, clump: u32) -> (f64, f64) {
+        let mut rng = StdRng::seed_from_u64(mix64(self.clump_seed ^ clump as u64));
+        let radius = -self.disk_scale_length * (1.0 - rng.gen::<f64>()).ln();
+        let angle = rng.gen_range(0.0..TAU);
+        (radius * angle.cos(), radius * angle.sin())
+    }
+
+    /// Draws one star position from the distribution.
+    ///
+    /// # Returns
+    ///
+    /// The position relative to the galaxy's centre in kiloparsecs.
+    pub fn sample(&self, rng: &mut impl Rng) -> (f64, f64, f64) {
+        let component: f64 = rng.gen();
+        if component < self.halo_fraction {
+            let (x, y, z) = random_direction(rng);
+            let r = hernquist_radius(rng, self.halo_radius);
+            return (x * r, y * r, z * r);
+        }
+        if component < self.halo_fraction + self.bulge_fraction {
+            let (x, y, z) = random_direction(rng);
+            let r = hernquist_radius(rng, self.bulge_radius);
+            return (x * r, y * r, z * r * self.bulge_flattening);
+        }
+
+        // An exponential disk: the radius follows R·exp(-R/h), a Gamma(2, h) distribution
+        let height = -self.disk_scale_height * (1.0 - rng.gen::<f64>()).ln() * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        if self.bar_length > 0.0 && rng.gen_bool(self.bar_fraction) {
+            let x = rng.gen_range(-self.bar_length..self.bar_length);
+            return (x, gaussian(rng) * self.bar_length * 0.2, height);
+        }
+        if self.clump_count > 0 && rng.gen_bool(self.clump_fraction) {
+            let (cx, cy) = self.clump_centre(rng.gen_range(0..self.clump_count));
+            let spread = self.disk_scale_length * 0.15;
+            return (cx + gaussian(rng) * spread, cy + gaussian(rng) * spread, height);
+        }
+        let radius = -self.disk_scale_length * ((1.0 - rng.gen::<f64>()) * (1.0 - rng.gen::<f64>())).ln();
+        let angle = if self.arm_count > 0 && rng.gen_bool(self.arm_strength) {
+            // Logarithmic spiral: the arm angle grows with ln(R) / tan(pitch)
+            let arm = rng.gen_range(0..self.arm_count) as f64;
+            // Arms of a barred spiral begin at the ends of the bar, along the x-axis
+            let start = if self.bar_length > 0.0 { self.bar_length } else { self.disk_scale_length };
+            let winding = (radius / start).max(1e-3).ln() / self.arm_pitch.tan();
+            winding + TAU * arm / self.arm_count as f64 + gaussian(rng) * self.arm_width
+        } else {
+            rng.gen_range(0.0..TAU)
+        };
+        (radius * angle.cos(), radius * angle.sin(), height)
+    }
+}
+
+/// How galaxy orbits are propagated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OrbitModel {
+    /// Keplerian motion with the origin at a focus of the ellipse: galaxies speed up near
+    /// periapsis and linger near apoapsis. Costs a few Newton iterations per update.
+    #[default]
+    Kepler,
+    /// The same ellipse, with the eccentric anomaly advancing at a constant rate. Not physical,
+    /// since galaxies keep the same speed all the way round, but it skips the Newton iterations;
+    /// good enough for distant background objects.
+    Approximate,
+}
+
+// An orbit's semi-major and semi-minor axis vectors in space, the phase shift that makes the
+// semi-major vector correspond to angle zero, the eccentricity, and the eccentric minus the mean
+// anomaly at time zero
+struct OrbitAxes {
+    major: (f64, f64, f64),
+    minor: (f64, f64, f64),
+    shift: f64,
+    eccentricity: f64,
+    lag: f64,
+}
+
+// The position on an orbit given its semi-major and semi-minor axis vectors. `lag` is the eccentric
+// minus the mean anomaly at time zero, which keeps the approximate model on the Keplerian position
+// then
+fn orbit_position(major: (f64, f64, f64), minor: (f64, f64, f64), eccentricity: f64, mean_anomaly: f64, lag: f64, model: OrbitModel) -> (f64, f64, f64) {
+    let anomaly = match model {
+        OrbitModel::Kepler => eccentric_anomaly(mean_anomaly, eccentricity),
+        OrbitModel::Approximate => mean_anomaly + lag,
+    };
+    let (sin, cos) = anomaly.sin_cos();
+    let cos = cos - eccentricity;
+    (
+        major.0 * cos + minor.0 * sin,
+        major.1 * cos + minor.1 * sin,
+        major.2 * cos + minor.2 * sin,
+    )
+}
+
+/// The orbit of a galaxy around the universe's centre, which lies at a focus of the ellipse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GalaxyOrbit {
+    pub semi_major_axis: f64,        // Half the extent of the ellipse along its first axis, in universe units
+    pub semi_minor_axis: f64,        // Half the extent along its second axis; may exceed the first
+    pub period: f64,                 // Orbital period in simulation time units
+    pub inclination: f64,            // Radians
+    pub ascending_node: f64,         // Longitude of the ascending node in radians
+    pub argument_of_periapsis: f64,  // Angle from the ascending node to the first axis, in radians
+    pub time_offset: f64,            // Time already travelled along the orbit at time zero
+}
+
+impl GalaxyOrbit {
+    /// Returns the eccentricity of the ellipse.
+    pub fn eccentricity(&self) -> f64 {
+        let (major, minor) = (self.semi_major_axis.max(self.semi_minor_axis), self.semi_major_axis.min(self.semi_minor_axis));
+        (1.0 - (minor / major).powi(2)).sqrt()
+    }
+}
+
+/// A galaxy of the universe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Galaxy {
     guid: Uuid, // Unique identifier for the galaxy
     position: (f64, f64, f64), // Current position in 3D space
-    velocity: (f64, f64, f64), // Velocity in 3D space (not used here)
+    structure: GalaxyStructure, // Distribution of the galaxy's stars
+    star_count: u64, // Number of stars
+    color_index: f64, // Integrated B−V color index
     a: f64, // Semi-major axis of the orbit
     b: f64, // Semi-minor axis of the orbit
     t: f64, // Orbital period
     inclination: f64, // Inclination angle of the orbit
     ascending_node: f64, // Longitude of the ascending node
+    argument_of_periapsis: f64, // Angle from the ascending node to the `a` axis
     time_offset: f64, // Initial time offset for orbit calculation
 }
 
-// Function to generate a deterministic GUID (UUID) from a seed value
-fn generate_guid_from_seed(seed: u64) -> Uuid {
-    let mut hasher = DefaultHasher::new();
-    seed.hash(&mut hasher);
-    let guid_bytes = hasher.finish().to_ne_bytes();
-    
-    // Ensure guid_bytes is extended to 16 bytes if needed
-    let mut full_bytes = [0; 16];
-    full_bytes[..guid_bytes.len()].copy_from_slice(&guid_bytes);
-    
-    Uuid::from_bytes(full_bytes)
-}
+impl Galaxy {
+    /// Returns the galaxy's GUID.
+    pub fn guid(&self) -> Uuid {
+        self.guid
+    }
 
-// Generate a top-level universe seed (GUID)
-fn generate_universe_seed() -> Uuid {
-    Uuid::new_v4()
+    /// Returns the galaxy's current position.
+    pub fn position(&self) -> (f64, f64, f64) {
+        self.position
+    }
+
+    /// Returns the galaxy's orbit.
+    pub fn orbit(&self) -> GalaxyOrbit {
+        GalaxyOrbit {
+            semi_major_axis: self.a,
+            semi_minor_axis: self.b,
+            period: self.t,
+            inclination: self.inclination,
+            ascending_node: self.ascending_node,
+            argument_of_periapsis: self.argument_of_periapsis,
+            time_offset: self.time_offset,
+        }
+    }
+
+    // The orbit's axes in space
+    fn orbit_axes(&self) -> OrbitAxes {
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        let (sin_o, cos_o) = self.ascending_node.sin_cos();
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        // The ellipse's `a` and `b` axes rotated by the argument of periapsis, inclination and
+        // ascending node
+        let u = (cos_o * cos_w - sin_o * sin_w * cos_i, sin_o * cos_w + cos_o * sin_w * cos_i, sin_w * sin_i);
+        let v = (-cos_o * sin_w - sin_o * cos_w * cos_i, -sin_o * sin_w + cos_o * cos_w * cos_i, cos_w * sin_i);
+        let p = scale(u, self.a);
+        let q = scale(v, self.b);
+        let (major, minor) = (self.a.max(self.b), self.a.min(self.b));
+        let eccentricity = if major > 0.0 { (1.0 - (minor / major).powi(2)).sqrt() } else { 0.0 };
+        let (major, minor, shift) = if self.a >= self.b {
+            (p, q, 0.0)
+        } else {
+            // p cos θ + q sin θ = q cos(θ - π/2) - p sin(θ - π/2)
+            (q, (-p.0, -p.1, -p.2), -PI / 2.0)
+        };
+        let mean_anomaly = 2.0 * PI * self.time_offset / self.t + shift;
+        OrbitAxes {
+            major,
+            minor,
+            shift,
+            eccentricity,
+            lag: eccentric_anomaly(mean_anomaly, eccentricity) - mean_anomaly,
+        }
+    }
+
+    /// Moves the galaxy to its position on its orbit at a time, with Keplerian motion.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Simulation time in the same units as the orbital period.
+    pub fn update_position(&mut self, time: f64) {
+        self.update_position_with(time, OrbitModel::Kepler);
+    }
+
+    /// Moves the galaxy to its position on its orbit at a time, with the given orbit model.
+    pub fn update_position_with(&mut self, time: f64, model: OrbitModel) {
+        let axes = self.orbit_axes();
+        let angle = 2.0 * PI * (time + self.time_offset) / self.t + axes.shift;
+        self.position = orbit_position(axes.major, axes.minor, axes.eccentricity, angle, axes.lag, model);
+    }
+
+    /// Returns the galaxy's class.
+    pub fn class(&self) -> GalaxyClass {
+        self.structure.galaxy_class
+    }
+
+    /// Returns the number of stars in the galaxy, and so the number of star system indices.
+    pub fn star_count(&self) -> u64 {
+        self.star_count
+    }
+
+    /// Returns the galaxy's integrated B−V color index; higher is redder.
+    pub fn color_index(&self) -> f64 {
+        self.color_index
+    }
+
+    /// Returns the galaxy's apparent color, from blue-white for young irregulars to yellow-orange
+    /// for old ellipticals.
+    ///
+    /// # Returns
+    ///
+    /// An RGB color.
+    pub fn color(&self) -> [u8; 3] {
+        const STOPS: [(f64, [f64; 3]); 4] = [
+            (0.3, [190.0, 205.0, 255.0]),
+            (0.6, [245.0, 240.0, 235.0]),
+            (0.8, [255.0, 225.0, 180.0]),
+            (1.0, [255.0, 200.0, 140.0]),
+        ];
+        gradient_color(&STOPS, self.color_index)
+    }
+
+    /// Returns the distribution of the galaxy's stars.
+    pub fn structure(&self) -> &GalaxyStructure {
+        &self.structure
+    }
+
+    /// Returns the position of a star system within the galaxy.
+    ///
+    /// The position is drawn from the galaxy's structure with a seed derived from the system's
+    /// GUID, so it agrees with `StarSystem::generate(galaxy.guid(), index)`.
+    ///
+    /// # Returns
+    ///
+    /// The position relative to the galaxy's centre in kiloparsecs.
+    pub fn system_position(&self, index: u64) -> (f64, f64, f64) {
+        let system = derive_guid(self.guid, SYSTEM_DISCRIMINANT, index);
+        let mut rng = StdRng::seed_from_u64(mix64(guid_seed(system) ^ POSITION_SALT));
+        self.structure.sample(&mut rng)
+    }
+
+    /// Builds an octree over the positions of the galaxy's first `count` star systems, for star
+    /// maps and for picking which systems to generate in detail.
+    ///
+    /// # Returns
+    ///
+    /// An octree in kiloparsecs whose values are the system indices.
+    pub fn system_octree(&self, count: u64) -> Octree<u64> {
+        Octree::new((0..count).into_par_iter().map(|index| (self.system_position(index), index)).collect::<Vec<_>>())
+    }
+
+    /// Returns the orbit of a star system around the galaxy's centre.
+    ///
+    /// The orbit is circular, passes through `system_position(index)` at the epoch and follows a
+    /// flat rotation curve, so every system moves at the same speed. It is expressed in
+    /// astronomical units and Earth days, to be the root orbit of an `OrbitTree`.
+    pub fn system_orbit(&self, index: u64) -> OrbitalElements {
+        let p = scale(self.system_position(index), AU_PER_KPC);
+        let radius = length(p).max(f64::MIN_POSITIVE);
+        // The orbital plane contains the position and the direction of disk rotation
+        let normal = normalize(cross(p, cross((0.0, 0.0, 1.0), p)));
+        let normal = if normal.0.is_finite() { normal } else { (0.0, 0.0, 1.0) };
+        let ascending_node = normal.0.atan2(-normal.1);
+        let node = (ascending_node.cos(), ascending_node.sin(), 0.0);
+        OrbitalElements {
+            semi_major_axis: radius,
+            eccentricity: 0.0,
+            inclination: normal.2.clamp(-1.0, 1.0).acos(),
+            ascending_node,
+            argument_of_periapsis: 0.0,
+            mean_anomaly_at_epoch: dot(cross(node, p), normal).atan2(dot(node, p)),
+            period: TAU * radius * KM_PER_AU / ROTATION_SPEED / 86_400.0,
+        }
+    }
 }
 
-// Generate a GUID for a galaxy based on its coordinates and universe seed
-fn generate_galaxy_guid(universe_seed: Uuid, coords: (f64, f64, f64)) -> Uuid {
-    let mut hasher = DefaultHasher::new();
-    universe_seed.hash(&mut hasher);
-    coords.0.to_bits().hash(&mut hasher);
-    coords.1.to_bits().hash(&mut hasher);
-    coords.2.to_bits().hash(&mut hasher);
-    let guid_bytes = hasher.finish().to_ne_bytes();
-    
-    // Ensure guid_bytes is extended to 16 bytes if needed
-    let mut full_bytes = [0; 16];
-    full_bytes[..guid_bytes.len()].copy_from_slice(&guid_bytes);
-    
-    Uuid::from_bytes(full_bytes)
+// Astronomical units per kiloparsec
+const AU_PER_KPC: f64 = 206_264_806.0;
+// Kilometres per astronomical unit
+const KM_PER_AU: f64 = 149_597_870.7;
+// Circular speed of disk stars in km/s, flat across the disk
+const ROTATION_SPEED: f64 = 220.0;
+
+/// Generates a random top-level universe seed.
+pub fn generate_universe_seed() -> Uuid {
+    Uuid::new_v4()
 }
 
 // Function to generate galaxy parameters from GUID
-fn generate_galaxy_parameters(guid: Uuid) -> (f64, f64, f64, f64, f64, f64) {
+fn generate_galaxy_parameters(guid: Uuid, ranges: &GalaxyOrbitRanges) -> (f64, f64, f64, f64, f64, f64) {
     let seed: [u8; 16] = *guid.as_bytes();
     let mut seed_32: [u8; 32] = [0; 32];
     seed_32[..16].copy_from_slice(&seed);
     let mut rng: StdRng = SeedableRng::from_seed(seed_32);
     
-    let a = rng.gen_range(10.0..50.0); // Semi-major axis
-    let b = rng.gen_range(5.0..25.0); // Semi-minor axis
-    let t = rng.gen_range(100.0..500.0); // Orbital period
+    let a = rng.gen_range(ranges.semi_major_axis.clone()); // Semi-major axis
+    let b = rng.gen_range(ranges.semi_minor_axis.clone()); // Semi-minor axis
+    let t = rng.gen_range(ranges.period.clone()); // Orbital period
     let inclination = rng.gen_range(0.0..PI); // Inclination angle
     let ascending_node = rng.gen_range(0.0..(2.0 * PI)); // Longitude of ascending node
     let time_offset = rng.gen_range(0.0..t); // Initial time offset for orbit calculation
@@ -73,100 +536,1121 @@ fn generate_galaxy_parameters(guid: Uuid) -> (f64, f64, f64, f64, f64, f64) {
     (a, b, t, inclination, ascending_node, time_offset)
 }
 
-// Function to update position based on elliptical orbit
-fn update_position(galaxy: &mut Galaxy, time: f64) {
-    // Calculate the current angle in the orbit based on time
-    let theta: f64 = 2.0 * PI * (time + galaxy.time_offset) / galaxy.t;
-    
-    // Calculate x and y coordinates in the orbital plane
-    let x: f64 = galaxy.a * theta.cos();
-    let y: f64 = galaxy.b * theta.sin();
-    
-    // Rotate the coordinates by the inclination and ascending node
-    let cos_i: f64 = galaxy.inclination.cos();
-    let sin_i: f64 = galaxy.inclination.sin();
-    let cos_o: f64 = galaxy.ascending_node.cos();
-    let sin_o: f64 = galaxy.ascending_node.sin();
+// Orbital elements around the origin, with `a` the semi-major axis, that put a galaxy at
+// `position` at time zero. The drawn axes give the ellipse's shape, the time offset how far along
+// it the galaxy is, and `turn` the orientation of the orbital plane about the position; the ellipse
+// is then scaled and rotated to pass through the position.
+//
+// Returns `(a, b, inclination, ascending_node, argument_of_periapsis)`.
+fn orbit_through(position: (f64, f64, f64), drawn: (f64, f64), period: f64, time_offset: f64, turn: f64) -> (f64, f64, f64, f64, f64) {
+    let (major, minor) = (drawn.0.max(drawn.1), drawn.0.min(drawn.1));
+    let ratio = if major > 0.0 { minor / major } else { 1.0 };
+    let eccentricity = (1.0 - ratio * ratio).sqrt();
+    let (sin_e, cos_e) = eccentric_anomaly(2.0 * PI * time_offset / period, eccentricity).sin_cos();
+    let true_anomaly = (ratio * sin_e).atan2(cos_e - eccentricity);
+    let distance = length(position);
+    let a = distance / (1.0 - eccentricity * cos_e);
 
-    let x_rot: f64 = x * cos_o - y * cos_i * sin_o;
-    let y_rot: f64 = x * sin_o + y * cos_i * cos_o;
-    let z_rot: f64 = y * sin_i;
+    // The orbit normal is perpendicular to the position, turned about it by `turn`
+    let direction = if distance > 0.0 { scale(position, 1.0 / distance) } else { (1.0, 0.0, 0.0) };
+    let side = cross(direction, (0.0, 0.0, 1.0));
+    let side = if length(side) > 1e-9 { normalize(side) } else { (0.0, 1.0, 0.0) };
+    let up = cross(direction, side);
+    let normal = add(scale(side, turn.cos()), scale(up, turn.sin()));
+    // The periapsis lies the true anomaly back from the position, in the orbital plane
+    let (sin_v, cos_v) = true_anomaly.sin_cos();
+    let periapsis = sub(scale(direction, cos_v), scale(cross(normal, direction), sin_v));
 
-    // Update the galaxy's position with the rotated coordinates
-    galaxy.position = (x_rot, y_rot, z_rot);
+    let inclination = normal.2.clamp(-1.0, 1.0).acos();
+    let ascending_node = normal.0.atan2(-normal.1);
+    let node = (ascending_node.cos(), ascending_node.sin(), 0.0);
+    let argument_of_periapsis = dot(periapsis, cross(normal, node)).atan2(dot(periapsis, node));
+    (a, a * ratio, inclination, ascending_node, argument_of_periapsis)
 }
 
-// Function to generate galaxies using the universe seed
-fn generate_galaxies(universe_seed: Uuid) -> Vec<Galaxy> {
-    let seed: [u8; 16] = *universe_seed.as_bytes();
-    let mut seed_32: [u8; 32] = [0; 32];
-    seed_32[..16].copy_from_slice(&seed);
-    let mut rng: StdRng = SeedableRng::from_seed(seed_32);
+// Generates a galaxy from its GUID and initial position
+fn generate_galaxy(guid: Uuid, position: (f64, f64, f64), ranges: &GalaxyOrbitRanges) -> Galaxy {
+    // Generate orbital parameters; the drawn inclination is superseded by the plane through the
+    // position, so only the other draws shape the orbit
+    let (a, b, t, _, turn, time_offset) = generate_galaxy_parameters(guid, ranges);
+    let (a, b, inclination, ascending_node, argument_of_periapsis) = orbit_through(position, (a, b), t, time_offset, turn);
+    let structure = GalaxyStructure::generate(guid);
+    let class = structure.galaxy_class;
+    let mut rng = StdRng::seed_from_u64(mix64(guid_seed(guid) ^ CLASS_PROPERTIES_SALT));
+    // Star counts span orders of magnitude, so draw them log-uniformly
+    let counts = class.star_count_range();
+    let star_count = (rng.gen_range((counts.start as f64).ln()..(counts.end as f64).ln())).exp() as u64;
 
-    // Generate the number of galaxies
-    let num_galaxies: i32 = rng.gen_range(1000000..5000000); // Generate between 1 and 5 million galaxies
-
-    // Generate galaxies
-    (0..num_galaxies).map(|_| {
-        // Generate initial position
-        let position = (
-            rng.gen_range(-100.0..100.0),
-            rng.gen_range(-100.0..100.0),
-            rng.gen_range(-100.0..100.0),
-        );
-
-        // Generate GUID based on position and universe seed
-        let guid = generate_galaxy_guid(universe_seed, position);
-
-        // Generate orbital parameters
-        let (a, b, t, inclination, ascending_node, time_offset) = generate_galaxy_parameters(guid);
-
-        Galaxy {
-            guid,
-            position,
-            velocity: (0.0, 0.0, 0.0), // Initially, velocity is not used
-            a,
-            b,
-            t,
-            inclination,
-            ascending_node,
-            time_offset,
+    Galaxy {
+        guid,
+        position,
+        structure,
+        star_count,
+        color_index: rng.gen_range(class.color_index_range()),
+        a,
+        b,
+        t,
+        inclination,
+        ascending_node,
+        argument_of_periapsis,
+        time_offset,
+    }
+}
+
+// Orbits of the loaded galaxies as a struct of arrays.
+//
+// Each orbit is stored as two rotated semi-axis vectors, so that the position at angle θ is
+// `p cos θ + q sin θ`; a step then costs one `sin_cos` per galaxy and streams through contiguous
+// arrays.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct GalaxyOrbits {
+    guid: Vec<Uuid>,
+    sector: Vec<(i32, i32, i32)>,
+    x: Vec<f64>, // Current position
+    y: Vec<f64>,
+    z: Vec<f64>,
+    px: Vec<f64>, // Semi-major axis vector in space
+    py: Vec<f64>,
+    pz: Vec<f64>,
+    qx: Vec<f64>, // Semi-minor axis vector in space
+    qy: Vec<f64>,
+    qz: Vec<f64>,
+    eccentricity: Vec<f64>,
+    rate: Vec<f64>,  // Angular rate, radians per unit time
+    phase: Vec<f64>, // Mean anomaly at time zero
+    lag: Vec<f64>,   // Eccentric minus mean anomaly at time zero
+    model: Vec<OrbitModel>,
+}
+
+impl GalaxyOrbits {
+    fn push(&mut self, galaxy: &Galaxy, sector: (i32, i32, i32), model: OrbitModel) {
+        let OrbitAxes {
+            major,
+            minor,
+            shift,
+            eccentricity,
+            lag,
+        } = galaxy.orbit_axes();
+        self.guid.push(galaxy.guid);
+        self.sector.push(sector);
+        self.x.push(galaxy.position.0);
+        self.y.push(galaxy.position.1);
+        self.z.push(galaxy.position.2);
+        self.px.push(major.0);
+        self.py.push(major.1);
+        self.pz.push(major.2);
+        self.qx.push(minor.0);
+        self.qy.push(minor.1);
+        self.qz.push(minor.2);
+        self.eccentricity.push(eccentricity);
+        self.rate.push(2.0 * PI / galaxy.t);
+        self.phase.push(2.0 * PI * galaxy.time_offset / galaxy.t + shift);
+        self.lag.push(lag);
+        self.model.push(model);
+    }
+
+    // Keeps only the galaxies for which `keep` returns true
+    fn retain(&mut self, keep: impl Fn(usize) -> bool) {
+        let mask: Vec<bool> = (0..self.guid.len()).map(keep).collect();
+        fn apply<T>(values: &mut Vec<T>, mask: &[bool]) {
+            let mut index = 0;
+            values.retain(|_| {
+                index += 1;
+                mask[index - 1]
+            });
+        }
+        apply(&mut self.guid, &mask);
+        apply(&mut self.sector, &mask);
+        apply(&mut self.model, &mask);
+        for values in [
+            &mut self.x, &mut self.y, &mut self.z, &mut self.px, &mut self.py, &mut self.pz, &mut self.qx, &mut self.qy,
+            &mut self.qz, &mut self.eccentricity, &mut self.rate, &mut self.phase, &mut self.lag,
+        ] {
+            apply(values, &mask);
+        }
+    }
+
+    // Positions of every galaxy at a time, as x, y and z arrays
+    fn positions_at(&self, time: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let mut positions = self.clone_positions();
+        positions.update(time);
+        (positions.x, positions.y, positions.z)
+    }
+
+    // A copy holding only what `update` needs, with the current positions
+    fn clone_positions(&self) -> GalaxyOrbits {
+        GalaxyOrbits {
+            x: self.x.clone(),
+            y: self.y.clone(),
+            z: self.z.clone(),
+            px: self.px.clone(),
+            py: self.py.clone(),
+            pz: self.pz.clone(),
+            qx: self.qx.clone(),
+            qy: self.qy.clone(),
+            qz: self.qz.clone(),
+            eccentricity: self.eccentricity.clone(),
+            rate: self.rate.clone(),
+            phase: self.phase.clone(),
+            lag: self.lag.clone(),
+            model: self.model.clone(),
+            ..GalaxyOrbits::default()
         }
-    }).collect()
+    }
+
+    fn update(&mut self, time: f64) {
+        let GalaxyOrbits {
+            x,
+            y,
+            z,
+            px,
+            py,
+            pz,
+            qx,
+            qy,
+            qz,
+            eccentricity,
+            rate,
+            phase,
+            lag,
+            model,
+            ..
+        } = self;
+        x.par_iter_mut()
+            .zip(y.par_iter_mut())
+            .zip(z.par_iter_mut())
+            .enumerate()
+            .with_min_len(4096)
+            .for_each(|(i, ((x, y), z))| {
+                let major = (px[i], py[i], pz[i]);
+                let minor = (qx[i], qy[i], qz[i]);
+                (*x, *y, *z) = orbit_position(major, minor, eccentricity[i], rate[i] * time + phase[i], lag[i], model[i]);
+            });
+    }
 }
 
-pub fn simulate() {
-    // Generate the universe seed
-    let start = Instant::now();
-    let universe_seed = generate_guid_from_seed(123);
-    let duration = start.elapsed();
-    println!("Universe Seed: {}", universe_seed.to_string());
-    println!("Generating universe seed took: {:?}", duration);
+/// Ranges from which galaxy orbits are drawn, in universe units and simulation time units.
+///
+/// Every orbit passes through its galaxy's generated position at time zero, with the universe's
+/// centre at a focus, so the drawn axes set only the orbit's shape: the ellipse is scaled until it
+/// reaches the position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GalaxyOrbitRanges {
+    #[serde(with = "range_pair")]
+    pub semi_major_axis: Range<f64>,
+    #[serde(with = "range_pair")]
+    pub semi_minor_axis: Range<f64>,
+    #[serde(with = "range_pair")]
+    pub period: Range<f64>,
+}
+
+// Serializes a range as a `[start, end]` pair rather than serde's `{ "start", "end" }` struct
+mod range_pair {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::ops::Range;
+
+    pub fn serialize<T: Serialize, S: Serializer>(range: &Range<T>, serializer: S) -> Result<S::Ok, S::Error> {
+        [&range.start, &range.end].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Range<T>, D::Error> {
+        let (start, end) = <(T, T)>::deserialize(deserializer)?;
+        Ok(start..end)
+    }
+}
+
+/// Semi-major axes of 10 to 50 units, semi-minor axes of 5 to 25 units and periods of 100 to 500
+/// time units.
+impl Default for GalaxyOrbitRanges {
+    fn default() -> Self {
+        GalaxyOrbitRanges {
+            semi_major_axis: 10.0..50.0,
+            semi_minor_axis: 5.0..25.0,
+            period: 100.0..500.0,
+        }
+    }
+}
+
+/// The most sectors a universe may have along each axis, so every sector index fits the 21 bits per
+/// axis of `Universe::sector_guid`.
+pub const MAX_SECTORS_PER_AXIS: i32 = 1 << 20;
+
+/// Error returned when a universe configuration cannot be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniverseConfigError {
+    /// The file could not be read.
+    Io(String),
+    /// The file is not valid JSON, or a field has the wrong type.
+    Json(String),
+    /// A field has an invalid value.
+    InvalidField(String),
+}
+
+impl std::fmt::Display for UniverseConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UniverseConfigError::Io(message) => write!(f, "cannot read universe config: {}", message),
+            UniverseConfigError::Json(message) => write!(f, "universe config is not valid JSON: {}", message),
+            UniverseConfigError::InvalidField(field) => write!(f, "universe config field `{}` is invalid", field),
+        }
+    }
+}
+
+impl std::error::Error for UniverseConfigError {}
+
+/// The parameters a universe is generated from, other than its seed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UniverseConfig {
+    #[serde(rename = "galaxy_count", with = "range_pair")]
+    pub galaxy_count_range: Range<u64>, // Range the expected galaxy count is drawn from
+    pub extent: f64,                    // Half the edge length of the universe cube
+    pub sector_size: f64,               // Edge length of a sector
+    pub orbit: GalaxyOrbitRanges,
+}
+
+/// One to five million galaxies in a cube 200 units across, split into sectors 10 units across,
+/// with the default orbit ranges.
+impl Default for UniverseConfig {
+    fn default() -> Self {
+        UniverseConfig {
+            galaxy_count_range: 1_000_000..5_000_000,
+            extent: 100.0,
+            sector_size: 10.0,
+            orbit: GalaxyOrbitRanges::default(),
+        }
+    }
+}
+
+impl UniverseConfig {
+    /// Checks that every range is non-empty, every size positive, at least one galaxy expected and
+    /// the universe at most `MAX_SECTORS_PER_AXIS` sectors across.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())`, or `UniverseConfigError::InvalidField` naming the first invalid field.
+    pub fn validate(&self) -> Result<(), UniverseConfigError> {
+        let invalid = |field: &str| Err(UniverseConfigError::InvalidField(field.to_string()));
+        let valid_range = |range: &Range<f64>| range.start.is_finite() && range.end.is_finite() && range.start < range.end;
+        if self.galaxy_count_range.is_empty() || self.galaxy_count_range.start == 0 {
+            return invalid("galaxy_count");
+        }
+        if !(self.extent.is_finite() && self.extent > 0.0) {
+            return invalid("extent");
+        }
+        if !(self.sector_size.is_finite() && self.sector_size > 0.0 && self.sector_size <= 2.0 * self.extent) {
+            return invalid("sector_size");
+        }
+        if (2.0 * self.extent / self.sector_size).round() > MAX_SECTORS_PER_AXIS as f64 {
+            return invalid("sector_size");
+        }
+        if !(valid_range(&self.orbit.semi_major_axis) && self.orbit.semi_major_axis.start >= 0.0) {
+            return invalid("orbit.semi_major_axis");
+        }
+        if !(valid_range(&self.orbit.semi_minor_axis) && self.orbit.semi_minor_axis.start >= 0.0) {
+            return invalid("orbit.semi_minor_axis");
+        }
+        if !(valid_range(&self.orbit.period) && self.orbit.period.start > 0.0) {
+            return invalid("orbit.period");
+        }
+        Ok(())
+    }
+
+    /// Describes the configuration as JSON.
+    ///
+    /// The result is an object with `galaxy_count` as a `[min, max)` pair, `extent`, `sector_size`
+    /// and an `orbit` object with `semi_major_axis`, `semi_minor_axis` and `period` pairs.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    /// Reads a configuration from JSON in the format of `to_json`.
+    ///
+    /// Missing fields keep their default values, so a file only needs the fields it changes.
+    pub fn from_json(value: &Value) -> Result<UniverseConfig, UniverseConfigError> {
+        let config = UniverseConfig::deserialize(value).map_err(|error| UniverseConfigError::Json(error.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads a configuration from a JSON file; see `from_json`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<UniverseConfig, UniverseConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|error| UniverseConfigError::Io(error.to_string()))?;
+        let value: Value = serde_json::from_str(&text).map_err(|error| UniverseConfigError::Json(error.to_string()))?;
+        UniverseConfig::from_json(&value)
+    }
+}
+
+/// A universe of galaxies, generated lazily one cubic sector at a time.
+///
+/// The universe spans a cube of `2 * extent()` units on each side centred on the origin, divided
+/// into sectors of `sector_size()` units. A sector's galaxies depend only on the universe seed
+/// and the sector's index, so any sector can be generated on its own and regenerated identically
+/// later; memory scales with the region being observed rather than with the whole universe.
+///
+/// Sectors can also be loaded into the universe, which then keeps their galaxies' orbits and
+/// advances them with `step`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Universe {
+    seed: Uuid,
+    config: UniverseConfig,
+    galaxy_count: u64,       // Expected number of galaxies in the whole universe
+    sectors_per_axis: i32,
+    time: f64,               // Simulation time of the loaded galaxies
+    orbit_model: OrbitModel, // Model given to newly loaded galaxies
+    loaded_sectors: Vec<(i32, i32, i32)>,
+    orbits: GalaxyOrbits,
+    loaded_index: HashMap<Uuid, usize>, // Position of each loaded galaxy in `orbits`
+}
+
+impl Universe {
+    /// Creates a universe from its seed with the default configuration.
+    ///
+    /// Only the expected galaxy count (between 1 and 5 million) is drawn here; no galaxies are
+    /// generated until a sector is requested.
+    pub fn new(seed: Uuid) -> Universe {
+        Universe::with_config(seed, UniverseConfig::default())
+    }
+
+    /// Creates a universe from its seed and a configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration fails `UniverseConfig::validate`.
+    pub fn with_config(seed: Uuid, config: UniverseConfig) -> Universe {
+        if let Err(error) = config.validate() {
+            panic!("{}", error);
+        }
+        let mut rng = StdRng::seed_from_u64(guid_seed(seed));
+        Universe {
+            seed,
+            galaxy_count: rng.gen_range(config.galaxy_count_range.clone()),
+            sectors_per_axis: (2.0 * config.extent / config.sector_size).round().max(1.0) as i32,
+            config,
+            time: 0.0,
+            orbit_model: OrbitModel::Kepler,
+            loaded_sectors: Vec::new(),
+            orbits: GalaxyOrbits::default(),
+            loaded_index: HashMap::new(),
+        }
+    }
+
+    /// Returns the universe seed.
+    pub fn seed(&self) -> Uuid {
+        self.seed
+    }
+
+    /// Returns the configuration the universe was generated from.
+    pub fn config(&self) -> &UniverseConfig {
+        &self.config
+    }
+
+    /// Returns the expected number of galaxies in the whole universe.
+    pub fn galaxy_count(&self) -> u64 {
+        self.galaxy_count
+    }
+
+    /// Returns half the edge length of the universe cube.
+    pub fn extent(&self) -> f64 {
+        self.config.extent
+    }
+
+    /// Returns the edge length of a sector.
+    pub fn sector_size(&self) -> f64 {
+        self.config.sector_size
+    }
+
+    /// Returns the universe's sector grid, for precise `SectorPosition` coordinates.
+    pub fn grid(&self) -> SectorGrid {
+        SectorGrid::new(self.config.sector_size)
+    }
+
+    /// Returns the range of valid sector indices along each axis.
+    pub fn sector_range(&self) -> Range<i32> {
+        -self.sectors_per_axis / 2..self.sectors_per_axis - self.sectors_per_axis / 2
+    }
+
+    /// Returns the index of the sector containing a position.
+    ///
+    /// The result may lie outside `sector_range` for positions outside the universe.
+    pub fn sector_of(&self, position: (f64, f64, f64)) -> (i32, i32, i32) {
+        let size = self.config.sector_size;
+        let index = |value: f64| (value / size).floor() as i32;
+        (index(position.0), index(position.1), index(position.2))
+    }
+
+    /// Returns the indices of all sectors that intersect a sphere, clamped to the universe.
+    pub fn sectors_within(&self, centre: (f64, f64, f64), radius: f64) -> Vec<(i32, i32, i32)> {
+        let range = self.sector_range();
+        let low = self.sector_of((centre.0 - radius, centre.1 - radius, centre.2 - radius));
+        let high = self.sector_of((centre.0 + radius, centre.1 + radius, centre.2 + radius));
+        let axis = |low: i32, high: i32| low.max(range.start)..=high.min(range.end - 1);
+        let size = self.config.sector_size;
+        let mut sectors = Vec::new();
+        for ix in axis(low.0, high.0) {
+            for iy in axis(low.1, high.1) {
+                for iz in axis(low.2, high.2) {
+                    // Distance from the centre to the nearest point of the sector's box
+                    let gap = |c: f64, i: i32| {
+                        let start = i as f64 * size;
+                        (start - c).max(c - start - size).max(0.0)
+                    };
+                    let (dx, dy, dz) = (gap(centre.0, ix), gap(centre.1, iy), gap(centre.2, iz));
+                    if dx * dx + dy * dy + dz * dz <= radius * radius {
+                        sectors.push((ix, iy, iz));
+                    }
+                }
+            }
+        }
+        sectors
+    }
+
+    /// Returns the GUID of a sector.
+    pub fn sector_guid(&self, ix: i32, iy: i32, iz: i32) -> Uuid {
+        // 21 bits per axis hold every index of the at most `MAX_SECTORS_PER_AXIS` sectors per axis
+        let pack = |i: i32| (i as u32 as u64) & 0x1F_FFFF;
+        derive_guid(self.seed, SECTOR_DISCRIMINANT, pack(ix) << 42 | pack(iy) << 21 | pack(iz))
+    }
+
+    /// Generates the galaxies of one sector.
+    ///
+    /// # Arguments
+    ///
+    /// * `ix`, `iy`, `iz` - The sector index along each axis, see `sector_range`.
+    ///
+    /// # Returns
+    ///
+    /// The galaxies whose initial positions lie in the sector; empty for sectors outside the
+    /// universe.
+    pub fn sector(&self, ix: i32, iy: i32, iz: i32) -> Vec<Galaxy> {
+        let range = self.sector_range();
+        if !(range.contains(&ix) && range.contains(&iy) && range.contains(&iz)) {
+            return Vec::new();
+        }
+        let sector = self.sector_guid(ix, iy, iz);
+        let mut rng = StdRng::seed_from_u64(guid_seed(sector));
+        let mean = self.galaxy_count as f64 / (self.sectors_per_axis as f64).powi(3);
+        let count = rng.gen_range(mean * 0.5..mean * 1.5).round() as u64;
+        let size = self.config.sector_size;
+        let origin = (ix as f64 * size, iy as f64 * size, iz as f64 * size);
+        (0..count)
+            .map(|index| {
+                let position = (
+                    origin.0 + rng.gen_range(0.0..size),
+                    origin.1 + rng.gen_range(0.0..size),
+                    origin.2 + rng.gen_range(0.0..size),
+                );
+                generate_galaxy(derive_guid(sector, GALAXY_DISCRIMINANT, index), position, &self.config.orbit)
+            })
+            .collect()
+    }
+
+    /// Returns the indices of every sector of the universe.
+    pub fn all_sectors(&self) -> Vec<(i32, i32, i32)> {
+        let range = self.sector_range();
+        let mut sectors = Vec::new();
+        for ix in range.clone() {
+            for iy in range.clone() {
+                for iz in range.clone() {
+                    sectors.push((ix, iy, iz));
+                }
+            }
+        }
+        sectors
+    }
+
+    /// Generates the galaxies of every sector.
+    ///
+    /// This materializes the whole universe, millions of galaxies; prefer `sector` for anything
+    /// that only needs a region.
+    pub fn all_galaxies(&self) -> Vec<Galaxy> {
+        self.all_sectors().into_par_iter().flat_map_iter(|(ix, iy, iz)| self.sector(ix, iy, iz)).collect()
+    }
+
+    /// Loads sectors into the simulation, skipping any that are already loaded.
+    ///
+    /// Galaxies sit at their generated positions, inside their sectors, at time zero. Newly loaded
+    /// galaxies are moved to their positions at the current simulation time, so sectors loaded
+    /// later may hold galaxies that have since left them; use `sector_of` on a position for the
+    /// sector a galaxy is in now.
+    pub fn load_sectors(&mut self, sectors: &[(i32, i32, i32)]) {
+        let loaded: HashSet<(i32, i32, i32)> = self.loaded_sectors.iter().copied().collect();
+        let new: Vec<(i32, i32, i32)> = sectors
+            .iter()
+            .copied()
+            .filter(|sector| !loaded.contains(sector))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        self.load_in_order(new);
+    }
+
+    // Loads sectors that are not yet loaded, keeping the given order
+    pub(crate) fn load_in_order(&mut self, new: Vec<(i32, i32, i32)>) {
+        let galaxies: Vec<Vec<Galaxy>> = new.par_iter().map(|&(ix, iy, iz)| self.sector(ix, iy, iz)).collect();
+        for (sector, galaxies) in new.iter().zip(&galaxies) {
+            for galaxy in galaxies {
+                self.orbits.push(galaxy, *sector, self.orbit_model);
+            }
+        }
+        self.loaded_sectors.extend(new);
+        self.orbits.update(self.time);
+        self.reindex();
+    }
+
+    // Rebuilds the GUID lookup after galaxies are loaded or unloaded
+    fn reindex(&mut self) {
+        self.loaded_index = self.orbits.guid.iter().enumerate().map(|(i, guid)| (*guid, i)).collect();
+    }
+
+    /// Sets the orbit model given to galaxies loaded from now on. The default is `Kepler`.
+    pub fn set_default_orbit_model(&mut self, model: OrbitModel) {
+        self.orbit_model = model;
+    }
+
+    /// Switches the galaxies of loaded sectors to an orbit model, e.g. `Approximate` for sectors far
+    /// from the camera, and moves them to their positions under that model.
+    pub fn set_orbit_model(&mut self, sectors: &[(i32, i32, i32)], model: OrbitModel) {
+        let sectors: HashSet<(i32, i32, i32)> = sectors.iter().copied().collect();
+        for (sector, current) in self.orbits.sector.iter().zip(self.orbits.model.iter_mut()) {
+            if sectors.contains(sector) {
+                *current = model;
+            }
+        }
+        self.orbits.update(self.time);
+    }
+
+    /// Loads every sector of the universe into the simulation.
+    pub fn load_all(&mut self) {
+        self.load_sectors(&self.all_sectors());
+    }
+
+    /// Drops sectors from the simulation.
+    pub fn unload_sectors(&mut self, sectors: &[(i32, i32, i32)]) {
+        let unloaded: HashSet<(i32, i32, i32)> = sectors.iter().copied().collect();
+        self.loaded_sectors.retain(|sector| !unloaded.contains(sector));
+        let keep: Vec<bool> = self.orbits.sector.iter().map(|sector| !unloaded.contains(sector)).collect();
+        self.orbits.retain(|i| keep[i]);
+        self.reindex();
+    }
 
+    /// Returns the loaded sectors in load order.
+    pub fn loaded_sectors(&self) -> &[(i32, i32, i32)] {
+        &self.loaded_sectors
+    }
+
+    /// Returns the number of loaded galaxies.
+    pub fn loaded_len(&self) -> usize {
+        self.orbits.guid.len()
+    }
+
+    /// Returns the GUIDs of the loaded galaxies.
+    pub fn loaded_guids(&self) -> &[Uuid] {
+        &self.orbits.guid
+    }
+
+    /// Returns the current positions of the loaded galaxies as separate x, y and z arrays, in the
+    /// same order as `loaded_guids`.
+    pub fn loaded_positions(&self) -> (&[f64], &[f64], &[f64]) {
+        (&self.orbits.x, &self.orbits.y, &self.orbits.z)
+    }
+
+    // The sector of every loaded galaxy, in the order of `loaded_guids`
+    fn loaded_galaxy_sectors(&self) -> Vec<(i32, i32, i32)> {
+        let (x, y, z) = self.loaded_positions();
+        (0..x.len()).into_par_iter().map(|i| self.sector_of((x[i], y[i], z[i]))).collect()
+    }
+
+    /// Returns `true` if a galaxy is loaded.
+    pub fn is_loaded(&self, guid: Uuid) -> bool {
+        self.loaded_index.contains_key(&guid)
+    }
+
+    /// Returns the current position of a loaded galaxy, or `None` if it is not loaded.
+    pub fn loaded_position(&self, guid: Uuid) -> Option<(f64, f64, f64)> {
+        let i = *self.loaded_index.get(&guid)?;
+        Some((self.orbits.x[i], self.orbits.y[i], self.orbits.z[i]))
+    }
+
+    /// Returns a loaded galaxy by GUID, at its current position.
+    ///
+    /// The galaxy is regenerated from its sector, so this costs a sector's worth of generation;
+    /// use `loaded_position` when only the position is needed.
+    ///
+    /// # Returns
+    ///
+    /// The galaxy, or `None` if it is not loaded. Galaxies outside the loaded sectors cannot be
+    /// found by GUID alone, since a GUID does not reveal its sector.
+    pub fn galaxy(&self, guid: Uuid) -> Option<Galaxy> {
+        let i = *self.loaded_index.get(&guid)?;
+        let (ix, iy, iz) = self.orbits.sector[i];
+        let mut galaxy = self.sector(ix, iy, iz).into_iter().find(|galaxy| galaxy.guid == guid)?;
+        galaxy.position = (self.orbits.x[i], self.orbits.y[i], self.orbits.z[i]);
+        Some(galaxy)
+    }
+
+    /// Iterates over the loaded galaxies at their current positions, one sector at a time.
+    ///
+    /// Galaxies are regenerated lazily, so only one sector's galaxies are held at once.
+    pub fn galaxies(&self) -> impl Iterator<Item = Galaxy> + '_ {
+        self.loaded_sectors.iter().flat_map(move |&(ix, iy, iz)| {
+            self.sector(ix, iy, iz).into_iter().map(move |mut galaxy| {
+                let i = self.loaded_index[&galaxy.guid];
+                galaxy.position = (self.orbits.x[i], self.orbits.y[i], self.orbits.z[i]);
+                galaxy
+            })
+        })
+    }
+
+    /// Builds an octree over the current positions of the loaded galaxies, keyed by GUID.
+    ///
+    /// The tree is a snapshot; rebuild it after stepping.
+    pub fn galaxy_octree(&self) -> Octree<Uuid> {
+        let (x, y, z) = self.loaded_positions();
+        Octree::new(self.orbits.guid.iter().enumerate().map(|(i, guid)| ((x[i], y[i], z[i]), *guid)))
+    }
+
+    /// Returns the simulation time.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Advances the loaded galaxies along their orbits.
+    ///
+    /// Positions are updated in parallel across all threads.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The time step, in the same units as the orbital periods.
+    pub fn step(&mut self, dt: f64) {
+        self.time += dt;
+        self.orbits.update(self.time);
+    }
+
+    // Sets the time and the loaded galaxies' orbit models and positions, in the order of
+    // `loaded_guids`, as saved by a snapshot
+    pub(crate) fn restore(&mut self, time: f64, models: &[OrbitModel], x: &[f64], y: &[f64], z: &[f64]) {
+        self.time = time;
+        self.orbits.model = models.to_vec();
+        self.orbits.x = x.to_vec();
+        self.orbits.y = y.to_vec();
+        self.orbits.z = z.to_vec();
+    }
+
+    /// Returns the orbit model given to newly loaded galaxies.
+    pub fn default_orbit_model(&self) -> OrbitModel {
+        self.orbit_model
+    }
+
+    /// Returns the orbit models of the loaded galaxies, in the order of `loaded_guids`.
+    pub fn loaded_orbit_models(&self) -> &[OrbitModel] {
+        &self.orbits.model
+    }
+
+    /// Computes the positions of the loaded galaxies at any time without changing the universe.
+    ///
+    /// Orbits are closed-form, so `time` may be far in the past or future at no extra cost.
+    ///
+    /// # Returns
+    ///
+    /// The x, y and z arrays, in the same order as `loaded_guids`.
+    pub fn positions_at(&self, time: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        self.orbits.positions_at(time)
+    }
+
+    /// Computes the position of one loaded galaxy at any time without changing the universe.
+    ///
+    /// # Returns
+    ///
+    /// The position, or `None` if the galaxy is not loaded.
+    pub fn galaxy_position_at(&self, guid: Uuid, time: f64) -> Option<(f64, f64, f64)> {
+        let i = *self.loaded_index.get(&guid)?;
+        let orbits = &self.orbits;
+        let major = (orbits.px[i], orbits.py[i], orbits.pz[i]);
+        let minor = (orbits.qx[i], orbits.qy[i], orbits.qz[i]);
+        Some(orbit_position(major, minor, orbits.eccentricity[i], orbits.rate[i] * time + orbits.phase[i], orbits.lag[i], orbits.model[i]))
+    }
+
+    /// Jumps the loaded galaxies to any time, past or future, in one update.
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+        self.orbits.update(self.time);
+    }
+}
+
+/// A snapshot of a universe simulation: everything needed to resume it, and the loaded galaxies'
+/// positions.
+///
+/// See `UniverseSimulation::snapshot` and `UniverseSimulation::resume`, and `to_bytes` for
+/// persisting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseState {
+    pub time: f64,                    // Seconds since the start of the simulation
+    pub time_scale: f64,              // Simulated seconds per real second
+    pub seed: Uuid,
+    pub config: UniverseConfig,
+    pub sectors: Vec<(i32, i32, i32)>, // Loaded sectors, in load order
+    pub default_model: OrbitModel,    // Model given to newly loaded galaxies
+    pub guids: Vec<Uuid>,             // Loaded galaxies, as in `Universe::loaded_guids`
+    pub models: Vec<OrbitModel>,      // Orbit model of each loaded galaxy
+    pub x: Vec<f64>,                  // Positions, in the order of `guids`
+    pub y: Vec<f64>,
+    pub z: Vec<f64>,
+    pub pending_events: Option<Vec<UniverseEvent>>, // Queued events, if the event queue is enabled
+}
+
+/// A change in a universe simulation, delivered to `on_event` subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniverseEvent {
+    /// A sector was loaded and its galaxies joined the simulation.
+    SectorLoaded((i32, i32, i32)),
+    /// A sector was unloaded and its galaxies left the simulation.
+    SectorUnloaded((i32, i32, i32)),
+    /// A galaxy's orbit carried it from one sector into another during a step, or had already
+    /// carried it out of the sector it was generated in when that sector was loaded.
+    GalaxyChangedSector {
+        guid: Uuid,
+        from: (i32, i32, i32),
+        to: (i32, i32, i32),
+    },
+}
+
+/// A callback run after every step with the universe and the step length in seconds.
+pub type TickCallback = dyn FnMut(&Universe, f64) + Send;
+/// A callback run for every universe event.
+pub type UniverseEventCallback = dyn FnMut(&UniverseEvent) + Send;
+
+/// Drives a universe's galaxies in simulated seconds, for hosts that own the game loop.
+///
+/// Nothing here sleeps or reads the clock except `run_realtime`; the host decides when to call
+/// `step` and by how much. Hosts can subscribe to ticks and events instead of polling the
+/// loaded galaxies every frame.
+#[derive(Debug)]
+pub struct UniverseSimulation {
+    universe: Universe,
+    tick_observers: Observers<TickCallback>,
+    event_observers: Observers<UniverseEventCallback>,
+    event_queue: Option<Vec<UniverseEvent>>, // Events awaiting `drain_events`, when enabled
+    time_scale: f64,                         // Simulated seconds per real second for `advance`
+}
+
+impl UniverseSimulation {
+    /// Real seconds per unit of orbital time.
+    pub const SECONDS_PER_TIME_UNIT: f64 = 15.0;
+
+    /// Creates a simulation of a whole universe.
+    ///
+    /// This loads every sector, millions of galaxies; use `with_sectors` to simulate a region.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The universe seed.
+    pub fn new(seed: u64) -> UniverseSimulation {
+        let mut universe = Universe::new(guid_from_seed(seed));
+        universe.load_all();
+        UniverseSimulation::from_universe(universe)
+    }
+
+    /// Creates a simulation of some sectors of a universe.
+    pub fn with_sectors(seed: u64, sectors: &[(i32, i32, i32)]) -> UniverseSimulation {
+        let mut universe = Universe::new(guid_from_seed(seed));
+        universe.load_sectors(sectors);
+        UniverseSimulation::from_universe(universe)
+    }
+
+    /// Creates a simulation of a universe, keeping its loaded sectors and time.
+    pub fn from_universe(universe: Universe) -> UniverseSimulation {
+        UniverseSimulation {
+            universe,
+            tick_observers: Observers::default(),
+            event_observers: Observers::default(),
+            event_queue: None,
+            time_scale: 1.0,
+        }
+    }
+
+    /// Returns the simulated universe.
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    /// Returns the simulated universe for direct modification.
+    ///
+    /// Changes made through it raise no events; use `load_sectors` and `unload_sectors` here to
+    /// notify subscribers.
+    pub fn universe_mut(&mut self) -> &mut Universe {
+        &mut self.universe
+    }
+
+    /// Loads sectors, raising `SectorLoaded` for each newly loaded one.
+    ///
+    /// Galaxies join the simulation at their positions at the current time, which after time zero
+    /// may already lie outside the sector they were generated in; `GalaxyChangedSector` is raised for each of them
+    /// after the `SectorLoaded` events, so subscribers always know which sector a galaxy is in.
+    pub fn load_sectors(&mut self, sectors: &[(i32, i32, i32)]) {
+        let before = self.universe.loaded_sectors().len();
+        let first_galaxy = self.universe.loaded_len();
+        self.universe.load_sectors(sectors);
+        let loaded = self.universe.loaded_sectors()[before..].to_vec();
+        for sector in loaded {
+            self.emit(UniverseEvent::SectorLoaded(sector));
+        }
+        if !self.event_observers.is_empty() || self.event_queue.is_some() {
+            let universe = &self.universe;
+            let (x, y, z) = universe.loaded_positions();
+            let moved: Vec<UniverseEvent> = (first_galaxy..universe.loaded_len())
+                .filter_map(|i| {
+                    let from = universe.orbits.sector[i];
+                    let to = universe.sector_of((x[i], y[i], z[i]));
+                    (from != to).then_some(UniverseEvent::GalaxyChangedSector { guid: universe.orbits.guid[i], from, to })
+                })
+                .collect();
+            for event in moved {
+                self.emit(event);
+            }
+        }
+    }
+
+    /// Unloads sectors, raising `SectorUnloaded` for each one that was loaded.
+    pub fn unload_sectors(&mut self, sectors: &[(i32, i32, i32)]) {
+        let requested: HashSet<(i32, i32, i32)> = sectors.iter().copied().collect();
+        let unloaded: Vec<(i32, i32, i32)> = self.universe.loaded_sectors().iter().copied().filter(|sector| requested.contains(sector)).collect();
+        self.universe.unload_sectors(sectors);
+        for sector in unloaded {
+            self.emit(UniverseEvent::SectorUnloaded(sector));
+        }
+    }
+
+    /// Turns the event queue on or off.
+    ///
+    /// While on, every event is also kept until `drain_events`, for hosts that poll instead of
+    /// subscribing; queued events are saved in snapshots. Turning it off discards the queue.
+    pub fn set_event_queue(&mut self, enabled: bool) {
+        match (enabled, self.event_queue.is_some()) {
+            (true, false) => self.event_queue = Some(Vec::new()),
+            (false, true) => self.event_queue = None,
+            _ => {}
+        }
+    }
+
+    /// Returns the queued events, oldest first, and empties the queue.
+    pub fn drain_events(&mut self) -> Vec<UniverseEvent> {
+        self.event_queue.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // Queues an event if the queue is enabled and delivers it to subscribers
+    fn emit(&mut self, event: UniverseEvent) {
+        self.event_observers.notify(&event);
+        if let Some(queue) = &mut self.event_queue {
+            queue.push(event);
+        }
+    }
+
+    /// Subscribes a callback run after every step.
+    ///
+    /// The callback receives the universe, whose `loaded_positions` are the new snapshot, and
+    /// the step length in seconds. It runs on the thread calling `step`.
+    pub fn on_tick(&mut self, callback: impl FnMut(&Universe, f64) + Send + 'static) -> SubscriptionId {
+        self.tick_observers.add(Box::new(callback))
+    }
+
+    /// Subscribes a callback run for every event, in the order the events happen.
+    ///
+    /// While any event subscription exists, each step also tracks which sector every galaxy is
+    /// in to raise `GalaxyChangedSector`, which costs a little extra per galaxy.
+    pub fn on_event(&mut self, callback: impl FnMut(&UniverseEvent) + Send + 'static) -> SubscriptionId {
+        self.event_observers.add(Box::new(callback))
+    }
+
+    /// Subscribes a channel receiving a copy of every event, for consumers on other threads.
+    pub fn subscribe_events(&mut self) -> (SubscriptionId, Receiver<UniverseEvent>) {
+        self.event_observers.add_channel()
+    }
+
+    /// Cancels a tick or event subscription.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the subscription existed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.tick_observers.remove(id) || self.event_observers.remove(id)
+    }
+
+
+    /// Returns the seconds simulated so far.
+    pub fn time(&self) -> f64 {
+        self.universe.time() * UniverseSimulation::SECONDS_PER_TIME_UNIT
+    }
+
+    /// Advances the simulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt_seconds` - Simulated seconds to advance; may be negative.
+    pub fn step(&mut self, dt_seconds: f64) {
+        self.change_time(dt_seconds, |universe| universe.step(dt_seconds / UniverseSimulation::SECONDS_PER_TIME_UNIT));
+    }
+
+    /// Jumps to any time, past or future, in a single update.
+    ///
+    /// Use this to scrub a timeline. Subscribers are notified as for one step of the difference:
+    /// tick callbacks receive the signed jump, and `GalaxyChangedSector` compares only the sectors
+    /// before and after, not the ones crossed in between.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Seconds since the start of the simulation; may be negative.
+    pub fn seek(&mut self, time: f64) {
+        let dt_seconds = time - self.time();
+        self.change_time(dt_seconds, |universe| universe.set_time(time / UniverseSimulation::SECONDS_PER_TIME_UNIT));
+    }
+
+    /// Returns the time scale: simulated seconds per real second for `advance`.
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Sets the time scale used by `advance` and `run_realtime`. The default is 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - Simulated seconds per real second: e.g. 86400.0 runs a day per second, 0.0
+    ///   pauses, and negative values run time backwards.
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.time_scale = scale;
+    }
+
+    /// Advances the simulation by real elapsed time, scaled by the time scale.
+    ///
+    /// # Arguments
+    ///
+    /// * `real_seconds` - Wall-clock seconds since the last call, e.g. the frame time.
+    pub fn advance(&mut self, real_seconds: f64) {
+        self.step(real_seconds * self.time_scale);
+    }
+
+    // Applies a change of time to the universe and notifies subscribers of its effects
+    fn change_time(&mut self, dt_seconds: f64, change: impl FnOnce(&mut Universe)) {
+        let listening = !self.event_observers.is_empty() || self.event_queue.is_some();
+        let before = if listening { self.universe.loaded_galaxy_sectors() } else { Vec::new() };
+        change(&mut self.universe);
+        if !before.is_empty() {
+            let after = self.universe.loaded_galaxy_sectors();
+            let guids = self.universe.loaded_guids().to_vec();
+            for ((from, to), guid) in before.into_iter().zip(after).zip(guids) {
+                if from != to {
+                    self.emit(UniverseEvent::GalaxyChangedSector { guid, from, to });
+                }
+            }
+        }
+        for callback in self.tick_observers.iter_mut() {
+            callback(&self.universe, dt_seconds);
+        }
+    }
+
+    /// Computes the state at any time without advancing the simulation.
+    ///
+    /// Orbits are closed-form, so hosts can render interpolated frames or look ahead freely.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Seconds since the start of the simulation.
+    pub fn state_at(&self, time: f64) -> UniverseState {
+        let (x, y, z) = self.universe.positions_at(time / UniverseSimulation::SECONDS_PER_TIME_UNIT);
+        UniverseState { x, y, z, ..self.state_without_positions(time) }
+    }
+
+    // Everything of a snapshot but the positions, which are left empty
+    fn state_without_positions(&self, time: f64) -> UniverseState {
+        UniverseState {
+            time,
+            time_scale: self.time_scale,
+            seed: self.universe.seed(),
+            config: self.universe.config().clone(),
+            sectors: self.universe.loaded_sectors().to_vec(),
+            default_model: self.universe.default_orbit_model(),
+            guids: self.universe.loaded_guids().to_vec(),
+            models: self.universe.loaded_orbit_models().to_vec(),
+            x: Vec::new(),
+            y: Vec::new(),
+            z: Vec::new(),
+            pending_events: self.event_queue.clone(),
+        }
+    }
+
+    /// Captures the current state, to persist with `UniverseState::to_bytes` and restore with
+    /// `resume`.
+    pub fn snapshot(&self) -> UniverseState {
+        let (x, y, z) = self.universe.loaded_positions();
+        UniverseState {
+            x: x.to_vec(),
+            y: y.to_vec(),
+            z: z.to_vec(),
+            ..self.state_without_positions(self.time())
+        }
+    }
+
+    /// Recreates a simulation from a snapshot.
+    ///
+    /// The universe is regenerated from the snapshot's seed and configuration with the same
+    /// sectors loaded in the same order; then the time, orbit models, positions and event queue
+    /// are restored, so stepping continues where the snapshot was taken. Subscriptions are
+    /// not part of a snapshot and must be made again.
+    ///
+    /// # Returns
+    ///
+    /// The simulation, or `SnapshotError::Mismatch` if the regenerated galaxies differ from the
+    /// snapshot's, e.g. because it was taken with an incompatible version of the generator.
+    pub fn resume(state: UniverseState) -> Result<UniverseSimulation, SnapshotError> {
+        state.config.validate().map_err(|error| SnapshotError::Invalid(error.to_string()))?;
+        let count = state.guids.len();
+        if [state.models.len(), state.x.len(), state.y.len(), state.z.len()].iter().any(|&len| len != count) {
+            return Err(SnapshotError::Invalid("galaxy arrays differ in length".to_string()));
+        }
+        let mut universe = Universe::with_config(state.seed, state.config);
+        universe.set_default_orbit_model(state.default_model);
+        let mut seen = HashSet::with_capacity(state.sectors.len());
+        let sectors: Vec<(i32, i32, i32)> = state.sectors.into_iter().filter(|&sector| seen.insert(sector)).collect();
+        universe.load_in_order(sectors);
+        if universe.loaded_guids() != state.guids.as_slice() {
+            return Err(SnapshotError::Mismatch);
+        }
+        universe.restore(state.time / UniverseSimulation::SECONDS_PER_TIME_UNIT, &state.models, &state.x, &state.y, &state.z);
+        let mut simulation = UniverseSimulation::from_universe(universe);
+        simulation.event_queue = state.pending_events;
+        simulation.time_scale = state.time_scale;
+        Ok(simulation)
+    }
+
+    /// Runs the simulation forever in real time, advancing every 15 seconds by the elapsed time
+    /// times the time scale and logging the timing of each update as a `tracing` event.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_realtime(&mut self) -> ! {
+        let mut last = Instant::now();
+        loop {
+            thread::sleep(Duration::from_secs_f64(UniverseSimulation::SECONDS_PER_TIME_UNIT));
+            let elapsed = last.elapsed();
+            last = Instant::now();
+            self.advance(elapsed.as_secs_f64());
+            tracing::debug!(
+                elapsed_ms = elapsed.as_secs_f64() * 1000.0,
+                update_ms = last.elapsed().as_secs_f64() * 1000.0,
+                objects = self.universe.loaded_len(),
+                "advanced universe"
+            );
+        }
+    }
+}
+
+/// Generates the universe with seed 123 and simulates it in real time forever.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn simulate() {
     // Generate galaxies using the universe seed
     let start = Instant::now();
-    let mut galaxies = generate_galaxies(universe_seed);
-    let duration = start.elapsed();
-    println!("Generating galaxies took: {:?}", duration);
-
-    // Simulation loop
-    let mut time = 0.0;
-    loop {
-        // Sleep for 15 seconds
-        let start = Instant::now();
-        thread::sleep(Duration::from_secs(15));
-        let duration = start.elapsed();
-        println!("Sleeping for 15 second took: {:?}", duration);
-
-        // Update galaxy positions
-        time += 1.0;
-        let start = Instant::now();
-        galaxies.par_iter_mut().for_each(|galaxy| {
-            update_position(galaxy, time);
-        });
-        let duration = start.elapsed();
-        println!("Updating galaxy positions took: {:?}", duration);
-        println!("Updated {} objects", galaxies.len().to_string());
-        println!("---------Lerp with last movement data to get smooth orbits---------");
-    }
+    let mut simulation = UniverseSimulation::new(123);
+    tracing::info!(seed = %simulation.universe().seed(), generation_ms = start.elapsed().as_secs_f64() * 1000.0, "generated universe");
+
+    simulation.run_realtime()
 }
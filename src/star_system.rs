@@ -0,0 +1,442 @@
+use crate::guid::{derive_guid, guid_seed, MOON_DISCRIMINANT, PLANET_DISCRIMINANT, RING_DISCRIMINANT, SYSTEM_DISCRIMINANT};
+use crate::noise::mix64;
+use crate::small_bodies::{generate_populations, SmallBodyPopulation};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::{PI, TAU};
+use uuid::Uuid;
+
+/// The Morgan–Keenan spectral class of a main-sequence star.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpectralClass {
+    O,
+    B,
+    A,
+    F,
+    G,
+    K,
+    M,
+}
+
+impl SpectralClass {
+    // Relative abundance among main-sequence stars and the mass range in solar masses
+    fn distribution() -> [(SpectralClass, f64, (f64, f64)); 7] {
+        [
+            (SpectralClass::O, 0.00003, (16.0, 60.0)),
+            (SpectralClass::B, 0.0013, (2.1, 16.0)),
+            (SpectralClass::A, 0.006, (1.4, 2.1)),
+            (SpectralClass::F, 0.03, (1.04, 1.4)),
+            (SpectralClass::G, 0.076, (0.8, 1.04)),
+            (SpectralClass::K, 0.121, (0.45, 0.8)),
+            (SpectralClass::M, 0.7653, (0.08, 0.45)),
+        ]
+    }
+}
+
+/// A system's central star.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Star {
+    pub class: SpectralClass,
+    pub mass: f64,        // Solar masses
+    pub luminosity: f64,  // Solar luminosities
+    pub radius: f64,      // Solar radii
+    pub temperature: f64, // Effective surface temperature in kelvin
+    pub age: f64,         // Billion years
+}
+
+/// The broad composition of a planet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlanetKind {
+    /// A terrestrial planet with a solid surface.
+    Rocky,
+    /// A hydrogen/helium giant.
+    GasGiant,
+    /// A giant made mostly of water, ammonia and methane ices.
+    IceGiant,
+}
+
+/// Keplerian orbital elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f64,       // Astronomical units
+    pub eccentricity: f64,
+    pub inclination: f64,           // Radians, relative to the system's reference plane
+    pub ascending_node: f64,        // Longitude of the ascending node in radians
+    pub argument_of_periapsis: f64, // Radians
+    pub mean_anomaly_at_epoch: f64, // Radians
+    pub period: f64,                // Earth days
+}
+
+// Solves Kepler's equation M = E - e sin E for the eccentric anomaly E with Newton's method
+pub(crate) fn eccentric_anomaly(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mean_anomaly = mean_anomaly.rem_euclid(TAU);
+    let mut anomaly = if eccentricity > 0.8 { PI } else { mean_anomaly };
+    for _ in 0..32 {
+        let step = (anomaly - eccentricity * anomaly.sin() - mean_anomaly) / (1.0 - eccentricity * anomaly.cos());
+        anomaly -= step;
+        if step.abs() < 1e-12 {
+            break;
+        }
+    }
+    anomaly
+}
+
+impl OrbitalElements {
+    /// Returns the mean anomaly at a time, in radians.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Earth days since the epoch.
+    pub fn mean_anomaly_at(&self, time: f64) -> f64 {
+        (self.mean_anomaly_at_epoch + TAU * time / self.period.max(f64::MIN_POSITIVE)).rem_euclid(TAU)
+    }
+
+    /// Returns the position of the orbiting body at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Earth days since the epoch.
+    ///
+    /// # Returns
+    ///
+    /// The position relative to the central body in astronomical units, in the reference frame of
+    /// the orbit (the system's reference plane for planets, the planet's equator for moons).
+    pub fn position_at(&self, time: f64) -> (f64, f64, f64) {
+        let e = self.eccentricity;
+        let anomaly = eccentric_anomaly(self.mean_anomaly_at(time), e);
+        // Position in the orbital plane, periapsis along +x
+        let x = self.semi_major_axis * (anomaly.cos() - e);
+        let y = self.semi_major_axis * (1.0 - e * e).sqrt() * anomaly.sin();
+
+        let (sin_w, cos_w) = self.argument_of_periapsis.sin_cos();
+        let (sin_o, cos_o) = self.ascending_node.sin_cos();
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        let (xp, yp) = (x * cos_w - y * sin_w, x * sin_w + y * cos_w);
+        (xp * cos_o - yp * cos_i * sin_o, xp * sin_o + yp * cos_i * cos_o, yp * sin_i)
+    }
+}
+
+/// A planet as seen from its star system: its orbit and bulk properties.
+///
+/// Use the planet's `guid` to generate its surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemPlanet {
+    pub guid: Uuid,
+    pub index: usize,                 // Position from the star outward, starting at 0
+    pub kind: PlanetKind,
+    pub mass: f64,                    // Earth masses
+    pub radius: f64,                  // Kilometres
+    pub orbit: OrbitalElements,
+    pub equilibrium_temperature: f64, // Kelvin, assuming a Bond albedo of 0.3
+    pub moons: Vec<Moon>,             // Ordered from the planet outward
+    pub rings: Option<RingSystem>,
+}
+
+/// The broad composition of a moon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoonKind {
+    /// A silicate body with a solid surface.
+    Rocky,
+    /// A body of water ice over a small rocky core.
+    Icy,
+}
+
+/// A natural satellite of a planet.
+///
+/// Use the moon's `guid` to generate its surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Moon {
+    pub guid: Uuid,
+    pub index: usize,           // Position from the planet outward, starting at 0
+    pub kind: MoonKind,
+    pub mass: f64,              // Earth masses
+    pub radius: f64,            // Kilometres
+    pub orbit: OrbitalElements, // Around the planet, relative to its equator
+    pub tidally_locked: bool,   // Whether the moon always shows the planet the same face
+}
+
+/// A planetary ring system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingSystem {
+    pub guid: Uuid,
+    pub inner_radius: f64,  // Kilometres from the planet's centre
+    pub outer_radius: f64,  // Kilometres from the planet's centre
+    pub optical_depth: f64, // Normal optical depth; above 1.0 the rings are opaque
+    pub icy: bool,          // Bright water ice rather than dark dust
+}
+
+/// A star and its planets, generated deterministically from a GUID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StarSystem {
+    guid: Uuid,
+    star: Star,
+    planets: Vec<SystemPlanet>,
+    small_bodies: Vec<SmallBodyPopulation>,
+}
+
+// Earth's radius in kilometres
+const EARTH_RADIUS_KM: f64 = 6371.0;
+// Earth masses per solar mass
+pub(crate) const EARTH_MASSES_PER_SOLAR_MASS: f64 = 332_946.0;
+// Kilometres per astronomical unit
+pub(crate) const KM_PER_AU: f64 = 149_597_870.7;
+
+fn generate_star(rng: &mut StdRng) -> Star {
+    let roll: f64 = rng.gen();
+    let mut cumulative = 0.0;
+    let mut chosen = SpectralClass::distribution()[6];
+    for entry in SpectralClass::distribution() {
+        cumulative += entry.1;
+        if roll < cumulative {
+            chosen = entry;
+            break;
+        }
+    }
+    let (class, _, (low, high)) = chosen;
+    // Log-uniform within the class so low masses dominate, as in the initial mass function
+    let mass = low * (high / low).powf(rng.gen::<f64>());
+    let luminosity = if mass < 0.43 { 0.23 * mass.powf(2.3) } else if mass < 2.0 { mass.powi(4) } else { 1.4 * mass.powf(3.5) };
+    let radius = if mass < 1.0 { mass.powf(0.8) } else { mass.powf(0.57) };
+    let temperature = 5772.0 * (luminosity / (radius * radius)).powf(0.25);
+    // Massive stars leave the main sequence quickly
+    let lifetime = 10.0 * mass.powf(-2.5);
+    let age = rng.gen_range(0.05..lifetime.clamp(0.1, 12.0));
+    Star {
+        class,
+        mass,
+        luminosity,
+        radius,
+        temperature,
+        age,
+    }
+}
+
+// Generates the moons and rings of a planet.
+//
+// Satellites draw from their own stream seeded by the planet GUID, so they do not disturb the
+// rest of the system. Rings sit inside the Roche limit; moons are spaced geometrically outward
+// from just beyond it to the stable part of the planet's Hill sphere.
+fn generate_satellites(star: &Star, planet: Uuid, kind: PlanetKind, mass: f64, radius: f64, semi_major_axis: f64) -> (Vec<Moon>, Option<RingSystem>) {
+    let mut rng = StdRng::seed_from_u64(mix64(guid_seed(planet) ^ MOON_DISCRIMINANT));
+    let giant = kind != PlanetKind::Rocky;
+
+    let ring_chance = match kind {
+        PlanetKind::Rocky => 0.02,
+        PlanetKind::GasGiant => 0.5,
+        PlanetKind::IceGiant => 0.35,
+    };
+    let rings = rng.gen_bool(ring_chance).then(|| {
+        let inner_radius = radius * rng.gen_range(1.1..1.5);
+        RingSystem {
+            guid: derive_guid(planet, RING_DISCRIMINANT, 0),
+            inner_radius,
+            outer_radius: (inner_radius + radius * rng.gen_range(0.3..1.3)).min(radius * 2.5),
+            optical_depth: 10f64.powf(rng.gen_range(-3.0..0.3)),
+            icy: kind == PlanetKind::GasGiant || rng.gen_bool(0.5),
+        }
+    });
+
+    let planet_solar_masses = mass / EARTH_MASSES_PER_SOLAR_MASS;
+    let hill_radius = semi_major_axis * KM_PER_AU * (planet_solar_masses / (3.0 * star.mass)).cbrt();
+    let count = if giant { rng.gen_range(1..=8usize) } else { rng.gen_range(0..=2usize) };
+    let inner = rings.as_ref().map_or(radius * 3.0, |rings| rings.outer_radius * 1.3).max(radius * 3.0);
+    // Giants keep their regular moons close in; a rocky planet's moon may be captured anywhere
+    let mut distance = if giant {
+        inner * rng.gen_range(1.0..2.0)
+    } else {
+        inner * (hill_radius * 0.3 / inner).max(1.0).powf(rng.gen::<f64>())
+    };
+    let mut moons = Vec::new();
+    while moons.len() < count && distance < hill_radius * 0.4 {
+        let index = moons.len();
+        let moon_kind = if giant && rng.gen_bool(0.7) { MoonKind::Icy } else { MoonKind::Rocky };
+        let moon_mass = mass * 10f64.powf(if giant { rng.gen_range(-6.5..-3.5) } else { rng.gen_range(-4.0..-1.9) });
+        let density = if moon_kind == MoonKind::Icy { 1.9 } else { 3.3 };
+        // Earth's bulk density is 5.51 g/cm³
+        let moon_radius = EARTH_RADIUS_KM * (moon_mass * 5.51 / density).cbrt();
+        let orbit = OrbitalElements {
+            semi_major_axis: distance / KM_PER_AU,
+            eccentricity: rng.gen::<f64>().powi(4) * 0.2,
+            inclination: rng.gen::<f64>().powi(3) * 0.5,
+            ascending_node: rng.gen_range(0.0..TAU),
+            argument_of_periapsis: rng.gen_range(0.0..TAU),
+            mean_anomaly_at_epoch: rng.gen_range(0.0..TAU),
+            period: 365.25 * ((distance / KM_PER_AU).powi(3) / planet_solar_masses).sqrt(),
+        };
+        // Despinning time grows with the sixth power of distance; Earth's Moon, at 60 Earth radii
+        // around a 1 Earth-mass planet, locked well within the age of the Solar System
+        let lock_distance = 60.0 * EARTH_RADIUS_KM * (mass * star.age / 4.6).powf(1.0 / 6.0);
+        moons.push(Moon {
+            guid: derive_guid(planet, MOON_DISCRIMINANT, index as u64),
+            index,
+            kind: moon_kind,
+            mass: moon_mass,
+            radius: moon_radius,
+            orbit,
+            tidally_locked: distance < lock_distance,
+        });
+        distance *= rng.gen_range(1.3..2.2);
+    }
+    (moons, rings)
+}
+
+impl StarSystem {
+    /// Generates the `index`-th star system of a galaxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `galaxy_guid` - The parent galaxy.
+    /// * `index` - The system's index within the galaxy.
+    pub fn generate(galaxy_guid: Uuid, index: u64) -> StarSystem {
+        StarSystem::from_guid(derive_guid(galaxy_guid, SYSTEM_DISCRIMINANT, index))
+    }
+
+    /// Generates a star system from its own GUID.
+    ///
+    /// The star's class is drawn from main-sequence abundances. Planets are spaced outward in
+    /// roughly geometric steps; inside the frost line they are rocky, beyond it gas or ice giants.
+    /// Giants get up to eight moons and often rings; rocky planets get at most two moons. Every
+    /// planet and moon gets a GUID derived from its parent's, so its surface can be generated later.
+    pub fn from_guid(guid: Uuid) -> StarSystem {
+        let mut rng = StdRng::seed_from_u64(guid_seed(guid));
+        let star = generate_star(&mut rng);
+
+        let frost_line = 2.7 * star.luminosity.sqrt();
+        let count = rng.gen_range(0..=10usize);
+        let mut semi_major_axis = rng.gen_range(0.1..0.5) * star.mass.sqrt();
+        let planets: Vec<SystemPlanet> = (0..count)
+            .map(|index| {
+                let kind = if semi_major_axis < frost_line {
+                    PlanetKind::Rocky
+                } else if rng.gen_bool(0.55) {
+                    PlanetKind::GasGiant
+                } else {
+                    PlanetKind::IceGiant
+                };
+                let (mass, radius) = match kind {
+                    PlanetKind::Rocky => {
+                        let mass = 0.05 * (8.0f64 / 0.05).powf(rng.gen::<f64>());
+                        (mass, EARTH_RADIUS_KM * mass.powf(0.27))
+                    }
+                    PlanetKind::GasGiant => {
+                        let mass = rng.gen_range(30.0f64..1500.0);
+                        (mass, 69_911.0 * (mass / 318.0).powf(0.05))
+                    }
+                    PlanetKind::IceGiant => {
+                        let mass = rng.gen_range(8.0f64..30.0);
+                        (mass, EARTH_RADIUS_KM * mass.powf(0.55))
+                    }
+                };
+                let orbit = OrbitalElements {
+                    semi_major_axis,
+                    eccentricity: rng.gen::<f64>().powi(3) * 0.3,
+                    inclination: rng.gen_range(0.0..4.0f64).to_radians(),
+                    ascending_node: rng.gen_range(0.0..TAU),
+                    argument_of_periapsis: rng.gen_range(0.0..TAU),
+                    mean_anomaly_at_epoch: rng.gen_range(0.0..TAU),
+                    period: 365.25 * (semi_major_axis.powi(3) / star.mass).sqrt(),
+                };
+                let equilibrium_temperature = 278.6 * star.luminosity.powf(0.25) / semi_major_axis.sqrt() * 0.7f64.powf(0.25);
+                let planet_guid = derive_guid(guid, PLANET_DISCRIMINANT, index as u64);
+                let (moons, rings) = generate_satellites(&star, planet_guid, kind, mass, radius, semi_major_axis);
+                let planet = SystemPlanet {
+                    guid: planet_guid,
+                    index,
+                    kind,
+                    mass,
+                    radius,
+                    orbit,
+                    equilibrium_temperature,
+                    moons,
+                    rings,
+                };
+                semi_major_axis *= rng.gen_range(1.4..2.2);
+                planet
+            })
+            .collect();
+
+        let small_bodies = generate_populations(guid, &star, &planets);
+        StarSystem {
+            guid,
+            star,
+            planets,
+            small_bodies,
+        }
+    }
+
+    /// Returns the system's GUID.
+    pub fn guid(&self) -> Uuid {
+        self.guid
+    }
+
+    /// Returns the central star.
+    pub fn star(&self) -> &Star {
+        &self.star
+    }
+
+    /// Returns the planets, ordered from the star outward.
+    pub fn planets(&self) -> &[SystemPlanet] {
+        &self.planets
+    }
+
+    /// Returns the asteroid belts and comet clouds, ordered from the star outward.
+    ///
+    /// Populations only describe their region; individual bodies are generated on demand with
+    /// `SmallBodyPopulation::body`.
+    pub fn small_bodies(&self) -> &[SmallBodyPopulation] {
+        &self.small_bodies
+    }
+
+    /// Returns the planet with the given GUID.
+    pub fn planet(&self, guid: Uuid) -> Option<&SystemPlanet> {
+        self.planets.iter().find(|planet| planet.guid == guid)
+    }
+
+    /// Returns the moon with the given GUID together with the planet it orbits.
+    pub fn moon(&self, guid: Uuid) -> Option<(&SystemPlanet, &Moon)> {
+        self.planets
+            .iter()
+            .find_map(|planet| planet.moons.iter().find(|moon| moon.guid == guid).map(|moon| (planet, moon)))
+    }
+
+    /// Returns the inner and outer edges of the habitable zone in astronomical units.
+    pub fn habitable_zone(&self) -> (f64, f64) {
+        let l = self.star.luminosity;
+        ((l / 1.1).sqrt(), (l / 0.53).sqrt())
+    }
+
+    /// Returns the irradiance a planet receives from the star, in W/m².
+    pub fn stellar_flux(&self, planet: &SystemPlanet) -> f64 {
+        crate::climate::SOLAR_CONSTANT * self.star.luminosity / planet.orbit.semi_major_axis.powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks that `E - e sin E` gives back the mean anomaly, modulo a full turn
+    fn assert_solves(mean_anomaly: f64, eccentricity: f64) {
+        let anomaly = eccentric_anomaly(mean_anomaly, eccentricity);
+        let residual = (anomaly - eccentricity * anomaly.sin() - mean_anomaly).rem_euclid(TAU);
+        let residual = residual.min(TAU - residual);
+        assert!(residual < 1e-10, "M = {mean_anomaly}, e = {eccentricity}: E = {anomaly} is off by {residual}");
+    }
+
+    #[test]
+    fn circular_orbits_have_eccentric_anomaly_equal_to_mean_anomaly() {
+        for mean_anomaly in [0.0, 0.5, PI, 4.0, TAU - 1e-9] {
+            assert_eq!(eccentric_anomaly(mean_anomaly, 0.0), mean_anomaly);
+            assert_solves(mean_anomaly, 0.0);
+        }
+    }
+
+    #[test]
+    fn solves_kepler_near_parabolic_and_wrapping_anomalies() {
+        for eccentricity in [0.3, 0.8, 0.81, 0.99, 0.999] {
+            for mean_anomaly in [0.0, 1e-12, 1e-6, 0.01, 1.0, PI, 5.0, TAU - 0.01, TAU - 1e-9, TAU, -1e-9, 7.0 * TAU + 0.5] {
+                assert_solves(mean_anomaly, eccentricity);
+            }
+        }
+    }
+}
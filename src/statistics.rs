@@ -0,0 +1,249 @@
+use crate::hydrology::{cell_areas, Drainage};
+use crate::planet::Planet;
+use crate::render::materials::Material;
+use crate::vegetation::VegetationLayer;
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+// Height in metres of each elevation histogram bin
+const ELEVATION_BIN: f64 = 1000.0;
+// Width in degrees of each latitude band
+const LATITUDE_BAND: f64 = 10.0;
+// Discharge in m³/s at which a stream is traced as a river when measuring lengths
+const RIVER_FLOW: f64 = 100.0;
+
+/// One bin of the elevation histogram of `WorldStatistics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElevationBin {
+    pub min: f64,   // Metres relative to sea level, inclusive
+    pub max: f64,   // Metres relative to sea level, exclusive
+    pub area: f64,  // Square kilometres
+    pub share: f64, // Share of the surface, 0.0 to 1.0
+}
+
+/// The area covered by one surface material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiomeArea {
+    pub material: Material,
+    pub area: f64,  // Square kilometres
+    pub share: f64, // Share of the surface, 0.0 to 1.0
+}
+
+/// The mean annual temperature of a band of latitude.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatitudeBand {
+    pub min_latitude: f64,     // Degrees, inclusive
+    pub max_latitude: f64,     // Degrees, exclusive except at the north pole
+    pub mean_temperature: f64, // Area-weighted mean annual temperature in °C
+}
+
+/// The longest river of a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiverSummary {
+    pub cells: Vec<usize>, // From source to mouth
+    pub length: f64,       // Kilometres
+    pub flow: f64,         // Discharge at the mouth in m³/s
+}
+
+/// The highest point of a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakSummary {
+    pub cell: usize,
+    pub elevation: f64, // Metres above sea level
+    pub latitude: f64,  // Degrees
+    pub longitude: f64, // Degrees
+}
+
+/// The largest lake of a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LakeSummary {
+    pub index: usize, // Index into `Drainage::lakes`
+    pub area: f64,    // Square kilometres
+    pub surface: f64, // Water surface elevation in metres
+    pub cells: usize, // Flooded cells
+}
+
+/// A summary of a planet's geography and climate from `Planet::statistics`, for comparing worlds
+/// or showing players what they are about to explore.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldStatistics {
+    pub cell_count: usize,
+    pub surface_area: f64,   // Square kilometres
+    pub land_fraction: f64,  // Share of the surface above sea level, 0.0 to 1.0
+    pub ocean_fraction: f64, // Share of the surface below sea level, 0.0 to 1.0
+    pub elevation_histogram: Vec<ElevationBin>, // 1000 m bins from the deepest to the highest cell
+    pub biomes: Vec<BiomeArea>,                 // Every material present, largest first
+    pub temperature_by_latitude: Vec<LatitudeBand>, // 10° bands from south to north; bands without cells are left out
+    pub longest_river: Option<RiverSummary>,
+    pub highest_peak: Option<PeakSummary>,
+    pub largest_lake: Option<LakeSummary>,
+}
+
+impl Planet {
+    /// Measures the planet's geography and climate.
+    ///
+    /// Areas are weighted by cell, so the shares hold on uneven cell layouts. Biomes are the
+    /// materials of `Planet::materials` with the vegetation of `VegetationLayer::from_planet`, and
+    /// temperatures are mean annual temperatures. Rivers and lakes come from `Drainage::compute`;
+    /// river lengths are traced from where a stream first carries 100 m³/s.
+    pub fn statistics(&self) -> WorldStatistics {
+        let areas = cell_areas(self);
+        let surface_area: f64 = areas.iter().sum();
+        let share = |area: f64| if surface_area > 0.0 { area / surface_area } else { 0.0 };
+        let elevation = self.elevation();
+        let land: f64 = (0..self.cell_count()).filter(|&cell| !self.is_ocean(cell)).map(|cell| areas[cell]).sum();
+
+        let lowest = (elevation.iter().copied().fold(f64::INFINITY, f64::min) / ELEVATION_BIN).floor();
+        let highest = (elevation.iter().copied().fold(f64::NEG_INFINITY, f64::max) / ELEVATION_BIN).floor();
+        let mut bins: Vec<f64> = if self.cell_count() > 0 { vec![0.0; (highest - lowest) as usize + 1] } else { Vec::new() };
+        for (cell, &height) in elevation.iter().enumerate() {
+            bins[((height / ELEVATION_BIN).floor() - lowest) as usize] += areas[cell];
+        }
+        let elevation_histogram = bins
+            .into_iter()
+            .enumerate()
+            .map(|(i, area)| {
+                let min = (lowest + i as f64) * ELEVATION_BIN;
+                ElevationBin { min, max: min + ELEVATION_BIN, area, share: share(area) }
+            })
+            .collect();
+
+        let mut biome_areas = [0.0; Material::ALL.len()];
+        for (cell, material) in self.materials(&VegetationLayer::from_planet(self)).into_iter().enumerate() {
+            biome_areas[material as usize] += areas[cell];
+        }
+        let mut biomes: Vec<BiomeArea> = Material::ALL
+            .iter()
+            .filter(|&&material| biome_areas[material as usize] > 0.0)
+            .map(|&material| BiomeArea { material, area: biome_areas[material as usize], share: share(biome_areas[material as usize]) })
+            .collect();
+        biomes.sort_by(|a, b| b.area.total_cmp(&a.area));
+
+        let bands = (180.0 / LATITUDE_BAND) as usize;
+        let mut band_sums = vec![(0.0, 0.0); bands];
+        for (cell, temperature) in self.mean_annual_temperature().into_iter().enumerate() {
+            let band = (((self.lat_lon(cell).0 + 90.0) / LATITUDE_BAND) as usize).min(bands - 1);
+            band_sums[band].0 += temperature * areas[cell];
+            band_sums[band].1 += areas[cell];
+        }
+        let temperature_by_latitude = band_sums
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, (_, area))| area > 0.0)
+            .map(|(band, (sum, area))| LatitudeBand {
+                min_latitude: band as f64 * LATITUDE_BAND - 90.0,
+                max_latitude: (band + 1) as f64 * LATITUDE_BAND - 90.0,
+                mean_temperature: sum / area,
+            })
+            .collect();
+
+        let drainage = Drainage::compute(self);
+        let longest_river = drainage
+            .rivers(RIVER_FLOW)
+            .into_iter()
+            .map(|cells| {
+                let length = cells.windows(2).map(|pair| self.distance_km(pair[0], pair[1])).sum();
+                let flow = cells.last().map_or(0.0, |&mouth| drainage.flow()[mouth]);
+                RiverSummary { cells, length, flow }
+            })
+            .max_by(|a, b| a.length.total_cmp(&b.length));
+        let highest_peak = (0..self.cell_count()).max_by(|&a, &b| elevation[a].total_cmp(&elevation[b])).map(|cell| {
+            let (latitude, longitude) = self.lat_lon(cell);
+            PeakSummary { cell, elevation: elevation[cell], latitude, longitude }
+        });
+        let largest_lake = drainage
+            .lakes()
+            .iter()
+            .enumerate()
+            .map(|(index, lake)| LakeSummary {
+                index,
+                area: lake.cells.iter().map(|&cell| areas[cell]).sum(),
+                surface: lake.surface,
+                cells: lake.cells.len(),
+            })
+            .max_by(|a, b| a.area.total_cmp(&b.area));
+
+        WorldStatistics {
+            cell_count: self.cell_count(),
+            surface_area,
+            land_fraction: share(land),
+            ocean_fraction: share(surface_area - land),
+            elevation_histogram,
+            biomes,
+            temperature_by_latitude,
+            longest_river,
+            highest_peak,
+            largest_lake,
+        }
+    }
+}
+
+impl WorldStatistics {
+    /// Returns the statistics as a JSON value, with the same field names and units. The longest
+    /// river is given by its length, flow and end cells rather than every cell.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "cell_count": self.cell_count,
+            "surface_area": self.surface_area,
+            "land_fraction": self.land_fraction,
+            "ocean_fraction": self.ocean_fraction,
+            "elevation_histogram": self.elevation_histogram.iter().map(|bin| json!({ "min": bin.min, "max": bin.max, "area": bin.area, "share": bin.share })).collect::<Vec<_>>(),
+            "biomes": self.biomes.iter().map(|biome| json!({ "material": biome.material.name(), "area": biome.area, "share": biome.share })).collect::<Vec<_>>(),
+            "temperature_by_latitude": self
+                .temperature_by_latitude
+                .iter()
+                .map(|band| json!({ "min_latitude": band.min_latitude, "max_latitude": band.max_latitude, "mean_temperature": band.mean_temperature }))
+                .collect::<Vec<_>>(),
+            "longest_river": self.longest_river.as_ref().map(|river| json!({
+                "length": river.length,
+                "flow": river.flow,
+                "source": river.cells.first(),
+                "mouth": river.cells.last(),
+            })),
+            "highest_peak": self.highest_peak.as_ref().map(|peak| json!({
+                "cell": peak.cell,
+                "elevation": peak.elevation,
+                "latitude": peak.latitude,
+                "longitude": peak.longitude,
+            })),
+            "largest_lake": self.largest_lake.as_ref().map(|lake| json!({
+                "index": lake.index,
+                "area": lake.area,
+                "surface": lake.surface,
+                "cells": lake.cells,
+            })),
+        })
+    }
+
+    /// Renders the statistics as a Markdown report with a table for each breakdown.
+    pub fn to_markdown(&self) -> String {
+        let mut report = String::from("# World Statistics\n\n");
+        let _ = writeln!(report, "- Cells: {}", self.cell_count);
+        let _ = writeln!(report, "- Surface area: {:.0} km²", self.surface_area);
+        let _ = writeln!(report, "- Land: {:.1}%", self.land_fraction * 100.0);
+        let _ = writeln!(report, "- Ocean: {:.1}%", self.ocean_fraction * 100.0);
+        if let Some(peak) = &self.highest_peak {
+            let _ = writeln!(report, "- Highest peak: {:.0} m at {:.2}°, {:.2}°", peak.elevation, peak.latitude, peak.longitude);
+        }
+        if let Some(river) = &self.longest_river {
+            let _ = writeln!(report, "- Longest river: {:.0} km, {:.0} m³/s at the mouth", river.length, river.flow);
+        }
+        if let Some(lake) = &self.largest_lake {
+            let _ = writeln!(report, "- Largest lake: {:.0} km², surface at {:.0} m", lake.area, lake.surface);
+        }
+
+        report.push_str("\n## Elevation\n\n| Elevation (m) | Area (km²) | Share |\n| --- | ---: | ---: |\n");
+        for bin in &self.elevation_histogram {
+            let _ = writeln!(report, "| {:.0} to {:.0} | {:.0} | {:.1}% |", bin.min, bin.max, bin.area, bin.share * 100.0);
+        }
+        report.push_str("\n## Biomes\n\n| Biome | Area (km²) | Share |\n| --- | ---: | ---: |\n");
+        for biome in &self.biomes {
+            let _ = writeln!(report, "| {} | {:.0} | {:.1}% |", biome.material.name(), biome.area, biome.share * 100.0);
+        }
+        report.push_str("\n## Temperature by Latitude\n\n| Latitude | Mean temperature (°C) |\n| --- | ---: |\n");
+        for band in &self.temperature_by_latitude {
+            let _ = writeln!(report, "| {:.0}° to {:.0}° | {:.1} |", band.min_latitude, band.max_latitude, band.mean_temperature);
+        }
+        report
+    }
+}
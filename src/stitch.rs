@@ -0,0 +1,327 @@
+use crate::hydrology::Drainage;
+use crate::planet::Planet;
+use crate::raster::lat_lon_to_unit;
+use crate::vector::{cross, dot, normalize, Vec3};
+use std::collections::{HashSet, VecDeque};
+
+/// An externally authored heightmap, optionally with a biome map, to embed into a planet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthoredPatch {
+    width: usize,
+    height: usize,
+    heights: Vec<f64>,       // Row-major elevation in metres, top row is north
+    biomes: Option<Vec<u8>>, // Row-major biome codes, if authored
+    size_km: (f64, f64),     // Ground size of the patch, east-west and north-south
+}
+
+/// Why an `AuthoredPatch` could not be created.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The heightmap has no samples along one of its axes.
+    Empty,
+    /// The heightmap does not hold `width * height` samples.
+    SampleCount { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Empty => write!(f, "patch heightmap has no samples"),
+            PatchError::SampleCount { expected, found } => write!(f, "patch heightmap holds {} samples, expected {}", found, expected),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl AuthoredPatch {
+    /// Creates a patch from a row-major heightmap.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`, `height` - The heightmap resolution in samples, at least one each.
+    /// * `heights` - Elevation in metres, row by row from the north edge.
+    /// * `size_km` - The ground size covered by the heightmap, east-west and north-south.
+    ///
+    /// # Returns
+    ///
+    /// The patch, or a `PatchError` if the heightmap is empty or `heights` does not hold
+    /// `width * height` samples.
+    pub fn new(width: usize, height: usize, heights: Vec<f64>, size_km: (f64, f64)) -> Result<AuthoredPatch, PatchError> {
+        if width == 0 || height == 0 {
+            return Err(PatchError::Empty);
+        }
+        if width.checked_mul(height) != Some(heights.len()) {
+            return Err(PatchError::SampleCount { expected: width.saturating_mul(height), found: heights.len() });
+        }
+        Ok(AuthoredPatch {
+            width,
+            height,
+            heights,
+            biomes: None,
+            size_km,
+        })
+    }
+
+    /// Attaches a biome map with the same resolution as the heightmap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `biomes` does not hold `width * height` samples.
+    pub fn with_biomes(mut self, biomes: Vec<u8>) -> AuthoredPatch {
+        assert_eq!(biomes.len(), self.width * self.height, "biomes must hold width * height samples");
+        self.biomes = Some(biomes);
+        self
+    }
+
+    /// Returns the ground size of the patch in kilometres, east-west and north-south.
+    pub fn size_km(&self) -> (f64, f64) {
+        self.size_km
+    }
+
+    // Bilinear height at normalized patch coordinates, (0, 0) being the north-west corner
+    fn sample_height(&self, u: f64, v: f64) -> f64 {
+        let x = (u * (self.width - 1) as f64).clamp(0.0, (self.width - 1) as f64);
+        let y = (v * (self.height - 1) as f64).clamp(0.0, (self.height - 1) as f64);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+        let at = |cx: usize, cy: usize| self.heights[cy * self.width + cx];
+        let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+        let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    // Nearest biome code at normalized patch coordinates
+    fn sample_biome(&self, u: f64, v: f64) -> Option<u8> {
+        let biomes = self.biomes.as_ref()?;
+        let x = (u * (self.width - 1) as f64).round().clamp(0.0, (self.width - 1) as f64) as usize;
+        let y = (v * (self.height - 1) as f64).round().clamp(0.0, (self.height - 1) as f64) as usize;
+        Some(biomes[y * self.width + x])
+    }
+}
+
+/// Where and how to embed a patch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StitchPlacement {
+    pub latitude: f64,  // Centre of the patch in degrees
+    pub longitude: f64, // Centre of the patch in degrees
+    pub rotation: f64,  // Clockwise rotation of the patch's north edge from true north, in degrees
+    pub blend_km: f64,  // Width of the border over which the patch fades into procedural terrain
+}
+
+/// The cells a stitched patch covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StitchedArea {
+    cells: Vec<usize>,        // Every cell touched by the patch
+    weights: Vec<f64>,        // Blend weight of each cell, 1.0 where the patch is used unchanged
+    biomes: Vec<Option<u8>>,  // Authored biome of each cell, where the patch defines one
+    interior: HashSet<usize>, // Cells the patch fully owns
+}
+
+impl Planet {
+    /// Embeds an authored patch into the elevation field.
+    ///
+    /// The patch is laid on the tangent plane at its centre, rotated, and sampled bilinearly at
+    /// every cell site it covers. Within `blend_km` of the patch edge the authored heights are
+    /// blended smoothly into the procedural terrain so no seam is visible.
+    ///
+    /// Roads and other paths generated before stitching can be moved out of the authored area
+    /// with `StitchedArea::reroute`. Rivers computed before stitching still follow the old terrain;
+    /// use `stitch_patch_with_drainage` to route them again.
+    ///
+    /// # Returns
+    ///
+    /// The cells covered by the patch with their blend weights and authored biomes.
+    pub fn stitch_patch(&mut self, patch: &AuthoredPatch, placement: &StitchPlacement) -> StitchedArea {
+        let centre: Vec3 = lat_lon_to_unit(placement.latitude, placement.longitude);
+        let east = {
+            let e = cross((0.0, 0.0, 1.0), centre);
+            if dot(e, e) < 1e-12 { (0.0, 1.0, 0.0) } else { normalize(e) }
+        };
+        let north = cross(centre, east);
+        let (sin, cos) = placement.rotation.to_radians().sin_cos();
+        let (half_x, half_y) = (patch.size_km.0 / 2.0, patch.size_km.1 / 2.0);
+        let blend = placement.blend_km.max(f64::EPSILON);
+        let radius = self.radius();
+
+        // Patch-local (u, v) and distance inside the patch edge for a unit-sphere point
+        let locate = |p: Vec3| -> Option<(f64, f64, f64)> {
+            let along = dot(p, centre);
+            if along <= 0.0 {
+                return None;
+            }
+            // Gnomonic projection onto the tangent plane, in kilometres
+            let (x, y) = (dot(p, east) / along * radius, dot(p, north) / along * radius);
+            let (px, py) = (x * cos - y * sin, x * sin + y * cos);
+            let inside = (half_x - px.abs()).min(half_y - py.abs());
+            (inside >= 0.0).then_some(((px + half_x) / patch.size_km.0, (half_y - py) / patch.size_km.1, inside))
+        };
+
+        let start = self.nearest_cell(centre, 0);
+        let mut elevation = self.elevation().to_vec();
+        let mut area = StitchedArea {
+            cells: Vec::new(),
+            weights: Vec::new(),
+            biomes: Vec::new(),
+            interior: HashSet::new(),
+        };
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(cell) = queue.pop_front() {
+            let Some((u, v, inside)) = locate(self.sites()[cell]) else {
+                continue;
+            };
+            let t = (inside / blend).min(1.0);
+            let weight = t * t * (3.0 - 2.0 * t);
+            elevation[cell] += (patch.sample_height(u, v) - elevation[cell]) * weight;
+            area.cells.push(cell);
+            area.weights.push(weight);
+            area.biomes.push(if weight >= 0.5 { patch.sample_biome(u, v) } else { None });
+            if weight >= 1.0 {
+                area.interior.insert(cell);
+            }
+            for &neighbor in self.neighbors(cell) {
+                if seen.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        self.set_elevation(elevation);
+        area
+    }
+
+    /// Embeds an authored patch into the elevation field, as `stitch_patch` does, and routes the
+    /// planet's rivers again over the new terrain.
+    ///
+    /// Rivers that crossed the seam follow the authored valleys, or pond against authored ridges
+    /// and spill elsewhere. Their discharge carries on downstream of the patch, and lakes whose rim
+    /// the patch moved fill to a new level upstream of it, so the whole planet is routed again.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - The authored heightmap.
+    /// * `placement` - Where and how to embed it.
+    /// * `precipitation` - Mean annual precipitation of every cell in mm/year, e.g.
+    ///   `Planet::annual_precipitation`.
+    ///
+    /// # Returns
+    ///
+    /// The cells covered by the patch, and the drainage of the stitched planet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precipitation` does not have one value per cell.
+    pub fn stitch_patch_with_drainage(&mut self, patch: &AuthoredPatch, placement: &StitchPlacement, precipitation: &[f64]) -> (StitchedArea, Drainage) {
+        assert_eq!(precipitation.len(), self.cell_count(), "precipitation must have one value per cell");
+        let area = self.stitch_patch(patch, placement);
+        (area, Drainage::from_precipitation(self, precipitation))
+    }
+}
+
+impl StitchedArea {
+    /// Returns every cell touched by the patch.
+    pub fn cells(&self) -> &[usize] {
+        &self.cells
+    }
+
+    /// Returns the blend weight of each cell in `cells()`; `1.0` means fully authored.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Returns the authored biome code of each cell in `cells()`, where one is defined.
+    pub fn biomes(&self) -> &[Option<u8>] {
+        &self.biomes
+    }
+
+    /// Returns `true` if the cell lies in the fully authored interior of the patch.
+    pub fn contains(&self, cell: usize) -> bool {
+        self.interior.contains(&cell)
+    }
+
+    /// Reroutes a path (such as a road) around the authored interior.
+    ///
+    /// Paths that don't enter the interior are returned unchanged. Otherwise the path is split
+    /// where it enters and leaves the interior and the gap is bridged with the cheapest route that
+    /// avoids it; endpoints inside the interior are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the path belongs to.
+    /// * `path` - The cells of the path, in order.
+    /// * `step_cost` - The cost of moving between neighboring cells, as for `Planet::find_path`.
+    ///
+    /// # Returns
+    ///
+    /// The rerouted path, or `None` if there is no way around the patch.
+    pub fn reroute<F>(&self, planet: &Planet, path: &[usize], step_cost: F) -> Option<Vec<usize>>
+    where
+        F: Fn(usize, usize) -> Option<f64>,
+    {
+        if !path.iter().any(|c| self.contains(*c)) {
+            return Some(path.to_vec());
+        }
+        let (first, last) = (*path.first()?, *path.last()?);
+        let blocked = |cell: usize| self.contains(cell) && cell != first && cell != last;
+        let mut rerouted: Vec<usize> = Vec::new();
+        let mut index = 0;
+        while index < path.len() {
+            let cell = path[index];
+            if !blocked(cell) {
+                rerouted.push(cell);
+                index += 1;
+                continue;
+            }
+            // Bridge from the last kept cell to the first cell after the blocked run
+            let resume = (index..path.len()).find(|&i| !blocked(path[i]))?;
+            let from = *rerouted.last()?;
+            let (detour, _) = planet.find_path(from, path[resume], |a, b| if blocked(b) { None } else { step_cost(a, b) })?;
+            rerouted.extend_from_slice(&detour[1..detour.len() - 1]);
+            index = resume;
+        }
+        Some(rerouted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procedural::PlanetParameters;
+    use uuid::Uuid;
+
+    #[test]
+    fn empty_and_mismatched_heightmaps_are_rejected() {
+        assert_eq!(AuthoredPatch::new(0, 4, Vec::new(), (10.0, 10.0)), Err(PatchError::Empty));
+        assert_eq!(AuthoredPatch::new(4, 0, Vec::new(), (10.0, 10.0)), Err(PatchError::Empty));
+        assert_eq!(AuthoredPatch::new(3, 2, vec![0.0; 5], (10.0, 10.0)), Err(PatchError::SampleCount { expected: 6, found: 5 }));
+        assert_eq!(AuthoredPatch::new(usize::MAX, 2, vec![0.0; 5], (10.0, 10.0)), Err(PatchError::SampleCount { expected: usize::MAX, found: 5 }));
+        // A single sample is a flat patch
+        let patch = AuthoredPatch::new(1, 1, vec![250.0], (10.0, 10.0)).unwrap();
+        assert_eq!(patch.sample_height(0.3, 0.9), 250.0);
+    }
+
+    #[test]
+    fn rivers_crossing_the_seam_are_rerouted() {
+        let mut parameters = PlanetParameters::from_guid(Uuid::from_u128(0x545));
+        parameters.samples = 2000;
+        let mut planet = parameters.build();
+        let precipitation = planet.annual_precipitation();
+        let before = Drainage::from_precipitation(&planet, &precipitation);
+
+        // A ridge across the largest river
+        let river = (0..planet.cell_count()).filter(|&cell| planet.elevation()[cell] >= 0.0).max_by(|&a, &b| before.flow()[a].total_cmp(&before.flow()[b])).unwrap();
+        let (latitude, longitude) = planet.lat_lon(river);
+        let heights = (0..256).map(|i| 3000.0 * (1.0 - (2.0 * (i / 16) as f64 / 15.0 - 1.0).abs())).collect();
+        let patch = AuthoredPatch::new(16, 16, heights, (1500.0, 1500.0)).unwrap();
+        let placement = StitchPlacement { latitude, longitude, rotation: 0.0, blend_km: 200.0 };
+        let (area, after) = planet.stitch_patch_with_drainage(&patch, &placement, &precipitation);
+
+        // Outside lakes, water only runs downhill or along the flat
+        let elevation = planet.elevation();
+        let uphill = |drainage: &Drainage, cell: usize| elevation[cell] >= 0.0 && drainage.lake_of(cell).is_none() && drainage.downstream(cell).is_some_and(|down| elevation[down] > elevation[cell]);
+        assert!(area.cells().iter().any(|&cell| uphill(&before, cell)));
+        assert!((0..planet.cell_count()).all(|cell| !uphill(&after, cell)));
+        assert_eq!(after, Drainage::from_precipitation(&planet, &precipitation));
+    }
+}
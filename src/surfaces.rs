@@ -0,0 +1,361 @@
+use crate::geom::spherical;
+use crate::image::Image;
+use crate::noise::fbm;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use crate::render::gradient_color;
+use crate::render::map::{cell_colors, MapBase};
+use crate::star_system::{MoonKind, PlanetKind};
+use crate::vector::{add, cross, dot, normalize, scale, sub, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+// Rocky bodies at or above this equilibrium temperature in kelvin have molten surfaces
+const LAVA_TEMPERATURE: f64 = 600.0;
+// Rocky bodies below this equilibrium temperature in kelvin are sealed under ice
+const ICE_TEMPERATURE: f64 = 130.0;
+
+/// Which surface pipeline shapes and colors a planet.
+///
+/// Deserializes from its short name or its variant name, e.g. `"gas_giant"` in TOML or `GasGiant`
+/// in RON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurfaceKind {
+    /// Continents and oceans from the terrain generator, colored by material.
+    #[default]
+    #[serde(alias = "Rocky")]
+    Rocky,
+    /// Banded hydrogen/helium cloud tops in creams and browns, with vortex storms.
+    #[serde(alias = "GasGiant")]
+    GasGiant,
+    /// Banded cloud tops in pale blues, softer than a gas giant's, with vortex storms.
+    #[serde(alias = "IceGiant")]
+    IceGiant,
+    /// A frozen shell over the oceans, split by a network of cracks.
+    #[serde(alias = "Ice")]
+    Ice,
+    /// Dark basalt crust with glowing lava lowlands and fissures; no oceans.
+    #[serde(alias = "Lava")]
+    Lava,
+}
+
+impl SurfaceKind {
+    /// Every surface kind.
+    pub const ALL: [SurfaceKind; 5] = [SurfaceKind::Rocky, SurfaceKind::GasGiant, SurfaceKind::IceGiant, SurfaceKind::Ice, SurfaceKind::Lava];
+
+    /// Returns the kind's name, e.g. `"gas_giant"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SurfaceKind::Rocky => "rocky",
+            SurfaceKind::GasGiant => "gas_giant",
+            SurfaceKind::IceGiant => "ice_giant",
+            SurfaceKind::Ice => "ice",
+            SurfaceKind::Lava => "lava",
+        }
+    }
+
+    /// Looks a kind up by its short name or variant name, ignoring case and underscores.
+    pub fn from_name(name: &str) -> Option<SurfaceKind> {
+        let name = name.to_lowercase().replace('_', "");
+        SurfaceKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().replace('_', "") == name || format!("{:?}", kind).to_lowercase() == name)
+    }
+
+    /// Selects the surface of a planet from its composition and temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The planet's composition; giants get banded cloud tops.
+    /// * `equilibrium_temperature` - The planet's equilibrium temperature in kelvin, e.g.
+    ///   `SystemPlanet::equilibrium_temperature`. Rocky planets at 600 K or more are lava worlds
+    ///   and those below 130 K ice worlds.
+    pub fn for_planet(kind: PlanetKind, equilibrium_temperature: f64) -> SurfaceKind {
+        match kind {
+            PlanetKind::GasGiant => SurfaceKind::GasGiant,
+            PlanetKind::IceGiant => SurfaceKind::IceGiant,
+            PlanetKind::Rocky => SurfaceKind::for_rock(equilibrium_temperature),
+        }
+    }
+
+    /// Selects the surface of a moon: icy moons are ice worlds and rocky moons follow their
+    /// equilibrium temperature in kelvin as in `for_planet`.
+    pub fn for_moon(kind: MoonKind, equilibrium_temperature: f64) -> SurfaceKind {
+        match kind {
+            MoonKind::Icy => SurfaceKind::Ice,
+            MoonKind::Rocky => SurfaceKind::for_rock(equilibrium_temperature),
+        }
+    }
+
+    fn for_rock(equilibrium_temperature: f64) -> SurfaceKind {
+        if equilibrium_temperature >= LAVA_TEMPERATURE {
+            SurfaceKind::Lava
+        } else if equilibrium_temperature < ICE_TEMPERATURE {
+            SurfaceKind::Ice
+        } else {
+            SurfaceKind::Rocky
+        }
+    }
+}
+
+/// A long-lived vortex in a giant's cloud tops, like Jupiter's Great Red Spot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Storm {
+    pub center: Vec3,  // Eye of the storm on the unit sphere
+    pub radius: f64,   // Angular radius in radians
+    pub strength: f64, // Twist of the cloud bands at the eye in radians; positive is counter-clockwise seen from outside
+}
+
+/// The colors of a planet's surface from one of the `SurfaceKind` pipelines, one value per cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Surface {
+    kind: SurfaceKind,
+    colors: Vec<[u8; 3]>, // sRGB
+    emission: Vec<f64>,   // Glow, 0.0 (none) to 1.0 (molten)
+    storms: Vec<Storm>,
+}
+
+impl Planet {
+    /// Reshapes the elevation for a surface kind, after `generate_terrain`.
+    ///
+    /// Giants have no solid ground, so their elevation is flattened to 0 m. Ice worlds freeze
+    /// their oceans into a shell at 0 m and keep the land. Lava worlds are lifted so their
+    /// lowest point is at 0 m, leaving no oceans. Rocky planets are left unchanged.
+    pub fn shape_surface(&mut self, kind: SurfaceKind) {
+        let elevation: Vec<f64> = match kind {
+            SurfaceKind::Rocky => return,
+            SurfaceKind::GasGiant | SurfaceKind::IceGiant => vec![0.0; self.cell_count()],
+            SurfaceKind::Ice => self.elevation().iter().map(|&height| height.max(0.0)).collect(),
+            SurfaceKind::Lava => {
+                let lowest = self.elevation().iter().copied().fold(f64::INFINITY, f64::min);
+                self.elevation().iter().map(|&height| height - lowest).collect()
+            }
+        };
+        self.set_elevation(elevation);
+    }
+}
+
+impl Surface {
+    /// Generates the surface colors of a planet.
+    ///
+    /// Giants get latitude bands from a few summed waves, frayed by turbulence and twisted by
+    /// one large and several small storms. Ice worlds get bright ice marked by great-circle
+    /// cracks. Lava worlds get dark basalt with glowing lowlands and fissures. Rocky planets
+    /// take the colors of their materials, as a `MapBase::Biomes` map shows them.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet, already reshaped with `Planet::shape_surface`.
+    /// * `kind` - The surface pipeline.
+    /// * `seed` - The surface seed, e.g. `PlanetParameters::surface_seed`.
+    pub fn generate(planet: &Planet, kind: SurfaceKind, seed: u64) -> Surface {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let count = planet.cell_count();
+        let (colors, emission, storms) = match kind {
+            SurfaceKind::Rocky => (cell_colors(planet, MapBase::Biomes), vec![0.0; count], Vec::new()),
+            SurfaceKind::GasGiant | SurfaceKind::IceGiant => {
+                let storms = generate_storms(&mut rng);
+                (giant_colors(planet, kind, &storms, &mut rng), vec![0.0; count], storms)
+            }
+            SurfaceKind::Ice => (ice_colors(planet, &mut rng), vec![0.0; count], Vec::new()),
+            SurfaceKind::Lava => {
+                let emission = lava_emission(planet, &mut rng);
+                let colors = emission.iter().map(|&glow| gradient_color(&LAVA_STOPS, glow)).collect();
+                (colors, emission, Vec::new())
+            }
+        };
+        Surface { kind, colors, emission, storms }
+    }
+
+    /// Returns the pipeline the surface was generated by.
+    pub fn kind(&self) -> SurfaceKind {
+        self.kind
+    }
+
+    /// Returns the sRGB color of every cell.
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    /// Returns the glow of every cell, 0.0 (none) to 1.0 (molten), for an emissive texture.
+    pub fn emission(&self) -> &[f64] {
+        &self.emission
+    }
+
+    /// Returns the vortices of a giant, largest first; empty for other kinds.
+    pub fn storms(&self) -> &[Storm] {
+        &self.storms
+    }
+
+    /// Renders the surface colors as a texture, ready for `Image::write_png`.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the surface was generated for.
+    /// * `projection` - The map projection of the texture.
+    /// * `width`, `height` - The texture size in pixels.
+    ///
+    /// # Returns
+    ///
+    /// An opaque image, transparent outside the projected globe.
+    pub fn color_texture(&self, planet: &Planet, projection: Projection, width: usize, height: usize) -> Image {
+        self.texture(planet, projection, width, height, |cell| {
+            let [r, g, b] = self.colors[cell];
+            [r, g, b, 255]
+        })
+    }
+
+    /// Renders the emission as a grayscale texture, laid out like `color_texture`.
+    pub fn emission_texture(&self, planet: &Planet, projection: Projection, width: usize, height: usize) -> Image {
+        self.texture(planet, projection, width, height, |cell| {
+            let value = (self.emission[cell].clamp(0.0, 1.0) * 255.0).round() as u8;
+            [value, value, value, 255]
+        })
+    }
+
+    fn texture(&self, planet: &Planet, projection: Projection, width: usize, height: usize, color: impl Fn(usize) -> [u8; 4]) -> Image {
+        let mut image = Image::new(width, height, [0, 0, 0, 0]);
+        for (pixel, cell) in planet.rasterize_cells(projection, width, height).into_iter().enumerate() {
+            if let Some(cell) = cell {
+                image.set(pixel % width, pixel / width, color(cell));
+            }
+        }
+        image
+    }
+}
+
+// Band colors of a gas giant and an ice giant, over the band value from -1 to 1
+const GAS_GIANT_STOPS: [(f64, [f64; 3]); 5] = [
+    (-1.0, [120.0, 80.0, 55.0]),
+    (-0.4, [175.0, 130.0, 90.0]),
+    (0.0, [210.0, 180.0, 140.0]),
+    (0.4, [235.0, 220.0, 195.0]),
+    (1.0, [250.0, 245.0, 230.0]),
+];
+const ICE_GIANT_STOPS: [(f64, [f64; 3]); 3] = [
+    (-1.0, [90.0, 150.0, 200.0]),
+    (0.0, [140.0, 200.0, 225.0]),
+    (1.0, [190.0, 230.0, 240.0]),
+];
+// The reddish tint of the largest storm's core
+const STORM_COLOR: [f64; 3] = [190.0, 90.0, 60.0];
+const ICE_STOPS: [(f64, [f64; 3]); 3] = [(-1.0, [185.0, 200.0, 210.0]), (0.0, [220.0, 228.0, 235.0]), (1.0, [245.0, 248.0, 250.0])];
+// The reddish brown of crack lineae, as on Europa
+const CRACK_COLOR: [f64; 3] = [150.0, 95.0, 70.0];
+const LAVA_STOPS: [(f64, [f64; 3]); 4] = [
+    (0.0, [35.0, 32.0, 32.0]),
+    (0.3, [120.0, 25.0, 10.0]),
+    (0.7, [235.0, 100.0, 20.0]),
+    (1.0, [255.0, 220.0, 110.0]),
+];
+
+// One large storm and a few small ones, in the cloud bands away from the poles
+fn generate_storms(rng: &mut StdRng) -> Vec<Storm> {
+    let count = rng.gen_range(3..8);
+    (0..count)
+        .map(|index| {
+            let latitude = rng.gen_range(10.0f64..50.0) * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+            let radius = if index == 0 { rng.gen_range(0.12..0.2) } else { rng.gen_range(0.03..0.07) };
+            // Anticyclones turn against the planet's spin: clockwise in the north
+            let strength = -latitude.signum() * rng.gen_range(2.0..4.0);
+            Storm { center: spherical::from_lat_lon(latitude, rng.gen_range(-180.0..180.0)), radius, strength }
+        })
+        .collect()
+}
+
+// Rotates a point about an axis through the centre of the sphere
+fn rotate(point: Vec3, axis: Vec3, angle: f64) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    add(add(scale(point, cos), scale(cross(axis, point), sin)), scale(axis, dot(axis, point) * (1.0 - cos)))
+}
+
+fn giant_colors(planet: &Planet, kind: SurfaceKind, storms: &[Storm], rng: &mut StdRng) -> Vec<[u8; 3]> {
+    let stops: &[(f64, [f64; 3])] = if kind == SurfaceKind::GasGiant { &GAS_GIANT_STOPS } else { &ICE_GIANT_STOPS };
+    let waves: Vec<(f64, f64, f64)> = (0..4).map(|i| (rng.gen_range(3.0..7.0) * (i + 1) as f64, rng.gen_range(0.0..std::f64::consts::TAU), 0.6f64.powi(i))).collect();
+    let amplitude: f64 = waves.iter().map(|wave| wave.2).sum();
+    let turbulence_seed = rng.gen();
+    let turbulence = if kind == SurfaceKind::GasGiant { 0.08 } else { 0.03 };
+    planet
+        .sites()
+        .par_iter()
+        .map(|&site| {
+            // Each storm twists the bands around its eye, most strongly at the centre
+            let mut point = site;
+            let mut core: f64 = 0.0;
+            for (index, storm) in storms.iter().enumerate() {
+                let falloff = (1.0 - spherical::distance(site, storm.center) / storm.radius).max(0.0);
+                point = rotate(point, storm.center, storm.strength * falloff * falloff);
+                if index == 0 {
+                    core = core.max(falloff);
+                }
+            }
+            // Turbulence stretched along the bands, so it shears east-west
+            let stretched = (point.0 * 2.0, point.1 * 2.0, point.2 * 12.0);
+            let latitude = point.2.asin() + turbulence * fbm(turbulence_seed, stretched, 4, 2.0, 0.5);
+            let band = waves.iter().map(|&(frequency, phase, weight)| weight * (frequency * latitude + phase).sin()).sum::<f64>() / amplitude;
+            let [r, g, b] = gradient_color(stops, band);
+            let tint = (core * 2.0).min(1.0) * if kind == SurfaceKind::GasGiant { 0.7 } else { 0.3 };
+            let mix = |channel: u8, storm: f64| (channel as f64 + (storm - channel as f64) * tint).round() as u8;
+            [mix(r, STORM_COLOR[0]), mix(g, STORM_COLOR[1]), mix(b, STORM_COLOR[2])]
+        })
+        .collect()
+}
+
+// Angular distance from a point to the great-circle arc between two points
+fn arc_distance(point: Vec3, a: Vec3, b: Vec3) -> f64 {
+    let normal = normalize(cross(a, b));
+    let offset = dot(point, normal);
+    let foot = normalize(sub(point, scale(normal, offset)));
+    let span = spherical::distance(a, b);
+    if (spherical::distance(a, foot) + spherical::distance(foot, b) - span).abs() < 1e-9 {
+        offset.clamp(-1.0, 1.0).asin().abs()
+    } else {
+        spherical::distance(point, a).min(spherical::distance(point, b))
+    }
+}
+
+fn ice_colors(planet: &Planet, rng: &mut StdRng) -> Vec<[u8; 3]> {
+    // Cracks are at least about a cell wide so they show at any resolution
+    let spacing = (4.0 * std::f64::consts::PI / planet.cell_count().max(1) as f64).sqrt();
+    let cracks: Vec<(Vec3, Vec3, f64)> = (0..rng.gen_range(20..40))
+        .map(|_| {
+            let start = spherical::from_lat_lon(rng.gen::<f64>().mul_add(2.0, -1.0).asin().to_degrees(), rng.gen_range(-180.0..180.0));
+            let heading = normalize(cross(start, spherical::from_lat_lon(rng.gen_range(-90.0..90.0), rng.gen_range(-180.0..180.0))));
+            let end = rotate(start, heading, rng.gen_range(0.3..2.0));
+            (start, end, spacing * rng.gen_range(0.5..1.2))
+        })
+        .collect();
+    let shading_seed = rng.gen();
+    planet
+        .sites()
+        .par_iter()
+        .map(|&site| {
+            let [r, g, b] = gradient_color(&ICE_STOPS, fbm(shading_seed, scale(site, 6.0), 4, 2.0, 0.5) * 2.0);
+            let crack = cracks.iter().map(|&(start, end, width)| (1.0 - arc_distance(site, start, end) / width).max(0.0)).fold(0.0, f64::max);
+            let mix = |channel: u8, dark: f64| (channel as f64 + (dark - channel as f64) * crack).round() as u8;
+            [mix(r, CRACK_COLOR[0]), mix(g, CRACK_COLOR[1]), mix(b, CRACK_COLOR[2])]
+        })
+        .collect()
+}
+
+fn lava_emission(planet: &Planet, rng: &mut StdRng) -> Vec<f64> {
+    let elevation = planet.elevation();
+    let highest = elevation.iter().copied().fold(0.0, f64::max).max(1.0);
+    let fissure_seed = rng.gen();
+    planet
+        .sites()
+        .par_iter()
+        .zip(elevation)
+        .map(|(&site, &height)| {
+            // Lava pools in the lowest tenth of the relief, crusting over toward its edge
+            let pool = ((0.1 * highest - height) / (0.05 * highest)).clamp(0.0, 1.0);
+            // Fissures follow the zero crossings of the noise, a web of thin lines
+            let ridge = 1.0 - fbm(fissure_seed, scale(site, 3.0), 2, 2.0, 0.5).abs();
+            let fissure = ((ridge - 0.9) / 0.1).max(0.0);
+            pool.max(fissure).min(1.0)
+        })
+        .collect()
+}
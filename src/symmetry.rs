@@ -0,0 +1,170 @@
+use crate::geom::spherical::from_lat_lon;
+use crate::planet::Planet;
+use crate::vector::{add, cross, dot, normalize, scale, sub, Vec3};
+
+/// A great circle, splitting the sphere into two hemispheres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GreatCircle {
+    pub pole: Vec3, // Unit normal of the circle's plane, the centre of its positive hemisphere
+}
+
+impl GreatCircle {
+    /// The great circle whose positive hemisphere is centred on a point, e.g. the equator for the
+    /// north pole.
+    pub fn from_pole(latitude: f64, longitude: f64) -> GreatCircle {
+        GreatCircle { pole: from_lat_lon(latitude, longitude) }
+    }
+
+    /// The great circle through two points, with the positive hemisphere on the left going from
+    /// `a` to `b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the points coincide or are antipodal, so no single circle passes through both.
+    pub fn through(a: (f64, f64), b: (f64, f64)) -> GreatCircle {
+        let normal = cross(from_lat_lon(a.0, a.1), from_lat_lon(b.0, b.1));
+        assert!(dot(normal, normal) > 1e-24, "no single great circle passes through {:?} and {:?}", a, b);
+        GreatCircle { pole: normalize(normal) }
+    }
+
+    /// Returns the sine of a point's angular distance from the circle: positive in the positive
+    /// hemisphere, negative in the other, zero on the circle.
+    pub fn side(&self, point: Vec3) -> f64 {
+        dot(normalize(point), self.pole)
+    }
+
+    /// Returns the mirror image of a point across the circle.
+    pub fn reflect(&self, point: Vec3) -> Vec3 {
+        sub(point, scale(self.pole, 2.0 * dot(point, self.pole)))
+    }
+}
+
+/// A symmetry of the sphere, mapping every point to its image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    /// Every point to the opposite side of the planet.
+    Antipodal,
+    /// Every point to its mirror image across a great circle.
+    Reflection(GreatCircle),
+    /// Every point turned about an axis through the planet's centre, anticlockwise seen from above
+    /// `pole`.
+    Rotation { pole: Vec3, degrees: f64 },
+}
+
+impl Symmetry {
+    /// Returns the image of a point on the unit sphere.
+    pub fn apply(&self, point: Vec3) -> Vec3 {
+        match *self {
+            Symmetry::Antipodal => scale(point, -1.0),
+            Symmetry::Reflection(circle) => circle.reflect(point),
+            Symmetry::Rotation { pole, degrees } => {
+                // Rodrigues' rotation formula
+                let axis = normalize(pole);
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                add(add(scale(point, cos), scale(cross(axis, point), sin)), scale(axis, dot(axis, point) * (1.0 - cos)))
+            }
+        }
+    }
+}
+
+impl Planet {
+    /// Returns the cell on the opposite side of the planet, the one nearest the antipode of the
+    /// cell's site.
+    pub fn antipode(&self, cell: usize) -> usize {
+        self.nearest_cell(scale(self.sites()[cell], -1.0), cell)
+    }
+
+    /// Returns the image of every cell under a symmetry: the cell nearest the image of its site.
+    ///
+    /// On a planet whose sites are themselves symmetric each cell maps exactly onto its
+    /// counterpart; otherwise cells map onto whichever cell covers their image, and several cells
+    /// may share one image.
+    pub fn symmetric_cells(&self, symmetry: Symmetry) -> Vec<usize> {
+        let mut hint = 0;
+        self.sites()
+            .iter()
+            .map(|&site| {
+                hint = self.nearest_cell(symmetry.apply(site), hint);
+                hint
+            })
+            .collect()
+    }
+
+    /// Returns which cells lie in the positive hemisphere of a great circle.
+    pub fn hemisphere(&self, circle: GreatCircle) -> Vec<bool> {
+        self.sites().iter().map(|&site| circle.side(site) >= 0.0).collect()
+    }
+
+    /// Makes a per-cell layer symmetric by copying the positive hemisphere of a great circle onto
+    /// the other: every cell in the negative hemisphere takes the value of its image under the
+    /// symmetry.
+    ///
+    /// Use a symmetry that carries the negative hemisphere onto the positive one, such as
+    /// `Symmetry::Antipodal` or a reflection across the same circle, so no copied value is itself
+    /// overwritten.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one value per cell.
+    pub fn mirror_layer<T: Copy>(&self, values: &mut [T], keep: GreatCircle, symmetry: Symmetry) {
+        assert_eq!(values.len(), self.cell_count(), "layer must have one value per cell");
+        let images = self.symmetric_cells(symmetry);
+        let source = values.to_vec();
+        for (cell, &image) in images.iter().enumerate() {
+            if keep.side(self.sites()[cell]) < 0.0 {
+                values[cell] = source[image];
+            }
+        }
+    }
+
+    /// Makes the terrain symmetric, mirroring elevation and rock types with `mirror_layer`, e.g.
+    /// for a competitive map where both sides must start equal.
+    pub fn mirror_terrain(&mut self, keep: GreatCircle, symmetry: Symmetry) {
+        let mut elevation = self.elevation().to_vec();
+        let mut rock_types = self.rock_types().to_vec();
+        self.mirror_layer(&mut elevation, keep, symmetry);
+        self.mirror_layer(&mut rock_types, keep, symmetry);
+        self.set_elevation(elevation);
+        self.set_rock_types(rock_types);
+    }
+
+    /// Makes a per-cell layer N-fold rotationally symmetric about the rotation axis: every cell
+    /// takes the value of its counterpart in the first wedge of `360 / folds` degrees of longitude
+    /// east of the prime meridian.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` does not have one value per cell.
+    pub fn repeat_layer<T: Copy>(&self, values: &mut [T], folds: u32) {
+        assert_eq!(values.len(), self.cell_count(), "layer must have one value per cell");
+        if folds <= 1 {
+            return;
+        }
+        let wedge = 360.0 / folds as f64;
+        let source = values.to_vec();
+        let mut hint = 0;
+        for (cell, &site) in self.sites().iter().enumerate() {
+            // Nudged so copies landing a rounding error short of a wedge boundary count as past it
+            let turns = (site.1.atan2(site.0).to_degrees().rem_euclid(360.0) / wedge + 1e-9).floor() % folds as f64;
+            if turns >= 1.0 {
+                let rotation = Symmetry::Rotation { pole: (0.0, 0.0, 1.0), degrees: -turns * wedge };
+                hint = self.nearest_cell(rotation.apply(site), hint);
+                values[cell] = source[hint];
+            }
+        }
+    }
+
+    /// Makes the terrain N-fold rotationally symmetric, repeating elevation and rock types with
+    /// `repeat_layer`. Climate and biomes follow from them, so every wedge plays the same.
+    pub fn repeat_terrain(&mut self, folds: u32) {
+        if folds <= 1 {
+            return;
+        }
+        let mut elevation = self.elevation().to_vec();
+        let mut rock_types = self.rock_types().to_vec();
+        self.repeat_layer(&mut elevation, folds);
+        self.repeat_layer(&mut rock_types, folds);
+        self.set_elevation(elevation);
+        self.set_rock_types(rock_types);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::planet::Planet;
+use crate::vector::{cross, dot, normalize, scale, sub, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// How two plates move relative to each other across a boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundaryKind {
+    /// Plates move toward each other: subduction zones and mountain belts.
+    Convergent,
+    /// Plates move apart: rifts and mid-ocean ridges.
+    Divergent,
+    /// Plates slide past each other: strike-slip faults.
+    Transform,
+}
+
+/// A rigid tectonic plate rotating about an Euler pole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plate {
+    pub euler_pole: Vec3,      // Unit rotation axis
+    pub angular_velocity: f64, // Degrees per million years
+    pub oceanic: bool,         // Whether most of the plate lies below sea level
+}
+
+/// One cell-to-cell edge where two plates meet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlateBoundary {
+    pub cell: usize,         // Cell on the first plate
+    pub neighbor: usize,     // Adjacent cell on the second plate
+    pub kind: BoundaryKind,
+    pub rate: f64,           // Relative plate speed across the boundary in cm/year
+    pub age: f64,            // Million years since the boundary formed
+}
+
+/// The plate layout of a planet and the geological history derived from it.
+#[derive(Debug, Clone)]
+pub struct Tectonics {
+    plates: Vec<Plate>,
+    plate_of: Vec<usize>,
+    boundaries: Vec<PlateBoundary>,
+    hotspots: Vec<usize>,
+    volcanic_activity: Vec<f64>,
+    boundary_distance: Vec<f64>, // Kilometres to the nearest plate boundary
+}
+
+// Relative speed (as a fraction of the total) below which motion counts as sliding
+const TRANSFORM_THRESHOLD: f64 = 0.35;
+// Distance in km over which arc volcanism fades away from a convergent boundary
+const ARC_WIDTH_KM: f64 = 400.0;
+// Radius in km of the volcanic province around a hotspot
+const HOTSPOT_RADIUS_KM: f64 = 300.0;
+
+#[derive(PartialEq)]
+struct Frontier(f64, usize, usize);
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Multi-source Dijkstra over the cell graph, returning kilometres to the nearest source and the
+// index into `sources` of that source
+pub(crate) fn nearest_source(planet: &Planet, sources: &[usize]) -> (Vec<f64>, Vec<usize>) {
+    let mut distance = vec![f64::INFINITY; planet.cell_count()];
+    let mut origin = vec![usize::MAX; planet.cell_count()];
+    let mut heap = BinaryHeap::new();
+    for (index, &source) in sources.iter().enumerate() {
+        if distance[source] > 0.0 {
+            distance[source] = 0.0;
+            origin[source] = index;
+            heap.push(Frontier(0.0, source, index));
+        }
+    }
+    while let Some(Frontier(d, cell, index)) = heap.pop() {
+        if d > distance[cell] {
+            continue;
+        }
+        for &neighbor in planet.neighbors(cell) {
+            let next = d + planet.distance_km(cell, neighbor);
+            if next < distance[neighbor] {
+                distance[neighbor] = next;
+                origin[neighbor] = index;
+                heap.push(Frontier(next, neighbor, index));
+            }
+        }
+    }
+    (distance, origin)
+}
+
+// Kilometres from every cell to the nearest source cell
+pub(crate) fn distance_from(planet: &Planet, sources: &[usize]) -> Vec<f64> {
+    nearest_source(planet, sources).0
+}
+
+// Classifies every cell-to-cell edge between two plates from their relative motion, taking the
+// age of each touching pair of plates, smaller index first, from `age`
+fn classify_boundaries(planet: &Planet, plates: &[Plate], plate_of: &[usize], mut age: impl FnMut((usize, usize)) -> f64) -> Vec<PlateBoundary> {
+    let mut boundaries = Vec::new();
+    let radius_cm = planet.radius() * 1.0e5;
+    for cell in 0..planet.cell_count() {
+        for &neighbor in planet.neighbors(cell) {
+            let (a, b) = (plate_of[cell], plate_of[neighbor]);
+            if a == b || cell > neighbor {
+                continue;
+            }
+            let site_a = planet.sites()[cell];
+            let site_b = planet.sites()[neighbor];
+            let mid = normalize(scale((site_a.0 + site_b.0, site_a.1 + site_b.1, site_a.2 + site_b.2), 0.5));
+            let velocity = |plate: &Plate| {
+                let omega = plate.angular_velocity.to_radians() / 1.0e6; // rad/year
+                scale(cross(plate.euler_pole, mid), omega * radius_cm)
+            };
+            let relative = sub(velocity(&plates[a]), velocity(&plates[b]));
+            let normal = normalize(sub(site_b, scale(site_a, dot(site_b, site_a))));
+            let speed = dot(relative, relative).sqrt();
+            let closing = dot(relative, normal);
+            let kind = if speed < f64::EPSILON || closing.abs() < TRANSFORM_THRESHOLD * speed {
+                BoundaryKind::Transform
+            } else if closing > 0.0 {
+                BoundaryKind::Convergent
+            } else {
+                BoundaryKind::Divergent
+            };
+            boundaries.push(PlateBoundary {
+                cell,
+                neighbor,
+                kind,
+                rate: speed,
+                age: age((a.min(b), a.max(b))),
+            });
+        }
+    }
+    boundaries
+}
+
+// The distance from every cell to the nearest boundary and its volcanic activity
+fn boundary_fields(planet: &Planet, plates: &[Plate], plate_of: &[usize], boundaries: &[PlateBoundary], hotspots: &[usize]) -> (Vec<f64>, Vec<f64>) {
+    let boundary_cells: Vec<usize> = boundaries.iter().flat_map(|b| [b.cell, b.neighbor]).collect();
+    let boundary_distance = distance_from(planet, &boundary_cells);
+
+    // Arc volcanism sits on the overriding side of convergent boundaries
+    let arc_cells: Vec<usize> = boundaries
+        .iter()
+        .filter(|b| b.kind == BoundaryKind::Convergent)
+        .map(|b| {
+            let (a, c) = (b.cell, b.neighbor);
+            if plates[plate_of[a]].oceanic && !plates[plate_of[c]].oceanic { c } else { a }
+        })
+        .collect();
+    let arc_distance = distance_from(planet, &arc_cells);
+    let hotspot_distance = distance_from(planet, hotspots);
+    let volcanic_activity = (0..planet.cell_count())
+        .map(|cell| {
+            let arc = (1.0 - arc_distance[cell] / ARC_WIDTH_KM).max(0.0);
+            let plume = (1.0 - hotspot_distance[cell] / HOTSPOT_RADIUS_KM).max(0.0);
+            arc.max(plume)
+        })
+        .collect();
+    (boundary_distance, volcanic_activity)
+}
+
+// Whether most of each plate's cells lie below sea level
+fn oceanic_plates(planet: &Planet, plate_of: &[usize], plate_count: usize) -> Vec<bool> {
+    let mut ocean_cells = vec![0usize; plate_count];
+    let mut total_cells = vec![0usize; plate_count];
+    for (cell, &plate) in plate_of.iter().enumerate() {
+        total_cells[plate] += 1;
+        if planet.is_ocean(cell) {
+            ocean_cells[plate] += 1;
+        }
+    }
+    (0..plate_count).map(|plate| ocean_cells[plate] * 2 > total_cells[plate]).collect()
+}
+
+impl Tectonics {
+    /// Generates a plate layout deterministically from a seed.
+    ///
+    /// Plates grow from random seed cells over the cell graph with randomized edge costs, which
+    /// gives irregular plate outlines. Each plate gets a random Euler pole; boundaries are then
+    /// classified from the relative plate motion, and hotspots are scattered at random.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet to partition.
+    /// * `seed` - The tectonics seed.
+    /// * `plate_count` - The number of plates (at least 2).
+    pub fn generate(planet: &Planet, seed: u64, plate_count: usize) -> Tectonics {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cells = planet.cell_count();
+        let plate_count = plate_count.clamp(2, cells.max(2));
+
+        // Grow plates with a randomized multi-source Dijkstra
+        let costs: Vec<f64> = (0..cells).map(|_| rng.gen_range(0.5..1.5)).collect();
+        let mut plate_of = vec![usize::MAX; cells];
+        let mut cost = vec![f64::INFINITY; cells];
+        let mut heap = BinaryHeap::new();
+        for plate in 0..plate_count.min(cells) {
+            let start = rng.gen_range(0..cells);
+            if cost[start] == 0.0 {
+                continue;
+            }
+            cost[start] = 0.0;
+            plate_of[start] = plate;
+            heap.push(Frontier(0.0, start, plate));
+        }
+        while let Some(Frontier(c, cell, plate)) = heap.pop() {
+            if c > cost[cell] {
+                continue;
+            }
+            for &neighbor in planet.neighbors(cell) {
+                let next = c + planet.distance_km(cell, neighbor) * costs[neighbor];
+                if next < cost[neighbor] {
+                    cost[neighbor] = next;
+                    plate_of[neighbor] = plate;
+                    heap.push(Frontier(next, neighbor, plate));
+                }
+            }
+        }
+        for plate in plate_of.iter_mut() {
+            if *plate == usize::MAX {
+                *plate = 0;
+            }
+        }
+
+        let oceanic = oceanic_plates(planet, &plate_of, plate_count);
+        let plates: Vec<Plate> = (0..plate_count)
+            .map(|plate| {
+                let z: f64 = rng.gen_range(-1.0..1.0);
+                let theta: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+                let r = (1.0 - z * z).sqrt();
+                Plate {
+                    euler_pole: (r * theta.cos(), r * theta.sin(), z),
+                    angular_velocity: rng.gen_range(0.2..1.2),
+                    oceanic: oceanic[plate],
+                }
+            })
+            .collect();
+
+        // Each pair of touching plates shares one boundary age
+        let mut pair_age: HashMap<(usize, usize), f64> = HashMap::new();
+        let boundaries = classify_boundaries(planet, &plates, &plate_of, |pair| *pair_age.entry(pair).or_insert_with(|| rng.gen_range(5.0..250.0)));
+
+        let hotspot_count = rng.gen_range(3..10).min(cells);
+        let hotspots: Vec<usize> = (0..hotspot_count).map(|_| rng.gen_range(0..cells)).collect();
+        let (boundary_distance, volcanic_activity) = boundary_fields(planet, &plates, &plate_of, &boundaries, &hotspots);
+
+        Tectonics {
+            plates,
+            plate_of,
+            boundaries,
+            hotspots,
+            volcanic_activity,
+            boundary_distance,
+        }
+    }
+
+    /// Returns every plate.
+    pub fn plates(&self) -> &[Plate] {
+        &self.plates
+    }
+
+    /// Returns the plate each cell belongs to.
+    pub fn plate_ids(&self) -> &[usize] {
+        &self.plate_of
+    }
+
+    /// Returns the plate a cell belongs to.
+    pub fn plate_of(&self, cell: usize) -> usize {
+        self.plate_of[cell]
+    }
+
+    /// Returns every cell-to-cell plate boundary edge.
+    pub fn boundaries(&self) -> &[PlateBoundary] {
+        &self.boundaries
+    }
+
+    /// Returns the cells sitting above mantle hotspots.
+    pub fn hotspots(&self) -> &[usize] {
+        &self.hotspots
+    }
+
+    /// Returns the distance from each cell to the nearest plate boundary in kilometres.
+    pub fn boundary_distance(&self) -> &[f64] {
+        &self.boundary_distance
+    }
+
+    /// Returns how volcanically active each cell is, from 0.0 (quiet) to 1.0 (on an arc or hotspot).
+    pub fn volcanic_activity(&self) -> &[f64] {
+        &self.volcanic_activity
+    }
+
+    /// Returns the boundaries of the given kind as cell pairs.
+    pub fn boundaries_of_kind(&self, kind: BoundaryKind) -> impl Iterator<Item = &PlateBoundary> {
+        self.boundaries.iter().filter(move |b| b.kind == kind)
+    }
+
+    /// Returns the distance from each cell to the nearest boundary of the given kind, in kilometres.
+    pub fn distance_to(&self, planet: &Planet, kind: BoundaryKind) -> Vec<f64> {
+        let cells: Vec<usize> = self.boundaries_of_kind(kind).flat_map(|b| [b.cell, b.neighbor]).collect();
+        distance_from(planet, &cells)
+    }
+
+    /// Finds the nearest boundary of the given kind for every cell.
+    ///
+    /// # Returns
+    ///
+    /// For each cell, the nearest boundary and its distance in kilometres, or `None` if the
+    /// planet has no boundary of that kind.
+    pub fn nearest_boundary(&self, planet: &Planet, kind: BoundaryKind) -> Vec<Option<(&PlateBoundary, f64)>> {
+        let boundaries: Vec<&PlateBoundary> = self.boundaries_of_kind(kind).collect();
+        let cells: Vec<usize> = boundaries.iter().map(|b| b.cell).collect();
+        let (distance, origin) = nearest_source(planet, &cells);
+        distance
+            .into_iter()
+            .zip(origin)
+            .map(|(d, index)| boundaries.get(index).map(|&b| (b, d)))
+            .collect()
+    }
+
+    /// Returns the cells on either side of any plate boundary.
+    pub fn boundary_cells(&self) -> Vec<usize> {
+        let mut cells: Vec<usize> = self.boundaries.iter().flat_map(|b| [b.cell, b.neighbor]).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    /// Returns the age in million years of the oldest boundary touching each cell, or `None`
+    /// for plate interiors.
+    pub fn boundary_age(&self) -> Vec<Option<f64>> {
+        let mut age = vec![None; self.plate_of.len()];
+        for boundary in &self.boundaries {
+            for cell in [boundary.cell, boundary.neighbor] {
+                let current: &mut Option<f64> = &mut age[cell];
+                *current = Some(current.map_or(boundary.age, |a: f64| a.max(boundary.age)));
+            }
+        }
+        age
+    }
+
+    /// Returns the number of graph hops from each cell to its plate's edge, a cheap proxy for
+    /// how deep in a stable plate interior (craton) a cell lies.
+    pub fn interior_depth(&self, planet: &Planet) -> Vec<usize> {
+        let mut depth = vec![usize::MAX; self.plate_of.len()];
+        let mut queue = VecDeque::new();
+        for cell in self.boundary_cells() {
+            depth[cell] = 0;
+            queue.push_back(cell);
+        }
+        while let Some(cell) = queue.pop_front() {
+            for &neighbor in planet.neighbors(cell) {
+                if depth[neighbor] == usize::MAX {
+                    depth[neighbor] = depth[cell] + 1;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        depth
+    }
+
+    /// Moves the plates to a new layout after they have drifted, e.g. by `Planet::advance_geology`.
+    ///
+    /// Boundaries are classified again for the new layout; boundaries between plates that already
+    /// touched grow older by `elapsed`, and new ones start at age 0. Hotspots stay put in the
+    /// mantle while the plates pass over them.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet with its elevation after the drift.
+    /// * `plate_of` - The plate of every cell after the drift.
+    /// * `elapsed` - Million years since the previous layout.
+    pub(crate) fn drift_to(&mut self, planet: &Planet, plate_of: Vec<usize>, elapsed: f64) {
+        let oceanic = oceanic_plates(planet, &plate_of, self.plates.len());
+        for (plate, oceanic) in self.plates.iter_mut().zip(oceanic) {
+            plate.oceanic = oceanic;
+        }
+        let ages: HashMap<(usize, usize), f64> = self
+            .boundaries
+            .iter()
+            .map(|b| {
+                let (a, c) = (self.plate_of[b.cell], self.plate_of[b.neighbor]);
+                ((a.min(c), a.max(c)), b.age + elapsed)
+            })
+            .collect();
+        self.boundaries = classify_boundaries(planet, &self.plates, &plate_of, |pair| ages.get(&pair).copied().unwrap_or(0.0));
+        self.plate_of = plate_of;
+        (self.boundary_distance, self.volcanic_activity) = boundary_fields(planet, &self.plates, &self.plate_of, &self.boundaries, &self.hotspots);
+    }
+}
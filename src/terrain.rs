@@ -0,0 +1,98 @@
+use crate::noise::{fbm, gradient_noise, mix64};
+use crate::vector::{scale, Vec3};
+use rayon::prelude::*;
+
+/// Controls the shape of generated elevation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainSettings {
+    pub continent_frequency: f64, // Noise frequency of continent shapes on the unit sphere
+    pub land_fraction: f64,       // Approximate share of the surface above sea level, 0.0 to 1.0
+    pub max_elevation: f64,       // Highest peaks in metres
+    pub max_depth: f64,           // Deepest ocean floor in metres (positive)
+    pub mountain_strength: f64,   // Weight of ridged mountain noise on land, 0.0 to 1.0
+}
+
+impl Default for TerrainSettings {
+    /// Earth-like settings: roughly 30% land, peaks near 8 km and trenches near 10 km.
+    fn default() -> Self {
+        TerrainSettings {
+            continent_frequency: 1.6,
+            land_fraction: 0.3,
+            max_elevation: 8000.0,
+            max_depth: 10000.0,
+            mountain_strength: 0.5,
+        }
+    }
+}
+
+// Ridged noise: sharp crests where the underlying noise crosses zero
+fn ridged(seed: u64, p: Vec3, octaves: u32) -> f64 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..octaves {
+        let n = gradient_noise(mix64(seed ^ (octave as u64 + 0x5EED)), scale(p, frequency));
+        sum += amplitude * (1.0 - n.abs()).powi(2);
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+/// Generates elevation for every site from seeded fractal noise.
+///
+/// Continents come from low-frequency noise thresholded to the requested land fraction, with
+/// ridged noise adding mountain ranges inland.
+///
+/// # Arguments
+///
+/// * `sites` - Unit-sphere cell sites.
+/// * `seed` - The terrain seed.
+/// * `settings` - The shape of the terrain.
+///
+/// # Returns
+///
+/// Elevation in metres relative to sea level, one value per site.
+pub fn generate_elevation(sites: &[Vec3], seed: u64, settings: &TerrainSettings) -> Vec<f64> {
+    let raw: Vec<f64> = sites.par_iter().map(|&p| continent_noise(p, seed, settings)).collect();
+    let levels = sea_levels(&raw, settings);
+    sites
+        .par_iter()
+        .zip(raw.par_iter())
+        .map(|(&p, &n)| shape_elevation(p, n, seed, levels, settings))
+        .collect()
+}
+
+// The raw continent noise at a site, before sea level is chosen
+pub(crate) fn continent_noise(p: Vec3, seed: u64, settings: &TerrainSettings) -> f64 {
+    fbm(seed, scale(p, settings.continent_frequency), 6, 2.0, 0.5)
+}
+
+// Chooses the sea level so that the requested fraction of sites is land, returning the sea level
+// and the lowest and highest raw values
+pub(crate) fn sea_levels(raw: &[f64], settings: &TerrainSettings) -> (f64, f64, f64) {
+    let mut sorted = raw.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let sea_level = if sorted.is_empty() {
+        0.0
+    } else {
+        let index = ((1.0 - settings.land_fraction.clamp(0.0, 1.0)) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[index]
+    };
+    let high = sorted.last().copied().unwrap_or(1.0).max(sea_level + f64::EPSILON);
+    let low = sorted.first().copied().unwrap_or(-1.0).min(sea_level - f64::EPSILON);
+    (sea_level, low, high)
+}
+
+// Turns raw continent noise into elevation in metres
+pub(crate) fn shape_elevation(p: Vec3, n: f64, seed: u64, (sea_level, low, high): (f64, f64, f64), settings: &TerrainSettings) -> f64 {
+    if n >= sea_level {
+        let t = (n - sea_level) / (high - sea_level);
+        let mountains = ridged(seed, scale(p, settings.continent_frequency * 4.0), 5);
+        let shape = t * (1.0 - settings.mountain_strength) + t.sqrt() * mountains * settings.mountain_strength;
+        shape.clamp(0.0, 1.0) * settings.max_elevation
+    } else {
+        let t = (sea_level - n) / (sea_level - low);
+        -t.sqrt() * settings.max_depth
+    }
+}
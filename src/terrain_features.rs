@@ -0,0 +1,399 @@
+use crate::geology::RockType;
+use crate::noise::{gradient_noise, mix64};
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+use crate::tectonics::{BoundaryKind, Tectonics};
+use crate::vector::scale;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+// Height in metres that canyon floors and sinkholes never cut below
+const CANYON_FLOOR: f64 = 1.0;
+
+// The value above which the given share of `values` lies
+fn share_threshold(values: &[f64], share: f64) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let share = share.clamp(0.0, 1.0);
+    if sorted.is_empty() || share >= 1.0 {
+        return f64::NEG_INFINITY;
+    }
+    sorted[((1.0 - share) * sorted.len() as f64) as usize]
+}
+
+/// A special terrain feature carved or built into the elevation after the terrain is generated.
+///
+/// Features are passes over the elevation: each one reads and rewrites the elevation the passes
+/// before it left, so they compose in any order through `Planet::apply_terrain_features`.
+pub trait TerrainFeature {
+    /// Reshapes the elevation.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet, for its cells, rock types and climate. Its own elevation is the
+    ///   one from before the passes ran.
+    /// * `elevation` - The elevation of every cell in metres, as earlier passes left it.
+    /// * `seed` - The seed of this pass.
+    fn apply(&self, planet: &Planet, elevation: &mut [f64], seed: u64);
+}
+
+/// Narrow, deep canyons cut along fault lines through dry land, where runoff follows the
+/// shattered rock and rare flash floods scour it down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotCanyons {
+    pub faults: Vec<(usize, usize)>, // Adjacent cells on either side of a fault
+    pub depth: f64,                  // Deepest cut in metres, in the driest land
+    pub max_precipitation: f64,      // Rain in mm/year above which slots widen into ordinary valleys
+}
+
+impl Default for SlotCanyons {
+    /// No faults yet, cuts up to 500 m deep and canyons only where less than 500 mm of rain falls
+    /// a year.
+    fn default() -> Self {
+        SlotCanyons {
+            faults: Vec::new(),
+            depth: 500.0,
+            max_precipitation: 500.0,
+        }
+    }
+}
+
+impl SlotCanyons {
+    /// Returns slot canyons with default settings along a planet's transform faults and rifts.
+    pub fn along_faults(tectonics: &Tectonics) -> SlotCanyons {
+        let faults = tectonics
+            .boundaries()
+            .iter()
+            .filter(|boundary| boundary.kind != BoundaryKind::Convergent)
+            .map(|boundary| (boundary.cell, boundary.neighbor))
+            .collect();
+        SlotCanyons { faults, ..SlotCanyons::default() }
+    }
+}
+
+impl TerrainFeature for SlotCanyons {
+    fn apply(&self, planet: &Planet, elevation: &mut [f64], seed: u64) {
+        let precipitation = planet.annual_precipitation();
+        let sites = planet.sites();
+        let mut cuts = vec![0.0f64; elevation.len()];
+        for &(a, b) in &self.faults {
+            if elevation[a] <= 0.0 || elevation[b] <= 0.0 {
+                continue;
+            }
+            for cell in [a, b] {
+                let aridity = (1.0 - precipitation[cell] / self.max_precipitation.max(f64::MIN_POSITIVE)).clamp(0.0, 1.0);
+                // The depth wanders along the fault
+                let variation = 0.5 + 0.5 * gradient_noise(seed, scale(sites[cell], 20.0)).clamp(-1.0, 1.0);
+                cuts[cell] = cuts[cell].max(self.depth * aridity * variation);
+            }
+        }
+        for (e, cut) in elevation.iter_mut().zip(cuts) {
+            if cut > 0.0 {
+                // Canyon floors stay above the sea
+                *e = (*e - cut).max(e.min(CANYON_FLOOR));
+            }
+        }
+    }
+}
+
+/// Fields of sinkholes pitting wet limestone country, where rain dissolves the bedrock and the
+/// roofs of caves collapse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KarstSinkholes {
+    pub frequency: f64,         // Noise frequency of karst fields on the unit sphere
+    pub field_share: f64,       // Share of limestone land inside a karst field, 0.0 to 1.0
+    pub sinkhole_chance: f64,   // Chance of a cell inside a field sinking, 0.0 to 1.0
+    pub depth: f64,             // Deepest sinkhole in metres below its lowest neighbor
+    pub min_precipitation: f64, // Rain in mm/year needed to dissolve limestone
+}
+
+impl Default for KarstSinkholes {
+    /// Fields over half the rainy limestone country, where one cell in two sinks up to 100 m below
+    /// its surroundings.
+    fn default() -> Self {
+        KarstSinkholes {
+            frequency: 12.0,
+            field_share: 0.5,
+            sinkhole_chance: 0.5,
+            depth: 100.0,
+            min_precipitation: 600.0,
+        }
+    }
+}
+
+impl TerrainFeature for KarstSinkholes {
+    fn apply(&self, planet: &Planet, elevation: &mut [f64], seed: u64) {
+        let precipitation = planet.annual_precipitation();
+        let rock_types = planet.rock_types();
+        let sites = planet.sites();
+        let karst: Vec<usize> = (0..elevation.len())
+            .filter(|&cell| rock_types[cell] == RockType::Limestone && elevation[cell] >= CANYON_FLOOR && precipitation[cell] >= self.min_precipitation)
+            .collect();
+        let fields: Vec<f64> = karst.iter().map(|&cell| gradient_noise(seed, scale(sites[cell], self.frequency))).collect();
+        let threshold = share_threshold(&fields, self.field_share);
+        let mut rng = StdRng::seed_from_u64(mix64(seed));
+        for (&cell, field) in karst.iter().zip(fields) {
+            let chance: f64 = rng.gen();
+            if field < threshold || chance >= self.sinkhole_chance {
+                continue;
+            }
+            let lowest = planet.neighbors(cell).iter().map(|&n| elevation[n]).fold(elevation[cell], f64::min);
+            let sunk = lowest - self.depth * rng.gen_range(0.2..1.0);
+            elevation[cell] = sunk.max(CANYON_FLOOR);
+        }
+    }
+}
+
+/// Flat-topped mesas and buttes standing over stepped arid plateaus, where hard cap rock shields
+/// the layers below while the land around it wears back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesas {
+    pub min_elevation: f64,     // Plateau height in metres where mesas begin
+    pub max_precipitation: f64, // Rain in mm/year above which the land is too wet for mesas
+    pub step: f64,              // Height of each cap rock layer in metres
+    pub frequency: f64,         // Noise frequency of mesa tops on the unit sphere
+    pub cap_share: f64,         // Share of the plateau still under cap rock, 0.0 to 1.0
+}
+
+impl Default for Mesas {
+    /// Mesas 200 m high on plateaus above 500 m with under 400 mm of rain a year, a third of the
+    /// plateau still capped.
+    fn default() -> Self {
+        Mesas {
+            min_elevation: 500.0,
+            max_precipitation: 400.0,
+            step: 200.0,
+            frequency: 40.0,
+            cap_share: 0.35,
+        }
+    }
+}
+
+impl TerrainFeature for Mesas {
+    fn apply(&self, planet: &Planet, elevation: &mut [f64], seed: u64) {
+        let precipitation = planet.annual_precipitation();
+        let sites = planet.sites();
+        let step = self.step.max(f64::MIN_POSITIVE);
+        let plateau: Vec<usize> = (0..elevation.len())
+            .filter(|&cell| elevation[cell] >= self.min_elevation && precipitation[cell] <= self.max_precipitation)
+            .collect();
+        let caps: Vec<f64> = plateau.iter().map(|&cell| gradient_noise(seed, scale(sites[cell], self.frequency))).collect();
+        let threshold = share_threshold(&caps, self.cap_share);
+        for (&cell, cap) in plateau.iter().zip(caps) {
+            // Capped cells keep the top of their layer; the rest wear down to the layer below
+            let layer = (elevation[cell] / step).floor() * step;
+            elevation[cell] = if cap >= threshold { layer + step } else { layer };
+        }
+    }
+}
+
+impl Planet {
+    /// Applies terrain features to the elevation in order, each pass working on the elevation the
+    /// passes before it left.
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - The passes to apply, e.g. `SlotCanyons`, `KarstSinkholes` and `Mesas`.
+    /// * `seed` - The feature seed; every pass gets its own seed derived from it and its position.
+    pub fn apply_terrain_features(&mut self, features: &[&dyn TerrainFeature], seed: u64) {
+        let mut elevation = self.elevation().to_vec();
+        for (index, feature) in features.iter().enumerate() {
+            feature.apply(self, &mut elevation, mix64(seed ^ mix64(index as u64)));
+        }
+        self.set_elevation(elevation);
+    }
+}
+
+/// A built-in stage of planet generation that terrain passes run after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildStage {
+    /// Noise elevation and rock types, from `Planet::generate_terrain`.
+    Terrain,
+    /// Reshaping for the surface kind, from `Planet::shape_surface`.
+    Surface,
+}
+
+/// The layers of a planet under construction that a `TerrainPass` may rewrite.
+///
+/// Changes are written back to the planet once the pass returns, so `planet` shows the layers as
+/// they stood before the pass while `elevation` and `rock_types` show its work so far.
+#[derive(Debug, Clone)]
+pub struct PlanetLayers {
+    planet: Planet,
+    elevation: Vec<f64>,
+    rock_types: Vec<RockType>,
+}
+
+impl PlanetLayers {
+    /// Opens the layers of a planet for a pass.
+    pub fn new(planet: Planet) -> PlanetLayers {
+        PlanetLayers {
+            elevation: planet.elevation().to_vec(),
+            rock_types: planet.rock_types().to_vec(),
+            planet,
+        }
+    }
+
+    /// Returns the planet, for its cells and climate, as it stood before the pass.
+    pub fn planet(&self) -> &Planet {
+        &self.planet
+    }
+
+    /// Returns the elevation of every cell in metres.
+    pub fn elevation(&self) -> &[f64] {
+        &self.elevation
+    }
+
+    /// Returns the elevation of every cell in metres for rewriting.
+    pub fn elevation_mut(&mut self) -> &mut [f64] {
+        &mut self.elevation
+    }
+
+    /// Returns the bedrock of every cell.
+    pub fn rock_types(&self) -> &[RockType] {
+        &self.rock_types
+    }
+
+    /// Returns the bedrock of every cell for rewriting.
+    pub fn rock_types_mut(&mut self) -> &mut [RockType] {
+        &mut self.rock_types
+    }
+
+    /// Writes the layers back and returns the planet. The climate is reset only if the elevation
+    /// changed.
+    pub fn into_planet(self) -> Planet {
+        let mut planet = self.planet;
+        if planet.elevation() != self.elevation.as_slice() {
+            planet.set_elevation(self.elevation);
+        }
+        planet.set_rock_types(self.rock_types);
+        planet
+    }
+}
+
+/// What a `TerrainPass` knows about the generation it runs in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassContext<'a> {
+    pub parameters: &'a PlanetParameters, // The parameters of the planet being built
+    pub stage: BuildStage,                // The built-in stage the pass runs after
+    pub index: usize,                     // Position of the pass in the pipeline
+    pub seed: u64,                        // The pass's own seed, from the terrain seed and its position
+}
+
+/// A terrain modification run as a step of planet generation, e.g. from
+/// `PlanetGenerator::with_pass`.
+///
+/// `SlotCanyons`, `KarstSinkholes` and `Mesas` are passes; implement this trait to slot custom
+/// modifications in beside them.
+pub trait TerrainPass: Send + Sync {
+    /// Rewrites the layers of the planet under construction.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The layers to rewrite.
+    /// * `ctx` - The parameters, stage and seed of this run.
+    fn apply(&self, planet: &mut PlanetLayers, ctx: &PassContext);
+}
+
+// Runs a terrain feature as a pass over the elevation
+fn apply_feature(feature: &dyn TerrainFeature, layers: &mut PlanetLayers, ctx: &PassContext) {
+    feature.apply(&layers.planet, &mut layers.elevation, ctx.seed);
+}
+
+impl TerrainPass for SlotCanyons {
+    /// Cuts the canyons, along the planet's own transform faults and rifts when `faults` is empty.
+    fn apply(&self, planet: &mut PlanetLayers, ctx: &PassContext) {
+        if self.faults.is_empty() {
+            let tectonics = ctx.parameters.tectonics(planet.planet());
+            let canyons = SlotCanyons { faults: SlotCanyons::along_faults(&tectonics).faults, ..self.clone() };
+            apply_feature(&canyons, planet, ctx);
+        } else {
+            apply_feature(self, planet, ctx);
+        }
+    }
+}
+
+impl TerrainPass for KarstSinkholes {
+    fn apply(&self, planet: &mut PlanetLayers, ctx: &PassContext) {
+        apply_feature(self, planet, ctx);
+    }
+}
+
+impl TerrainPass for Mesas {
+    fn apply(&self, planet: &mut PlanetLayers, ctx: &PassContext) {
+        apply_feature(self, planet, ctx);
+    }
+}
+
+/// An ordered list of terrain passes, each attached to the built-in stage it runs after.
+#[derive(Clone, Default)]
+pub struct TerrainPipeline {
+    passes: Vec<(BuildStage, Arc<dyn TerrainPass>)>,
+}
+
+impl TerrainPipeline {
+    /// Creates an empty pipeline.
+    pub fn new() -> TerrainPipeline {
+        TerrainPipeline::default()
+    }
+
+    /// Appends a pass to run after a built-in stage, after the passes already there.
+    pub fn push(&mut self, after: BuildStage, pass: impl TerrainPass + 'static) {
+        self.passes.push((after, Arc::new(pass)));
+    }
+
+    /// Returns the number of passes.
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Returns `true` if there are no passes.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Runs the passes attached to a stage on a planet, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet, just after the stage.
+    /// * `parameters` - The parameters of the planet; pass seeds derive from its terrain seed.
+    /// * `stage` - The stage that just finished.
+    ///
+    /// # Returns
+    ///
+    /// The planet after the passes.
+    pub fn run(&self, planet: Planet, parameters: &PlanetParameters, stage: BuildStage) -> Planet {
+        let mut planet = planet;
+        for (index, (after, pass)) in self.passes.iter().enumerate() {
+            if *after != stage {
+                continue;
+            }
+            let ctx = PassContext {
+                parameters,
+                stage,
+                index,
+                seed: mix64(parameters.terrain_seed ^ mix64(index as u64)),
+            };
+            let mut layers = PlanetLayers::new(planet);
+            pass.apply(&mut layers, &ctx);
+            planet = layers.into_planet();
+        }
+        planet
+    }
+}
+
+impl std::fmt::Debug for TerrainPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.passes.iter().map(|(stage, _)| stage)).finish()
+    }
+}
+
+impl PartialEq for TerrainPipeline {
+    /// Pipelines are equal when they run the same pass instances after the same stages.
+    fn eq(&self, other: &TerrainPipeline) -> bool {
+        self.passes.len() == other.passes.len()
+            && self.passes.iter().zip(&other.passes).all(|((a, x), (b, y))| a == b && Arc::ptr_eq(x, y))
+    }
+}
@@ -0,0 +1,147 @@
+use crate::climate::OrbitalCalendar;
+use crate::planet::Planet;
+use crate::raster::lat_lon_to_unit;
+use crate::star_system::{OrbitalElements, StarSystem, SystemPlanet, EARTH_MASSES_PER_SOLAR_MASS, KM_PER_AU};
+use crate::vector::{dot, length, scale, Vec3};
+use std::f64::consts::TAU;
+
+// Ocean shallower than this in metres counts as continental shelf, which piles the tide up
+const SHELF_DEPTH: f64 = 200.0;
+// Tide range on a coast backed by the shallowest shelf relative to the open-ocean tide
+const COASTAL_AMPLIFICATION: f64 = 3.0;
+
+/// A body raising tides on a planet, usually one of its moons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TidalBody {
+    pub amplitude: f64,         // Equilibrium high water above mean sea level at mean distance, in metres
+    pub orbit: OrbitalElements, // Around the planet, relative to its equator
+}
+
+/// The bodies raising ocean tides on a planet.
+///
+/// Tides follow the equilibrium theory: each body pulls the ocean into a bulge under it and one
+/// opposite, `amplitude * (3 cos² ψ - 1) / 2` high at an angle ψ from the point beneath the body.
+/// The amplitude scales with the body's mass over the planet's and with `R⁴ / d³`, so Earth's Moon
+/// raises 0.36 m and the Sun 0.16 m, and grows with the inverse cube of distance as an eccentric
+/// moon nears periapsis. The default is a planet without tides.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Tides {
+    pub bodies: Vec<TidalBody>,
+    pub stellar_amplitude: f64, // Equilibrium high water raised by the star in metres; its direction is the subsolar point
+}
+
+// Equilibrium high water in metres raised by a body on a planet
+fn equilibrium_amplitude(body_mass: f64, planet_mass: f64, planet_radius: f64, distance_km: f64) -> f64 {
+    if planet_mass <= 0.0 || distance_km <= 0.0 {
+        return 0.0;
+    }
+    body_mass / planet_mass * planet_radius.powi(4) / distance_km.powi(3) * 1000.0
+}
+
+// Height of the tidal bulge in units of the amplitude at a cosine `cos_angle` from the body
+fn bulge(cos_angle: f64) -> f64 {
+    (3.0 * cos_angle * cos_angle - 1.0) / 2.0
+}
+
+impl Tides {
+    /// Derives the tides of a planet of a generated star system from its moons and star.
+    pub fn from_system(system: &StarSystem, planet: &SystemPlanet) -> Tides {
+        let bodies = planet
+            .moons
+            .iter()
+            .map(|moon| TidalBody {
+                amplitude: equilibrium_amplitude(moon.mass, planet.mass, planet.radius, moon.orbit.semi_major_axis * KM_PER_AU),
+                orbit: moon.orbit.clone(),
+            })
+            .collect();
+        let star_mass = system.star().mass * EARTH_MASSES_PER_SOLAR_MASS;
+        Tides {
+            bodies,
+            stellar_amplitude: equilibrium_amplitude(star_mass, planet.mass, planet.radius, planet.orbit.semi_major_axis * KM_PER_AU),
+        }
+    }
+
+    /// Returns the largest possible open-ocean high water in metres, with every body aligned at
+    /// periapsis, e.g. to scale coastal gameplay.
+    pub fn max_amplitude(&self) -> f64 {
+        let moons: f64 = self.bodies.iter().map(|body| body.amplitude / (1.0 - body.orbit.eccentricity).powi(3)).sum();
+        moons + self.stellar_amplitude
+    }
+
+    /// Returns the open-ocean sea-level offset in metres at a point on the unit sphere.
+    ///
+    /// # Arguments
+    ///
+    /// * `site` - The point on the unit sphere, z is the rotation axis.
+    /// * `sun` - The unit vector toward the subsolar point.
+    /// * `calendar` - The planet's calendar, which sets how fast it turns under its moons.
+    /// * `time` - Days since generation.
+    pub fn equilibrium_height(&self, site: Vec3, sun: Vec3, calendar: &OrbitalCalendar, time: f64) -> f64 {
+        let moons: f64 = self
+            .bodies
+            .iter()
+            .map(|body| {
+                let (direction, closeness) = body_direction(&body.orbit, calendar, time);
+                body.amplitude * closeness.powi(3) * bulge(dot(site, direction))
+            })
+            .sum();
+        moons + self.stellar_amplitude * bulge(dot(site, sun))
+    }
+}
+
+// The direction of an orbiting body in the planet's rotating frame and its mean distance over its
+// current distance
+fn body_direction(orbit: &OrbitalElements, calendar: &OrbitalCalendar, time: f64) -> (Vec3, f64) {
+    let (x, y, z) = orbit.position_at(time * calendar.day_length_hours() / 24.0);
+    let distance = length((x, y, z)).max(f64::MIN_POSITIVE);
+    // A solar day is one turn relative to the sun, which itself moves once around the sky a year
+    let (sin, cos) = (TAU * time * (1.0 + 1.0 / calendar.year_length_days())).sin_cos();
+    let direction = scale((x * cos + y * sin, y * cos - x * sin, z), 1.0 / distance);
+    (direction, orbit.semi_major_axis / distance)
+}
+
+impl Planet {
+    /// Returns `true` if a cell lies on the coast: it is on the other side of sea level from at
+    /// least one neighbor.
+    pub fn is_coastal(&self, cell: usize) -> bool {
+        let ocean = self.is_ocean(cell);
+        self.neighbors(cell).iter().any(|&neighbor| self.is_ocean(neighbor) != ocean)
+    }
+
+    /// Returns the tidal sea-level offset at a cell, for tide pools, stranded boats and causeways
+    /// that flood.
+    ///
+    /// Open-ocean cells get the equilibrium tide of `Tides::equilibrium_height`. Coasts amplify
+    /// it up to threefold where the sea beside them is shallow shelf, as the tide piles up over
+    /// shoaling water; a coastal land cell gets the offset of the water at its shore. Inland cells
+    /// have no sea and return `0.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - The cell to query.
+    /// * `time` - Days since generation; the fractional part is the time of day.
+    ///
+    /// # Returns
+    ///
+    /// The water level relative to mean sea level in metres, positive at high tide.
+    pub fn tide_at(&self, cell: usize, time: f64) -> f64 {
+        let coastal = self.is_coastal(cell);
+        if !self.is_ocean(cell) && !coastal {
+            return 0.0;
+        }
+        let (lat, lon) = self.subsolar_point(time);
+        let height = self.tides().equilibrium_height(self.sites()[cell], lat_lon_to_unit(lat, lon), self.calendar(), time);
+        if !coastal {
+            return height;
+        }
+        // The shallowest sea touching the coast sets how much the tide piles up
+        let elevation = self.elevation();
+        let depth = std::iter::once(cell)
+            .chain(self.neighbors(cell).iter().copied())
+            .filter(|&n| self.is_ocean(n))
+            .map(|n| -elevation[n])
+            .fold(f64::INFINITY, f64::min);
+        let shallowness = (1.0 - depth / SHELF_DEPTH).clamp(0.0, 1.0);
+        height * (1.0 + (COASTAL_AMPLIFICATION - 1.0) * shallowness)
+    }
+}
@@ -0,0 +1,246 @@
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::raster::lat_lon_to_unit;
+use crate::render::map::{cell_colors, MapBase};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+/// Width and height of a tile in pixels, the slippy-map standard.
+pub const TILE_SIZE: usize = 256;
+
+/// Deepest zoom level served; at 24 a tile spans a couple of metres of an Earth-sized planet.
+pub const MAX_ZOOM: u32 = 24;
+
+/// Connections answered at once by `TileServer::serve`.
+pub const WORKERS: usize = 8;
+
+/// Longest a connection may stall reading the request or writing the response.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Longest request head read before the request is refused
+const MAX_REQUEST_BYTES: usize = 8192;
+
+// A Leaflet viewer over both bases, served at `/`
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>TerraForge</title>
+<link rel="stylesheet" href="https://unpkg.com/leaflet@1.9.4/dist/leaflet.css">
+<script src="https://unpkg.com/leaflet@1.9.4/dist/leaflet.js"></script>
+<style>html, body, #map { height: 100%; margin: 0; background: #000; }</style>
+</head>
+<body>
+<div id="map"></div>
+<script>
+const options = { maxZoom: MAX_ZOOM, noWrap: true, attribution: "TerraForge" };
+const elevation = L.tileLayer("/elevation/{z}/{x}/{y}.png", options);
+const biomes = L.tileLayer("/biomes/{z}/{x}/{y}.png", options);
+const map = L.map("map", { layers: [elevation], worldCopyJump: false }).setView([0, 0], 2);
+L.control.layers({ "Elevation": elevation, "Biomes": biomes }).addTo(map);
+</script>
+</body>
+</html>
+"#;
+
+// The base a tile path names
+fn base_from_name(name: &str) -> Option<MapBase> {
+    match name {
+        "elevation" => Some(MapBase::Elevation),
+        "biomes" => Some(MapBase::Biomes),
+        _ => None,
+    }
+}
+
+// Latitude and longitude in degrees of a point given in tiles at a zoom level, Web Mercator
+fn tile_lat_lon(zoom: u32, x: f64, y: f64) -> (f64, f64) {
+    let tiles = (1u64 << zoom) as f64;
+    let longitude = x / tiles * 360.0 - 180.0;
+    let latitude = (PI * (1.0 - 2.0 * y / tiles)).sinh().atan().to_degrees();
+    (latitude, longitude)
+}
+
+/// Serves slippy-map tiles of a planet over HTTP, rendered on demand, for Leaflet, OpenLayers or
+/// any other web map viewer.
+///
+/// Tiles are 256-pixel PNGs in the Web Mercator tiling every web map library uses, at
+/// `/elevation/{z}/{x}/{y}.png` and `/biomes/{z}/{x}/{y}.png`; like any Web Mercator map they
+/// stop short of the poles at about 85°. `/` serves a Leaflet viewer of both layers. Each
+/// connection is handled on its own thread and closed after one response.
+///
+/// Available with the `tile-server` feature.
+#[derive(Debug)]
+pub struct TileServer {
+    planet: Planet,
+    elevation: Vec<[u8; 3]>, // Color of every cell per base
+    biomes: Vec<[u8; 3]>,
+}
+
+impl TileServer {
+    /// Creates a server for a planet, coloring its cells once up front.
+    pub fn new(planet: Planet) -> TileServer {
+        let elevation = cell_colors(&planet, MapBase::Elevation);
+        let biomes = cell_colors(&planet, MapBase::Biomes);
+        TileServer { planet, elevation, biomes }
+    }
+
+    /// Returns the planet being served.
+    pub fn planet(&self) -> &Planet {
+        &self.planet
+    }
+
+    /// Renders one tile.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - What fills the tile.
+    /// * `zoom` - The zoom level, 0 for the whole planet in one tile.
+    /// * `x` - The tile column, counted east from the antimeridian.
+    /// * `y` - The tile row, counted south from the top of the map.
+    ///
+    /// # Returns
+    ///
+    /// A `TILE_SIZE` × `TILE_SIZE` image, or `None` if the tile does not exist at that zoom.
+    pub fn render_tile(&self, base: MapBase, zoom: u32, x: u32, y: u32) -> Option<Image> {
+        if zoom > MAX_ZOOM || x >= 1 << zoom || y >= 1 << zoom || self.planet.cell_count() == 0 {
+            return None;
+        }
+        let colors = match base {
+            MapBase::Elevation => &self.elevation,
+            MapBase::Biomes => &self.biomes,
+        };
+        let rows: Vec<Vec<[u8; 3]>> = (0..TILE_SIZE)
+            .into_par_iter()
+            .map(|row| {
+                let mut hint = 0;
+                (0..TILE_SIZE)
+                    .map(|column| {
+                        let (latitude, longitude) = tile_lat_lon(
+                            zoom,
+                            x as f64 + (column as f64 + 0.5) / TILE_SIZE as f64,
+                            y as f64 + (row as f64 + 0.5) / TILE_SIZE as f64,
+                        );
+                        hint = self.planet.nearest_cell(lat_lon_to_unit(latitude, longitude), hint);
+                        colors[hint]
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut image = Image::new(TILE_SIZE, TILE_SIZE, [0, 0, 0, 255]);
+        for (row, pixels) in rows.into_iter().enumerate() {
+            for (column, [r, g, b]) in pixels.into_iter().enumerate() {
+                image.set(column, row, [r, g, b, 255]);
+            }
+        }
+        Some(image)
+    }
+
+    /// Accepts and answers connections until the listener fails; this never returns otherwise.
+    ///
+    /// Connections are answered by a fixed pool of `WORKERS` threads, with up to as many more
+    /// waiting their turn; beyond that, accepting pauses until a worker is free. A client that
+    /// stalls for `TIMEOUT` while sending its request or receiving the response is dropped. When
+    /// the listener fails, the connections already accepted are still answered before the error is
+    /// returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `listener` - A bound listener, e.g. `TcpListener::bind("127.0.0.1:8080")?`.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(WORKERS);
+        let receiver = Mutex::new(receiver);
+        std::thread::scope(|scope| {
+            for _ in 0..WORKERS {
+                let receiver = &receiver;
+                scope.spawn(move || loop {
+                    // The lock is released before handling, so workers take connections in turn
+                    let next = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => break,
+                    };
+                    let Ok(stream) = next else { break };
+                    if let Err(error) = self.handle(stream) {
+                        tracing::warn!("tile server: {}", error);
+                    }
+                });
+            }
+            // Dropping the sender on return lets the workers finish the queue and exit
+            for stream in listener.incoming() {
+                if sender.send(stream?).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Reads one request from a connection and writes the response
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST_BYTES as u64);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Drain the headers; nothing in them changes the response
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return respond(stream, "400 Bad Request", "text/plain", b"incomplete or oversized request\n", true);
+            }
+            if line == "\r\n" || line == "\n" {
+                break;
+            }
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let include_body = method == "GET";
+        if !include_body && method != "HEAD" {
+            return respond(stream, "405 Method Not Allowed", "text/plain", b"only GET and HEAD are supported\n", true);
+        }
+        let path = target.split(['?', '#']).next().unwrap_or("");
+        if path == "/" || path == "/index.html" {
+            let page = INDEX_HTML.replace("MAX_ZOOM", &MAX_ZOOM.to_string());
+            return respond(stream, "200 OK", "text/html; charset=utf-8", page.as_bytes(), include_body);
+        }
+        match self.tile_for_path(path) {
+            Some(image) => respond(stream, "200 OK", "image/png", &image.encode_png(), include_body),
+            None => respond(stream, "404 Not Found", "text/plain", b"no such tile\n", include_body),
+        }
+    }
+
+    // Renders the tile a `/{base}/{z}/{x}/{y}.png` path names
+    fn tile_for_path(&self, path: &str) -> Option<Image> {
+        let mut segments = path.strip_prefix('/')?.strip_suffix(".png")?.split('/');
+        let base = base_from_name(segments.next()?)?;
+        let zoom = segments.next()?.parse().ok()?;
+        let x = segments.next()?.parse().ok()?;
+        let y = segments.next()?.parse().ok()?;
+        if segments.next().is_some() {
+            return None;
+        }
+        self.render_tile(base, zoom, x, y)
+    }
+}
+
+// Writes a complete HTTP/1.1 response and closes the connection; tiles never change while the
+// server runs, so browsers may cache them
+fn respond(mut stream: TcpStream, status: &str, content_type: &str, body: &[u8], include_body: bool) -> io::Result<()> {
+    let cache = if content_type == "image/png" { "public, max-age=3600" } else { "no-cache" };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nCache-Control: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+        cache
+    )?;
+    if include_body {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
@@ -0,0 +1,135 @@
+use crate::cell_graph::{circumcentre, CellGraph};
+use crate::geom::spherical;
+use crate::planet::Planet;
+use crate::vector::{dot, Vec3};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+// Largest relative difference between the summed cell areas and the sphere's area
+const AREA_TOLERANCE: f64 = 1e-6;
+// Largest distance of a site from the unit sphere
+const UNIT_TOLERANCE: f64 = 1e-9;
+
+/// An invariant of a planet's cell layout that does not hold, as found by `Planet::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopologyError {
+    /// The site of this cell is not a finite point on the unit sphere.
+    InvalidSite(usize),
+    /// This triangle names a cell out of range or the same cell twice.
+    InvalidTriangle(usize),
+    /// This triangle is flat or runs clockwise seen from outside, so its Voronoi vertex is not
+    /// finite or lies on the wrong side of the sphere.
+    InvertedTriangle(usize),
+    /// The Delaunay edge between two cells borders this many triangles, running this way round,
+    /// instead of one each way; the cells' shared Voronoi edge is missing or overlaps another.
+    UnpairedEdge { cells: (usize, usize), count: usize },
+    /// The first cell lists the second as a neighbor but not the other way round.
+    AsymmetricAdjacency { cell: usize, neighbor: usize },
+    /// The neighbors stored for this cell differ from the cells it shares triangles with.
+    NeighborMismatch(usize),
+    /// The cells cover this much of the unit sphere in steradians instead of 4π.
+    AreaMismatch(f64),
+}
+
+impl std::fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyError::InvalidSite(cell) => write!(f, "the site of cell {} is not a finite unit vector", cell),
+            TopologyError::InvalidTriangle(index) => write!(f, "triangle {} does not name three distinct cells", index),
+            TopologyError::InvertedTriangle(index) => write!(f, "triangle {} is flat or inverted", index),
+            TopologyError::UnpairedEdge { cells, count } => {
+                write!(f, "the edge from cell {} to cell {} borders {} triangles in that direction instead of 1", cells.0, cells.1, count)
+            }
+            TopologyError::AsymmetricAdjacency { cell, neighbor } => write!(f, "cell {} lists cell {} as a neighbor but not the reverse", cell, neighbor),
+            TopologyError::NeighborMismatch(cell) => write!(f, "the neighbors of cell {} do not match its triangles", cell),
+            TopologyError::AreaMismatch(area) => write!(f, "the cells cover {} steradians instead of 4π", area),
+        }
+    }
+}
+
+impl std::error::Error for TopologyError {}
+
+impl Planet {
+    /// Checks that the cells form a valid tiling of the sphere: every site is a finite unit
+    /// vector, every triangle is counter-clockwise with a finite Voronoi vertex, every Delaunay
+    /// edge borders exactly two triangles so every Voronoi edge separates exactly two cells, the
+    /// adjacency is symmetric and matches the triangles, and the cell areas sum to the sphere's
+    /// within a relative 1e-6.
+    ///
+    /// Planets built by `new`, `try_new` and the site editing methods always pass; the check is
+    /// for tests and for planets restored from files or built by custom code.
+    ///
+    /// # Returns
+    ///
+    /// The first invariant found broken, checked in the order above.
+    pub fn validate(&self) -> Result<(), TopologyError> {
+        let sites = self.sites();
+        let triangles = self.triangles();
+        if let Some(cell) = sites.par_iter().position_first(|&site| !site_is_unit(site)) {
+            return Err(TopologyError::InvalidSite(cell));
+        }
+        let in_range = |&[a, b, c]: &[usize; 3]| a < sites.len() && b < sites.len() && c < sites.len() && a != b && b != c && c != a;
+        if let Some(index) = triangles.par_iter().position_first(|triangle| !in_range(triangle)) {
+            return Err(TopologyError::InvalidTriangle(index));
+        }
+        let inverted = |triangle: &[usize; 3]| {
+            let [a, b, c] = triangle.map(|cell| sites[cell]);
+            // A flat triangle's circumcentre comes out NaN
+            !(spherical::triangle_area(a, b, c) > 0.0 && site_is_unit(circumcentre(sites, triangle)))
+        };
+        if let Some(index) = triangles.par_iter().position_first(inverted) {
+            return Err(TopologyError::InvertedTriangle(index));
+        }
+
+        let mut directed: HashMap<(usize, usize), usize> = HashMap::with_capacity(3 * triangles.len());
+        for triangle in triangles {
+            for k in 0..3 {
+                *directed.entry((triangle[k], triangle[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        for triangle in triangles {
+            for k in 0..3 {
+                let (from, to) = (triangle[k], triangle[(k + 1) % 3]);
+                let count = directed[&(from, to)];
+                if count != 1 {
+                    return Err(TopologyError::UnpairedEdge { cells: (from, to), count });
+                }
+                if !directed.contains_key(&(to, from)) {
+                    return Err(TopologyError::UnpairedEdge { cells: (to, from), count: 0 });
+                }
+            }
+        }
+
+        for cell in 0..sites.len() {
+            if let Some(&neighbor) = self.neighbors(cell).iter().find(|&&neighbor| neighbor >= sites.len() || !self.neighbors(neighbor).contains(&cell)) {
+                return Err(TopologyError::AsymmetricAdjacency { cell, neighbor });
+            }
+        }
+        let mut expected: Vec<Vec<usize>> = vec![Vec::new(); sites.len()];
+        for &(from, to) in directed.keys() {
+            expected[from].push(to);
+        }
+        let mismatch = (0..sites.len()).into_par_iter().position_first(|cell| {
+            let mut stored = self.neighbors(cell).to_vec();
+            stored.sort_unstable();
+            let mut derived = expected[cell].clone();
+            derived.sort_unstable();
+            stored != derived
+        });
+        if let Some(cell) = mismatch {
+            return Err(TopologyError::NeighborMismatch(cell));
+        }
+
+        let graph = CellGraph::new(self);
+        let area: f64 = (0..sites.len()).into_par_iter().map(|cell| spherical::polygon_area(&graph.polygon(cell))).sum();
+        let sphere = 4.0 * std::f64::consts::PI;
+        if area.is_nan() || (area - sphere).abs() > AREA_TOLERANCE * sphere {
+            return Err(TopologyError::AreaMismatch(area));
+        }
+        Ok(())
+    }
+}
+
+fn site_is_unit(site: Vec3) -> bool {
+    (dot(site, site) - 1.0).abs() <= UNIT_TOLERANCE
+}
@@ -0,0 +1,159 @@
+use crate::coords::CoordinateFrame;
+use crate::planet::Planet;
+use crate::render::materials::Material;
+use crate::vector::{add, scale, Vec3};
+use crate::vegetation::VegetationLayer;
+use std::fmt::Write as _;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+
+// File signature and format version of the binary mesh blob
+const MAGIC: &[u8; 4] = b"TFUE";
+const VERSION: u32 = 1;
+
+/// Places a planet in Unreal Engine's world.
+///
+/// Axes, units and winding come from `frame`, `CoordinateFrame::UNREAL` by default; any other
+/// frame works too, e.g. for loading the same blob into another engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnrealTransform {
+    pub frame: CoordinateFrame,
+    pub vertical_exaggeration: f64, // Multiplier on elevation when displacing the surface
+    pub origin: Vec3,               // Position of the planet centre in the frame
+}
+
+/// Unreal's frame in centimetres, no exaggeration, centred on the world origin.
+impl Default for UnrealTransform {
+    fn default() -> Self {
+        UnrealTransform {
+            frame: CoordinateFrame::UNREAL,
+            vertical_exaggeration: 1.0,
+            origin: (0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl UnrealTransform {
+    /// Converts a point in kilometres from the planet centre to the frame.
+    pub fn point(&self, point: Vec3) -> Vec3 {
+        add(self.origin, self.frame.from_native(point))
+    }
+
+    /// Returns a cell's surface position in the frame, displaced by its elevation.
+    pub fn cell_position(&self, planet: &Planet, cell: usize) -> Vec3 {
+        let height = planet.radius() + planet.elevation()[cell] / 1000.0 * self.vertical_exaggeration;
+        self.point(scale(planet.sites()[cell], height))
+    }
+}
+
+// Biome of every cell
+fn biomes(planet: &Planet) -> Vec<Material> {
+    planet.materials(&VegetationLayer::from_planet(planet))
+}
+
+/// Formats a planet as a CSV for import as an Unreal DataTable, one row per cell named by its
+/// index.
+///
+/// The row struct needs the fields `X`, `Y`, `Z`, `Latitude`, `Longitude`, `Elevation` and
+/// `Temperature` (float), `Biome` (name, see `Material::name`) and `Neighbors` (array of int).
+///
+/// # Arguments
+///
+/// * `planet` - The planet to export.
+/// * `transform` - The conversion to Unreal's frame.
+pub fn unreal_data_table_csv(planet: &Planet, transform: &UnrealTransform) -> String {
+    let mut csv = String::from("Name,X,Y,Z,Latitude,Longitude,Elevation,Temperature,Biome,Neighbors\n");
+    for (cell, biome) in biomes(planet).into_iter().enumerate() {
+        let (x, y, z) = transform.cell_position(planet, cell);
+        let (latitude, longitude) = planet.lat_lon(cell);
+        let neighbors: Vec<String> = planet.neighbors(cell).iter().map(|n| n.to_string()).collect();
+        let _ = writeln!(
+            csv,
+            "{},{:.3},{:.3},{:.3},{:.6},{:.6},{:.2},{:.2},{},\"({})\"",
+            cell,
+            x,
+            y,
+            z,
+            latitude,
+            longitude,
+            planet.elevation()[cell],
+            planet.temperature()[cell],
+            biome.name(),
+            neighbors.join(",")
+        );
+    }
+    csv
+}
+
+/// Encodes a planet as a binary mesh blob for a native Unreal plugin.
+///
+/// All values are little-endian, with `n` cells and `t` triangles:
+///
+/// | Field          | Type           | Contents                                        |
+/// |----------------|----------------|-------------------------------------------------|
+/// | magic          | `[u8; 4]`      | `TFUE`                                          |
+/// | version        | `u32`          | 1                                               |
+/// | cell count     | `u32`          | `n`                                             |
+/// | triangle count | `u32`          | `t`                                             |
+/// | radius         | `f64`          | Planet radius in kilometres                     |
+/// | scale          | `f64`          | `CoordinateFrame::scale` used for the positions |
+/// | positions      | `[f32; 3] × n` | Cell surface positions in the frame             |
+/// | indices        | `[u32; 3] × t` | Triangles in the frame's front-face winding     |
+/// | elevation      | `f32 × n`      | Metres relative to sea level                    |
+/// | temperature    | `f32 × n`      | Degrees Celsius                                 |
+/// | biome          | `u8 × n`       | `Material` ids                                  |
+///
+/// # Arguments
+///
+/// * `planet` - The planet to export.
+/// * `transform` - The conversion to Unreal's frame.
+///
+/// # Panics
+///
+/// Panics if the planet has more than `u32::MAX` cells or triangles.
+pub fn unreal_mesh_bytes(planet: &Planet, transform: &UnrealTransform) -> Vec<u8> {
+    let cells = planet.cell_count();
+    let triangles = planet.triangles();
+    let count = |n: usize| u32::try_from(n).expect("count fits in u32");
+
+    let mut out = Vec::with_capacity(32 + cells * 21 + triangles.len() * 12);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&count(cells).to_le_bytes());
+    out.extend_from_slice(&count(triangles.len()).to_le_bytes());
+    out.extend_from_slice(&planet.radius().to_le_bytes());
+    out.extend_from_slice(&transform.frame.scale.to_le_bytes());
+    for cell in 0..cells {
+        let (x, y, z) = transform.cell_position(planet, cell);
+        for value in [x, y, z] {
+            out.extend_from_slice(&(value as f32).to_le_bytes());
+        }
+    }
+    for &triangle in triangles {
+        for index in transform.frame.triangle(triangle) {
+            out.extend_from_slice(&count(index).to_le_bytes());
+        }
+    }
+    for &elevation in planet.elevation() {
+        out.extend_from_slice(&(elevation as f32).to_le_bytes());
+    }
+    for &temperature in planet.temperature() {
+        out.extend_from_slice(&(temperature as f32).to_le_bytes());
+    }
+    out.extend(biomes(planet).into_iter().map(|biome| biome as u8));
+    out
+}
+
+/// Writes `unreal_data_table_csv` to a file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_unreal_data_table(path: &str, planet: &Planet, transform: &UnrealTransform) -> io::Result<()> {
+    fs::write(path, unreal_data_table_csv(planet, transform))
+}
+
+/// Writes `unreal_mesh_bytes` to a file.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_unreal_mesh(path: &str, planet: &Planet, transform: &UnrealTransform) -> io::Result<()> {
+    fs::write(path, unreal_mesh_bytes(planet, transform))
+}
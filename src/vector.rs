@@ -0,0 +1,52 @@
+// Small helpers for working with `(f64, f64, f64)` vectors. The crate represents points and
+// directions as plain tuples, so these keep the arithmetic readable without a linear algebra
+// dependency.
+
+/// A 3D point or direction in Cartesian coordinates.
+pub(crate) type Vec3 = (f64, f64, f64);
+
+/// Adds two vectors component-wise.
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+/// Subtracts `b` from `a` component-wise.
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+/// Multiplies a vector by a scalar.
+pub(crate) fn scale(a: Vec3, s: f64) -> Vec3 {
+    (a.0 * s, a.1 * s, a.2 * s)
+}
+
+/// Returns the dot product of two vectors.
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+/// Returns the cross product `a × b`.
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    (
+        a.1 * b.2 - a.2 * b.1,
+        a.2 * b.0 - a.0 * b.2,
+        a.0 * b.1 - a.1 * b.0,
+    )
+}
+
+/// Returns the Euclidean length of a vector.
+pub(crate) fn length(a: Vec3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Scales a vector to unit length.
+///
+/// Zero-length input is returned unchanged rather than producing NaNs.
+pub(crate) fn normalize(a: Vec3) -> Vec3 {
+    let len = length(a);
+    if len > 0.0 {
+        scale(a, 1.0 / len)
+    } else {
+        a
+    }
+}
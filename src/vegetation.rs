@@ -0,0 +1,92 @@
+use crate::image::Image;
+use crate::planet::Planet;
+use crate::projection::Projection;
+use rayon::prelude::*;
+
+/// Per-cell soil fertility and vegetation density.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VegetationLayer {
+    fertility: Vec<f64>, // Soil fertility, 0.0 to 1.0
+    density: Vec<f64>,   // Vegetation cover, 0.0 to 1.0
+}
+
+// Growth response to mean annual temperature, peaking in warm climates
+fn temperature_factor(temperature: f64) -> f64 {
+    if temperature < -5.0 {
+        0.0
+    } else {
+        (-((temperature - 22.0) / 15.0).powi(2)).exp()
+    }
+}
+
+// Water availability from annual precipitation in mm/year
+fn moisture_factor(precipitation: f64) -> f64 {
+    1.0 - (-precipitation.max(0.0) / 800.0).exp()
+}
+
+// Steep slopes lose their soil
+fn slope_factor(slope_degrees: f64) -> f64 {
+    (1.0 - slope_degrees / 45.0).clamp(0.0, 1.0)
+}
+
+impl VegetationLayer {
+    /// Computes soil fertility and vegetation density for every cell.
+    ///
+    /// Fertility combines bedrock, slope, temperature and precipitation; vegetation density is
+    /// fertility limited further by water availability and reduced under snow. Ocean cells have
+    /// no vegetation.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet, with terrain generated.
+    /// * `precipitation` - Annual precipitation per cell in mm/year, e.g. from
+    ///   `Planet::annual_precipitation`.
+    /// * `temperature` - Mean annual temperature per cell in °C, e.g. from
+    ///   `Planet::mean_annual_temperature`.
+    pub fn compute(planet: &Planet, precipitation: &[f64], temperature: &[f64]) -> VegetationLayer {
+        let rocks = planet.rock_types();
+        let snow = planet.snow_cover();
+        let (fertility, density) = (0..planet.cell_count())
+            .into_par_iter()
+            .map(|cell| {
+                if planet.is_ocean(cell) {
+                    return (0.0, 0.0);
+                }
+                let moisture = moisture_factor(precipitation[cell]);
+                let warmth = temperature_factor(temperature[cell]);
+                let soil = rocks[cell].soil_quality() * slope_factor(planet.slope(cell));
+                let fertility = (soil * (0.5 + 0.5 * moisture) * (0.3 + 0.7 * warmth)).clamp(0.0, 1.0);
+                let density = (fertility * moisture * warmth * (1.0 - snow[cell])).sqrt().clamp(0.0, 1.0);
+                (fertility, density)
+            })
+            .unzip();
+        VegetationLayer { fertility, density }
+    }
+
+    /// Computes the layer using the planet's own precipitation and temperature estimates.
+    pub fn from_planet(planet: &Planet) -> VegetationLayer {
+        VegetationLayer::compute(planet, &planet.annual_precipitation(), &planet.mean_annual_temperature())
+    }
+
+    /// Returns soil fertility per cell, 0.0 to 1.0.
+    pub fn fertility(&self) -> &[f64] {
+        &self.fertility
+    }
+
+    /// Returns vegetation density per cell, 0.0 to 1.0.
+    pub fn density(&self) -> &[f64] {
+        &self.density
+    }
+
+    /// Renders vegetation density as a grayscale map for foliage scattering in engines.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the layer was computed for.
+    /// * `projection` - The map projection of the texture.
+    /// * `width`, `height` - The texture size in pixels.
+    pub fn density_map(&self, planet: &Planet, projection: Projection, width: usize, height: usize) -> Image {
+        let raster = planet.rasterize_values(&self.density, projection, width, height);
+        Image::from_raster_gray(&raster, 0.0, 1.0)
+    }
+}
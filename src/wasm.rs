@@ -0,0 +1,113 @@
+use crate::coords::CoordinateFrame;
+use crate::guid::guid_from_seed;
+use crate::planet::Planet;
+use crate::procedural::PlanetParameters;
+use crate::render::elevation_color;
+use crate::vegetation::VegetationLayer;
+use wasm_bindgen::prelude::*;
+
+/// A planet generated in the browser.
+///
+/// Arrays are returned as typed arrays with one entry (or one group of entries) per cell, in cell
+/// order, so they index into each other and into `indices`. Positions use three.js conventions:
+/// Y up, right-handed, kilometres. Call `free()` when done to release the Rust memory.
+#[wasm_bindgen(js_name = Planet)]
+pub struct WasmPlanet {
+    planet: Planet,
+}
+
+#[wasm_bindgen(js_class = Planet)]
+impl WasmPlanet {
+    /// Generates a planet from a seed, the same one `Planet::from_guid(guid_from_seed(seed))`
+    /// gives natively.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The planet seed, a `BigInt` in JavaScript.
+    /// * `samples` - The number of cells, or 0 to derive it from the seed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64, samples: u32) -> WasmPlanet {
+        let mut parameters = PlanetParameters::from_guid(guid_from_seed(seed));
+        if samples > 0 {
+            parameters.samples = samples as usize;
+        }
+        WasmPlanet { planet: parameters.build() }
+    }
+
+    /// Returns the number of cells.
+    #[wasm_bindgen(getter, js_name = cellCount)]
+    pub fn cell_count(&self) -> usize {
+        self.planet.cell_count()
+    }
+
+    /// Returns the radius in kilometres.
+    #[wasm_bindgen(getter)]
+    pub fn radius(&self) -> f64 {
+        self.planet.radius()
+    }
+
+    /// Returns the elevation of every cell in metres relative to sea level, as a `Float32Array`.
+    pub fn heights(&self) -> Vec<f32> {
+        self.planet.elevation().iter().map(|&height| height as f32).collect()
+    }
+
+    /// Returns the temperature of every cell in degrees Celsius, as a `Float32Array`.
+    pub fn temperatures(&self) -> Vec<f32> {
+        self.planet.temperature().iter().map(|&temperature| temperature as f32).collect()
+    }
+
+    /// Returns the surface position of every cell as consecutive x, y, z values, as a
+    /// `Float32Array`.
+    ///
+    /// # Arguments
+    ///
+    /// * `exaggeration` - Multiplier on elevation when displacing the surface; 0 gives a sphere.
+    pub fn positions(&self, exaggeration: f64) -> Vec<f32> {
+        let frame = CoordinateFrame::BEVY.with_scale(1.0);
+        let radius = self.planet.radius();
+        let mut positions = Vec::with_capacity(self.planet.cell_count() * 3);
+        for (&site, &height) in self.planet.sites().iter().zip(self.planet.elevation()) {
+            let r = radius + height / 1000.0 * exaggeration;
+            let (x, y, z) = frame.from_native((site.0 * r, site.1 * r, site.2 * r));
+            positions.extend([x as f32, y as f32, z as f32]);
+        }
+        positions
+    }
+
+    /// Returns the latitude and longitude of every cell in degrees as consecutive pairs, as a
+    /// `Float64Array`, for 2D map viewers.
+    #[wasm_bindgen(js_name = latLon)]
+    pub fn lat_lon(&self) -> Vec<f64> {
+        (0..self.planet.cell_count())
+            .flat_map(|cell| {
+                let (latitude, longitude) = self.planet.lat_lon(cell);
+                [latitude, longitude]
+            })
+            .collect()
+    }
+
+    /// Returns the triangles as consecutive triples of cell indices wound counter-clockwise from
+    /// outside, as a `Uint32Array`.
+    pub fn indices(&self) -> Vec<u32> {
+        let frame = CoordinateFrame::BEVY;
+        self.planet.triangles().iter().flat_map(|&triangle| frame.triangle(triangle)).map(|cell| cell as u32).collect()
+    }
+
+    /// Returns the neighbours of a cell as a `Uint32Array`, empty if the cell is out of range.
+    pub fn neighbors(&self, cell: usize) -> Vec<u32> {
+        if cell >= self.planet.cell_count() {
+            return Vec::new();
+        }
+        self.planet.neighbors(cell).iter().map(|&neighbor| neighbor as u32).collect()
+    }
+
+    /// Returns the biome of every cell as a `Material` id, as a `Uint8Array`.
+    pub fn biomes(&self) -> Vec<u8> {
+        self.planet.materials(&VegetationLayer::from_planet(&self.planet)).into_iter().map(|material| material as u8).collect()
+    }
+
+    /// Returns the hypsometric tint of every cell as consecutive sRGB bytes, as a `Uint8Array`.
+    pub fn colors(&self) -> Vec<u8> {
+        self.planet.elevation().iter().flat_map(|&height| elevation_color(height)).collect()
+    }
+}
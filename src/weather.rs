@@ -1,42 +1,104 @@
+use crate::climate;
+use crate::fibonacci_sphere::seeded_fibonacci_sphere;
+use crate::geom::spherical;
+use crate::guard::guard_value;
+use crate::planet::Planet;
+use crate::precision::Precision;
 use rand::{SeedableRng, Rng};
 use rand::rngs::StdRng;
-use std::f64::consts::PI;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-// Function to generate Fibonacci sphere points
-pub fn fibonacci_sphere(samples: usize) -> Vec<[f64; 3]> {
-    let mut points = Vec::with_capacity(samples);
-    let phi = PI * (3.0 - (5.0f64).sqrt());
+// Fraction of a cell's air replaced from upwind per step, per m/s of wind
+const ADVECTION_PER_WIND_SPEED: f64 = 0.015;
 
-    for i in 0..samples {
-        let y = 1.0 - (i as f64 / (samples as f64 - 1.0)) * 2.0;
-        let radius = (1.0 - y * y).sqrt();
-        let theta = phi * i as f64;
+/// The weather of one cell.
+///
+/// Serializes as a struct with one number per field, e.g. a JSON object.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherCondition {
+    pub temperature: f64,    // Degrees Celsius
+    pub humidity: f64,       // Percent
+    pub wind_speed: f64,     // m/s
+    pub wind_direction: f64, // Degrees clockwise from north the wind blows from, 0-360
+    pub precipitation: f64,  // mm/h
+}
 
-        let x = radius * theta.cos();
-        let z = radius * theta.sin();
+// Field names in declaration order
+const CONDITION_FIELDS: &[&str] = &["temperature", "humidity", "wind_speed", "wind_direction", "precipitation"];
 
-        points.push([x, y, z]);
+impl WeatherCondition {
+    /// Returns every field with its name, in declaration order, e.g. for invariant checks or
+    /// generic export.
+    pub fn fields(&self) -> [(&'static str, f64); 5] {
+        let values = [self.temperature, self.humidity, self.wind_speed, self.wind_direction, self.precipitation];
+        std::array::from_fn(|i| (CONDITION_FIELDS[i], values[i]))
     }
+}
 
-    points
+/// Weather conditions stored as one array per field in precision `T`, e.g. `f32` for a
+/// million-cell weather state that is uploaded to a GPU every frame.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WeatherLayers<T: Precision> {
+    pub temperature: Vec<T>,    // Degrees Celsius
+    pub humidity: Vec<T>,       // Percent
+    pub wind_speed: Vec<T>,     // m/s
+    pub wind_direction: Vec<T>, // Degrees, 0-360
+    pub precipitation: Vec<T>,  // mm/h
 }
 
-// Weather conditions struct
-#[derive(Debug, Clone)]
-struct WeatherCondition {
-    temperature: f64,
-    humidity: f64,
-    wind_speed: f64,
-    wind_direction: f64, // In degrees, 0-360
-    precipitation: f64,  // In mm/h
+impl<T: Precision> WeatherLayers<T> {
+    /// Stores weather conditions field by field in precision `T`.
+    pub fn from_conditions(conditions: &[WeatherCondition]) -> WeatherLayers<T> {
+        let field = |value: fn(&WeatherCondition) -> f64| conditions.iter().map(|condition| T::from_f64(value(condition))).collect();
+        WeatherLayers {
+            temperature: field(|condition| condition.temperature),
+            humidity: field(|condition| condition.humidity),
+            wind_speed: field(|condition| condition.wind_speed),
+            wind_direction: field(|condition| condition.wind_direction),
+            precipitation: field(|condition| condition.precipitation),
+        }
+    }
+
+    /// Returns the number of cells.
+    pub fn len(&self) -> usize {
+        self.temperature.len()
+    }
+
+    /// Returns `true` if there are no cells.
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_empty()
+    }
+
+    /// Returns the weather of one cell, widened to `f64` for simulation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn condition(&self, index: usize) -> WeatherCondition {
+        WeatherCondition {
+            temperature: self.temperature[index].to_f64(),
+            humidity: self.humidity[index].to_f64(),
+            wind_speed: self.wind_speed[index].to_f64(),
+            wind_direction: self.wind_direction[index].to_f64(),
+            precipitation: self.precipitation[index].to_f64(),
+        }
+    }
+
+    /// Returns the weather of every cell, widened to `f64` for simulation.
+    pub fn to_conditions(&self) -> Vec<WeatherCondition> {
+        (0..self.len()).map(|index| self.condition(index)).collect()
+    }
 }
 
-// Function to generate initial weather conditions
-pub fn generate_weather_conditions(seed: u64, samples: usize) -> Vec<WeatherCondition> {
+// Function to generate initial weather conditions, one per cell of the planet
+pub fn generate_weather_conditions(seed: u64, planet: &Planet) -> Vec<WeatherCondition> {
     let mut rng = StdRng::seed_from_u64(seed);
-    let mut conditions = Vec::with_capacity(samples);
+    let mut conditions = Vec::with_capacity(planet.cell_count());
 
-    for _ in 0..samples {
+    for _ in 0..planet.cell_count() {
         let temperature = rng.gen_range(-30.0..50.0); // Example temperature range in Celsius
         let humidity = rng.gen_range(0.0..100.0); // Example humidity range in percentage
         let wind_speed = rng.gen_range(0.0..30.0); // Example wind speed in m/s
@@ -55,6 +117,86 @@ pub fn generate_weather_conditions(seed: u64, samples: usize) -> Vec<WeatherCond
     conditions
 }
 
+/// The weather of every cell of a planet, indexed by cell.
+///
+/// Serializes as a sequence of `WeatherCondition`s in cell order.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WeatherMap {
+    conditions: Vec<WeatherCondition>, // One per cell
+}
+
+impl WeatherMap {
+    /// Generates random initial weather for every cell of a planet, as
+    /// `generate_weather_conditions` does.
+    pub fn generate(seed: u64, planet: &Planet) -> WeatherMap {
+        WeatherMap::from_conditions(generate_weather_conditions(seed, planet))
+    }
+
+    /// Wraps conditions that are already ordered by cell.
+    pub fn from_conditions(conditions: Vec<WeatherCondition>) -> WeatherMap {
+        WeatherMap { conditions }
+    }
+
+    /// Returns the number of cells.
+    pub fn len(&self) -> usize {
+        self.conditions.len()
+    }
+
+    /// Returns `true` if there are no cells.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Returns the weather of a cell, or `None` if the cell is out of range.
+    pub fn get(&self, cell: usize) -> Option<&WeatherCondition> {
+        self.conditions.get(cell)
+    }
+
+    /// Returns the weather of the cell containing a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `planet` - The planet the weather was generated for.
+    /// * `latitude` - Latitude in degrees.
+    /// * `longitude` - Longitude in degrees.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planet has more cells than the map.
+    pub fn at(&self, planet: &Planet, latitude: f64, longitude: f64) -> &WeatherCondition {
+        &self.conditions[planet.nearest_cell(spherical::from_lat_lon(latitude, longitude), 0)]
+    }
+
+    /// Returns the weather of every cell.
+    pub fn conditions(&self) -> &[WeatherCondition] {
+        &self.conditions
+    }
+
+    /// Returns the weather of every cell for editing, e.g. by the free simulation functions.
+    pub fn conditions_mut(&mut self) -> &mut [WeatherCondition] {
+        &mut self.conditions
+    }
+
+    /// Advances the weather by one step, see `simulate_weather`.
+    pub fn step(&mut self, planet: &Planet, time_step: usize) {
+        simulate_weather(&mut self.conditions, planet, time_step);
+    }
+
+    /// Stores the weather field by field in precision `T`.
+    pub fn layers<T: Precision>(&self) -> WeatherLayers<T> {
+        WeatherLayers::from_conditions(&self.conditions)
+    }
+}
+
+impl std::ops::Index<usize> for WeatherMap {
+    type Output = WeatherCondition;
+
+    fn index(&self, cell: usize) -> &WeatherCondition {
+        &self.conditions[cell]
+    }
+}
+
 // Function to calculate precipitation based on temperature and humidity
 pub fn calculate_precipitation(temperature: f64, humidity: f64) -> f64 {
     if temperature > 0.0 && humidity > 50.0 {
@@ -64,8 +206,23 @@ pub fn calculate_precipitation(temperature: f64, humidity: f64) -> f64 {
     }
 }
 
-// Function to simulate weather evolution
-pub fn simulate_weather(conditions: &mut Vec<WeatherCondition>, time_step: usize) {
+/// Advances the weather of every cell by one step.
+///
+/// Wind first carries temperature and humidity between neighboring cells, then every cell drifts
+/// on its own and precipitation is recomputed.
+///
+/// # Arguments
+///
+/// * `conditions` - The weather conditions, one per cell of `planet`.
+/// * `planet` - The planet whose cell adjacency the wind follows.
+/// * `time_step` - The index of the step.
+///
+/// # Panics
+///
+/// Panics if there is not exactly one condition per cell.
+pub fn simulate_weather(conditions: &mut [WeatherCondition], planet: &Planet, time_step: usize) {
+    assert_eq!(conditions.len(), planet.cell_count(), "weather needs one condition per cell");
+    advect(conditions, planet);
     for condition in conditions.iter_mut() {
         condition.temperature += (time_step as f64 * 0.1) % 5.0 - 2.5; // Simplified temperature change
         condition.humidity += (time_step as f64 * 0.05) % 10.0 - 5.0; // Simplified humidity change
@@ -73,77 +230,471 @@ pub fn simulate_weather(conditions: &mut Vec<WeatherCondition>, time_step: usize
         condition.wind_direction = (condition.wind_direction + (time_step as f64 * 5.0) % 360.0) % 360.0; // Wind direction change
         condition.precipitation = calculate_precipitation(condition.temperature, condition.humidity); // Update precipitation
 
-        // Clamp values to realistic ranges
-        condition.temperature = condition.temperature.clamp(-30.0, 50.0);
-        condition.humidity = condition.humidity.clamp(0.0, 100.0);
-        condition.wind_speed = condition.wind_speed.clamp(0.0, 30.0);
-        condition.precipitation = condition.precipitation.clamp(0.0, 100.0);
+        clamp_condition(condition);
     }
 }
 
-// Function to introduce global weather events
-pub fn global_weather_event(conditions: &mut Vec<WeatherCondition>, event_type: &str) {
-    match event_type {
-        "storm" => {
-            for condition in conditions.iter_mut() {
-                condition.wind_speed += 10.0; // Increase wind speed
-                condition.wind_direction = (condition.wind_direction + 45.0) % 360.0; // Change wind direction
-                condition.humidity += 20.0; // Increase humidity
-                condition.precipitation = calculate_precipitation(condition.temperature, condition.humidity); // Update precipitation
+// Mixes into every cell the temperature and humidity of the neighbors its wind blows from, in
+// proportion to the wind speed; all cells read the conditions from before the step
+fn advect(conditions: &mut [WeatherCondition], planet: &Planet) {
+    let sites = planet.sites();
+    let carried: Vec<(f64, f64)> = (0..conditions.len())
+        .into_par_iter()
+        .map(|cell| {
+            let condition = &conditions[cell];
+            let (mut weight, mut temperature, mut humidity) = (0.0, 0.0, 0.0);
+            for &neighbor in planet.neighbors(cell) {
+                // Neighbors toward the wind's origin weigh most; those downwind not at all
+                let alignment = (spherical::bearing(sites[cell], sites[neighbor]) - condition.wind_direction).to_radians().cos();
+                if alignment > 0.0 {
+                    weight += alignment;
+                    temperature += alignment * conditions[neighbor].temperature;
+                    humidity += alignment * conditions[neighbor].humidity;
+                }
             }
+            if weight == 0.0 {
+                return (condition.temperature, condition.humidity);
+            }
+            let share = (condition.wind_speed * ADVECTION_PER_WIND_SPEED).clamp(0.0, 1.0);
+            (
+                condition.temperature + (temperature / weight - condition.temperature) * share,
+                condition.humidity + (humidity / weight - condition.humidity) * share,
+            )
+        })
+        .collect();
+    for (condition, (temperature, humidity)) in conditions.iter_mut().zip(carried) {
+        condition.temperature = temperature;
+        condition.humidity = humidity;
+    }
+}
+
+/// Nudges temperatures toward the seasonal equilibrium for the given day.
+///
+/// This couples the weather simulation to the orbital calendar so that insolation, and with it
+/// temperature, varies by day-of-year and latitude.
+///
+/// # Arguments
+///
+/// * `conditions` - The weather conditions, one per cell of `planet`.
+/// * `planet` - Supplies the cell latitudes, orbital calendar, axial tilt and solar flux.
+/// * `day` - The current day.
+/// * `strength` - How far to move toward equilibrium, from 0.0 (not at all) to 1.0 (all the way).
+pub fn apply_seasonal_forcing(conditions: &mut [WeatherCondition], planet: &Planet, day: f64, strength: f64) {
+    let declination = planet.calendar().solar_declination(day, planet.axial_tilt());
+    for (cell, condition) in conditions.iter_mut().enumerate().take(planet.cell_count()) {
+        let latitude = planet.lat_lon(cell).0;
+        let insolation = climate::daily_mean_insolation(latitude, declination, planet.solar_flux());
+        let target = climate::equilibrium_temperature(insolation) + planet.atmosphere().greenhouse_offset();
+        condition.temperature += (target - condition.temperature) * strength.clamp(0.0, 1.0);
+        condition.precipitation = calculate_precipitation(condition.temperature, condition.humidity);
+        clamp_condition(condition);
+    }
+}
+
+/// Describes which part of the planet a weather event affects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AffectedArea {
+    /// Every cell of the planet.
+    Global,
+    /// The cells whose sites lie in a spherical cap around `center` (a unit vector, z toward the
+    /// north pole) with the given angular radius in degrees.
+    Cap { center: [f64; 3], radius_degrees: f64 },
+    /// An explicit list of cell indices.
+    Cells(Vec<usize>),
+}
+
+/// Parameters shared by every weather event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventParams {
+    pub intensity: f64,       // 1.0 is a typical event; effects scale linearly
+    pub duration: usize,      // Number of simulation steps the event lasts
+    pub area: AffectedArea,
+}
+
+/// A weather event that can be applied to a set of weather conditions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherEvent {
+    Storm(EventParams),
+    Heatwave(EventParams),
+    ColdSnap(EventParams),
+    Drought(EventParams),
+    /// A user-defined event, looked up by name in a `WeatherEventRegistry`.
+    Custom(String, EventParams),
+}
+
+/// Error returned when a weather event cannot be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherEventError {
+    /// A `WeatherEvent::Custom` name was not registered.
+    UnknownEvent(String),
+    /// An affected-area cell index was outside the conditions slice.
+    CellOutOfRange(usize),
+}
+
+impl std::fmt::Display for WeatherEventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeatherEventError::UnknownEvent(name) => write!(f, "unknown weather event `{}`", name),
+            WeatherEventError::CellOutOfRange(index) => write!(f, "weather event cell {} is out of range", index),
         }
-        "heatwave" => {
-            for condition in conditions.iter_mut() {
-                condition.temperature += 10.0; // Increase temperature
-                condition.humidity -= 10.0; // Decrease humidity
-                condition.precipitation = calculate_precipitation(condition.temperature, condition.humidity); // Update precipitation
+    }
+}
+
+impl std::error::Error for WeatherEventError {}
+
+/// A function applying a custom event to one weather condition, given the event intensity.
+pub type EventHandler = Box<dyn Fn(&mut WeatherCondition, f64) + Send + Sync>;
+
+/// Holds user-defined weather event types keyed by name.
+#[derive(Default)]
+pub struct WeatherEventRegistry {
+    handlers: HashMap<String, EventHandler>,
+}
+
+impl WeatherEventRegistry {
+    /// Creates an empty registry. The built-in events never need registering.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers (or replaces) a custom event type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name used by `WeatherEvent::Custom`.
+    /// * `handler` - Applies the event to a single weather condition. It receives the intensity
+    ///   for the current step and does not need to clamp; values are clamped afterwards.
+    ///   Precipitation is recomputed from the new temperature and humidity, so change those to
+    ///   make it rain.
+    pub fn register<F>(&mut self, name: &str, handler: F)
+    where
+        F: Fn(&mut WeatherCondition, f64) + Send + Sync + 'static,
+    {
+        self.handlers.insert(name.to_string(), Box::new(handler));
+    }
+
+    /// Returns `true` if a custom event with this name has been registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+}
+
+impl WeatherEvent {
+    /// Returns the parameters of the event.
+    pub fn params(&self) -> &EventParams {
+        match self {
+            WeatherEvent::Storm(params)
+            | WeatherEvent::Heatwave(params)
+            | WeatherEvent::ColdSnap(params)
+            | WeatherEvent::Drought(params)
+            | WeatherEvent::Custom(_, params) => params,
+        }
+    }
+}
+
+// Applies one built-in event to a single condition at the given intensity
+fn apply_builtin(event: &WeatherEvent, condition: &mut WeatherCondition, intensity: f64) {
+    match event {
+        WeatherEvent::Storm(_) => {
+            condition.wind_speed += 10.0 * intensity; // Increase wind speed
+            condition.wind_direction = (condition.wind_direction + 45.0 * intensity) % 360.0; // Change wind direction
+            condition.humidity += 20.0 * intensity; // Increase humidity
+        }
+        WeatherEvent::Heatwave(_) => {
+            condition.temperature += 10.0 * intensity; // Increase temperature
+            condition.humidity -= 10.0 * intensity; // Decrease humidity
+        }
+        WeatherEvent::ColdSnap(_) => {
+            condition.temperature -= 15.0 * intensity; // Decrease temperature
+            condition.wind_speed += 5.0 * intensity; // Cold fronts are windy
+        }
+        WeatherEvent::Drought(_) => {
+            condition.humidity -= 25.0 * intensity; // Dry out the air
+            condition.temperature += 3.0 * intensity;
+        }
+        WeatherEvent::Custom(..) => {}
+    }
+}
+
+// Clamps values to realistic ranges
+fn clamp_condition(condition: &mut WeatherCondition) {
+    // `clamp` passes NaN through, so non-finite values are caught by the guard first
+    condition.temperature = guard_value("weather.temperature", condition.temperature, (-1.0e3, 1.0e3), 15.0).clamp(-30.0, 50.0);
+    condition.humidity = guard_value("weather.humidity", condition.humidity, (-1.0e3, 1.0e3), 50.0).clamp(0.0, 100.0);
+    condition.wind_speed = guard_value("weather.wind_speed", condition.wind_speed, (-1.0e3, 1.0e3), 0.0).clamp(0.0, 30.0);
+    condition.wind_direction = guard_value("weather.wind_direction", condition.wind_direction, (-360.0, 360.0), 0.0);
+    condition.precipitation = guard_value("weather.precipitation", condition.precipitation, (-1.0e3, 1.0e3), 0.0).clamp(0.0, 100.0);
+}
+
+/// Applies a weather event to every condition inside the event's affected area.
+///
+/// The full intensity of the event is applied at once; use `ActiveWeatherEvents` to spread an
+/// event over its duration instead.
+///
+/// # Arguments
+///
+/// * `conditions` - The weather conditions, one per cell of `planet`.
+/// * `planet` - The planet whose cell sites `AffectedArea::Cap` is tested against.
+/// * `event` - The event to apply.
+/// * `registry` - Custom event handlers.
+///
+/// # Returns
+///
+/// `Ok(())` if the event was applied, or a `WeatherEventError` if the event is unknown or its
+/// area refers to missing cells. Nothing is modified when an error is returned.
+pub fn apply_weather_event(
+    conditions: &mut [WeatherCondition],
+    planet: &Planet,
+    event: &WeatherEvent,
+    registry: &WeatherEventRegistry,
+) -> Result<(), WeatherEventError> {
+    apply_scaled(conditions, planet, event, registry, 1.0)
+}
+
+fn apply_scaled(
+    conditions: &mut [WeatherCondition],
+    planet: &Planet,
+    event: &WeatherEvent,
+    registry: &WeatherEventRegistry,
+    scale: f64,
+) -> Result<(), WeatherEventError> {
+    let resolved = resolve_event(conditions.len(), planet, event, registry)?;
+    apply_resolved(conditions, event, resolved, scale);
+    Ok(())
+}
+
+// An event's custom handler, if any, and the cells it affects
+type ResolvedEvent<'a> = (Option<&'a EventHandler>, Vec<usize>);
+
+// Looks up an event's handler and affected cells among `cells` conditions, failing before
+// anything is modified
+fn resolve_event<'a>(
+    cells: usize,
+    planet: &Planet,
+    event: &WeatherEvent,
+    registry: &'a WeatherEventRegistry,
+) -> Result<ResolvedEvent<'a>, WeatherEventError> {
+    let custom = match event {
+        WeatherEvent::Custom(name, _) => Some(
+            registry
+                .handlers
+                .get(name)
+                .ok_or_else(|| WeatherEventError::UnknownEvent(name.clone()))?,
+        ),
+        _ => None,
+    };
+
+    let indices: Vec<usize> = match &event.params().area {
+        AffectedArea::Global => (0..cells).collect(),
+        AffectedArea::Cap { center, radius_degrees } => {
+            let min_dot = radius_degrees.to_radians().cos();
+            planet
+                .sites()
+                .iter()
+                .take(cells)
+                .enumerate()
+                .filter(|(_, p)| p.0 * center[0] + p.1 * center[1] + p.2 * center[2] >= min_dot)
+                .map(|(i, _)| i)
+                .collect()
+        }
+        AffectedArea::Cells(indices) => {
+            if let Some(&bad) = indices.iter().find(|&&i| i >= cells) {
+                return Err(WeatherEventError::CellOutOfRange(bad));
             }
+            indices.clone()
+        }
+    };
+    Ok((custom, indices))
+}
+
+// Applies a resolved event at its intensity times `scale`
+fn apply_resolved(conditions: &mut [WeatherCondition], event: &WeatherEvent, (custom, indices): ResolvedEvent, scale: f64) {
+    let intensity = event.params().intensity * scale;
+    for i in indices {
+        let condition = &mut conditions[i];
+        match custom {
+            Some(handler) => handler(condition, intensity),
+            None => apply_builtin(event, condition, intensity),
         }
-        _ => {}
+        condition.precipitation = calculate_precipitation(condition.temperature, condition.humidity); // Update precipitation
+        clamp_condition(condition);
     }
+}
 
-    // Clamp values to realistic ranges
-    for condition in conditions.iter_mut() {
-        condition.temperature = condition.temperature.clamp(-30.0, 50.0);
-        condition.humidity = condition.humidity.clamp(0.0, 100.0);
-        condition.wind_speed = condition.wind_speed.clamp(0.0, 30.0);
-        condition.precipitation = condition.precipitation.clamp(0.0, 100.0);
-    }
-}
-
-/////////////////////////////////////////////////
-///  
-///  # Docs Coming Soon!!!
-///  
-///  
-///  
-///  
-///  
-///  
-///  
-///  
-///  
-///  
-///  
-
- fn init(planet_uuid: u64, num_samples: u32) {
+/// Tracks events that are still in progress and applies them gradually, one step at a time.
+#[derive(Debug, Default, Clone)]
+pub struct ActiveWeatherEvents {
+    events: Vec<(WeatherEvent, usize)>, // Event and remaining steps
+}
+
+impl ActiveWeatherEvents {
+    /// Creates an empty set of active events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts an event. Custom events are validated immediately so that typos surface here
+    /// rather than several steps later.
+    pub fn start(&mut self, event: WeatherEvent, registry: &WeatherEventRegistry) -> Result<(), WeatherEventError> {
+        if let WeatherEvent::Custom(name, _) = &event {
+            if !registry.contains(name) {
+                return Err(WeatherEventError::UnknownEvent(name.clone()));
+            }
+        }
+        let duration = event.params().duration.max(1);
+        self.events.push((event, duration));
+        Ok(())
+    }
+
+    /// Returns the number of events still in progress.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no events are in progress.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Applies one step of every active event, spreading each event's intensity evenly over its
+    /// duration, and drops events that have finished.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every event was applied, or the first `WeatherEventError` found, e.g. a custom
+    /// event missing from `registry`. Every event is checked before any is applied, so nothing is
+    /// modified and no event advances when an error is returned.
+    pub fn step(
+        &mut self,
+        conditions: &mut [WeatherCondition],
+        planet: &Planet,
+        registry: &WeatherEventRegistry,
+    ) -> Result<(), WeatherEventError> {
+        let resolved = self
+            .events
+            .iter()
+            .map(|(event, _)| resolve_event(conditions.len(), planet, event, registry))
+            .collect::<Result<Vec<_>, _>>()?;
+        for ((event, remaining), resolved) in self.events.iter_mut().zip(resolved) {
+            let share = 1.0 / event.params().duration.max(1) as f64;
+            apply_resolved(conditions, event, resolved, share);
+            *remaining -= 1;
+        }
+        self.events.retain(|(_, remaining)| *remaining > 0);
+        Ok(())
+    }
+}
+
+/// Runs a short demonstration of the weather simulation.
+///
+/// Builds a planet of `num_samples` cells, generates weather for every cell, simulates ten steps,
+/// applies a global storm, and logs the resulting conditions as `tracing` trace events.
+///
+/// # Arguments
+///
+/// * `planet_uuid` - The seed for the cell layout and the initial weather conditions.
+/// * `num_samples` - The number of cells.
+///
+/// # Returns
+///
+/// `Ok(())` on success, or a `WeatherEventError` if the storm could not be applied.
+ pub fn init(planet_uuid: u64, num_samples: usize) -> Result<(), WeatherEventError> {
  
-     // Generate Fibonacci sphere points
-     let points = fibonacci_sphere(num_samples);
+     // Build the planet whose cells carry the weather
+     let planet = Planet::new(seeded_fibonacci_sphere(num_samples, 0.0, planet_uuid));
  
      // Generate initial weather conditions
-     let mut weather_conditions = generate_weather_conditions(planet_uuid, num_samples);
+     let mut weather_conditions = generate_weather_conditions(planet_uuid, &planet);
  
      // Simulate weather for 10 time steps
      for time_step in 0..10 {
-         simulate_weather(&mut weather_conditions, time_step);
+         simulate_weather(&mut weather_conditions, &planet, time_step);
      }
  
      // Introduce a global storm event
-     global_weather_event(&mut weather_conditions, "storm");
+     let storm = WeatherEvent::Storm(EventParams {
+         intensity: 1.0,
+         duration: 1,
+         area: AffectedArea::Global,
+     });
+     apply_weather_event(&mut weather_conditions, &planet, &storm, &WeatherEventRegistry::new())?;
  
-     // Print final weather conditions
+     // Log final weather conditions
      for (i, condition) in weather_conditions.iter().enumerate() {
-         println!("Point {}: {:?}", i, condition);
+         tracing::trace!("Cell {} {:?}: {:?}", i, planet.lat_lon(i), condition);
      }
+
+     Ok(())
  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planet() -> Planet {
+        Planet::new(seeded_fibonacci_sphere(50, 0.0, 3))
+    }
+
+    fn params(duration: usize, area: AffectedArea) -> EventParams {
+        EventParams { intensity: 1.0, duration, area }
+    }
+
+    fn registry() -> WeatherEventRegistry {
+        let mut registry = WeatherEventRegistry::new();
+        registry.register("monsoon", |condition, intensity| {
+            condition.humidity += 40.0 * intensity;
+            condition.wind_direction = 225.0;
+        });
+        registry
+    }
+
+    #[test]
+    fn custom_events_change_the_affected_cells() {
+        let planet = planet();
+        let mut conditions = generate_weather_conditions(1, &planet);
+        let before = conditions.clone();
+        let monsoon = WeatherEvent::Custom("monsoon".to_string(), params(1, AffectedArea::Cells(vec![2, 5])));
+        apply_weather_event(&mut conditions, &planet, &monsoon, &registry()).unwrap();
+        for (i, (after, before)) in conditions.iter().zip(&before).enumerate() {
+            if i == 2 || i == 5 {
+                assert_eq!(after.wind_direction, 225.0);
+                assert_eq!(after.humidity, (before.humidity + 40.0).min(100.0));
+                assert_eq!(after.precipitation, calculate_precipitation(after.temperature, after.humidity).clamp(0.0, 100.0));
+            } else {
+                assert_eq!(after, before);
+            }
+        }
+
+        let unknown = WeatherEvent::Custom("hail".to_string(), params(1, AffectedArea::Global));
+        assert_eq!(apply_weather_event(&mut conditions, &planet, &unknown, &registry()), Err(WeatherEventError::UnknownEvent("hail".to_string())));
+    }
+
+    #[test]
+    fn failed_steps_change_nothing() {
+        let planet = planet();
+        let mut conditions = generate_weather_conditions(2, &planet);
+        let mut active = ActiveWeatherEvents::new();
+        active.start(WeatherEvent::Heatwave(params(2, AffectedArea::Global)), &registry()).unwrap();
+        active.start(WeatherEvent::Custom("monsoon".to_string(), params(2, AffectedArea::Global)), &registry()).unwrap();
+        assert_eq!(active.start(WeatherEvent::Custom("hail".to_string(), params(2, AffectedArea::Global)), &registry()), Err(WeatherEventError::UnknownEvent("hail".to_string())));
+
+        // The heatwave is fine but the monsoon's handler is missing, so neither is applied
+        let before = conditions.clone();
+        let result = active.step(&mut conditions, &planet, &WeatherEventRegistry::new());
+        assert_eq!(result, Err(WeatherEventError::UnknownEvent("monsoon".to_string())));
+        assert_eq!(conditions, before);
+
+        // Nor does a cell beyond the conditions
+        let mut late = ActiveWeatherEvents::new();
+        late.start(WeatherEvent::Storm(params(1, AffectedArea::Global)), &registry()).unwrap();
+        late.start(WeatherEvent::Drought(params(1, AffectedArea::Cells(vec![0, 50]))), &registry()).unwrap();
+        assert_eq!(late.step(&mut conditions, &planet, &registry()), Err(WeatherEventError::CellOutOfRange(50)));
+        assert_eq!(conditions, before);
+        assert_eq!(late.len(), 2);
+
+        active.step(&mut conditions, &planet, &registry()).unwrap();
+        assert_ne!(conditions, before);
+        active.step(&mut conditions, &planet, &registry()).unwrap();
+        assert!(active.is_empty());
+    }
+}
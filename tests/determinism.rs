@@ -0,0 +1,109 @@
+//! End-to-end determinism: universe seed → sector → galaxy → star system → planet surface →
+//! exported buffers.
+//!
+//! The golden hashes pin the output of the whole chain. Any change to one of them is a breaking
+//! change to world generation: the same seed or saved GUID no longer produces the same galaxy,
+//! system or planet. Update them only for deliberate changes, and call out the break, with the
+//! hashes it moved, in the commit message. Changes that leave every hash alone, such as new
+//! layers computed from the same surface, are not breaking.
+
+use terraforge::guid::guid_from_seed;
+use terraforge::planet::Planet;
+use terraforge::procedural::PlanetParameters;
+use terraforge::projection::Projection;
+use terraforge::render::materials::MaterialPalette;
+use terraforge::space::{Galaxy, Universe};
+use terraforge::star_system::{PlanetKind, StarSystem};
+use terraforge::vegetation::VegetationLayer;
+
+// FNV-1a, which unlike `DefaultHasher` is fixed across Rust versions
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+// Hashes floating-point values quantized to a resolution, so that last-bit differences in
+// platform math libraries do not change the hash
+fn hash_quantized(values: &[f64], resolution: f64) -> u64 {
+    fnv1a(values.iter().flat_map(|value| ((value / resolution).round() as i64).to_le_bytes()))
+}
+
+const SEED: u64 = 0x7E44_A0F0;
+
+// Picks the first galaxy of the universe's central sector
+fn chosen_galaxy() -> Galaxy {
+    Universe::new(guid_from_seed(SEED)).sector(0, 0, 0).into_iter().next().unwrap()
+}
+
+// Picks the first system of the galaxy with a rocky planet and returns the innermost one
+fn chosen_planet(galaxy: &Galaxy) -> (StarSystem, usize) {
+    (0..)
+        .map(|index| StarSystem::generate(galaxy.guid(), index))
+        .find_map(|system| {
+            let planet = system.planets().iter().position(|planet| planet.kind == PlanetKind::Rocky)?;
+            Some((system, planet))
+        })
+        .unwrap()
+}
+
+fn build() -> (Galaxy, StarSystem, Planet) {
+    let galaxy = chosen_galaxy();
+    let (system, index) = chosen_planet(&galaxy);
+    let parameters = PlanetParameters::from_system_planet(&system, &system.planets()[index]);
+    let planet = parameters.build();
+    (galaxy, system, planet)
+}
+
+#[test]
+fn chain_is_repeatable() {
+    let (galaxy_a, system_a, planet_a) = build();
+    let (galaxy_b, system_b, planet_b) = build();
+    assert_eq!(galaxy_a, galaxy_b);
+    assert_eq!(system_a, system_b);
+    assert_eq!(planet_a.sites(), planet_b.sites());
+    assert_eq!(planet_a.elevation(), planet_b.elevation());
+    assert_eq!(planet_a.temperature(), planet_b.temperature());
+}
+
+#[test]
+fn chain_matches_golden_hashes() {
+    let (galaxy, system, planet) = build();
+
+    let guids: Vec<u8> = [galaxy.guid(), system.guid()]
+        .into_iter()
+        .chain(system.planets().iter().map(|planet| planet.guid))
+        .flat_map(|guid| guid.into_bytes())
+        .collect();
+    let (x, y, z) = galaxy.position();
+    let orbit = galaxy.orbit();
+    let galaxy_values = [x, y, z, orbit.semi_major_axis, orbit.semi_minor_axis, orbit.period, orbit.inclination, orbit.ascending_node, orbit.argument_of_periapsis, orbit.time_offset];
+    let sites: Vec<f64> = planet.sites().iter().flat_map(|&(x, y, z)| [x, y, z]).collect();
+    let materials = planet.materials(&VegetationLayer::from_planet(&planet));
+    let texture = planet.material_id_texture(&materials, Projection::Equirectangular, 128, 64);
+    let palette = MaterialPalette::default().to_json().to_string();
+
+    let hashes = [
+        ("galaxy", hash_quantized(&galaxy_values, 1e-9)),
+        ("guids", fnv1a(guids)),
+        ("cells", planet.cell_count() as u64),
+        ("sites", hash_quantized(&sites, 1e-9)),
+        ("elevation", hash_quantized(planet.elevation(), 0.01)),
+        ("temperature", hash_quantized(planet.temperature(), 0.001)),
+        ("rock_types", fnv1a(planet.rock_types().iter().map(|rock| *rock as u8))),
+        ("material_texture", fnv1a(texture)),
+        ("palette", fnv1a(palette.into_bytes())),
+    ];
+    let golden: [(&str, u64); 9] = [
+        ("galaxy", 0x70FF_EFCE_A120_6A68),
+        ("guids", 0x7BE6_4E01_26D9_AEAA),
+        ("cells", 7854),
+        ("sites", 0x0B35_11DA_08EC_C521),
+        ("elevation", 0xC5B1_78DA_CAFC_324E),
+        ("temperature", 0x9462_0EC9_A93E_D16F),
+        ("rock_types", 0xA6D2_155F_40D8_F418),
+        ("material_texture", 0x9C70_ACDE_40F3_056A),
+        ("palette", 0x3CB7_8BF7_7DE6_4C93),
+    ];
+    for ((name, hash), (_, expected)) in hashes.iter().zip(golden) {
+        assert_eq!(*hash, expected, "{name} hash changed: {hash:#X}");
+    }
+}
@@ -0,0 +1,187 @@
+//! Randomized checks of the cell topology: planets built from many kinds of site sets, and edited
+//! afterwards, must always pass `Planet::validate`.
+//!
+//! Every case draws its sites from a generator seeded with the case number, so a failure names the
+//! seed that reproduces it. The core invariants are also `proptest` properties over the seed and
+//! cell count, which shrink a failure to the smallest planet that shows it.
+
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use terraforge::hex_grid::HexGrid;
+use terraforge::planet::{Planet, TriangulationError};
+use terraforge::precision::CompactPlanet;
+use terraforge::sampling::{density_sphere, poisson_radius, poisson_sphere};
+use terraforge::topology::TopologyError;
+
+const CASES: u64 = 24;
+
+// Builds a planet from the sites of every case and validates it
+fn check_cases(name: &str, sites: impl Fn(&mut StdRng) -> Vec<(f64, f64, f64)>) {
+    for seed in 0..CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let planet = Planet::new(sites(&mut rng));
+        if let Err(error) = planet.validate() {
+            panic!("{} sites with seed {}: {}", name, seed, error);
+        }
+    }
+}
+
+// A uniformly distributed random direction
+fn random_direction(rng: &mut StdRng) -> (f64, f64, f64) {
+    let z: f64 = rng.gen_range(-1.0..1.0);
+    let angle: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+    let ring = (1.0 - z * z).sqrt();
+    (ring * angle.cos(), ring * angle.sin(), z)
+}
+
+// A planet of uniformly random sites
+fn random_planet(seed: u64, cells: usize) -> Planet {
+    let mut rng = StdRng::seed_from_u64(seed);
+    Planet::new((0..cells).map(|_| random_direction(&mut rng)).collect())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES as u32))]
+
+    // Cells, Delaunay edges and triangles tile a sphere, so V - E + F = 2
+    #[test]
+    fn euler_characteristic_is_two(seed in any::<u64>(), cells in 4usize..2000) {
+        let planet = random_planet(seed, cells);
+        let edges: usize = (0..planet.cell_count()).map(|cell| planet.neighbors(cell).len()).sum::<usize>() / 2;
+        prop_assert_eq!(planet.cell_count() as i64 - edges as i64 + planet.triangles().len() as i64, 2);
+    }
+
+    #[test]
+    fn adjacency_is_symmetric(seed in any::<u64>(), cells in 4usize..2000) {
+        let planet = random_planet(seed, cells);
+        for cell in 0..planet.cell_count() {
+            for &neighbor in planet.neighbors(cell) {
+                prop_assert!(planet.neighbors(neighbor).contains(&cell), "cell {} lists {} but not the reverse", cell, neighbor);
+            }
+        }
+    }
+}
+
+#[test]
+fn uniform_random_sites_validate() {
+    check_cases("uniform", |rng| {
+        let count = rng.gen_range(4..1500);
+        (0..count).map(|_| random_direction(rng)).collect()
+    });
+}
+
+#[test]
+fn clustered_sites_validate() {
+    // Dense clusters, some at the first site or its antipode where the projection is stitched
+    check_cases("clustered", |rng| {
+        let mut sites: Vec<(f64, f64, f64)> = (0..8).map(|_| random_direction(rng)).collect();
+        let first = sites[0];
+        let centres = [first, (-first.0, -first.1, -first.2), random_direction(rng)];
+        for centre in centres {
+            let spread = 10f64.powf(rng.gen_range(-6.0..-1.0));
+            for _ in 0..rng.gen_range(10..300) {
+                let offset = random_direction(rng);
+                sites.push((centre.0 + offset.0 * spread, centre.1 + offset.1 * spread, centre.2 + offset.2 * spread));
+            }
+        }
+        sites
+    });
+}
+
+#[test]
+fn sites_at_any_radius_validate() {
+    check_cases("scaled", |rng| {
+        let count = rng.gen_range(4..800);
+        (0..count)
+            .map(|_| {
+                let (x, y, z) = random_direction(rng);
+                let radius = 10f64.powf(rng.gen_range(-3.0..6.0));
+                (x * radius, y * radius, z * radius)
+            })
+            .collect()
+    });
+}
+
+#[test]
+fn duplicate_sites_validate() {
+    check_cases("duplicated", |rng| {
+        let mut sites: Vec<(f64, f64, f64)> = (0..rng.gen_range(4..400)).map(|_| random_direction(rng)).collect();
+        for _ in 0..rng.gen_range(1..100) {
+            let (x, y, z) = sites[rng.gen_range(0..sites.len())];
+            let nudge = rng.gen_range(0.0..1e-10);
+            sites.push((x + nudge, y, z));
+        }
+        sites
+    });
+}
+
+#[test]
+fn jittered_fibonacci_sites_validate() {
+    check_cases("jittered Fibonacci", |rng| {
+        let count = rng.gen_range(4..3000);
+        density_sphere(count, rng.gen_range(0.0..1.0), rng.gen(), |_| 1.0)
+    });
+}
+
+#[test]
+fn poisson_sites_validate() {
+    check_cases("Poisson", |rng| poisson_sphere(poisson_radius(rng.gen_range(50..3000)), rng.gen()));
+}
+
+#[test]
+fn goldberg_sites_validate() {
+    // Hex layouts put four sites on many circumcircles, the worst case for the triangulation
+    check_cases("Goldberg", |rng| HexGrid::with_cell_count(rng.gen_range(12..3000)).centers().to_vec());
+}
+
+#[test]
+fn edited_planets_validate() {
+    for seed in 0..CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut planet = Planet::new((0..rng.gen_range(5..300)).map(|_| random_direction(&mut rng)).collect());
+        for edit in 0..40 {
+            if rng.gen_bool(0.5) && planet.cell_count() > 5 {
+                planet.remove_site(rng.gen_range(0..planet.cell_count()));
+            } else {
+                planet.insert_site(rng.gen_range(-90.0..90.0), rng.gen_range(-180.0..180.0));
+            }
+            if let Err(error) = planet.validate() {
+                panic!("edit {} with seed {}: {}", edit, seed, error);
+            }
+        }
+    }
+}
+
+#[test]
+fn compact_round_trip_validates() {
+    for seed in 0..CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let planet = Planet::new(density_sphere(rng.gen_range(4..2000), 0.5, rng.gen(), |_| 1.0));
+        let compact: CompactPlanet<f32> = planet.compact();
+        if let Err(error) = Planet::from_compact(&compact).validate() {
+            panic!("f32 round trip with seed {}: {}", seed, error);
+        }
+    }
+}
+
+#[test]
+fn incomplete_planets_fail_validation() {
+    // Too few sites to triangulate leave cells but no triangles, which cover nothing
+    let planet = Planet::new(vec![(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)]);
+    assert_eq!(planet.validate(), Err(TopologyError::AreaMismatch(0.0)));
+}
+
+#[test]
+fn degenerate_sites_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let equator: Vec<(f64, f64, f64)> = (0..100)
+        .map(|_| {
+            let angle: f64 = rng.gen_range(0.0..std::f64::consts::TAU);
+            (angle.cos(), angle.sin(), 0.0)
+        })
+        .collect();
+    assert_eq!(Planet::try_new(equator).err(), Some(TriangulationError::Degenerate));
+    assert_eq!(Planet::try_new(vec![(1.0, 0.0, 0.0), (f64::NAN, 0.0, 0.0)]).err(), Some(TriangulationError::InvalidSite(1)));
+    assert_eq!(Planet::try_new(vec![(1.0, 0.0, 0.0); 10]).err(), Some(TriangulationError::TooFewSites(1)));
+}
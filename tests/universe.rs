@@ -0,0 +1,97 @@
+//! Checks of universe configuration and simulation events. A subscriber that follows
+//! `SectorLoaded` and `GalaxyChangedSector` must always know which sector every loaded galaxy is
+//! in.
+
+use std::collections::HashMap;
+use terraforge::guid::guid_from_seed;
+use terraforge::space::{OrbitModel, Universe, UniverseConfig, UniverseConfigError, UniverseEvent, UniverseSimulation};
+use uuid::Uuid;
+
+// Applies queued events to the sector each galaxy was last reported in
+fn follow_events(simulation: &mut UniverseSimulation, reported: &mut HashMap<Uuid, (i32, i32, i32)>) {
+    for event in simulation.drain_events() {
+        match event {
+            UniverseEvent::SectorLoaded((ix, iy, iz)) => {
+                for galaxy in simulation.universe().sector(ix, iy, iz) {
+                    reported.insert(galaxy.guid(), (ix, iy, iz));
+                }
+            }
+            UniverseEvent::SectorUnloaded(_) => {}
+            UniverseEvent::GalaxyChangedSector { guid, from, to } => {
+                assert_eq!(reported.insert(guid, to), Some(from), "galaxy {} changed sector from one it was not in", guid);
+            }
+        }
+    }
+}
+
+// Checks that every loaded galaxy lies in the sector it was last reported in
+fn check_reported(simulation: &UniverseSimulation, reported: &HashMap<Uuid, (i32, i32, i32)>) {
+    let universe = simulation.universe();
+    let (x, y, z) = universe.loaded_positions();
+    for (i, guid) in universe.loaded_guids().iter().enumerate() {
+        assert_eq!(Some(&universe.sector_of((x[i], y[i], z[i]))), reported.get(guid), "galaxy {} is not in its reported sector", guid);
+    }
+}
+
+#[test]
+fn loaded_galaxies_lie_in_their_reported_sectors() {
+    let mut simulation = UniverseSimulation::from_universe(Universe::new(guid_from_seed(7)));
+    simulation.set_event_queue(true);
+    let mut reported = HashMap::new();
+
+    simulation.load_sectors(&[(0, 0, 0), (1, 0, 0), (-3, 2, 4)]);
+    follow_events(&mut simulation, &mut reported);
+    assert!(simulation.universe().loaded_len() > 0);
+    check_reported(&simulation, &reported);
+
+    // Sectors loaded later join at the current time, further along their orbits
+    simulation.step(600.0);
+    simulation.load_sectors(&[(5, -5, 0)]);
+    follow_events(&mut simulation, &mut reported);
+    check_reported(&simulation, &reported);
+}
+
+#[test]
+fn configs_that_break_sector_generation_are_rejected() {
+    let invalid = |field: &str| Err(UniverseConfigError::InvalidField(field.to_string()));
+    let empty = UniverseConfig { galaxy_count_range: 0..10, ..UniverseConfig::default() };
+    assert_eq!(empty.validate(), invalid("galaxy_count"));
+    // More sectors per axis than the sector GUID can tell apart
+    let fine = UniverseConfig { sector_size: 1e-5, ..UniverseConfig::default() };
+    assert_eq!(fine.validate(), invalid("sector_size"));
+    assert_eq!(UniverseConfig::default().validate(), Ok(()));
+}
+
+#[test]
+fn unloading_sectors_keeps_the_rest_loaded() {
+    let mut universe = Universe::new(guid_from_seed(11));
+    let sectors = [(0, 0, 0), (1, 0, 0), (0, -1, 2), (3, 3, 3)];
+    universe.load_sectors(&sectors);
+    universe.load_sectors(&sectors[..2]);
+    assert_eq!(universe.loaded_sectors().len(), 4);
+
+    universe.unload_sectors(&[(1, 0, 0), (3, 3, 3), (9, 9, 9)]);
+    // Sectors loaded in one call are loaded in sorted order
+    assert_eq!(universe.loaded_sectors(), &[(0, -1, 2), (0, 0, 0)]);
+    let expected: Vec<Uuid> = [(0, -1, 2), (0, 0, 0)].iter().flat_map(|&(ix, iy, iz)| universe.sector(ix, iy, iz)).map(|galaxy| galaxy.guid()).collect();
+    assert_eq!(universe.loaded_guids(), expected.as_slice());
+}
+
+#[test]
+fn freshly_loaded_galaxies_stay_in_their_sectors() {
+    for model in [OrbitModel::Kepler, OrbitModel::Approximate] {
+        let mut universe = Universe::new(guid_from_seed(3));
+        universe.set_default_orbit_model(model);
+        let sectors = [(0, 0, 0), (2, -1, 0), (-4, 4, -4)];
+        universe.load_sectors(&sectors);
+        for &(ix, iy, iz) in &sectors {
+            for galaxy in universe.sector(ix, iy, iz) {
+                let (x, y, z) = galaxy.position();
+                let loaded = universe.loaded_position(galaxy.guid()).unwrap();
+                let offset = ((loaded.0 - x).powi(2) + (loaded.1 - y).powi(2) + (loaded.2 - z).powi(2)).sqrt();
+                assert!(offset < 1e-9, "{model:?} galaxy {} loaded {offset} from its generated position", galaxy.guid());
+                assert_eq!(universe.sector_of(loaded), (ix, iy, iz));
+            }
+        }
+    }
+}